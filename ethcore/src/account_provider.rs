@@ -19,12 +19,16 @@
 use std::{fs, fmt};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::{Instant, Duration};
-use util::{Mutex, RwLock};
+use std::sync::Arc;
+use std::time::{Instant, Duration, SystemTime, UNIX_EPOCH};
+use util::{Mutex, RwLock, U256};
 use ethstore::{SecretStore, Error as SSError, SafeAccount, EthStore};
 use ethstore::dir::{KeyDirectory};
-use ethstore::ethkey::{Address, Message, Secret, Random, Generator};
-use ethjson::misc::AccountMeta;
+use ethstore::ethkey::{Address, Message, Public, Secret, Random, Generator, Mnemonic, DerivationPath};
+use ethjson::misc::{AccountMeta, DailySpending};
+use ethjson::uint::Uint;
+use signing_policy::{SigningPolicy, PolicyViolation};
+use transaction::Transaction;
 pub use ethstore::ethkey::Signature;
 
 /// Type of unlock.
@@ -53,6 +57,10 @@ pub enum Error {
 	NotUnlocked,
 	/// Returned when signing fails.
 	SStore(SSError),
+	/// Returned when an external signer fails or refuses to sign.
+	External(String),
+	/// Returned when the account key is held by an external signer which can't perform the operation.
+	NotSupported,
 }
 
 impl fmt::Display for Error {
@@ -60,6 +68,8 @@ impl fmt::Display for Error {
 		match *self {
 			Error::NotUnlocked => write!(f, "Account is locked"),
 			Error::SStore(ref e) => write!(f, "{}", e),
+			Error::External(ref e) => write!(f, "External signer error: {}", e),
+			Error::NotSupported => write!(f, "Operation not supported by the external signer"),
 		}
 	}
 }
@@ -153,12 +163,142 @@ impl AddressBook {
 	}
 }
 
+/// Disk-backed record of value sent by accounts with a daily limit. Uses JSON.
+/// Value of transactions which are being signed and dispatched is reserved in memory,
+/// so concurrent requests can't exceed the limit together.
+/// If the record is missing or can't be read, spending is unknown and daily limits don't allow automatic signing.
+struct SpendingBook {
+	path: Option<PathBuf>,
+	spent: HashMap<Address, (u64, U256)>,
+	reserved: HashMap<Address, U256>,
+	readable: bool,
+}
+
+impl SpendingBook {
+	fn new(path: Option<PathBuf>) -> Self {
+		let mut book = SpendingBook {
+			path: path.map(|p| p.join("signing_spending.json")),
+			spent: HashMap::new(),
+			reserved: HashMap::new(),
+			readable: true,
+		};
+		book.revert();
+		book
+	}
+
+	/// Value sent today together with the value reserved by transactions in flight, `None` if it's unknown.
+	fn spent_today(&self, account: &Address) -> Option<U256> {
+		if !self.readable {
+			return None;
+		}
+		let spent = match self.spent.get(account) {
+			Some(&(day, value)) if day == self::day() => value,
+			_ => U256::zero(),
+		};
+		// reservations are checked against the limit, so there is no overflow
+		Some(spent + self.reserved.get(account).cloned().unwrap_or_else(U256::zero))
+	}
+
+	fn reserve(&mut self, account: Address, value: U256) {
+		let reserved = self.reserved.entry(account).or_insert_with(U256::zero);
+		*reserved = *reserved + value;
+	}
+
+	fn release(&mut self, account: &Address, value: U256) {
+		let empty = match self.reserved.get_mut(account) {
+			Some(reserved) => {
+				*reserved = if *reserved > value { *reserved - value } else { U256::zero() };
+				reserved.is_zero()
+			},
+			None => false,
+		};
+		if empty {
+			self.reserved.remove(account);
+		}
+	}
+
+	fn record(&mut self, account: Address, value: U256) {
+		self.release(&account, value);
+		let today = day();
+		let spent = match self.spent.get(&account) {
+			Some(&(day, spent)) if day == today => spent,
+			_ => U256::zero(),
+		};
+		self.spent.insert(account, (today, spent + value));
+		self.save();
+	}
+
+	fn revert(&mut self) {
+		let path = match self.path {
+			Some(ref path) => path.clone(),
+			None => return,
+		};
+		let readable = fs::File::open(&path)
+			.map_err(|e| warn!(target: "signing_policy", "Couldn't open spending record {}: {}", path.display(), e))
+			.and_then(|f| DailySpending::read_address_map(&f)
+				.map_err(|e| warn!(target: "signing_policy", "Couldn't read spending record {}: {}", path.display(), e))
+				.map(|m| self.spent = m.into_iter().map(|(a, s)| (a, (s.day, s.spent.into()))).collect())
+			)
+			.is_ok();
+		if !readable {
+			warn!(target: "signing_policy", "Accounts with a daily limit won't sign automatically. Write `{{}}` to {} to start a new record.", path.display());
+		}
+		self.readable = readable;
+	}
+
+	fn save(&self) {
+		let path = match self.path {
+			Some(ref path) if self.readable => path,
+			_ => return,
+		};
+		let spending = self.spent.iter()
+			.map(|(a, &(day, spent))| (*a, DailySpending { day: day, spent: Uint(spent) }))
+			.collect();
+		// write the whole record to a hidden file first, so a crash never leaves it truncated
+		let temp_path = path.with_file_name(".signing_spending.json.tmp");
+		let _ = fs::File::create(&temp_path)
+			.map_err(|e| warn!(target: "signing_policy", "Couldn't open spending record for writing: {}", e))
+			.and_then(|mut f| DailySpending::write_address_map(&spending, &mut f)
+				.map_err(|e| warn!(target: "signing_policy", "Couldn't write spending record: {}", e))
+			)
+			.and_then(|_| fs::rename(&temp_path, path)
+				.map_err(|e| warn!(target: "signing_policy", "Couldn't replace spending record: {}", e))
+			);
+	}
+}
+
+/// State of an external signing device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+	/// Device is connected and idle.
+	Ready,
+	/// Device waits for the user to confirm or reject a signing request.
+	AwaitingConfirmation,
+	/// Device is disconnected or can't be accessed.
+	Unavailable,
+}
+
+/// Signer holding account keys outside of the `SecretStore`, e.g. on a hardware wallet.
+pub trait SignerBackend: Send + Sync {
+	/// Addresses of the accounts held by the signer.
+	fn accounts(&self) -> Vec<Address>;
+
+	/// Signs an RLP-encoded unsigned transaction. Blocks until the user confirms or rejects it on the device.
+	fn sign_transaction(&self, account: &Address, transaction: &[u8]) -> Result<Signature, String>;
+
+	/// State of the device holding the account.
+	fn state(&self, account: &Address) -> DeviceState;
+}
+
 /// Account management.
 /// Responsible for unlocking accounts.
 pub struct AccountProvider {
 	unlocked: Mutex<HashMap<Address, AccountData>>,
 	sstore: Box<SecretStore>,
 	address_book: Mutex<AddressBook>,
+	backends: RwLock<Vec<Arc<SignerBackend>>>,
+	policies: RwLock<HashMap<Address, SigningPolicy>>,
+	spending: Mutex<SpendingBook>,
 }
 
 impl AccountProvider {
@@ -167,7 +307,10 @@ impl AccountProvider {
 		AccountProvider {
 			unlocked: Mutex::new(HashMap::new()),
 			address_book: Mutex::new(AddressBook::new(sstore.local_path().into())),
+			spending: Mutex::new(SpendingBook::new(Some(sstore.local_path().into()))),
 			sstore: sstore,
+			backends: RwLock::new(Vec::new()),
+			policies: RwLock::new(HashMap::new()),
 		}
 	}
 
@@ -176,7 +319,10 @@ impl AccountProvider {
 		AccountProvider {
 			unlocked: Mutex::new(HashMap::new()),
 			address_book: Mutex::new(AddressBook::new(Default::default())),
-			sstore: Box::new(EthStore::open(Box::new(NullDir::default())).unwrap())
+			sstore: Box::new(EthStore::open(Box::new(NullDir::default())).unwrap()),
+			backends: RwLock::new(Vec::new()),
+			policies: RwLock::new(HashMap::new()),
+			spending: Mutex::new(SpendingBook::new(None)),
 		}
 	}
 
@@ -194,6 +340,13 @@ impl AccountProvider {
 		Ok(address)
 	}
 
+	/// Creates new account from a BIP39 mnemonic phrase and a BIP32 derivation path, e.g. `m/44'/60'/0'/0/0`.
+	pub fn new_account_from_mnemonic(&self, phrase: &str, mnemonic_password: &str, path: &str, password: &str) -> Result<Address, Error> {
+		let path: DerivationPath = try!(path.parse().map_err(SSError::EthKey));
+		let keypair = try!(Mnemonic::new(phrase.to_owned(), mnemonic_password.to_owned(), path).generate().map_err(SSError::EthKey));
+		self.insert_account(keypair.secret().clone(), password)
+	}
+
 	/// Import a new presale wallet.
 	pub fn import_presale(&self, presale_json: &[u8], password: &str) -> Result<Address, Error> {
 		let address = try!(self.sstore.import_presale(presale_json, password));
@@ -206,12 +359,102 @@ impl AccountProvider {
 		Ok(Address::from(address).into())
 	}
 
-	/// Returns addresses of all accounts.
+	/// Returns addresses of all accounts, including the ones held by external signers.
 	pub fn accounts(&self) -> Result<Vec<Address>, Error> {
-		let accounts = try!(self.sstore.accounts());
+		let mut accounts = try!(self.sstore.accounts());
+		accounts.extend(self.external_accounts());
 		Ok(accounts)
 	}
 
+	/// Adds an external signer. Its accounts are listed along with the local ones.
+	pub fn add_signer_backend(&self, backend: Arc<SignerBackend>) {
+		self.backends.write().push(backend);
+	}
+
+	/// Returns addresses of the accounts held by external signers.
+	pub fn external_accounts(&self) -> Vec<Address> {
+		self.backends.read().iter().flat_map(|b| b.accounts().into_iter()).collect()
+	}
+
+	fn backend(&self, account: &Address) -> Option<Arc<SignerBackend>> {
+		self.backends.read().iter().find(|b| b.accounts().contains(account)).cloned()
+	}
+
+	/// Checks if the account key is held by an external signer.
+	pub fn is_external(&self, account: &Address) -> bool {
+		self.backend(account).is_some()
+	}
+
+	/// Returns state of the device holding the account, if it is held by an external signer.
+	pub fn device_state(&self, account: &Address) -> Option<DeviceState> {
+		self.backend(account).map(|b| b.state(account))
+	}
+
+	/// Signs an RLP-encoded unsigned transaction with an external signer.
+	/// Blocks until the user confirms or rejects it on the device.
+	pub fn sign_transaction_external(&self, account: Address, transaction: &[u8]) -> Result<Signature, Error> {
+		let backend = try!(self.backend(&account).ok_or(Error::NotSupported));
+		backend.sign_transaction(&account, transaction).map_err(Error::External)
+	}
+
+	/// Sets limits on transactions signed automatically with the account. `None` removes the limits.
+	pub fn set_signing_policy(&self, account: Address, policy: Option<SigningPolicy>) {
+		match policy {
+			Some(policy) => self.policies.write().insert(account, policy),
+			None => self.policies.write().remove(&account),
+		};
+	}
+
+	/// Returns limits on transactions signed automatically with the account.
+	pub fn signing_policy(&self, account: &Address) -> Option<SigningPolicy> {
+		self.policies.read().get(account).cloned()
+	}
+
+	/// Checks if the transaction could be signed automatically with the account.
+	pub fn check_signing_policy(&self, account: &Address, transaction: &Transaction) -> Result<(), PolicyViolation> {
+		match self.policies.read().get(account) {
+			Some(policy) => policy.check(transaction, self.spending.lock().spent_today(account)),
+			None => Ok(()),
+		}
+	}
+
+	/// Checks the transaction against the policy of the account and reserves its value towards the daily limit.
+	/// Must be followed by `record_spending` once the transaction is dispatched or `cancel_spending` if it's not.
+	pub fn authorize_transaction(&self, account: &Address, transaction: &Transaction) -> Result<(), PolicyViolation> {
+		let policies = self.policies.read();
+		let policy = match policies.get(account) {
+			Some(policy) => policy,
+			None => return Ok(()),
+		};
+
+		let mut spending = self.spending.lock();
+		try!(policy.check(transaction, spending.spent_today(account)));
+		if policy.daily_limit.is_some() {
+			spending.reserve(*account, transaction.value);
+		}
+		Ok(())
+	}
+
+	/// Counts value of the dispatched transaction towards the daily limit of the account.
+	pub fn record_spending(&self, account: &Address, transaction: &Transaction) {
+		let has_daily_limit = self.has_daily_limit(account);
+		let mut spending = self.spending.lock();
+		if has_daily_limit {
+			spending.record(*account, transaction.value);
+		} else {
+			spending.release(account, transaction.value);
+		}
+	}
+
+	/// Releases value reserved by a transaction which was not dispatched.
+	pub fn cancel_spending(&self, account: &Address, transaction: &Transaction) {
+		self.spending.lock().release(account, transaction.value);
+	}
+
+	fn has_daily_limit(&self, account: &Address) -> bool {
+		self.policies.read().get(account).map_or(false, |policy| policy.daily_limit.is_some())
+	}
+
 	/// Returns each address along with metadata.
 	pub fn addresses_info(&self) -> Result<HashMap<Address, AccountMeta>, Error> {
 		Ok(self.address_book.lock().get())
@@ -257,6 +500,43 @@ impl AccountProvider {
 		Ok(())
 	}
 
+	/// Creates new vault, encrypted with given password, and opens it.
+	pub fn create_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		self.sstore.create_vault(name, password).map_err(Into::into)
+	}
+
+	/// Opens the vault, making its accounts available.
+	pub fn open_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		self.sstore.open_vault(name, password).map_err(Into::into)
+	}
+
+	/// Closes the vault. Its accounts are locked and not listed anymore.
+	pub fn close_vault(&self, name: &str) -> Result<(), Error> {
+		try!(self.sstore.close_vault(name));
+		let accounts = try!(self.sstore.accounts());
+		let mut unlocked = self.unlocked.lock();
+		let closed: Vec<Address> = unlocked.keys().filter(|address| !accounts.contains(address)).cloned().collect();
+		for address in closed {
+			unlocked.remove(&address);
+		}
+		Ok(())
+	}
+
+	/// Returns names of all vaults.
+	pub fn list_vaults(&self) -> Result<Vec<String>, Error> {
+		self.sstore.list_vaults().map_err(Into::into)
+	}
+
+	/// Returns names of opened vaults.
+	pub fn list_opened_vaults(&self) -> Result<Vec<String>, Error> {
+		self.sstore.list_opened_vaults().map_err(Into::into)
+	}
+
+	/// Moves the account to an opened vault, or out of vaults if `vault` is `None`.
+	pub fn change_vault(&self, account: Address, vault: Option<&str>) -> Result<(), Error> {
+		self.sstore.change_account_vault(&account, vault).map_err(Into::into)
+	}
+
 	/// Helper method used for unlocking accounts.
 	fn unlock_account(&self, account: Address, password: String, unlock: Unlock) -> Result<(), Error> {
 		// verify password by signing dump message
@@ -301,49 +581,58 @@ impl AccountProvider {
 		unlocked.get(&account).is_some()
 	}
 
-	/// Signs the message. Account must be unlocked.
-	pub fn sign(&self, account: Address, message: Message) -> Result<Signature, Error> {
-		let data = {
-			let mut unlocked = self.unlocked.lock();
-			let data = try!(unlocked.get(&account).ok_or(Error::NotUnlocked)).clone();
-			if let Unlock::Temp = data.unlock {
-				unlocked.remove(&account).expect("data exists: so key must exist: qed");
+	/// Returns the password of an unlocked account.
+	/// If `use_up` is set an account unlocked for one use only is locked again.
+	fn unlocked_password(&self, account: &Address, use_up: bool) -> Result<String, Error> {
+		let mut unlocked = self.unlocked.lock();
+		let data = try!(unlocked.get(account).ok_or(Error::NotUnlocked)).clone();
+		if let Unlock::Temp = data.unlock {
+			if use_up {
+				unlocked.remove(account).expect("data exists: so key must exist: qed");
 			}
-			if let Unlock::Timed((ref start, ref duration)) = data.unlock {
-				if start.elapsed() > Duration::from_millis(*duration as u64) {
-					unlocked.remove(&account).expect("data exists: so key must exist: qed");
-					return Err(Error::NotUnlocked);
-				}
+		}
+		if let Unlock::Timed((ref start, ref duration)) = data.unlock {
+			if start.elapsed() > Duration::from_millis(*duration as u64) {
+				unlocked.remove(account).expect("data exists: so key must exist: qed");
+				return Err(Error::NotUnlocked);
 			}
-			data
-		};
+		}
+		Ok(data.password)
+	}
 
-		let signature = try!(self.sstore.sign(&account, &data.password, &message));
+	/// Signs the message. Account must be unlocked.
+	pub fn sign(&self, account: Address, message: Message) -> Result<Signature, Error> {
+		if self.is_external(&account) {
+			return Err(Error::NotSupported);
+		}
+		let password = try!(self.unlocked_password(&account, true));
+		let signature = try!(self.sstore.sign(&account, &password, &message));
 		Ok(signature)
 	}
 
 	/// Decrypts a message. Account must be unlocked.
 	pub fn decrypt(&self, account: Address, shared_mac: &[u8], message: &[u8]) -> Result<Vec<u8>, Error> {
-		let data = {
-			let mut unlocked = self.unlocked.lock();
-			let data = try!(unlocked.get(&account).ok_or(Error::NotUnlocked)).clone();
-			if let Unlock::Temp = data.unlock {
-				unlocked.remove(&account).expect("data exists: so key must exist: qed");
-			}
-			if let Unlock::Timed((ref start, ref duration)) = data.unlock {
-				if start.elapsed() > Duration::from_millis(*duration as u64) {
-					unlocked.remove(&account).expect("data exists: so key must exist: qed");
-					return Err(Error::NotUnlocked);
-				}
-			}
-			data
-		};
+		let password = try!(self.unlocked_password(&account, true));
+		Ok(try!(self.sstore.decrypt(&account, &password, shared_mac, message)))
+	}
+
+	/// Decrypts a message with given account password.
+	pub fn decrypt_with_password(&self, account: Address, password: String, shared_mac: &[u8], message: &[u8]) -> Result<Vec<u8>, Error> {
+		Ok(try!(self.sstore.decrypt(&account, &password, shared_mac, message)))
+	}
 
-		Ok(try!(self.sstore.decrypt(&account, &data.password, shared_mac, message)))
+	/// Returns the public key of the account. Account must be unlocked.
+	/// Doesn't use up a temporary unlock.
+	pub fn account_public(&self, account: Address) -> Result<Public, Error> {
+		let password = try!(self.unlocked_password(&account, false));
+		Ok(try!(self.sstore.public(&account, &password)))
 	}
 
 	/// Unlocks an account, signs the message, and locks it again.
 	pub fn sign_with_password(&self, account: Address, password: String, message: Message) -> Result<Signature, Error> {
+		if self.is_external(&account) {
+			return Err(Error::NotSupported);
+		}
 		let signature = try!(self.sstore.sign(&account, &password, &message));
 		Ok(signature)
 	}
@@ -359,14 +648,26 @@ impl AccountProvider {
 	}
 }
 
+/// Number of the current day since the epoch.
+fn day() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / (24 * 60 * 60)).unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::{AccountProvider, AddressBook};
 	use std::collections::HashMap;
+	use std::fs::File;
+	use std::io::Write;
 	use ethjson::misc::AccountMeta;
-	use ethstore::ethkey::{Generator, Random};
+	use ethstore::ethkey::{Generator, Random, Address};
 	use std::time::Duration;
 	use devtools::RandomTempPath;
+	use util::U256;
+	use signing_policy::{SigningPolicy, PolicyViolation};
+	use transaction::{Transaction, Action};
+	use ethstore::EthStore;
+	use ethstore::dir::DiskDirectory;
 
 	#[test]
 	fn should_save_and_reload_address_book() {
@@ -379,6 +680,129 @@ mod tests {
 		assert_eq!(b.get(), hash_map![1.into() => AccountMeta{name: "One".to_owned(), meta: "{1:1}".to_owned(), uuid: None}]);
 	}
 
+	#[test]
+	fn should_lock_accounts_of_closed_vault() {
+		let temp = RandomTempPath::create_dir();
+		let dir = DiskDirectory::create(temp.as_str()).unwrap();
+		let ap = AccountProvider::new(Box::new(EthStore::open_with_iterations(Box::new(dir), 1024).unwrap()));
+		let address = ap.new_account("test").unwrap();
+		ap.create_vault("vault", "vault password").unwrap();
+		ap.change_vault(address, Some("vault")).unwrap();
+		ap.unlock_account_permanently(address, "test".into()).unwrap();
+		assert!(ap.sign(address, Default::default()).is_ok());
+
+		ap.close_vault("vault").unwrap();
+		assert!(ap.accounts().unwrap().is_empty());
+		assert!(ap.sign(address, Default::default()).is_err());
+
+		ap.open_vault("vault", "vault password").unwrap();
+		assert_eq!(ap.accounts().unwrap(), vec![address]);
+		assert!(ap.sign(address, Default::default()).is_err());
+	}
+
+	#[test]
+	fn should_count_authorized_transactions_towards_daily_limit() {
+		let ap = AccountProvider::transient_provider();
+		let address = ap.new_account("test").unwrap();
+		ap.set_signing_policy(address, Some(SigningPolicy {
+			daily_limit: Some(U256::from(150)),
+			..Default::default()
+		}));
+		let t = Transaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(1),
+			gas: U256::from(21_000),
+			action: Action::Call(Address::from(5)),
+			value: U256::from(100),
+			data: vec![],
+		};
+
+		assert!(ap.authorize_transaction(&address, &t).is_ok());
+		assert_eq!(ap.check_signing_policy(&address, &t), Err(PolicyViolation::DailyLimitExceeded { limit: U256::from(150), spent: U256::from(100) }));
+		assert!(ap.authorize_transaction(&address, &t).is_err());
+		ap.cancel_spending(&address, &t);
+		assert!(ap.authorize_transaction(&address, &t).is_ok());
+		ap.record_spending(&address, &t);
+		assert!(ap.authorize_transaction(&address, &t).is_err());
+
+		ap.set_signing_policy(address, None);
+		assert!(ap.authorize_transaction(&address, &t).is_ok());
+	}
+
+	#[test]
+	fn should_keep_spending_across_restarts() {
+		let temp = RandomTempPath::create_dir();
+		let policy = SigningPolicy {
+			daily_limit: Some(U256::from(150)),
+			..Default::default()
+		};
+		let t = Transaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(1),
+			gas: U256::from(21_000),
+			action: Action::Call(Address::from(5)),
+			value: U256::from(100),
+			data: vec![],
+		};
+		let dir = DiskDirectory::create(temp.as_str()).unwrap();
+		File::create(temp.as_path().join("signing_spending.json")).unwrap().write_all(b"{}").unwrap();
+		let address = {
+			let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(dir)).unwrap()));
+			let address = ap.new_account("test").unwrap();
+			ap.set_signing_policy(address, Some(policy.clone()));
+			ap.authorize_transaction(&address, &t).unwrap();
+			ap.record_spending(&address, &t);
+			address
+		};
+
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(DiskDirectory::create(temp.as_str()).unwrap())).unwrap()));
+		ap.set_signing_policy(address, Some(policy));
+		assert_eq!(ap.check_signing_policy(&address, &t), Err(PolicyViolation::DailyLimitExceeded { limit: U256::from(150), spent: U256::from(100) }));
+	}
+
+	#[test]
+	fn should_refuse_daily_limit_without_spending_record() {
+		let temp = RandomTempPath::create_dir();
+		let dir = DiskDirectory::create(temp.as_str()).unwrap();
+		File::create(temp.as_path().join("signing_spending.json")).unwrap().write_all(b"{\"0x").unwrap();
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(dir)).unwrap()));
+		let limited = ap.new_account("test").unwrap();
+		let unlimited = ap.new_account("test").unwrap();
+		ap.set_signing_policy(limited, Some(SigningPolicy {
+			daily_limit: Some(U256::from(150)),
+			..Default::default()
+		}));
+		ap.set_signing_policy(unlimited, Some(SigningPolicy::default()));
+		let t = Transaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(1),
+			gas: U256::from(21_000),
+			action: Action::Call(Address::from(5)),
+			value: U256::from(100),
+			data: vec![],
+		};
+
+		assert_eq!(ap.authorize_transaction(&limited, &t), Err(PolicyViolation::SpendingUnknown));
+		assert!(ap.authorize_transaction(&unlimited, &t).is_ok());
+
+		let temp = RandomTempPath::create_dir();
+		let ap = AccountProvider::new(Box::new(EthStore::open(Box::new(DiskDirectory::create(temp.as_str()).unwrap())).unwrap()));
+		ap.set_signing_policy(limited, Some(SigningPolicy {
+			daily_limit: Some(U256::from(150)),
+			..Default::default()
+		}));
+		assert_eq!(ap.check_signing_policy(&limited, &t), Err(PolicyViolation::SpendingUnknown));
+	}
+
+	#[test]
+	fn new_account_from_mnemonic() {
+		let ap = AccountProvider::transient_provider();
+		let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+		let address = ap.new_account_from_mnemonic(phrase, "", "m/44'/60'/0'/0/0", "test").unwrap();
+		assert_eq!(address, "9858effd232b4033e47d90003d41ec34ecaeda94".parse::<Address>().unwrap());
+		assert!(ap.new_account_from_mnemonic(phrase, "", "44'/60'", "test").is_err());
+	}
+
 	#[test]
 	fn unlock_account_temp() {
 		let kp = Random.generate().unwrap();
@@ -404,6 +828,27 @@ mod tests {
 		assert!(ap.sign(kp.address(), Default::default()).is_ok());
 	}
 
+	#[test]
+	fn should_return_public_of_unlocked_account() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.account_public(kp.address()).is_err());
+		assert!(ap.unlock_account_permanently(kp.address(), "test".into()).is_ok());
+		assert_eq!(&ap.account_public(kp.address()).unwrap(), kp.public());
+	}
+
+	#[test]
+	fn should_keep_temporary_unlock_when_returning_public() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+		assert_eq!(&ap.account_public(kp.address()).unwrap(), kp.public());
+		assert!(ap.sign(kp.address(), Default::default()).is_ok());
+		assert!(ap.sign(kp.address(), Default::default()).is_err());
+	}
+
 	#[test]
 	fn unlock_account_timer() {
 		let kp = Random.generate().unwrap();
@@ -160,7 +160,7 @@ impl bc::group::BloomGroupDatabase for BlockChain {
 pub struct BlockChain {
 	// All locks must be captured in the order declared here.
 	blooms_config: bc::Config,
-	first_block: H256,
+	first_block: RwLock<H256>,
 
 	best_block: RwLock<BestBlock>,
 
@@ -192,7 +192,7 @@ impl BlockProvider for BlockChain {
 	}
 
 	fn first_block(&self) -> H256 {
-		self.first_block
+		self.first_block.read().clone()
 	}
 
 	/// Get raw block data
@@ -396,7 +396,7 @@ impl BlockChain {
 				levels: LOG_BLOOMS_LEVELS,
 				elements_per_index: LOG_BLOOMS_ELEMENTS_PER_INDEX,
 			},
-			first_block: H256::zero(),
+			first_block: RwLock::new(H256::zero()),
 			best_block: RwLock::new(BestBlock::default()),
 			block_headers: RwLock::new(HashMap::new()),
 			block_bodies: RwLock::new(HashMap::new()),
@@ -474,9 +474,9 @@ impl BlockChain {
 				batch.put(db::COL_EXTRA, b"first", &hash);
 				db.write(batch).expect("Low level database error.");
 
-				bc.first_block = hash;
+				*bc.first_block.write() = hash;
 			} else {
-				bc.first_block = H256::from_slice(&raw_first);
+				*bc.first_block.write() = H256::from_slice(&raw_first);
 			}
 
 			// and write them
@@ -725,6 +725,95 @@ impl BlockChain {
 		}
 	}
 
+	/// Inserts a verified block from the canonical chain directly below the first block.
+	///
+	/// This is used to fill in the ancient part of the chain after snapshot restoration.
+	/// The block's state is not executed. Total difficulty is derived from the first block.
+	/// Returns false if the block is not the parent of the current first block.
+	pub fn insert_ancient_block(&self, bytes: &[u8], receipts: Vec<Receipt>) -> bool {
+		let block = BlockView::new(bytes);
+		let header = block.header_view();
+		let hash = header.sha3();
+
+		let first_hash = self.first_block();
+		let first_details = match self.block_details(&first_hash) {
+			Some(details) => details,
+			None => return false,
+		};
+
+		if first_details.number == 0 || first_details.parent != hash || self.is_known(&hash) {
+			return false;
+		}
+
+		let first_difficulty = self.block_header(&first_hash)
+			.expect("first block is always stored; qed")
+			.difficulty()
+			.clone();
+
+		let info = BlockInfo {
+			hash: hash,
+			number: header.number(),
+			total_difficulty: first_details.total_difficulty - first_difficulty,
+			location: BlockLocation::CanonChain,
+		};
+
+		let mut batch = self.db.transaction();
+
+		let block_rlp = UntrustedRlp::new(bytes);
+		let compressed_header = block_rlp.at(0).unwrap().compress(RlpType::Blocks);
+		let compressed_body = UntrustedRlp::new(&Self::block_to_body(bytes)).compress(RlpType::Blocks);
+
+		// store block in db
+		batch.put(db::COL_HEADERS, &hash, &compressed_header);
+		batch.put(db::COL_BODIES, &hash, &compressed_body);
+
+		let mut details_update = HashMap::new();
+		details_update.insert(hash, BlockDetails {
+			number: header.number(),
+			total_difficulty: info.total_difficulty,
+			parent: header.parent_hash(),
+			children: vec![first_hash],
+		});
+
+		// the block right after genesis connects the restored chain to it.
+		let new_first = match self.block_details(&header.parent_hash()) {
+			Some(mut parent_details) => {
+				if !parent_details.children.contains(&hash) {
+					parent_details.children.push(hash);
+				}
+				details_update.insert(header.parent_hash(), parent_details);
+				header.parent_hash()
+			},
+			None => hash,
+		};
+
+		self.prepare_update(&mut batch, ExtrasUpdate {
+			block_hashes: self.prepare_block_hashes_update(bytes, &info),
+			block_details: details_update,
+			block_receipts: self.prepare_block_receipts_update(receipts, &info),
+			blocks_blooms: self.prepare_block_blooms_update(bytes, &info),
+			transactions_addresses: self.prepare_transaction_addresses_update(bytes, &info),
+			info: info,
+			block: bytes,
+		}, false);
+
+		batch.put(db::COL_EXTRA, b"first", &new_first);
+		self.db.write(batch).unwrap();
+		*self.first_block.write() = new_first;
+
+		true
+	}
+
+	/// Set the first block of the contiguous chain.
+	///
+	/// Used in snapshots to mark the oldest restored block.
+	pub fn set_first_block(&self, hash: H256) {
+		let mut batch = self.db.transaction();
+		batch.put(db::COL_EXTRA, b"first", &hash);
+		self.db.write(batch).unwrap();
+		*self.first_block.write() = hash;
+	}
+
 	/// Add a child to a given block. Assumes that the block hash is in
 	/// the chain and the child's parent is this block.
 	///
@@ -22,17 +22,19 @@ use std::time::{Instant};
 use time::precise_time_ns;
 
 // util
-use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock};
+use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock, HashDB};
 use util::journaldb;
-use util::{U256, H256, Address, H2048, Uint};
-use util::TrieFactory;
+use util::{U256, H256, Address, H2048, Uint, FixedHash, Hashable};
+use util::{TrieFactory, Trie, FatDB, Mismatch};
+use util::trie::{TrieItem, Result as TrieResult};
+use util::triehash::ordered_trie_root;
 use util::kvdb::*;
 
 // other
 use io::*;
 use views::{HeaderView, BodyView};
 use error::{ImportError, ExecutionError, CallError, BlockError, ImportResult, Error as EthcoreError};
-use header::BlockNumber;
+use header::{BlockNumber, Header};
 use state::State;
 use spec::Spec;
 use basic_types::Seal;
@@ -56,7 +58,7 @@ use client::{
 use client::Error as ClientError;
 use env_info::EnvInfo;
 use executive::{Executive, Executed, TransactOptions, contract_address};
-use receipt::LocalizedReceipt;
+use receipt::{Receipt, LocalizedReceipt};
 use trace::{TraceDB, ImportRequest as TraceImportRequest, LocalizedTrace, Database as TraceDatabase};
 use trace;
 use trace::FlatTransactionTraces;
@@ -141,6 +143,7 @@ pub struct Client {
 	queue_transactions: AtomicUsize,
 	last_hashes: RwLock<VecDeque<H256>>,
 	factories: Factories,
+	state_changes: Mutex<snapshot::ChangeTracker>,
 }
 
 /// The pruning constant -- how old blocks must be before we
@@ -171,9 +174,10 @@ impl Client {
 		let chain = Arc::new(BlockChain::new(config.blockchain.clone(), &gb, db.clone()));
 		let tracedb = RwLock::new(TraceDB::new(config.tracing.clone(), db.clone(), chain.clone()));
 
+		let trie_factory = TrieFactory::new(config.trie_spec.clone());
 		let journal_db = journaldb::new(db.clone(), config.pruning, ::db::COL_STATE);
 		let mut state_db = StateDB::new(journal_db);
-		if state_db.journal_db().is_empty() && try!(spec.ensure_db_good(&mut state_db)) {
+		if state_db.journal_db().is_empty() && try!(spec.ensure_db_good(&mut state_db, &trie_factory)) {
 			let mut batch = DBTransaction::new(&db);
 			try!(state_db.commit(&mut batch, 0, &spec.genesis_header().hash(), None));
 			try!(db.write(batch).map_err(ClientError::Database));
@@ -193,7 +197,7 @@ impl Client {
 
 		let factories = Factories {
 			vm: EvmFactory::new(config.vm_type.clone()),
-			trie: TrieFactory::new(config.trie_spec.clone()),
+			trie: trie_factory,
 			accountdb: Default::default(),
 		};
 
@@ -219,6 +223,7 @@ impl Client {
 			queue_transactions: AtomicUsize::new(0),
 			last_hashes: RwLock::new(VecDeque::new()),
 			factories: factories,
+			state_changes: Mutex::new(snapshot::ChangeTracker::new()),
 		};
 		Ok(Arc::new(client))
 	}
@@ -444,6 +449,10 @@ impl Client {
 		// already-imported block of the same number.
 		// TODO: Prove it with a test.
 		let mut state = block.drain();
+		if self.config.track_state_changes {
+			let touched = state.touched_addresses().into_iter().map(|address| address.sha3());
+			self.state_changes.lock().note_block(number, touched);
+		}
 		state.commit(&mut batch, number, hash, ancient).expect("DB commit failed.");
 
 		let route = chain.insert_block(&mut batch, block_data, receipts);
@@ -627,6 +636,35 @@ impl Client {
 
 		try!(snapshot::take_snapshot(&self.chain.read(), start_hash, db.as_hashdb(), writer, p));
 
+		// later incremental snapshots are based on this one.
+		self.state_changes.lock().prune(block_number);
+
+		Ok(())
+	}
+
+	/// Take an incremental snapshot at the given block, on top of the full snapshot with the given manifest.
+	/// Fails if the accounts changed since the base snapshot weren't tracked.
+	pub fn take_incremental_snapshot<W: snapshot_io::SnapshotWriter + Send>(
+		&self,
+		writer: W,
+		at: BlockID,
+		base: &snapshot::ManifestData,
+		p: &snapshot::Progress
+	) -> Result<(), EthcoreError> {
+		let db = self.state_db.lock().journal_db().boxed_clone();
+		let best_block_number = self.chain_info().best_block_number;
+		let block_number = try!(self.block_number(at).ok_or(snapshot::Error::InvalidStartingBlock(at)));
+
+		if best_block_number > HISTORY + block_number && db.is_pruned() {
+			return Err(snapshot::Error::OldBlockPrunedDB.into());
+		}
+
+		let start_hash = try!(self.block_hash(at).ok_or(snapshot::Error::InvalidStartingBlock(at)));
+		let changed = try!(self.state_changes.lock().changed_since(base.block_number, block_number)
+			.ok_or(snapshot::Error::UntrackedChanges(base.block_number)));
+
+		try!(snapshot::take_incremental_snapshot(&self.chain.read(), start_hash, db.as_hashdb(), base, &changed, writer, p));
+
 		Ok(())
 	}
 
@@ -671,6 +709,20 @@ impl Client {
 			}
 		}
 	}
+
+	/// Check block receipts against the header receipts root.
+	fn verify_receipts(header: &Header, receipts_bytes: &[u8]) -> Result<Vec<Receipt>, BlockImportError> {
+		let receipts_rlp = UntrustedRlp::new(receipts_bytes);
+		let receipts: Vec<Receipt> = try!(receipts_rlp.as_val().map_err(EthcoreError::from));
+		let receipts_root = ordered_trie_root(receipts_rlp.iter().map(|r| r.as_raw().to_vec()));
+		if receipts_root != *header.receipts_root() {
+			return Err(BlockImportError::Block(BlockError::InvalidReceiptsRoot(Mismatch {
+				expected: header.receipts_root().clone(),
+				found: receipts_root,
+			})));
+		}
+		Ok(receipts)
+	}
 }
 
 impl snapshot::DatabaseRestore for Client {
@@ -689,11 +741,21 @@ impl snapshot::DatabaseRestore for Client {
 		*state_db = StateDB::new(journaldb::new(db.clone(), self.pruning, ::db::COL_STATE));
 		*chain = Arc::new(BlockChain::new(self.config.blockchain.clone(), &[], db.clone()));
 		*tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
+		*self.state_changes.lock() = snapshot::ChangeTracker::new();
 		Ok(())
 	}
 }
 
 
+/// Iterate over the fat DB items, starting at the hash of `after`, or from the beginning.
+fn fat_db_iter<'a>(trie: &'a FatDB, after: Option<&[u8]>) -> TrieResult<Box<Iterator<Item = TrieItem<'a>> + 'a>> {
+	match after {
+		Some(after) => trie.iter_from(after).map(|iter| Box::new(iter) as Box<_>),
+		// seeking to an empty key would skip every item hashing below keccak("")
+		None => trie.iter(),
+	}
+}
+
 impl BlockChainClient for Client {
 	fn call(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics) -> Result<Executed, CallError> {
 		let header = try!(self.block_header(block).ok_or(CallError::StatePruned));
@@ -842,6 +904,83 @@ impl BlockChainClient for Client {
 		self.state_at(id).map(|s| s.storage_at(address, position))
 	}
 
+	fn list_accounts(&self, id: BlockID, after: Option<&Address>, count: u64) -> Option<Vec<Address>> {
+		if !self.factories.trie.is_fat() {
+			trace!(target: "fatdb", "list_accounts: Not a fat DB");
+			return None;
+		}
+
+		let (root, db) = match self.state_at(id) {
+			Some(state) => state.drop(),
+			None => return None,
+		};
+
+		let trie = match FatDB::new(db.as_hashdb(), &root) {
+			Ok(trie) => trie,
+			Err(e) => {
+				trace!(target: "fatdb", "list_accounts: Couldn't open the DB: {}", e);
+				return None;
+			}
+		};
+
+		let iter = match fat_db_iter(&trie, after.map(|after| &**after)) {
+			Ok(iter) => iter,
+			Err(e) => {
+				trace!(target: "fatdb", "list_accounts: Couldn't seek the DB: {}", e);
+				return None;
+			}
+		};
+
+		let accounts = iter.filter_map(|item| item.ok().map(|(addr, _)| Address::from_slice(&addr)))
+			.skip_while(|addr| after.map_or(false, |a| a == addr))
+			.take(count as usize)
+			.collect();
+
+		Some(accounts)
+	}
+
+	fn list_storage(&self, id: BlockID, account: &Address, after: Option<&H256>, count: u64) -> Option<Vec<H256>> {
+		if !self.factories.trie.is_fat() {
+			trace!(target: "fatdb", "list_storage: Not a fat DB");
+			return None;
+		}
+
+		let state = match self.state_at(id) {
+			Some(state) => state,
+			None => return None,
+		};
+
+		let root = match state.storage_root(account) {
+			Some(root) => root,
+			None => return Some(Vec::new()),
+		};
+
+		let (_, db) = state.drop();
+		let account_db = self.factories.accountdb.readonly(db.as_hashdb(), account.sha3());
+		let trie = match FatDB::new(account_db.as_hashdb(), &root) {
+			Ok(trie) => trie,
+			Err(e) => {
+				trace!(target: "fatdb", "list_storage: Couldn't open the DB: {}", e);
+				return None;
+			}
+		};
+
+		let iter = match fat_db_iter(&trie, after.map(|after| &**after)) {
+			Ok(iter) => iter,
+			Err(e) => {
+				trace!(target: "fatdb", "list_storage: Couldn't seek the DB: {}", e);
+				return None;
+			}
+		};
+
+		let keys = iter.filter_map(|item| item.ok().map(|(key, _)| H256::from_slice(&key)))
+			.skip_while(|key| after.map_or(false, |k| k == key))
+			.take(count as usize)
+			.collect();
+
+		Some(keys)
+	}
+
 	fn transaction(&self, id: TransactionID) -> Option<LocalizedTransaction> {
 		self.transaction_address(id).and_then(|address| self.chain.read().transaction(&address))
 	}
@@ -938,6 +1077,63 @@ impl BlockChainClient for Client {
 		Ok(try!(self.block_queue.import(unverified)))
 	}
 
+	fn import_ancient_block(&self, block_bytes: Bytes, receipts_bytes: Bytes) -> Result<H256, BlockImportError> {
+		let header: Header = try!(UntrustedRlp::new(&block_bytes).val_at(0).map_err(EthcoreError::from));
+		let hash = header.hash();
+		if self.chain.read().is_known(&hash) {
+			return Err(BlockImportError::Import(ImportError::AlreadyInChain));
+		}
+
+		try!(verification::verify_block_basic(&header, &block_bytes, &*self.engine));
+		try!(self.engine.verify_block_seal(&header));
+		let receipts = try!(Self::verify_receipts(&header, &receipts_bytes));
+
+		let chain = self.chain.read();
+		if !chain.insert_ancient_block(&block_bytes, receipts) {
+			return Err(BlockImportError::Other(format!("Block {} is not the parent of the first block", hash)));
+		}
+		chain.commit();
+		Ok(hash)
+	}
+
+	fn import_state_nodes(&self, nodes: Vec<Bytes>) {
+		let db = self.db.read().clone();
+		let mut state_db = journaldb::new(db.clone(), self.pruning, ::db::COL_STATE);
+		for node in nodes {
+			state_db.emplace(node.sha3(), node);
+		}
+		let mut batch = DBTransaction::new(&db);
+		let result = state_db.inject(&mut batch).map_err(|e| format!("{:?}", e)).and_then(|_| db.write(batch));
+		if let Err(e) = result {
+			warn!(target: "client", "Error writing downloaded state: {}", e);
+		}
+	}
+
+	fn import_pivot_block(&self, block_bytes: Bytes, receipts_bytes: Bytes, total_difficulty: U256) -> Result<H256, BlockImportError> {
+		let _import_lock = self.import_lock.lock();
+		let header: Header = try!(UntrustedRlp::new(&block_bytes).val_at(0).map_err(EthcoreError::from));
+		let hash = header.hash();
+		if self.chain.read().is_known(&hash) {
+			return Err(BlockImportError::Import(ImportError::AlreadyInChain));
+		}
+
+		try!(verification::verify_block_basic(&header, &block_bytes, &*self.engine));
+		try!(self.engine.verify_block_seal(&header));
+		let receipts = try!(Self::verify_receipts(&header, &receipts_bytes));
+		if total_difficulty < *header.difficulty() {
+			return Err(BlockImportError::Other(format!("Invalid total difficulty of block {}", hash)));
+		}
+		if !self.state_db.lock().journal_db().contains(header.state_root()) {
+			return Err(BlockImportError::Other(format!("State of block {} is missing", hash)));
+		}
+
+		let chain = self.chain.read();
+		chain.insert_snapshot_block(&block_bytes, receipts, Some(total_difficulty - *header.difficulty()), true);
+		chain.commit();
+		chain.set_first_block(hash.clone());
+		Ok(hash)
+	}
+
 	fn queue_info(&self) -> BlockQueueInfo {
 		self.block_queue.queue_info()
 	}
@@ -948,12 +1144,15 @@ impl BlockChainClient for Client {
 
 	fn chain_info(&self) -> BlockChainInfo {
 		let chain = self.chain.read();
+		let first_block_number = chain.first_block_number();
 		BlockChainInfo {
 			total_difficulty: chain.best_block_total_difficulty(),
 			pending_total_difficulty: chain.best_block_total_difficulty(),
 			genesis_hash: chain.genesis_hash(),
 			best_block_hash: chain.best_block_hash(),
-			best_block_number: From::from(chain.best_block_number())
+			best_block_number: From::from(chain.best_block_number()),
+			first_block_hash: if first_block_number == 0 { None } else { Some(chain.first_block()) },
+			first_block_number: if first_block_number == 0 { None } else { Some(first_block_number) },
 		}
 	}
 
@@ -1043,6 +1242,10 @@ impl BlockChainClient for Client {
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.miner.pending_transactions()
 	}
+
+	fn pending_transaction(&self, hash: &H256) -> Option<SignedTransaction> {
+		self.miner.transaction(hash)
+	}
 }
 
 impl MiningBlockChainClient for Client {
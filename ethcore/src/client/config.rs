@@ -25,7 +25,7 @@ use util::{journaldb, CompactionProfile};
 use util::trie::TrieSpec;
 
 /// Client state db compaction profile
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum DatabaseCompactionProfile {
 	/// Default compaction profile
 	Default,
@@ -107,6 +107,8 @@ pub struct ClientConfig {
 	pub mode: Mode,
 	/// Type of block verifier used by client.
 	pub verifier_type: VerifierType,
+	/// Whether to track the accounts changed by each block, for incremental snapshots.
+	pub track_state_changes: bool,
 }
 
 #[cfg(test)]
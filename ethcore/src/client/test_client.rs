@@ -32,7 +32,7 @@ use filter::Filter;
 use log_entry::LocalizedLogEntry;
 use receipt::{Receipt, LocalizedReceipt};
 use blockchain::extras::BlockReceipts;
-use error::{ImportResult};
+use error::{ImportResult, ImportError};
 use evm::{Factory as EvmFactory, VMType};
 use miner::{Miner, MinerService, TransactionImportResult};
 use spec::Spec;
@@ -80,6 +80,8 @@ pub struct TestBlockChainClient {
 	pub vm_factory: EvmFactory,
 	/// Timestamp assigned to latest sealed block
 	pub latest_block_timestamp: RwLock<u64>,
+	/// First block of the chain if ancient blocks are missing.
+	pub first_block: RwLock<Option<(H256, BlockNumber)>>,
 }
 
 #[derive(Clone)]
@@ -123,6 +125,7 @@ impl TestBlockChainClient {
 			spec: spec,
 			vm_factory: EvmFactory::new(VMType::Interpreter),
 			latest_block_timestamp: RwLock::new(10_000_000),
+			first_block: RwLock::new(None),
 		};
 		client.add_blocks(1, EachBlockWith::Nothing); // add genesis block
 		client.genesis_hash = client.last_hash.read().clone();
@@ -169,6 +172,12 @@ impl TestBlockChainClient {
 		*self.latest_block_timestamp.write() = ts;
 	}
 
+	/// Mark blocks below the given number as ancient blocks that are yet to be imported.
+	pub fn set_first_block(&self, number: BlockNumber) {
+		let hash = self.numbers.read()[&(number as usize)].clone();
+		*self.first_block.write() = Some((hash, number));
+	}
+
 	/// Set logs to return for each logs call.
 	pub fn set_logs(&self, logs: Vec<LocalizedLogEntry>) {
 		*self.logs.write() = logs;
@@ -301,7 +310,7 @@ impl MiningBlockChainClient for TestBlockChainClient {
 		let genesis_header = self.spec.genesis_header();
 		let mut db_result = get_temp_state_db();
 		let mut db = db_result.take();
-		self.spec.ensure_db_good(&mut db).unwrap();
+		self.spec.ensure_db_good(&mut db, &Default::default()).unwrap();
 
 		let last_hashes = vec![genesis_header.hash()];
 		let mut open_block = OpenBlock::new(
@@ -384,6 +393,14 @@ impl BlockChainClient for TestBlockChainClient {
 		}
 	}
 
+	fn list_accounts(&self, _id: BlockID, _after: Option<&Address>, _count: u64) -> Option<Vec<Address>> {
+		None
+	}
+
+	fn list_storage(&self, _id: BlockID, _account: &Address, _after: Option<&H256>, _count: u64) -> Option<Vec<H256>> {
+		None
+	}
+
 	fn transaction(&self, _id: TransactionID) -> Option<LocalizedTransaction> {
 		None	// Simple default.
 	}
@@ -546,6 +563,40 @@ impl BlockChainClient for TestBlockChainClient {
 		Ok(h)
 	}
 
+	fn import_ancient_block(&self, b: Bytes, _r: Bytes) -> Result<H256, BlockImportError> {
+		let header = Rlp::new(&b).val_at::<BlockHeader>(0);
+		let h = header.hash();
+		let mut first_block = self.first_block.write();
+		let first_parent = match *first_block {
+			Some((ref first_hash, _)) => Rlp::new(&self.blocks.read()[first_hash]).val_at::<BlockHeader>(0).parent_hash().clone(),
+			None => return Err(BlockImportError::Import(ImportError::AlreadyInChain)),
+		};
+		if first_parent != h {
+			return Err(BlockImportError::Other(format!("Block {} is not the parent of the first block", h)));
+		}
+		self.blocks.write().insert(h.clone(), b);
+		self.numbers.write().insert(header.number() as usize, h.clone());
+		*first_block = if header.number() > 1 { Some((h.clone(), header.number())) } else { None };
+		Ok(h)
+	}
+
+	fn import_state_nodes(&self, _nodes: Vec<Bytes>) {
+	}
+
+	fn import_pivot_block(&self, b: Bytes, _r: Bytes, total_difficulty: U256) -> Result<H256, BlockImportError> {
+		let header = Rlp::new(&b).val_at::<BlockHeader>(0);
+		let h = header.hash();
+		if self.blocks.read().contains_key(&h) {
+			return Err(BlockImportError::Import(ImportError::AlreadyInChain));
+		}
+		self.blocks.write().insert(h.clone(), b);
+		self.numbers.write().insert(header.number() as usize, h.clone());
+		*self.last_hash.write() = h.clone();
+		*self.difficulty.write() = total_difficulty;
+		*self.first_block.write() = Some((h.clone(), header.number()));
+		Ok(h)
+	}
+
 	fn queue_info(&self) -> QueueInfo {
 		QueueInfo {
 			verified_queue_size: self.queue_size.load(AtomicOrder::Relaxed),
@@ -571,6 +622,8 @@ impl BlockChainClient for TestBlockChainClient {
 			genesis_hash: self.genesis_hash.clone(),
 			best_block_hash: self.last_hash.read().clone(),
 			best_block_number: self.blocks.read().len() as BlockNumber - 1,
+			first_block_hash: self.first_block.read().as_ref().map(|&(ref h, _)| h.clone()),
+			first_block_number: self.first_block.read().as_ref().map(|&(_, n)| n),
 		}
 	}
 
@@ -599,4 +652,8 @@ impl BlockChainClient for TestBlockChainClient {
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.miner.pending_transactions()
 	}
+
+	fn pending_transaction(&self, hash: &H256) -> Option<SignedTransaction> {
+		self.miner.transaction(hash)
+	}
 }
@@ -112,6 +112,14 @@ pub trait BlockChainClient : Sync + Send {
 			Therefore storage_at has returned Some; qed")
 	}
 
+	/// Get a list of all accounts in the block `id`, if fat DB is in operation, otherwise `None`.
+	/// If `after` is set the list starts with the following item.
+	fn list_accounts(&self, id: BlockID, after: Option<&Address>, count: u64) -> Option<Vec<Address>>;
+
+	/// Get a list of all storage keys of `account` in the block `id`, if fat DB is in operation,
+	/// otherwise `None`. If `after` is set the list starts with the following item.
+	fn list_storage(&self, id: BlockID, account: &Address, after: Option<&H256>, count: u64) -> Option<Vec<H256>>;
+
 	/// Get transaction with given hash.
 	fn transaction(&self, id: TransactionID) -> Option<LocalizedTransaction>;
 
@@ -137,6 +145,18 @@ pub trait BlockChainClient : Sync + Send {
 	/// Import a block into the blockchain.
 	fn import_block(&self, bytes: Bytes) -> Result<H256, BlockImportError>;
 
+	/// Import a block with its receipts below the first block of the chain, without executing it.
+	/// Used to fill in the ancient part of the chain after snapshot restoration.
+	fn import_ancient_block(&self, block_bytes: Bytes, receipts_bytes: Bytes) -> Result<H256, BlockImportError>;
+
+	/// Write downloaded state trie nodes and code to the state database.
+	fn import_state_nodes(&self, nodes: Vec<Bytes>);
+
+	/// Import a block whose state has been downloaded as the new best block, without executing it.
+	/// The block and its total difficulty must come from a trusted checkpoint.
+	/// Blocks below it are imported later as ancient blocks.
+	fn import_pivot_block(&self, block_bytes: Bytes, receipts_bytes: Bytes, total_difficulty: U256) -> Result<H256, BlockImportError>;
+
 	/// Get block queue information.
 	fn queue_info(&self) -> BlockQueueInfo;
 
@@ -185,6 +205,9 @@ pub trait BlockChainClient : Sync + Send {
 	/// list all transactions
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
 
+	/// Get a pending transaction by hash.
+	fn pending_transaction(&self, hash: &H256) -> Option<SignedTransaction>;
+
 	/// Get the gas price distribution.
 	fn gas_price_statistics(&self, sample_size: usize, distribution_size: usize) -> Result<Vec<U256>, ()> {
 		let mut h = self.chain_info().best_block_hash;
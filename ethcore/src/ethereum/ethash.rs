@@ -77,13 +77,18 @@ impl From<ethjson::spec::EthashParams> for EthashParams {
 	}
 }
 
+lazy_static! {
+	/// Light caches depend on the block number only, so all chains running in the process share them.
+	static ref ETHASH_MANAGER: Arc<EthashManager> = Arc::new(EthashManager::new());
+}
+
 /// Engine using Ethash proof-of-work consensus algorithm, suitable for Ethereum
 /// mainnet chains in the Olympic, Frontier and Homestead eras.
 pub struct Ethash {
 	params: CommonParams,
 	ethash_params: EthashParams,
 	builtins: BTreeMap<Address, Builtin>,
-	pow: EthashManager,
+	pow: Arc<EthashManager>,
 }
 
 impl Ethash {
@@ -93,7 +98,7 @@ impl Ethash {
 			params: params,
 			ethash_params: ethash_params,
 			builtins: builtins,
-			pow: EthashManager::new(),
+			pow: ETHASH_MANAGER.clone(),
 		}
 	}
 }
@@ -380,7 +385,7 @@ mod tests {
 		let genesis_header = spec.genesis_header();
 		let mut db_result = get_temp_state_db();
 		let mut db = db_result.take();
-		spec.ensure_db_good(&mut db).unwrap();
+		spec.ensure_db_good(&mut db, &Default::default()).unwrap();
 		let last_hashes = Arc::new(vec![genesis_header.hash()]);
 		let b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, Address::zero(), (3141562.into(), 31415620.into()), vec![]).unwrap();
 		let b = b.close();
@@ -394,7 +399,7 @@ mod tests {
 		let genesis_header = spec.genesis_header();
 		let mut db_result = get_temp_state_db();
 		let mut db = db_result.take();
-		spec.ensure_db_good(&mut db).unwrap();
+		spec.ensure_db_good(&mut db, &Default::default()).unwrap();
 		let last_hashes = Arc::new(vec![genesis_header.hash()]);
 		let mut b = OpenBlock::new(engine, Default::default(), false, db, &genesis_header, last_hashes, Address::zero(), (3141562.into(), 31415620.into()), vec![]).unwrap();
 		let mut uncle = Header::new();
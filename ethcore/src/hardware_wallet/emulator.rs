@@ -0,0 +1,161 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Software emulation of a Ledger device running the Ethereum application.
+
+use std::io;
+use std::time::Duration;
+use std::collections::VecDeque;
+use util::Address;
+use util::sha3::Hashable;
+use rlp::PayloadInfo;
+use ethkey::{KeyPair, ExtendedSecret, DerivationPath, sign};
+use super::hid::{self, HidDevice, Decoder};
+
+/// Emulated device holding keys derived from a fixed seed.
+pub struct Emulator {
+	master: ExtendedSecret,
+	decoder: Decoder,
+	responses: VecDeque<Vec<u8>>,
+	signing: Option<(DerivationPath, Vec<u8>)>,
+	reject: bool,
+	silent: bool,
+}
+
+impl Emulator {
+	/// Creates emulated device with a fixed seed.
+	pub fn new() -> Self {
+		Emulator {
+			master: ExtendedSecret::with_seed(&[0x42; 32]).unwrap(),
+			decoder: Decoder::default(),
+			responses: VecDeque::new(),
+			signing: None,
+			reject: false,
+			silent: false,
+		}
+	}
+
+	/// Makes the emulated user reject all signing requests.
+	pub fn reject_signing(&mut self) {
+		self.reject = true;
+	}
+
+	/// Makes the emulated device stop responding, like a user who never confirms a request.
+	pub fn stop_responding(&mut self) {
+		self.silent = true;
+	}
+
+	/// Address of the key at given path.
+	pub fn address(&self, path: &DerivationPath) -> Address {
+		self.keypair(path).address()
+	}
+
+	fn keypair(&self, path: &DerivationPath) -> KeyPair {
+		KeyPair::from_secret(self.master.derive_path(path).unwrap().secret().clone()).unwrap()
+	}
+
+	fn handle(&mut self, apdu: &[u8]) -> (Vec<u8>, u16) {
+		if apdu.len() < 5 || apdu[0] != 0xe0 || apdu.len() != 5 + apdu[4] as usize {
+			return (Vec::new(), 0x6700);
+		}
+		let data = &apdu[5..];
+		match (apdu[1], apdu[2]) {
+			(0x02, _) => {
+				let path = match decode_path(data) {
+					Some((path, _)) => path,
+					None => return (Vec::new(), 0x6a80),
+				};
+				let keypair = self.keypair(&path);
+				let address = format!("{:?}", keypair.address());
+				let mut response = vec![65, 0x04];
+				response.extend_from_slice(&**keypair.public());
+				response.push(address.len() as u8);
+				response.extend_from_slice(address.as_bytes());
+				(response, 0x9000)
+			},
+			(0x04, 0x00) => {
+				self.signing = decode_path(data).map(|(path, rest)| (path, rest.to_vec()));
+				self.sign()
+			},
+			(0x04, 0x80) => {
+				if let Some((_, ref mut transaction)) = self.signing {
+					transaction.extend_from_slice(data);
+				}
+				self.sign()
+			},
+			_ => (Vec::new(), 0x6d00),
+		}
+	}
+
+	fn sign(&mut self) -> (Vec<u8>, u16) {
+		let complete = match self.signing {
+			Some((_, ref transaction)) => PayloadInfo::from(transaction).ok().map_or(false, |info| info.total() == transaction.len()),
+			None => return (Vec::new(), 0x6a80),
+		};
+		if !complete {
+			return (Vec::new(), 0x9000);
+		}
+
+		let (path, transaction) = self.signing.take().unwrap();
+		if self.reject {
+			return (Vec::new(), 0x6985);
+		}
+		let signature = sign(self.keypair(&path).secret(), &transaction.sha3()).unwrap();
+		let mut response = vec![signature.v() + 27];
+		response.extend_from_slice(signature.r());
+		response.extend_from_slice(signature.s());
+		(response, 0x9000)
+	}
+}
+
+impl HidDevice for Emulator {
+	fn write_report(&mut self, report: &[u8]) -> io::Result<()> {
+		if let Some(apdu) = try!(self.decoder.push(report)) {
+			if self.silent {
+				return Ok(());
+			}
+			let (mut response, status) = self.handle(&apdu);
+			response.push((status >> 8) as u8);
+			response.push(status as u8);
+			self.responses.extend(hid::encode(&response));
+		}
+		Ok(())
+	}
+
+	fn read_report(&mut self, report: &mut [u8], _timeout: Duration) -> io::Result<usize> {
+		let next = try!(self.responses.pop_front().ok_or_else(hid::timed_out));
+		report[..next.len()].copy_from_slice(&next);
+		Ok(next.len())
+	}
+}
+
+fn decode_path(data: &[u8]) -> Option<(DerivationPath, &[u8])> {
+	let len = match data.first() {
+		Some(len) => *len as usize,
+		None => return None,
+	};
+	if data.len() < 1 + len * 4 {
+		return None;
+	}
+	let path: Vec<u32> = (0..len)
+		.map(|i| {
+			let index = &data[1 + i * 4..5 + i * 4];
+			(index[0] as u32) << 24 | (index[1] as u32) << 16 | (index[2] as u32) << 8 | index[3] as u32
+		})
+		.collect();
+	Some((path.into(), &data[1 + len * 4..]))
+}
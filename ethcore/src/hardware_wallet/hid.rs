@@ -0,0 +1,254 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! HID transport and APDU framing used by Ledger devices.
+
+use std::io;
+use std::time::Duration;
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::io::{Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use libc;
+
+/// Size of a single HID report.
+pub const REPORT_SIZE: usize = 64;
+
+const CHANNEL: u16 = 0x0101;
+const TAG_APDU: u8 = 0x05;
+#[cfg(target_os = "linux")]
+const HIDRAW_CLASS: &'static str = "/sys/class/hidraw";
+
+/// Raw HID device exchanging fixed-size reports.
+pub trait HidDevice: Send {
+	/// Writes a single report of `REPORT_SIZE` bytes.
+	fn write_report(&mut self, report: &[u8]) -> io::Result<()>;
+
+	/// Reads a single report into `report`, returning the number of bytes read.
+	/// Fails with `TimedOut` if no report arrives within `timeout`.
+	fn read_report(&mut self, report: &mut [u8], timeout: Duration) -> io::Result<usize>;
+}
+
+/// HID device accessed through the Linux `hidraw` driver.
+#[cfg(target_os = "linux")]
+pub struct Hidraw {
+	file: fs::File,
+}
+
+#[cfg(target_os = "linux")]
+impl Hidraw {
+	/// Opens a `/dev/hidrawN` device node.
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		let file = try!(fs::OpenOptions::new().read(true).write(true).open(path));
+		Ok(Hidraw {
+			file: file,
+		})
+	}
+
+	/// Lists device nodes of all connected devices made by one of given vendors.
+	pub fn enumerate(vendors: &[u16]) -> Vec<PathBuf> {
+		let entries = match fs::read_dir(HIDRAW_CLASS) {
+			Ok(entries) => entries,
+			Err(_) => return Vec::new(),
+		};
+
+		let mut devices: Vec<PathBuf> = entries
+			.filter_map(|entry| entry.ok())
+			.filter(|entry| {
+				let mut uevent = String::new();
+				fs::File::open(entry.path().join("device").join("uevent"))
+					.and_then(|mut f| f.read_to_string(&mut uevent))
+					.ok()
+					.and_then(|_| uevent_vendor(&uevent))
+					.map_or(false, |vendor| vendors.contains(&vendor))
+			})
+			.map(|entry| Path::new("/dev").join(entry.file_name()))
+			.collect();
+		devices.sort();
+		devices
+	}
+}
+
+#[cfg(target_os = "linux")]
+impl HidDevice for Hidraw {
+	fn write_report(&mut self, report: &[u8]) -> io::Result<()> {
+		// hidraw expects the report number in front of the data, Ledger uses report 0.
+		let mut data = Vec::with_capacity(report.len() + 1);
+		data.push(0);
+		data.extend_from_slice(report);
+		self.file.write_all(&data)
+	}
+
+	fn read_report(&mut self, report: &mut [u8], timeout: Duration) -> io::Result<usize> {
+		let timeout_ms = timeout.as_secs() * 1000 + (timeout.subsec_nanos() / 1_000_000) as u64;
+		let mut fd = libc::pollfd { fd: self.file.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+		let ready = unsafe { libc::poll(&mut fd, 1, timeout_ms as libc::c_int) };
+		match ready {
+			r if r < 0 => Err(io::Error::last_os_error()),
+			0 => Err(timed_out()),
+			_ => self.file.read(report),
+		}
+	}
+}
+
+/// Extracts vendor id from `HID_ID=bus:vendor:product` line of device `uevent` file.
+#[cfg(target_os = "linux")]
+fn uevent_vendor(uevent: &str) -> Option<u16> {
+	uevent.lines()
+		.filter(|line| line.starts_with("HID_ID="))
+		.filter_map(|line| line.trim().split(':').nth(1))
+		.filter_map(|vendor| u32::from_str_radix(vendor, 16).ok())
+		.map(|vendor| vendor as u16)
+		.next()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Error returned when the device does not respond in time.
+pub fn timed_out() -> io::Error {
+	io::Error::new(io::ErrorKind::TimedOut, "Device did not respond in time")
+}
+
+/// Splits an APDU into HID reports.
+pub fn encode(apdu: &[u8]) -> Vec<Vec<u8>> {
+	let mut reports = Vec::new();
+	let mut offset = 0;
+	let mut sequence: u16 = 0;
+	while offset < apdu.len() || sequence == 0 {
+		let mut report = Vec::with_capacity(REPORT_SIZE);
+		report.push((CHANNEL >> 8) as u8);
+		report.push(CHANNEL as u8);
+		report.push(TAG_APDU);
+		report.push((sequence >> 8) as u8);
+		report.push(sequence as u8);
+		if sequence == 0 {
+			report.push((apdu.len() >> 8) as u8);
+			report.push(apdu.len() as u8);
+		}
+		let chunk = ::std::cmp::min(REPORT_SIZE - report.len(), apdu.len() - offset);
+		report.extend_from_slice(&apdu[offset..offset + chunk]);
+		report.resize(REPORT_SIZE, 0);
+		reports.push(report);
+		offset += chunk;
+		sequence += 1;
+	}
+	reports
+}
+
+/// Reassembles an APDU from consecutive HID reports.
+#[derive(Default)]
+pub struct Decoder {
+	sequence: u16,
+	expected: usize,
+	data: Vec<u8>,
+}
+
+impl Decoder {
+	/// Consumes next report. Returns the APDU once all its reports were received.
+	pub fn push(&mut self, report: &[u8]) -> io::Result<Option<Vec<u8>>> {
+		let header = if self.sequence == 0 { 7 } else { 5 };
+		if report.len() < header {
+			return Err(invalid_data("HID report too short"));
+		}
+		if ((report[0] as u16) << 8 | report[1] as u16) != CHANNEL || report[2] != TAG_APDU {
+			return Err(invalid_data("Unexpected HID channel or tag"));
+		}
+		if ((report[3] as u16) << 8 | report[4] as u16) != self.sequence {
+			return Err(invalid_data("Unexpected HID report sequence"));
+		}
+		if self.sequence == 0 {
+			self.expected = (report[5] as usize) << 8 | report[6] as usize;
+		}
+
+		let chunk = ::std::cmp::min(report.len() - header, self.expected - self.data.len());
+		self.data.extend_from_slice(&report[header..header + chunk]);
+		self.sequence += 1;
+
+		if self.data.len() == self.expected {
+			self.sequence = 0;
+			Ok(Some(::std::mem::replace(&mut self.data, Vec::new())))
+		} else {
+			Ok(None)
+		}
+	}
+}
+
+/// Sends an APDU to the device.
+pub fn write_apdu(device: &mut HidDevice, apdu: &[u8]) -> io::Result<()> {
+	for report in encode(apdu) {
+		try!(device.write_report(&report));
+	}
+	Ok(())
+}
+
+/// Reads an APDU response from the device. Fails if any of its reports does not arrive within `timeout`.
+pub fn read_apdu(device: &mut HidDevice, timeout: Duration) -> io::Result<Vec<u8>> {
+	let mut decoder = Decoder::default();
+	let mut report = [0u8; REPORT_SIZE];
+	loop {
+		let read = try!(device.read_report(&mut report, timeout));
+		if let Some(apdu) = try!(decoder.push(&report[..read])) {
+			return Ok(apdu);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{encode, Decoder, REPORT_SIZE};
+
+	#[test]
+	fn should_split_and_reassemble_apdu() {
+		let apdu: Vec<u8> = (0..200).map(|i| i as u8).collect();
+		let reports = encode(&apdu);
+		assert_eq!(reports.len(), 4);
+		assert!(reports.iter().all(|r| r.len() == REPORT_SIZE));
+		assert_eq!(&reports[0][..7], &[0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 200]);
+		assert_eq!(&reports[1][..5], &[0x01, 0x01, 0x05, 0x00, 0x01]);
+
+		let mut decoder = Decoder::default();
+		assert_eq!(decoder.push(&reports[0]).unwrap(), None);
+		assert_eq!(decoder.push(&reports[1]).unwrap(), None);
+		assert_eq!(decoder.push(&reports[2]).unwrap(), None);
+		assert_eq!(decoder.push(&reports[3]).unwrap(), Some(apdu));
+	}
+
+	#[test]
+	fn should_reject_out_of_order_reports() {
+		let apdu = vec![0u8; 100];
+		let reports = encode(&apdu);
+		let mut decoder = Decoder::default();
+		assert!(decoder.push(&reports[1]).is_err());
+	}
+
+	#[test]
+	#[cfg(target_os = "linux")]
+	fn should_parse_vendor_from_uevent() {
+		use super::uevent_vendor;
+
+		let uevent = "DRIVER=hid-generic\nHID_ID=0003:00002C97:00000001\nHID_NAME=Ledger Nano S\n";
+		assert_eq!(uevent_vendor(uevent), Some(0x2c97));
+		assert_eq!(uevent_vendor("DRIVER=hid-generic\n"), None);
+	}
+}
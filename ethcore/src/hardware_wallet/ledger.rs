@@ -0,0 +1,505 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Ledger Nano S Ethereum application client.
+
+use std::{fmt, io};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use util::{Mutex, RwLock, Address, H256};
+use ethkey::{Signature, DerivationPath};
+use account_provider::{SignerBackend, DeviceState};
+use super::hid::{self, HidDevice};
+
+/// USB vendor ids used by Ledger devices.
+pub const LEDGER_VENDORS: [u16; 2] = [0x2c97, 0x2581];
+
+/// Number of accounts looked up on the device.
+const ACCOUNTS_SCANNED: u32 = 5;
+/// Maximal size of APDU data.
+const MAX_CHUNK_SIZE: usize = 255;
+/// Time to wait for a response which needs no user interaction.
+const RESPONSE_TIMEOUT_MS: u64 = 5_000;
+/// Time to wait for the user to confirm or reject a signature.
+const CONFIRMATION_TIMEOUT_MS: u64 = 300_000;
+/// Minimal time between looking for connected devices.
+const SCAN_INTERVAL_MS: u64 = 5_000;
+
+const CLA: u8 = 0xe0;
+const INS_GET_ADDRESS: u8 = 0x02;
+const INS_SIGN: u8 = 0x04;
+const P1_FIRST_CHUNK: u8 = 0x00;
+const P1_MORE_CHUNKS: u8 = 0x80;
+const SW_OK: u16 = 0x9000;
+const SW_REJECTED: u16 = 0x6985;
+
+/// Ledger communication errors.
+#[derive(Debug)]
+pub enum Error {
+	/// Transport error.
+	Io(io::Error),
+	/// Device responded with malformed data.
+	Protocol(&'static str),
+	/// Device returned an error status word.
+	Status(u16),
+	/// User rejected the request on the device.
+	UserRejected,
+	/// Account is not held by this device.
+	UnknownAccount,
+	/// Device is handling another request.
+	Busy,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			Error::Io(ref e) => write!(f, "Device communication error: {}", e),
+			Error::Protocol(ref e) => write!(f, "Invalid device response: {}", e),
+			Error::Status(status) => write!(f, "Device returned status {:#06x}", status),
+			Error::UserRejected => write!(f, "Request rejected on the device"),
+			Error::UnknownAccount => write!(f, "Account not found on the device"),
+			Error::Busy => write!(f, "Device is busy with another request"),
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		Error::Io(e)
+	}
+}
+
+/// Ledger device running the Ethereum application.
+pub struct Ledger {
+	/// The device, taken out while a request is in progress.
+	device: Mutex<Option<Box<HidDevice>>>,
+	accounts: RwLock<Vec<(Address, DerivationPath)>>,
+	pending: Mutex<Option<Address>>,
+	available: AtomicBool,
+}
+
+impl Ledger {
+	/// Creates a client for a device connected over given transport.
+	pub fn new(device: Box<HidDevice>) -> Self {
+		Ledger {
+			device: Mutex::new(Some(device)),
+			accounts: RwLock::new(Vec::new()),
+			pending: Mutex::new(None),
+			available: AtomicBool::new(true),
+		}
+	}
+
+	/// Reloads list of accounts from the device.
+	pub fn refresh(&self) -> Result<(), Error> {
+		let mut accounts = Vec::new();
+		for index in 0..ACCOUNTS_SCANNED {
+			let path = DerivationPath::ethereum(index);
+			let address = try!(self.address(&path));
+			accounts.push((address, path));
+		}
+		*self.accounts.write() = accounts;
+		Ok(())
+	}
+
+	/// Returns address of the key at given derivation path.
+	pub fn address(&self, path: &DerivationPath) -> Result<Address, Error> {
+		let response = try!(self.with_device(|device| exchange(device, INS_GET_ADDRESS, 0, 0, &encode_path(path), response_timeout())));
+		// public key length, public key, address length, address as hex
+		let public_len = try!(response.first().cloned().ok_or(Error::Protocol("missing public key"))) as usize;
+		let address_start = 1 + public_len + 1;
+		if response.len() < address_start {
+			return Err(Error::Protocol("missing address"));
+		}
+		let address_len = response[address_start - 1] as usize;
+		if response.len() < address_start + address_len {
+			return Err(Error::Protocol("truncated address"));
+		}
+		::std::str::from_utf8(&response[address_start..address_start + address_len])
+			.ok()
+			.and_then(|address| address.parse().ok())
+			.ok_or(Error::Protocol("invalid address"))
+	}
+
+	/// Signs RLP-encoded unsigned transaction. Waits for user confirmation on the device
+	/// for at most `CONFIRMATION_TIMEOUT_MS`.
+	pub fn sign(&self, account: &Address, transaction: &[u8]) -> Result<Signature, Error> {
+		let path = try!(self.accounts.read().iter()
+			.find(|&&(ref address, _)| address == account)
+			.map(|&(_, ref path)| path.clone())
+			.ok_or(Error::UnknownAccount));
+
+		let mut data = encode_path(&path);
+		data.extend_from_slice(transaction);
+
+		*self.pending.lock() = Some(*account);
+		// all chunks are sent in a single request, so nothing else reaches the device in between
+		let result = self.with_device(|device| data.chunks(MAX_CHUNK_SIZE)
+			.enumerate()
+			.fold(Ok(Vec::new()), |result, (index, chunk)| result.and_then(|_| {
+				let p1 = if index == 0 { P1_FIRST_CHUNK } else { P1_MORE_CHUNKS };
+				exchange(device, INS_SIGN, p1, 0, chunk, Duration::from_millis(CONFIRMATION_TIMEOUT_MS))
+			})));
+		*self.pending.lock() = None;
+
+		let response = try!(result);
+		if response.len() != 65 || response[0] < 27 {
+			return Err(Error::Protocol("invalid signature"));
+		}
+		Ok(Signature::from_rsv(&H256::from_slice(&response[1..33]), &H256::from_slice(&response[33..65]), response[0] - 27))
+	}
+
+	/// Runs a request with exclusive access to the device. The device lock is only held to take
+	/// the device out and put it back, so other requests fail with `Busy` instead of waiting
+	/// for the user to confirm a signature.
+	fn with_device<F, T>(&self, f: F) -> Result<T, Error> where F: FnOnce(&mut HidDevice) -> Result<T, Error> {
+		let mut device = try!(self.device.lock().take().ok_or(Error::Busy));
+		let result = f(&mut *device);
+		*self.device.lock() = Some(device);
+		let transport_failed = match result {
+			Err(Error::Io(_)) => true,
+			_ => false,
+		};
+		self.available.store(!transport_failed, Ordering::SeqCst);
+		result
+	}
+}
+
+impl SignerBackend for Ledger {
+	fn accounts(&self) -> Vec<Address> {
+		self.accounts.read().iter().map(|&(address, _)| address).collect()
+	}
+
+	fn sign_transaction(&self, account: &Address, transaction: &[u8]) -> Result<Signature, String> {
+		self.sign(account, transaction).map_err(|e| format!("{}", e))
+	}
+
+	fn state(&self, account: &Address) -> DeviceState {
+		if !self.available.load(Ordering::SeqCst) {
+			DeviceState::Unavailable
+		} else if self.pending.lock().as_ref() == Some(account) {
+			DeviceState::AwaitingConfirmation
+		} else {
+			DeviceState::Ready
+		}
+	}
+}
+
+/// All Ledger devices connected to this machine. Devices are looked up again when accounts are
+/// listed, at most once every `SCAN_INTERVAL_MS`, so wallets can be plugged in and out while running.
+pub struct LedgerManager {
+	/// Lists identifiers of connected devices.
+	list: Box<Fn() -> Vec<String> + Send + Sync>,
+	/// Opens a device by its identifier.
+	open: Box<Fn(&str) -> io::Result<Box<HidDevice>> + Send + Sync>,
+	devices: RwLock<Vec<(String, Arc<Ledger>)>>,
+	last_scan: Mutex<Option<Instant>>,
+}
+
+impl LedgerManager {
+	/// Creates a manager for devices listed and opened with given functions.
+	pub fn new<L, O>(list: L, open: O) -> Self
+		where L: Fn() -> Vec<String> + Send + Sync + 'static, O: Fn(&str) -> io::Result<Box<HidDevice>> + Send + Sync + 'static
+	{
+		LedgerManager {
+			list: Box::new(list),
+			open: Box::new(open),
+			devices: RwLock::new(Vec::new()),
+			last_scan: Mutex::new(None),
+		}
+	}
+
+	/// Creates a manager for Ledger devices connected to this machine.
+	#[cfg(target_os = "linux")]
+	pub fn connected() -> Self {
+		use super::hid::Hidraw;
+
+		LedgerManager::new(
+			|| Hidraw::enumerate(&LEDGER_VENDORS).into_iter().map(|path| path.to_string_lossy().into_owned()).collect(),
+			|path| Hidraw::open(path).map(|device| Box::new(device) as Box<HidDevice>),
+		)
+	}
+
+	/// Creates a manager for Ledger devices connected to this machine.
+	/// Devices are accessed through `hidraw`, so none are found on other platforms.
+	#[cfg(not(target_os = "linux"))]
+	pub fn connected() -> Self {
+		LedgerManager::new(Vec::new, |_| Err(io::Error::new(io::ErrorKind::NotFound, "Hardware wallets are not supported on this platform")))
+	}
+
+	/// Opens newly connected devices and forgets disconnected ones.
+	pub fn scan(&self) {
+		let paths = (self.list)();
+		let known = self.devices.read().iter().map(|&(ref path, _)| path.clone()).collect::<Vec<_>>();
+		let mut added = Vec::new();
+		for path in paths.iter().filter(|path| !known.contains(path)) {
+			let ledger = match (self.open)(path) {
+				Ok(device) => Ledger::new(device),
+				Err(e) => {
+					debug!(target: "hw", "Unable to open hardware wallet {}: {}", path, e);
+					continue;
+				},
+			};
+			match ledger.refresh() {
+				Ok(()) => {
+					info!(target: "hw", "Using hardware wallet {} with {} accounts", path, ledger.accounts().len());
+					added.push((path.clone(), Arc::new(ledger)));
+				},
+				Err(e) => debug!(target: "hw", "Unable to read hardware wallet accounts from {}: {}", path, e),
+			}
+		}
+
+		let mut devices = self.devices.write();
+		devices.retain(|&(ref path, _)| paths.contains(path));
+		for (path, ledger) in added {
+			if !devices.iter().any(|&(ref p, _)| *p == path) {
+				devices.push((path, ledger));
+			}
+		}
+	}
+
+	fn scan_if_due(&self) {
+		let mut last_scan = self.last_scan.lock();
+		if last_scan.map_or(true, |time| time.elapsed() >= Duration::from_millis(SCAN_INTERVAL_MS)) {
+			self.scan();
+			*last_scan = Some(Instant::now());
+		}
+	}
+
+	fn device(&self, account: &Address) -> Option<Arc<Ledger>> {
+		self.devices.read().iter()
+			.find(|&&(_, ref ledger)| ledger.accounts().contains(account))
+			.map(|&(_, ref ledger)| ledger.clone())
+	}
+}
+
+impl SignerBackend for LedgerManager {
+	fn accounts(&self) -> Vec<Address> {
+		self.scan_if_due();
+		self.devices.read().iter().flat_map(|&(_, ref ledger)| ledger.accounts()).collect()
+	}
+
+	fn sign_transaction(&self, account: &Address, transaction: &[u8]) -> Result<Signature, String> {
+		let ledger = try!(self.device(account).ok_or_else(|| format!("{}", Error::UnknownAccount)));
+		ledger.sign_transaction(account, transaction)
+	}
+
+	fn state(&self, account: &Address) -> DeviceState {
+		self.device(account).map_or(DeviceState::Unavailable, |ledger| ledger.state(account))
+	}
+}
+
+/// Sends an APDU and returns the response data once the status word says it succeeded.
+fn exchange(device: &mut HidDevice, ins: u8, p1: u8, p2: u8, data: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+	let mut apdu = vec![CLA, ins, p1, p2, data.len() as u8];
+	apdu.extend_from_slice(data);
+
+	let mut response = try!(hid::write_apdu(device, &apdu).and_then(|_| hid::read_apdu(device, timeout)));
+	if response.len() < 2 {
+		return Err(Error::Protocol("missing status word"));
+	}
+	let status_offset = response.len() - 2;
+	let status = (response[status_offset] as u16) << 8 | response[status_offset + 1] as u16;
+	response.truncate(status_offset);
+	match status {
+		SW_OK => Ok(response),
+		SW_REJECTED => Err(Error::UserRejected),
+		status => Err(Error::Status(status)),
+	}
+}
+
+fn response_timeout() -> Duration {
+	Duration::from_millis(RESPONSE_TIMEOUT_MS)
+}
+
+/// Encodes derivation path as expected by the device.
+fn encode_path(path: &DerivationPath) -> Vec<u8> {
+	let indexes = path.indexes();
+	let mut data = Vec::with_capacity(1 + indexes.len() * 4);
+	data.push(indexes.len() as u8);
+	for index in indexes {
+		data.push((index >> 24) as u8);
+		data.push((index >> 16) as u8);
+		data.push((index >> 8) as u8);
+		data.push(*index as u8);
+	}
+	data
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io;
+	use std::sync::Arc;
+	use util::{U256, Address, Mutex};
+	use rlp::RlpStream;
+	use ethkey::DerivationPath;
+	use account_provider::{AccountProvider, SignerBackend, DeviceState, Error as AccountError};
+	use transaction::{Transaction, Action};
+	use super::super::emulator::Emulator;
+	use super::super::hid::HidDevice;
+	use super::{Ledger, LedgerManager, Error};
+
+	fn transaction() -> Transaction {
+		Transaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(20_000_000_000u64),
+			gas: U256::from(21_000),
+			action: Action::Call(Address::from(5)),
+			value: U256::from(1),
+			// longer than a single APDU chunk
+			data: vec![0xab; 300],
+		}
+	}
+
+	fn unsigned_rlp(t: &Transaction) -> Vec<u8> {
+		let mut stream = RlpStream::new();
+		t.rlp_append_unsigned_transaction(&mut stream);
+		stream.out()
+	}
+
+	#[test]
+	fn should_list_device_accounts() {
+		let emulator = Emulator::new();
+		let expected = emulator.address(&DerivationPath::ethereum(0));
+		let ledger = Ledger::new(Box::new(emulator));
+
+		ledger.refresh().unwrap();
+
+		let accounts = ledger.accounts();
+		assert_eq!(accounts.len(), 5);
+		assert_eq!(accounts[0], expected);
+		assert_eq!(ledger.state(&accounts[0]), DeviceState::Ready);
+	}
+
+	#[test]
+	fn should_sign_transaction_on_device() {
+		let ledger = Ledger::new(Box::new(Emulator::new()));
+		ledger.refresh().unwrap();
+		let account = ledger.accounts()[1];
+		let t = transaction();
+
+		let signature = ledger.sign(&account, &unsigned_rlp(&t)).unwrap();
+
+		assert_eq!(t.with_signature(signature).sender().unwrap(), account);
+	}
+
+	#[test]
+	fn should_report_rejected_signature() {
+		let mut emulator = Emulator::new();
+		emulator.reject_signing();
+		let ledger = Ledger::new(Box::new(emulator));
+		ledger.refresh().unwrap();
+		let account = ledger.accounts()[0];
+
+		match ledger.sign(&account, &unsigned_rlp(&transaction())) {
+			Err(Error::UserRejected) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(ledger.state(&account), DeviceState::Ready);
+	}
+
+	#[test]
+	fn should_refuse_unknown_account() {
+		let ledger = Ledger::new(Box::new(Emulator::new()));
+		ledger.refresh().unwrap();
+
+		match ledger.sign(&Address::from(1), &unsigned_rlp(&transaction())) {
+			Err(Error::UnknownAccount) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_sign_through_account_provider() {
+		let ledger = Arc::new(Ledger::new(Box::new(Emulator::new())));
+		ledger.refresh().unwrap();
+		let account = ledger.accounts()[0];
+		let provider = AccountProvider::transient_provider();
+		provider.add_signer_backend(ledger);
+		let t = transaction();
+
+		assert!(provider.accounts().unwrap().contains(&account));
+		assert_eq!(provider.device_state(&account), Some(DeviceState::Ready));
+		match provider.sign(account, t.hash()) {
+			Err(AccountError::NotSupported) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		let signature = provider.sign_transaction_external(account, &unsigned_rlp(&t)).unwrap();
+		assert_eq!(t.with_signature(signature).sender().unwrap(), account);
+	}
+
+	#[test]
+	fn should_time_out_when_device_does_not_respond() {
+		let mut emulator = Emulator::new();
+		let account = emulator.address(&DerivationPath::ethereum(0));
+		emulator.stop_responding();
+		let ledger = Ledger::new(Box::new(emulator));
+		ledger.accounts.write().push((account, DerivationPath::ethereum(0)));
+
+		match ledger.sign(&account, &unsigned_rlp(&transaction())) {
+			Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::TimedOut => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		assert_eq!(ledger.state(&account), DeviceState::Unavailable);
+		assert!(ledger.refresh().is_err());
+	}
+
+	#[test]
+	fn should_not_wait_for_busy_device() {
+		let ledger = Ledger::new(Box::new(Emulator::new()));
+		ledger.refresh().unwrap();
+		let account = ledger.accounts()[0];
+		// the device is taken out while another request waits for the user
+		let device = ledger.device.lock().take().unwrap();
+
+		match ledger.sign(&account, &unsigned_rlp(&transaction())) {
+			Err(Error::Busy) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+		*ledger.device.lock() = Some(device);
+		assert!(ledger.sign(&account, &unsigned_rlp(&transaction())).is_ok());
+	}
+
+	#[test]
+	fn should_pick_up_connected_devices() {
+		let connected = Arc::new(Mutex::new(Vec::new()));
+		let list = connected.clone();
+		let manager = LedgerManager::new(move || list.lock().clone(), |_| Ok(Box::new(Emulator::new()) as Box<HidDevice>));
+		assert!(manager.accounts().is_empty());
+
+		connected.lock().push("/dev/hidraw0".to_owned());
+		manager.scan();
+		let accounts = manager.accounts();
+		assert_eq!(accounts.len(), 5);
+		assert_eq!(manager.state(&accounts[0]), DeviceState::Ready);
+		let t = transaction();
+		let signature = manager.sign_transaction(&accounts[0], &unsigned_rlp(&t)).unwrap();
+		assert_eq!(t.with_signature(signature).sender().unwrap(), accounts[0]);
+
+		connected.lock().clear();
+		manager.scan();
+		assert!(manager.accounts().is_empty());
+		assert_eq!(manager.state(&accounts[0]), DeviceState::Unavailable);
+		assert!(manager.sign_transaction(&accounts[0], &unsigned_rlp(&t)).is_err());
+	}
+
+	#[test]
+	fn should_skip_devices_failing_to_open() {
+		let manager = LedgerManager::new(|| vec!["/dev/hidraw0".to_owned()], |_| Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied")));
+		assert!(manager.accounts().is_empty());
+	}
+}
@@ -0,0 +1,32 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Hardware wallet support.
+//!
+//! Keys held by a hardware wallet never leave the device. Accounts are exposed
+//! to `AccountProvider` through the `SignerBackend` trait and every signature
+//! has to be confirmed by the user on the device itself.
+
+mod hid;
+mod ledger;
+#[cfg(test)]
+mod emulator;
+
+pub use self::hid::{HidDevice, REPORT_SIZE};
+#[cfg(target_os = "linux")]
+pub use self::hid::Hidraw;
+pub use self::ledger::{Ledger, LedgerManager, Error, LEDGER_VENDORS};
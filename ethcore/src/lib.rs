@@ -111,6 +111,8 @@ extern crate heapsize;
 #[macro_use]
 extern crate ethcore_ipc as ipc;
 extern crate lru_cache;
+#[cfg(target_os = "linux")]
+extern crate libc;
 
 #[cfg(feature = "jit" )]
 extern crate evmjit;
@@ -134,6 +136,8 @@ pub mod miner;
 pub mod snapshot;
 pub mod action_params;
 pub mod db;
+pub mod hardware_wallet;
+pub mod signing_policy;
 #[macro_use] pub mod evm;
 
 mod cache_manager;
@@ -30,7 +30,7 @@ use transaction::{Action, SignedTransaction};
 use receipt::{Receipt, RichReceipt};
 use spec::Spec;
 use engines::Engine;
-use miner::{MinerService, MinerStatus, TransactionQueue, AccountDetails, TransactionOrigin};
+use miner::{MinerService, MinerStatus, TransactionQueue, AccountDetails, TransactionOrigin, TransactionCondition};
 use miner::work_notify::WorkPoster;
 use client::TransactionImportResult;
 use miner::price_info::PriceInfo;
@@ -171,6 +171,7 @@ pub struct Miner {
 	// NOTE [ToDr]  When locking always lock in this order!
 	transaction_queue: Arc<Mutex<TransactionQueue>>,
 	sealing_work: Mutex<SealingWork>,
+	held_transactions: Mutex<Vec<(SignedTransaction, TransactionCondition)>>,
 	next_allowed_reseal: Mutex<Instant>,
 	sealing_block_last_request: Mutex<u64>,
 	// for sealing...
@@ -197,6 +198,7 @@ impl Miner {
 		let txq = Arc::new(Mutex::new(TransactionQueue::with_limits(options.tx_queue_size, options.tx_gas_limit)));
 		Miner {
 			transaction_queue: txq,
+			held_transactions: Mutex::new(Vec::new()),
 			next_allowed_reseal: Mutex::new(Instant::now()),
 			sealing_block_last_request: Mutex::new(0),
 			sealing_work: Mutex::new(SealingWork{
@@ -732,6 +734,24 @@ impl MinerService for Miner {
 		imported
 	}
 
+	fn import_conditional_transaction(
+		&self,
+		chain: &MiningBlockChainClient,
+		transaction: SignedTransaction,
+		condition: TransactionCondition,
+	) -> Result<TransactionImportResult, Error> {
+		let (number, timestamp) = best_block_details(chain);
+		if condition.is_met(number, timestamp) {
+			return self.import_own_transaction(chain, transaction);
+		}
+
+		// make sure the transaction is valid before holding it
+		try!(transaction.sender());
+		trace!(target: "own_tx", "Holding transaction {:?} until {:?}", transaction.hash(), condition);
+		self.held_transactions.lock().push((transaction, condition));
+		Ok(TransactionImportResult::Future)
+	}
+
 	fn all_transactions(&self) -> Vec<SignedTransaction> {
 		let queue = self.transaction_queue.lock();
 		queue.top_transactions()
@@ -823,7 +843,12 @@ impl MinerService for Miner {
 	}
 
 	fn last_nonce(&self, address: &Address) -> Option<U256> {
-		self.transaction_queue.lock().last_nonce(address)
+		let queued = self.transaction_queue.lock().last_nonce(address);
+		// nonces of held transactions are taken as well
+		self.held_transactions.lock().iter()
+			.filter(|&&(ref t, _)| t.sender().ok().as_ref() == Some(address))
+			.map(|&(ref t, _)| t.nonce)
+			.fold(queued, |last, nonce| Some(last.map_or(nonce, |last| cmp::max(last, nonce))))
 	}
 
 
@@ -949,6 +974,27 @@ impl MinerService for Miner {
 			});
 		}
 
+		// Finally import held transactions which are ready at the new best block
+		{
+			let (number, timestamp) = best_block_details(chain);
+			let ready = {
+				let mut held = self.held_transactions.lock();
+				let (ready, waiting): (Vec<_>, Vec<_>) = held.drain(..)
+					.partition(|&(_, ref condition)| condition.is_met(number, timestamp));
+				*held = waiting;
+				ready
+			};
+
+			if !ready.is_empty() {
+				let mut transaction_queue = self.transaction_queue.lock();
+				let transactions = ready.into_iter().map(|(transaction, _)| transaction).collect();
+				let results = self.add_transactions_to_queue(chain, transactions, TransactionOrigin::Local, &mut transaction_queue);
+				for e in results.into_iter().filter_map(|r| r.err()) {
+					warn!(target: "own_tx", "Error importing held transaction: {:?}", e);
+				}
+			}
+		}
+
 		if enacted.len() > 0 {
 			// --------------------------------------------------------------------------
 			// | NOTE Code below requires transaction_queue and sealing_work locks.     |
@@ -959,11 +1005,18 @@ impl MinerService for Miner {
 	}
 }
 
+/// Number and timestamp of the best block.
+fn best_block_details(chain: &MiningBlockChainClient) -> (BlockNumber, u64) {
+	let header = chain.best_block_header();
+	let header = HeaderView::new(&header);
+	(header.number(), header.timestamp())
+}
+
 #[cfg(test)]
 mod tests {
 
 	use std::time::Duration;
-	use super::super::MinerService;
+	use super::super::{MinerService, TransactionCondition};
 	use super::*;
 	use util::*;
 	use ethkey::{Generator, Random};
@@ -1057,6 +1110,31 @@ mod tests {
 		assert!(!miner.prepare_work_sealing(&client));
 	}
 
+	#[test]
+	fn should_hold_conditional_transaction_until_condition_is_met() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		let transaction = transaction();
+		let sender = transaction.sender().unwrap();
+
+		// when
+		let res = miner.import_conditional_transaction(&client, transaction, TransactionCondition::Number(2));
+
+		// then
+		assert_eq!(res.unwrap(), TransactionImportResult::Future);
+		assert_eq!(miner.all_transactions().len(), 0);
+		assert_eq!(miner.last_nonce(&sender), Some(U256::zero()));
+
+		client.add_blocks(1, EachBlockWith::Nothing);
+		miner.chain_new_blocks(&client, &[], &[], &[], &[]);
+		assert_eq!(miner.all_transactions().len(), 0);
+
+		client.add_blocks(1, EachBlockWith::Nothing);
+		miner.chain_new_blocks(&client, &[], &[], &[], &[]);
+		assert_eq!(miner.all_transactions().len(), 1);
+	}
+
 	#[test]
 	fn should_import_external_transaction() {
 		// given
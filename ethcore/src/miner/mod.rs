@@ -59,6 +59,7 @@ use block::ClosedBlock;
 use receipt::{RichReceipt, Receipt};
 use error::{Error, CallError};
 use transaction::SignedTransaction;
+use header::BlockNumber;
 
 /// Miner client API
 pub trait MinerService : Send + Sync {
@@ -114,6 +115,11 @@ pub trait MinerService : Send + Sync {
 	fn import_own_transaction(&self, chain: &MiningBlockChainClient, transaction: SignedTransaction) ->
 		Result<TransactionImportResult, Error>;
 
+	/// Holds own transaction until the condition is met and imports it to queue then.
+	/// Transactions with a condition which is already met are imported immediately.
+	fn import_conditional_transaction(&self, chain: &MiningBlockChainClient, transaction: SignedTransaction, condition: TransactionCondition) ->
+		Result<TransactionImportResult, Error>;
+
 	/// Returns hashes of transactions currently in pending
 	fn pending_transactions_hashes(&self) -> Vec<H256>;
 
@@ -177,6 +183,25 @@ pub trait MinerService : Send + Sync {
 	fn code(&self, chain: &MiningBlockChainClient, address: &Address) -> Option<Bytes>;
 }
 
+/// Condition which has to be met before a held transaction is imported to queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionCondition {
+	/// Best block has at least given number.
+	Number(BlockNumber),
+	/// Best block has at least given timestamp.
+	Timestamp(u64),
+}
+
+impl TransactionCondition {
+	/// Checks if the condition is met by the best block with given number and timestamp.
+	pub fn is_met(&self, number: BlockNumber, timestamp: u64) -> bool {
+		match *self {
+			TransactionCondition::Number(n) => number >= n,
+			TransactionCondition::Timestamp(t) => timestamp >= t,
+		}
+	}
+}
+
 /// Mining status
 #[derive(Debug)]
 pub struct MinerStatus {
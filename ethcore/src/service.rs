@@ -48,6 +48,8 @@ pub enum ClientIoMessage {
 	FeedBlockChunk(H256, Bytes),
 	/// Take a snapshot for the block with given number.
 	TakeSnapshot(u64),
+	/// Take an incremental snapshot for the block with given number.
+	TakeIncrementalSnapshot(u64),
 }
 
 /// Client service setup. Creates and registers client and network services with the IO subsystem.
@@ -203,6 +205,11 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
 					warn!("Failed to take snapshot at block #{}: {}", num, e);
 				}
 			}
+			ClientIoMessage::TakeIncrementalSnapshot(num) => {
+				if let Err(e) = self.snapshot.take_incremental_snapshot(&*self.client, num) {
+					warn!("Failed to take incremental snapshot at block #{}: {}", num, e);
+				}
+			}
 			_ => {} // ignore other messages
 		}
 	}
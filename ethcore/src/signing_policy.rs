@@ -0,0 +1,222 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Limits on transactions signed automatically with unlocked accounts.
+
+use std::fmt;
+use std::io::Read;
+use std::collections::HashMap;
+use util::{Address, U256, Uint, Bytes, ToPretty};
+use ethjson;
+use transaction::{Transaction, Action};
+
+/// Per-account signing policy. Unset fields don't restrict anything.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SigningPolicy {
+	/// Addresses the account may send transactions to.
+	pub allowed_destinations: Option<Vec<Address>>,
+	/// Maximal value of a single transaction.
+	pub max_value: Option<U256>,
+	/// Maximal value of all transactions sent during a day.
+	pub daily_limit: Option<U256>,
+	/// Maximal gas price.
+	pub max_gas_price: Option<U256>,
+	/// Selectors of contract methods the account may call.
+	pub allowed_methods: Option<Vec<[u8; 4]>>,
+}
+
+/// Reason the transaction can't be signed automatically.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyViolation {
+	/// Destination is not allowed, `None` for contract creation.
+	DestinationNotAllowed(Option<Address>),
+	/// Transaction value is above the limit.
+	ValueTooHigh {
+		/// Maximal value.
+		limit: U256,
+		/// Value of the transaction.
+		value: U256,
+	},
+	/// Value sent today is unknown, because the spending record couldn't be read.
+	SpendingUnknown,
+	/// Transaction would exceed the daily limit.
+	DailyLimitExceeded {
+		/// Daily limit.
+		limit: U256,
+		/// Value already sent today.
+		spent: U256,
+	},
+	/// Gas price is above the limit.
+	GasPriceTooHigh {
+		/// Maximal gas price.
+		limit: U256,
+		/// Gas price of the transaction.
+		gas_price: U256,
+	},
+	/// Called contract method is not allowed.
+	MethodNotAllowed(Bytes),
+	/// Signing arbitrary data is never allowed for accounts with a policy.
+	ArbitraryData,
+}
+
+impl fmt::Display for PolicyViolation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		match *self {
+			PolicyViolation::DestinationNotAllowed(None) => write!(f, "Contract creation is not allowed"),
+			PolicyViolation::DestinationNotAllowed(Some(ref to)) => write!(f, "Destination 0x{:?} is not allowed", to),
+			PolicyViolation::ValueTooHigh { ref limit, ref value } => write!(f, "Value {} exceeds the limit of {}", value, limit),
+			PolicyViolation::SpendingUnknown => write!(f, "Spending record couldn't be read, daily limit can't be checked"),
+			PolicyViolation::DailyLimitExceeded { ref limit, ref spent } => write!(f, "Daily limit of {} exceeded, {} already sent today", limit, spent),
+			PolicyViolation::GasPriceTooHigh { ref limit, ref gas_price } => write!(f, "Gas price {} exceeds the limit of {}", gas_price, limit),
+			PolicyViolation::MethodNotAllowed(ref selector) => write!(f, "Method 0x{} is not allowed", selector.to_hex()),
+			PolicyViolation::ArbitraryData => write!(f, "Signing arbitrary data is not allowed"),
+		}
+	}
+}
+
+impl SigningPolicy {
+	/// Reads policies of multiple accounts from a JSON file keyed by address.
+	pub fn read_address_map<R>(reader: R) -> Result<HashMap<Address, SigningPolicy>, String> where R: Read {
+		ethjson::misc::SigningPolicy::read_address_map(reader)
+			.map_err(|e| format!("{}", e))
+			.and_then(|policies| policies.into_iter()
+				.map(|(address, policy)| SigningPolicy::from_json(policy).map(|policy| (address, policy)))
+				.collect())
+	}
+
+	fn from_json(json: ethjson::misc::SigningPolicy) -> Result<Self, String> {
+		let methods = match json.allowed_methods {
+			Some(methods) => Some(try!(methods.into_iter().map(|method| {
+				let method: Vec<u8> = method.into();
+				if method.len() != 4 {
+					return Err(format!("Invalid method selector 0x{}, selectors are 4 bytes long", method.to_hex()));
+				}
+				Ok([method[0], method[1], method[2], method[3]])
+			}).collect::<Result<Vec<_>, _>>())),
+			None => None,
+		};
+
+		Ok(SigningPolicy {
+			allowed_destinations: json.allowed_destinations.map(|d| d.into_iter().map(Into::into).collect()),
+			max_value: json.max_value.map(Into::into),
+			daily_limit: json.daily_limit.map(Into::into),
+			max_gas_price: json.max_gas_price.map(Into::into),
+			allowed_methods: methods,
+		})
+	}
+
+	/// Checks the transaction against the policy. `spent_today` is the value already sent today, `None` if it's unknown.
+	pub fn check(&self, transaction: &Transaction, spent_today: Option<U256>) -> Result<(), PolicyViolation> {
+		if let Some(ref allowed) = self.allowed_destinations {
+			match transaction.action {
+				Action::Call(ref to) if allowed.contains(to) => {},
+				Action::Call(ref to) => return Err(PolicyViolation::DestinationNotAllowed(Some(*to))),
+				Action::Create => return Err(PolicyViolation::DestinationNotAllowed(None)),
+			}
+		}
+
+		if let Some(limit) = self.max_value {
+			if transaction.value > limit {
+				return Err(PolicyViolation::ValueTooHigh { limit: limit, value: transaction.value });
+			}
+		}
+
+		if let Some(limit) = self.daily_limit {
+			let spent_today = try!(spent_today.ok_or(PolicyViolation::SpendingUnknown));
+			let (total, overflow) = spent_today.overflowing_add(transaction.value);
+			if overflow || total > limit {
+				return Err(PolicyViolation::DailyLimitExceeded { limit: limit, spent: spent_today });
+			}
+		}
+
+		if let Some(limit) = self.max_gas_price {
+			if transaction.gas_price > limit {
+				return Err(PolicyViolation::GasPriceTooHigh { limit: limit, gas_price: transaction.gas_price });
+			}
+		}
+
+		if let Some(ref allowed) = self.allowed_methods {
+			let data = &transaction.data;
+			let is_allowed = data.is_empty() || (data.len() >= 4 && allowed.iter().any(|method| &method[..] == &data[..4]));
+			if !is_allowed {
+				return Err(PolicyViolation::MethodNotAllowed(data.iter().take(4).cloned().collect()));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, U256};
+	use transaction::{Transaction, Action};
+	use super::{SigningPolicy, PolicyViolation};
+
+	fn transaction(to: u64, value: u64, data: Vec<u8>) -> Transaction {
+		Transaction {
+			nonce: U256::zero(),
+			gas_price: U256::from(20),
+			gas: U256::from(21_000),
+			action: Action::Call(Address::from(to)),
+			value: U256::from(value),
+			data: data,
+		}
+	}
+
+	#[test]
+	fn should_allow_everything_by_default() {
+		let policy = SigningPolicy::default();
+		assert_eq!(policy.check(&transaction(1, 1_000_000, vec![1, 2, 3]), Some(U256::from(1_000_000))), Ok(()));
+	}
+
+	#[test]
+	fn should_check_limits() {
+		let policy = SigningPolicy {
+			allowed_destinations: Some(vec![Address::from(5)]),
+			max_value: Some(U256::from(100)),
+			daily_limit: Some(U256::from(150)),
+			max_gas_price: Some(U256::from(10)),
+			allowed_methods: Some(vec![[0xa9, 0x05, 0x9c, 0xbb]]),
+		};
+		let mut allowed = transaction(5, 100, vec![0xa9, 0x05, 0x9c, 0xbb, 1]);
+		allowed.gas_price = U256::from(10);
+		assert_eq!(policy.check(&allowed, Some(U256::from(50))), Ok(()));
+
+		assert_eq!(policy.check(&allowed, Some(U256::from(51))), Err(PolicyViolation::DailyLimitExceeded { limit: U256::from(150), spent: U256::from(51) }));
+		assert_eq!(policy.check(&allowed, None), Err(PolicyViolation::SpendingUnknown));
+		assert_eq!(policy.check(&transaction(6, 1, vec![]), Some(U256::zero())), Err(PolicyViolation::DestinationNotAllowed(Some(Address::from(6)))));
+		assert_eq!(policy.check(&transaction(5, 101, vec![]), Some(U256::zero())), Err(PolicyViolation::ValueTooHigh { limit: U256::from(100), value: U256::from(101) }));
+		assert_eq!(policy.check(&transaction(5, 1, vec![]), Some(U256::zero())), Err(PolicyViolation::GasPriceTooHigh { limit: U256::from(10), gas_price: U256::from(20) }));
+		let mut call = transaction(5, 1, vec![0x09, 0x5e, 0xa7, 0xb3]);
+		call.gas_price = U256::from(1);
+		assert_eq!(policy.check(&call, Some(U256::zero())), Err(PolicyViolation::MethodNotAllowed(vec![0x09, 0x5e, 0xa7, 0xb3])));
+	}
+
+	#[test]
+	fn should_read_policies_file() {
+		let json = r#"{"0x0000000000000000000000000000000000000001": {"maxGasPrice": "0x04a817c800", "allowedMethods": ["0xa9059cbb00"]}}"#;
+		assert!(SigningPolicy::read_address_map(json.as_bytes()).is_err());
+
+		let json = r#"{"0x0000000000000000000000000000000000000001": {"maxGasPrice": "0x04a817c800"}}"#;
+		let policies = SigningPolicy::read_address_map(json.as_bytes()).unwrap();
+		assert_eq!(policies[&Address::from(1)], SigningPolicy {
+			max_gas_price: Some(U256::from(20_000_000_000u64)),
+			..Default::default()
+		});
+	}
+}
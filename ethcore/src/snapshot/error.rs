@@ -39,6 +39,12 @@ pub enum Error {
 	MissingCode(Vec<H256>),
 	/// Unrecognized code encoding.
 	UnrecognizedCodeState(u8),
+	/// Chunk data doesn't match its hash in the manifest (expected, found).
+	ChunkHashMismatch(H256, H256),
+	/// Rebuilt state root doesn't match the manifest (expected, found).
+	StateRootMismatch(H256, H256),
+	/// State changes since the base snapshot at the given block weren't tracked.
+	UntrackedChanges(u64),
 	/// Trie error.
 	Trie(TrieError),
 	/// Decoder error.
@@ -57,6 +63,9 @@ impl fmt::Display for Error {
 				a pruned database. Please re-run with the --pruning archive flag."),
 			Error::MissingCode(ref missing) => write!(f, "Incomplete snapshot: {} contract codes not found.", missing.len()),
 			Error::UnrecognizedCodeState(state) => write!(f, "Unrecognized code encoding ({})", state),
+			Error::ChunkHashMismatch(ref expected, ref found) => write!(f, "Chunk {} is corrupted: data hashes to {}", expected, found),
+			Error::StateRootMismatch(ref expected, ref found) => write!(f, "Rebuilt state root {} doesn't match the expected {}", found, expected),
+			Error::UntrackedChanges(ref base) => write!(f, "State changes since the snapshot at #{} are unknown", base),
 			Error::Io(ref err) => err.fmt(f),
 			Error::Decoder(ref err) => err.fmt(f),
 			Error::Trie(ref err) => err.fmt(f),
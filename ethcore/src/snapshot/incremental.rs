@@ -0,0 +1,178 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracking of state changes for incremental snapshots.
+
+use std::collections::{BTreeMap, HashSet};
+
+use util::H256;
+
+/// Default maximum number of account hashes kept by the tracker.
+pub const DEFAULT_TRACKED_ACCOUNTS: usize = 1_000_000;
+
+/// Records the hashes of the accounts touched by each imported block, so
+/// the accounts changed since a full snapshot can be chunked into an
+/// incremental one.
+///
+/// Blocks of the same number on different forks are merged, which can only
+/// make the set of changed accounts larger than needed.
+///
+/// Tracking starts with the first block noted, so after a restart or once the
+/// oldest blocks are dropped to stay within the limit, changes since older
+/// blocks are unknown and a full snapshot has to be taken instead.
+pub struct ChangeTracker {
+	blocks: BTreeMap<u64, HashSet<H256>>,
+	// number of the first block tracked.
+	first: Option<u64>,
+	// number of account hashes held.
+	len: usize,
+	// maximum number of account hashes held.
+	limit: usize,
+}
+
+impl Default for ChangeTracker {
+	fn default() -> Self {
+		ChangeTracker::with_limit(DEFAULT_TRACKED_ACCOUNTS)
+	}
+}
+
+impl ChangeTracker {
+	/// Create a new, empty tracker.
+	pub fn new() -> Self {
+		ChangeTracker::default()
+	}
+
+	/// Create a new, empty tracker holding at most `limit` account hashes.
+	pub fn with_limit(limit: usize) -> Self {
+		ChangeTracker {
+			blocks: BTreeMap::new(),
+			first: None,
+			len: 0,
+			limit: limit,
+		}
+	}
+
+	/// Note the hashes of the accounts touched by a block.
+	pub fn note_block<I>(&mut self, number: u64, accounts: I) where I: IntoIterator<Item=H256> {
+		// blocks below the first one tracked (e.g. on a fork) don't make the
+		// blocks imported before tracking started known.
+		if self.first.is_none() {
+			self.first = Some(number);
+		}
+
+		{
+			let block = self.blocks.entry(number).or_insert_with(HashSet::new);
+			let old_len = block.len();
+			block.extend(accounts);
+			self.len += block.len() - old_len;
+		}
+
+		// drop the oldest blocks to stay within the limit.
+		while self.len > self.limit {
+			let oldest = match self.blocks.keys().next() {
+				Some(&oldest) if oldest < number => oldest,
+				_ => break,
+			};
+			self.prune(oldest);
+		}
+	}
+
+	/// Forget the changes made up to and including the given block.
+	pub fn prune(&mut self, number: u64) {
+		let pruned: Vec<_> = self.blocks.keys().take_while(|&&n| n <= number).cloned().collect();
+		for n in pruned {
+			if let Some(accounts) = self.blocks.remove(&n) {
+				self.len -= accounts.len();
+			}
+		}
+
+		if self.first.map_or(false, |first| first <= number) {
+			self.first = Some(number + 1);
+		}
+	}
+
+	/// Hashes of all accounts changed after block `base`, up to and including block `at`,
+	/// sorted. Returns `None` if not all of those blocks were tracked.
+	pub fn changed_since(&self, base: u64, at: u64) -> Option<Vec<H256>> {
+		match self.first {
+			Some(first) if first <= base + 1 => {},
+			_ => return None,
+		}
+
+		let changed: HashSet<_> = self.blocks.iter()
+			.skip_while(|&(&n, _)| n <= base)
+			.take_while(|&(&n, _)| n <= at)
+			.flat_map(|(_, accounts)| accounts.iter().cloned())
+			.collect();
+
+		let mut changed: Vec<_> = changed.into_iter().collect();
+		changed.sort();
+		Some(changed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ChangeTracker;
+	use util::H256;
+
+	#[test]
+	fn changed_since() {
+		let mut tracker = ChangeTracker::new();
+		for i in 5..10 {
+			tracker.note_block(i, vec![H256::from(i), H256::from(100)]);
+		}
+
+		assert_eq!(tracker.changed_since(3, 9), None);
+		assert_eq!(tracker.changed_since(4, 6), Some(vec![H256::from(5), H256::from(6), H256::from(100)]));
+		assert_eq!(tracker.changed_since(8, 20), Some(vec![H256::from(9), H256::from(100)]));
+	}
+
+	#[test]
+	fn prune() {
+		let mut tracker = ChangeTracker::new();
+		for i in 5..10 {
+			tracker.note_block(i, vec![H256::from(i)]);
+		}
+
+		tracker.prune(7);
+		assert_eq!(tracker.changed_since(6, 9), None);
+		assert_eq!(tracker.changed_since(7, 9), Some(vec![H256::from(8), H256::from(9)]));
+	}
+
+	#[test]
+	fn drops_oldest_blocks_over_limit() {
+		let mut tracker = ChangeTracker::with_limit(4);
+		for i in 5..10 {
+			tracker.note_block(i, vec![H256::from(i), H256::from(100 + i)]);
+		}
+
+		// only the last two blocks fit.
+		assert_eq!(tracker.changed_since(6, 9), None);
+		assert_eq!(tracker.changed_since(7, 9), Some(vec![H256::from(8), H256::from(9), H256::from(108), H256::from(109)]));
+	}
+
+	#[test]
+	fn older_blocks_stay_untracked() {
+		// as after a restart: blocks before the first one noted are unknown.
+		let mut tracker = ChangeTracker::new();
+		tracker.note_block(10, vec![H256::from(10)]);
+		tracker.note_block(8, vec![H256::from(8)]);
+
+		assert_eq!(tracker.changed_since(7, 10), None);
+		assert_eq!(tracker.changed_since(9, 10), Some(vec![H256::from(10)]));
+	}
+}
@@ -118,6 +118,11 @@ impl SnapshotWriter for PackedWriter {
 	}
 
 	fn finish(mut self, manifest: ManifestData) -> io::Result<()> {
+		// incremental snapshots refer to chunks of their base, which aren't in this file.
+		if manifest.base.is_some() {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "packed snapshots cannot be incremental"));
+		}
+
 		// we ignore the hashes fields of the manifest under the assumption that
 		// they are consistent with ours.
 		let mut stream = RlpStream::new_list(5);
@@ -209,6 +214,10 @@ pub trait SnapshotReader {
 	/// Get raw chunk data by hash. implementation defined behavior
 	/// if a chunk not in the manifest is requested.
 	fn chunk(&self, hash: H256) -> io::Result<Bytes>;
+
+	/// Get the size of a compressed chunk in bytes. implementation defined behavior
+	/// if a chunk not in the manifest is requested.
+	fn chunk_size(&self, hash: H256) -> io::Result<u64>;
 }
 
 /// Packed snapshot reader.
@@ -266,6 +275,8 @@ impl PackedReader {
 			state_root: try!(rlp.val_at(2)),
 			block_number: try!(rlp.val_at(3)),
 			block_hash: try!(rlp.val_at(4)),
+			delta_hashes: Vec::new(),
+			base: None,
 		};
 
 		Ok(Some(PackedReader {
@@ -295,6 +306,13 @@ impl SnapshotReader for PackedReader {
 
 		Ok(buf)
 	}
+
+	fn chunk_size(&self, hash: H256) -> io::Result<u64> {
+		let &(len, _) = self.state_hashes.get(&hash).or_else(|| self.block_hashes.get(&hash))
+			.expect("only chunks in the manifest can be requested; qed");
+
+		Ok(len)
+	}
 }
 
 /// reader for "loose" snapshots
@@ -340,6 +358,13 @@ impl SnapshotReader for LooseReader {
 
 		Ok(buf)
 	}
+
+	fn chunk_size(&self, hash: H256) -> io::Result<u64> {
+		let mut path = self.dir.clone();
+		path.push(hash.hex());
+
+		Ok(try!(fs::metadata(&path)).len())
+	}
 }
 
 #[cfg(test)]
@@ -379,6 +404,8 @@ mod tests {
 			state_root: b"notarealroot".sha3(),
 			block_number: 12345678987654321,
 			block_hash: b"notarealblock".sha3(),
+			delta_hashes: Vec::new(),
+			base: None,
 		};
 
 		writer.finish(manifest.clone()).unwrap();
@@ -417,6 +444,8 @@ mod tests {
 			state_root: b"notarealroot".sha3(),
 			block_number: 12345678987654321,
 			block_hash: b"notarealblock".sha3(),
+			delta_hashes: Vec::new(),
+			base: None,
 		};
 
 		writer.finish(manifest.clone()).unwrap();
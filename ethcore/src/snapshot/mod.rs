@@ -41,12 +41,13 @@ use rlp::{RlpStream, Stream, UntrustedRlp, View};
 
 use self::account::Account;
 use self::block::AbridgedBlock;
-use self::io::SnapshotWriter;
+use self::io::{SnapshotReader, SnapshotWriter};
 
 use crossbeam::{scope, ScopedJoinHandle};
 use rand::{Rng, OsRng};
 
 pub use self::error::Error;
+pub use self::incremental::ChangeTracker;
 
 pub use self::service::{Service, DatabaseRestore};
 pub use self::traits::{SnapshotService, RemoteSnapshotService};
@@ -60,6 +61,7 @@ pub mod service;
 mod account;
 mod block;
 mod error;
+mod incremental;
 mod watcher;
 
 #[cfg(test)]
@@ -143,6 +145,58 @@ pub fn take_snapshot<W: SnapshotWriter + Send>(
 		state_root: *state_root,
 		block_number: number,
 		block_hash: block_at,
+		delta_hashes: Vec::new(),
+		base: None,
+	};
+
+	try!(writer.into_inner().finish(manifest_data));
+
+	p.done.store(true, Ordering::SeqCst);
+
+	Ok(())
+}
+
+/// Take an incremental snapshot on top of the full snapshot described by `base`,
+/// using the given blockchain, starting block hash, and database, writing into the given writer.
+///
+/// Only the accounts in `changed`, sorted by their address hash, are written out as delta chunks.
+/// The state chunks of the base are referred to by the manifest, but not written.
+pub fn take_incremental_snapshot<W: SnapshotWriter + Send>(
+	chain: &BlockChain,
+	block_at: H256,
+	state_db: &HashDB,
+	base: &ManifestData,
+	changed: &[H256],
+	writer: W,
+	p: &Progress
+) -> Result<(), Error> {
+	let start_header = try!(chain.block_header(&block_at)
+		.ok_or(Error::InvalidStartingBlock(BlockID::Hash(block_at))));
+	let state_root = start_header.state_root();
+	let number = start_header.number();
+
+	info!("Taking incremental snapshot starting at block {} on top of block {}", number, base.block_number);
+
+	let writer = Mutex::new(writer);
+	let (delta_hashes, block_hashes) = try!(scope(|scope| {
+		let block_guard = scope.spawn(|| chunk_blocks(chain, (number, block_at), &writer, p));
+		let state_res = chunk_state_delta(state_db, state_root, changed, &writer, p);
+
+		state_res.and_then(|delta_hashes| {
+			block_guard.join().map(|block_hashes| (delta_hashes, block_hashes))
+		})
+	}));
+
+	info!("produced {} delta chunks and {} block chunks.", delta_hashes.len(), block_hashes.len());
+
+	let manifest_data = ManifestData {
+		state_hashes: base.state_hashes.clone(),
+		block_hashes: block_hashes,
+		state_root: *state_root,
+		block_number: number,
+		block_hash: block_at,
+		delta_hashes: delta_hashes,
+		base: Some(base.clone().into_rlp().sha3()),
 	};
 
 	try!(writer.into_inner().finish(manifest_data));
@@ -379,22 +433,77 @@ pub fn chunk_state<'a>(db: &HashDB, root: &H256, writer: &Mutex<SnapshotWriter +
 	Ok(chunker.hashes)
 }
 
+/// Chunk the given accounts of the state at the given root, creating delta chunks
+/// and writing them out. Accounts which no longer exist are written with an empty
+/// body, so they are removed when the delta is applied.
+///
+/// Returns a list of hashes of chunks created, or any error it may
+/// have encountered.
+pub fn chunk_state_delta<'a>(db: &HashDB, root: &H256, changed: &[H256], writer: &Mutex<SnapshotWriter + 'a>, progress: &'a Progress) -> Result<Vec<H256>, Error> {
+	let account_trie = try!(TrieDB::new(db, &root));
+
+	let mut chunker = StateChunker {
+		hashes: Vec::new(),
+		rlps: Vec::new(),
+		cur_size: 0,
+		snappy_buffer: vec![0; snappy::max_compressed_len(PREFERRED_CHUNK_SIZE)],
+		writer: writer,
+		progress: progress,
+	};
+
+	let mut used_code = HashSet::new();
+
+	for account_key_hash in changed {
+		let fat_rlp = match try!(account_trie.get(account_key_hash)) {
+			Some(account_data) => {
+				let account = Account::from_thin_rlp(account_data);
+				let account_db = AccountDB::from_hash(db, *account_key_hash);
+
+				try!(account.to_fat_rlp(&account_db, &mut used_code))
+			}
+			None => ::rlp::NULL_RLP.to_vec(),
+		};
+
+		try!(chunker.push(account_key_hash.to_vec(), fat_rlp));
+	}
+
+	if chunker.cur_size != 0 {
+		try!(chunker.write_chunk());
+	}
+
+	Ok(chunker.hashes)
+}
+
 /// Used to rebuild the state trie piece by piece.
 pub struct StateRebuilder {
 	db: Box<JournalDB>,
 	state_root: H256,
 	code_map: HashMap<H256, Bytes>, // maps code hashes to code itself.
 	missing_code: HashMap<H256, Vec<H256>>, // maps code hashes to lists of accounts missing that code.
+	new_code: Vec<H256>, // code hashes first seen since the last call to `take_new_code`.
 }
 
 impl StateRebuilder {
 	/// Create a new state rebuilder to write into the given backing DB.
 	pub fn new(db: Arc<Database>, pruning: Algorithm) -> Self {
+		StateRebuilder::resume(db, pruning, SHA3_NULL_RLP, HashMap::new(), HashMap::new())
+	}
+
+	/// Create a state rebuilder continuing an interrupted restoration into the given backing DB,
+	/// from the state root, known code and missing code recorded when it stopped.
+	pub fn resume(
+		db: Arc<Database>,
+		pruning: Algorithm,
+		state_root: H256,
+		code_map: HashMap<H256, Bytes>,
+		missing_code: HashMap<H256, Vec<H256>>
+	) -> Self {
 		StateRebuilder {
 			db: journaldb::new(db.clone(), pruning, ::db::COL_STATE),
-			state_root: SHA3_NULL_RLP,
-			code_map: HashMap::new(),
-			missing_code: HashMap::new(),
+			state_root: state_root,
+			code_map: code_map,
+			missing_code: missing_code,
+			new_code: Vec::new(),
 		}
 	}
 
@@ -451,7 +560,9 @@ impl StateRebuilder {
 				db.emplace(code_hash, code.clone());
 			}
 
-			self.code_map.insert(code_hash, code);
+			if self.code_map.insert(code_hash, code).is_none() {
+				self.new_code.push(code_hash);
+			}
 		}
 
 
@@ -464,7 +575,11 @@ impl StateRebuilder {
 			};
 
 			for (hash, thin_rlp) in pairs {
-				try!(account_trie.insert(&hash, &thin_rlp));
+				// accounts removed by a delta chunk have no body.
+				match thin_rlp.is_empty() {
+					true => { try!(account_trie.remove(&hash)); }
+					false => { try!(account_trie.insert(&hash, &thin_rlp)); }
+				}
 			}
 		}
 
@@ -488,6 +603,86 @@ impl StateRebuilder {
 
 	/// Get the state root of the rebuilder.
 	pub fn state_root(&self) -> H256 { self.state_root }
+
+	/// Get the code hashes which are still missing, along with the accounts that need them.
+	pub fn missing_code(&self) -> &HashMap<H256, Vec<H256>> { &self.missing_code }
+
+	/// Take the code which has become known since the last call.
+	pub fn take_new_code(&mut self) -> Vec<(H256, Bytes)> {
+		let code_map = &self.code_map;
+		self.new_code.drain(..)
+			.filter_map(|hash| code_map.get(&hash).map(|code| (hash, code.clone())))
+			.collect()
+	}
+}
+
+/// Verify the snapshot read by the given reader.
+///
+/// Every chunk is checked against the hash it is listed under in the manifest,
+/// and the state is rebuilt into the given database to ensure the final state root
+/// matches the manifest. Block chunks are only checked for integrity, since
+/// importing them requires the chain they belong to.
+pub fn verify_snapshot<R: SnapshotReader + ?Sized>(reader: &R, db: Arc<Database>, progress: &Progress) -> Result<(), ::error::Error> {
+	let manifest = reader.manifest();
+	let mut rebuilder = StateRebuilder::new(db, Algorithm::Archive);
+	let mut snappy_buffer = Vec::new();
+
+	for &hash in &manifest.state_hashes {
+		let chunk = try!(reader.chunk(hash));
+		try!(check_chunk_hash(hash, &chunk));
+
+		let len = try!(snappy::decompress_into(&chunk, &mut snappy_buffer));
+		try!(rebuilder.feed(&snappy_buffer[..len]));
+
+		progress.accounts.fetch_add(UntrustedRlp::new(&snappy_buffer[..len]).item_count(), Ordering::SeqCst);
+		progress.size.fetch_add(chunk.len(), Ordering::SeqCst);
+	}
+
+	// deltas apply on top of the complete base state.
+	for &hash in &manifest.delta_hashes {
+		let chunk = try!(reader.chunk(hash));
+		try!(check_chunk_hash(hash, &chunk));
+
+		let len = try!(snappy::decompress_into(&chunk, &mut snappy_buffer));
+		try!(rebuilder.feed(&snappy_buffer[..len]));
+
+		progress.accounts.fetch_add(UntrustedRlp::new(&snappy_buffer[..len]).item_count(), Ordering::SeqCst);
+		progress.size.fetch_add(chunk.len(), Ordering::SeqCst);
+	}
+
+	for &hash in &manifest.block_hashes {
+		let chunk = try!(reader.chunk(hash));
+		try!(check_chunk_hash(hash, &chunk));
+
+		let len = try!(snappy::decompress_into(&chunk, &mut snappy_buffer));
+		let rlp = UntrustedRlp::new(&snappy_buffer[..len]);
+
+		// first three items are the parent number, hash and total difficulty.
+		try!(rlp.val_at::<u64>(0));
+		try!(rlp.val_at::<H256>(1));
+
+		progress.blocks.fetch_add(rlp.item_count().saturating_sub(3), Ordering::SeqCst);
+		progress.size.fetch_add(chunk.len(), Ordering::SeqCst);
+	}
+
+	let root = rebuilder.state_root();
+	if root != manifest.state_root {
+		return Err(Error::StateRootMismatch(manifest.state_root, root).into());
+	}
+
+	try!(rebuilder.check_missing());
+
+	progress.done.store(true, Ordering::SeqCst);
+	Ok(())
+}
+
+// ensure the chunk data hashes to the value it's listed under.
+fn check_chunk_hash(expected: H256, chunk: &[u8]) -> Result<(), Error> {
+	let found = chunk.sha3();
+	match found == expected {
+		true => Ok(()),
+		false => Err(Error::ChunkHashMismatch(expected, found)),
+	}
 }
 
 #[derive(Default)]
@@ -512,6 +707,11 @@ fn rebuild_accounts(
 		let hash: H256 = try!(account_rlp.val_at(0));
 		let fat_rlp = try!(account_rlp.at(1));
 
+		if fat_rlp.is_empty() {
+			*out = (hash, Vec::new());
+			continue;
+		}
+
 		let thin_rlp = {
 			let mut acct_db = AccountDBMut::from_hash(db, hash);
 
@@ -558,14 +758,23 @@ pub struct BlockRebuilder {
 impl BlockRebuilder {
 	/// Create a new BlockRebuilder.
 	pub fn new(chain: BlockChain, best_number: u64) -> Result<Self, ::error::Error> {
+		BlockRebuilder::resume(chain, best_number, Vec::new())
+	}
+
+	/// Create a BlockRebuilder continuing an interrupted restoration, given the
+	/// disconnected chunk heads recorded when it stopped.
+	pub fn resume(chain: BlockChain, best_number: u64, disconnected: Vec<(u64, H256)>) -> Result<Self, ::error::Error> {
 		Ok(BlockRebuilder {
 			chain: chain,
 			rng: try!(OsRng::new()),
-			disconnected: Vec::new(),
+			disconnected: disconnected,
 			best_number: best_number,
 		})
 	}
 
+	/// Get the first blocks of chunks which are yet to be glued to their parents.
+	pub fn disconnected(&self) -> &[(u64, H256)] { &self.disconnected }
+
 	/// Feed the rebuilder an uncompressed block chunk.
 	/// Returns the number of blocks fed or any errors.
 	pub fn feed(&mut self, chunk: &[u8], engine: &Engine) -> Result<u64, ::error::Error> {
@@ -622,16 +831,25 @@ impl BlockRebuilder {
 
 	/// Glue together any disconnected chunks. To be called at the end.
 	pub fn glue_chunks(self) {
+		let mut first: Option<(u64, H256)> = None;
 		for (first_num, first_hash) in self.disconnected {
 			let parent_num = first_num - 1;
 
 			// check if the parent is even in the chain.
 			// since we don't restore every single block in the chain,
 			// the first block of the first chunks has nothing to connect to.
-			if let Some(parent_hash) = self.chain.block_hash(parent_num) {
+			match self.chain.block_hash(parent_num) {
 				// if so, add the child to it.
-				self.chain.add_child(parent_hash, first_hash);
+				Some(parent_hash) => self.chain.add_child(parent_hash, first_hash),
+				None => if first.map_or(true, |(n, _)| first_num < n) {
+					first = Some((first_num, first_hash));
+				},
 			}
 		}
+
+		// the oldest unglued block is where ancient block sync picks up.
+		if let Some((_, first_hash)) = first {
+			self.chain.set_first_block(first_hash);
+		}
 	}
 }
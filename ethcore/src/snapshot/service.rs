@@ -16,14 +16,15 @@
 
 //! Snapshot network service implementation.
 
-use std::collections::HashSet;
-use std::io::ErrorKind;
-use std::fs;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io::{ErrorKind, Read, Write};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use super::{ManifestData, StateRebuilder, BlockRebuilder, RestorationStatus, SnapshotService};
+use super::Error as SnapshotError;
 use super::io::{SnapshotReader, LooseReader, SnapshotWriter, LooseWriter};
 
 use blockchain::BlockChain;
@@ -35,10 +36,11 @@ use service::ClientIoMessage;
 
 use io::IoChannel;
 
-use util::{Bytes, H256, Mutex, RwLock, RwLockReadGuard, UtilError};
+use util::{Bytes, H256, Hashable, Mutex, RwLock, RwLockReadGuard, UtilError};
 use util::journaldb::Algorithm;
 use util::kvdb::{Database, DatabaseConfig};
 use util::snappy;
+use rlp::{DecoderError, PayloadInfo, RlpStream, Stream, UntrustedRlp, View};
 
 /// Helper for removing directories in case of error.
 struct Guard(bool, PathBuf);
@@ -63,17 +65,188 @@ pub trait DatabaseRestore: Send + Sync {
 	fn restore_db(&self, new_db: &str) -> Result<(), Error>;
 }
 
+/// Restoration progress which is persisted to disk after every chunk,
+/// so an interrupted restoration of the same snapshot can be resumed.
+///
+/// On disk, the progress is a header with the manifest followed by a record
+/// for every persisted chunk, holding only the chunk hashes fed since the
+/// previous record. This keeps the cost of persisting independent of the number
+/// of chunks already fed.
+#[derive(Debug, PartialEq)]
+struct RestorationProgress {
+	manifest: ManifestData,
+	recover: bool,
+	fed_state: Vec<H256>,
+	fed_blocks: Vec<H256>,
+	state_root: H256,
+	missing_code: Vec<(H256, Vec<H256>)>,
+	disconnected: Vec<(u64, H256)>,
+}
+
+impl RestorationProgress {
+	// encode the header of a progress file.
+	fn header_rlp(manifest: &ManifestData, recover: bool) -> Bytes {
+		let mut stream = RlpStream::new_list(2);
+		stream.append_raw(&manifest.clone().into_rlp(), 1);
+		stream.append(&recover);
+		stream.out()
+	}
+
+	// encode a progress record with the chunks fed since the previous one.
+	fn record_rlp(fed_state: &[H256], fed_blocks: &[H256], state_root: &H256,
+		missing_code: &[(H256, Vec<H256>)], disconnected: &[(u64, H256)]) -> Bytes
+	{
+		let mut stream = RlpStream::new_list(5);
+		stream.append(&fed_state);
+		stream.append(&fed_blocks);
+		stream.append(state_root);
+
+		stream.begin_list(missing_code.len());
+		for &(ref code_hash, ref accounts) in missing_code {
+			stream.begin_list(2).append(code_hash).append(accounts);
+		}
+
+		stream.begin_list(disconnected.len());
+		for &(ref number, ref hash) in disconnected {
+			stream.begin_list(2).append(number).append(hash);
+		}
+
+		stream.out()
+	}
+
+	fn from_rlp(raw: &[u8]) -> Result<Self, DecoderError> {
+		let mut items = Vec::new();
+		let mut offset = 0;
+		while offset < raw.len() {
+			// a truncated trailing record was never completely written; ignore it.
+			let len = match PayloadInfo::from(&raw[offset..]) {
+				Ok(info) => info.total(),
+				Err(_) => break,
+			};
+			if offset + len > raw.len() { break }
+
+			items.push(UntrustedRlp::new(&raw[offset..offset + len]));
+			offset += len;
+		}
+
+		let header = match items.first() {
+			Some(header) => header,
+			None => return Err(DecoderError::RlpIsTooShort),
+		};
+
+		let mut progress = RestorationProgress {
+			manifest: try!(ManifestData::from_rlp(try!(header.at(0)).as_raw())),
+			recover: try!(header.val_at(1)),
+			fed_state: Vec::new(),
+			fed_blocks: Vec::new(),
+			state_root: Default::default(),
+			missing_code: Vec::new(),
+			disconnected: Vec::new(),
+		};
+
+		for record in &items[1..] {
+			let fed_state: Vec<H256> = try!(record.val_at(0));
+			let fed_blocks: Vec<H256> = try!(record.val_at(1));
+			progress.fed_state.extend(fed_state);
+			progress.fed_blocks.extend(fed_blocks);
+			progress.state_root = try!(record.val_at(2));
+
+			progress.missing_code.clear();
+			for item in try!(record.at(3)).iter() {
+				progress.missing_code.push((try!(item.val_at(0)), try!(item.val_at(1))));
+			}
+
+			progress.disconnected.clear();
+			for item in try!(record.at(4)).iter() {
+				progress.disconnected.push((try!(item.val_at(0)), try!(item.val_at(1))));
+			}
+		}
+
+		Ok(progress)
+	}
+
+	// read the progress from the given file, if there is a valid one.
+	fn read(path: &Path) -> Option<Self> {
+		let mut buf = Vec::new();
+		match File::open(path).and_then(|mut file| file.read_to_end(&mut buf)) {
+			Ok(_) => RestorationProgress::from_rlp(&buf).ok(),
+			Err(_) => None,
+		}
+	}
+
+	// atomically overwrite the progress file at the given path with a compacted one.
+	fn write(&self, path: &Path) -> Result<(), Error> {
+		let mut temp_path = path.to_owned();
+		temp_path.set_extension("tmp");
+
+		{
+			let mut file = try!(File::create(&temp_path));
+			try!(file.write_all(&RestorationProgress::header_rlp(&self.manifest, self.recover)));
+			try!(file.write_all(&RestorationProgress::record_rlp(&self.fed_state, &self.fed_blocks,
+				&self.state_root, &self.missing_code, &self.disconnected)));
+		}
+
+		try!(fs::rename(&temp_path, path));
+		Ok(())
+	}
+}
+
+// link the chunk files with the given hashes from one loose snapshot directory into another,
+// copying them where linking isn't supported.
+fn link_chunks(from: &Path, to: &Path, hashes: &[H256]) -> ::std::io::Result<()> {
+	for hash in hashes {
+		let (src, dst) = (from.join(hash.hex()), to.join(hash.hex()));
+		if fs::hard_link(&src, &dst).is_err() {
+			try!(fs::copy(&src, &dst));
+		}
+	}
+
+	Ok(())
+}
+
+// read all code appended to the code file at the given path.
+fn read_code(path: &Path) -> Result<HashMap<H256, Bytes>, Error> {
+	let mut buf = Vec::new();
+	match File::open(path) {
+		Ok(mut file) => { try!(file.read_to_end(&mut buf)); }
+		Err(ref e) if e.kind() == ErrorKind::NotFound => {},
+		Err(e) => return Err(e.into()),
+	}
+
+	let mut code_map = HashMap::new();
+	let mut offset = 0;
+	while offset < buf.len() {
+		// a truncated trailing entry belongs to a chunk never recorded as fed; ignore it.
+		let len = match PayloadInfo::from(&buf[offset..]) {
+			Ok(info) => info.total(),
+			Err(_) => break,
+		};
+		if offset + len > buf.len() { break }
+
+		let rlp = UntrustedRlp::new(&buf[offset..offset + len]);
+		code_map.insert(try!(rlp.val_at(0)), try!(rlp.val_at(1)));
+		offset += len;
+	}
+
+	Ok(code_map)
+}
+
 /// State restoration manager.
 struct Restoration {
 	manifest: ManifestData,
 	state_chunks_left: HashSet<H256>,
+	delta_chunks_left: HashSet<H256>,
 	block_chunks_left: HashSet<H256>,
+	pending_deltas: Vec<(H256, Bytes)>, // delta chunks received before the base state is complete.
 	state: StateRebuilder,
 	blocks: BlockRebuilder,
 	writer: Option<LooseWriter>,
 	snappy_buffer: Bytes,
 	final_state_root: H256,
-	guard: Guard,
+	progress_path: PathBuf,
+	code_path: PathBuf,
+	unpersisted_state: Vec<H256>, // state and delta chunks fed since the last progress record.
+	unpersisted_blocks: Vec<H256>, // block chunks fed since the last progress record.
 }
 
 struct RestorationParams<'a> {
@@ -83,7 +256,9 @@ struct RestorationParams<'a> {
 	db_config: &'a DatabaseConfig, // configuration for the database.
 	writer: Option<LooseWriter>, // writer for recovered snapshot.
 	genesis: &'a [u8], // genesis block of the chain.
-	guard: Guard, // guard for the restoration directory.
+	progress_path: PathBuf, // path to persist restoration progress to.
+	code_path: PathBuf, // path to persist restored code to.
+	resume: Option<RestorationProgress>, // progress of an interrupted restoration to continue.
 }
 
 impl Restoration {
@@ -91,41 +266,110 @@ impl Restoration {
 	fn new(params: RestorationParams) -> Result<Self, Error> {
 		let manifest = params.manifest;
 
-		let state_chunks = manifest.state_hashes.iter().cloned().collect();
-		let block_chunks = manifest.block_hashes.iter().cloned().collect();
+		let mut state_chunks: HashSet<H256> = manifest.state_hashes.iter().cloned().collect();
+		let mut delta_chunks: HashSet<H256> = manifest.delta_hashes.iter().cloned().collect();
+		let mut block_chunks: HashSet<H256> = manifest.block_hashes.iter().cloned().collect();
 
 		let raw_db = Arc::new(try!(Database::open(params.db_config, &*params.db_path.to_string_lossy())
 			.map_err(UtilError::SimpleString)));
 
 		let chain = BlockChain::new(Default::default(), params.genesis, raw_db.clone());
-		let blocks = try!(BlockRebuilder::new(chain, manifest.block_number));
+
+		let (state, blocks) = match params.resume {
+			Some(progress) => {
+				for hash in &progress.fed_state {
+					state_chunks.remove(hash);
+					delta_chunks.remove(hash);
+				}
+				for hash in &progress.fed_blocks { block_chunks.remove(hash); }
+
+				let code_map = try!(read_code(&params.code_path));
+				let missing_code = progress.missing_code.into_iter().collect();
+
+				let state = StateRebuilder::resume(raw_db, params.pruning, progress.state_root, code_map, missing_code);
+				let blocks = try!(BlockRebuilder::resume(chain, manifest.block_number, progress.disconnected));
+				(state, blocks)
+			}
+			None => {
+				let blocks = try!(BlockRebuilder::new(chain, manifest.block_number));
+				(StateRebuilder::new(raw_db, params.pruning), blocks)
+			}
+		};
 
 		let root = manifest.state_root.clone();
-		Ok(Restoration {
+		let restoration = Restoration {
 			manifest: manifest,
 			state_chunks_left: state_chunks,
+			delta_chunks_left: delta_chunks,
 			block_chunks_left: block_chunks,
-			state: StateRebuilder::new(raw_db, params.pruning),
+			pending_deltas: Vec::new(),
+			state: state,
 			blocks: blocks,
 			writer: params.writer,
 			snappy_buffer: Vec::new(),
 			final_state_root: root,
-			guard: params.guard,
-		})
+			progress_path: params.progress_path,
+			code_path: params.code_path,
+			unpersisted_state: Vec::new(),
+			unpersisted_blocks: Vec::new(),
+		};
+
+		// start from a compacted progress file, which later records are appended to.
+		let (fed_state, fed_blocks) = restoration.fed_chunks();
+		let progress = RestorationProgress {
+			manifest: restoration.manifest.clone(),
+			recover: restoration.writer.is_some(),
+			fed_state: fed_state,
+			fed_blocks: fed_blocks,
+			state_root: restoration.state.state_root(),
+			missing_code: restoration.missing_code(),
+			disconnected: restoration.blocks.disconnected().to_vec(),
+		};
+		try!(progress.write(&restoration.progress_path));
+
+		Ok(restoration)
 	}
 
-	// feeds a state chunk
+	// feeds a state chunk. delta chunks are held back until the base state is complete.
 	fn feed_state(&mut self, hash: H256, chunk: &[u8]) -> Result<(), Error> {
 		if self.state_chunks_left.remove(&hash) {
-			let len = try!(snappy::decompress_into(chunk, &mut self.snappy_buffer));
+			try!(self.rebuild_state(hash, chunk));
 
-			try!(self.state.feed(&self.snappy_buffer[..len]));
+			if self.state_chunks_left.is_empty() {
+				for (hash, chunk) in ::std::mem::replace(&mut self.pending_deltas, Vec::new()) {
+					self.delta_chunks_left.remove(&hash);
+					try!(self.rebuild_state(hash, &chunk));
+				}
+			}
 
-			if let Some(ref mut writer) = self.writer.as_mut() {
-				try!(writer.write_state_chunk(hash, chunk));
+			try!(self.persist());
+		} else if self.delta_chunks_left.contains(&hash) {
+			if !self.state_chunks_left.is_empty() {
+				if !self.pending_deltas.iter().any(|&(ref h, _)| h == &hash) {
+					self.pending_deltas.push((hash, chunk.to_vec()));
+				}
+				return Ok(());
 			}
+
+			self.delta_chunks_left.remove(&hash);
+			try!(self.rebuild_state(hash, chunk));
+			try!(self.persist());
+		}
+
+		Ok(())
+	}
+
+	// feed a state or delta chunk into the state rebuilder.
+	fn rebuild_state(&mut self, hash: H256, chunk: &[u8]) -> Result<(), Error> {
+		let len = try!(snappy::decompress_into(chunk, &mut self.snappy_buffer));
+
+		try!(self.state.feed(&self.snappy_buffer[..len]));
+
+		if let Some(ref mut writer) = self.writer.as_mut() {
+			try!(writer.write_state_chunk(hash, chunk));
 		}
 
+		self.unpersisted_state.push(hash);
 		Ok(())
 	}
 
@@ -138,17 +382,72 @@ impl Restoration {
 			if let Some(ref mut writer) = self.writer.as_mut() {
 				try!(writer.write_block_chunk(hash, chunk));
 			}
+
+			self.unpersisted_blocks.push(hash);
+			try!(self.persist());
 		}
 
 		Ok(())
 	}
 
+	// number of state and block chunks fed so far. delta chunks count as state chunks.
+	fn chunks_done(&self) -> (usize, usize) {
+		(self.manifest.state_hashes.len() - self.state_chunks_left.len() +
+			self.manifest.delta_hashes.len() - self.delta_chunks_left.len(),
+			self.manifest.block_hashes.len() - self.block_chunks_left.len())
+	}
+
+	// hashes of state and block chunks fed so far.
+	fn fed_chunks(&self) -> (Vec<H256>, Vec<H256>) {
+		let fed_state = self.manifest.state_hashes.iter().filter(|h| !self.state_chunks_left.contains(h))
+			.chain(self.manifest.delta_hashes.iter().filter(|h| !self.delta_chunks_left.contains(h)))
+			.cloned().collect();
+		let fed_blocks = self.manifest.block_hashes.iter().filter(|h| !self.block_chunks_left.contains(h)).cloned().collect();
+		(fed_state, fed_blocks)
+	}
+
+	// code hashes which are still missing, with the accounts waiting for them.
+	fn missing_code(&self) -> Vec<(H256, Vec<H256>)> {
+		self.state.missing_code().iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+	}
+
+	// persist the progress made since the last call. code is written before the progress
+	// record, so any chunk recorded as fed has its code available on resumption.
+	fn persist(&mut self) -> Result<(), Error> {
+		let new_code = self.state.take_new_code();
+		if !new_code.is_empty() {
+			let mut file = try!(OpenOptions::new().create(true).append(true).open(&self.code_path));
+			for (hash, code) in new_code {
+				let mut stream = RlpStream::new_list(2);
+				stream.append(&hash).append(&code);
+				try!(file.write_all(&stream.out()));
+			}
+		}
+
+		let record = RestorationProgress::record_rlp(&self.unpersisted_state, &self.unpersisted_blocks,
+			&self.state.state_root(), &self.missing_code(), self.blocks.disconnected());
+
+		let mut file = try!(OpenOptions::new().append(true).open(&self.progress_path));
+		try!(file.write_all(&record));
+
+		self.unpersisted_state.clear();
+		self.unpersisted_blocks.clear();
+		Ok(())
+	}
+
 	// finish up restoration.
 	fn finalize(self) -> Result<(), Error> {
 		use util::trie::TrieError;
 
 		if !self.is_done() { return Ok(()) }
 
+		// the restoration can't be resumed past this point.
+		if let Err(e) = fs::remove_file(&self.progress_path) {
+			if e.kind() != ErrorKind::NotFound {
+				return Err(e.into())
+			}
+		}
+
 		// verify final state root.
 		let root = self.state.state_root();
 		if root != self.final_state_root {
@@ -166,13 +465,12 @@ impl Restoration {
 			try!(writer.finish(self.manifest));
 		}
 
-		self.guard.disarm();
 		Ok(())
 	}
 
 	// is everything done?
 	fn is_done(&self) -> bool {
-		self.block_chunks_left.is_empty() && self.state_chunks_left.is_empty()
+		self.block_chunks_left.is_empty() && self.state_chunks_left.is_empty() && self.delta_chunks_left.is_empty()
 	}
 }
 
@@ -208,6 +506,7 @@ pub struct Service {
 	pruning: Algorithm,
 	status: Mutex<RestorationStatus>,
 	reader: RwLock<Option<LooseReader>>,
+	base_reader: RwLock<Option<LooseReader>>, // full snapshot an incremental `reader` is based on.
 	engine: Arc<Engine>,
 	genesis_block: Bytes,
 	state_chunks: AtomicUsize,
@@ -228,6 +527,7 @@ impl Service {
 			pruning: params.pruning,
 			status: Mutex::new(RestorationStatus::Inactive),
 			reader: RwLock::new(None),
+			base_reader: RwLock::new(None),
 			engine: params.engine,
 			genesis_block: params.genesis_block,
 			state_chunks: AtomicUsize::new(0),
@@ -244,12 +544,7 @@ impl Service {
 			}
 		}
 
-		// delete the temporary restoration dir if it does exist.
-		if let Err(e) = fs::remove_dir_all(service.restoration_dir()) {
-			if e.kind() != ErrorKind::NotFound {
-				return Err(e.into())
-			}
-		}
+		// the restoration dir is kept, so an interrupted restoration can be resumed.
 
 		// delete the temporary snapshot dir if it does exist.
 		if let Err(e) = fs::remove_dir_all(service.temp_snapshot_dir()) {
@@ -258,8 +553,20 @@ impl Service {
 			}
 		}
 
+		// serve the latest incremental snapshot if there is one on top of the full snapshot.
 		let reader = LooseReader::new(service.snapshot_dir()).ok();
-		*service.reader.get_mut() = reader;
+		let incremental = reader.as_ref().and_then(|full| {
+			let base_hash = full.manifest().clone().into_rlp().sha3();
+			LooseReader::new(service.incremental_snapshot_dir()).ok()
+				.and_then(|r| if r.manifest().base == Some(base_hash) { Some(r) } else { None })
+		});
+		match incremental {
+			Some(incremental) => {
+				*service.reader.get_mut() = Some(incremental);
+				*service.base_reader.get_mut() = reader;
+			}
+			None => *service.reader.get_mut() = reader,
+		}
 
 		Ok(service)
 	}
@@ -271,6 +578,13 @@ impl Service {
 		dir
 	}
 
+	// get the incremental snapshot dir.
+	fn incremental_snapshot_dir(&self) -> PathBuf {
+		let mut dir = self.snapshot_root.clone();
+		dir.push("incremental");
+		dir
+	}
+
 	// get the temporary snapshot dir.
 	fn temp_snapshot_dir(&self) -> PathBuf {
 		let mut dir = self.snapshot_root.clone();
@@ -299,6 +613,20 @@ impl Service {
 		dir
 	}
 
+	// restoration progress path.
+	fn restoration_progress(&self) -> PathBuf {
+		let mut dir = self.restoration_dir();
+		dir.push("progress");
+		dir
+	}
+
+	// restored code path.
+	fn restoration_code(&self) -> PathBuf {
+		let mut dir = self.restoration_dir();
+		dir.push("code");
+		dir
+	}
+
 	// replace one the client's database with our own.
 	fn replace_client_db(&self) -> Result<(), Error> {
 		let our_db = self.restoration_db();
@@ -361,13 +689,17 @@ impl Service {
 
 		let mut reader = self.reader.write();
 
-		// destroy the old snapshot reader.
+		// destroy the old snapshot readers.
 		*reader = None;
+		*self.base_reader.write() = None;
 
 		if snapshot_dir.exists() {
 			try!(fs::remove_dir_all(&snapshot_dir));
 		}
 
+		// incremental snapshots on top of the old one are obsolete.
+		let _ = fs::remove_dir_all(self.incremental_snapshot_dir());
+
 		try!(fs::rename(temp_dir, &snapshot_dir));
 
 		*reader = Some(try!(LooseReader::new(snapshot_dir)));
@@ -376,8 +708,91 @@ impl Service {
 		Ok(())
 	}
 
+	/// Take an incremental snapshot at the block with the given number, on top of the
+	/// current full snapshot. Falls back to a full snapshot if the accounts changed since
+	/// the full snapshot weren't tracked.
+	///
+	/// The base's state chunks are linked into the incremental snapshot, so it can be
+	/// served on its own.
+	pub fn take_incremental_snapshot(&self, client: &Client, num: u64) -> Result<(), Error> {
+		let base = match LooseReader::new(self.snapshot_dir()) {
+			Ok(reader) => reader.manifest().clone(),
+			Err(_) => {
+				info!("No full snapshot to base an incremental snapshot at #{} on. Taking a full snapshot.", num);
+				return self.take_snapshot(client, num);
+			}
+		};
+
+		if base.base.is_some() || base.block_number >= num {
+			trace!(target: "snapshot", "Skipping incremental snapshot at #{} on top of #{}", num, base.block_number);
+			return Ok(());
+		}
+
+		if self.taking_snapshot.compare_and_swap(false, true, Ordering::SeqCst) {
+			info!("Skipping snapshot at #{} as another one is currently in-progress.", num);
+			return Ok(());
+		}
+
+		info!("Taking incremental snapshot at #{}", num);
+		self.progress.reset();
+
+		let temp_dir = self.temp_snapshot_dir();
+		let snapshot_dir = self.snapshot_dir();
+		let incremental_dir = self.incremental_snapshot_dir();
+
+		let _ = fs::remove_dir_all(&temp_dir);
+
+		let guard = Guard::new(temp_dir.clone());
+		let res = LooseWriter::new(temp_dir.clone())
+			.and_then(|writer| link_chunks(&snapshot_dir, &temp_dir, &base.state_hashes).map(|_| writer))
+			.map_err(Error::from)
+			.and_then(|writer| client.take_incremental_snapshot(writer, BlockID::Number(num), &base, &self.progress));
+
+		self.taking_snapshot.store(false, Ordering::SeqCst);
+		match res {
+			Err(Error::Snapshot(SnapshotError::UntrackedChanges(_))) => {
+				info!("Accounts changed since the snapshot at #{} are unknown. Taking a full snapshot instead.", base.block_number);
+				drop(guard);
+				return self.take_snapshot(client, num);
+			}
+			Err(e) => {
+				if client.chain_info().best_block_number >= num + ::client::HISTORY {
+					info!("Cancelled prematurely-started periodic snapshot.");
+					return Ok(())
+				} else {
+					return Err(e);
+				}
+			}
+			Ok(()) => {}
+		}
+
+		info!("Finished taking incremental snapshot at #{}", num);
+
+		let mut reader = self.reader.write();
+
+		// destroy the old snapshot reader.
+		*reader = None;
+
+		if incremental_dir.exists() {
+			try!(fs::remove_dir_all(&incremental_dir));
+		}
+
+		try!(fs::rename(temp_dir, &incremental_dir));
+
+		*reader = Some(try!(LooseReader::new(incremental_dir)));
+		*self.base_reader.write() = Some(try!(LooseReader::new(snapshot_dir)));
+
+		guard.disarm();
+		Ok(())
+	}
+
 	/// Initialize the restoration synchronously.
 	/// The recover flag indicates whether to recover the restored snapshot.
+	///
+	/// If an interrupted restoration of the same snapshot is found on disk, it is resumed
+	/// instead and only the remaining chunks need to be fed. The recover flag of the
+	/// interrupted restoration is kept in that case. Aborted restorations and those stopped
+	/// by a shutdown keep their data; only failed restorations delete it.
 	pub fn init_restore(&self, manifest: ManifestData, recover: bool) -> Result<(), Error> {
 		let rest_dir = self.restoration_dir();
 
@@ -389,15 +804,28 @@ impl Service {
 		// tear down existing restoration.
 		*res = None;
 
-		// delete and restore the restoration dir.
-		if let Err(e) = fs::remove_dir_all(&rest_dir) {
-			match e.kind() {
-				ErrorKind::NotFound => {},
-				_ => return Err(e.into()),
+		let resume = RestorationProgress::read(&self.restoration_progress())
+			.and_then(|progress| if progress.manifest == manifest { Some(progress) } else { None });
+
+		let recover = match resume {
+			Some(ref progress) => {
+				info!("Resuming snapshot restoration with {} state chunks and {} block chunks already done.",
+					progress.fed_state.len(), progress.fed_blocks.len());
+				progress.recover
 			}
-		}
+			None => {
+				// delete and restore the restoration dir.
+				if let Err(e) = fs::remove_dir_all(&rest_dir) {
+					match e.kind() {
+						ErrorKind::NotFound => {},
+						_ => return Err(e.into()),
+					}
+				}
 
-		try!(fs::create_dir_all(&rest_dir));
+				try!(fs::create_dir_all(&rest_dir));
+				recover
+			}
+		};
 
 		// make new restoration.
 		let writer = match recover {
@@ -405,6 +833,7 @@ impl Service {
 			false => None
 		};
 
+		let resumed = resume.is_some();
 		let params = RestorationParams {
 			manifest: manifest,
 			pruning: self.pruning,
@@ -412,15 +841,41 @@ impl Service {
 			db_config: &self.db_config,
 			writer: writer,
 			genesis: &self.genesis_block,
-			guard: Guard::new(rest_dir),
+			progress_path: self.restoration_progress(),
+			code_path: self.restoration_code(),
+			resume: resume,
 		};
 
-		*res = Some(try!(Restoration::new(params)));
+		let restoration = match Restoration::new(params) {
+			Ok(restoration) => restoration,
+			Err(e) => {
+				let _ = fs::remove_dir_all(&rest_dir);
+				*self.status.lock() = RestorationStatus::Failed;
+				return Err(e);
+			}
+		};
+		let (state_done, blocks_done) = restoration.chunks_done();
+		let is_done = resumed && restoration.is_done();
+
+		self.state_chunks.store(state_done, Ordering::SeqCst);
+		self.block_chunks.store(blocks_done, Ordering::SeqCst);
+
+		*res = Some(restoration);
 
 		*self.status.lock() = RestorationStatus::Ongoing {
 			state_chunks_done: self.state_chunks.load(Ordering::SeqCst) as u32,
 			block_chunks_done: self.block_chunks.load(Ordering::SeqCst) as u32,
 		};
+
+		// the interrupted restoration may have been stopped right before finalization.
+		if is_done {
+			if let Err(e) = self.finalize_restoration(&mut *res) {
+				let _ = fs::remove_dir_all(&rest_dir);
+				*self.status.lock() = RestorationStatus::Failed;
+				return Err(e);
+			}
+		}
+
 		Ok(())
 	}
 
@@ -438,7 +893,8 @@ impl Service {
 
 		if recover {
 			let mut reader = self.reader.write();
-			*reader = None; // destroy the old reader if it existed.
+			*reader = None; // destroy the old readers if they existed.
+			*self.base_reader.write() = None;
 
 			let snapshot_dir = self.snapshot_dir();
 
@@ -447,6 +903,8 @@ impl Service {
 				try!(fs::remove_dir_all(&snapshot_dir));
 			}
 
+			let _ = fs::remove_dir_all(self.incremental_snapshot_dir());
+
 			trace!(target: "snapshot", "copying restored snapshot files over");
 			try!(fs::rename(self.temp_recovery_dir(), &snapshot_dir));
 
@@ -476,15 +934,14 @@ impl Service {
 					match is_state {
 						true => rest.feed_state(hash, chunk),
 						false => rest.feed_blocks(hash, chunk, &*self.engine),
-					}.map(|_| rest.is_done())
+					}.map(|_| (rest.is_done(), rest.chunks_done()))
 				};
 
 				match res {
-					Ok(is_done) => {
-						match is_state {
-							true => self.state_chunks.fetch_add(1, Ordering::SeqCst),
-							false => self.block_chunks.fetch_add(1, Ordering::SeqCst),
-						};
+					Ok((is_done, (state_done, blocks_done))) => {
+						// chunks fed before resumption are ignored, so count what was actually fed.
+						self.state_chunks.store(state_done, Ordering::SeqCst);
+						self.block_chunks.store(blocks_done, Ordering::SeqCst);
 
 						match is_done {
 							true => self.finalize_restoration(&mut *restoration),
@@ -529,8 +986,19 @@ impl SnapshotService for Service {
 		self.reader.read().as_ref().map(|r| r.manifest().clone())
 	}
 
+	fn full_manifest(&self) -> Option<ManifestData> {
+		let reader = self.reader.read();
+		let base_reader = self.base_reader.read();
+		base_reader.as_ref().or(reader.as_ref())
+			.map(|r| r.manifest().clone())
+			.and_then(|m| if m.base.is_none() { Some(m) } else { None })
+	}
+
 	fn chunk(&self, hash: H256) -> Option<Bytes> {
-		self.reader.read().as_ref().and_then(|r| r.chunk(hash).ok())
+		let reader = self.reader.read();
+		let base_reader = self.base_reader.read();
+		reader.as_ref().and_then(|r| r.chunk(hash).ok())
+			.or_else(|| base_reader.as_ref().and_then(|r| r.chunk(hash).ok()))
 	}
 
 	fn status(&self) -> RestorationStatus {
@@ -543,6 +1011,19 @@ impl SnapshotService for Service {
 		cur_status.clone()
 	}
 
+	fn completed_chunks(&self, manifest: &ManifestData) -> Vec<H256> {
+		let (fed_state, fed_blocks) = match *self.restoration.lock() {
+			Some(ref rest) if &rest.manifest == manifest => rest.fed_chunks(),
+			Some(_) => return Vec::new(),
+			None => match RestorationProgress::read(&self.restoration_progress()) {
+				Some(progress) if &progress.manifest == manifest => (progress.fed_state, progress.fed_blocks),
+				_ => return Vec::new(),
+			},
+		};
+
+		fed_state.into_iter().chain(fed_blocks).collect()
+	}
+
 	fn begin_restore(&self, manifest: ManifestData) {
 		self.io_channel.send(ClientIoMessage::BeginRestoration(manifest))
 			.expect("snapshot service and io service are kept alive by client service; qed");
@@ -622,6 +1103,8 @@ mod tests {
 			state_root: Default::default(),
 			block_number: 0,
 			block_hash: Default::default(),
+			delta_hashes: Vec::new(),
+			base: None,
 		};
 
 		service.begin_restore(manifest);
@@ -629,4 +1112,77 @@ mod tests {
 		service.restore_state_chunk(Default::default(), vec![]);
 		service.restore_block_chunk(Default::default(), vec![]);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn progress_roundtrip() {
+		let progress = RestorationProgress {
+			manifest: ManifestData {
+				state_hashes: vec![1.into(), 2.into()],
+				block_hashes: vec![3.into()],
+				state_root: 4.into(),
+				block_number: 5,
+				block_hash: 6.into(),
+				delta_hashes: Vec::new(),
+				base: None,
+			},
+			recover: true,
+			fed_state: vec![2.into()],
+			fed_blocks: vec![],
+			state_root: 7.into(),
+			missing_code: vec![(8.into(), vec![9.into(), 10.into()])],
+			disconnected: vec![(11, 12.into())],
+		};
+
+		let path = RandomTempPath::new();
+		progress.write(path.as_path()).unwrap();
+		assert_eq!(RestorationProgress::read(path.as_path()), Some(progress));
+	}
+
+	#[test]
+	fn progress_records_accumulate() {
+		use std::fs::OpenOptions;
+		use std::io::Write;
+		use util::H256;
+
+		let manifest = ManifestData {
+			state_hashes: vec![1.into(), 2.into()],
+			block_hashes: vec![3.into()],
+			state_root: 4.into(),
+			block_number: 5,
+			block_hash: 6.into(),
+			delta_hashes: Vec::new(),
+			base: None,
+		};
+
+		let path = RandomTempPath::new();
+		RestorationProgress {
+			manifest: manifest.clone(),
+			recover: false,
+			fed_state: vec![1.into()],
+			fed_blocks: vec![],
+			state_root: 7.into(),
+			missing_code: vec![(8.into(), vec![9.into()])],
+			disconnected: vec![],
+		}.write(path.as_path()).unwrap();
+
+		{
+			let mut file = OpenOptions::new().append(true).open(path.as_path()).unwrap();
+			let record = RestorationProgress::record_rlp(&[H256::from(2)], &[H256::from(3)], &H256::from(10), &[], &[(11, H256::from(12))]);
+			file.write_all(&record).unwrap();
+
+			// a truncated record is ignored.
+			let record = RestorationProgress::record_rlp(&[], &[], &H256::from(13), &[], &[]);
+			file.write_all(&record[..record.len() - 1]).unwrap();
+		}
+
+		assert_eq!(RestorationProgress::read(path.as_path()), Some(RestorationProgress {
+			manifest: manifest,
+			recover: false,
+			fed_state: vec![1.into(), 2.into()],
+			fed_blocks: vec![3.into()],
+			state_root: 10.into(),
+			missing_code: vec![],
+			disconnected: vec![(11, 12.into())],
+		}));
+	}
+}
@@ -28,18 +28,27 @@ pub trait SnapshotService : Sync + Send {
 	/// Query the most recent manifest data.
 	fn manifest(&self) -> Option<ManifestData>;
 
+	/// Query the most recent manifest data of a full snapshot, for peers
+	/// which don't support incremental snapshots.
+	fn full_manifest(&self) -> Option<ManifestData>;
+
 	/// Get raw chunk for a given hash.
 	fn chunk(&self, hash: H256) -> Option<Bytes>;
 
 	/// Ask the snapshot service for the restoration status.
 	fn status(&self) -> RestorationStatus;
 
+	/// Get the hashes of chunks of the given snapshot which were already restored
+	/// by an interrupted restoration. `begin_restore` will resume it rather than start over.
+	fn completed_chunks(&self, manifest: &ManifestData) -> Vec<H256>;
+
 	/// Begin snapshot restoration.
-	/// If restoration in-progress, this will reset it.
+	/// If restoration in-progress, this will reset it, unless it is of the same snapshot.
 	/// From this point on, any previous snapshot may become unavailable.
 	fn begin_restore(&self, manifest: ManifestData);
 
 	/// Abort an in-progress restoration if there is one.
+	/// Its progress is kept on disk, so the restoration can be resumed later.
 	fn abort_restore(&self);
 
 	/// Feed a raw state chunk to the service to be processed asynchronously.
@@ -19,7 +19,7 @@
 use devtools::RandomTempPath;
 
 use blockchain::generator::{ChainGenerator, ChainIterator, BlockFinalizer};
-use blockchain::BlockChain;
+use blockchain::{BlockChain, BlockProvider};
 use snapshot::{chunk_blocks, BlockRebuilder, Progress};
 use snapshot::io::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
 
@@ -64,6 +64,8 @@ fn chunk_and_restore(amount: u64) {
 		state_root: Default::default(),
 		block_number: amount,
 		block_hash: best_hash,
+		delta_hashes: Vec::new(),
+		base: None,
 	}).unwrap();
 
 	// restore it.
@@ -83,6 +85,21 @@ fn chunk_and_restore(amount: u64) {
 	// and test it.
 	let new_chain = BlockChain::new(Default::default(), &genesis, new_db);
 	assert_eq!(new_chain.best_block_hash(), best_hash);
+
+	// fill in the ancient blocks the snapshot didn't cover.
+	while new_chain.first_block_number() != 0 {
+		let parent = new_chain.block_details(&new_chain.first_block()).unwrap().parent;
+		let block = bc.block(&parent).unwrap();
+		let receipts = bc.block_receipts(&parent).unwrap().receipts;
+		assert!(new_chain.insert_ancient_block(&block, receipts));
+		new_chain.commit();
+	}
+
+	assert_eq!(new_chain.first_block(), bc.genesis_hash());
+	assert_eq!(new_chain.block_hash(1), bc.block_hash(1));
+	if let Some(hash) = bc.block_hash(1) {
+		assert_eq!(new_chain.block_details(&hash).unwrap().total_difficulty, bc.block_details(&hash).unwrap().total_difficulty);
+	}
 }
 
 #[test]
@@ -32,6 +32,23 @@ fn manifest_rlp() {
 		block_number: 1234567,
 		state_root: Default::default(),
 		block_hash: Default::default(),
+		delta_hashes: Vec::new(),
+		base: None,
+	};
+	let raw = manifest.clone().into_rlp();
+	assert_eq!(ManifestData::from_rlp(&raw).unwrap(), manifest);
+}
+
+#[test]
+fn incremental_manifest_rlp() {
+	let manifest = ManifestData {
+		block_hashes: vec![1.into()],
+		state_hashes: vec![2.into(), 3.into()],
+		block_number: 1234567,
+		state_root: Default::default(),
+		block_hash: Default::default(),
+		delta_hashes: vec![4.into()],
+		base: Some(5.into()),
 	};
 	let raw = manifest.clone().into_rlp();
 	assert_eq!(ManifestData::from_rlp(&raw).unwrap(), manifest);
@@ -104,7 +104,7 @@ fn restored_is_equivalent() {
 }
 
 #[test]
-fn guards_delete_folders() {
+fn keeps_aborted_restoration() {
 	let spec = Spec::new_null();
 	let path = RandomTempPath::create_dir();
 	let mut path = path.as_path().clone();
@@ -126,18 +126,102 @@ fn guards_delete_folders() {
 		block_hashes: vec![],
 		block_number: 0,
 		block_hash: Default::default(),
+		delta_hashes: Vec::new(),
+		base: None,
 		state_root: Default::default(),
 	};
 
 	service.init_restore(manifest.clone(), true).unwrap();
 	assert!(path.exists());
 
+	// aborted and shut down restorations are kept, so they can be resumed.
 	service.abort_restore();
-	assert!(!path.exists());
+	assert!(path.exists());
 
 	service.init_restore(manifest.clone(), true).unwrap();
 	assert!(path.exists());
 
 	drop(service);
-	assert!(!path.exists());
+	assert!(path.exists());
+}
+
+#[test]
+fn resumes_interrupted_restoration() {
+	const NUM_BLOCKS: u32 = 400;
+	const TX_PER: usize = 5;
+
+	let gas_prices = vec![1.into(), 2.into(), 3.into(), 999.into()];
+
+	let client = generate_dummy_client_with_spec_and_data(Spec::new_null, NUM_BLOCKS, TX_PER, &gas_prices);
+
+	let path = RandomTempPath::create_dir();
+	let mut path = path.as_path().clone();
+	let mut client_db = path.clone();
+
+	client_db.push("client_db");
+	path.push("snapshot");
+
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	let spec = Spec::new_null();
+	let client2 = Client::new(
+		Default::default(),
+		&spec,
+		&client_db,
+		Arc::new(::miner::Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config,
+	).unwrap();
+
+	let service_params = || ServiceParams {
+		engine: spec.engine.clone(),
+		genesis_block: spec.genesis_block(),
+		db_config: db_config.clone(),
+		pruning: ::util::journaldb::Algorithm::Archive,
+		channel: IoChannel::disconnected(),
+		snapshot_root: path.clone(),
+		db_restore: client2.clone(),
+	};
+
+	let service = Service::new(service_params()).unwrap();
+	service.take_snapshot(&client, NUM_BLOCKS as u64).unwrap();
+
+	let manifest = service.manifest().unwrap();
+	let chunks: Vec<_> = manifest.state_hashes.iter().chain(&manifest.block_hashes)
+		.map(|hash| (*hash, service.chunk(*hash).unwrap()))
+		.collect();
+
+	// feed the first state and block chunks, then shut down.
+	service.init_restore(manifest.clone(), true).unwrap();
+	let (first_state, first_block) = (manifest.state_hashes[0], manifest.block_hashes[0]);
+	service.feed_state_chunk(first_state, &chunks[0].1);
+	service.feed_block_chunk(first_block, &chunks[manifest.state_hashes.len()].1);
+	drop(service);
+
+	let service = Service::new(service_params()).unwrap();
+	assert_eq!(service.completed_chunks(&manifest), vec![first_state, first_block]);
+
+	service.init_restore(manifest.clone(), true).unwrap();
+	assert_eq!(service.status(), ::snapshot::RestorationStatus::Ongoing {
+		state_chunks_done: 1,
+		block_chunks_done: 1,
+	});
+
+	for &(hash, ref chunk) in &chunks {
+		if hash == first_state || hash == first_block { continue }
+
+		match manifest.state_hashes.contains(&hash) {
+			true => service.feed_state_chunk(hash, chunk),
+			false => service.feed_block_chunk(hash, chunk),
+		}
+	}
+
+	assert_eq!(service.status(), ::snapshot::RestorationStatus::Inactive);
+
+	for x in 0..NUM_BLOCKS {
+		let block1 = client.block(BlockID::Number(x as u64)).unwrap();
+		let block2 = client2.block(BlockID::Number(x as u64)).unwrap();
+
+		assert_eq!(block1, block2);
+	}
 }
\ No newline at end of file
@@ -16,18 +16,23 @@
 
 //! State snapshotting tests.
 
-use snapshot::{chunk_state, Progress, StateRebuilder};
+use account_db::AccountDB;
+use snapshot::{chunk_state, chunk_state_delta, verify_snapshot, Progress, StateRebuilder};
 use snapshot::io::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
 use super::helpers::{compare_dbs, StateProducer};
 
 use rand::{XorShiftRng, SeedableRng};
+use rlp::{Rlp, View};
 use util::hash::H256;
+use util::HashDB;
 use util::journaldb::{self, Algorithm};
 use util::kvdb::{Database, DatabaseConfig};
 use util::memorydb::MemoryDB;
+use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut};
 use util::Mutex;
 use devtools::RandomTempPath;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[test]
@@ -56,6 +61,8 @@ fn snap_and_restore() {
 		state_root: state_root,
 		block_number: 0,
 		block_hash: H256::default(),
+		delta_hashes: Vec::new(),
+		base: None,
 	}).unwrap();
 
 	let mut db_path = snap_dir.as_path().to_owned();
@@ -82,3 +89,152 @@ fn snap_and_restore() {
 
 	compare_dbs(&old_db, new_db.as_hashdb());
 }
+
+#[test]
+fn verify_snapshot_root() {
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([5, 6, 7, 8]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..50 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	let snap_dir = RandomTempPath::create_dir();
+	let state_root = producer.state_root();
+
+	// write two snapshots: one with the correct root and one with a bogus one.
+	for &(name, root) in &[("GOOD", state_root), ("BAD", H256::from(1))] {
+		let mut snap_file = snap_dir.as_path().to_owned();
+		snap_file.push(name);
+
+		let writer = Mutex::new(PackedWriter::new(&snap_file).unwrap());
+		let state_hashes = chunk_state(&old_db, &state_root, &writer, &Progress::default()).unwrap();
+
+		writer.into_inner().finish(::snapshot::ManifestData {
+			state_hashes: state_hashes,
+			block_hashes: Vec::new(),
+			state_root: root,
+			block_number: 0,
+			block_hash: H256::default(),
+			delta_hashes: Vec::new(),
+			base: None,
+		}).unwrap();
+	}
+
+	for &(name, valid) in &[("GOOD", true), ("BAD", false)] {
+		let mut snap_file = snap_dir.as_path().to_owned();
+		snap_file.push(name);
+
+		let mut db_path = snap_dir.as_path().to_owned();
+		db_path.push(format!("{}-db", name));
+
+		let db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+		let reader = PackedReader::new(&snap_file).unwrap().unwrap();
+		let progress = Progress::default();
+
+		assert_eq!(verify_snapshot(&reader, db, &progress).is_ok(), valid);
+		assert_eq!(progress.done(), valid);
+	}
+}
+
+#[test]
+fn delta_on_top_of_base() {
+	let mut producer = StateProducer::new();
+	let mut rng = XorShiftRng::from_seed([9, 10, 11, 12]);
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..100 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	let base_root = producer.state_root();
+
+	for _ in 0..20 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	// remove an account, so the delta has to delete it.
+	let mut new_root = producer.state_root();
+	{
+		let removed = TrieDB::new(&old_db, &base_root).unwrap().iter().unwrap().next().unwrap().unwrap().0;
+		let mut trie = TrieDBMut::from_existing(&mut old_db, &mut new_root).unwrap();
+		trie.remove(&removed).unwrap();
+	}
+
+	let accounts = |root: &H256| -> HashMap<Vec<u8>, Vec<u8>> {
+		TrieDB::new(&old_db, root).unwrap().iter().unwrap()
+			.map(Result::unwrap)
+			.map(|(k, v)| (k, v.to_vec()))
+			.collect()
+	};
+
+	let (base, new) = (accounts(&base_root), accounts(&new_root));
+	let mut changed: Vec<H256> = new.iter().filter(|&(k, v)| base.get(k) != Some(v)).map(|(k, _)| H256::from_slice(k))
+		.chain(base.keys().filter(|k| !new.contains_key(*k)).map(|k| H256::from_slice(k)))
+		.collect();
+	changed.sort();
+
+	let snap_dir = RandomTempPath::create_dir();
+	let mut chunks = Vec::new();
+	for (name, is_delta) in vec![("BASE", false), ("DELTA", true)] {
+		let mut snap_file = snap_dir.as_path().to_owned();
+		snap_file.push(name);
+
+		let writer = Mutex::new(PackedWriter::new(&snap_file).unwrap());
+		let state_hashes = match is_delta {
+			false => chunk_state(&old_db, &base_root, &writer, &Progress::default()).unwrap(),
+			true => chunk_state_delta(&old_db, &new_root, &changed, &writer, &Progress::default()).unwrap(),
+		};
+
+		writer.into_inner().finish(::snapshot::ManifestData {
+			state_hashes: state_hashes,
+			block_hashes: Vec::new(),
+			state_root: new_root,
+			block_number: 0,
+			block_hash: H256::default(),
+			delta_hashes: Vec::new(),
+			base: None,
+		}).unwrap();
+
+		let reader = PackedReader::new(&snap_file).unwrap().unwrap();
+		for chunk_hash in &reader.manifest().state_hashes {
+			chunks.push(::util::snappy::decompress(&reader.chunk(*chunk_hash).unwrap()).unwrap());
+		}
+	}
+
+	let mut db_path = snap_dir.as_path().to_owned();
+	db_path.push("db");
+	let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+	let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::Archive);
+
+	for chunk in &chunks {
+		rebuilder.feed(chunk).unwrap();
+	}
+
+	assert_eq!(rebuilder.state_root(), new_root);
+	rebuilder.check_missing().unwrap();
+
+	// the restored accounts and their storage must match the new state.
+	let new_db = journaldb::new(new_db, Algorithm::Archive, ::db::COL_STATE);
+	let restored: HashMap<Vec<u8>, Vec<u8>> = TrieDB::new(new_db.as_hashdb(), &new_root).unwrap().iter().unwrap()
+		.map(Result::unwrap)
+		.map(|(k, v)| (k, v.to_vec()))
+		.collect();
+	assert_eq!(restored, new);
+
+	for (address_hash, account) in &new {
+		let address_hash = H256::from_slice(address_hash);
+		let storage_root: H256 = Rlp::new(account).val_at(2);
+		let storage = |db: &HashDB| -> Vec<(Vec<u8>, Vec<u8>)> {
+			let account_db = AccountDB::from_hash(db, address_hash);
+			let trie = TrieDB::new(&account_db, &storage_root).unwrap();
+			let items = trie.iter().unwrap().map(Result::unwrap).map(|(k, v)| (k, v.to_vec())).collect();
+			items
+		};
+
+		assert_eq!(storage(&old_db), storage(new_db.as_hashdb()));
+	}
+}
@@ -55,6 +55,8 @@ impl<F> Oracle for StandardOracle<F>
 // helper trait for broadcasting a block to take a snapshot at.
 trait Broadcast: Send + Sync {
 	fn take_at(&self, num: Option<u64>);
+
+	fn take_incremental_at(&self, num: Option<u64>);
 }
 
 impl Broadcast for IoChannel<ClientIoMessage> {
@@ -70,6 +72,19 @@ impl Broadcast for IoChannel<ClientIoMessage> {
 			warn!("Snapshot watcher disconnected from IoService: {}", e);
 		}
 	}
+
+	fn take_incremental_at(&self, num: Option<u64>) {
+		let num = match num {
+			Some(n) => n,
+			None => return,
+		};
+
+		trace!(target: "snapshot_watcher", "broadcast incremental: {}", num);
+
+		if let Err(e) = self.send(ClientIoMessage::TakeIncrementalSnapshot(num)) {
+			warn!("Snapshot watcher disconnected from IoService: {}", e);
+		}
+	}
 }
 
 /// A `ChainNotify` implementation which will trigger a snapshot event
@@ -78,14 +93,23 @@ pub struct Watcher {
 	oracle: Box<Oracle>,
 	broadcast: Box<Broadcast>,
 	period: u64,
+	incremental_period: u64,
 	history: u64,
 }
 
 impl Watcher {
 	/// Create a new `Watcher` which will trigger a snapshot event
-	/// once every `period` blocks, but only after that block is
-	/// `history` blocks old.
-	pub fn new<F>(client: Arc<Client>, sync_status: F, channel: IoChannel<ClientIoMessage>, period: u64, history: u64) -> Self
+	/// once every `period` blocks, and an incremental snapshot event once every
+	/// `incremental_period` blocks in between, but only after that block is
+	/// `history` blocks old. An `incremental_period` of 0 disables incremental snapshots.
+	pub fn new<F>(
+		client: Arc<Client>,
+		sync_status: F,
+		channel: IoChannel<ClientIoMessage>,
+		period: u64,
+		incremental_period: u64,
+		history: u64
+	) -> Self
 		where F: 'static + Send + Sync + Fn() -> bool
 	{
 		Watcher {
@@ -95,9 +119,20 @@ impl Watcher {
 			}),
 			broadcast: Box::new(channel),
 			period: period,
+			incremental_period: incremental_period,
 			history: history,
 		}
 	}
+
+	// highest of the given block numbers which is `history` blocks past a multiple of `period`,
+	// minus `history`. 0 if there is none.
+	fn highest(&self, numbers: &[u64], period: u64) -> u64 {
+		numbers.iter()
+			.filter(|&&num| num >= period + self.history)
+			.map(|&num| num - self.history)
+			.filter(|num| num % period == 0)
+			.fold(0, ::std::cmp::max)
+	}
 }
 
 impl ChainNotify for Watcher {
@@ -114,17 +149,27 @@ impl ChainNotify for Watcher {
 
 		trace!(target: "snapshot_watcher", "{} imported", imported.len());
 
-		let highest = imported.into_iter()
+		let numbers: Vec<_> = imported.into_iter()
 			.filter_map(|h| self.oracle.to_number(h))
-			.filter(|&num| num >= self.period + self.history)
-			.map(|num| num - self.history)
-			.filter(|num| num % self.period == 0)
-			.fold(0, ::std::cmp::max);
+			.collect();
+
+		let highest = self.highest(&numbers, self.period);
 
 		match highest {
 			0 => self.broadcast.take_at(None),
 			_ => self.broadcast.take_at(Some(highest)),
 		}
+
+		// full snapshots take the place of incremental ones.
+		let incremental = match (highest, self.incremental_period) {
+			(0, period) if period != 0 => self.highest(&numbers, period),
+			_ => 0,
+		};
+
+		match incremental {
+			0 => self.broadcast.take_incremental_at(None),
+			_ => self.broadcast.take_incremental_at(Some(incremental)),
+		}
 	}
 }
 
@@ -148,24 +193,43 @@ mod tests {
 		fn is_major_syncing(&self) -> bool { false }
 	}
 
-	struct TestBroadcast(Option<u64>);
+	struct TestBroadcast(Option<u64>, Option<u64>);
 	impl Broadcast for TestBroadcast {
 		fn take_at(&self, num: Option<u64>) {
 			if num != self.0 {
 				panic!("Watcher broadcast wrong number. Expected {:?}, found {:?}", self.0, num);
 			}
 		}
+
+		fn take_incremental_at(&self, num: Option<u64>) {
+			if num != self.1 {
+				panic!("Watcher broadcast wrong incremental number. Expected {:?}, found {:?}", self.1, num);
+			}
+		}
 	}
 
 	// helper harness for tests which expect a notification.
 	fn harness(numbers: Vec<u64>, period: u64, history: u64, expected: Option<u64>) {
+		incremental_harness(numbers, period, 0, history, expected, None)
+	}
+
+	// helper harness for tests which expect an incremental snapshot notification.
+	fn incremental_harness(
+		numbers: Vec<u64>,
+		period: u64,
+		incremental_period: u64,
+		history: u64,
+		expected: Option<u64>,
+		expected_incremental: Option<u64>
+	) {
 		let hashes: Vec<_> = numbers.clone().into_iter().map(|x| H256::from(U256::from(x))).collect();
 		let map = hashes.clone().into_iter().zip(numbers).collect();
 
 		let watcher = Watcher {
 			oracle: Box::new(TestOracle(map)),
-			broadcast: Box::new(TestBroadcast(expected)),
+			broadcast: Box::new(TestBroadcast(expected, expected_incremental)),
 			period: period,
+			incremental_period: incremental_period,
 			history: history,
 		};
 
@@ -200,4 +264,14 @@ mod tests {
 	fn doesnt_fire_before_history() {
 		harness(vec![10, 11], 10, 5, None);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn fires_incremental() {
+		incremental_harness(vec![22, 23], 10, 2, 5, None, Some(18));
+	}
+
+	#[test]
+	fn full_takes_place_of_incremental() {
+		incremental_harness(vec![14, 15], 10, 2, 5, Some(10), None);
+	}
+}
@@ -27,6 +27,27 @@ use ethereum;
 use ethjson;
 use rlp::{Rlp, RlpStream, View, Stream};
 
+/// Trusted checkpoint block. Peers whose chain does not include it are ignored.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Checkpoint {
+	/// Block number.
+	pub number: BlockNumber,
+	/// Block hash.
+	pub hash: H256,
+	/// Total difficulty of the chain up to and including the block.
+	pub total_difficulty: U256,
+}
+
+impl From<ethjson::spec::Checkpoint> for Checkpoint {
+	fn from(c: ethjson::spec::Checkpoint) -> Self {
+		Checkpoint {
+			number: c.number.into(),
+			hash: c.hash.into(),
+			total_difficulty: c.total_difficulty.into(),
+		}
+	}
+}
+
 /// Parameters common to all engines.
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(test, derive(Default))]
@@ -43,6 +64,8 @@ pub struct CommonParams {
 	pub min_gas_limit: U256,
 	/// Fork block to check.
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Trusted checkpoint.
+	pub checkpoint: Option<Checkpoint>,
 }
 
 impl From<ethjson::spec::Params> for CommonParams {
@@ -54,6 +77,7 @@ impl From<ethjson::spec::Params> for CommonParams {
 			subprotocol_name: p.subprotocol_name.unwrap_or_else(|| "eth".to_owned()),
 			min_gas_limit: p.min_gas_limit.into(),
 			fork_block: if let (Some(n), Some(h)) = (p.fork_block, p.fork_hash) { Some((n.into(), h.into())) } else { None },
+			checkpoint: p.checkpoint.map(Into::into),
 		}
 	}
 }
@@ -165,6 +189,9 @@ impl Spec {
 	/// Get the configured network fork block.
 	pub fn fork_block(&self) -> Option<(BlockNumber, H256)> { self.params.fork_block }
 
+	/// Get the configured trusted checkpoint.
+	pub fn checkpoint(&self) -> Option<Checkpoint> { self.params.checkpoint }
+
 	/// Get the header of the genesis block.
 	pub fn genesis_header(&self) -> Header {
 		let mut header: Header = Default::default();
@@ -234,20 +261,20 @@ impl Spec {
 	}
 
 	/// Ensure that the given state DB has the trie nodes in for the genesis state.
-	pub fn ensure_db_good(&self, db: &mut StateDB) -> Result<bool, Box<TrieError>> {
+	pub fn ensure_db_good(&self, db: &mut StateDB, factory: &TrieFactory) -> Result<bool, Box<TrieError>> {
 		if !db.as_hashdb().contains(&self.state_root()) {
 			trace!(target: "spec", "ensure_db_good: Fresh database? Cannot find state root {}", self.state_root());
 			let mut root = H256::new();
 
 			{
-				let mut t = SecTrieDBMut::new(db.as_hashdb_mut(), &mut root);
+				let mut t = factory.create(db.as_hashdb_mut(), &mut root);
 				for (address, account) in self.genesis_state.get().iter() {
 					try!(t.insert(&**address, &account.rlp()));
 				}
 			}
 			trace!(target: "spec", "ensure_db_good: Populated sec trie; root is {}", root);
 			for (address, account) in self.genesis_state.get().iter() {
-				account.insert_additional(&mut AccountDBMut::new(db.as_hashdb_mut(), address));
+				account.insert_additional(&mut AccountDBMut::new(db.as_hashdb_mut(), address), factory);
 			}
 			assert!(db.as_hashdb().contains(&self.state_root()));
 			Ok(true)
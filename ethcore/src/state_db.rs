@@ -109,6 +109,11 @@ impl StateDB {
 		&*self.db
 	}
 
+	/// Addresses of the accounts with pending cache changes, i.e. touched since the last commit.
+	pub fn touched_addresses(&self) -> Vec<Address> {
+		self.cache_overlay.iter().map(|&(ref address, _)| address.clone()).collect()
+	}
+
 	/// Enqueue cache change.
 	pub fn cache_account(&mut self, addr: Address, data: Option<Account>) {
 		self.cache_overlay.push((addr, data));
@@ -24,6 +24,8 @@ use common::*;
 use devtools::*;
 use miner::Miner;
 use rlp::{Rlp, View};
+use spec::Spec;
+use util::trie::TrieSpec;
 
 #[test]
 fn imports_from_empty() {
@@ -60,6 +62,84 @@ fn should_return_registrar() {
 	assert_eq!(client.additional_params().get("registrar"), Some(&"8e4e9b13d4b45cb0befc93c3061b1408f67316b2".to_owned()));
 }
 
+#[test]
+fn should_list_accounts_in_fat_db() {
+	let dir = RandomTempPath::new();
+	let spec = Spec::new_null();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.trie_spec = TrieSpec::Fat;
+	config.pruning = journaldb::Algorithm::Archive;
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+
+	let mut all = client.list_accounts(BlockID::Latest, None, 10).unwrap();
+	assert_eq!(all.len(), 5);
+
+	let first_two = client.list_accounts(BlockID::Latest, None, 2).unwrap();
+	let rest = client.list_accounts(BlockID::Latest, first_two.last(), 10).unwrap();
+	assert_eq!(first_two.into_iter().chain(rest.into_iter()).collect::<Vec<_>>(), all);
+
+	all.sort();
+	assert_eq!(all[0], Address::from(1));
+	assert_eq!(all[4], "9cce34f7ab185c7aba1b7c8140d620b4bda941d6".into());
+	assert_eq!(client.list_storage(BlockID::Latest, &all[4], None, 10), Some(Vec::new()));
+}
+
+#[test]
+fn should_list_all_accounts_of_populated_fat_db() {
+	let dir = RandomTempPath::new();
+	let accounts: String = (0x100..0x140).map(|i| format!("\"{:040x}\": {{ \"balance\": \"1\", \"nonce\": \"0\" }},\n", i)).collect();
+	let spec_json = String::from_utf8(include_bytes!("../../res/null.json").to_vec()).unwrap()
+		.replace("\"accounts\": {", &format!("\"accounts\": {{\n{}", accounts));
+	let spec = Spec::load(spec_json.as_bytes()).unwrap();
+	let db_config = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+	let mut config = ClientConfig::default();
+	config.trie_spec = TrieSpec::Fat;
+	config.pruning = journaldb::Algorithm::Archive;
+
+	let client = Client::new(
+		config,
+		&spec,
+		dir.as_path(),
+		Arc::new(Miner::with_spec(&spec)),
+		IoChannel::disconnected(),
+		&db_config
+	).unwrap();
+
+	let mut all = client.list_accounts(BlockID::Latest, None, 100).unwrap();
+	assert_eq!(all.len(), 0x40 + 5);
+
+	let mut paged = Vec::new();
+	loop {
+		let page = client.list_accounts(BlockID::Latest, paged.last(), 7).unwrap();
+		if page.is_empty() {
+			break;
+		}
+		paged.extend(page);
+	}
+	assert_eq!(paged, all);
+
+	all.sort();
+	let expected: Vec<Address> = (1..5).chain(0x100..0x140).map(Address::from).chain(Some("9cce34f7ab185c7aba1b7c8140d620b4bda941d6".into())).collect();
+	assert_eq!(all, expected);
+}
+
+#[test]
+fn should_not_list_accounts_without_fat_db() {
+	let client_result = generate_dummy_client(0);
+	let client = client_result.reference();
+
+	assert!(client.list_accounts(BlockID::Latest, None, 10).is_none());
+}
+
 #[test]
 fn returns_state_root_basic() {
 	let client_result = generate_dummy_client(6);
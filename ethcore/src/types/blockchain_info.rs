@@ -31,5 +31,9 @@ pub struct BlockChainInfo {
 	/// Best blockchain block hash.
 	pub best_block_hash: H256,
 	/// Best blockchain block number.
-	pub best_block_number: BlockNumber
+	pub best_block_number: BlockNumber,
+	/// Oldest block of the chain if the chain does not reach back to genesis.
+	pub first_block_hash: Option<H256>,
+	/// Oldest block number of the chain if the chain does not reach back to genesis.
+	pub first_block_number: Option<BlockNumber>,
 }
@@ -33,17 +33,27 @@ pub struct ManifestData {
 	pub block_number: u64,
 	/// Block hash this snapshot was taken at.
 	pub block_hash: H256,
+	/// List of state delta chunk hashes, applied on top of the state chunks.
+	/// Empty for full snapshots.
+	pub delta_hashes: Vec<H256>,
+	/// Hash of the manifest of the full snapshot an incremental snapshot is based on.
+	pub base: Option<H256>,
 }
 
 impl ManifestData {
 	/// Encode the manifest data to rlp.
 	pub fn into_rlp(self) -> Bytes {
-		let mut stream = RlpStream::new_list(5);
+		// full snapshots keep the original five-item encoding.
+		let mut stream = RlpStream::new_list(if self.base.is_some() { 7 } else { 5 });
 		stream.append(&self.state_hashes);
 		stream.append(&self.block_hashes);
 		stream.append(&self.state_root);
 		stream.append(&self.block_number);
 		stream.append(&self.block_hash);
+		if let Some(base) = self.base {
+			stream.append(&self.delta_hashes);
+			stream.append(&base);
+		}
 
 		stream.out()
 	}
@@ -58,12 +68,19 @@ impl ManifestData {
 		let block_number: u64 = try!(decoder.val_at(3));
 		let block_hash: H256 = try!(decoder.val_at(4));
 
+		let (delta_hashes, base) = match decoder.item_count() {
+			7 => (try!(decoder.val_at(5)), Some(try!(decoder.val_at(6)))),
+			_ => (Vec::new(), None),
+		};
+
 		Ok(ManifestData {
 			state_hashes: state_hashes,
 			block_hashes: block_hashes,
 			state_root: state_root,
 			block_number: block_number,
 			block_hash: block_hash,
+			delta_hashes: delta_hashes,
+			base: base,
 		})
 	}
 }
@@ -311,10 +311,75 @@ pub mod ecies {
 	}
 }
 
+/// Document encryption with a random symmetric key sealed to each of the recipients
+pub mod document {
+	use bigint::hash::{FixedHash, H128, H256};
+	use ethkey::Public;
+	use rcrypto::util::fixed_time_eq;
+	use {Error, ecies, aes, derive_mac, Keccak256, KEY_LENGTH, KEY_LENGTH_AES};
+
+	/// Document encrypted with a random document key
+	#[derive(Debug, PartialEq)]
+	pub struct EncryptedDocument {
+		/// Encrypted document: iv, ciphertext and mac
+		pub document: Vec<u8>,
+		/// Document key encrypted with ECIES to each of the recipients, in the same order
+		pub keys: Vec<Vec<u8>>,
+	}
+
+	/// Encrypt a document with a random key and seal the key to each of the recipients
+	pub fn encrypt(recipients: &[Public], plain: &[u8]) -> Result<EncryptedDocument, Error> {
+		let key = H256::random();
+		let mut keys = Vec::with_capacity(recipients.len());
+		for public in recipients {
+			keys.push(try!(ecies::encrypt(public, &[0u8; 0], &key)));
+		}
+
+		Ok(EncryptedDocument {
+			document: try!(encrypt_with_key(&key, plain)),
+			keys: keys,
+		})
+	}
+
+	/// Encrypt a document with a 32-byte document key
+	pub fn encrypt_with_key(key: &[u8], plain: &[u8]) -> Result<Vec<u8>, Error> {
+		if key.len() != KEY_LENGTH {
+			return Err(Error::InvalidMessage);
+		}
+
+		// first half of the key encrypts the document, second half authenticates it
+		let iv = H128::random();
+		let mut encrypted = vec![0u8; 16 + plain.len() + 32];
+		encrypted[0..16].copy_from_slice(&iv);
+		aes::encrypt(&key[0..KEY_LENGTH_AES], &iv, plain, &mut encrypted[16..(16 + plain.len())]);
+		let mac = derive_mac(&key[KEY_LENGTH_AES..KEY_LENGTH], &encrypted[0..(16 + plain.len())]).keccak256();
+		encrypted[(16 + plain.len())..].copy_from_slice(&mac);
+		Ok(encrypted)
+	}
+
+	/// Decrypt a document with a 32-byte document key
+	pub fn decrypt(key: &[u8], encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+		if key.len() != KEY_LENGTH || encrypted.len() < 16 + 32 {
+			return Err(Error::InvalidMessage);
+		}
+
+		let clen = encrypted.len() - 16 - 32;
+		let mac = derive_mac(&key[KEY_LENGTH_AES..KEY_LENGTH], &encrypted[0..(16 + clen)]).keccak256();
+		// compared in constant time, so that the mac can't be guessed byte by byte
+		if !fixed_time_eq(&mac[..], &encrypted[(16 + clen)..]) {
+			return Err(Error::InvalidMessage);
+		}
+
+		let mut plain = vec![0u8; clen];
+		aes::decrypt(&key[0..KEY_LENGTH_AES], &encrypted[0..16], &encrypted[16..(16 + clen)], &mut plain);
+		Ok(plain)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use ethkey::{Random, Generator};
-	use ecies;
+	use {ecies, document};
 
 	#[test]
 	fn ecies_shared() {
@@ -341,5 +406,23 @@ mod tests {
 		let decrypted = ecies::decrypt_single_message(kp.secret(), &encrypted).unwrap();
 		assert_eq!(decrypted[..message.len()], message[..]);
 	}
-}
 
+	#[test]
+	fn document_sealed_to_recipients() {
+		let first = Random.generate().unwrap();
+		let second = Random.generate().unwrap();
+		let message = b"So many books, so little time";
+		let encrypted = document::encrypt(&[first.public().clone(), second.public().clone()], message).unwrap();
+		assert_eq!(encrypted.keys.len(), 2);
+
+		let first_key = ecies::decrypt(first.secret(), &[], &encrypted.keys[0]).unwrap();
+		let second_key = ecies::decrypt(second.secret(), &[], &encrypted.keys[1]).unwrap();
+		assert_eq!(first_key, second_key);
+		assert_eq!(document::decrypt(&first_key, &encrypted.document).unwrap(), message.to_vec());
+
+		let mut tampered = encrypted.document.clone();
+		tampered[0] ^= 1;
+		assert!(document::decrypt(&first_key, &tampered).is_err());
+		assert!(ecies::decrypt(first.secret(), &[], &encrypted.keys[1]).is_err());
+	}
+}
@@ -18,11 +18,12 @@ extern crate docopt;
 extern crate rustc_serialize;
 extern crate ethkey;
 
-use std::{env, fmt, process};
+use std::{env, fmt, fs, io, process};
+use std::io::BufRead;
 use std::num::ParseIntError;
 use docopt::Docopt;
 use rustc_serialize::hex::{FromHex, FromHexError};
-use ethkey::{KeyPair, Random, Brain, Prefix, Error as EthkeyError, Generator, sign, verify_public, verify_address};
+use ethkey::{KeyPair, Random, Brain, Prefix, Mnemonic, Error as EthkeyError, Generator, sign, verify_public, verify_address};
 
 pub const USAGE: &'static str = r#"
 Ethereum keys generator.
@@ -33,6 +34,7 @@ Usage:
     ethkey generate random [options]
     ethkey generate prefix <prefix> <iterations> [options]
     ethkey generate brain <seed> [options]
+    ethkey derive [options]
     ethkey sign <secret> <message>
     ethkey verify public <public> <signature> <message>
     ethkey verify address <address> <signature> <message>
@@ -43,6 +45,13 @@ Options:
     -s, --secret       Display only the secret.
     -p, --public       Display only the public.
     -a, --address      Display only the address.
+    --path PATH        BIP32 derivation path [default: m/44'/60'/0'/0/0].
+    --phrase-file FILE
+                       Read the BIP39 mnemonic phrase from FILE instead of
+                       the first line of stdin.
+    --password-file FILE
+                       Read the BIP39 mnemonic password from FILE instead of
+                       the next line of stdin.
 
 Commands:
     info               Display public and address of the secret.
@@ -50,6 +59,7 @@ Commands:
     random             Random generation.
     prefix             Random generation, but address must start with a prefix
     brain              Generate new key from string seed.
+    derive             Derive key from BIP39 mnemonic phrase and password.
     sign               Sign message using secret.
     verify             Verify signer of the signature.
 "#;
@@ -61,6 +71,7 @@ struct Args {
 	cmd_random: bool,
 	cmd_prefix: bool,
 	cmd_brain: bool,
+	cmd_derive: bool,
 	cmd_sign: bool,
 	cmd_verify: bool,
 	cmd_public: bool,
@@ -76,6 +87,9 @@ struct Args {
 	flag_secret: bool,
 	flag_public: bool,
 	flag_address: bool,
+	flag_path: String,
+	flag_phrase_file: String,
+	flag_password_file: String,
 }
 
 #[derive(Debug)]
@@ -83,6 +97,7 @@ enum Error {
 	Ethkey(EthkeyError),
 	FromHex(FromHexError),
 	ParseInt(ParseIntError),
+	Io(io::Error),
 }
 
 impl From<EthkeyError> for Error {
@@ -103,12 +118,19 @@ impl From<ParseIntError> for Error {
 	}
 }
 
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
 impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
 		match *self {
 			Error::Ethkey(ref e) => write!(f, "{}", e),
 			Error::FromHex(ref e) => write!(f, "{}", e),
 			Error::ParseInt(ref e) => write!(f, "{}", e),
+			Error::Io(ref e) => write!(f, "{}", e),
 		}
 	}
 }
@@ -153,7 +175,30 @@ fn display(keypair: KeyPair, mode: DisplayMode) -> String {
 	}
 }
 
+/// Reads a line without the line terminator.
+fn read_line<R>(input: &mut R) -> Result<String, Error> where R: BufRead {
+	let mut line = String::new();
+	try!(input.read_line(&mut line));
+	Ok(line.trim_right_matches(|c| c == '\n' || c == '\r').to_owned())
+}
+
+/// Reads the first line of the file, or of the input if no file is given.
+fn read_secret<R>(file: &str, input: &mut R) -> Result<String, Error> where R: BufRead {
+	if file.is_empty() {
+		read_line(input)
+	} else {
+		read_line(&mut io::BufReader::new(try!(fs::File::open(file))))
+	}
+}
+
 fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item=S>, S: AsRef<str> {
+	let stdin = io::stdin();
+	let mut input = stdin.lock();
+	execute_with_input(command, &mut input)
+}
+
+/// Secrets which would be visible in the process list are read from the input instead of the command line.
+fn execute_with_input<S, I, R>(command: I, input: &mut R) -> Result<String, Error> where I: IntoIterator<Item=S>, S: AsRef<str>, R: BufRead {
 	let args: Args = Docopt::new(USAGE)
 		.and_then(|d| d.argv(command).decode())
 		.unwrap_or_else(|e| e.exit());
@@ -177,6 +222,13 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 			unreachable!();
 		};
 		Ok(display(try!(keypair), display_mode))
+	} else if args.cmd_derive {
+		let display_mode = DisplayMode::new(&args);
+		let path = try!(args.flag_path.parse());
+		let phrase = try!(read_secret(&args.flag_phrase_file, input));
+		let password = try!(read_secret(&args.flag_password_file, input));
+		let keypair = try!(Mnemonic::new(phrase, password, path).generate());
+		Ok(display(keypair, display_mode))
 	} else if args.cmd_sign {
 		let secret = try!(args.arg_secret.parse().map_err(|_| EthkeyError::InvalidSecret));
 		let message = try!(args.arg_message.parse().map_err(|_| EthkeyError::InvalidMessage));
@@ -202,7 +254,7 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 
 #[cfg(test)]
 mod tests {
-	use super::execute;
+	use super::{execute, execute_with_input};
 
 	#[test]
 	fn info() {
@@ -265,6 +317,42 @@ address: 26d1ec50b4e62c1d1a40d16e7cacc6a6580757d5".to_owned();
 		assert_eq!(execute(command).unwrap(), expected);
 	}
 
+	#[test]
+	fn derive() {
+		let command = vec!["ethkey", "derive", "--secret"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let mut input = &b"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about\n"[..];
+		let expected = "1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727".to_owned();
+		assert_eq!(execute_with_input(command, &mut input).unwrap(), expected);
+	}
+
+	#[test]
+	fn derive_path() {
+		let command = vec!["ethkey", "derive", "--path", "m/44'/60'/0'/0/0", "--secret"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let mut input = &b"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about\n\n"[..];
+		let expected = "1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727".to_owned();
+		assert_eq!(execute_with_input(command, &mut input).unwrap(), expected);
+	}
+
+	#[test]
+	fn derive_password() {
+		let command = vec!["ethkey", "derive", "--secret"]
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<String>>();
+
+		let mut input = &b"abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about\nTREZOR\n"[..];
+		let expected = "1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727".to_owned();
+		assert!(execute_with_input(command, &mut input).unwrap() != expected);
+	}
+
 	#[test]
 	fn sign() {
 		let command = vec!["ethkey", "sign", "17d08f5fe8c77af811caa0c9a187e668ce3b74a99acc3f6d976f075fa8e0be55", "bd50b7370c3f96733b31744c6c45079e7ae6c8d299613246d28ebcef507ec987"]
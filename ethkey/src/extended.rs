@@ -0,0 +1,280 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! BIP32 hierarchical deterministic keys.
+
+use std::fmt;
+use std::str::FromStr;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha512;
+use secp256k1::key;
+use bigint::hash::H256;
+use super::{KeyPair, Error, Secret, Public, SECP256K1};
+
+/// Child indexes starting from this one are hardened.
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// BIP32 derivation path, e.g. `m/44'/60'/0'/0/0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+	/// Path of the n-th address used by Ethereum hardware and mobile wallets: `m/44'/60'/0'/0/n`.
+	pub fn ethereum(index: u32) -> Self {
+		DerivationPath(vec![44 | HARDENED, 60 | HARDENED, HARDENED, 0, index])
+	}
+
+	/// Child indexes, starting from the master key.
+	pub fn indexes(&self) -> &[u32] {
+		&self.0
+	}
+}
+
+impl From<Vec<u32>> for DerivationPath {
+	fn from(indexes: Vec<u32>) -> Self {
+		DerivationPath(indexes)
+	}
+}
+
+impl FromStr for DerivationPath {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.split('/');
+		if parts.next() != Some("m") {
+			return Err(Error::InvalidDerivationPath);
+		}
+		let indexes = try!(parts.map(parse_index).collect::<Result<Vec<_>, _>>());
+		Ok(DerivationPath(indexes))
+	}
+}
+
+impl fmt::Display for DerivationPath {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		try!(write!(f, "m"));
+		for index in &self.0 {
+			if *index >= HARDENED {
+				try!(write!(f, "/{}'", index - HARDENED));
+			} else {
+				try!(write!(f, "/{}", index));
+			}
+		}
+		Ok(())
+	}
+}
+
+fn parse_index(part: &str) -> Result<u32, Error> {
+	let (number, hardened) = if part.ends_with('\'') || part.ends_with('h') || part.ends_with('H') {
+		(&part[..part.len() - 1], true)
+	} else {
+		(part, false)
+	};
+	let index: u32 = try!(number.parse().map_err(|_| Error::InvalidDerivationPath));
+	if index >= HARDENED {
+		return Err(Error::InvalidDerivationPath);
+	}
+	Ok(if hardened { index | HARDENED } else { index })
+}
+
+/// Extended private key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedSecret {
+	secret: Secret,
+	chain_code: H256,
+}
+
+impl ExtendedSecret {
+	/// Creates extended private key from a secret and a chain code.
+	pub fn new(secret: Secret, chain_code: H256) -> Self {
+		ExtendedSecret {
+			secret: secret,
+			chain_code: chain_code,
+		}
+	}
+
+	/// Creates master key from a seed.
+	pub fn with_seed(seed: &[u8]) -> Result<Self, Error> {
+		let (secret, chain_code) = hmac_sha512(b"Bitcoin seed", seed);
+		try!(key::SecretKey::from_slice(&SECP256K1, &secret[..]));
+		Ok(ExtendedSecret::new(secret, chain_code))
+	}
+
+	/// Private key.
+	pub fn secret(&self) -> &Secret {
+		&self.secret
+	}
+
+	/// Chain code.
+	pub fn chain_code(&self) -> &H256 {
+		&self.chain_code
+	}
+
+	/// Extended public key of this key.
+	pub fn public(&self) -> Result<ExtendedPublic, Error> {
+		let keypair = try!(KeyPair::from_secret(self.secret.clone()));
+		Ok(ExtendedPublic::new(keypair.public().clone(), self.chain_code.clone()))
+	}
+
+	/// Derives a child key. Indexes from `HARDENED` up give hardened keys.
+	pub fn derive(&self, index: u32) -> Result<Self, Error> {
+		let context = &SECP256K1;
+		let mut data = Vec::with_capacity(37);
+		if index >= HARDENED {
+			data.push(0u8);
+			data.extend_from_slice(&self.secret[..]);
+		} else {
+			let keypair = try!(KeyPair::from_secret(self.secret.clone()));
+			data.extend_from_slice(&try!(to_secp256k1_public(keypair.public())).serialize_vec(context, true));
+		}
+		data.extend_from_slice(&index_to_bytes(index));
+
+		let (tweak, chain_code) = hmac_sha512(&self.chain_code[..], &data);
+		let tweak = try!(key::SecretKey::from_slice(context, &tweak[..]));
+		let mut child = try!(key::SecretKey::from_slice(context, &self.secret[..]));
+		try!(child.add_assign(context, &tweak));
+
+		let mut secret = Secret::default();
+		secret.copy_from_slice(&child[0..32]);
+		Ok(ExtendedSecret::new(secret, chain_code))
+	}
+
+	/// Derives a key along the path.
+	pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Error> {
+		path.indexes().iter().fold(Ok(self.clone()), |key, index| key.and_then(|key| key.derive(*index)))
+	}
+}
+
+/// Extended public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublic {
+	public: Public,
+	chain_code: H256,
+}
+
+impl ExtendedPublic {
+	/// Creates extended public key from a public key and a chain code.
+	pub fn new(public: Public, chain_code: H256) -> Self {
+		ExtendedPublic {
+			public: public,
+			chain_code: chain_code,
+		}
+	}
+
+	/// Public key.
+	pub fn public(&self) -> &Public {
+		&self.public
+	}
+
+	/// Chain code.
+	pub fn chain_code(&self) -> &H256 {
+		&self.chain_code
+	}
+
+	/// Derives a non-hardened child key. Hardened keys can only be derived from the private key.
+	pub fn derive(&self, index: u32) -> Result<Self, Error> {
+		if index >= HARDENED {
+			return Err(Error::InvalidDerivationPath);
+		}
+		let context = &SECP256K1;
+		let mut child = try!(to_secp256k1_public(&self.public));
+		let mut data = child.serialize_vec(context, true).to_vec();
+		data.extend_from_slice(&index_to_bytes(index));
+
+		let (tweak, chain_code) = hmac_sha512(&self.chain_code[..], &data);
+		let tweak = try!(key::SecretKey::from_slice(context, &tweak[..]));
+		try!(child.add_exp_assign(context, &tweak));
+
+		let serialized = child.serialize_vec(context, false);
+		let mut public = Public::default();
+		public.copy_from_slice(&serialized[1..65]);
+		Ok(ExtendedPublic::new(public, chain_code))
+	}
+
+	/// Derives a key along the path. The path must not contain hardened indexes.
+	pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Error> {
+		path.indexes().iter().fold(Ok(self.clone()), |key, index| key.and_then(|key| key.derive(*index)))
+	}
+}
+
+fn to_secp256k1_public(public: &Public) -> Result<key::PublicKey, Error> {
+	let mut serialized = [4u8; 65];
+	serialized[1..65].copy_from_slice(&public[..]);
+	Ok(try!(key::PublicKey::from_slice(&SECP256K1, &serialized)))
+}
+
+fn index_to_bytes(index: u32) -> [u8; 4] {
+	[(index >> 24) as u8, (index >> 16) as u8, (index >> 8) as u8, index as u8]
+}
+
+/// Splits HMAC-SHA512 of the data into two halves.
+fn hmac_sha512(key: &[u8], data: &[u8]) -> (H256, H256) {
+	let mut hmac = Hmac::new(Sha512::new(), key);
+	hmac.input(data);
+	let mut result = [0u8; 64];
+	hmac.raw_result(&mut result);
+	(H256::from_slice(&result[0..32]), H256::from_slice(&result[32..64]))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::FromHex;
+	use bigint::hash::H256;
+	use {KeyPair, Secret};
+	use super::{ExtendedSecret, DerivationPath, HARDENED};
+
+	fn master() -> ExtendedSecret {
+		ExtendedSecret::with_seed(&"000102030405060708090a0b0c0d0e0f".from_hex().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn parses_derivation_path() {
+		let path: DerivationPath = "m/44'/60'/0'/0/1".parse().unwrap();
+		assert_eq!(path, DerivationPath::ethereum(1));
+		assert_eq!(path.indexes(), &[44 | HARDENED, 60 | HARDENED, HARDENED, 0, 1]);
+		assert_eq!(path.to_string(), "m/44'/60'/0'/0/1");
+		assert_eq!("m".parse::<DerivationPath>().unwrap().indexes().len(), 0);
+		assert!("44'/60'".parse::<DerivationPath>().is_err());
+		assert!("m/x".parse::<DerivationPath>().is_err());
+		assert!("m/2147483648".parse::<DerivationPath>().is_err());
+	}
+
+	#[test]
+	fn derives_bip32_test_vector() {
+		let master = master();
+		assert_eq!(master.secret(), &Secret::from_str("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35").unwrap());
+		assert_eq!(master.chain_code(), &H256::from_str("873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508").unwrap());
+
+		let child = master.derive(HARDENED).unwrap();
+		assert_eq!(child.secret(), &Secret::from_str("edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea").unwrap());
+		assert_eq!(child.chain_code(), &H256::from_str("47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141").unwrap());
+
+		let grandchild = master.derive_path(&"m/0'/1".parse().unwrap()).unwrap();
+		assert_eq!(grandchild.secret(), &Secret::from_str("3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368").unwrap());
+		assert_eq!(grandchild.chain_code(), &H256::from_str("2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19").unwrap());
+	}
+
+	#[test]
+	fn public_derivation_matches_private() {
+		let parent = master().derive(HARDENED).unwrap();
+		let from_secret = parent.derive(1).unwrap();
+		let from_public = parent.public().unwrap().derive(1).unwrap();
+		assert_eq!(from_public.public(), KeyPair::from_secret(from_secret.secret().clone()).unwrap().public());
+		assert_eq!(from_public.chain_code(), from_secret.chain_code());
+		assert!(parent.public().unwrap().derive(HARDENED).is_err());
+	}
+}
@@ -21,11 +21,15 @@ extern crate tiny_keccak;
 extern crate secp256k1;
 extern crate rustc_serialize;
 extern crate ethcore_bigint as bigint;
+extern crate crypto;
+extern crate unicode_normalization;
 
 mod brain;
 mod error;
+mod extended;
 mod keypair;
 mod keccak;
+mod mnemonic;
 mod prefix;
 mod random;
 mod signature;
@@ -42,7 +46,9 @@ pub trait Generator {
 
 pub use self::brain::Brain;
 pub use self::error::Error;
+pub use self::extended::{ExtendedSecret, ExtendedPublic, DerivationPath, HARDENED};
 pub use self::keypair::{KeyPair, public_to_address};
+pub use self::mnemonic::{Mnemonic, mnemonic_to_seed};
 pub use self::prefix::Prefix;
 pub use self::random::Random;
 pub use self::signature::{sign, verify_public, verify_address, recover, Signature};
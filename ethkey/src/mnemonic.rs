@@ -0,0 +1,144 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::sha2::{Sha256, Sha512};
+use crypto::pbkdf2::pbkdf2;
+use unicode_normalization::UnicodeNormalization;
+use super::{KeyPair, Error, Generator, ExtendedSecret, DerivationPath};
+
+lazy_static! {
+	// BIP39 English word list with the index of each word.
+	static ref WORDS: HashMap<&'static str, usize> = include_str!("../res/bip39_english.txt")
+		.lines()
+		.enumerate()
+		.map(|(i, word)| (word, i))
+		.collect();
+}
+
+/// Generates a key from a BIP39 mnemonic phrase and a BIP32 derivation path,
+/// the way hardware and mobile wallets do.
+pub struct Mnemonic {
+	phrase: String,
+	password: String,
+	path: DerivationPath,
+}
+
+impl Mnemonic {
+	pub fn new(phrase: String, password: String, path: DerivationPath) -> Self {
+		Mnemonic {
+			phrase: phrase,
+			password: password,
+			path: path,
+		}
+	}
+}
+
+impl Generator for Mnemonic {
+	fn generate(self) -> Result<KeyPair, Error> {
+		let seed = try!(mnemonic_to_seed(&self.phrase, &self.password));
+		let key = try!(try!(ExtendedSecret::with_seed(&seed)).derive_path(&self.path));
+		KeyPair::from_secret(key.secret().clone())
+	}
+}
+
+/// Returns BIP39 seed of the mnemonic phrase. The phrase and the password are NFKD normalized.
+/// Fails if the phrase has words missing from the English word list or an invalid checksum.
+pub fn mnemonic_to_seed(phrase: &str, password: &str) -> Result<[u8; 64], Error> {
+	let phrase = phrase.nfkd().collect::<String>();
+	let words = phrase.split_whitespace().collect::<Vec<_>>();
+	try!(check_phrase(&words));
+	let phrase = words.join(" ");
+	let salt = format!("mnemonic{}", password).nfkd().collect::<String>();
+	let mut hmac = Hmac::new(Sha512::new(), phrase.as_bytes());
+	let mut seed = [0u8; 64];
+	pbkdf2(&mut hmac, salt.as_bytes(), 2048, &mut seed);
+	Ok(seed)
+}
+
+/// Checks that all words are in the word list and that the checksum bits match the entropy.
+/// Each word encodes 11 bits, one in every 33 bits of the phrase is a checksum bit.
+fn check_phrase(words: &[&str]) -> Result<(), Error> {
+	if words.len() < 12 || words.len() > 24 || words.len() % 3 != 0 {
+		return Err(Error::InvalidMnemonic);
+	}
+	let mut bits = Vec::with_capacity(words.len() * 11);
+	for word in words {
+		let index = try!(WORDS.get(*word).ok_or(Error::InvalidMnemonic));
+		for i in (0..11).rev() {
+			bits.push((index >> i) & 1 == 1);
+		}
+	}
+	let checksum_len = words.len() / 3;
+	let entropy_len = bits.len() - checksum_len;
+	let entropy = bits[..entropy_len].chunks(8)
+		.map(|byte| byte.iter().fold(0u8, |acc, bit| (acc << 1) | *bit as u8))
+		.collect::<Vec<_>>();
+	let mut hash = [0u8; 32];
+	let mut sha256 = Sha256::new();
+	sha256.input(&entropy);
+	sha256.result(&mut hash);
+	let checksum_valid = (0..checksum_len).all(|i| bits[entropy_len + i] == ((hash[i / 8] >> (7 - i % 8)) & 1 == 1));
+	if checksum_valid {
+		Ok(())
+	} else {
+		Err(Error::InvalidMnemonic)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+	use rustc_serialize::hex::ToHex;
+	use {Mnemonic, Generator, DerivationPath, Secret};
+	use super::mnemonic_to_seed;
+
+	const PHRASE: &'static str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+	#[test]
+	fn mnemonic_seed() {
+		let seed = mnemonic_to_seed(PHRASE, "TREZOR").unwrap();
+		assert_eq!(seed.to_hex(), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+		let seed = mnemonic_to_seed("legal winner thank year wave sausage worth useful legal winner thank year wave sausage worth useful legal will", "TREZOR").unwrap();
+		assert_eq!(seed.to_hex(), "f2b94508732bcbacbcc020faefecfc89feafa6649a5491b8c952cede496c214a0c7b3c392d168748f2d4a612bada0753b52a1c7ac53c1e93abd5c6320b9e95dd");
+	}
+
+	#[test]
+	fn mnemonic_invalid() {
+		// unknown word
+		assert!(mnemonic_to_seed("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon parity", "").is_err());
+		// invalid checksum
+		assert!(mnemonic_to_seed("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon", "").is_err());
+		// invalid length
+		assert!(mnemonic_to_seed("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about", "").is_err());
+	}
+
+	#[test]
+	fn mnemonic_normalized() {
+		// precomposed and decomposed forms of the same password give the same seed
+		assert_eq!(mnemonic_to_seed(PHRASE, "caf\u{e9}").unwrap()[..], mnemonic_to_seed(PHRASE, "cafe\u{301}").unwrap()[..]);
+		// extra whitespace is ignored
+		assert_eq!(mnemonic_to_seed(&format!("  {}  ", PHRASE.replace(" ", "\t ")), "").unwrap()[..], mnemonic_to_seed(PHRASE, "").unwrap()[..]);
+	}
+
+	#[test]
+	fn mnemonic_ethereum_key() {
+		let keypair = Mnemonic::new(PHRASE.to_owned(), String::new(), DerivationPath::ethereum(0)).generate().unwrap();
+		assert_eq!(keypair.secret(), &Secret::from_str("1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727").unwrap());
+	}
+}
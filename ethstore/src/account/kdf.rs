@@ -38,6 +38,42 @@ pub struct Scrypt {
 	pub salt: [u8; 32],
 }
 
+/// Parameters of the scrypt key derivation function used to encrypt new data.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ScryptParams {
+	/// CPU/memory cost, has to be a power of 2.
+	pub n: u32,
+	/// Parallelization.
+	pub p: u32,
+	/// Block size.
+	pub r: u32,
+}
+
+impl Default for ScryptParams {
+	fn default() -> Self {
+		ScryptParams {
+			n: 262144,
+			p: 1,
+			r: 8,
+		}
+	}
+}
+
+/// Key derivation function and its parameters used to encrypt new data.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KdfParams {
+	/// PBKDF2 with given number of iterations.
+	Pbkdf2(u32),
+	/// Scrypt with given parameters.
+	Scrypt(ScryptParams),
+}
+
+impl Default for KdfParams {
+	fn default() -> Self {
+		KdfParams::Scrypt(ScryptParams::default())
+	}
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Kdf {
 	Pbkdf2(Pbkdf2),
@@ -14,11 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethkey::{KeyPair, sign, Address, Secret, Signature, Message};
+use ethkey::{KeyPair, sign, Address, Secret, Public, Signature, Message};
 use {json, Error, crypto};
 use crypto::Keccak256;
 use random::Random;
-use account::{Version, Cipher, Kdf, Aes128Ctr, Pbkdf2, Prf};
+use account::{Version, Cipher, Kdf, KdfParams, Aes128Ctr, Pbkdf2, Prf, Scrypt, ScryptParams};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Crypto {
@@ -75,33 +75,65 @@ impl Into<json::KeyFile> for SafeAccount {
 }
 
 impl Crypto {
-	pub fn create(secret: &Secret, password: &str, iterations: u32) -> Self {
+	pub fn create(secret: &Secret, password: &str, kdf: &KdfParams) -> Self {
+		Crypto::encrypt(&**secret, password, kdf)
+	}
+
+	/// Encrypts arbitrary data with a key derived from the password using given key derivation function.
+	pub fn encrypt(plain: &[u8], password: &str, kdf: &KdfParams) -> Self {
+		match *kdf {
+			KdfParams::Pbkdf2(iterations) => Crypto::encrypt_with_pbkdf2(plain, password, iterations),
+			KdfParams::Scrypt(ref params) => Crypto::encrypt_with_scrypt(plain, password, params),
+		}
+	}
+
+	/// Encrypts arbitrary data with a key derived from the password using PBKDF2.
+	pub fn encrypt_with_pbkdf2(plain: &[u8], password: &str, iterations: u32) -> Self {
 		let salt: [u8; 32] = Random::random();
-		let iv: [u8; 16] = Random::random();
 
 		// two parts of derived key
 		// DK = [ DK[0..15] DK[16..31] ] = [derived_left_bits, derived_right_bits]
 		let (derived_left_bits, derived_right_bits) = crypto::derive_key_iterations(password, &salt, iterations);
 
-		let mut ciphertext = [0u8; 32];
+		Crypto::encrypt_with_key(plain, &derived_left_bits, &derived_right_bits, Kdf::Pbkdf2(Pbkdf2 {
+			dklen: crypto::KEY_LENGTH as u32,
+			salt: salt,
+			c: iterations,
+			prf: Prf::HmacSha256,
+		}))
+	}
+
+	/// Encrypts arbitrary data with a key derived from the password using scrypt.
+	pub fn encrypt_with_scrypt(plain: &[u8], password: &str, params: &ScryptParams) -> Self {
+		let salt: [u8; 32] = Random::random();
+		let (derived_left_bits, derived_right_bits) = crypto::derive_key_scrypt(password, &salt, params.n, params.p, params.r);
+
+		Crypto::encrypt_with_key(plain, &derived_left_bits, &derived_right_bits, Kdf::Scrypt(Scrypt {
+			dklen: crypto::KEY_LENGTH as u32,
+			salt: salt,
+			n: params.n,
+			p: params.p,
+			r: params.r,
+		}))
+	}
+
+	fn encrypt_with_key(plain: &[u8], derived_left_bits: &[u8], derived_right_bits: &[u8], kdf: Kdf) -> Self {
+		let iv: [u8; 16] = Random::random();
+
+		let mut ciphertext = vec![0u8; plain.len()];
 
 		// aes-128-ctr with initial vector of iv
-		crypto::aes::encrypt(&derived_left_bits, &iv, &**secret, &mut ciphertext);
+		crypto::aes::encrypt(derived_left_bits, &iv, plain, &mut ciphertext);
 
 		// KECCAK(DK[16..31] ++ <ciphertext>), where DK[16..31] - derived_right_bits
-		let mac = crypto::derive_mac(&derived_right_bits, &ciphertext).keccak256();
+		let mac = crypto::derive_mac(derived_right_bits, &ciphertext).keccak256();
 
 		Crypto {
 			cipher: Cipher::Aes128Ctr(Aes128Ctr {
 				iv: iv,
 			}),
-			ciphertext: ciphertext.to_vec(),
-			kdf: Kdf::Pbkdf2(Pbkdf2 {
-				dklen: crypto::KEY_LENGTH as u32,
-				salt: salt,
-				c: iterations,
-				prf: Prf::HmacSha256,
-			}),
+			ciphertext: ciphertext,
+			kdf: kdf,
 			mac: mac,
 		}
 	}
@@ -111,6 +143,15 @@ impl Crypto {
 			return Err(Error::InvalidSecret);
 		}
 
+		let plain = try!(self.decrypt(password));
+		let mut secret = Secret::default();
+		let from = 32 - plain.len();
+		(&mut *secret)[from..].copy_from_slice(&plain);
+		Ok(secret)
+	}
+
+	/// Decrypts the data, checking the password against the MAC first.
+	pub fn decrypt(&self, password: &str) -> Result<Vec<u8>, Error> {
 		let (derived_left_bits, derived_right_bits) = match self.kdf {
 			Kdf::Pbkdf2(ref params) => crypto::derive_key_iterations(password, &params.salt, params.c),
 			Kdf::Scrypt(ref params) => crypto::derive_key_scrypt(password, &params.salt, params.n, params.p, params.r),
@@ -122,16 +163,15 @@ impl Crypto {
 			return Err(Error::InvalidPassword);
 		}
 
-		let mut secret = Secret::default();
+		let mut plain = vec![0u8; self.ciphertext.len()];
 
 		match self.cipher {
 			Cipher::Aes128Ctr(ref params) => {
-				let from = 32 - self.ciphertext.len();
-				crypto::aes::decrypt(&derived_left_bits, &params.iv, &self.ciphertext, &mut (&mut *secret)[from..])
+				crypto::aes::decrypt(&derived_left_bits, &params.iv, &self.ciphertext, &mut plain)
 			},
 		}
 
-		Ok(secret)
+		Ok(plain)
 	}
 }
 
@@ -140,14 +180,14 @@ impl SafeAccount {
 		keypair: &KeyPair,
 		id: [u8; 16],
 		password: &str,
-		iterations: u32,
+		kdf: &KdfParams,
 		name: String,
 		meta: String
 	) -> Self {
 		SafeAccount {
 			id: id,
 			version: Version::V3,
-			crypto: Crypto::create(keypair.secret(), password, iterations),
+			crypto: Crypto::create(keypair.secret(), password, kdf),
 			address: keypair.address(),
 			filename: None,
 			name: name,
@@ -180,12 +220,17 @@ impl SafeAccount {
 		crypto::ecies::decrypt(&secret, shared_mac, message).map_err(From::from)
 	}
 
-	pub fn change_password(&self, old_password: &str, new_password: &str, iterations: u32) -> Result<Self, Error> {
+	pub fn public(&self, password: &str) -> Result<Public, Error> {
+		let secret = try!(self.crypto.secret(password));
+		Ok(try!(KeyPair::from_secret(secret)).public().clone())
+	}
+
+	pub fn change_password(&self, old_password: &str, new_password: &str, kdf: &KdfParams) -> Result<Self, Error> {
 		let secret = try!(self.crypto.secret(old_password));
 		let result = SafeAccount {
 			id: self.id.clone(),
 			version: self.version.clone(),
-			crypto: Crypto::create(&secret, new_password, iterations),
+			crypto: Crypto::create(&secret, new_password, kdf),
 			address: self.address.clone(),
 			filename: self.filename.clone(),
 			name: self.name.clone(),
@@ -202,43 +247,68 @@ impl SafeAccount {
 #[cfg(test)]
 mod tests {
 	use ethkey::{Generator, Random, verify_public, Message};
+	use account::{KdfParams, ScryptParams};
 	use super::{Crypto, SafeAccount};
 
 	#[test]
 	fn crypto_create() {
 		let keypair = Random.generate().unwrap();
-		let crypto = Crypto::create(keypair.secret(), "this is sparta", 10240);
+		let crypto = Crypto::create(keypair.secret(), "this is sparta", &KdfParams::Pbkdf2(10240));
+		let secret = crypto.secret("this is sparta").unwrap();
+		assert_eq!(keypair.secret(), &secret);
+	}
+
+	#[test]
+	fn crypto_create_with_scrypt() {
+		let keypair = Random.generate().unwrap();
+		let crypto = Crypto::create(keypair.secret(), "this is sparta", &KdfParams::Scrypt(ScryptParams { n: 1024, p: 1, r: 8 }));
 		let secret = crypto.secret("this is sparta").unwrap();
 		assert_eq!(keypair.secret(), &secret);
+		assert!(crypto.secret("this is sparta!").is_err());
 	}
 
 	#[test]
 	#[should_panic]
 	fn crypto_invalid_password() {
 		let keypair = Random.generate().unwrap();
-		let crypto = Crypto::create(keypair.secret(), "this is sparta", 10240);
+		let crypto = Crypto::create(keypair.secret(), "this is sparta", &KdfParams::Pbkdf2(10240));
 		let _ = crypto.secret("this is sparta!").unwrap();
 	}
 
+	#[test]
+	fn crypto_encrypt_data() {
+		let crypto = Crypto::encrypt(b"vault metadata of arbitrary length", "this is sparta", &KdfParams::Pbkdf2(10240));
+		assert_eq!(crypto.decrypt("this is sparta").unwrap(), b"vault metadata of arbitrary length".to_vec());
+		assert!(crypto.decrypt("this is sparta!").is_err());
+	}
+
 	#[test]
 	fn sign_and_verify_public() {
 		let keypair = Random.generate().unwrap();
 		let password = "hello world";
 		let message = Message::default();
-		let account = SafeAccount::create(&keypair, [0u8; 16], password, 10240, "Test".to_owned(), "{}".to_owned());
+		let account = SafeAccount::create(&keypair, [0u8; 16], password, &KdfParams::Pbkdf2(10240), "Test".to_owned(), "{}".to_owned());
 		let signature = account.sign(password, &message).unwrap();
 		assert!(verify_public(keypair.public(), &signature, &message).unwrap());
 	}
 
+	#[test]
+	fn derive_public() {
+		let keypair = Random.generate().unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], "hello world", &KdfParams::Pbkdf2(1024), "Test".to_owned(), "{}".to_owned());
+		assert_eq!(&account.public("hello world").unwrap(), keypair.public());
+		assert!(account.public("this is sparta").is_err());
+	}
+
 	#[test]
 	fn change_password() {
 		let keypair = Random.generate().unwrap();
 		let first_password = "hello world";
 		let sec_password = "this is sparta";
-		let i = 10240;
+		let kdf = KdfParams::Pbkdf2(10240);
 		let message = Message::default();
-		let account = SafeAccount::create(&keypair, [0u8; 16], first_password, i, "Test".to_owned(), "{}".to_owned());
-		let new_account = account.change_password(first_password, sec_password, i).unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], first_password, &kdf, "Test".to_owned(), "{}".to_owned());
+		let new_account = account.change_password(first_password, sec_password, &kdf).unwrap();
 		assert!(account.sign(first_password, &message).is_ok());
 		assert!(account.sign(sec_password, &message).is_err());
 		assert!(new_account.sign(first_password, &message).is_err());
@@ -0,0 +1,135 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Password-protected backups of a keystore.
+
+use std::collections::BTreeMap;
+use serde_json;
+use ethkey::Address;
+use dir::KeyDirectory;
+use account::{Crypto, ScryptParams};
+use {json, SafeAccount, Error};
+
+/// Accounts and other files of a keystore, which can be encrypted with a backup password.
+#[derive(Debug, PartialEq)]
+pub struct Backup {
+	/// Accounts, still encrypted with their own passwords.
+	pub accounts: Vec<SafeAccount>,
+	/// Other files of the keystore, by path relative to the keys directory.
+	pub files: BTreeMap<String, Vec<u8>>,
+}
+
+impl Backup {
+	/// Creates backup of all accounts in given directory.
+	pub fn of(dir: &KeyDirectory) -> Result<Self, Error> {
+		Ok(Backup {
+			accounts: try!(dir.load()),
+			files: BTreeMap::new(),
+		})
+	}
+
+	/// Encrypts the backup with a key derived from the password using scrypt.
+	pub fn encrypt(&self, password: &str, params: &ScryptParams) -> Result<Vec<u8>, Error> {
+		let backup = json::Backup {
+			accounts: self.accounts.iter().cloned().map(Into::into).collect(),
+			files: self.files.iter().map(|(path, content)| (path.clone(), content.clone().into())).collect(),
+		};
+		let plain = try!(serde_json::to_vec(&backup).map_err(|e| Error::Custom(format!("{:?}", e))));
+		let crypto: json::Crypto = Crypto::encrypt_with_scrypt(&plain, password, params).into();
+		serde_json::to_vec(&crypto).map_err(|e| Error::Custom(format!("{:?}", e)))
+	}
+
+	/// Decrypts backup created with `encrypt`.
+	pub fn decrypt(data: &[u8], password: &str) -> Result<Self, Error> {
+		let crypto: json::Crypto = try!(serde_json::from_slice(data).map_err(|e| Error::Custom(format!("Invalid backup file: {}", e))));
+		let plain = try!(Crypto::from(crypto).decrypt(password));
+		let backup: json::Backup = try!(serde_json::from_slice(&plain).map_err(|e| Error::Custom(format!("Invalid backup file: {}", e))));
+		Ok(Backup {
+			accounts: backup.accounts.into_iter().map(|keyfile| SafeAccount::from_file(keyfile, None)).collect(),
+			files: backup.files.into_iter().map(|(path, content)| (path, content.into())).collect(),
+		})
+	}
+
+	/// Inserts accounts missing in given directory. Returns addresses of the inserted accounts.
+	pub fn restore_accounts(&self, dst: &KeyDirectory) -> Result<Vec<Address>, Error> {
+		let existing = try!(dst.load()).into_iter().map(|account| account.address).collect::<Vec<_>>();
+		self.accounts.iter()
+			.filter(|account| !existing.contains(&account.address))
+			.map(|account| {
+				try!(dst.insert(account.clone()));
+				Ok(account.address.clone())
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use std::sync::RwLock;
+	use ethkey::{Address, Random, Generator};
+	use dir::KeyDirectory;
+	use account::{SafeAccount, KdfParams, ScryptParams};
+	use super::Backup;
+	use Error;
+
+	#[derive(Default)]
+	struct MemoryDirectory {
+		accounts: RwLock<HashMap<Address, SafeAccount>>,
+	}
+
+	impl KeyDirectory for MemoryDirectory {
+		fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+			Ok(self.accounts.read().unwrap().values().cloned().collect())
+		}
+
+		fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+			self.accounts.write().unwrap().insert(account.address.clone(), account.clone());
+			Ok(account)
+		}
+
+		fn remove(&self, address: &Address) -> Result<(), Error> {
+			self.accounts.write().unwrap().remove(address);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_restore_encrypted_backup() {
+		// given
+		let params = ScryptParams { n: 1024, p: 1, r: 8 };
+		let src = MemoryDirectory::default();
+		let keypair = Random.generate().unwrap();
+		src.insert(SafeAccount::create(&keypair, [1u8; 16], "account password", &KdfParams::Pbkdf2(1024), "Test".into(), "{\"a\":1}".into())).unwrap();
+		let mut backup = Backup::of(&src).unwrap();
+		backup.files.insert("address_book.json".into(), b"{}".to_vec());
+
+		// when
+		let data = backup.encrypt("backup password", &params).unwrap();
+		let dst = MemoryDirectory::default();
+		let restored = Backup::decrypt(&data, "backup password").unwrap();
+
+		// then
+		assert!(Backup::decrypt(&data, "wrong password").is_err());
+		assert_eq!(restored.files, backup.files);
+		assert_eq!(restored.restore_accounts(&dst).unwrap(), vec![keypair.address()]);
+		assert_eq!(restored.restore_accounts(&dst).unwrap(), vec![]);
+		let accounts = dst.load().unwrap();
+		assert_eq!(accounts[0].name, "Test");
+		assert_eq!(accounts[0].meta, "{\"a\":1}");
+		assert!(accounts[0].check_password("account password"));
+	}
+}
@@ -23,18 +23,19 @@ use std::io::Read;
 use docopt::Docopt;
 use ethstore::ethkey::Address;
 use ethstore::dir::{KeyDirectory, ParityDirectory, DiskDirectory, GethDirectory, DirectoryType};
-use ethstore::{EthStore, SecretStore, import_accounts, Error, PresaleWallet};
+use ethstore::{EthStore, SecretStore, KdfParams, ScryptParams, import_accounts, Error, PresaleWallet};
 
 pub const USAGE: &'static str = r#"
 Ethereum key management.
   Copyright 2016 Ethcore (UK) Limited
 
 Usage:
-    ethstore insert <secret> <password> [--dir DIR]
-    ethstore change-pwd <address> <old-pwd> <new-pwd> [--dir DIR]
+    ethstore insert <secret> <password> [options]
+    ethstore change-pwd <address> <old-pwd> <new-pwd> [options]
+    ethstore upgrade-kdf <address> <password> [options]
     ethstore list [--dir DIR]
     ethstore import [--src DIR] [--dir DIR]
-    ethstore import-wallet <path> <password> [--dir DIR]
+    ethstore import-wallet <path> <password> [options]
     ethstore remove <address> <password> [--dir DIR]
     ethstore sign <address> <password> <message> [--dir DIR]
     ethstore [-h | --help]
@@ -47,10 +48,17 @@ Options:
     --src DIR          Specify import source. It may be either
                        parity, parity-test, get, geth-test
                        or a path [default: geth].
+    --kdf KDF          Key derivation function used to encrypt new keys.
+                       It may be either scrypt or pbkdf2 [default: scrypt].
+    --iterations N     Number of PBKDF2 iterations [default: 10240].
+    --scrypt-n N       Scrypt CPU/memory cost, a power of 2 [default: 262144].
+    --scrypt-r N       Scrypt block size [default: 8].
+    --scrypt-p N       Scrypt parallelization [default: 1].
 
 Commands:
     insert             Save account with password.
     change-pwd         Change password.
+    upgrade-kdf        Re-encrypt account with given key derivation function.
     list               List accounts.
     import             Import accounts from src.
     import-wallet      Import presale wallet.
@@ -62,6 +70,7 @@ Commands:
 struct Args {
 	cmd_insert: bool,
 	cmd_change_pwd: bool,
+	cmd_upgrade_kdf: bool,
 	cmd_list: bool,
 	cmd_import: bool,
 	cmd_import_wallet: bool,
@@ -76,6 +85,11 @@ struct Args {
 	arg_path: String,
 	flag_src: String,
 	flag_dir: String,
+	flag_kdf: String,
+	flag_iterations: u32,
+	flag_scrypt_n: u32,
+	flag_scrypt_r: u32,
+	flag_scrypt_p: u32,
 }
 
 fn main() {
@@ -100,6 +114,35 @@ fn key_dir(location: &str) -> Result<Box<KeyDirectory>, Error> {
 	Ok(dir)
 }
 
+/// Default of `--iterations` in the usage above.
+const DEFAULT_ITERATIONS: u32 = 10240;
+
+fn kdf_params(args: &Args) -> Result<KdfParams, Error> {
+	let default_scrypt = ScryptParams::default();
+	let scrypt_changed = args.flag_scrypt_n != default_scrypt.n ||
+		args.flag_scrypt_p != default_scrypt.p ||
+		args.flag_scrypt_r != default_scrypt.r;
+
+	match args.flag_kdf.as_ref() {
+		"pbkdf2" if scrypt_changed => Err(Error::Custom("Scrypt parameters require --kdf scrypt.".into())),
+		"pbkdf2" => Ok(KdfParams::Pbkdf2(args.flag_iterations)),
+		"scrypt" if args.flag_iterations != DEFAULT_ITERATIONS =>
+			Err(Error::Custom("--iterations only applies to --kdf pbkdf2.".into())),
+		"scrypt" => {
+			let params = ScryptParams {
+				n: args.flag_scrypt_n,
+				p: args.flag_scrypt_p,
+				r: args.flag_scrypt_r,
+			};
+			if params.n < 2 || !params.n.is_power_of_two() || params.p == 0 || params.r == 0 {
+				return Err(Error::Custom("Invalid scrypt parameters.".into()));
+			}
+			Ok(KdfParams::Scrypt(params))
+		},
+		other => Err(Error::Custom(format!("Unknown key derivation function: {}", other))),
+	}
+}
+
 fn format_accounts(accounts: &[Address]) -> String {
 	accounts.iter()
 		.enumerate()
@@ -122,7 +165,7 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 		.and_then(|d| d.argv(command).decode())
 		.unwrap_or_else(|e| e.exit());
 
-	let store = try!(EthStore::open(try!(key_dir(&args.flag_dir))));
+	let store = try!(EthStore::open_with_kdf(try!(key_dir(&args.flag_dir)), try!(kdf_params(&args))));
 
 	return if args.cmd_insert {
 		let secret = try!(args.arg_secret.parse().map_err(|_| Error::InvalidSecret));
@@ -135,6 +178,11 @@ fn execute<S, I>(command: I) -> Result<String, Error> where I: IntoIterator<Item
 		let new_pwd = try!(load_password(&args.arg_new_pwd));
 		let ok = store.change_password(&address, &old_pwd, &new_pwd).is_ok();
 		Ok(format!("{}", ok))
+	} else if args.cmd_upgrade_kdf {
+		let address = try!(args.arg_address.parse().map_err(|_| Error::InvalidAccount));
+		let password = try!(load_password(&args.arg_password));
+		let ok = store.upgrade_kdf(&address, &password).is_ok();
+		Ok(format!("{}", ok))
 	} else if args.cmd_list {
 		let accounts = try!(store.accounts());
 		Ok(format_accounts(&accounts))
@@ -22,10 +22,10 @@ use ethkey::Address;
 use {json, SafeAccount, Error};
 use super::KeyDirectory;
 
-const IGNORED_FILES: &'static [&'static str] = &["thumbs.db", "address_book.json"];
+const IGNORED_FILES: &'static [&'static str] = &["thumbs.db", "address_book.json", "signing_spending.json"];
 
 #[cfg(not(windows))]
-fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
+pub fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
 	use std::ffi;
 	use libc;
 	let cstr = ffi::CString::new(file_path.to_str().unwrap()).unwrap();
@@ -36,10 +36,15 @@ fn restrict_permissions_to_owner(file_path: &Path) -> Result<(), i32>  {
 }
 
 #[cfg(windows)]
-fn restrict_permissions_to_owner(_file_path: &Path) -> Result<(), i32> {
+pub fn restrict_permissions_to_owner(_file_path: &Path) -> Result<(), i32> {
 	Ok(())
 }
 
+/// Path of the hidden file a key file is written to before it replaces the original.
+pub fn temp_path(dir: &Path, filename: &str) -> PathBuf {
+	dir.join(format!(".{}.tmp", filename))
+}
+
 pub struct DiskDirectory {
 	path: PathBuf,
 }
@@ -123,14 +128,19 @@ impl KeyDirectory for DiskDirectory {
 			let mut keyfile_path = self.path.clone();
 			keyfile_path.push(filename.as_str());
 
-			// save the file
-			let mut file = try!(fs::File::create(&keyfile_path));
-			try!(keyfile.write(&mut file).map_err(|e| Error::Custom(format!("{:?}", e))));
+			// save to a hidden file first, so that an existing key is never left half-written
+			let temp_path = temp_path(&self.path, &filename);
+			{
+				let mut file = try!(fs::File::create(&temp_path));
+				try!(keyfile.write(&mut file).map_err(|e| Error::Custom(format!("{:?}", e))));
+			}
 
-			if let Err(_) = restrict_permissions_to_owner(keyfile_path.as_path()) {
-				fs::remove_file(keyfile_path).expect("Expected to remove recently created file");
+			if let Err(_) = restrict_permissions_to_owner(temp_path.as_path()) {
+				fs::remove_file(temp_path).expect("Expected to remove recently created file");
 				return Err(Error::Io(io::Error::last_os_error()));
 			}
+
+			try!(fs::rename(&temp_path, &keyfile_path));
 		}
 
 		Ok(account)
@@ -157,9 +167,10 @@ impl KeyDirectory for DiskDirectory {
 #[cfg(test)]
 mod test {
 	use std::{env, fs};
+	use devtools::RandomTempPath;
 	use super::DiskDirectory;
 	use dir::KeyDirectory;
-	use account::SafeAccount;
+	use account::{SafeAccount, KdfParams};
 	use ethkey::{Random, Generator};
 
 	#[test]
@@ -171,7 +182,7 @@ mod test {
 		let directory = DiskDirectory::create(dir.clone()).unwrap();
 
 		// when
-		let account = SafeAccount::create(&keypair, [0u8; 16], password, 1024, "Test".to_owned(), "{}".to_owned());
+		let account = SafeAccount::create(&keypair, [0u8; 16], password, &KdfParams::Pbkdf2(1024), "Test".to_owned(), "{}".to_owned());
 		let res = directory.insert(account);
 
 
@@ -182,4 +193,24 @@ mod test {
 		// cleanup
 		let _ = fs::remove_dir_all(dir);
 	}
+
+	#[test]
+	fn should_replace_existing_key_file() {
+		// given
+		let temp = RandomTempPath::create_dir();
+		let keypair = Random.generate().unwrap();
+		let directory = DiskDirectory::create(temp.as_path()).unwrap();
+		let account = SafeAccount::create(&keypair, [0u8; 16], "old", &KdfParams::Pbkdf2(1024), "Test".to_owned(), "{}".to_owned());
+		let account = directory.insert(account).unwrap();
+
+		// when
+		let account = account.change_password("old", "new", &KdfParams::Pbkdf2(1024)).unwrap();
+		directory.insert(account).unwrap();
+
+		// then
+		let files: Vec<_> = fs::read_dir(temp.as_path()).unwrap().collect();
+		assert_eq!(files.len(), 1);
+		let accounts = directory.load().unwrap();
+		assert!(accounts[0].check_password("new"));
+	}
 }
@@ -0,0 +1,194 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::{fs, io};
+use std::io::Write;
+use std::path::{PathBuf, Path};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use serde_json;
+use ethkey::Address;
+use account::{Crypto, KdfParams};
+use {json, SafeAccount, Error};
+use super::KeyDirectory;
+use super::disk::{restrict_permissions_to_owner, temp_path};
+
+/// Name of the file holding vault password check.
+const VAULT_FILE: &'static str = "vault.json";
+
+/// Key directory with all the key files encrypted with the vault password.
+/// Addresses, names and meta of the accounts are not readable until the vault is opened.
+pub struct VaultDirectory {
+	path: PathBuf,
+	password: String,
+	kdf: KdfParams,
+	accounts: RwLock<BTreeMap<Address, SafeAccount>>,
+}
+
+impl VaultDirectory {
+	/// Creates new empty vault at given path.
+	pub fn create<P>(path: P, password: &str, kdf: KdfParams) -> Result<Self, Error> where P: AsRef<Path> {
+		if Self::is_vault(&path) {
+			return Err(Error::VaultExists);
+		}
+
+		try!(fs::create_dir_all(&path));
+		let crypto = Crypto::encrypt(b"{}", password, &kdf);
+		try!(write_file(&path.as_ref().join(VAULT_FILE), crypto));
+		Self::open(path, password, kdf)
+	}
+
+	/// Opens the vault, decrypting all its key files.
+	pub fn open<P>(path: P, password: &str, kdf: KdfParams) -> Result<Self, Error> where P: AsRef<Path> {
+		if !Self::is_vault(&path) {
+			return Err(Error::VaultNotFound);
+		}
+
+		let path = path.as_ref().to_path_buf();
+		let check = try!(read_file(&path.join(VAULT_FILE)));
+		try!(check.decrypt(password));
+
+		let mut accounts = BTreeMap::new();
+		for entry in try!(fs::read_dir(&path)) {
+			let entry = try!(entry);
+			let file_name = entry.file_name().to_string_lossy().into_owned();
+			if file_name == VAULT_FILE || file_name.starts_with(".") || try!(entry.metadata()).is_dir() {
+				continue;
+			}
+
+			let plain = try!(try!(read_file(&entry.path())).decrypt(password));
+			let keyfile = try!(json::KeyFile::load(&plain[..]).map_err(|e| Error::InvalidKeyFile(format!("{:?}: {}", entry.path(), e))));
+			let account = SafeAccount::from_file(keyfile, Some(file_name));
+			accounts.insert(account.address.clone(), account);
+		}
+
+		Ok(VaultDirectory {
+			path: path,
+			password: password.to_owned(),
+			kdf: kdf,
+			accounts: RwLock::new(accounts),
+		})
+	}
+
+	/// Checks if there is a vault at given path.
+	pub fn is_vault<P>(path: P) -> bool where P: AsRef<Path> {
+		path.as_ref().join(VAULT_FILE).is_file()
+	}
+
+	/// Checks if the vault holds given account.
+	pub fn contains(&self, address: &Address) -> bool {
+		self.accounts.read().unwrap().contains_key(address)
+	}
+}
+
+impl KeyDirectory for VaultDirectory {
+	fn load(&self) -> Result<Vec<SafeAccount>, Error> {
+		Ok(self.accounts.read().unwrap().values().cloned().collect())
+	}
+
+	fn insert(&self, account: SafeAccount) -> Result<SafeAccount, Error> {
+		// file names are random so that they don't reveal the address
+		let mut account = account;
+		let filename = format!("{}.json", json::UUID::from(account.id));
+		account.filename = Some(filename.clone());
+
+		let keyfile: json::KeyFile = account.clone().into();
+		let plain = try!(serde_json::to_vec(&keyfile).map_err(|e| Error::Custom(format!("{:?}", e))));
+		let crypto = Crypto::encrypt(&plain, &self.password, &self.kdf);
+		try!(write_file(&self.path.join(&filename), crypto));
+
+		self.accounts.write().unwrap().insert(account.address.clone(), account.clone());
+		Ok(account)
+	}
+
+	fn remove(&self, address: &Address) -> Result<(), Error> {
+		let mut accounts = self.accounts.write().unwrap();
+		let filename = try!(accounts.get(address).and_then(|account| account.filename.clone()).ok_or(Error::InvalidAccount));
+		try!(fs::remove_file(self.path.join(filename)));
+		accounts.remove(address);
+		Ok(())
+	}
+
+	fn path(&self) -> Option<&PathBuf> { Some(&self.path) }
+}
+
+fn read_file(path: &Path) -> Result<Crypto, Error> {
+	let file = try!(fs::File::open(path));
+	let crypto: json::Crypto = try!(serde_json::from_reader(file).map_err(|e| Error::InvalidKeyFile(format!("{:?}: {}", path, e))));
+	Ok(crypto.into())
+}
+
+fn write_file(path: &Path, crypto: Crypto) -> Result<(), Error> {
+	let crypto: json::Crypto = crypto.into();
+	let data = try!(serde_json::to_vec(&crypto).map_err(|e| Error::Custom(format!("{:?}", e))));
+	let dir = try!(path.parent().ok_or(Error::Custom("Invalid vault file path.".into())));
+	let filename = try!(path.file_name().and_then(|n| n.to_str()).ok_or(Error::Custom("Invalid vault file path.".into())));
+	let temp_path = temp_path(dir, filename);
+	{
+		let mut file = try!(fs::File::create(&temp_path));
+		try!(file.write_all(&data));
+	}
+
+	if let Err(_) = restrict_permissions_to_owner(&temp_path) {
+		fs::remove_file(temp_path).expect("Expected to remove recently created file");
+		return Err(Error::Io(io::Error::last_os_error()));
+	}
+	try!(fs::rename(&temp_path, path));
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+	use std::io::Read;
+	use devtools::RandomTempPath;
+	use super::VaultDirectory;
+	use dir::KeyDirectory;
+	use account::{SafeAccount, KdfParams};
+	use ethkey::{Random, Generator};
+	use Error;
+
+	#[test]
+	fn should_hide_accounts_until_opened() {
+		// given
+		let temp_path = RandomTempPath::new();
+		let dir = temp_path.as_path();
+		let keypair = Random.generate().unwrap();
+		let vault = VaultDirectory::create(dir, "vault password", KdfParams::Pbkdf2(1024)).unwrap();
+
+		// when
+		let account = SafeAccount::create(&keypair, [1u8; 16], "account password", &KdfParams::Pbkdf2(1024), "Test".to_owned(), "{}".to_owned());
+		vault.insert(account).unwrap();
+
+		// then
+		let file = fs::read_dir(dir).unwrap()
+			.map(|entry| entry.unwrap().file_name().into_string().unwrap())
+			.find(|name| name != "vault.json")
+			.unwrap();
+		let mut content = String::new();
+		fs::File::open(dir.join(file)).unwrap().read_to_string(&mut content).unwrap();
+		assert!(!content.contains(&format!("{:?}", keypair.address())));
+		match VaultDirectory::open(dir, "wrong password", KdfParams::Pbkdf2(1024)) {
+			Err(Error::InvalidPassword) => {},
+			_ => panic!("Expected invalid password"),
+		}
+		let accounts = VaultDirectory::open(dir, "vault password", KdfParams::Pbkdf2(1024)).unwrap().load().unwrap();
+		assert_eq!(accounts.len(), 1);
+		assert_eq!(accounts[0].address, keypair.address());
+		assert_eq!(accounts[0].name, "Test");
+	}
+}
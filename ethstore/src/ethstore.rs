@@ -16,22 +16,27 @@
 
 use std::collections::BTreeMap;
 use std::sync::RwLock;
-use std::mem;
+use std::path::PathBuf;
+use std::{fs, mem};
 use ethkey::KeyPair;
 use crypto::KEY_ITERATIONS;
 use random::Random;
-use ethkey::{Signature, Address, Message, Secret};
-use dir::KeyDirectory;
-use account::SafeAccount;
+use ethkey::{Signature, Address, Message, Secret, Public};
+use dir::{KeyDirectory, VaultDirectory};
+use account::{SafeAccount, KdfParams};
 use {Error, SecretStore};
 use json;
 use json::UUID;
 use presale::PresaleWallet;
 use import;
 
+/// Subdirectory of the keys directory holding the vaults.
+const VAULTS_DIR: &'static str = "vaults";
+
 pub struct EthStore {
 	dir: Box<KeyDirectory>,
-	iterations: u32,
+	vaults: RwLock<BTreeMap<String, VaultDirectory>>,
+	kdf: KdfParams,
 	cache: RwLock<BTreeMap<Address, SafeAccount>>,
 }
 
@@ -41,19 +46,31 @@ impl EthStore {
 	}
 
 	pub fn open_with_iterations(directory: Box<KeyDirectory>, iterations: u32) -> Result<Self, Error> {
+		Self::open_with_kdf(directory, KdfParams::Pbkdf2(iterations))
+	}
+
+	/// Opens the store, encrypting new and re-encrypted keys using given key derivation function.
+	pub fn open_with_kdf(directory: Box<KeyDirectory>, kdf: KdfParams) -> Result<Self, Error> {
 		let accounts = try!(directory.load());
 		let cache = accounts.into_iter().map(|account| (account.address.clone(), account)).collect();
 		let store = EthStore {
 			dir: directory,
-			iterations: iterations,
+			vaults: RwLock::new(BTreeMap::new()),
+			kdf: kdf,
 			cache: RwLock::new(cache),
 		};
 		Ok(store)
 	}
 
 	fn save(&self, account: SafeAccount) -> Result<(), Error> {
-		// save to file
-		let account = try!(self.dir.insert(account.clone()));
+		// save to file, accounts already stored in a vault stay there
+		let account = {
+			let vaults = self.vaults.read().unwrap();
+			match vaults.values().find(|vault| vault.contains(&account.address)) {
+				Some(vault) => try!(vault.insert(account)),
+				None => try!(self.dir.insert(account)),
+			}
+		};
 
 		// update cache
 		let mut cache = self.cache.write().unwrap();
@@ -63,7 +80,10 @@ impl EthStore {
 
 	fn reload_accounts(&self) -> Result<(), Error> {
 		let mut cache = self.cache.write().unwrap();
-		let accounts = try!(self.dir.load());
+		let mut accounts = try!(self.dir.load());
+		for vault in self.vaults.read().unwrap().values() {
+			accounts.extend(try!(vault.load()));
+		}
 		let new_accounts: BTreeMap<_, _> = accounts.into_iter().map(|account| (account.address.clone(), account)).collect();
 		mem::replace(&mut *cache, new_accounts);
 		Ok(())
@@ -80,13 +100,34 @@ impl EthStore {
 		let cache = self.cache.read().unwrap();
 		cache.get(address).cloned().ok_or(Error::InvalidAccount)
 	}
+
+	fn vault_path(&self, name: &str) -> Result<PathBuf, Error> {
+		let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+		if !valid {
+			return Err(Error::InvalidVaultName);
+		}
+		self.vaults_dir().map(|dir| dir.join(name))
+	}
+
+	fn vaults_dir(&self) -> Result<PathBuf, Error> {
+		self.dir.path().map(|path| path.join(VAULTS_DIR)).ok_or(Error::VaultsNotSupported)
+	}
+
+	/// Removes the account from the directory or the vault holding it.
+	fn remove(&self, address: &Address) -> Result<(), Error> {
+		let vaults = self.vaults.read().unwrap();
+		match vaults.values().find(|vault| vault.contains(address)) {
+			Some(vault) => vault.remove(address),
+			None => self.dir.remove(address),
+		}
+	}
 }
 
 impl SecretStore for EthStore {
 	fn insert_account(&self, secret: Secret, password: &str) -> Result<Address, Error> {
 		let keypair = try!(KeyPair::from_secret(secret).map_err(|_| Error::CreationFailed));
 		let id: [u8; 16] = Random::random();
-		let account = SafeAccount::create(&keypair, id, password, self.iterations, UUID::from(id).into(), "{}".to_owned());
+		let account = SafeAccount::create(&keypair, id, password, &self.kdf, UUID::from(id).into(), "{}".to_owned());
 		let address = account.address.clone();
 		try!(self.save(account));
 		Ok(address)
@@ -117,7 +158,16 @@ impl SecretStore for EthStore {
 	fn change_password(&self, address: &Address, old_password: &str, new_password: &str) -> Result<(), Error> {
 		// change password
 		let account = try!(self.get(address));
-		let account = try!(account.change_password(old_password, new_password, self.iterations));
+		let account = try!(account.change_password(old_password, new_password, &self.kdf));
+
+		// save to file
+		self.save(account)
+	}
+
+	fn upgrade_kdf(&self, address: &Address, password: &str) -> Result<(), Error> {
+		// re-encrypt with the same password and current key derivation function
+		let account = try!(self.get(address));
+		let account = try!(account.change_password(password, password, &self.kdf));
 
 		// save to file
 		self.save(account)
@@ -130,7 +180,7 @@ impl SecretStore for EthStore {
 		};
 
 		if can_remove {
-			try!(self.dir.remove(address));
+			try!(self.remove(address));
 			let mut cache = self.cache.write().unwrap();
 			cache.remove(address);
 			Ok(())
@@ -149,6 +199,11 @@ impl SecretStore for EthStore {
 		account.decrypt(password, shared_mac, message)
 	}
 
+	fn public(&self, account: &Address, password: &str) -> Result<Public, Error> {
+		let account = try!(self.get(account));
+		account.public(password)
+	}
+
 	fn uuid(&self, address: &Address) -> Result<UUID, Error> {
 		let account = try!(self.get(address));
 		Ok(account.id.into())
@@ -180,6 +235,75 @@ impl SecretStore for EthStore {
 		self.save(account)
 	}
 
+	fn create_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		let path = try!(self.vault_path(name));
+		let vault = try!(VaultDirectory::create(path, password, self.kdf));
+		self.vaults.write().unwrap().insert(name.to_owned(), vault);
+		Ok(())
+	}
+
+	fn open_vault(&self, name: &str, password: &str) -> Result<(), Error> {
+		let path = try!(self.vault_path(name));
+		let vault = try!(VaultDirectory::open(path, password, self.kdf));
+		self.vaults.write().unwrap().insert(name.to_owned(), vault);
+		self.reload_accounts()
+	}
+
+	fn close_vault(&self, name: &str) -> Result<(), Error> {
+		try!(self.vaults.write().unwrap().remove(name).ok_or(Error::VaultNotFound));
+		self.reload_accounts()
+	}
+
+	fn list_vaults(&self) -> Result<Vec<String>, Error> {
+		let dir = try!(self.vaults_dir());
+		if !dir.exists() {
+			return Ok(Vec::new());
+		}
+
+		let mut vaults = Vec::new();
+		for entry in try!(fs::read_dir(dir)) {
+			let entry = try!(entry);
+			if VaultDirectory::is_vault(entry.path()) {
+				vaults.push(entry.file_name().to_string_lossy().into_owned());
+			}
+		}
+		vaults.sort();
+		Ok(vaults)
+	}
+
+	fn list_opened_vaults(&self) -> Result<Vec<String>, Error> {
+		Ok(self.vaults.read().unwrap().keys().cloned().collect())
+	}
+
+	fn change_account_vault(&self, address: &Address, vault: Option<&str>) -> Result<(), Error> {
+		let mut account = try!(self.get(address));
+		// file name of the account is chosen by its new location
+		account.filename = None;
+
+		{
+			let vaults = self.vaults.read().unwrap();
+			let source = vaults.iter().find(|&(_, v)| v.contains(address)).map(|(name, _)| name.clone());
+			if source.as_ref().map(|name| name.as_str()) == vault {
+				return Ok(());
+			}
+
+			// store in the new location first so that the key is never lost
+			let target: &KeyDirectory = match vault {
+				Some(name) => try!(vaults.get(name).ok_or(Error::VaultNotFound)),
+				None => &*self.dir,
+			};
+			try!(target.insert(account));
+
+			let source: &KeyDirectory = match source {
+				Some(ref name) => &vaults[name],
+				None => &*self.dir,
+			};
+			try!(source.remove(address));
+		}
+
+		self.reload_accounts()
+	}
+
 	fn local_path(&self) -> String {
 		self.dir.path().map(|p| p.to_string_lossy().into_owned()).unwrap_or_else(|| String::new())
 	}
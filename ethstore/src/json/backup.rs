@@ -0,0 +1,27 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use super::{KeyFile, Bytes};
+
+/// Decrypted contents of a keystore backup.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+	/// Key files of the accounts.
+	pub accounts: Vec<KeyFile>,
+	/// Other files of the keystore, by path relative to the keys directory.
+	pub files: BTreeMap<String, Bytes>,
+}
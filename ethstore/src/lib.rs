@@ -31,6 +31,8 @@ extern crate lazy_static;
 // reexport it nicely
 extern crate ethkey as _ethkey;
 extern crate ethcrypto as crypto;
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
 
 pub mod dir;
 pub mod ethkey;
@@ -38,6 +40,7 @@ pub mod ethkey;
 mod account;
 mod json;
 
+mod backup;
 mod error;
 mod ethstore;
 mod import;
@@ -45,7 +48,8 @@ mod presale;
 mod random;
 mod secret_store;
 
-pub use self::account::SafeAccount;
+pub use self::account::{SafeAccount, KdfParams, ScryptParams};
+pub use self::backup::Backup;
 pub use self::error::Error;
 pub use self::ethstore::EthStore;
 pub use self::import::{import_accounts, read_geth_accounts};
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethkey::{Address, Message, Signature, Secret};
+use ethkey::{Address, Message, Signature, Secret, Public};
 use Error;
 use json::UUID;
 
@@ -23,10 +23,12 @@ pub trait SecretStore: Send + Sync {
 	fn import_presale(&self, json: &[u8], password: &str) -> Result<Address, Error>;
 	fn import_wallet(&self, json: &[u8], password: &str) -> Result<Address, Error>;
 	fn change_password(&self, account: &Address, old_password: &str, new_password: &str) -> Result<(), Error>;
+	fn upgrade_kdf(&self, account: &Address, password: &str) -> Result<(), Error>;
 	fn remove_account(&self, account: &Address, password: &str) -> Result<(), Error>;
 
 	fn sign(&self, account: &Address, password: &str, message: &Message) -> Result<Signature, Error>;
 	fn decrypt(&self, account: &Address, password: &str, shared_mac: &[u8], message: &[u8]) -> Result<Vec<u8>, Error>;
+	fn public(&self, account: &Address, password: &str) -> Result<Public, Error>;
 
 	fn accounts(&self) -> Result<Vec<Address>, Error>;
 	fn uuid(&self, account: &Address) -> Result<UUID, Error>;
@@ -36,6 +38,13 @@ pub trait SecretStore: Send + Sync {
 	fn set_name(&self, address: &Address, name: String) -> Result<(), Error>;
 	fn set_meta(&self, address: &Address, meta: String) -> Result<(), Error>;
 
+	fn create_vault(&self, name: &str, password: &str) -> Result<(), Error>;
+	fn open_vault(&self, name: &str, password: &str) -> Result<(), Error>;
+	fn close_vault(&self, name: &str) -> Result<(), Error>;
+	fn list_vaults(&self) -> Result<Vec<String>, Error>;
+	fn list_opened_vaults(&self) -> Result<Vec<String>, Error>;
+	fn change_account_vault(&self, account: &Address, vault: Option<&str>) -> Result<(), Error>;
+
 	fn local_path(&self) -> String;
 	fn list_geth_accounts(&self, testnet: bool) -> Vec<Address>;
 	fn import_geth_accounts(&self, desired: Vec<Address>, testnet: bool) -> Result<Vec<Address>, Error>;
@@ -19,9 +19,11 @@ extern crate ethstore;
 
 mod util;
 
-use ethstore::{SecretStore, EthStore};
+use std::fs;
+use std::io::Read;
+use ethstore::{SecretStore, EthStore, KdfParams, ScryptParams};
 use ethstore::ethkey::{Random, Generator, Secret, KeyPair, verify_address};
-use ethstore::dir::DiskDirectory;
+use ethstore::dir::{KeyDirectory, DiskDirectory};
 use util::TransientDir;
 
 #[test]
@@ -76,6 +78,31 @@ fn secret_store_change_password() {
 	assert!(store.sign(&accounts[0], "1", &Default::default()).is_ok());
 }
 
+#[test]
+fn secret_store_upgrade_kdf() {
+	let dir = TransientDir::create().unwrap();
+	let path = dir.path().cloned().unwrap();
+	let store = EthStore::open_with_iterations(Box::new(DiskDirectory::at(&path)), 1024).unwrap();
+	assert!(store.insert_account(random_secret(), "1").is_ok());
+	let account = store.accounts().unwrap()[0];
+
+	let scrypt = KdfParams::Scrypt(ScryptParams { n: 1024, p: 1, r: 8 });
+	let store = EthStore::open_with_kdf(Box::new(DiskDirectory::at(&path)), scrypt).unwrap();
+	assert!(store.upgrade_kdf(&account, "").is_err());
+	assert!(store.upgrade_kdf(&account, "1").is_ok());
+	assert!(store.sign(&account, "1", &Default::default()).is_ok());
+
+	let mut keyfile = String::new();
+	let entry = fs::read_dir(&path).unwrap().next().unwrap().unwrap();
+	fs::File::open(entry.path()).unwrap().read_to_string(&mut keyfile).unwrap();
+	assert!(keyfile.contains("\"scrypt\""));
+
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	assert_eq!(store.accounts().unwrap(), vec![account]);
+	assert!(store.sign(&account, "1", &Default::default()).is_ok());
+	assert!(store.sign(&account, "", &Default::default()).is_err());
+}
+
 #[test]
 fn secret_store_remove_account() {
 	let dir = TransientDir::create().unwrap();
@@ -88,6 +115,35 @@ fn secret_store_remove_account() {
 	assert!(store.remove_account(&accounts[0], "").is_err());
 }
 
+#[test]
+fn secret_store_vaults() {
+	let dir = TransientDir::create().unwrap();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	assert!(store.insert_account(random_secret(), "").is_ok());
+	let account = store.accounts().unwrap()[0];
+	assert!(store.create_vault("customer-1", "vault").is_ok());
+	assert!(store.create_vault("customer-1", "vault").is_err());
+	assert!(store.create_vault("../escape", "vault").is_err());
+
+	assert!(store.change_account_vault(&account, Some("customer-1")).is_ok());
+	assert_eq!(store.accounts().unwrap(), vec![account]);
+	assert!(store.sign(&account, "", &Default::default()).is_ok());
+
+	assert!(store.close_vault("customer-1").is_ok());
+	assert_eq!(store.accounts().unwrap().len(), 0);
+	assert_eq!(store.list_vaults().unwrap(), vec!["customer-1".to_owned()]);
+	assert_eq!(store.list_opened_vaults().unwrap().len(), 0);
+
+	assert!(store.open_vault("customer-1", "wrong").is_err());
+	assert!(store.open_vault("customer-1", "vault").is_ok());
+	assert_eq!(store.accounts().unwrap(), vec![account]);
+	assert_eq!(store.list_opened_vaults().unwrap(), vec!["customer-1".to_owned()]);
+
+	assert!(store.change_account_vault(&account, None).is_ok());
+	assert!(store.close_vault("customer-1").is_ok());
+	assert_eq!(store.accounts().unwrap(), vec![account]);
+}
+
 fn test_path() -> &'static str {
 	match ::std::fs::metadata("ethstore") {
 		Ok(_) => "ethstore/tests/res/geth_keystore",
@@ -0,0 +1,66 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Daily spending of accounts with a signing policy.
+
+use std::io::{Read, Write};
+use std::collections::HashMap;
+use serde_json;
+use util;
+use hash;
+use uint::Uint;
+
+/// Value sent by an account during a day.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailySpending {
+	/// Number of the day since the epoch.
+	pub day: u64,
+	/// Value sent during the day.
+	pub spent: Uint,
+}
+
+impl DailySpending {
+	/// Read a hash map of Address -> DailySpending.
+	pub fn read_address_map<R>(reader: R) -> Result<HashMap<util::Address, DailySpending>, serde_json::Error> where R: Read {
+		serde_json::from_reader(reader).map(|ok: HashMap<hash::Address, DailySpending>|
+			ok.into_iter().map(|(a, s)| (a.into(), s)).collect()
+		)
+	}
+
+	/// Write a hash map of Address -> DailySpending.
+	pub fn write_address_map<W>(m: &HashMap<util::Address, DailySpending>, writer: &mut W) -> Result<(), serde_json::Error> where W: Write {
+		serde_json::to_writer(writer, &m.iter().map(|(a, s)| (a.clone().into(), s)).collect::<HashMap<hash::Address, _>>())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use util::{Address, U256};
+	use uint::Uint;
+	use misc::DailySpending;
+
+	#[test]
+	fn daily_spending_roundtrip() {
+		let mut spending = HashMap::new();
+		spending.insert(Address::from(1), DailySpending { day: 17000, spent: Uint(U256::from(1_000_000)) });
+		spending.insert(Address::from(2), DailySpending { day: 17001, spent: Uint(U256::zero()) });
+
+		let mut out = Vec::new();
+		DailySpending::write_address_map(&spending, &mut out).unwrap();
+		assert_eq!(DailySpending::read_address_map(&out[..]).unwrap(), spending);
+	}
+}
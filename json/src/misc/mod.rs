@@ -17,5 +17,9 @@
 //! Misc deserialization.
 
 mod account_meta;
+mod daily_spending;
+mod signing_policy;
 
 pub use self::account_meta::AccountMeta;
+pub use self::daily_spending::DailySpending;
+pub use self::signing_policy::SigningPolicy;
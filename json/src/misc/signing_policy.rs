@@ -0,0 +1,82 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Signing policy deserialization.
+
+use std::io::Read;
+use std::collections::HashMap;
+use serde_json;
+use util;
+use hash;
+use uint::Uint;
+use bytes::Bytes;
+
+/// Limits on transactions signed automatically with an unlocked account.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct SigningPolicy {
+	/// Addresses the account may send transactions to. Any if not present.
+	#[serde(rename="allowedDestinations")]
+	pub allowed_destinations: Option<Vec<hash::Address>>,
+	/// Maximal value of a single transaction.
+	#[serde(rename="maxValue")]
+	pub max_value: Option<Uint>,
+	/// Maximal value of all transactions sent during a day.
+	#[serde(rename="dailyLimit")]
+	pub daily_limit: Option<Uint>,
+	/// Maximal gas price.
+	#[serde(rename="maxGasPrice")]
+	pub max_gas_price: Option<Uint>,
+	/// 4-byte selectors of contract methods the account may call. Any if not present.
+	#[serde(rename="allowedMethods")]
+	pub allowed_methods: Option<Vec<Bytes>>,
+}
+
+impl SigningPolicy {
+	/// Read a hash map of Address -> SigningPolicy.
+	pub fn read_address_map<R>(reader: R) -> Result<HashMap<util::Address, SigningPolicy>, serde_json::Error> where R: Read {
+		serde_json::from_reader(reader).map(|ok: HashMap<hash::Address, SigningPolicy>|
+			ok.into_iter().map(|(a, p)| (a.into(), p)).collect()
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, U256};
+	use misc::SigningPolicy;
+
+	#[test]
+	fn signing_policy_deserialization() {
+		let s = r#"{
+			"0x00a329c0648769a73afac7f9381e08fb43dbea72": {
+				"allowedDestinations": ["0x0000000000000000000000000000000000000005"],
+				"maxValue": "0x0de0b6b3a7640000",
+				"dailyLimit": "5000000000000000000",
+				"allowedMethods": ["0xa9059cbb"]
+			},
+			"0x0000000000000000000000000000000000000001": {}
+		}"#;
+		let policies = SigningPolicy::read_address_map(s.as_bytes()).unwrap();
+		let policy = &policies[&"00a329c0648769a73afac7f9381e08fb43dbea72".parse::<Address>().unwrap()];
+		assert_eq!(policy.allowed_destinations.as_ref().unwrap().len(), 1);
+		assert_eq!(policy.max_value.unwrap().0, U256::from(1_000_000_000_000_000_000u64));
+		assert_eq!(policy.daily_limit.unwrap().0, U256::from(5_000_000_000_000_000_000u64));
+		assert_eq!(policy.max_gas_price, None);
+		assert_eq!(policy.allowed_methods.as_ref().unwrap()[0].to_vec(), vec![0xa9, 0x05, 0x9c, 0xbb]);
+		assert_eq!(policies[&Address::from(1)].max_value, None);
+	}
+}
@@ -16,6 +16,7 @@
 
 //! Spec account deserialization.
 
+use std::collections::BTreeMap;
 use uint::Uint;
 use bytes::Bytes;
 use spec::builtin::Builtin;
@@ -30,18 +31,21 @@ pub struct Account {
 	/// Nonce.
 	pub nonce: Option<Uint>,
 	/// Code.
-	pub code: Option<Bytes>
+	pub code: Option<Bytes>,
+	/// Storage.
+	pub storage: Option<BTreeMap<Uint, Uint>>,
 }
 
 impl Account {
-	/// Returns true if account does not have nonce and balance.
+	/// Returns true if account does not have nonce, balance, code and storage.
 	pub fn is_empty(&self) -> bool {
-		self.balance.is_none() && self.nonce.is_none()
+		self.balance.is_none() && self.nonce.is_none() && self.code.is_none() && self.storage.is_none()
 	}
 }
 
 #[cfg(test)]
 mod tests {
+	use std::collections::BTreeMap;
 	use serde_json;
 	use spec::account::Account;
 	use util::U256;
@@ -62,4 +66,21 @@ mod tests {
 		assert_eq!(deserialized.code.unwrap(), Bytes::new(vec![0x12, 0x34]));
 		assert!(deserialized.builtin.is_some()); // Further tested in builtin.rs
 	}
+
+	#[test]
+	fn account_storage_deserialization() {
+		let s = r#"{
+			"balance": "1",
+			"nonce": "0",
+			"code": "1234",
+			"storage": { "0x7fffffffffffffff7fffffffffffffff": "0x1" }
+		}"#;
+		let deserialized: Account = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.balance.unwrap(), Uint(U256::from(1)));
+		assert_eq!(deserialized.nonce.unwrap(), Uint(U256::from(0)));
+		assert_eq!(deserialized.code.unwrap(), Bytes::new(vec![0x12, 0x34]));
+		let mut storage = BTreeMap::new();
+		storage.insert(Uint(U256::from("7fffffffffffffff7fffffffffffffff")), Uint(U256::from(1)));
+		assert_eq!(deserialized.storage.unwrap(), storage);
+	}
 }
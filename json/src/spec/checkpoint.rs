@@ -0,0 +1,54 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Spec trusted checkpoint deserialization.
+
+use uint::Uint;
+use hash::H256;
+
+/// Trusted checkpoint block.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Checkpoint {
+	/// Block number.
+	pub number: Uint,
+	/// Block hash.
+	pub hash: H256,
+	/// Total difficulty of the chain up to and including the block.
+	#[serde(rename="totalDifficulty")]
+	pub total_difficulty: Uint,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use spec::Checkpoint;
+	use std::str::FromStr;
+	use util::{U256, hash};
+	use uint::Uint;
+	use hash::H256;
+
+	#[test]
+	fn checkpoint_deserialization() {
+		let s = r#"{
+			"number": "0x1d4c00",
+			"hash": "0x4985f5ca3d2afbec36529aa96f74de3cc10a2a4a6c44f2157a57d2c6059a11bb",
+			"totalDifficulty": "0x2e5efcb8acd4fcef03"
+		}"#;
+		let deserialized: Checkpoint = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.number, Uint(U256::from(0x1d4c00)));
+		assert_eq!(deserialized.hash, H256(hash::H256::from_str("4985f5ca3d2afbec36529aa96f74de3cc10a2a4a6c44f2157a57d2c6059a11bb").unwrap()));
+	}
+}
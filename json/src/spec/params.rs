@@ -18,6 +18,7 @@
 
 use uint::Uint;
 use hash::H256;
+use spec::Checkpoint;
 
 /// Spec params.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -43,6 +44,8 @@ pub struct Params {
 	/// Expected fork block hash.
 	#[serde(rename="forkCanonHash")]
 	pub fork_hash: Option<H256>,
+	/// Optional trusted checkpoint.
+	pub checkpoint: Option<Checkpoint>,
 }
 
 #[cfg(test)]
@@ -17,7 +17,7 @@
 //! Lenient uint json deserialization for test json files.
 
 use std::str::FromStr;
-use serde::{Deserialize, Deserializer, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, Error};
 use serde::de::Visitor;
 use util::{U256, Uint as U};
 
@@ -56,6 +56,12 @@ impl Deserialize for Uint {
 	}
 }
 
+impl Serialize for Uint {
+	fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error> where S: Serializer {
+		serializer.serialize_str(&format!("{:x}", self.0))
+	}
+}
+
 struct UintVisitor;
 
 impl Visitor for UintVisitor {
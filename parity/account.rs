@@ -14,21 +14,35 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethcore::ethstore::{EthStore, import_accounts};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf, Component};
+use serde_json;
+use serde_json::Value;
+use ethcore::ethstore::{EthStore, SecretStore, Backup, KdfParams, ScryptParams, import_accounts};
 use ethcore::ethstore::dir::DiskDirectory;
 use ethcore::account_provider::AccountProvider;
-use helpers::{password_prompt, password_from_file};
+use util::path::restrict_permissions_owner;
+use helpers::{password_prompt, password_read, password_from_file, passwords_from_files};
+
+/// Name of the address book file in the keys directory.
+const ADDRESS_BOOK: &'static str = "address_book.json";
+/// Name of the directory holding vaults in the keys directory.
+const VAULTS_DIR: &'static str = "vaults";
 
 #[derive(Debug, PartialEq)]
 pub enum AccountCmd {
 	New(NewAccount),
 	List(String),
 	Import(ImportAccounts),
+	Backup(BackupAccounts),
+	Restore(RestoreAccounts),
+	UpgradeKdf(UpgradeKdf),
 }
 
 #[derive(Debug, PartialEq)]
 pub struct NewAccount {
-	pub iterations: u32,
+	pub kdf: KdfParams,
 	pub path: String,
 	pub password_file: Option<String>,
 }
@@ -39,11 +53,36 @@ pub struct ImportAccounts {
 	pub to: String,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct BackupAccounts {
+	pub path: String,
+	pub file: String,
+	pub password_file: Option<String>,
+	pub scrypt: ScryptParams,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RestoreAccounts {
+	pub path: String,
+	pub file: String,
+	pub password_file: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UpgradeKdf {
+	pub path: String,
+	pub password_files: Vec<String>,
+	pub kdf: KdfParams,
+}
+
 pub fn execute(cmd: AccountCmd) -> Result<String, String> {
 	match cmd {
 		AccountCmd::New(new_cmd) => new(new_cmd),
 		AccountCmd::List(path) => list(path),
 		AccountCmd::Import(import_cmd) => import(import_cmd),
+		AccountCmd::Backup(backup_cmd) => backup(backup_cmd),
+		AccountCmd::Restore(restore_cmd) => restore(restore_cmd),
+		AccountCmd::UpgradeKdf(upgrade_cmd) => upgrade_kdf(upgrade_cmd),
 	}
 }
 
@@ -58,7 +97,7 @@ fn new(n: NewAccount) -> Result<String, String> {
 	};
 
 	let dir = Box::new(try!(keys_dir(n.path)));
-	let secret_store = Box::new(EthStore::open_with_iterations(dir, n.iterations).unwrap());
+	let secret_store = Box::new(EthStore::open_with_kdf(dir, n.kdf).unwrap());
 	let acc_provider = AccountProvider::new(secret_store);
 	let new_account = try!(acc_provider.new_account(&password).map_err(|e| format!("Could not create new account: {}", e)));
 	Ok(format!("{:?}", new_account))
@@ -86,3 +125,274 @@ fn import(i: ImportAccounts) -> Result<String, String> {
 	}
 	Ok(format!("{}", imported))
 }
+
+/// Returns paths (relative to the keys directory) of the keystore files other than plain key files.
+fn keystore_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+	let mut files = Vec::new();
+	if path.join(ADDRESS_BOOK).is_file() {
+		files.push(PathBuf::from(ADDRESS_BOOK));
+	}
+
+	let vaults = path.join(VAULTS_DIR);
+	if vaults.is_dir() {
+		for vault in try!(fs::read_dir(&vaults).map_err(|e| format!("Could not read vaults: {}", e))) {
+			let vault = try!(vault.map_err(|e| format!("Could not read vaults: {}", e)));
+			if !vault.path().is_dir() {
+				continue;
+			}
+			for file in try!(fs::read_dir(vault.path()).map_err(|e| format!("Could not read vault: {}", e))) {
+				let file = try!(file.map_err(|e| format!("Could not read vault: {}", e)));
+				if file.path().is_file() {
+					files.push(PathBuf::from(VAULTS_DIR).join(vault.file_name()).join(file.file_name()));
+				}
+			}
+		}
+	}
+
+	Ok(files)
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+	let mut content = Vec::new();
+	try!(fs::File::open(path)
+		.and_then(|mut file| file.read_to_end(&mut content))
+		.map_err(|e| format!("Could not read {}: {}", path.display(), e)));
+	Ok(content)
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), String> {
+	try!(fs::File::create(path)
+		.and_then(|mut file| file.write_all(content))
+		.map_err(|e| format!("Could not write {}: {}", path.display(), e)));
+	let _ = restrict_permissions_owner(path);
+	Ok(())
+}
+
+fn backup(b: BackupAccounts) -> Result<String, String> {
+	let password: String = match b.password_file {
+		Some(file) => try!(password_from_file(file)),
+		None => try!(password_prompt()),
+	};
+
+	let path = PathBuf::from(&b.path);
+	let dir = try!(keys_dir(b.path));
+	let mut backup = try!(Backup::of(&dir).map_err(|e| format!("Could not read accounts: {}", e)));
+	for file in try!(keystore_files(&path)) {
+		let content = try!(read_file(&path.join(&file)));
+		backup.files.insert(file.to_string_lossy().into_owned(), content);
+	}
+
+	let data = try!(backup.encrypt(&password, &b.scrypt).map_err(|e| format!("Could not encrypt backup: {}", e)));
+	try!(write_file(Path::new(&b.file), &data));
+	Ok(format!("{}", backup.accounts.len()))
+}
+
+/// Adds address book entries from the backup which are missing in the existing address book.
+fn merge_address_book(existing: &[u8], restored: &[u8]) -> Result<Vec<u8>, String> {
+	let existing: Value = try!(serde_json::from_slice(existing).map_err(|e| format!("Invalid address book: {}", e)));
+	let restored: Value = try!(serde_json::from_slice(restored).map_err(|e| format!("Invalid address book in backup: {}", e)));
+	match (existing, restored) {
+		(Value::Object(mut existing), Value::Object(restored)) => {
+			for (address, meta) in restored {
+				existing.entry(address).or_insert(meta);
+			}
+			serde_json::to_vec(&Value::Object(existing)).map_err(|e| format!("{:?}", e))
+		},
+		_ => Err("Invalid address book.".into()),
+	}
+}
+
+fn restore(r: RestoreAccounts) -> Result<String, String> {
+	let password: String = match r.password_file {
+		Some(file) => try!(password_from_file(file)),
+		None => try!(password_read()),
+	};
+
+	let data = try!(read_file(Path::new(&r.file)));
+	let backup = try!(Backup::decrypt(&data, &password).map_err(|e| format!("Could not decrypt backup: {}", e)));
+
+	let path = PathBuf::from(&r.path);
+	for file in backup.files.keys() {
+		let relative = Path::new(file);
+		if !relative.components().all(|c| match c { Component::Normal(_) => true, _ => false }) {
+			return Err(format!("Invalid file in backup: {}", file));
+		}
+
+		// keys in a vault are encrypted with its password, so they can't be added to another vault with the same name
+		if relative.starts_with(VAULTS_DIR) {
+			if let Some(vault) = relative.iter().nth(1) {
+				if path.join(VAULTS_DIR).join(vault).exists() {
+					return Err(format!("Vault {} already exists. Rename or remove it before restoring the backup.", vault.to_string_lossy()));
+				}
+			}
+		}
+	}
+
+	let dir = try!(keys_dir(r.path));
+	let restored = try!(backup.restore_accounts(&dir).map_err(|e| format!("Could not restore accounts: {}", e)));
+
+	for (file, content) in &backup.files {
+		let target = path.join(file);
+		if file == ADDRESS_BOOK && target.is_file() {
+			let merged = try!(merge_address_book(&try!(read_file(&target)), content));
+			try!(write_file(&target, &merged));
+		} else if !target.exists() {
+			if let Some(parent) = target.parent() {
+				try!(fs::create_dir_all(parent).map_err(|e| format!("Could not create {}: {}", parent.display(), e)));
+			}
+			try!(write_file(&target, content));
+		}
+	}
+
+	Ok(format!("{}", restored.len()))
+}
+
+fn upgrade_kdf(u: UpgradeKdf) -> Result<String, String> {
+	let passwords = try!(passwords_from_files(u.password_files));
+	if passwords.is_empty() {
+		return Err("Provide the passwords of the accounts to upgrade with --password.".into());
+	}
+
+	let dir = Box::new(try!(keys_dir(u.path)));
+	let store = try!(EthStore::open_with_kdf(dir, u.kdf).map_err(|e| format!("Could not open keys directory: {}", e)));
+	let accounts = try!(store.accounts().map_err(|e| format!("Could not read accounts: {}", e)));
+
+	let mut upgraded = 0;
+	let mut locked = Vec::new();
+	for account in accounts {
+		if passwords.iter().any(|password| store.upgrade_kdf(&account, password).is_ok()) {
+			upgraded += 1;
+		} else {
+			locked.push(format!("{:?}", account));
+		}
+	}
+
+	if locked.is_empty() {
+		Ok(format!("{}", upgraded))
+	} else {
+		Err(format!("Upgraded {} accounts. No valid password for: {}", upgraded, locked.join(", ")))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::io::{Read, Write};
+	use devtools::RandomTempPath;
+	use ethcore::ethstore::{EthStore, SecretStore, KdfParams, ScryptParams};
+	use ethcore::ethstore::dir::DiskDirectory;
+	use ethcore::ethstore::ethkey::{Random, Generator};
+	use super::{backup, restore, upgrade_kdf, BackupAccounts, RestoreAccounts, UpgradeKdf, merge_address_book};
+
+	#[test]
+	fn should_merge_address_books() {
+		let existing = br#"{"0x01":{"name":"a"}}"#;
+		let restored = br#"{"0x01":{"name":"b"},"0x02":{"name":"c"}}"#;
+		let merged = merge_address_book(existing, restored).unwrap();
+		assert_eq!(String::from_utf8(merged).unwrap(), r#"{"0x01":{"name":"a"},"0x02":{"name":"c"}}"#);
+	}
+
+	#[test]
+	fn should_backup_and_restore_keystore() {
+		// given
+		let temp = RandomTempPath::create_dir();
+		let src = temp.as_path().join("src");
+		let dst = temp.as_path().join("dst");
+		let file = temp.as_path().join("backup.json");
+		let password = temp.as_path().join("password");
+		fs::File::create(&password).unwrap().write_all(b"backup password\n").unwrap();
+		let secret = Random.generate().unwrap().secret().clone();
+		let address = {
+			let store = EthStore::open_with_iterations(Box::new(DiskDirectory::create(&src).unwrap()), 1024).unwrap();
+			store.insert_account(secret, "account password").unwrap()
+		};
+		fs::File::create(src.join("address_book.json")).unwrap().write_all(br#"{"0x01":{"name":"a"}}"#).unwrap();
+
+		// when
+		let backed_up = backup(BackupAccounts {
+			path: src.to_str().unwrap().into(),
+			file: file.to_str().unwrap().into(),
+			password_file: Some(password.to_str().unwrap().into()),
+			scrypt: ScryptParams { n: 1024, p: 1, r: 8 },
+		}).unwrap();
+		let restored = restore(RestoreAccounts {
+			path: dst.to_str().unwrap().into(),
+			file: file.to_str().unwrap().into(),
+			password_file: Some(password.to_str().unwrap().into()),
+		}).unwrap();
+
+		// then
+		assert_eq!(backed_up, "1");
+		assert_eq!(restored, "1");
+		let store = EthStore::open(Box::new(DiskDirectory::at(&dst))).unwrap();
+		assert_eq!(store.accounts().unwrap(), vec![address]);
+		assert!(dst.join("address_book.json").is_file());
+	}
+
+	#[test]
+	fn should_refuse_to_restore_into_existing_vault() {
+		// given
+		let temp = RandomTempPath::create_dir();
+		let src = temp.as_path().join("src");
+		let dst = temp.as_path().join("dst");
+		let file = temp.as_path().join("backup.json");
+		let password = temp.as_path().join("password");
+		fs::File::create(&password).unwrap().write_all(b"backup password\n").unwrap();
+		{
+			let store = EthStore::open_with_iterations(Box::new(DiskDirectory::create(&src).unwrap()), 1024).unwrap();
+			store.insert_account(Random.generate().unwrap().secret().clone(), "account password").unwrap();
+		}
+		fs::create_dir_all(src.join("vaults").join("work")).unwrap();
+		fs::File::create(src.join("vaults").join("work").join("vault.json")).unwrap().write_all(b"{}").unwrap();
+		fs::create_dir_all(dst.join("vaults").join("work")).unwrap();
+		backup(BackupAccounts {
+			path: src.to_str().unwrap().into(),
+			file: file.to_str().unwrap().into(),
+			password_file: Some(password.to_str().unwrap().into()),
+			scrypt: ScryptParams { n: 1024, p: 1, r: 8 },
+		}).unwrap();
+
+		// when
+		let restored = restore(RestoreAccounts {
+			path: dst.to_str().unwrap().into(),
+			file: file.to_str().unwrap().into(),
+			password_file: Some(password.to_str().unwrap().into()),
+		});
+
+		// then
+		assert_eq!(restored, Err("Vault work already exists. Rename or remove it before restoring the backup.".into()));
+		assert!(!dst.join("vaults").join("work").join("vault.json").exists());
+		let store = EthStore::open(Box::new(DiskDirectory::at(&dst))).unwrap();
+		assert!(store.accounts().unwrap().is_empty());
+	}
+
+	#[test]
+	fn should_upgrade_kdf_of_accounts() {
+		// given
+		let temp = RandomTempPath::create_dir();
+		let keys = temp.as_path().join("keys");
+		let passwords = temp.as_path().join("passwords");
+		fs::File::create(&passwords).unwrap().write_all(b"wrong password\naccount password\n").unwrap();
+		let secret = Random.generate().unwrap().secret().clone();
+		let address = {
+			let store = EthStore::open_with_iterations(Box::new(DiskDirectory::create(&keys).unwrap()), 1024).unwrap();
+			store.insert_account(secret, "account password").unwrap()
+		};
+
+		// when
+		let upgraded = upgrade_kdf(UpgradeKdf {
+			path: keys.to_str().unwrap().into(),
+			password_files: vec![passwords.to_str().unwrap().into()],
+			kdf: KdfParams::Scrypt(ScryptParams { n: 1024, p: 1, r: 8 }),
+		}).unwrap();
+
+		// then
+		assert_eq!(upgraded, "1");
+		let mut keyfile = String::new();
+		let entry = fs::read_dir(&keys).unwrap().next().unwrap().unwrap();
+		fs::File::open(entry.path()).unwrap().read_to_string(&mut keyfile).unwrap();
+		assert!(keyfile.contains("\"scrypt\""));
+		let store = EthStore::open(Box::new(DiskDirectory::at(&keys))).unwrap();
+		assert!(store.sign(&address, "account password", &Default::default()).is_ok());
+	}
+}
@@ -17,20 +17,22 @@
 use std::str::{FromStr, from_utf8};
 use std::{io, fs};
 use std::io::{BufReader, BufRead};
+use std::collections::BTreeMap;
 use std::time::{Instant, Duration};
 use std::thread::sleep;
 use std::sync::Arc;
-use rustc_serialize::hex::FromHex;
+use rustc_serialize::hex::{FromHex, ToHex};
+use serde_json::{self, Value};
 use ethcore_logger::{setup_log, Config as LogConfig};
 use io::{PanicHandler, ForwardPanic};
-use util::{ToPretty, Uint};
+use util::{ToPretty, Uint, U256, H256, Address, clean_0x};
 use rlp::PayloadInfo;
 use ethcore::service::ClientService;
 use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, BlockImportError, BlockChainClient, BlockID};
 use ethcore::error::ImportError;
 use ethcore::miner::Miner;
 use cache::CacheConfig;
-use params::{SpecType, Pruning, Switch, tracing_switch_to_bool};
+use params::{SpecType, Pruning, Switch, tracing_switch_to_bool, fatdb_switch_to_bool};
 use informant::{Informant, MillisecondDuration};
 use io_handler::ImportIoHandler;
 use helpers::{to_client_config, execute_upgrades};
@@ -66,6 +68,7 @@ impl FromStr for DataFormat {
 pub enum BlockchainCmd {
 	Import(ImportBlockchain),
 	Export(ExportBlockchain),
+	ExportState(ExportState),
 }
 
 #[derive(Debug, PartialEq)]
@@ -81,6 +84,7 @@ pub struct ImportBlockchain {
 	pub wal: bool,
 	pub mode: Mode,
 	pub tracing: Switch,
+	pub fat_db: Switch,
 	pub vm_type: VMType,
 }
 
@@ -97,14 +101,36 @@ pub struct ExportBlockchain {
 	pub wal: bool,
 	pub mode: Mode,
 	pub tracing: Switch,
+	pub fat_db: Switch,
 	pub from_block: BlockID,
 	pub to_block: BlockID,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct ExportState {
+	pub spec: SpecType,
+	pub logger_config: LogConfig,
+	pub cache_config: CacheConfig,
+	pub dirs: Directories,
+	pub file_path: Option<String>,
+	pub pruning: Pruning,
+	pub compaction: DatabaseCompactionProfile,
+	pub wal: bool,
+	pub mode: Mode,
+	pub tracing: Switch,
+	pub fat_db: Switch,
+	pub at: BlockID,
+	pub min_balance: Option<U256>,
+	pub addresses: Vec<Address>,
+	pub storage: bool,
+	pub as_spec: bool,
+}
+
 pub fn execute(cmd: BlockchainCmd) -> Result<String, String> {
 	match cmd {
 		BlockchainCmd::Import(import_cmd) => execute_import(import_cmd),
 		BlockchainCmd::Export(export_cmd) => execute_export(export_cmd),
+		BlockchainCmd::ExportState(export_cmd) => execute_export_state(export_cmd),
 	}
 }
 
@@ -143,6 +169,9 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	// select pruning algorithm
 	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
 
+	// check if fatdb is on
+	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
+
 	// prepare client and snapshot paths.
 	let client_path = db_dirs.client_path(algorithm);
 	let snapshot_path = db_dirs.snapshot_path();
@@ -151,7 +180,7 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
 
 	// prepare client config
-	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, cmd.compaction, cmd.wal, cmd.vm_type, "".into(), algorithm);
+	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, fat_db, cmd.compaction, cmd.wal, cmd.vm_type, "".into(), algorithm);
 
 	// build client
 	let service = try!(ClientService::start(
@@ -240,6 +269,7 @@ fn execute_import(cmd: ImportBlockchain) -> Result<String, String> {
 	// save user defaults
 	user_defaults.pruning = algorithm;
 	user_defaults.tracing = tracing;
+	user_defaults.fat_db = fat_db;
 	try!(user_defaults.save(&user_defaults_path));
 
 	let report = client.report();
@@ -291,6 +321,9 @@ fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
 	// select pruning algorithm
 	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
 
+	// check if fatdb is on
+	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
+
 	// prepare client and snapshot paths.
 	let client_path = db_dirs.client_path(algorithm);
 	let snapshot_path = db_dirs.snapshot_path();
@@ -299,7 +332,7 @@ fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
 	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
 
 	// prepare client config
-	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, cmd.compaction, cmd.wal, VMType::default(), "".into(), algorithm);
+	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, fat_db, cmd.compaction, cmd.wal, VMType::default(), "".into(), algorithm);
 
 	let service = try!(ClientService::start(
 		client_config,
@@ -332,9 +365,199 @@ fn execute_export(cmd: ExportBlockchain) -> Result<String, String> {
 	Ok("Export completed.".into())
 }
 
+fn execute_export_state(cmd: ExportState) -> Result<String, String> {
+	// Setup panic handler
+	let panic_handler = PanicHandler::new_in_arc();
+
+	// Setup logging
+	let _logger = setup_log(&cmd.logger_config);
+
+	// create dirs used by parity
+	try!(cmd.dirs.create_dirs());
+
+	// load spec file
+	let spec = try!(cmd.spec.spec());
+
+	// load genesis hash
+	let genesis_hash = spec.genesis_header().hash();
+
+	// database paths
+	let db_dirs = cmd.dirs.database(genesis_hash, spec.fork_name.clone());
+
+	// user defaults path
+	let user_defaults_path = db_dirs.user_defaults_path();
+
+	// load user defaults
+	let user_defaults = try!(UserDefaults::load(&user_defaults_path));
+
+	// check if tracing is on
+	let tracing = try!(tracing_switch_to_bool(cmd.tracing, &user_defaults));
+
+	fdlimit::raise_fd_limit();
+
+	// select pruning algorithm
+	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+
+	// check if fatdb is on
+	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
+
+	// enumerating accounts and storage keys is only possible with fat db
+	if !fat_db && (cmd.addresses.is_empty() || cmd.storage) {
+		return Err("Exporting the whole state or account storage requires a database synced with `--fat-db on`. Use `--addresses` to export particular accounts.".into());
+	}
+
+	// prepare client and snapshot paths.
+	let client_path = db_dirs.client_path(algorithm);
+	let snapshot_path = db_dirs.snapshot_path();
+
+	// execute upgrades
+	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
+
+	// prepare client config
+	let client_config = to_client_config(&cmd.cache_config, cmd.mode, tracing, fat_db, cmd.compaction, cmd.wal, VMType::default(), "".into(), algorithm);
+
+	let service = try!(ClientService::start(
+		client_config,
+		&spec,
+		&client_path,
+		&snapshot_path,
+		&cmd.dirs.ipc_path(),
+		Arc::new(Miner::with_spec(&spec)),
+	).map_err(|e| format!("Client service error: {:?}", e)));
+
+	panic_handler.forward_from(&service);
+	let client = service.client();
+
+	let at = cmd.at;
+	let number = try!(client.block_number(at).ok_or("Specified block could not be found"));
+
+	// when exporting a chain specification, everything apart from the accounts is taken from the base spec.
+	let (mut base, mut builtins) = if cmd.as_spec {
+		try!(spec_parts(&try!(cmd.spec.raw())))
+	} else {
+		(BTreeMap::new(), BTreeMap::new())
+	};
+
+	let mut out: Box<io::Write> = match cmd.file_path {
+		Some(f) => Box::new(try!(fs::File::create(&f).map_err(|_| format!("Cannot write to file given: {}", f)))),
+		None => Box::new(io::stdout()),
+	};
+
+	out.write_fmt(format_args!("{{")).expect("Couldn't write to stream.");
+	if cmd.as_spec {
+		// the state root of the exported state differs from the one in the base genesis.
+		if let Some(&mut Value::Object(ref mut genesis)) = base.get_mut("genesis") {
+			genesis.remove("stateRoot");
+		}
+		for (key, value) in &base {
+			let value = try!(serde_json::to_string(value).map_err(|e| format!("Couldn't serialize spec: {:?}", e)));
+			out.write_fmt(format_args!("\n\"{}\": {},", key, value)).expect("Couldn't write to stream.");
+		}
+	}
+	out.write_fmt(format_args!("\n\"accounts\": {{")).expect("Couldn't write to stream.");
+
+	let mut exported = 0;
+	{
+		let mut export_account = |account: &Address| -> Result<(), String> {
+			let balance = client.balance(account, at).unwrap_or_else(U256::zero);
+			if cmd.min_balance.map_or(false, |min| balance < min) {
+				return Ok(());
+			}
+			let nonce = client.nonce(account, at).unwrap_or_else(U256::zero);
+
+			let comma = if exported == 0 { "" } else { "," };
+			out.write_fmt(format_args!("{}\n\"0x{}\": {{\"balance\": \"0x{:x}\", \"nonce\": \"0x{:x}\"", comma, account.hex(), balance, nonce)).expect("Couldn't write to stream.");
+
+			if let Some(builtin) = builtins.remove(account) {
+				let builtin = try!(serde_json::to_string(&builtin).map_err(|e| format!("Couldn't serialize spec: {:?}", e)));
+				out.write_fmt(format_args!(", \"builtin\": {}", builtin)).expect("Couldn't write to stream.");
+			}
+
+			if let Some(Some(code)) = client.code(account, at) {
+				if !code.is_empty() {
+					out.write_fmt(format_args!(", \"code\": \"0x{}\"", code.to_hex())).expect("Couldn't write to stream.");
+				}
+			}
+
+			if cmd.storage {
+				out.write_fmt(format_args!(", \"storage\": {{")).expect("Couldn't write to stream.");
+				let mut last_storage: Option<H256> = None;
+				let mut first = true;
+				loop {
+					let keys = try!(client.list_storage(at, account, last_storage.as_ref(), 1000).ok_or("Specified block could not be found"));
+					if keys.is_empty() { break; }
+
+					for key in &keys {
+						let value = client.storage_at(account, key, at).unwrap_or_else(H256::new);
+						let comma = if first { "" } else { "," };
+						out.write_fmt(format_args!("{}\n\t\"0x{}\": \"0x{}\"", comma, key.hex(), value.hex())).expect("Couldn't write to stream.");
+						first = false;
+					}
+					last_storage = keys.last().cloned();
+				}
+				out.write_fmt(format_args!("\n}}")).expect("Couldn't write to stream.");
+			}
+
+			out.write_fmt(format_args!("}}")).expect("Couldn't write to stream.");
+			exported += 1;
+			Ok(())
+		};
+
+		if cmd.addresses.is_empty() {
+			let mut last: Option<Address> = None;
+			loop {
+				let accounts = try!(client.list_accounts(at, last.as_ref(), 1000).ok_or("Specified block could not be found"));
+				if accounts.is_empty() { break; }
+
+				for account in &accounts {
+					try!(export_account(account));
+				}
+				last = accounts.last().cloned();
+			}
+		} else {
+			for account in &cmd.addresses {
+				try!(export_account(account));
+			}
+		}
+	}
+
+	// builtins are part of the chain specification even if they hold no state.
+	for (i, (address, builtin)) in builtins.into_iter().enumerate() {
+		let builtin = try!(serde_json::to_string(&builtin).map_err(|e| format!("Couldn't serialize spec: {:?}", e)));
+		let comma = if exported == 0 && i == 0 { "" } else { "," };
+		out.write_fmt(format_args!("{}\n\"0x{}\": {{\"builtin\": {}}}", comma, address.hex(), builtin)).expect("Couldn't write to stream.");
+	}
+
+	out.write_fmt(format_args!("\n}}\n}}\n")).expect("Couldn't write to stream.");
+
+	Ok(format!("Export completed. {} accounts exported at block #{}.", exported, number))
+}
+
+/// Splits raw chain specification into everything but the accounts and builtin contracts definitions.
+fn spec_parts(raw: &[u8]) -> Result<(BTreeMap<String, Value>, BTreeMap<Address, Value>), String> {
+	let mut spec = match serde_json::from_slice(raw) {
+		Ok(Value::Object(spec)) => spec,
+		_ => return Err("Invalid chain specification.".into()),
+	};
+
+	let mut builtins = BTreeMap::new();
+	if let Some(Value::Object(accounts)) = spec.remove("accounts") {
+		for (address, account) in accounts {
+			let address: Address = try!(clean_0x(&address).parse().map_err(|_| format!("Invalid address in chain specification: {}", address)));
+			if let Value::Object(mut account) = account {
+				if let Some(builtin) = account.remove("builtin") {
+					builtins.insert(address, builtin);
+				}
+			}
+		}
+	}
+
+	Ok((spec, builtins))
+}
+
 #[cfg(test)]
 mod test {
-	use super::DataFormat;
+	use super::{DataFormat, spec_parts};
 
 	#[test]
 	fn test_data_format_parsing() {
@@ -342,4 +565,23 @@ mod test {
 		assert_eq!(DataFormat::Binary, "bin".parse().unwrap());
 		assert_eq!(DataFormat::Hex, "hex".parse().unwrap());
 	}
+
+	#[test]
+	fn test_spec_parts() {
+		let raw = br#"{
+			"name": "Test",
+			"genesis": { "stateRoot": "0x00" },
+			"accounts": {
+				"0000000000000000000000000000000000000001": { "balance": "1", "builtin": { "name": "ecrecover" } },
+				"0x0000000000000000000000000000000000000002": { "balance": "1" }
+			}
+		}"#;
+
+		let (spec, builtins) = spec_parts(raw).unwrap();
+		assert!(spec.contains_key("name"));
+		assert!(spec.contains_key("genesis"));
+		assert!(!spec.contains_key("accounts"));
+		assert_eq!(builtins.len(), 1);
+		assert!(builtins.contains_key(&1.into()));
+	}
 }
@@ -25,15 +25,21 @@ usage! {
 		cmd_account: bool,
 		cmd_new: bool,
 		cmd_list: bool,
+		cmd_backup: bool,
+		cmd_upgrade_kdf: bool,
 		cmd_export: bool,
+		cmd_export_state: bool,
 		cmd_import: bool,
 		cmd_signer: bool,
 		cmd_new_token: bool,
 		cmd_snapshot: bool,
+		cmd_inspect: bool,
+		cmd_verify: bool,
 		cmd_restore: bool,
 		cmd_ui: bool,
 		cmd_tools: bool,
 		cmd_hash: bool,
+		cmd_nodes: bool,
 
 		// Arguments
 		arg_pid_file: String,
@@ -89,6 +95,18 @@ usage! {
 			or |c: &Config| otry!(c.account).password.clone(),
 		flag_keys_iterations: u32 = 10240u32,
 			or |c: &Config| otry!(c.account).keys_iterations.clone(),
+		flag_keys_kdf: String = "scrypt",
+			or |c: &Config| otry!(c.account).keys_kdf.clone(),
+		flag_keys_scrypt_n: u32 = 262144u32,
+			or |c: &Config| otry!(c.account).keys_scrypt_n.clone(),
+		flag_keys_scrypt_r: u32 = 8u32,
+			or |c: &Config| otry!(c.account).keys_scrypt_r.clone(),
+		flag_keys_scrypt_p: u32 = 1u32,
+			or |c: &Config| otry!(c.account).keys_scrypt_p.clone(),
+		flag_no_hardware_wallets: bool = false,
+			or |c: &Config| otry!(c.account).disable_hardware_wallets.clone(),
+		flag_signing_policies: Option<String> = None,
+			or |c: &Config| otry!(c.account).signing_policies.clone().map(Some),
 
 		flag_force_signer: bool = false,
 			or |c: &Config| otry!(c.signer).force.clone(),
@@ -100,6 +118,10 @@ usage! {
 			or |c: &Config| otry!(c.signer).interface.clone(),
 		flag_signer_path: String = "$HOME/.parity/signer",
 			or |c: &Config| otry!(c.signer).path.clone(),
+		flag_signer_queue_limit: usize = 50usize,
+			or |c: &Config| otry!(c.signer).queue_limit.clone(),
+		flag_signer_request_expiry: u64 = 0u64,
+			or |c: &Config| otry!(c.signer).request_expiry.clone(),
 		// NOTE [todr] For security reasons don't put this to config files
 		flag_signer_no_validation: bool = false, or |_| None,
 
@@ -126,6 +148,22 @@ usage! {
 			or |c: &Config| otry!(c.network).reserved_peers.clone().map(Some),
 		flag_reserved_only: bool = false,
 			or |c: &Config| otry!(c.network).reserved_only.clone(),
+		flag_allow_peers: Option<String> = None,
+			or |c: &Config| otry!(c.network).allow_peers.clone().map(Some),
+		flag_deny_peers: Option<String> = None,
+			or |c: &Config| otry!(c.network).deny_peers.clone().map(Some),
+		flag_checkpoint: Option<String> = None,
+			or |c: &Config| otry!(c.network).checkpoint.clone().map(Some),
+		flag_fast_sync: bool = false,
+			or |c: &Config| otry!(c.network).fast_sync.clone(),
+		flag_max_upload: u32 = 0u32,
+			or |c: &Config| otry!(c.network).max_upload.clone(),
+		flag_max_download: u32 = 0u32,
+			or |c: &Config| otry!(c.network).max_download.clone(),
+		flag_peer_max_upload: u32 = 0u32,
+			or |c: &Config| otry!(c.network).peer_max_upload.clone(),
+		flag_peer_max_download: u32 = 0u32,
+			or |c: &Config| otry!(c.network).peer_max_download.clone(),
 
 		// -- API and Console Options
 		// RPC
@@ -217,18 +255,28 @@ usage! {
 			or |c: &Config| otry!(c.footprint).fast_and_loose.clone(),
 		flag_db_compaction: String = "ssd",
 			or |c: &Config| otry!(c.footprint).db_compaction.clone(),
-		flag_fat_db: bool = false,
+		flag_fat_db: String = "auto",
 			or |c: &Config| otry!(c.footprint).fat_db.clone(),
 
 		// -- Import/Export Options
 		flag_from: String = "1", or |_| None,
 		flag_to: String = "latest", or |_| None,
 		flag_format: Option<String> = None, or |_| None,
+		flag_min_balance: Option<String> = None, or |_| None,
+		flag_addresses: Option<String> = None, or |_| None,
+		flag_with_storage: bool = false, or |_| None,
+		flag_as_spec: bool = false, or |_| None,
 
 		// -- Snapshot Optons
 		flag_at: String = "latest", or |_| None,
 		flag_no_periodic_snapshot: bool = false,
 			or |c: &Config| otry!(c.snapshots).disable_periodic.clone(),
+		flag_snapshot_period: u64 = 10000u64,
+			or |c: &Config| otry!(c.snapshots).period.clone(),
+		flag_snapshot_history: u64 = 500u64,
+			or |c: &Config| otry!(c.snapshots).history.clone(),
+		flag_incremental_snapshot_period: u64 = 0u64,
+			or |c: &Config| otry!(c.snapshots).incremental_period.clone(),
 
 		// -- Virtual Machine Options
 		flag_jitvm: bool = false,
@@ -278,6 +326,12 @@ struct Account {
 	unlock: Option<Vec<String>>,
 	password: Option<Vec<String>>,
 	keys_iterations: Option<u32>,
+	keys_kdf: Option<String>,
+	keys_scrypt_n: Option<u32>,
+	keys_scrypt_r: Option<u32>,
+	keys_scrypt_p: Option<u32>,
+	disable_hardware_wallets: Option<bool>,
+	signing_policies: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -287,6 +341,8 @@ struct Signer {
 	port: Option<u16>,
 	interface: Option<String>,
 	path: Option<String>,
+	queue_limit: Option<usize>,
+	request_expiry: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -302,6 +358,14 @@ struct Network {
 	node_key: Option<String>,
 	reserved_peers: Option<String>,
 	reserved_only: Option<bool>,
+	allow_peers: Option<String>,
+	deny_peers: Option<String>,
+	checkpoint: Option<String>,
+	fast_sync: Option<bool>,
+	max_upload: Option<u32>,
+	max_download: Option<u32>,
+	peer_max_upload: Option<u32>,
+	peer_max_download: Option<u32>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -362,12 +426,15 @@ struct Footprint {
 	cache_size_blocks: Option<u32>,
 	cache_size_queue: Option<u32>,
 	db_compaction: Option<String>,
-	fat_db: Option<bool>,
+	fat_db: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
 struct Snapshots {
 	disable_periodic: Option<bool>,
+	period: Option<u64>,
+	history: Option<u64>,
+	incremental_period: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, RustcDecodable)]
@@ -436,15 +503,21 @@ mod tests {
 			cmd_account: false,
 			cmd_new: false,
 			cmd_list: false,
+			cmd_backup: false,
+			cmd_upgrade_kdf: false,
 			cmd_export: false,
+			cmd_export_state: false,
 			cmd_import: false,
 			cmd_signer: false,
 			cmd_new_token: false,
 			cmd_snapshot: false,
+			cmd_inspect: false,
+			cmd_verify: false,
 			cmd_restore: false,
 			cmd_ui: false,
 			cmd_tools: false,
 			cmd_hash: false,
+			cmd_nodes: false,
 
 			// Arguments
 			arg_pid_file: "".into(),
@@ -464,12 +537,20 @@ mod tests {
 			flag_unlock: Some("0xdeadbeefcafe0000000000000000000000000000".into()),
 			flag_password: vec!["~/.safe/password.file".into()],
 			flag_keys_iterations: 10240u32,
+			flag_keys_kdf: "scrypt".into(),
+			flag_keys_scrypt_n: 262144u32,
+			flag_keys_scrypt_r: 8u32,
+			flag_keys_scrypt_p: 1u32,
+			flag_no_hardware_wallets: false,
+			flag_signing_policies: Some("./signing_policies.json".into()),
 
 			flag_force_signer: false,
 			flag_no_signer: false,
 			flag_signer_port: 8180u16,
 			flag_signer_interface: "127.0.0.1".into(),
 			flag_signer_path: "$HOME/.parity/signer".into(),
+			flag_signer_queue_limit: 50usize,
+			flag_signer_request_expiry: 0u64,
 			flag_signer_no_validation: false,
 
 			// -- Networking Options
@@ -484,6 +565,14 @@ mod tests {
 			flag_node_key: None,
 			flag_reserved_peers: Some("./path_to_file".into()),
 			flag_reserved_only: false,
+			flag_allow_peers: None,
+			flag_deny_peers: Some("./path_to_deny_file".into()),
+			flag_checkpoint: None,
+			flag_fast_sync: false,
+			flag_max_upload: 0u32,
+			flag_max_download: 0u32,
+			flag_peer_max_upload: 0u32,
+			flag_peer_max_download: 0u32,
 
 			// -- API and Console Options
 			// RPC
@@ -535,16 +624,23 @@ mod tests {
 			flag_cache_size: Some(128),
 			flag_fast_and_loose: false,
 			flag_db_compaction: "ssd".into(),
-			flag_fat_db: false,
+			flag_fat_db: "auto".into(),
 
 			// -- Import/Export Options
 			flag_from: "1".into(),
 			flag_to: "latest".into(),
 			flag_format: None,
+			flag_min_balance: None,
+			flag_addresses: None,
+			flag_with_storage: false,
+			flag_as_spec: false,
 
 			// -- Snapshot Optons
 			flag_at: "latest".into(),
 			flag_no_periodic_snapshot: false,
+			flag_snapshot_period: 10000u64,
+			flag_snapshot_history: 500u64,
+			flag_incremental_snapshot_period: 0u64,
 
 			// -- Virtual Machine Options
 			flag_jitvm: false,
@@ -617,6 +713,12 @@ mod tests {
 				unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
 				password: Some(vec!["passwdfile path".into()]),
 				keys_iterations: None,
+				keys_kdf: None,
+				keys_scrypt_n: None,
+				keys_scrypt_r: None,
+				keys_scrypt_p: None,
+				disable_hardware_wallets: None,
+				signing_policies: None,
 			}),
 			signer: Some(Signer {
 				force: None,
@@ -624,6 +726,8 @@ mod tests {
 				port: None,
 				interface: None,
 				path: None,
+				queue_limit: None,
+				request_expiry: None,
 			}),
 			network: Some(Network {
 				disable: Some(false),
@@ -637,6 +741,14 @@ mod tests {
 				node_key: None,
 				reserved_peers: Some("./path/to/reserved_peers".into()),
 				reserved_only: Some(true),
+				allow_peers: None,
+				deny_peers: None,
+				checkpoint: None,
+				fast_sync: None,
+				max_upload: None,
+				max_download: None,
+				peer_max_upload: Some(256),
+				peer_max_download: None,
 			}),
 			rpc: Some(Rpc {
 				disable: Some(true),
@@ -687,10 +799,13 @@ mod tests {
 				cache_size_blocks: Some(16),
 				cache_size_queue: Some(100),
 				db_compaction: Some("ssd".into()),
-				fat_db: Some(true),
+				fat_db: Some("on".into()),
 			}),
 			snapshots: Some(Snapshots {
 				disable_periodic: Some(true),
+				period: None,
+				history: None,
+				incremental_period: Some(1000),
 			}),
 			vm: Some(VM {
 				jit: Some(false),
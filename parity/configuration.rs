@@ -25,21 +25,23 @@ use util::log::Colour;
 use ethsync::{NetworkConfiguration, is_valid_node_url};
 use ethcore::client::{VMType, Mode};
 use ethcore::miner::MinerOptions;
+use ethcore::spec::Checkpoint;
+use ethcore::ethstore::{KdfParams, ScryptParams};
 
 use rpc::{IpcConfiguration, HttpConfiguration};
 use ethcore_rpc::NetworkSettings;
 use cache::CacheConfig;
-use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, replace_home,
+use helpers::{to_duration, to_mode, to_block_id, to_u256, to_checkpoint, to_pending_set, to_price, replace_home,
 geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address};
-use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, SpecType};
+use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, SpecType, SnapshotConfiguration};
 use ethcore_logger::Config as LogConfig;
 use dir::Directories;
 use dapps::Configuration as DappsConfiguration;
 use signer::Configuration as SignerConfiguration;
 use run::RunCmd;
-use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat};
+use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, ExportState, DataFormat};
 use presale::ImportWallet;
-use account::{AccountCmd, NewAccount, ImportAccounts};
+use account::{AccountCmd, NewAccount, ImportAccounts, BackupAccounts, RestoreAccounts, UpgradeKdf};
 use snapshot::{self, SnapshotCommand};
 
 #[derive(Debug, PartialEq)]
@@ -52,6 +54,7 @@ pub enum Cmd {
 	SignerToken(String),
 	Snapshot(SnapshotCommand),
 	Hash(Option<String>),
+	Nodes(String),
 }
 
 #[derive(Debug, PartialEq)]
@@ -84,6 +87,7 @@ impl Configuration {
 		let cache_config = self.cache_config();
 		let spec = try!(self.chain().parse());
 		let tracing = try!(self.args.flag_tracing.parse());
+		let fat_db = try!(self.args.flag_fat_db.parse());
 		let compaction = try!(self.args.flag_db_compaction.parse());
 		let wal = !self.args.flag_fast_and_loose;
 		let enable_network = self.enable_network(&mode);
@@ -92,6 +96,8 @@ impl Configuration {
 		let dapps_conf = self.dapps_config();
 		let signer_conf = self.signer_config();
 		let format = try!(self.format());
+		let extra_chains = try!(self.extra_chains());
+		let several_chains = !extra_chains.is_empty();
 
 		let cmd = if self.args.flag_version {
 			Cmd::Version
@@ -99,10 +105,12 @@ impl Configuration {
 			Cmd::SignerToken(dirs.signer)
 		} else if self.args.cmd_tools && self.args.cmd_hash {
 			Cmd::Hash(self.args.arg_file)
+		} else if self.args.cmd_tools && self.args.cmd_nodes {
+			Cmd::Nodes(try!(self.net_specific_path()).to_str().unwrap().to_owned())
 		} else if self.args.cmd_account {
 			let account_cmd = if self.args.cmd_new {
 				let new_acc = NewAccount {
-					iterations: self.args.flag_keys_iterations,
+					kdf: try!(self.keys_kdf()),
 					path: dirs.keys,
 					password_file: self.args.flag_password.first().cloned(),
 				};
@@ -115,13 +123,35 @@ impl Configuration {
 					to: dirs.keys,
 				};
 				AccountCmd::Import(import_acc)
+			} else if self.args.cmd_backup {
+				let backup_acc = BackupAccounts {
+					path: dirs.keys,
+					file: self.args.arg_file.clone().expect("<file> is required by the command; qed"),
+					password_file: self.args.flag_password.first().cloned(),
+					scrypt: try!(self.keys_scrypt()),
+				};
+				AccountCmd::Backup(backup_acc)
+			} else if self.args.cmd_restore {
+				let restore_acc = RestoreAccounts {
+					path: dirs.keys,
+					file: self.args.arg_file.clone().expect("<file> is required by the command; qed"),
+					password_file: self.args.flag_password.first().cloned(),
+				};
+				AccountCmd::Restore(restore_acc)
+			} else if self.args.cmd_upgrade_kdf {
+				let upgrade_acc = UpgradeKdf {
+					path: dirs.keys,
+					password_files: self.args.flag_password.clone(),
+					kdf: try!(self.keys_kdf()),
+				};
+				AccountCmd::UpgradeKdf(upgrade_acc)
 			} else {
 				unreachable!();
 			};
 			Cmd::Account(account_cmd)
 		} else if self.args.cmd_wallet {
 			let presale_cmd = ImportWallet {
-				iterations: self.args.flag_keys_iterations,
+				kdf: try!(self.keys_kdf()),
 				path: dirs.keys,
 				wallet_path: self.args.arg_path.first().unwrap().clone(),
 				password_file: self.args.flag_password.first().cloned(),
@@ -140,6 +170,7 @@ impl Configuration {
 				wal: wal,
 				mode: mode,
 				tracing: tracing,
+				fat_db: fat_db,
 				vm_type: vm_type,
 			};
 			Cmd::Blockchain(BlockchainCmd::Import(import_cmd))
@@ -156,10 +187,34 @@ impl Configuration {
 				wal: wal,
 				mode: mode,
 				tracing: tracing,
+				fat_db: fat_db,
 				from_block: try!(to_block_id(&self.args.flag_from)),
 				to_block: try!(to_block_id(&self.args.flag_to)),
 			};
 			Cmd::Blockchain(BlockchainCmd::Export(export_cmd))
+		} else if self.args.cmd_export_state {
+			let export_cmd = ExportState {
+				spec: spec,
+				logger_config: logger_config,
+				cache_config: cache_config,
+				dirs: dirs,
+				file_path: self.args.arg_file.clone(),
+				pruning: pruning,
+				compaction: compaction,
+				wal: wal,
+				mode: mode,
+				tracing: tracing,
+				fat_db: fat_db,
+				at: try!(to_block_id(&self.args.flag_at)),
+				min_balance: match self.args.flag_min_balance {
+					Some(ref balance) => Some(try!(to_u256(balance))),
+					None => None,
+				},
+				addresses: try!(to_addresses(&self.args.flag_addresses)),
+				storage: self.args.flag_with_storage,
+				as_spec: self.args.flag_as_spec,
+			};
+			Cmd::Blockchain(BlockchainCmd::ExportState(export_cmd))
 		} else if self.args.cmd_snapshot {
 			let snapshot_cmd = SnapshotCommand {
 				cache_config: cache_config,
@@ -172,7 +227,13 @@ impl Configuration {
 				compaction: compaction,
 				file_path: self.args.arg_file.clone(),
 				wal: wal,
-				kind: snapshot::Kind::Take,
+				kind: if self.args.cmd_inspect {
+					snapshot::Kind::Inspect
+				} else if self.args.cmd_verify {
+					snapshot::Kind::Verify
+				} else {
+					snapshot::Kind::Take
+				},
 				block_at: try!(to_block_id(&self.args.flag_at)),
 			};
 			Cmd::Snapshot(snapshot_cmd)
@@ -198,6 +259,7 @@ impl Configuration {
 			} else {
 				None
 			};
+			try!(self.check_extra_rpc_ports(&http_conf, &dapps_conf, signer_port, extra_chains.len()));
 
 			let run_cmd = RunCmd {
 				cache_config: cache_config,
@@ -216,6 +278,7 @@ impl Configuration {
 				miner_extras: try!(self.miner_extras()),
 				mode: mode,
 				tracing: tracing,
+				fat_db: fat_db,
 				compaction: compaction,
 				wal: wal,
 				vm_type: vm_type,
@@ -229,11 +292,19 @@ impl Configuration {
 				name: self.args.flag_identity,
 				custom_bootnodes: self.args.flag_bootnodes.is_some(),
 				no_periodic_snapshot: self.args.flag_no_periodic_snapshot,
+				snapshot_conf: try!(self.snapshot_config()),
+				checkpoint: try!(self.checkpoint()),
+				fast_sync: self.args.flag_fast_sync,
+				extra_chains: extra_chains,
 			};
 			Cmd::Run(run_cmd)
 		};
 
-		Ok(cmd)
+		match cmd {
+			Cmd::Run(_) | Cmd::Version => Ok(cmd),
+			_ if several_chains => Err("Only one chain can be given with --chain for this command.".into()),
+			_ => Ok(cmd),
+		}
 	}
 
 	fn enable_network(&self, mode: &Mode) -> bool {
@@ -293,10 +364,41 @@ impl Configuration {
 		if self.args.flag_testnet {
 			"morden".to_owned()
 		} else {
-			self.args.flag_chain.clone()
+			self.args.flag_chain.split(',').next().unwrap_or("").to_owned()
 		}
 	}
 
+	/// Chains to run alongside the main one, given after it in the `--chain` list.
+	fn extra_chains(&self) -> Result<Vec<SpecType>, String> {
+		self.args.flag_chain.split(',').skip(1).map(|chain| chain.parse()).collect()
+	}
+
+	/// Makes sure the HTTP JSON-RPC ports of the extra chains (following the main one) are not taken by other servers.
+	fn check_extra_rpc_ports(&self, http_conf: &HttpConfiguration, dapps_conf: &DappsConfiguration, signer_port: Option<u16>, extra_chains: usize) -> Result<(), String> {
+		if !http_conf.enabled {
+			return Ok(());
+		}
+		for i in 0..extra_chains {
+			let port = match http_conf.port.checked_add(i as u16 + 1) {
+				Some(port) => port,
+				None => return Err(format!("No JSON-RPC port left for chain #{} given with --chain.", i + 2)),
+			};
+			let taken_by = if dapps_conf.enabled && dapps_conf.port == port {
+				Some("--dapps-port")
+			} else if signer_port == Some(port) {
+				Some("--signer-port")
+			} else if self.args.flag_port == port {
+				Some("--port")
+			} else {
+				None
+			};
+			if let Some(flag) = taken_by {
+				return Err(format!("JSON-RPC port {} of chain #{} given with --chain is already used by {}.", port, i + 2, flag));
+			}
+		}
+		Ok(())
+	}
+
 	fn max_peers(&self) -> u32 {
 		let peers = self.args.flag_max_peers as u32;
 		max(self.min_peers(), peers)
@@ -310,13 +412,48 @@ impl Configuration {
 		self.args.flag_notify_work.as_ref().map_or_else(Vec::new, |s| s.split(',').map(|s| s.to_owned()).collect())
 	}
 
+	fn keys_scrypt(&self) -> Result<ScryptParams, String> {
+		let params = ScryptParams {
+			n: self.args.flag_keys_scrypt_n,
+			p: self.args.flag_keys_scrypt_p,
+			r: self.args.flag_keys_scrypt_r,
+		};
+		if params.n < 2 || !params.n.is_power_of_two() {
+			return Err(format!("Invalid --keys-scrypt-n: {}. It has to be a power of 2.", params.n));
+		}
+		if params.p == 0 || params.r == 0 {
+			return Err("Scrypt block size and parallelization have to be greater than 0.".into());
+		}
+		Ok(params)
+	}
+
+	fn keys_kdf(&self) -> Result<KdfParams, String> {
+		let defaults = Args::default();
+		let iterations_changed = self.args.flag_keys_iterations != defaults.flag_keys_iterations;
+		let scrypt_changed = self.args.flag_keys_scrypt_n != defaults.flag_keys_scrypt_n ||
+			self.args.flag_keys_scrypt_p != defaults.flag_keys_scrypt_p ||
+			self.args.flag_keys_scrypt_r != defaults.flag_keys_scrypt_r;
+
+		match self.args.flag_keys_kdf.as_ref() {
+			"scrypt" if iterations_changed =>
+				Err("--keys-iterations only applies to PBKDF2. Use --keys-kdf pbkdf2 or the --keys-scrypt-* options.".into()),
+			"scrypt" => Ok(KdfParams::Scrypt(try!(self.keys_scrypt()))),
+			"pbkdf2" if scrypt_changed =>
+				Err("--keys-scrypt-* options only apply to scrypt. Use --keys-kdf scrypt or --keys-iterations.".into()),
+			"pbkdf2" => Ok(KdfParams::Pbkdf2(self.args.flag_keys_iterations)),
+			other => Err(format!("Invalid key derivation function: {}", other)),
+		}
+	}
+
 	fn accounts_config(&self) -> Result<AccountsConfig, String> {
 		let cfg = AccountsConfig {
-			iterations: self.args.flag_keys_iterations,
+			kdf: try!(self.keys_kdf()),
 			import_keys: self.args.flag_import_geth_keys,
 			testnet: self.args.flag_testnet,
 			password_files: self.args.flag_password.clone(),
 			unlocked_accounts: try!(to_addresses(&self.args.flag_unlock)),
+			enable_hardware_wallets: !self.args.flag_no_hardware_wallets,
+			signing_policies: self.args.flag_signing_policies.clone(),
 		};
 
 		Ok(cfg)
@@ -351,6 +488,11 @@ impl Configuration {
 			interface: self.signer_interface(),
 			signer_path: self.directories().signer,
 			skip_origin_validation: self.args.flag_signer_no_validation,
+			queue_limit: self.args.flag_signer_queue_limit,
+			request_expiry: match self.args.flag_signer_request_expiry {
+				0 => None,
+				secs => Some(secs),
+			},
 		}
 	}
 
@@ -393,6 +535,24 @@ impl Configuration {
 		Ok(GasPricerConfig::Fixed(U256::from_dec_str(&format!("{:.0}", wei_per_gas)).unwrap()))
 	}
 
+	fn snapshot_config(&self) -> Result<SnapshotConfiguration, String> {
+		let conf = SnapshotConfiguration {
+			period: self.args.flag_snapshot_period,
+			history: self.args.flag_snapshot_history,
+			incremental_period: self.args.flag_incremental_snapshot_period,
+		};
+
+		if conf.period == 0 {
+			return Err("Snapshot period must be greater than 0".into());
+		}
+
+		if conf.incremental_period >= conf.period {
+			return Err("Incremental snapshot period must be less than the snapshot period".into());
+		}
+
+		Ok(conf)
+	}
+
 	fn extra_data(&self) -> Result<Bytes, String> {
 		match self.args.flag_extradata.as_ref().or(self.args.flag_extra_data.as_ref()) {
 			Some(x) if x.len() <= 32 => Ok(x.as_bytes().to_owned()),
@@ -419,6 +579,20 @@ impl Configuration {
 		}
 	}
 
+	fn init_peer_rules(path: &Option<String>) -> Result<Vec<String>, String> {
+		use std::fs::File;
+
+		match *path {
+			Some(ref path) => {
+				let mut buffer = String::new();
+				let mut rules_file = try!(File::open(path).map_err(|e| format!("Error opening peer rules file: {}", e)));
+				try!(rules_file.read_to_string(&mut buffer).map_err(|_| "Error reading peer rules file"));
+				Ok(buffer.lines().map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect())
+			},
+			None => Ok(Vec::new())
+		}
+	}
+
 	fn net_addresses(&self) -> Result<(Option<SocketAddr>, Option<SocketAddr>), String> {
 		let port = self.args.flag_port;
 		let listen_address = Some(SocketAddr::new("0.0.0.0".parse().unwrap(), port));
@@ -443,16 +617,30 @@ impl Configuration {
 		ret.discovery_enabled = !self.args.flag_no_discovery && !self.args.flag_nodiscover;
 		ret.max_peers = self.max_peers();
 		ret.min_peers = self.min_peers();
-		let mut net_path = PathBuf::from(self.directories().db);
-		net_path.push("network");
-		let net_specific_path = net_path.join(&try!(self.network_specific_path()));
-		ret.config_path = Some(net_path.to_str().unwrap().to_owned());
-		ret.net_config_path = Some(net_specific_path.to_str().unwrap().to_owned());
+		ret.config_path = Some(self.net_path().to_str().unwrap().to_owned());
+		ret.net_config_path = Some(try!(self.net_specific_path()).to_str().unwrap().to_owned());
 		ret.reserved_nodes = try!(self.init_reserved_nodes());
 		ret.allow_non_reserved = !self.args.flag_reserved_only;
+		ret.allow_peers = try!(Self::init_peer_rules(&self.args.flag_allow_peers));
+		ret.deny_peers = try!(Self::init_peer_rules(&self.args.flag_deny_peers));
+		let limit = |kbps: u32| if kbps == 0 { None } else { Some(kbps as u64 * 1024) };
+		ret.upload_limit = limit(self.args.flag_max_upload);
+		ret.download_limit = limit(self.args.flag_max_download);
+		ret.peer_upload_limit = limit(self.args.flag_peer_max_upload);
+		ret.peer_download_limit = limit(self.args.flag_peer_max_download);
 		Ok(ret)
 	}
 
+	fn net_path(&self) -> PathBuf {
+		let mut net_path = PathBuf::from(self.directories().db);
+		net_path.push("network");
+		net_path
+	}
+
+	fn net_specific_path(&self) -> Result<PathBuf, String> {
+		Ok(self.net_path().join(&try!(self.network_specific_path())))
+	}
+
 	fn network_specific_path(&self) -> Result<PathBuf, String> {
 		let spec_type : SpecType = try!(self.chain().parse());
 		let spec = try!(spec_type.spec());
@@ -470,6 +658,13 @@ impl Configuration {
 		}
 	}
 
+	fn checkpoint(&self) -> Result<Option<Checkpoint>, String> {
+		match self.args.flag_checkpoint {
+			Some(ref checkpoint) => to_checkpoint(checkpoint).map(Some),
+			None => Ok(None),
+		}
+	}
+
 	fn rpc_apis(&self) -> String {
 		self.args.flag_rpcapi.clone().unwrap_or(self.args.flag_jsonrpc_apis.clone())
 	}
@@ -636,9 +831,12 @@ mod tests {
 	use helpers::{replace_home, default_network_config};
 	use run::RunCmd;
 	use signer::Configuration as SignerConfiguration;
-	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat};
+	use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, ExportState, DataFormat};
 	use presale::ImportWallet;
-	use account::{AccountCmd, NewAccount, ImportAccounts};
+	use account::{AccountCmd, NewAccount, ImportAccounts, BackupAccounts, RestoreAccounts, UpgradeKdf};
+	use ethcore::ethstore::{KdfParams, ScryptParams};
+	use snapshot::{self, SnapshotCommand};
+	use params::{SnapshotConfiguration, SpecType};
 	use devtools::{RandomTempPath};
 	use std::io::Write;
 	use std::fs::{File, create_dir};
@@ -659,17 +857,68 @@ mod tests {
 		assert_eq!(conf.into_command().unwrap(), Cmd::Version);
 	}
 
+	#[test]
+	fn test_command_tools_nodes() {
+		let args = vec!["parity", "tools", "nodes", "--chain", "morden"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Nodes(replace_home("$HOME/.parity/network/2")));
+	}
+
 	#[test]
 	fn test_command_account_new() {
 		let args = vec!["parity", "account", "new"];
 		let conf = parse(&args);
 		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::New(NewAccount {
-			iterations: 10240,
+			kdf: KdfParams::Scrypt(ScryptParams { n: 262144, p: 1, r: 8 }),
 			path: replace_home("$HOME/.parity/keys"),
 			password_file: None,
 		})));
 	}
 
+	#[test]
+	fn test_command_account_new_with_pbkdf2() {
+		let args = vec!["parity", "account", "new", "--keys-kdf", "pbkdf2", "--keys-iterations", "20480"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::New(NewAccount {
+			kdf: KdfParams::Pbkdf2(20480),
+			path: replace_home("$HOME/.parity/keys"),
+			password_file: None,
+		})));
+	}
+
+	#[test]
+	fn test_command_account_upgrade_kdf() {
+		let args = vec!["parity", "account", "upgrade-kdf", "--password", "pwd", "--keys-scrypt-n", "1048576"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::UpgradeKdf(UpgradeKdf {
+			path: replace_home("$HOME/.parity/keys"),
+			password_files: vec!["pwd".into()],
+			kdf: KdfParams::Scrypt(ScryptParams { n: 1048576, p: 1, r: 8 }),
+		})));
+	}
+
+	#[test]
+	fn should_reject_invalid_scrypt_params() {
+		let args = vec!["parity", "account", "new", "--keys-kdf", "scrypt", "--keys-scrypt-n", "1000"];
+		let conf = parse(&args);
+		assert!(conf.into_command().is_err());
+	}
+
+	#[test]
+	fn should_reject_kdf_options_that_do_not_apply() {
+		let args = vec!["parity", "account", "new", "--keys-kdf", "scrypt", "--keys-iterations", "20480"];
+		let conf = parse(&args);
+		assert!(conf.into_command().is_err());
+
+		let args = vec!["parity", "account", "new", "--keys-iterations", "20480"];
+		let conf = parse(&args);
+		assert!(conf.into_command().is_err());
+
+		let args = vec!["parity", "account", "new", "--keys-kdf", "pbkdf2", "--keys-scrypt-n", "1048576"];
+		let conf = parse(&args);
+		assert!(conf.into_command().is_err());
+	}
+
 	#[test]
 	fn test_command_account_list() {
 		let args = vec!["parity", "account", "list"];
@@ -689,12 +938,35 @@ mod tests {
 		})));
 	}
 
+	#[test]
+	fn test_command_account_backup() {
+		let args = vec!["parity", "account", "backup", "backup.json", "--password", "pwd"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::Backup(BackupAccounts {
+			path: replace_home("$HOME/.parity/keys"),
+			file: "backup.json".into(),
+			password_file: Some("pwd".into()),
+			scrypt: Default::default(),
+		})));
+	}
+
+	#[test]
+	fn test_command_account_restore() {
+		let args = vec!["parity", "account", "restore", "backup.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Account(AccountCmd::Restore(RestoreAccounts {
+			path: replace_home("$HOME/.parity/keys"),
+			file: "backup.json".into(),
+			password_file: None,
+		})));
+	}
+
 	#[test]
 	fn test_command_wallet_import() {
 		let args = vec!["parity", "wallet", "import", "my_wallet.json", "--password", "pwd"];
 		let conf = parse(&args);
 		assert_eq!(conf.into_command().unwrap(), Cmd::ImportPresaleWallet(ImportWallet {
-			iterations: 10240,
+			kdf: Default::default(),
 			path: replace_home("$HOME/.parity/keys"),
 			wallet_path: "my_wallet.json".into(),
 			password_file: Some("pwd".into()),
@@ -717,6 +989,7 @@ mod tests {
 			wal: true,
 			mode: Default::default(),
 			tracing: Default::default(),
+			fat_db: Default::default(),
 			vm_type: VMType::Interpreter,
 		})));
 	}
@@ -737,6 +1010,7 @@ mod tests {
 			wal: true,
 			mode: Default::default(),
 			tracing: Default::default(),
+			fat_db: Default::default(),
 			from_block: BlockID::Number(1),
 			to_block: BlockID::Latest,
 		})));
@@ -758,11 +1032,56 @@ mod tests {
 			wal: true,
 			mode: Default::default(),
 			tracing: Default::default(),
+			fat_db: Default::default(),
 			from_block: BlockID::Number(1),
 			to_block: BlockID::Latest,
 		})));
 	}
 
+	#[test]
+	fn test_command_state_export() {
+		let args = vec!["parity", "export-state", "--at", "100", "--min-balance", "1000", "--addresses", "0x1,0x2", "--with-storage", "state.json"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Blockchain(BlockchainCmd::ExportState(ExportState {
+			spec: Default::default(),
+			logger_config: Default::default(),
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			file_path: Some("state.json".into()),
+			pruning: Default::default(),
+			compaction: Default::default(),
+			wal: true,
+			mode: Default::default(),
+			tracing: Default::default(),
+			fat_db: Default::default(),
+			at: BlockID::Number(100),
+			min_balance: Some(1000.into()),
+			addresses: vec![1.into(), 2.into()],
+			storage: true,
+			as_spec: false,
+		})));
+	}
+
+	#[test]
+	fn test_command_snapshot_inspect() {
+		let args = vec!["parity", "snapshot", "inspect", "snapshot.bin"];
+		let conf = parse(&args);
+		assert_eq!(conf.into_command().unwrap(), Cmd::Snapshot(SnapshotCommand {
+			cache_config: Default::default(),
+			dirs: Default::default(),
+			spec: Default::default(),
+			pruning: Default::default(),
+			logger_config: Default::default(),
+			mode: Default::default(),
+			tracing: Default::default(),
+			compaction: Default::default(),
+			file_path: Some("snapshot.bin".into()),
+			wal: true,
+			kind: snapshot::Kind::Inspect,
+			block_at: BlockID::Latest,
+		}));
+	}
+
 	#[test]
 	fn test_command_signer_new_token() {
 		let args = vec!["parity", "signer", "new-token"];
@@ -792,6 +1111,7 @@ mod tests {
 			miner_extras: Default::default(),
 			mode: Default::default(),
 			tracing: Default::default(),
+			fat_db: Default::default(),
 			compaction: Default::default(),
 			wal: true,
 			vm_type: Default::default(),
@@ -805,6 +1125,10 @@ mod tests {
 			name: "".into(),
 			custom_bootnodes: false,
 			no_periodic_snapshot: false,
+			snapshot_conf: Default::default(),
+			checkpoint: None,
+			fast_sync: false,
+			extra_chains: Vec::new(),
 		}));
 	}
 
@@ -826,6 +1150,75 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn should_parse_snapshot_settings() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--snapshot-period", "5000", "--snapshot-history", "100", "--incremental-snapshot-period", "1000"]);
+		let bad_period = parse(&["parity", "--snapshot-period", "1000", "--incremental-snapshot-period", "1000"]);
+
+		// then
+		assert_eq!(conf.snapshot_config(), Ok(SnapshotConfiguration {
+			period: 5000,
+			history: 100,
+			incremental_period: 1000,
+		}));
+		assert!(bad_period.snapshot_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_chain_list() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--chain", "homestead,./private.json"]);
+
+		// then
+		assert_eq!(conf.chain(), "homestead".to_owned());
+		assert_eq!(conf.extra_chains(), Ok(vec![SpecType::Custom("./private.json".into())]));
+		assert_eq!(parse(&["parity"]).extra_chains(), Ok(Vec::new()));
+	}
+
+	#[test]
+	fn should_reject_several_chains_outside_run() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "export", "blockchain.json", "--chain", "homestead,morden"]);
+
+		// then
+		assert!(conf.into_command().is_err());
+	}
+
+	#[test]
+	fn should_reject_extra_chain_port_collisions() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--chain", "homestead,morden", "--jsonrpc-port", "8545", "--port", "8546"]);
+		let free = parse(&["parity", "--chain", "homestead,morden", "--jsonrpc-port", "8545", "--port", "8547"]);
+
+		// then
+		assert!(conf.into_command().is_err());
+		assert!(free.into_command().is_ok());
+	}
+
+	#[test]
+	fn should_parse_bandwidth_limits() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--max-upload", "1000", "--peer-max-download", "64"]);
+
+		// then
+		let net = conf.net_config().unwrap();
+		assert_eq!(net.upload_limit, Some(1024000));
+		assert_eq!(net.download_limit, None);
+		assert_eq!(net.peer_upload_limit, None);
+		assert_eq!(net.peer_download_limit, Some(65536));
+	}
+
 	#[test]
 	fn should_parse_rpc_settings_with_geth_compatiblity() {
 		// given
@@ -924,6 +1317,7 @@ mod tests {
 		let conf1 = parse(&["parity", "--signer-path", "signer", "--signer-no-validation"]);
 		let conf2 = parse(&["parity", "--signer-path", "signer", "--signer-port", "3123"]);
 		let conf3 = parse(&["parity", "--signer-path", "signer", "--signer-interface", "test"]);
+		let conf4 = parse(&["parity", "--signer-path", "signer", "--signer-queue-limit", "10", "--signer-request-expiry", "600"]);
 
 		// then
 		assert_eq!(conf0.signer_config(), SignerConfiguration {
@@ -932,6 +1326,8 @@ mod tests {
 			interface: "127.0.0.1".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: false,
+			queue_limit: 50,
+			request_expiry: None,
 		});
 		assert_eq!(conf1.signer_config(), SignerConfiguration {
 			enabled: true,
@@ -939,6 +1335,8 @@ mod tests {
 			interface: "127.0.0.1".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: true,
+			queue_limit: 50,
+			request_expiry: None,
 		});
 		assert_eq!(conf2.signer_config(), SignerConfiguration {
 			enabled: true,
@@ -946,6 +1344,8 @@ mod tests {
 			interface: "127.0.0.1".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: false,
+			queue_limit: 50,
+			request_expiry: None,
 		});
 		assert_eq!(conf3.signer_config(), SignerConfiguration {
 			enabled: true,
@@ -953,6 +1353,17 @@ mod tests {
 			interface: "test".into(),
 			signer_path: "signer".into(),
 			skip_origin_validation: false,
+			queue_limit: 50,
+			request_expiry: None,
+		});
+		assert_eq!(conf4.signer_config(), SignerConfiguration {
+			enabled: true,
+			port: 8180,
+			interface: "127.0.0.1".into(),
+			signer_path: "signer".into(),
+			skip_origin_validation: false,
+			queue_limit: 10,
+			request_expiry: Some(600),
 		});
 	}
 
@@ -966,5 +1377,18 @@ mod tests {
 		let conf = Configuration::parse(&args).unwrap();
 		assert!(conf.init_reserved_nodes().is_ok());
 	}
+
+	#[test]
+	fn should_read_peer_rules() {
+		let temp = RandomTempPath::new();
+		create_dir(temp.as_str().to_owned()).unwrap();
+		let filename = temp.as_str().to_owned() + "/deny";
+		File::create(filename.clone()).unwrap().write_all(b"10.0.0.0/8\n\n  client:^Geth/v1\\.4\\.1-\n").unwrap();
+		let args = vec!["parity", "--deny-peers", &filename];
+		let conf = Configuration::parse(&args).unwrap();
+		let net = conf.net_config().unwrap();
+		assert_eq!(net.deny_peers, vec!["10.0.0.0/8".to_owned(), "client:^Geth/v1\\.4\\.1-".to_owned()]);
+		assert!(net.allow_peers.is_empty());
+	}
 }
 
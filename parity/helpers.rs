@@ -21,8 +21,10 @@ use std::path::Path;
 use std::fs::File;
 use util::{clean_0x, U256, Uint, Address, path, CompactionProfile};
 use util::journaldb::Algorithm;
+use util::trie::TrieSpec;
 use ethcore::client::{Mode, BlockID, VMType, DatabaseCompactionProfile, ClientConfig};
 use ethcore::miner::PendingSet;
+use ethcore::spec::Checkpoint;
 use cache::CacheConfig;
 use dir::DatabaseDirectories;
 use upgrade::upgrade;
@@ -84,6 +86,19 @@ pub fn to_u256(s: &str) -> Result<U256, String> {
 	}
 }
 
+/// Parses a trusted checkpoint given as `NUMBER:HASH:TOTAL_DIFFICULTY`.
+pub fn to_checkpoint(s: &str) -> Result<Checkpoint, String> {
+	let parts = s.split(':').collect::<Vec<_>>();
+	if parts.len() != 3 {
+		return Err(format!("Invalid checkpoint: {}. Expected NUMBER:HASH:TOTAL_DIFFICULTY.", s));
+	}
+	Ok(Checkpoint {
+		number: try!(parts[0].parse().map_err(|_| format!("Invalid checkpoint block number: {}", parts[0]))),
+		hash: try!(clean_0x(parts[1]).parse().map_err(|_| format!("Invalid checkpoint block hash: {}", parts[1]))),
+		total_difficulty: try!(to_u256(parts[2])),
+	})
+}
+
 pub fn to_pending_set(s: &str) -> Result<PendingSet, String> {
 	match s {
 		"cheap" => Ok(PendingSet::AlwaysQueue),
@@ -183,6 +198,12 @@ pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 		min_peers: 25,
 		reserved_nodes: Vec::new(),
 		allow_non_reserved: true,
+		upload_limit: None,
+		download_limit: None,
+		peer_upload_limit: None,
+		peer_download_limit: None,
+		allow_peers: Vec::new(),
+		deny_peers: Vec::new(),
 	}
 }
 
@@ -191,6 +212,7 @@ pub fn to_client_config(
 		cache_config: &CacheConfig,
 		mode: Mode,
 		tracing: bool,
+		fat_db: bool,
 		compaction: DatabaseCompactionProfile,
 		wal: bool,
 		vm_type: VMType,
@@ -217,6 +239,7 @@ pub fn to_client_config(
 
 	client_config.mode = mode;
 	client_config.tracing.enabled = tracing;
+	client_config.trie_spec = if fat_db { TrieSpec::Fat } else { TrieSpec::Secure };
 	client_config.pruning = pruning;
 	client_config.db_compaction = compaction;
 	client_config.db_wal = wal;
@@ -267,6 +290,16 @@ pub fn password_prompt() -> Result<String, String> {
 	Ok(password)
 }
 
+/// Asks for a password of existing data.
+pub fn password_read() -> Result<String, String> {
+	use rpassword::read_password;
+
+	print!("Type password: ");
+	flush_stdout();
+
+	read_password().map_err(|e| format!("Unable to read password: {}", e))
+}
+
 /// Read a password from password file.
 pub fn password_from_file<P>(path: P) -> Result<String, String> where P: AsRef<Path> {
 	let mut file = try!(File::open(path).map_err(|_| "Unable to open password file."));
@@ -295,7 +328,8 @@ mod tests {
 	use util::{U256};
 	use ethcore::client::{Mode, BlockID};
 	use ethcore::miner::PendingSet;
-	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_address, to_addresses, to_price, geth_ipc_path, to_bootnodes};
+	use ethcore::spec::Checkpoint;
+	use super::{to_duration, to_mode, to_block_id, to_u256, to_checkpoint, to_pending_set, to_address, to_addresses, to_price, geth_ipc_path, to_bootnodes};
 
 	#[test]
 	fn test_to_duration() {
@@ -345,6 +379,19 @@ mod tests {
 		assert!(to_u256("u").is_err())
 	}
 
+	#[test]
+	fn test_to_checkpoint() {
+		let hash = "4985f5ca3d2afbec36529aa96f74de3cc10a2a4a6c44f2157a57d2c6059a11bb";
+		assert_eq!(to_checkpoint(&format!("1920000:0x{}:0x2e5efcb8acd4fcef03", hash)).unwrap(), Checkpoint {
+			number: 1920000,
+			hash: hash.parse().unwrap(),
+			total_difficulty: U256::from_dec_str("855394775976782917379").unwrap(),
+		});
+		assert!(to_checkpoint("1920000").is_err());
+		assert!(to_checkpoint(&format!("x:{}:1", hash)).is_err());
+		assert!(to_checkpoint("1:0x12:1").is_err());
+	}
+
 	#[test]
 	fn test_pending_set() {
 		assert_eq!(to_pending_set("cheap").unwrap(), PendingSet::AlwaysQueue);
@@ -126,11 +126,19 @@ impl Informant {
 				false => String::new(),
 			},
 			match (&sync_status, &network_config) {
-				(&Some(ref sync_info), &Some(ref net_config)) => format!("{}{}/{}/{} peers",
+				(&Some(ref sync_info), &Some(ref net_config)) => format!("{}{}{}{}/{}/{} peers",
 					match importing {
 						true => format!("{}   ", paint(Green.bold(), format!("{:>8}", format!("#{}", sync_info.last_imported_block_number.unwrap_or(chain_info.best_block_number))))),
 						false => String::new(),
 					},
+					match sync_info.ancient_block_number {
+						Some(ancient) => format!("Ancient {}   ", paint(Green.bold(), format!("{:>8}", format!("#{}", ancient)))),
+						None => String::new(),
+					},
+					match sync_info.state_nodes_remaining {
+						0 => String::new(),
+						remaining => format!("State {}   ", paint(Green.bold(), format!("{}/{}", sync_info.state_nodes_done, sync_info.state_nodes_done + remaining))),
+					},
 					paint(Cyan.bold(), format!("{:2}", sync_info.num_active_peers)),
 					paint(Cyan.bold(), format!("{:2}", sync_info.num_peers)),
 					paint(Cyan.bold(), format!("{:2}", sync_info.current_max_peers(net_config.min_peers, net_config.max_peers))),
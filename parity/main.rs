@@ -131,6 +131,26 @@ fn print_hash_of(maybe_file: Option<String>) -> Result<String, String> {
 	}
 }
 
+fn print_nodes(path: String) -> Result<String, String> {
+	let nodes = ethsync::saved_nodes(&path);
+	if nodes.is_empty() {
+		return Ok(format!("No nodes saved in {}", path));
+	}
+	let optional = |v: Option<u64>| v.map_or("never".to_owned(), |t| format!("{}", t));
+	let lines: Vec<String> = nodes.into_iter().map(|n| format!(
+		"{}\n  client: {}, reputation: {}, failures: {} ({} recent), last seen: {}, last session: {}, session time: {}s",
+		n.url,
+		n.client_version.unwrap_or_else(|| "unknown".into()),
+		n.reputation,
+		n.failures,
+		n.recent_failures.len(),
+		optional(n.last_seen),
+		optional(n.last_session),
+		n.session_time
+	)).collect();
+	Ok(lines.join("\n"))
+}
+
 fn execute(command: Cmd) -> Result<String, String> {
 	match command {
 		Cmd::Run(run_cmd) => {
@@ -139,6 +159,7 @@ fn execute(command: Cmd) -> Result<String, String> {
 		},
 		Cmd::Version => Ok(Args::print_version()),
 		Cmd::Hash(maybe_file) => print_hash_of(maybe_file),
+		Cmd::Nodes(path) => print_nodes(path),
 		Cmd::Account(account_cmd) => account::execute(account_cmd),
 		Cmd::ImportPresaleWallet(presale_cmd) => presale::execute(presale_cmd),
 		Cmd::Blockchain(blockchain_cmd) => blockchain::execute(blockchain_cmd),
@@ -66,6 +66,9 @@ pub type SyncModules = (
 #[cfg(not(feature="ipc"))]
 pub type SyncModules = (Arc<SyncProvider>, Arc<ManageNetwork>, Arc<ChainNotify>);
 
+/// Sync configuration, client and snapshot service of a chain synced alongside the main one.
+pub type ExtraChain = (SyncConfig, Arc<BlockChainClient>, Arc<SnapshotService>);
+
 #[cfg(feature="ipc")]
 mod ipc_deps {
 	pub use ethsync::{SyncClient, NetworkManagerClient, ServiceConfiguration};
@@ -123,9 +126,10 @@ pub fn sync
 		net_cfg: NetworkConfiguration,
 		_client: Arc<BlockChainClient>,
 		_snapshot_service: Arc<SnapshotService>,
+		_extra_chains: Vec<ExtraChain>,
 		log_settings: &LogConfig,
 	)
-	-> Result<SyncModules, NetworkError>
+	-> Result<(SyncModules, Vec<SyncModules>), NetworkError>
 {
 	let mut hypervisor = hypervisor_ref.take().expect("There should be hypervisor for ipc configuration");
 	let args = sync_arguments(&hypervisor.io_path, sync_cfg, net_cfg, log_settings);
@@ -142,7 +146,8 @@ pub fn sync
 		&service_urls::with_base(&hypervisor.io_path, service_urls::NETWORK_MANAGER)).unwrap();
 
 	*hypervisor_ref = Some(hypervisor);
-	Ok((sync_client, manage_client, notify_client))
+	// the sync module process serves a single chain
+	Ok(((sync_client, manage_client, notify_client), Vec::new()))
 }
 
 #[cfg(not(feature="ipc"))]
@@ -153,10 +158,16 @@ pub fn sync
 		net_cfg: NetworkConfiguration,
 		client: Arc<BlockChainClient>,
 		snapshot_service: Arc<SnapshotService>,
+		extra_chains: Vec<ExtraChain>,
 		_log_settings: &LogConfig,
 	)
-	-> Result<SyncModules, NetworkError>
+	-> Result<(SyncModules, Vec<SyncModules>), NetworkError>
 {
-	let eth_sync = try!(EthSync::new(sync_cfg, client, snapshot_service, net_cfg));
-	Ok((eth_sync.clone() as Arc<SyncProvider>, eth_sync.clone() as Arc<ManageNetwork>, eth_sync.clone() as Arc<ChainNotify>))
+	let mut chains = vec![(sync_cfg, client, snapshot_service)];
+	chains.extend(extra_chains);
+	let mut modules = try!(EthSync::new_multi(chains, net_cfg)).into_iter()
+		.map(|eth_sync| (eth_sync.clone() as Arc<SyncProvider>, eth_sync.clone() as Arc<ManageNetwork>, eth_sync as Arc<ChainNotify>))
+		.collect::<Vec<_>>();
+	let main = modules.remove(0);
+	Ok((main, modules))
 }
@@ -15,11 +15,13 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{str, fs};
+use std::io::Read;
 use std::time::Duration;
 use util::{Address, U256, version_data};
 use util::journaldb::Algorithm;
 use ethcore::spec::Spec;
 use ethcore::ethereum;
+use ethcore::ethstore::KdfParams;
 use ethcore::miner::{GasPricer, GasPriceCalibratorOptions};
 use user_defaults::UserDefaults;
 
@@ -69,6 +71,23 @@ impl SpecType {
 			}
 		}
 	}
+
+	/// Returns the raw JSON chain specification.
+	pub fn raw(&self) -> Result<Vec<u8>, String> {
+		match *self {
+			SpecType::Mainnet => Ok(include_bytes!("../ethcore/res/ethereum/frontier.json").to_vec()),
+			SpecType::Testnet => Ok(include_bytes!("../ethcore/res/ethereum/morden.json").to_vec()),
+			SpecType::Olympic => Ok(include_bytes!("../ethcore/res/ethereum/olympic.json").to_vec()),
+			SpecType::Classic => Ok(include_bytes!("../ethcore/res/ethereum/classic.json").to_vec()),
+			SpecType::Expanse => Ok(include_bytes!("../ethcore/res/ethereum/expanse.json").to_vec()),
+			SpecType::Custom(ref filename) => {
+				let mut file = try!(fs::File::open(filename).map_err(|_| "Could not load specification file."));
+				let mut buffer = Vec::new();
+				try!(file.read_to_end(&mut buffer).map_err(|_| "Could not read specification file."));
+				Ok(buffer)
+			}
+		}
+	}
 }
 
 #[derive(Debug, PartialEq)]
@@ -141,21 +160,25 @@ impl str::FromStr for ResealPolicy {
 
 #[derive(Debug, PartialEq)]
 pub struct AccountsConfig {
-	pub iterations: u32,
+	pub kdf: KdfParams,
 	pub import_keys: bool,
 	pub testnet: bool,
 	pub password_files: Vec<String>,
 	pub unlocked_accounts: Vec<Address>,
+	pub enable_hardware_wallets: bool,
+	pub signing_policies: Option<String>,
 }
 
 impl Default for AccountsConfig {
 	fn default() -> Self {
 		AccountsConfig {
-			iterations: 10240,
+			kdf: KdfParams::default(),
 			import_keys: false,
 			testnet: false,
 			password_files: Vec::new(),
 			unlocked_accounts: Vec::new(),
+			enable_hardware_wallets: true,
+			signing_policies: None,
 		}
 	}
 }
@@ -213,6 +236,27 @@ impl Default for MinerExtras {
 	}
 }
 
+/// Periodic snapshot configuration.
+#[derive(Debug, PartialEq)]
+pub struct SnapshotConfiguration {
+	/// How often to take full snapshots.
+	pub period: u64,
+	/// How many blocks to wait before taking a snapshot at a block.
+	pub history: u64,
+	/// How often to take incremental snapshots in between full ones. 0 if disabled.
+	pub incremental_period: u64,
+}
+
+impl Default for SnapshotConfiguration {
+	fn default() -> Self {
+		SnapshotConfiguration {
+			period: 10000,
+			history: 500,
+			incremental_period: 0,
+		}
+	}
+}
+
 /// 3-value enum.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Switch {
@@ -252,11 +296,25 @@ pub fn tracing_switch_to_bool(switch: Switch, user_defaults: &UserDefaults) -> R
 	}
 }
 
+pub fn fatdb_switch_to_bool(switch: Switch, user_defaults: &UserDefaults, algorithm: Algorithm) -> Result<bool, String> {
+	let result = match (user_defaults.is_first_launch, switch, user_defaults.fat_db) {
+		(false, Switch::On, false) => Err("FatDB resync required".into()),
+		(_, Switch::On, _) => Ok(true),
+		(_, Switch::Off, _) => Ok(false),
+		(_, Switch::Auto, def) => Ok(def),
+	};
+
+	if result.clone().unwrap_or(false) && algorithm != Algorithm::Archive {
+		return Err("Fat DB is not supported with the chosen pruning option. Please rerun with `--pruning=archive`".into());
+	}
+	result
+}
+
 #[cfg(test)]
 mod tests {
 	use util::journaldb::Algorithm;
 	use user_defaults::UserDefaults;
-	use super::{SpecType, Pruning, ResealPolicy, Switch, tracing_switch_to_bool};
+	use super::{SpecType, Pruning, ResealPolicy, Switch, tracing_switch_to_bool, fatdb_switch_to_bool};
 
 	#[test]
 	fn test_spec_type_parsing() {
@@ -336,4 +394,24 @@ mod tests {
 		assert!(tracing_switch_to_bool(Switch::On, &user_defaults_with_tracing(false, true)).unwrap());
 		assert!(tracing_switch_to_bool(Switch::On, &user_defaults_with_tracing(false, false)).is_err());
 	}
+
+	fn user_defaults_with_fat_db(first_launch: bool, fat_db: bool) -> UserDefaults {
+		let mut ud = UserDefaults::default();
+		ud.is_first_launch = first_launch;
+		ud.fat_db = fat_db;
+		ud
+	}
+
+	#[test]
+	fn test_fatdb_switch_to_bool() {
+		let archive = Algorithm::Archive;
+		assert!(!fatdb_switch_to_bool(Switch::Off, &user_defaults_with_fat_db(false, true), archive).unwrap());
+		assert!(!fatdb_switch_to_bool(Switch::Auto, &user_defaults_with_fat_db(false, false), archive).unwrap());
+		assert!(fatdb_switch_to_bool(Switch::Auto, &user_defaults_with_fat_db(false, true), archive).unwrap());
+		assert!(fatdb_switch_to_bool(Switch::On, &user_defaults_with_fat_db(true, false), archive).unwrap());
+		assert!(fatdb_switch_to_bool(Switch::On, &user_defaults_with_fat_db(false, false), archive).is_err());
+
+		assert!(fatdb_switch_to_bool(Switch::On, &user_defaults_with_fat_db(true, false), Algorithm::OverlayRecent).is_err());
+		assert!(!fatdb_switch_to_bool(Switch::Off, &user_defaults_with_fat_db(true, false), Algorithm::OverlayRecent).unwrap());
+	}
 }
@@ -27,7 +27,7 @@ use helpers::parity_ipc_path;
 pub use jsonipc::Server as IpcServer;
 pub use ethcore_rpc::Server as HttpServer;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct HttpConfiguration {
 	pub enabled: bool,
 	pub interface: String,
@@ -19,15 +19,16 @@ use std::io::ErrorKind;
 use ctrlc::CtrlC;
 use fdlimit::raise_fd_limit;
 use ethcore_logger::{Config as LogConfig, setup_log};
-use ethcore_rpc::NetworkSettings;
+use ethcore_rpc::{NetworkSettings, QueueOptions};
 use ethsync::NetworkConfiguration;
 use util::{Colour, version, U256};
 use io::{MayPanic, ForwardPanic, PanicHandler};
-use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, ChainNotify};
+use ethcore::client::{Mode, DatabaseCompactionProfile, VMType, ChainNotify, BlockChainClient};
 use ethcore::service::ClientService;
 use ethcore::account_provider::AccountProvider;
 use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions};
 use ethcore::snapshot;
+use ethcore::spec::{Spec, Checkpoint};
 use ethsync::{SyncConfig, SyncProvider};
 use informant::Informant;
 
@@ -35,7 +36,7 @@ use rpc::{HttpServer, IpcServer, HttpConfiguration, IpcConfiguration};
 use signer::SignerServer;
 use dapps::WebappServer;
 use io_handler::ClientIoHandler;
-use params::{SpecType, Pruning, AccountsConfig, GasPricerConfig, MinerExtras, Switch, tracing_switch_to_bool};
+use params::{SpecType, Pruning, AccountsConfig, GasPricerConfig, MinerExtras, SnapshotConfiguration, Switch, tracing_switch_to_bool, fatdb_switch_to_bool};
 use helpers::{to_client_config, execute_upgrades, passwords_from_files};
 use dir::Directories;
 use cache::CacheConfig;
@@ -47,12 +48,6 @@ use rpc_apis;
 use rpc;
 use url;
 
-// how often to take periodic snapshots.
-const SNAPSHOT_PERIOD: u64 = 10000;
-
-// how many blocks to wait before starting a periodic snapshot.
-const SNAPSHOT_HISTORY: u64 = 500;
-
 #[derive(Debug, PartialEq)]
 pub struct RunCmd {
 	pub cache_config: CacheConfig,
@@ -72,6 +67,7 @@ pub struct RunCmd {
 	pub miner_extras: MinerExtras,
 	pub mode: Mode,
 	pub tracing: Switch,
+	pub fat_db: Switch,
 	pub compaction: DatabaseCompactionProfile,
 	pub wal: bool,
 	pub vm_type: VMType,
@@ -85,6 +81,18 @@ pub struct RunCmd {
 	pub name: String,
 	pub custom_bootnodes: bool,
 	pub no_periodic_snapshot: bool,
+	pub snapshot_conf: SnapshotConfiguration,
+	pub checkpoint: Option<Checkpoint>,
+	pub fast_sync: bool,
+	pub extra_chains: Vec<SpecType>,
+}
+
+/// A chain running alongside the main chain in the same process.
+struct ExtraChain {
+	spec: Spec,
+	sync_config: SyncConfig,
+	service: ClientService,
+	miner: Arc<Miner>,
 }
 
 pub fn execute(cmd: RunCmd) -> Result<(), String> {
@@ -121,6 +129,9 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// select pruning algorithm
 	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
 
+	// check if fatdb is on
+	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
+
 	// prepare client and snapshot paths.
 	let client_path = db_dirs.client_path(algorithm);
 	let snapshot_path = db_dirs.snapshot_path();
@@ -129,8 +140,8 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
 
 	// run in daemon mode
-	if let Some(pid_file) = cmd.daemon {
-		try!(daemonize(pid_file));
+	if let Some(ref pid_file) = cmd.daemon {
+		try!(daemonize(pid_file.clone()));
 	}
 
 	// display info about used pruning algorithm
@@ -142,18 +153,37 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		warn!("Your chosen strategy is {}! You can re-run with --pruning to change.", Colour::Red.bold().paint("unstable"));
 	}
 
+	// start clients of the chains running alongside the main one
+	if cfg!(feature = "ipc") && !cmd.extra_chains.is_empty() {
+		return Err("Running several chains is not supported with IPC modules.".into());
+	}
+	let extra_chains = try!(cmd.extra_chains.iter().map(|spec_type| start_extra_chain(&cmd, spec_type)).collect::<Result<Vec<_>, _>>());
+	for chain in &extra_chains {
+		panic_handler.forward_from(&chain.service);
+	}
+
 	// create sync config
-	let mut sync_config = SyncConfig::default();
-	sync_config.network_id = match cmd.network_id {
-		Some(id) => id,
-		None => spec.network_id(),
-	};
-	if spec.subprotocol_name().len() != 3 {
-		warn!("Your chain specification's subprotocol length is not 3. Ignoring.");
-	} else {
-		sync_config.subprotocol_name.clone_from_slice(spec.subprotocol_name().as_bytes());
+	let mut sync_config = to_sync_config(&spec, cmd.fast_sync);
+	if let Some(id) = cmd.network_id {
+		sync_config.network_id = id;
+	}
+	if cmd.checkpoint.is_some() {
+		sync_config.checkpoint = cmd.checkpoint;
+	}
+	if sync_config.state_sync && sync_config.checkpoint.map_or(true, |c| c.number == 0) {
+		warn!("Fast sync needs a trusted checkpoint to pick the state to download. Use --checkpoint to set one. Falling back to full sync.");
+	}
+	{
+		// each chain is served over its own subprotocol
+		let mut subprotocols = vec![sync_config.subprotocol_name];
+		for chain in &extra_chains {
+			let name = chain.sync_config.subprotocol_name;
+			if subprotocols.contains(&name) {
+				return Err(format!("Chain {} uses the same subprotocol name as another chain. Set a unique subprotocolName in its specification.", chain.spec.name));
+			}
+			subprotocols.push(name);
+		}
 	}
-	sync_config.fork_block = spec.fork_block();
 
 	// prepare account provider
 	let account_provider = Arc::new(try!(prepare_account_provider(&cmd.dirs, cmd.acc_conf)));
@@ -167,10 +197,11 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	miner.set_transactions_limit(cmd.miner_extras.transactions_limit);
 
 	// create client config
-	let client_config = to_client_config(
+	let mut client_config = to_client_config(
 		&cmd.cache_config,
 		cmd.mode,
 		tracing,
+		fat_db,
 		cmd.compaction,
 		cmd.wal,
 		cmd.vm_type,
@@ -178,10 +209,16 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		algorithm,
 	);
 
+	// incremental snapshots need the accounts changed by each block.
+	client_config.track_state_changes = !cmd.no_periodic_snapshot && cmd.snapshot_conf.incremental_period != 0;
+
 	// set up bootnodes
 	let mut net_conf = cmd.net_conf;
 	if !cmd.custom_bootnodes {
 		net_conf.boot_nodes = spec.nodes.clone();
+		for chain in &extra_chains {
+			net_conf.boot_nodes.extend(chain.spec.nodes.iter().cloned());
+		}
 	}
 
 	// create supervisor
@@ -208,22 +245,34 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	let external_miner = Arc::new(ExternalMiner::default());
 
 	// create sync object
-	let (sync_provider, manage_network, chain_notify) = try!(modules::sync(
-		&mut hypervisor, sync_config, net_conf.into(), client.clone(), snapshot_service, &cmd.logger_config,
+	let extra_sync = extra_chains.iter().map(|chain| (
+		chain.sync_config,
+		chain.service.client() as Arc<BlockChainClient>,
+		chain.service.snapshot_service() as Arc<snapshot::SnapshotService>,
+	)).collect();
+	let ((sync_provider, manage_network, chain_notify), extra_sync_modules) = try!(modules::sync(
+		&mut hypervisor, sync_config, net_conf.into(), client.clone(), snapshot_service, extra_sync, &cmd.logger_config,
 	).map_err(|e| format!("Sync error: {}", e)));
 
 	service.add_notify(chain_notify.clone());
+	for (chain, &(_, _, ref notify)) in extra_chains.iter().zip(extra_sync_modules.iter()) {
+		chain.service.add_notify(notify.clone());
+	}
 
 	// start network
 	if cmd.enable_network {
 		chain_notify.start();
+		for &(_, _, ref notify) in &extra_sync_modules {
+			notify.start();
+		}
 	}
 
 	// set up dependencies for rpc servers
 	let signer_path = cmd.signer_conf.signer_path.clone();
+	let queue_options = signer::queue_options(&cmd.signer_conf);
 	let deps_for_rpc_apis = Arc::new(rpc_apis::Dependencies {
 		signer_port: cmd.signer_port,
-		signer_service: Arc::new(rpc_apis::SignerService::new(move || signer::new_token(signer_path.clone()))),
+		signer_service: Arc::new(rpc_apis::SignerService::new(move || signer::new_token(signer_path.clone()), queue_options)),
 		client: client.clone(),
 		sync: sync_provider.clone(),
 		net: manage_network.clone(),
@@ -242,7 +291,43 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	};
 
 	// start rpc servers
-	let http_server = try!(rpc::new_http(cmd.http_conf, &dependencies));
+	let http_server = try!(rpc::new_http(cmd.http_conf.clone(), &dependencies));
+
+	// each extra chain gets its own HTTP JSON-RPC server on the ports following the main one
+	let _extra_http_servers = try!(extra_chains.iter().zip(extra_sync_modules.iter()).enumerate().map(|(i, (chain, &(ref sync, ref net, _)))| {
+		let mut http_conf = cmd.http_conf.clone();
+		http_conf.port = cmd.http_conf.port + i as u16 + 1;
+		// the Trusted Signer and stored passwords serve the main chain only,
+		// so transactions are never confirmed there and then sent on another chain.
+		http_conf.apis = rpc_apis::ApiSet::List(cmd.http_conf.apis.list_apis().into_iter()
+			.filter(|api| *api != rpc_apis::Api::Personal && *api != rpc_apis::Api::Signer)
+			.collect());
+		let mut settings = cmd.net_settings.clone();
+		settings.chain = chain.spec.name.clone();
+		settings.rpc_port = http_conf.port;
+		let dependencies = rpc::Dependencies {
+			panic_handler: panic_handler.clone(),
+			apis: Arc::new(rpc_apis::Dependencies {
+				signer_port: None,
+				signer_service: Arc::new(rpc_apis::SignerService::new(
+					|| Err("The Trusted Signer is only available for the main chain.".into()),
+					QueueOptions::default(),
+				)),
+				client: chain.service.client(),
+				sync: sync.clone(),
+				net: net.clone(),
+				secret_store: account_provider.clone(),
+				miner: chain.miner.clone(),
+				external_miner: Arc::new(ExternalMiner::default()),
+				logger: logger.clone(),
+				settings: Arc::new(settings),
+				net_service: net.clone(),
+				geth_compatibility: cmd.geth_compatibility,
+			}),
+		};
+		info!("JSON-RPC of chain {} available on port {} (signing with unlocked accounts only)", chain.spec.name, http_conf.port);
+		rpc::new_http(http_conf, &dependencies)
+	}).collect::<Result<Vec<_>, _>>());
 	let ipc_server = try!(rpc::new_ipc(cmd.ipc_conf, &dependencies));
 
 	let dapps_deps = dapps::Dependencies {
@@ -285,8 +370,9 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 				service.client(),
 				move || sync.status().is_major_syncing(),
 				service.io().channel(),
-				SNAPSHOT_PERIOD,
-				SNAPSHOT_HISTORY,
+				cmd.snapshot_conf.period,
+				cmd.snapshot_conf.incremental_period,
+				cmd.snapshot_conf.history,
 			));
 
 			service.add_notify(watcher.clone());
@@ -305,6 +391,7 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// save user defaults
 	user_defaults.pruning = algorithm;
 	user_defaults.tracing = tracing;
+	user_defaults.fat_db = fat_db;
 	try!(user_defaults.save(&user_defaults_path));
 
 	// Handle exit
@@ -322,6 +409,69 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	Ok(())
 }
 
+fn to_sync_config(spec: &Spec, state_sync: bool) -> SyncConfig {
+	let mut sync_config = SyncConfig::default();
+	sync_config.network_id = spec.network_id();
+	if spec.subprotocol_name().len() != 3 {
+		warn!("Your chain specification's subprotocol length is not 3. Ignoring.");
+	} else {
+		sync_config.subprotocol_name.clone_from_slice(spec.subprotocol_name().as_bytes());
+	}
+	sync_config.fork_block = spec.fork_block();
+	sync_config.checkpoint = spec.checkpoint();
+	sync_config.state_sync = state_sync;
+	sync_config
+}
+
+/// Open the database of an additional chain and start its client.
+/// Extra chains share the settings of the main chain but keep their own database directory.
+fn start_extra_chain(cmd: &RunCmd, spec_type: &SpecType) -> Result<ExtraChain, String> {
+	let spec = try!(spec_type.spec());
+	let db_dirs = cmd.dirs.database(spec.genesis_header().hash(), spec.fork_name.clone());
+	let user_defaults_path = db_dirs.user_defaults_path();
+	let mut user_defaults = try!(UserDefaults::load(&user_defaults_path));
+	let tracing = try!(tracing_switch_to_bool(cmd.tracing, &user_defaults));
+	let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+	let fat_db = try!(fatdb_switch_to_bool(cmd.fat_db, &user_defaults, algorithm));
+	try!(execute_upgrades(&db_dirs, algorithm, cmd.compaction.compaction_profile()));
+
+	let client_config = to_client_config(
+		&cmd.cache_config,
+		cmd.mode.clone(),
+		tracing,
+		fat_db,
+		cmd.compaction.clone(),
+		cmd.wal,
+		cmd.vm_type.clone(),
+		cmd.name.clone(),
+		algorithm,
+	);
+
+	// extra chains are followed, not sealed.
+	let miner = Arc::new(Miner::with_spec(&spec));
+	let service = try!(ClientService::start(
+		client_config,
+		&spec,
+		&db_dirs.client_path(algorithm),
+		&db_dirs.snapshot_path(),
+		&cmd.dirs.ipc_path(),
+		miner.clone(),
+	).map_err(|e| format!("Client service error: {:?}", e)));
+
+	user_defaults.pruning = algorithm;
+	user_defaults.tracing = tracing;
+	user_defaults.fat_db = fat_db;
+	try!(user_defaults.save(&user_defaults_path));
+
+	info!("Running chain {} alongside the main chain", Colour::White.bold().paint(spec.name.clone()));
+	Ok(ExtraChain {
+		sync_config: to_sync_config(&spec, cmd.fast_sync),
+		spec: spec,
+		service: service,
+		miner: miner,
+	})
+}
+
 #[cfg(not(windows))]
 fn daemonize(pid_file: String) -> Result<(), String> {
 	extern crate daemonize;
@@ -343,6 +493,9 @@ fn prepare_account_provider(dirs: &Directories, cfg: AccountsConfig) -> Result<A
 	use ethcore::ethstore::{import_accounts, EthStore};
 	use ethcore::ethstore::dir::{GethDirectory, DirectoryType, DiskDirectory};
 	use ethcore::ethstore::Error;
+	use ethcore::hardware_wallet::LedgerManager;
+	use ethcore::signing_policy::SigningPolicy;
+	use std::fs;
 
 	let passwords = try!(passwords_from_files(cfg.password_files));
 
@@ -364,9 +517,21 @@ fn prepare_account_provider(dirs: &Directories, cfg: AccountsConfig) -> Result<A
 
 	let dir = Box::new(try!(DiskDirectory::create(dirs.keys.clone()).map_err(|e| format!("Could not open keys directory: {}", e))));
 	let account_service = AccountProvider::new(Box::new(
-		try!(EthStore::open_with_iterations(dir, cfg.iterations).map_err(|e| format!("Could not open keys directory: {}", e)))
+		try!(EthStore::open_with_kdf(dir, cfg.kdf).map_err(|e| format!("Could not open keys directory: {}", e)))
 	));
 
+	if cfg.enable_hardware_wallets {
+		account_service.add_signer_backend(Arc::new(LedgerManager::connected()));
+	}
+
+	if let Some(path) = cfg.signing_policies {
+		let file = try!(fs::File::open(&path).map_err(|e| format!("Could not open signing policies file {}: {}", path, e)));
+		let policies = try!(SigningPolicy::read_address_map(file).map_err(|e| format!("Invalid signing policies file {}: {}", path, e)));
+		for (address, policy) in policies {
+			account_service.set_signing_policy(address, Some(policy));
+		}
+	}
+
 	for a in cfg.unlocked_accounts {
 		if passwords.iter().find(|p| account_service.unlock_account_permanently(a, (*p).clone()).is_ok()).is_none() {
 			return Err(format!("No password found to unlock account {}. Make sure valid password is present in files passed using `--password`.", a));
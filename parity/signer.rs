@@ -22,10 +22,13 @@ use io::{ForwardPanic, PanicHandler};
 use util::path::restrict_permissions_owner;
 use rpc_apis;
 use ethcore_signer as signer;
+use ethcore_rpc::{QueueOptions, DEFAULT_QUEUE_LIMIT};
 use helpers::replace_home;
 pub use ethcore_signer::Server as SignerServer;
 
 const CODES_FILENAME: &'static str = "authcodes";
+const REQUESTS_FILENAME: &'static str = "requests.json";
+const AUDIT_FILENAME: &'static str = "audit.log";
 
 #[derive(Debug, PartialEq)]
 pub struct Configuration {
@@ -34,6 +37,8 @@ pub struct Configuration {
 	pub interface: String,
 	pub signer_path: String,
 	pub skip_origin_validation: bool,
+	pub queue_limit: usize,
+	pub request_expiry: Option<u64>,
 }
 
 impl Default for Configuration {
@@ -44,6 +49,8 @@ impl Default for Configuration {
 			interface: "127.0.0.1".into(),
 			signer_path: replace_home("$HOME/.parity/signer"),
 			skip_origin_validation: false,
+			queue_limit: DEFAULT_QUEUE_LIMIT,
+			request_expiry: None,
 		}
 	}
 }
@@ -68,6 +75,17 @@ fn codes_path(path: String) -> PathBuf {
 	p
 }
 
+/// Returns options of the confirmations queue for given configuration.
+pub fn queue_options(conf: &Configuration) -> QueueOptions {
+	let path = PathBuf::from(&conf.signer_path);
+	QueueOptions {
+		limit: conf.queue_limit,
+		expiry: conf.request_expiry,
+		path: Some(path.join(REQUESTS_FILENAME)),
+		audit_path: Some(path.join(AUDIT_FILENAME)),
+	}
+}
+
 pub fn new_token(path: String) -> Result<String, String> {
 	generate_new_token(path)
 		.map(|code| format!("This key code will authorise your System Signer UI: {}", Colour::White.bold().paint(code)))
@@ -16,18 +16,21 @@
 
 //! Snapshot and restoration commands.
 
+use std::fs;
 use std::time::Duration;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Condvar};
 
 use ethcore_logger::{setup_log, Config as LogConfig};
-use ethcore::snapshot::{Progress, RestorationStatus, SnapshotService as SS};
-use ethcore::snapshot::io::{SnapshotReader, PackedReader, PackedWriter};
+use ethcore::snapshot::{self, Progress, RestorationStatus, SnapshotService as SS};
+use ethcore::snapshot::io::{SnapshotReader, PackedReader, PackedWriter, LooseReader};
 use ethcore::snapshot::service::Service as SnapshotService;
 use ethcore::service::ClientService;
 use ethcore::client::{Mode, DatabaseCompactionProfile, VMType};
 use ethcore::miner::Miner;
 use ethcore::ids::BlockID;
+use util::{H64, FixedHash};
+use util::kvdb::{Database, DatabaseConfig};
 
 use cache::CacheConfig;
 use params::{SpecType, Pruning, Switch, tracing_switch_to_bool};
@@ -44,7 +47,11 @@ pub enum Kind {
 	/// Take a snapshot.
 	Take,
 	/// Restore a snapshot.
-	Restore
+	Restore,
+	/// Print the manifest of a snapshot.
+	Inspect,
+	/// Check the integrity of a snapshot.
+	Verify,
 }
 
 /// Command for snapshot creation or restoration.
@@ -75,7 +82,7 @@ fn restore_using<R: SnapshotReader>(snapshot: Arc<SnapshotService>, reader: &R,
 		format!("Failed to begin restoration: {}", e)
 	}));
 
-	let (num_state, num_blocks) = (manifest.state_hashes.len(), manifest.block_hashes.len());
+	let (num_state, num_blocks) = (manifest.state_hashes.len() + manifest.delta_hashes.len(), manifest.block_hashes.len());
 
 	let informant_handle = snapshot.clone();
 	::std::thread::spawn(move || {
@@ -87,7 +94,7 @@ fn restore_using<R: SnapshotReader>(snapshot: Arc<SnapshotService>, reader: &R,
  	});
 
  	info!("Restoring state");
- 	for &state_hash in &manifest.state_hashes {
+ 	for &state_hash in manifest.state_hashes.iter().chain(&manifest.delta_hashes) {
  		if snapshot.status() == RestorationStatus::Failed {
  			return Err("Restoration failed".into());
  		}
@@ -118,6 +125,30 @@ fn restore_using<R: SnapshotReader>(snapshot: Arc<SnapshotService>, reader: &R,
 	}
 }
 
+// open either a packed snapshot file or a loose snapshot directory.
+fn open_reader(path: &str) -> Result<Box<SnapshotReader>, String> {
+	let path = Path::new(path);
+	if path.is_dir() {
+		let reader = try!(LooseReader::new(path.to_owned())
+			.map_err(|e| format!("Couldn't open snapshot directory: {}", e)));
+		Ok(Box::new(reader))
+	} else {
+		let reader = try!(PackedReader::new(path)
+			.map_err(|e| format!("Couldn't open snapshot file: {}", e))
+			.and_then(|x| x.ok_or("Snapshot file has invalid format.".to_owned())));
+		Ok(Box::new(reader))
+	}
+}
+
+// total compressed size of the given chunks.
+fn chunks_size(reader: &SnapshotReader, hashes: &[::util::H256]) -> Result<u64, String> {
+	let mut total = 0;
+	for &hash in hashes {
+		total += try!(reader.chunk_size(hash).map_err(|e| format!("Couldn't read chunk {:?}: {}", hash, e)));
+	}
+	Ok(total)
+}
+
 impl SnapshotCommand {
 	// shared portion of snapshot commands: start the client service
 	fn start_service(self) -> Result<(ClientService, Arc<PanicHandler>), String> {
@@ -158,7 +189,7 @@ impl SnapshotCommand {
 		try!(execute_upgrades(&db_dirs, algorithm, self.compaction.compaction_profile()));
 
 		// prepare client config
-		let client_config = to_client_config(&self.cache_config, self.mode, tracing, self.compaction, self.wal, VMType::default(), "".into(), algorithm);
+		let client_config = to_client_config(&self.cache_config, self.mode, tracing, user_defaults.fat_db, self.compaction, self.wal, VMType::default(), "".into(), algorithm);
 
 		let service = try!(ClientService::start(
 			client_config,
@@ -248,6 +279,98 @@ impl SnapshotCommand {
 
 		Ok(())
 	}
+
+	/// Print the manifest of a snapshot.
+	pub fn inspect(self) -> Result<String, String> {
+		let file = try!(self.file_path.ok_or("No file path provided.".to_owned()));
+		let reader = try!(open_reader(&file));
+		let manifest = reader.manifest();
+
+		let state_size = try!(chunks_size(&*reader, &manifest.state_hashes));
+		let block_size = try!(chunks_size(&*reader, &manifest.block_hashes));
+
+		let mut info = format!("Block number: {}\nBlock hash: {:?}\nState root: {:?}\nState chunks: {} ({} bytes)\nBlock chunks: {} ({} bytes)",
+			manifest.block_number,
+			manifest.block_hash,
+			manifest.state_root,
+			manifest.state_hashes.len(),
+			state_size,
+			manifest.block_hashes.len(),
+			block_size,
+		);
+
+		if let Some(ref base) = manifest.base {
+			let delta_size = try!(chunks_size(&*reader, &manifest.delta_hashes));
+			info.push_str(&format!("\nDelta chunks: {} ({} bytes)\nBase manifest: {:?}",
+				manifest.delta_hashes.len(),
+				delta_size,
+				base,
+			));
+		}
+
+		Ok(info)
+	}
+
+	/// Verify chunk hashes of a snapshot and rebuild its state in a temporary
+	/// database, checking the resulting state root.
+	pub fn verify(self) -> Result<String, String> {
+		let file = try!(self.file_path.ok_or("No file path provided.".to_owned()));
+
+		// Setup logging
+		let _logger = setup_log(&self.logger_config);
+
+		let reader = try!(open_reader(&file));
+		let num_state = reader.manifest().state_hashes.len() + reader.manifest().delta_hashes.len();
+		let num_blocks = reader.manifest().block_hashes.len();
+
+		info!("Verifying snapshot of block #{} with {} state chunks and {} block chunks.",
+			reader.manifest().block_number, num_state, num_blocks);
+
+		// the rebuilt state is thrown away once verification is finished.
+		let temp_path = Path::new(&self.dirs.db).join(format!("snapshot-verify-{}", H64::random().hex()));
+		try!(fs::create_dir_all(&temp_path).map_err(|e| format!("Couldn't create temporary directory: {}", e)));
+		let result = verify_in(&*reader, &temp_path);
+		if let Err(e) = fs::remove_dir_all(&temp_path) {
+			warn!("Couldn't remove temporary directory {}: {}", temp_path.display(), e);
+		}
+
+		let progress = try!(result);
+		Ok(format!("Snapshot is valid: {} accounts and {} blocks verified.", progress.accounts(), progress.blocks()))
+	}
+}
+
+// rebuild the snapshot state in a database at `db_path`, checking chunk hashes and the state root.
+fn verify_in(reader: &SnapshotReader, db_path: &Path) -> Result<Arc<Progress>, String> {
+	let db_config = DatabaseConfig::with_columns(::ethcore::db::NUM_COLUMNS);
+	let db = Arc::new(try!(Database::open(&db_config, &db_path.to_string_lossy())
+		.map_err(|e| format!("Couldn't open temporary database: {}", e))));
+
+	let progress = Arc::new(Progress::default());
+	let finished = Arc::new((Mutex::new(false), Condvar::new()));
+	let (p, f) = (progress.clone(), finished.clone());
+	let informant_handle = ::std::thread::spawn(move || {
+		let (ref lock, ref cvar) = *f;
+		let mut done = lock.lock().expect("informant lock is never poisoned; qed");
+		while !*done {
+			done = cvar.wait_timeout(done, Duration::from_secs(5)).expect("informant lock is never poisoned; qed").0;
+			if !*done {
+				info!("Verified {} accounts and {} blocks ({} bytes).", p.accounts(), p.blocks(), p.size());
+			}
+		}
+	});
+
+	let result = snapshot::verify_snapshot(reader, db, &*progress);
+
+	// the informant must exit even if verification failed, without waiting for its next report.
+	{
+		let (ref lock, ref cvar) = *finished;
+		*lock.lock().expect("informant lock is never poisoned; qed") = true;
+		cvar.notify_one();
+	}
+	try!(informant_handle.join().map_err(|_| "failed to join logger thread"));
+
+	try!(result.map_err(|e| format!("Snapshot verification failed: {}", e)));
+	Ok(progress)
 }
 
 /// Execute this snapshot command.
@@ -255,6 +378,8 @@ pub fn execute(cmd: SnapshotCommand) -> Result<String, String> {
 	match cmd.kind {
 		Kind::Take => try!(cmd.take_snapshot()),
 		Kind::Restore => try!(cmd.restore()),
+		Kind::Inspect => return cmd.inspect(),
+		Kind::Verify => return cmd.verify(),
 	}
 
 	Ok(String::new())
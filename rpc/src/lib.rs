@@ -55,7 +55,7 @@ use self::jsonrpc_core::{IoHandler, IoDelegate};
 
 pub use jsonrpc_http_server::{ServerBuilder, Server, RpcServerError};
 pub mod v1;
-pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, NetworkSettings};
+pub use v1::{SigningQueue, SignerService, ConfirmationsQueue, QueueOptions, DEFAULT_QUEUE_LIMIT, NetworkSettings, with_signer_token};
 
 /// An object that can be extended with `IoDelegates`
 pub trait Extendable {
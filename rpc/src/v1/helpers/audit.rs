@@ -0,0 +1,209 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Audit log of decisions made through the Trusted Signer.
+
+use std::io::Write;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::cell::RefCell;
+use std::mem;
+use std::collections::BTreeMap;
+use serde_json;
+use jsonrpc_core::{to_value, Value};
+use time;
+use util::Mutex;
+use util::path::restrict_permissions_owner;
+use v1::types;
+use v1::helpers::{ConfirmationRequest, ConfirmationPayload};
+use v1::helpers::signing_queue::RpcResult;
+
+thread_local!(static SIGNER_TOKEN: RefCell<Option<String>> = RefCell::new(None));
+
+/// Runs given closure on behalf of the Signer UI authorized with given token.
+/// Decisions made by the closure are attributed to that token in the audit log.
+pub fn with_signer_token<F, T>(token: Option<String>, f: F) -> T where F: FnOnce() -> T {
+	let previous = SIGNER_TOKEN.with(|t| mem::replace(&mut *t.borrow_mut(), token));
+	let result = f();
+	SIGNER_TOKEN.with(|t| *t.borrow_mut() = previous);
+	result
+}
+
+/// Returns identifier of the Signer UI token the current thread acts on behalf of.
+pub fn signer_token() -> Option<String> {
+	SIGNER_TOKEN.with(|t| t.borrow().clone())
+}
+
+/// Resolution of a confirmation request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+	/// Request was confirmed.
+	Confirmed,
+	/// Request was rejected.
+	Rejected,
+	/// Request was not resolved in time.
+	Expired,
+}
+
+impl Decision {
+	fn as_str(&self) -> &'static str {
+		match *self {
+			Decision::Confirmed => "confirmed",
+			Decision::Rejected => "rejected",
+			Decision::Expired => "expired",
+		}
+	}
+}
+
+/// Append-only log of resolved confirmation requests.
+/// Each entry is written as a single line of JSON.
+pub struct AuditLog {
+	path: Option<PathBuf>,
+	lock: Mutex<()>,
+}
+
+impl AuditLog {
+	/// Creates new audit log appending to given file.
+	/// With no file decisions are only logged.
+	pub fn new(path: Option<PathBuf>) -> Self {
+		AuditLog {
+			path: path,
+			lock: Mutex::new(()),
+		}
+	}
+
+	/// Records resolution of given request.
+	pub fn record(&self, request: &ConfirmationRequest, decision: Decision, result: Option<&RpcResult>) {
+		let token = signer_token();
+		info!(target: "signer", "Request {} {} (token: {}).", request.id, decision.as_str(), token.as_ref().map_or("none", |t| &t[..]));
+
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return,
+		};
+
+		let mut entry = BTreeMap::new();
+		entry.insert("timestamp".to_owned(), Value::U64(now()));
+		entry.insert("id".to_owned(), to_value(&types::U256::from(request.id)));
+		entry.insert("decision".to_owned(), Value::String(decision.as_str().to_owned()));
+		entry.insert("token".to_owned(), token.map_or(Value::Null, Value::String));
+		entry.insert("request".to_owned(), to_value(&types::ConfirmationPayload::from(request.payload.clone())));
+		entry.insert("result".to_owned(), match (&request.payload, result) {
+			// decrypted plaintext must never end up in the log
+			(&ConfirmationPayload::Decrypt(..), _) => Value::Null,
+			(_, Some(&Ok(ref value))) => value.clone(),
+			(_, Some(&Err(ref err))) => Value::String(err.message.clone()),
+			(_, None) => Value::Null,
+		});
+		let line = serde_json::to_string(&Value::Object(entry)).expect("Serialization of JSON value is infallible.");
+
+		let _guard = self.lock.lock();
+		let created = !path.exists();
+		let res = OpenOptions::new().create(true).append(true).open(path)
+			.and_then(|mut file| {
+				if created {
+					if let Err(e) = restrict_permissions_owner(path) {
+						warn!(target: "signer", "Failed to restrict permissions of {}: {}", path.display(), e);
+					}
+				}
+				writeln!(file, "{}", line)
+			});
+		if let Err(e) = res {
+			warn!(target: "signer", "Unable to write signer audit log {}: {}", path.display(), e);
+		}
+	}
+}
+
+/// Returns a number of seconds since epoch.
+pub fn now() -> u64 {
+	time::get_time().sec as u64
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::Read;
+	use serde_json;
+	use jsonrpc_core::{to_value, Value};
+	use devtools::RandomTempPath;
+	use v1::helpers::{ConfirmationRequest, ConfirmationPayload};
+	use v1::types::{H256, Bytes};
+	use super::{AuditLog, Decision, with_signer_token, signer_token};
+
+	#[test]
+	fn should_restore_previous_token() {
+		assert_eq!(signer_token(), None);
+		let token = with_signer_token(Some("abc".into()), || {
+			let outer = signer_token();
+			let inner = with_signer_token(None, signer_token);
+			(outer, inner, signer_token())
+		});
+		assert_eq!(token, (Some("abc".into()), None, Some("abc".into())));
+		assert_eq!(signer_token(), None);
+	}
+
+	#[test]
+	fn should_append_decisions_to_file() {
+		// given
+		let path = RandomTempPath::new();
+		let log = AuditLog::new(Some(path.as_path().to_owned()));
+		let request = ConfirmationRequest {
+			id: 5.into(),
+			payload: ConfirmationPayload::Sign(1.into(), 2.into()),
+		};
+
+		// when
+		with_signer_token(Some("abc".into()), || {
+			log.record(&request, Decision::Confirmed, Some(&Ok(to_value(&H256::from(3)))));
+		});
+		log.record(&request, Decision::Expired, None);
+
+		// then
+		let mut content = String::new();
+		File::open(path.as_path()).unwrap().read_to_string(&mut content).unwrap();
+		let lines = content.lines()
+			.map(|line| serde_json::from_str::<Value>(line).unwrap())
+			.collect::<Vec<_>>();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0].find("decision"), Some(&Value::String("confirmed".into())));
+		assert_eq!(lines[0].find("token"), Some(&Value::String("abc".into())));
+		assert_eq!(lines[0].find("id"), Some(&Value::String("0x5".into())));
+		assert_eq!(lines[0].find("result"), Some(&to_value(&H256::from(3))));
+		assert_eq!(lines[1].find("decision"), Some(&Value::String("expired".into())));
+		assert_eq!(lines[1].find("token"), Some(&Value::Null));
+	}
+
+	#[test]
+	fn should_not_log_decrypted_messages() {
+		// given
+		let path = RandomTempPath::new();
+		let log = AuditLog::new(Some(path.as_path().to_owned()));
+		let request = ConfirmationRequest {
+			id: 1.into(),
+			payload: ConfirmationPayload::Decrypt(1.into(), vec![1, 2, 3].into()),
+		};
+
+		// when
+		log.record(&request, Decision::Confirmed, Some(&Ok(to_value(&Bytes::new(b"secret".to_vec())))));
+
+		// then
+		let mut content = String::new();
+		File::open(path.as_path()).unwrap().read_to_string(&mut content).unwrap();
+		let line = serde_json::from_str::<Value>(content.trim()).unwrap();
+		assert_eq!(line.find("decision"), Some(&Value::String("confirmed".into())));
+		assert_eq!(line.find("result"), Some(&Value::Null));
+	}
+}
@@ -15,16 +15,22 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use util::{Address, H256, U256, Uint};
+use ethkey::Signature;
 use util::bytes::ToPretty;
-use ethcore::miner::MinerService;
-use ethcore::client::MiningBlockChainClient;
+use ethcore::miner::{MinerService, TransactionCondition};
+use ethcore::client::{MiningBlockChainClient, CallAnalytics};
+use ethcore::trace::trace::Res;
 use ethcore::transaction::{Action, SignedTransaction, Transaction};
 use ethcore::account_provider::AccountProvider;
+use ethcore::signing_policy::PolicyViolation;
 use jsonrpc_core::{Error, Value, to_value};
 use v1::helpers::TransactionRequest;
-use v1::types::{H256 as RpcH256, H520 as RpcH520};
+use v1::types::{H256 as RpcH256, H520 as RpcH520, Bytes as RpcBytes, TransactionSimulation};
 use v1::helpers::errors;
 
+/// Gas limit used to estimate gas required by a transaction.
+const ESTIMATE_GAS_LIMIT: u64 = 50_000_000;
+
 fn prepare_transaction<C, M>(client: &C, miner: &M, request: TransactionRequest) -> Transaction where C: MiningBlockChainClient, M: MinerService {
 	Transaction {
 		nonce: request.nonce
@@ -52,39 +58,149 @@ pub fn dispatch_transaction<C, M>(client: &C, miner: &M, signed_transaction: Sig
 		.map(|_| hash)
 }
 
+/// Imports the transaction to the queue once the condition is met.
+pub fn dispatch_conditional_transaction<C, M>(client: &C, miner: &M, signed_transaction: SignedTransaction, condition: TransactionCondition) -> Result<RpcH256, Error>
+	where C: MiningBlockChainClient, M: MinerService {
+	let hash = RpcH256::from(signed_transaction.hash());
+
+	miner.import_conditional_transaction(client, signed_transaction, condition)
+		.map_err(errors::from_transaction_error)
+		.map(|_| hash)
+}
+
 pub fn signature_with_password(accounts: &AccountProvider, address: Address, hash: H256, pass: String) -> Result<Value, Error> {
+	if accounts.is_external(&address) {
+		return Err(errors::device_sign_unsupported());
+	}
 	accounts.sign_with_password(address, pass, hash)
 		.map_err(errors::from_password_error)
 		.map(|hash| to_value(&RpcH520::from(hash)))
 }
 
-pub fn unlock_sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, password: String) -> Result<Value, Error>
+pub fn decrypt_with_password(accounts: &AccountProvider, address: Address, msg: &[u8], pass: String) -> Result<Value, Error> {
+	accounts.decrypt_with_password(address, pass, &[0; 0], msg)
+		.map_err(errors::from_password_error)
+		.map(|data| to_value(&RpcBytes::from(data)))
+}
+
+/// Signs the transaction with the hardware wallet holding the account. Blocks until the user confirms it on the device.
+fn sign_on_device(account_provider: &AccountProvider, address: Address, t: &Transaction) -> Result<Signature, Error> {
+	let mut stream = ::rlp::RlpStream::new();
+	t.rlp_append_unsigned_transaction(&mut stream);
+	account_provider.sign_transaction_external(address, &stream.out()).map_err(errors::from_device_error)
+}
+
+/// Signs the transaction with the account unlocked by given password and dispatches it.
+/// Transactions with a condition are held by the miner until the condition is met.
+pub fn unlock_sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, condition: Option<TransactionCondition>, account_provider: &AccountProvider, password: String) -> Result<Value, Error>
 	where C: MiningBlockChainClient, M: MinerService {
 
 	let address = request.from;
 	let signed_transaction = {
 		let t = prepare_transaction(client, miner, request);
-		let hash = t.hash();
-		let signature = try!(account_provider.sign_with_password(address, password, hash).map_err(errors::from_password_error));
+		let signature = if account_provider.is_external(&address) {
+			// the key is on a hardware wallet, the user confirms the transaction on the device instead of providing a password
+			try!(sign_on_device(account_provider, address, &t))
+		} else {
+			let hash = t.hash();
+			try!(account_provider.sign_with_password(address, password, hash).map_err(errors::from_password_error))
+		};
 		t.with_signature(signature)
 	};
 
 	trace!(target: "miner", "send_transaction: dispatching tx: {}", ::rlp::encode(&signed_transaction).to_vec().pretty());
-	dispatch_transaction(&*client, &*miner, signed_transaction).map(to_value)
+	match condition {
+		Some(condition) => dispatch_conditional_transaction(&*client, &*miner, signed_transaction, condition).map(to_value),
+		None => dispatch_transaction(&*client, &*miner, signed_transaction).map(to_value),
+	}
+}
+
+/// Checks if the transaction could be signed without confirmation, according to the signing policy of the sender.
+pub fn check_signing_policy<C, M>(client: &C, miner: &M, request: &TransactionRequest, account_provider: &AccountProvider) -> Result<(), PolicyViolation>
+	where C: MiningBlockChainClient, M: MinerService {
+	let t = prepare_transaction(client, miner, request.clone());
+	account_provider.check_signing_policy(&request.from, &t)
 }
 
 pub fn sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, account_provider: &AccountProvider, address: Address) -> Result<Value, Error>
 	where C: MiningBlockChainClient, M: MinerService {
 
-	let signed_transaction = {
-		let t = prepare_transaction(client, miner, request);
-		let hash = t.hash();
-		let signature = try!(account_provider.sign(address, hash).map_err(errors::from_signing_error));
-		t.with_signature(signature)
+	let t = prepare_transaction(client, miner, request);
+	// value of the transaction is reserved towards the daily limit until it's dispatched
+	try!(account_provider.authorize_transaction(&address, &t).map_err(errors::from_policy_violation));
+	let signature = if account_provider.is_external(&address) {
+		sign_on_device(account_provider, address, &t)
+	} else {
+		account_provider.sign(address, t.hash()).map_err(errors::from_signing_error)
 	};
+	let result = signature.and_then(|signature| {
+		let signed_transaction = t.clone().with_signature(signature);
+		trace!(target: "miner", "send_transaction: dispatching tx: {}", ::rlp::encode(&signed_transaction).to_vec().pretty());
+		dispatch_transaction(&*client, &*miner, signed_transaction)
+	});
+
+	match result {
+		Ok(_) => account_provider.record_spending(&address, &t),
+		Err(_) => account_provider.cancel_spending(&address, &t),
+	}
+	result.map(to_value)
+}
 
-	trace!(target: "miner", "send_transaction: dispatching tx: {}", ::rlp::encode(&signed_transaction).to_vec().pretty());
-	dispatch_transaction(&*client, &*miner, signed_transaction).map(to_value)
+/// Simulates execution of the transaction against pending state without signing it.
+/// The outcome describes whether the transaction would be valid and succeed if it was dispatched.
+pub fn simulate_transaction<C, M>(client: &C, miner: &M, request: TransactionRequest) -> TransactionSimulation
+	where C: MiningBlockChainClient, M: MinerService {
+	let from = request.from;
+	let t = prepare_transaction(client, miner, request);
+	let mut simulation = TransactionSimulation::default();
+
+	// estimate gas the same way `eth_estimateGas` does
+	let estimate = Transaction { gas: ESTIMATE_GAS_LIMIT.into(), ..t.clone() }.fake_sign(from);
+	let gas_estimate = match miner.call(client, &estimate, Default::default()) {
+		Ok(executed) => executed.gas_used + executed.refunded,
+		Err(e) => {
+			simulation.error = Some(format!("{}", e));
+			return simulation;
+		},
+	};
+	simulation.gas_estimate = Some(gas_estimate.into());
+
+	// dry-run with the requested gas limit
+	let analytics = CallAnalytics {
+		transaction_tracing: true,
+		vm_tracing: false,
+		state_diffing: true,
+	};
+	let executed = match miner.call(client, &t.clone().fake_sign(from), analytics) {
+		Ok(executed) => executed,
+		Err(e) => {
+			simulation.error = Some(format!("{}", e));
+			return simulation;
+		},
+	};
+	simulation.gas_used = Some(executed.gas_used.into());
+	simulation.output = Some(executed.output.into());
+	simulation.logs = executed.logs.into_iter().map(Into::into).collect();
+	simulation.state_diff = executed.state_diff.map(Into::into);
+
+	// calls don't check nonce nor balance of the sender
+	let nonce = miner.nonce(client, &from);
+	let balance = miner.balance(client, &from);
+	let cost = t.value + t.gas * t.gas_price;
+	simulation.error = if t.nonce < nonce {
+		Some(format!("Nonce is too low: the account has already sent {} transactions.", nonce))
+	} else if balance < cost {
+		Some(format!("Insufficient funds: the transaction costs up to {} wei, but the account has {} wei.", cost, balance))
+	} else if t.gas < gas_estimate {
+		Some(format!("Gas limit is too low: the transaction requires {} gas.", gas_estimate))
+	} else {
+		executed.trace.first().and_then(|trace| match trace.result {
+			Res::FailedCall(ref e) | Res::FailedCreate(ref e) => Some(format!("Transaction would fail: {}", e)),
+			_ => None,
+		})
+	};
+	simulation.success = simulation.error.is_none();
+	simulation
 }
 
 pub fn default_gas_price<C, M>(client: &C, miner: &M) -> U256 where C: MiningBlockChainClient, M: MinerService {
@@ -23,8 +23,10 @@ macro_rules! rpc_unimplemented {
 use std::fmt;
 use ethcore::error::Error as EthcoreError;
 use ethcore::account_provider::{Error as AccountError};
+use ethcore::signing_policy::PolicyViolation;
 use fetch::FetchError;
-use jsonrpc_core::{Error, ErrorCode, Value};
+use jsonrpc_core::{Error, ErrorCode, Value, to_value};
+use v1::types::TransactionSimulation;
 
 mod codes {
 	// NOTE [ToDr] Codes from [-32099, -32000]
@@ -34,9 +36,12 @@ mod codes {
 	pub const NO_NEW_WORK: i64 = -32003;
 	pub const UNKNOWN_ERROR: i64 = -32009;
 	pub const TRANSACTION_ERROR: i64 = -32010;
+	pub const SIMULATION_FAILED: i64 = -32011;
 	pub const ACCOUNT_LOCKED: i64 = -32020;
 	pub const PASSWORD_INVALID: i64 = -32021;
 	pub const ACCOUNT_ERROR: i64 = -32023;
+	pub const DEVICE_ERROR: i64 = -32024;
+	pub const POLICY_VIOLATION: i64 = -32025;
 	pub const SIGNER_DISABLED: i64 = -32030;
 	pub const REQUEST_REJECTED: i64 = -32040;
 	pub const REQUEST_REJECTED_LIMIT: i64 = -32041;
@@ -173,6 +178,38 @@ pub fn from_signing_error(error: AccountError) -> Error {
 	}
 }
 
+pub fn from_device_error(error: AccountError) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::DEVICE_ERROR),
+		message: "Signing with the hardware wallet failed. Make sure the device is connected and confirm the request on the device.".into(),
+		data: Some(Value::String(format!("{:?}", error))),
+	}
+}
+
+pub fn device_sign_unsupported() -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::DEVICE_ERROR),
+		message: "The account is held by a hardware wallet, which can only sign transactions.".into(),
+		data: None,
+	}
+}
+
+pub fn from_policy_violation(violation: PolicyViolation) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::POLICY_VIOLATION),
+		message: format!("Signing policy of the account doesn't allow automatic signing: {}", violation),
+		data: None,
+	}
+}
+
+pub fn simulation_failed(simulation: TransactionSimulation) -> Error {
+	Error {
+		code: ErrorCode::ServerError(codes::SIMULATION_FAILED),
+		message: format!("Transaction was not signed. {} Set `ignoreSimulation` to sign it anyway.", simulation.error.clone().unwrap_or_default()),
+		data: Some(to_value(&simulation)),
+	}
+}
+
 pub fn from_password_error(error: AccountError) -> Error {
 	Error {
 		code: ErrorCode::ServerError(codes::PASSWORD_INVALID),
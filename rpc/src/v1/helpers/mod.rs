@@ -22,7 +22,9 @@ pub mod errors;
 
 pub mod dispatch;
 pub mod params;
+pub mod vaults;
 
+mod audit;
 mod poll_manager;
 mod poll_filter;
 mod requests;
@@ -33,6 +35,7 @@ mod network_settings;
 pub use self::poll_manager::PollManager;
 pub use self::poll_filter::{PollFilter, limit_logs};
 pub use self::requests::{TransactionRequest, FilledTransactionRequest, ConfirmationRequest, ConfirmationPayload, CallRequest};
-pub use self::signing_queue::{ConfirmationsQueue, ConfirmationPromise, ConfirmationResult, SigningQueue, QueueEvent};
+pub use self::signing_queue::{ConfirmationsQueue, ConfirmationPromise, ConfirmationResult, SigningQueue, QueueEvent, QueueOptions, DEFAULT_QUEUE_LIMIT};
+pub use self::audit::with_signer_token;
 pub use self::signer::SignerService;
 pub use self::network_settings::NetworkSettings;
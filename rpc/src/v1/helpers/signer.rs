@@ -16,7 +16,12 @@
 
 use std::sync::Arc;
 use std::ops::Deref;
-use v1::helpers::signing_queue::{ConfirmationsQueue};
+use std::thread;
+use std::time::Duration;
+use v1::helpers::signing_queue::{ConfirmationsQueue, QueueOptions};
+
+/// How often expired requests are removed from the queue.
+const EXPIRY_CHECK_INTERVAL_MS: u64 = 1000;
 
 /// Manages communication with Signer crate
 pub struct SignerService {
@@ -26,11 +31,28 @@ pub struct SignerService {
 
 impl SignerService {
 
-	/// Creates new Signer Service given function to generate new tokens.
-	pub fn new<F>(new_token: F) -> Self
+	/// Creates new Signer Service given function to generate new tokens and queue options.
+	pub fn new<F>(new_token: F, options: QueueOptions) -> Self
 		where F: Fn() -> Result<String, String> + Send + Sync + 'static {
+		let expires = options.expiry.is_some();
+		let queue = Arc::new(ConfirmationsQueue::new(options));
+		if expires {
+			// reject expired requests even when nobody reads the queue
+			let weak = Arc::downgrade(&queue);
+			let spawned = thread::Builder::new().name("signer-expiry".into()).spawn(move || loop {
+				thread::sleep(Duration::from_millis(EXPIRY_CHECK_INTERVAL_MS));
+				match weak.upgrade() {
+					Some(queue) => queue.remove_expired(),
+					None => break,
+				}
+			});
+			if let Err(e) = spawned {
+				warn!(target: "signer", "Unable to start request expiry thread: {}", e);
+			}
+		}
+
 		SignerService {
-			queue: Arc::new(ConfirmationsQueue::default()),
+			queue: queue,
 			generate_new_token: Box::new(new_token),
 		}
 	}
@@ -48,7 +70,7 @@ impl SignerService {
 	#[cfg(test)]
 	/// Creates new Signer Service for tests.
 	pub fn new_test() -> Self {
-		SignerService::new(|| Ok("new_token".into()))
+		SignerService::new(|| Ok("new_token".into()), QueueOptions::default())
 	}
 }
 
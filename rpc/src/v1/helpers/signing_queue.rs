@@ -15,12 +15,18 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::mem;
+use std::fs::{self, File};
+use std::path::PathBuf;
 use std::cell::RefCell;
 use std::sync::{mpsc, Arc};
 use std::collections::BTreeMap;
+use serde_json;
 use jsonrpc_core;
 use util::{Mutex, RwLock, U256};
+use util::path::restrict_permissions_owner;
+use v1::types;
 use v1::helpers::{ConfirmationRequest, ConfirmationPayload};
+use v1::helpers::audit::{self, AuditLog, Decision};
 
 /// Result that can be returned from JSON RPC.
 pub type RpcResult = Result<jsonrpc_core::Value, jsonrpc_core::Error>;
@@ -56,8 +62,32 @@ pub enum QueueAddError {
 /// Message Receiver type
 pub type QueueEventReceiver = mpsc::Receiver<QueueEvent>;
 
-// TODO [todr] to consider: timeout instead of limit?
-const QUEUE_LIMIT: usize = 50;
+/// Default maximal number of requests awaiting confirmation.
+pub const DEFAULT_QUEUE_LIMIT: usize = 50;
+
+/// Confirmations queue configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueOptions {
+	/// Maximal number of requests awaiting confirmation.
+	pub limit: usize,
+	/// Number of seconds after which unresolved requests are rejected.
+	pub expiry: Option<u64>,
+	/// File to persist requests awaiting confirmation to.
+	pub path: Option<PathBuf>,
+	/// File to append resolved requests to.
+	pub audit_path: Option<PathBuf>,
+}
+
+impl Default for QueueOptions {
+	fn default() -> Self {
+		QueueOptions {
+			limit: DEFAULT_QUEUE_LIMIT,
+			expiry: None,
+			path: None,
+			audit_path: None,
+		}
+	}
+}
 
 /// A queue of transactions awaiting to be confirmed and signed.
 pub trait SigningQueue: Send + Sync {
@@ -105,6 +135,7 @@ pub struct ConfirmationToken {
 	result: Arc<Mutex<ConfirmationResult>>,
 	listeners: Arc<Mutex<Vec<Listener>>>,
 	request: ConfirmationRequest,
+	expires: Option<u64>,
 }
 
 pub struct ConfirmationPromise {
@@ -114,6 +145,15 @@ pub struct ConfirmationPromise {
 }
 
 impl ConfirmationToken {
+	fn new(request: ConfirmationRequest, expires: Option<u64>) -> Self {
+		ConfirmationToken {
+			result: Arc::new(Mutex::new(ConfirmationResult::Waiting)),
+			listeners: Default::default(),
+			request: request,
+			expires: expires,
+		}
+	}
+
 	/// Submit solution to all listeners
 	fn resolve(&self, result: Option<RpcResult>) {
 		let wrapped = result.clone().map_or(ConfirmationResult::Rejected, |h| ConfirmationResult::Confirmed(h));
@@ -169,22 +209,102 @@ pub struct ConfirmationsQueue {
 	queue: RwLock<BTreeMap<U256, ConfirmationToken>>,
 	sender: Mutex<mpsc::Sender<QueueEvent>>,
 	receiver: Mutex<Option<mpsc::Receiver<QueueEvent>>>,
+	limit: usize,
+	expiry: Option<u64>,
+	path: Option<Mutex<PathBuf>>,
+	audit: AuditLog,
 }
 
 impl Default for ConfirmationsQueue {
 	fn default() -> Self {
+		ConfirmationsQueue::new(QueueOptions::default())
+	}
+}
+
+impl ConfirmationsQueue {
+	/// Creates new queue with given options.
+	/// Requests persisted by previous instance of the queue are restored.
+	pub fn new(options: QueueOptions) -> Self {
 		let (send, recv) = mpsc::channel();
+		let restored = options.path.as_ref().map_or_else(Vec::new, |path| Self::load(path));
+		let last_id = restored.iter().map(|token| token.request.id).max().unwrap_or_else(U256::zero);
 
 		ConfirmationsQueue {
-			id: Mutex::new(U256::from(0)),
-			queue: RwLock::new(BTreeMap::new()),
+			id: Mutex::new(last_id),
+			queue: RwLock::new(restored.into_iter().map(|token| (token.request.id, token)).collect()),
 			sender: Mutex::new(send),
 			receiver: Mutex::new(Some(recv)),
+			limit: options.limit,
+			expiry: options.expiry,
+			path: options.path.map(Mutex::new),
+			audit: AuditLog::new(options.audit_path),
 		}
 	}
-}
 
-impl ConfirmationsQueue {
+	/// Reads requests persisted in given file.
+	fn load(path: &PathBuf) -> Vec<ConfirmationToken> {
+		let file = match File::open(path) {
+			Ok(file) => file,
+			Err(_) => return Vec::new(),
+		};
+
+		match serde_json::from_reader::<_, Vec<(types::ConfirmationRequest, Option<u64>)>>(file) {
+			Ok(requests) => requests.into_iter()
+				.map(|(request, expires)| ConfirmationToken::new(request.into(), expires))
+				.collect(),
+			Err(e) => {
+				warn!(target: "signer", "Unable to restore pending requests from {}: {:?}", path.display(), e);
+				Vec::new()
+			},
+		}
+	}
+
+	/// Writes requests awaiting confirmation to a file (if persistence is enabled).
+	fn save(&self) {
+		if let Some(ref path) = self.path {
+			let path = path.lock();
+			// messages to decrypt are never written to disk
+			let requests = self.queue.read().values()
+				.filter(|token| match token.request.payload {
+					ConfirmationPayload::Decrypt(..) => false,
+					_ => true,
+				})
+				.map(|token| (types::ConfirmationRequest::from(token.request.clone()), token.expires))
+				.collect::<Vec<_>>();
+			// requests are written aside and moved in place, so a crash never leaves the file truncated
+			let temp_path = path.with_extension("json.tmp");
+			let res = File::create(&temp_path)
+				.map_err(|e| format!("{}", e))
+				.and_then(|mut file| {
+					if let Err(e) = restrict_permissions_owner(&temp_path) {
+						warn!(target: "signer", "Failed to restrict permissions of {}: {}", temp_path.display(), e);
+					}
+					serde_json::to_writer(&mut file, &requests).map_err(|e| format!("{:?}", e))
+				})
+				.and_then(|_| fs::rename(&temp_path, &*path).map_err(|e| format!("{}", e)));
+			if let Err(e) = res {
+				warn!(target: "signer", "Unable to persist pending requests to {}: {}", path.display(), e);
+			}
+		}
+	}
+
+	/// Rejects all requests that were not resolved in time.
+	/// Called periodically by `SignerService` and before requests are read.
+	pub fn remove_expired(&self) {
+		if self.expiry.is_none() {
+			return;
+		}
+
+		let now = audit::now();
+		let expired = self.queue.read().values()
+			.filter(|token| token.expires.map_or(false, |expires| expires <= now))
+			.map(|token| token.request.id)
+			.collect::<Vec<_>>();
+		for id in expired {
+			debug!(target: "own_tx", "Signer: Request expired ({:?}).", id);
+			self.remove(id, None, Decision::Expired);
+		}
+	}
 
 	/// Blocks the thread and starts listening for notifications regarding all actions in the queue.
 	/// For each event, `listener` callback will be invoked.
@@ -221,10 +341,12 @@ impl ConfirmationsQueue {
 
 	/// Removes requests from this queue and notifies `ConfirmationPromise` holders about the result.
 	/// Notifies also a receiver about that event.
-	fn remove(&self, id: U256, result: Option<RpcResult>) -> Option<ConfirmationRequest> {
+	fn remove(&self, id: U256, result: Option<RpcResult>, decision: Decision) -> Option<ConfirmationRequest> {
 		let token = self.queue.write().remove(&id);
 
 		if let Some(token) = token {
+			self.save();
+			self.audit.record(&token.request, decision, result.as_ref());
 			// notify receiver about the event
 			self.notify(result.clone().map_or_else(
 				|| QueueEvent::RequestRejected(id),
@@ -247,7 +369,7 @@ impl Drop for ConfirmationsQueue {
 
 impl SigningQueue for ConfirmationsQueue {
 	fn add_request(&self, request: ConfirmationPayload) -> Result<ConfirmationPromise, QueueAddError> {
-		if self.len() > QUEUE_LIMIT {
+		if self.len() >= self.limit {
 			return Err(QueueAddError::LimitReached);
 		}
 
@@ -263,46 +385,47 @@ impl SigningQueue for ConfirmationsQueue {
 			trace!(target: "own_tx", "Signer: ({:?}) : {:?}", id, request);
 
 			let mut queue = self.queue.write();
-			queue.insert(id, ConfirmationToken {
-				result: Arc::new(Mutex::new(ConfirmationResult::Waiting)),
-				listeners: Default::default(),
-				request: ConfirmationRequest {
-					id: id,
-					payload: request,
-				},
-			});
+			queue.insert(id, ConfirmationToken::new(ConfirmationRequest {
+				id: id,
+				payload: request,
+			}, self.expiry.map(|expiry| audit::now() + expiry)));
 			queue.get(&id).map(|token| token.as_promise()).expect("Token was just inserted.")
 		};
+		self.save();
 		// Notify listeners
 		self.notify(QueueEvent::NewRequest(id));
 		Ok(res)
 	}
 
 	fn peek(&self, id: &U256) -> Option<ConfirmationRequest> {
+		self.remove_expired();
 		self.queue.read().get(id).map(|token| token.request.clone())
 	}
 
 	fn request_rejected(&self, id: U256) -> Option<ConfirmationRequest> {
 		debug!(target: "own_tx", "Signer: Request rejected ({:?}).", id);
-		self.remove(id, None)
+		self.remove(id, None, Decision::Rejected)
 	}
 
 	fn request_confirmed(&self, id: U256, result: RpcResult) -> Option<ConfirmationRequest> {
 		debug!(target: "own_tx", "Signer: Transaction confirmed ({:?}).", id);
-		self.remove(id, Some(result))
+		self.remove(id, Some(result), Decision::Confirmed)
 	}
 
 	fn requests(&self) -> Vec<ConfirmationRequest> {
+		self.remove_expired();
 		let queue = self.queue.read();
 		queue.values().map(|token| token.request.clone()).collect()
 	}
 
 	fn len(&self) -> usize {
+		self.remove_expired();
 		let queue = self.queue.read();
 		queue.len()
 	}
 
 	fn is_empty(&self) -> bool {
+		self.remove_expired();
 		let queue = self.queue.read();
 		queue.is_empty()
 	}
@@ -316,8 +439,11 @@ mod test {
 	use std::sync::{mpsc, Arc};
 	use util::{Address, U256, H256, Mutex};
 	use v1::helpers::{SigningQueue, ConfirmationsQueue, QueueEvent, FilledTransactionRequest, ConfirmationPayload};
+	use v1::helpers::{QueueOptions, ConfirmationResult, SignerService};
+	use super::QueueAddError;
 	use v1::types::H256 as NH256;
 	use jsonrpc_core::to_value;
+	use devtools::RandomTempPath;
 
 	fn request() -> ConfirmationPayload {
 		ConfirmationPayload::Transaction(FilledTransactionRequest {
@@ -400,4 +526,102 @@ mod test {
 		assert_eq!(el.id, U256::from(1));
 		assert_eq!(el.payload, request);
 	}
+
+	#[test]
+	fn should_reject_requests_over_limit() {
+		// given
+		let queue = ConfirmationsQueue::new(QueueOptions {
+			limit: 1,
+			..Default::default()
+		});
+		queue.add_request(request()).unwrap();
+
+		// when
+		let res = queue.add_request(request());
+
+		// then
+		assert_eq!(res.err(), Some(QueueAddError::LimitReached));
+	}
+
+	#[test]
+	fn should_reject_expired_requests() {
+		// given
+		let queue = ConfirmationsQueue::new(QueueOptions {
+			expiry: Some(0),
+			..Default::default()
+		});
+
+		// when
+		let promise = queue.add_request(request()).unwrap();
+
+		// then
+		assert_eq!(queue.requests().len(), 0);
+		assert_eq!(promise.result(), ConfirmationResult::Rejected);
+	}
+
+	#[test]
+	fn should_reject_expired_requests_without_reads() {
+		// given
+		let signer = SignerService::new(|| Ok("new_token".into()), QueueOptions {
+			expiry: Some(0),
+			..Default::default()
+		});
+
+		// when
+		let promise = signer.add_request(request()).unwrap();
+		thread::sleep(Duration::from_millis(1500));
+
+		// then
+		assert_eq!(promise.result(), ConfirmationResult::Rejected);
+	}
+
+	#[test]
+	fn should_restore_persisted_requests() {
+		// given
+		let path = RandomTempPath::new();
+		let options = QueueOptions {
+			path: Some(path.as_path().to_owned()),
+			..Default::default()
+		};
+		{
+			let queue = ConfirmationsQueue::new(options.clone());
+			queue.add_request(request()).unwrap();
+			queue.add_request(request()).unwrap();
+			queue.request_rejected(U256::from(1));
+		}
+
+		// when
+		let queue = ConfirmationsQueue::new(options);
+		let promise = queue.add_request(request()).unwrap();
+
+		// then
+		let all = queue.requests();
+		assert_eq!(all.len(), 2);
+		assert_eq!(all[0].id, U256::from(2));
+		assert_eq!(all[0].payload, request());
+		assert_eq!(promise.id(), U256::from(3));
+	}
+
+	#[test]
+	fn should_not_persist_decrypt_requests() {
+		// given
+		let path = RandomTempPath::new();
+		let options = QueueOptions {
+			path: Some(path.as_path().to_owned()),
+			..Default::default()
+		};
+		{
+			let queue = ConfirmationsQueue::new(options.clone());
+			queue.add_request(request()).unwrap();
+			queue.add_request(ConfirmationPayload::Decrypt(Address::from(1), vec![1, 2, 3].into())).unwrap();
+		}
+
+		// when
+		let queue = ConfirmationsQueue::new(options);
+
+		// then
+		let all = queue.requests();
+		assert_eq!(all.len(), 1);
+		assert_eq!(all[0].payload, request());
+	}
 }
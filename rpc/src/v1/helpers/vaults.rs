@@ -0,0 +1,69 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Vault management shared by the `personal` and `ethcore` APIs.
+
+use jsonrpc_core::*;
+use ethcore::account_provider::AccountProvider;
+use v1::helpers::errors;
+use v1::helpers::params::expect_no_params;
+use v1::types::H160 as RpcH160;
+
+/// Creates new vault with given name and password and opens it.
+pub fn new_vault(store: &AccountProvider, params: Params) -> Result<Value, Error> {
+	from_params::<(String, String)>(params).and_then(|(name, password)| {
+		try!(store.create_vault(&name, &password).map_err(|e| errors::account("Could not create vault.", e)));
+		Ok(Value::Bool(true))
+	})
+}
+
+/// Opens the vault with given name and password.
+pub fn open_vault(store: &AccountProvider, params: Params) -> Result<Value, Error> {
+	from_params::<(String, String)>(params).and_then(|(name, password)| {
+		try!(store.open_vault(&name, &password).map_err(|e| errors::account("Could not open vault.", e)));
+		Ok(Value::Bool(true))
+	})
+}
+
+/// Closes the vault with given name.
+pub fn close_vault(store: &AccountProvider, params: Params) -> Result<Value, Error> {
+	from_params::<(String,)>(params).and_then(|(name,)| {
+		try!(store.close_vault(&name).map_err(|e| errors::account("Could not close vault.", e)));
+		Ok(Value::Bool(true))
+	})
+}
+
+/// Returns names of all vaults.
+pub fn list_vaults(store: &AccountProvider, params: Params) -> Result<Value, Error> {
+	try!(expect_no_params(params));
+	Ok(to_value(&try!(store.list_vaults().map_err(|e| errors::account("Could not list vaults.", e)))))
+}
+
+/// Returns names of opened vaults.
+pub fn list_opened_vaults(store: &AccountProvider, params: Params) -> Result<Value, Error> {
+	try!(expect_no_params(params));
+	Ok(to_value(&try!(store.list_opened_vaults().map_err(|e| errors::account("Could not list vaults.", e)))))
+}
+
+/// Moves the account to an opened vault. Empty vault name moves it out of vaults.
+pub fn change_vault(store: &AccountProvider, params: Params) -> Result<Value, Error> {
+	from_params::<(RpcH160, String)>(params).and_then(|(address, name)| {
+		let vault = if name.is_empty() { None } else { Some(name.as_str()) };
+		try!(store.change_vault(address.into(), vault).map_err(|e| errors::account("Could not change account vault.", e)));
+		Ok(Value::Bool(true))
+	})
+}
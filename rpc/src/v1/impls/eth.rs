@@ -255,24 +255,26 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		try!(self.active());
 
 		let status = take_weak!(self.sync).status();
-		match status.state {
-			SyncState::Idle => Ok(SyncStatus::None),
+		let current_block = U256::from(take_weak!(self.client).chain_info().best_block_number);
+		let highest_block = U256::from(status.highest_block_number.unwrap_or(status.start_block_number));
+		let syncing = match status.state {
+			SyncState::Idle => false,
 			SyncState::Waiting | SyncState::Blocks | SyncState::NewBlocks | SyncState::ChainHead
-				| SyncState::SnapshotManifest | SyncState::SnapshotData | SyncState::SnapshotWaiting => {
-				let current_block = U256::from(take_weak!(self.client).chain_info().best_block_number);
-				let highest_block = U256::from(status.highest_block_number.unwrap_or(status.start_block_number));
-
-				if highest_block > current_block + U256::from(6) {
-					let info = SyncInfo {
-						starting_block: status.start_block_number.into(),
-						current_block: current_block.into(),
-						highest_block: highest_block.into(),
-					};
-					Ok(SyncStatus::Info(info))
-				} else {
-					Ok(SyncStatus::None)
-				}
-			}
+				| SyncState::SnapshotManifest | SyncState::SnapshotData | SyncState::SnapshotWaiting
+				| SyncState::StateData => highest_block > current_block + U256::from(6),
+		};
+
+		// blocks below a restored snapshot are downloaded in the background, which is reported as syncing too.
+		if syncing || status.ancient_block_number.is_some() {
+			let info = SyncInfo {
+				starting_block: status.start_block_number.into(),
+				current_block: current_block.into(),
+				highest_block: highest_block.into(),
+				ancient_block: status.ancient_block_number.map(Into::into),
+			};
+			Ok(SyncStatus::Info(info))
+		} else {
+			Ok(SyncStatus::None)
 		}
 	}
 
@@ -23,10 +23,11 @@ use ethcore::client::MiningBlockChainClient;
 use util::{U256, Address, H256, Mutex};
 use transient_hashmap::TransientHashMap;
 use ethcore::account_provider::AccountProvider;
+use ethcore::signing_policy::PolicyViolation;
 use v1::helpers::{errors, SigningQueue, ConfirmationPromise, ConfirmationResult, ConfirmationPayload, TransactionRequest as TRequest, FilledTransactionRequest as FilledRequest, SignerService};
-use v1::helpers::dispatch::{default_gas_price, sign_and_dispatch};
+use v1::helpers::dispatch::{default_gas_price, sign_and_dispatch, check_signing_policy};
 use v1::traits::EthSigning;
-use v1::types::{TransactionRequest, H160 as RpcH160, H256 as RpcH256, H520 as RpcH520, U256 as RpcU256, Bytes as RpcBytes};
+use v1::types::{TransactionRequest, H160 as RpcH160, H256 as RpcH256, H512 as RpcH512, H520 as RpcH520, U256 as RpcU256, Bytes as RpcBytes};
 
 fn fill_optional_fields<C, M>(request: TRequest, client: &C, miner: &M) -> FilledRequest
 	where C: MiningBlockChainClient, M: MinerService {
@@ -82,7 +83,11 @@ impl<C, M> EthSigningQueueClient<C, M> where C: MiningBlockChainClient, M: Miner
 			let msg: H256 = msg.into();
 
 			let accounts = take_weak!(self.accounts);
-			if accounts.is_unlocked(address) {
+			if accounts.is_external(&address) {
+				return Err(errors::device_sign_unsupported());
+			}
+			// accounts with a signing policy may only sign transactions checked against it
+			if accounts.is_unlocked(address) && accounts.signing_policy(&address).is_none() {
 				return Ok(DispatchResult::Value(to_value(&accounts.sign(address, msg).ok().map_or_else(RpcH520::default, Into::into))))
 			}
 
@@ -93,6 +98,24 @@ impl<C, M> EthSigningQueueClient<C, M> where C: MiningBlockChainClient, M: Miner
 		})
 	}
 
+	fn dispatch_decrypt(&self, params: Params) -> Result<DispatchResult, Error> {
+		from_params::<(RpcH160, RpcBytes)>(params).and_then(|(address, msg)| {
+			let address: Address = address.into();
+
+			let accounts = take_weak!(self.accounts);
+			if accounts.is_unlocked(address) && accounts.signing_policy(&address).is_none() {
+				return accounts.decrypt(address, &[0; 0], &msg.0)
+					.map(|data| DispatchResult::Value(to_value(&RpcBytes::from(data))))
+					.map_err(|e| errors::account("Could not decrypt message.", e));
+			}
+
+			let signer = take_weak!(self.signer);
+			signer.add_request(ConfirmationPayload::Decrypt(address, msg.into()))
+				.map(DispatchResult::Promise)
+				.map_err(|_| errors::request_rejected_limit())
+		})
+	}
+
 	fn dispatch_transaction(&self, params: Params) -> Result<DispatchResult, Error> {
 		from_params::<(TransactionRequest, )>(params)
 			.and_then(|(request, )| {
@@ -101,8 +124,15 @@ impl<C, M> EthSigningQueueClient<C, M> where C: MiningBlockChainClient, M: Miner
 				let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
 
 				if accounts.is_unlocked(request.from) {
-					let sender = request.from;
-					return sign_and_dispatch(&*client, &*miner, request, &*accounts, sender).map(DispatchResult::Value);
+					match check_signing_policy(&*client, &*miner, &request, &*accounts) {
+						Ok(_) => {
+							let sender = request.from;
+							return sign_and_dispatch(&*client, &*miner, request, &*accounts, sender).map(DispatchResult::Value);
+						},
+						Err(violation) => {
+							debug!(target: "signer", "Transaction from {} requires confirmation: {}", request.from, violation);
+						},
+					}
 				}
 
 				let signer = take_weak!(self.signer);
@@ -168,10 +198,25 @@ impl<C, M> EthSigning for EthSigningQueueClient<C, M>
 		})
 	}
 
-	fn decrypt_message(&self, params: Params) -> Result<Value, Error> {
+	fn decrypt_message(&self, params: Params, ready: Ready) {
+		let res = self.active().and_then(|_| self.dispatch_decrypt(params));
+		match res {
+			Ok(DispatchResult::Promise(promise)) => {
+				promise.wait_for_result(move |result| {
+					ready.ready(result.unwrap_or_else(|| Err(errors::request_rejected())))
+				})
+			},
+			Ok(DispatchResult::Value(v)) => ready.ready(Ok(v)),
+			Err(e) => ready.ready(Err(e)),
+		}
+	}
+
+	fn account_public_key(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params::<(RpcH160, RpcBytes)>(params).and_then(|(_account, _ciphertext)| {
-			Err(errors::unimplemented())
+		from_params::<(RpcH160, )>(params).and_then(|(address, )| {
+			take_weak!(self.accounts).account_public(address.into())
+				.map(|public| to_value(&RpcH512::from(public)))
+				.map_err(|e| errors::account("Could not read the public key. Make sure the account is unlocked.", e))
 		})
 	}
 
@@ -234,7 +279,14 @@ impl<C, M> EthSigning for EthSigningUnsafeClient<C, M> where
 			.and_then(|(address, msg)| {
 				let address: Address = address.into();
 				let msg: H256 = msg.into();
-				Ok(to_value(&take_weak!(self.accounts).sign(address, msg).ok().map_or_else(RpcH520::default, Into::into)))
+				let accounts = take_weak!(self.accounts);
+				if accounts.is_external(&address) {
+					return Err(errors::device_sign_unsupported());
+				}
+				if accounts.signing_policy(&address).is_some() {
+					return Err(errors::from_policy_violation(PolicyViolation::ArbitraryData));
+				}
+				Ok(to_value(&accounts.sign(address, msg).ok().map_or_else(RpcH520::default, Into::into)))
 			}))
 	}
 
@@ -248,11 +300,21 @@ impl<C, M> EthSigning for EthSigningUnsafeClient<C, M> where
 			}))
 	}
 
-	fn decrypt_message(&self, params: Params) -> Result<Value, Error> {
+	fn decrypt_message(&self, params: Params, ready: Ready) {
+		ready.ready(self.active()
+			.and_then(|_| from_params::<(RpcH160, RpcBytes)>(params))
+			.and_then(|(address, ciphertext)| {
+				let s = try!(take_weak!(self.accounts).decrypt(address.into(), &[0; 0], &ciphertext.0).map_err(errors::from_signing_error));
+				Ok(to_value(RpcBytes::from(s)))
+			}))
+	}
+
+	fn account_public_key(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params::<(RpcH160, RpcBytes)>(params).and_then(|(address, ciphertext)| {
-			let s = try!(take_weak!(self.accounts).decrypt(address.into(), &[0; 0], &ciphertext.0).map_err(|_| Error::internal_error()));
-			Ok(to_value(RpcBytes::from(s)))
+		from_params::<(RpcH160, )>(params).and_then(|(address, )| {
+			take_weak!(self.accounts).account_public(address.into())
+				.map(|public| to_value(&RpcH512::from(public)))
+				.map_err(|e| errors::account("Could not read the public key. Make sure the account is unlocked.", e))
 		})
 	}
 
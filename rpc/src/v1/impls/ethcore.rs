@@ -22,9 +22,9 @@ use std::collections::{BTreeMap};
 use util::{RotatingLogger, Address, Mutex, sha3};
 use util::misc::version_data;
 
-use crypto::ecies;
+use crypto::{ecies, document};
 use fetch::{Client as FetchClient, Fetch};
-use ethkey::{Brain, Generator};
+use ethkey::{Brain, Generator, Public};
 use ethstore::random_phrase;
 use ethsync::{SyncProvider, ManageNetwork};
 use ethcore::miner::MinerService;
@@ -32,7 +32,7 @@ use ethcore::client::{MiningBlockChainClient};
 
 use jsonrpc_core::{from_params, to_value, Value, Error, Params, Ready};
 use v1::traits::Ethcore;
-use v1::types::{Bytes, U256, H160, H256, H512, Peers, Transaction};
+use v1::types::{Bytes, U256, H160, H256, H512, Peers, NetworkTraffic, NodeTableEntry, SyncDetails, Transaction, EncryptedDocument};
 use v1::helpers::{errors, SigningQueue, SignerService, NetworkSettings};
 use v1::helpers::params::expect_no_params;
 
@@ -166,15 +166,41 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 		try!(expect_no_params(params));
 
 		let sync_status = take_weak!(self.sync).status();
-		let net_config = take_weak!(self.net).network_config();
+		let net = take_weak!(self.net);
+		let net_config = net.network_config();
 
 		Ok(to_value(&Peers {
 			active: sync_status.num_active_peers,
 			connected: sync_status.num_peers,
 			max: sync_status.current_max_peers(net_config.min_peers, net_config.max_peers),
+			peers: net.peers_traffic().into_iter().map(Into::into).collect(),
 		}))
 	}
 
+	fn net_traffic(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		let traffic: NetworkTraffic = take_weak!(self.net).network_traffic().into();
+		Ok(to_value(&traffic))
+	}
+
+	fn net_node_table(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		let nodes: Vec<NodeTableEntry> = take_weak!(self.net).node_table().into_iter().map(Into::into).collect();
+		Ok(to_value(&nodes))
+	}
+
+	fn sync_status(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		let details: SyncDetails = take_weak!(self.sync).details().into();
+		Ok(to_value(&details))
+	}
+
 	fn net_port(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		try!(expect_no_params(params));
@@ -259,6 +285,23 @@ impl<C, M, S: ?Sized, F> Ethcore for EthcoreClient<C, M, S, F> where
 		})
 	}
 
+	fn encrypt_document(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Vec<H512>, Bytes)>(params).and_then(|(recipients, doc)| {
+			let recipients = recipients.into_iter().map(Into::into).collect::<Vec<Public>>();
+			let encrypted = try!(document::encrypt(&recipients, &doc.0).map_err(|e| errors::invalid_params("Invalid recipient public key", e)));
+			Ok(to_value(&EncryptedDocument::from(encrypted)))
+		})
+	}
+
+	fn decrypt_document(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Bytes, Bytes)>(params).and_then(|(key, doc)| {
+			let plain = try!(document::decrypt(&key.0, &doc.0).map_err(|e| errors::invalid_params("Invalid key or document", e)));
+			Ok(to_value(&Bytes::from(plain)))
+		})
+	}
+
 	fn pending_transactions(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		try!(expect_no_params(params));
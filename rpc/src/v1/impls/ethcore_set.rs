@@ -19,9 +19,11 @@ use std::sync::{Arc, Weak};
 use jsonrpc_core::*;
 use ethcore::miner::MinerService;
 use ethcore::client::MiningBlockChainClient;
+use ethcore::account_provider::AccountProvider;
 use ethsync::ManageNetwork;
 use v1::helpers::errors;
 use v1::helpers::params::expect_no_params;
+use v1::helpers::vaults;
 use v1::traits::EthcoreSet;
 use v1::types::{Bytes, H160, U256};
 
@@ -33,17 +35,19 @@ pub struct EthcoreSetClient<C, M> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	net: Weak<ManageNetwork>,
+	accounts: Weak<AccountProvider>,
 }
 
 impl<C, M> EthcoreSetClient<C, M> where
 	C: MiningBlockChainClient,
 	M: MinerService {
 	/// Creates new `EthcoreSetClient`.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>) -> Self {
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>, accounts: &Arc<AccountProvider>) -> Self {
 		EthcoreSetClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			net: Arc::downgrade(net),
+			accounts: Arc::downgrade(accounts),
 		}
 	}
 
@@ -148,6 +152,26 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 		Ok(to_value(&true))
 	}
 
+	fn set_allowed_peers(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Vec<String>,)>(params).and_then(|(rules,)| {
+			match take_weak!(self.net).set_allowed_peers(rules) {
+				Ok(()) => Ok(to_value(&true)),
+				Err(e) => Err(errors::invalid_params("Peer rules", e)),
+			}
+		})
+	}
+
+	fn set_denied_peers(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Vec<String>,)>(params).and_then(|(rules,)| {
+			match take_weak!(self.net).set_denied_peers(rules) {
+				Ok(()) => Ok(to_value(&true)),
+				Err(e) => Err(errors::invalid_params("Peer rules", e)),
+			}
+		})
+	}
+
 	fn start_network(&self, params: Params) -> Result<Value, Error> {
 		try!(expect_no_params(params));
 		take_weak!(self.net).start_network();
@@ -156,7 +180,39 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 
 	fn stop_network(&self, params: Params) -> Result<Value, Error> {
 		try!(expect_no_params(params));
-		take_weak!(self.net).stop_network();
-		Ok(Value::Bool(true))
+		match take_weak!(self.net).stop_network() {
+			Ok(()) => Ok(Value::Bool(true)),
+			Err(e) => Err(errors::internal("Could not stop the network.", e)),
+		}
+	}
+
+	fn new_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::new_vault(&*take_weak!(self.accounts), params)
+	}
+
+	fn open_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::open_vault(&*take_weak!(self.accounts), params)
+	}
+
+	fn close_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::close_vault(&*take_weak!(self.accounts), params)
+	}
+
+	fn list_vaults(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::list_vaults(&*take_weak!(self.accounts), params)
+	}
+
+	fn list_opened_vaults(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::list_opened_vaults(&*take_weak!(self.accounts), params)
+	}
+
+	fn change_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::change_vault(&*take_weak!(self.accounts), params)
 	}
 }
@@ -25,6 +25,7 @@ use v1::types::{H160 as RpcH160, TransactionRequest};
 use v1::helpers::{errors, TransactionRequest as TRequest};
 use v1::helpers::params::expect_no_params;
 use v1::helpers::dispatch::unlock_sign_and_dispatch;
+use v1::helpers::vaults;
 use ethcore::account_provider::AccountProvider;
 use ethcore::client::MiningBlockChainClient;
 use ethcore::miner::MinerService;
@@ -142,7 +143,7 @@ impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBl
 				let request: TRequest = request.into();
 				let accounts = take_weak!(self.accounts);
 
-				unlock_sign_and_dispatch(&*take_weak!(self.client), &*take_weak!(self.miner), request, &*accounts, password)
+				unlock_sign_and_dispatch(&*take_weak!(self.client), &*take_weak!(self.miner), request, None, &*accounts, password)
 			})
 	}
 
@@ -202,4 +203,34 @@ impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBl
 			).into_iter().map(Into::into).collect::<Vec<RpcH160>>()))
 		})
 	}
+
+	fn new_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::new_vault(&*take_weak!(self.accounts), params)
+	}
+
+	fn open_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::open_vault(&*take_weak!(self.accounts), params)
+	}
+
+	fn close_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::close_vault(&*take_weak!(self.accounts), params)
+	}
+
+	fn list_vaults(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::list_vaults(&*take_weak!(self.accounts), params)
+	}
+
+	fn list_opened_vaults(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::list_opened_vaults(&*take_weak!(self.accounts), params)
+	}
+
+	fn change_vault(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		vaults::change_vault(&*take_weak!(self.accounts), params)
+	}
 }
@@ -25,7 +25,7 @@ use v1::traits::PersonalSigner;
 use v1::types::{TransactionModification, ConfirmationRequest, U256};
 use v1::helpers::{errors, SignerService, SigningQueue, ConfirmationPayload};
 use v1::helpers::params::expect_no_params;
-use v1::helpers::dispatch::{unlock_sign_and_dispatch, signature_with_password};
+use v1::helpers::dispatch::{unlock_sign_and_dispatch, signature_with_password, decrypt_with_password, simulate_transaction};
 
 /// Transactions confirmation (personal) rpc implementation.
 pub struct SignerClient<C, M> where C: MiningBlockChainClient, M: MinerService {
@@ -65,7 +65,15 @@ impl<C: 'static, M: 'static> PersonalSigner for SignerClient<C, M> where C: Mini
 		try!(self.active());
 		try!(expect_no_params(params));
 		let signer = take_weak!(self.signer);
-		Ok(to_value(&signer.requests().into_iter().map(From::from).collect::<Vec<ConfirmationRequest>>()))
+		let accounts = take_weak!(self.accounts);
+		Ok(to_value(&signer.requests().into_iter().map(|request| {
+			let address = match request.payload {
+				ConfirmationPayload::Transaction(ref request) => request.from,
+				ConfirmationPayload::Sign(address, _) => address,
+				ConfirmationPayload::Decrypt(address, _) => address,
+			};
+			ConfirmationRequest::from(request).with_device(accounts.device_state(&address))
+		}).collect::<Vec<ConfirmationRequest>>()))
 	}
 
 	fn confirm_request(&self, params: Params) -> Result<Value, Error> {
@@ -83,16 +91,26 @@ impl<C: 'static, M: 'static> PersonalSigner for SignerClient<C, M> where C: Mini
 				signer.peek(&id).map(|confirmation| {
 					let result = match confirmation.payload {
 						ConfirmationPayload::Transaction(mut request) => {
-							// apply modification
-							if let Some(gas_price) = modification.gas_price {
-								request.gas_price = gas_price.into();
+							modification.apply(&mut request);
+
+							// make sure the modified transaction is still valid before signing it
+							let simulation = simulate_transaction(&*client, &*miner, request.clone().into());
+							if !simulation.success {
+								if !modification.ignore_simulation.unwrap_or(false) {
+									return Err(errors::simulation_failed(simulation));
+								}
+								warn!(target: "signer", "Signing request {} despite failed simulation: {}", id, simulation.error.unwrap_or_default());
 							}
 
-							unlock_sign_and_dispatch(&*client, &*miner, request.into(), &*accounts, pass)
+							let condition = modification.condition.map(Into::into);
+							unlock_sign_and_dispatch(&*client, &*miner, request.into(), condition, &*accounts, pass)
 						},
 						ConfirmationPayload::Sign(address, hash) => {
 							signature_with_password(&*accounts, address, hash, pass)
-						}
+						},
+						ConfirmationPayload::Decrypt(address, msg) => {
+							decrypt_with_password(&*accounts, address, &msg, pass)
+						},
 					};
 					if let Ok(ref response) = result {
 						signer.request_confirmed(id, Ok(response.clone()));
@@ -103,6 +121,27 @@ impl<C: 'static, M: 'static> PersonalSigner for SignerClient<C, M> where C: Mini
 		)
 	}
 
+	fn simulate_request(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(U256, TransactionModification)>(params).and_then(
+			|(id, modification)| {
+				let id = id.into();
+				let signer = take_weak!(self.signer);
+				let client = take_weak!(self.client);
+				let miner = take_weak!(self.miner);
+
+				match signer.peek(&id).map(|confirmation| confirmation.payload) {
+					Some(ConfirmationPayload::Transaction(mut request)) => {
+						modification.apply(&mut request);
+						Ok(to_value(&simulate_transaction(&*client, &*miner, request.into())))
+					},
+					Some(ConfirmationPayload::Sign(..)) | Some(ConfirmationPayload::Decrypt(..)) => Err(errors::invalid_params("Only transactions can be simulated", id)),
+					None => Err(errors::invalid_params("Unknown RequestID", id)),
+				}
+			}
+		)
+	}
+
 	fn reject_request(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		from_params::<(U256, )>(params).and_then(
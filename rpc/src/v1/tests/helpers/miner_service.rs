@@ -19,16 +19,18 @@
 use util::{Address, H256, Bytes, U256, FixedHash, Uint};
 use util::standard::*;
 use ethcore::error::{Error, CallError};
-use ethcore::client::{MiningBlockChainClient, Executed, CallAnalytics};
+use ethcore::client::{MiningBlockChainClient, Executed, CallAnalytics, BlockID};
 use ethcore::block::{ClosedBlock, IsBlock};
 use ethcore::transaction::SignedTransaction;
 use ethcore::receipt::{Receipt, RichReceipt};
-use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult};
+use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, TransactionCondition};
 
 /// Test miner service.
 pub struct TestMinerService {
 	/// Imported transactions.
 	pub imported_transactions: Mutex<Vec<SignedTransaction>>,
+	/// Transactions held until their condition is met.
+	pub held_transactions: Mutex<Vec<(SignedTransaction, TransactionCondition)>>,
 	/// Latest closed block.
 	pub latest_closed_block: Mutex<Option<ClosedBlock>>,
 	/// Pre-existed pending transactions
@@ -50,6 +52,7 @@ impl Default for TestMinerService {
 	fn default() -> TestMinerService {
 		TestMinerService {
 			imported_transactions: Mutex::new(Vec::new()),
+			held_transactions: Mutex::new(Vec::new()),
 			latest_closed_block: Mutex::new(None),
 			pending_transactions: Mutex::new(HashMap::new()),
 			pending_receipts: Mutex::new(BTreeMap::new()),
@@ -161,6 +164,14 @@ impl MinerService for TestMinerService {
 		Ok(TransactionImportResult::Current)
 	}
 
+	/// Holds own transaction until the condition is met.
+	fn import_conditional_transaction(&self, _chain: &MiningBlockChainClient, transaction: SignedTransaction, condition: TransactionCondition) ->
+		Result<TransactionImportResult, Error> {
+
+		self.held_transactions.lock().push((transaction, condition));
+		Ok(TransactionImportResult::Future)
+	}
+
 	/// Returns hashes of transactions currently in pending
 	fn pending_transactions_hashes(&self) -> Vec<H256> {
 		vec![]
@@ -230,12 +241,12 @@ impl MinerService for TestMinerService {
 		unimplemented!();
 	}
 
-	fn balance(&self, _chain: &MiningBlockChainClient, address: &Address) -> U256 {
-		self.latest_closed_block.lock().as_ref().map_or_else(U256::zero, |b| b.block().fields().state.balance(address).clone())
+	fn balance(&self, chain: &MiningBlockChainClient, address: &Address) -> U256 {
+		self.latest_closed_block.lock().as_ref().map_or_else(|| chain.latest_balance(address), |b| b.block().fields().state.balance(address).clone())
 	}
 
-	fn call(&self, _chain: &MiningBlockChainClient, _t: &SignedTransaction, _analytics: CallAnalytics) -> Result<Executed, CallError> {
-		unimplemented!();
+	fn call(&self, chain: &MiningBlockChainClient, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, CallError> {
+		chain.call(t, BlockID::Latest, analytics)
 	}
 
 	fn storage_at(&self, _chain: &MiningBlockChainClient, address: &Address, position: &H256) -> H256 {
@@ -17,7 +17,8 @@
 //! Test implementation of SyncProvider.
 
 use util::{RwLock, U256};
-use ethsync::{SyncProvider, SyncStatus, SyncState};
+use ethcore::snapshot::RestorationStatus;
+use ethsync::{SyncProvider, SyncStatus, SyncState, SyncDetails};
 
 /// TestSyncProvider config.
 pub struct Config {
@@ -51,6 +52,9 @@ impl TestSyncProvider {
 				mem_used: 0,
 				num_snapshot_chunks: 0,
 				snapshot_chunks_done: 0,
+				ancient_block_number: None,
+				state_nodes_done: 0,
+				state_nodes_remaining: 0,
 			}),
 		}
 	}
@@ -60,5 +64,15 @@ impl SyncProvider for TestSyncProvider {
 	fn status(&self) -> SyncStatus {
 		self.status.read().clone()
 	}
+
+	fn details(&self) -> SyncDetails {
+		SyncDetails {
+			status: self.status(),
+			peers: Vec::new(),
+			blocks: Default::default(),
+			restoration: RestorationStatus::Inactive,
+			history: Vec::new(),
+		}
+	}
 }
 
@@ -138,6 +138,27 @@ fn rpc_eth_syncing() {
 	assert_eq!(tester.io.handle_request_sync(request), Some(false_res.to_owned()));
 }
 
+#[test]
+fn rpc_eth_syncing_ancient_blocks() {
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_syncing", "params": [], "id": 1}"#;
+
+	let tester = EthTester::default();
+	tester.sync.status.write().ancient_block_number = Some(1000);
+
+	// downloading ancient blocks alone is reported as syncing.
+	let ancient_res = r#"{"jsonrpc":"2.0","result":{"ancientBlock":"0x3e8","currentBlock":"0x0","highestBlock":"0x0","startingBlock":"0x0"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(ancient_res.to_owned()));
+
+	{
+		let mut status = tester.sync.status.write();
+		status.state = SyncState::Blocks;
+		status.highest_block_number = Some(2500);
+	}
+
+	let true_res = r#"{"jsonrpc":"2.0","result":{"ancientBlock":"0x3e8","currentBlock":"0x0","highestBlock":"0x9c4","startingBlock":"0x0"},"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(true_res.to_owned()));
+}
+
 #[test]
 fn rpc_eth_hashrate() {
 	let tester = EthTester::default();
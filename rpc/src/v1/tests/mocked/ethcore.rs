@@ -15,10 +15,14 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
+use rustc_serialize::hex::{ToHex, FromHex};
+use serde_json::{self, Value};
 use util::log::RotatingLogger;
 use util::U256;
 use ethsync::ManageNetwork;
 use ethcore::client::{TestBlockChainClient};
+use ethkey::{Random, Generator};
+use crypto::ecies;
 
 use jsonrpc_core::IoHandler;
 use v1::{Ethcore, EthcoreClient};
@@ -208,7 +212,52 @@ fn rpc_ethcore_net_peers() {
 	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_netPeers", "params":[], "id": 1}"#;
-	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50},"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50,"peers":[]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_net_traffic() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_netTraffic", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"bytesSent":1024,"bytesReceived":2048,"sessions":3,"packets":[]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_net_node_table() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_netNodeTable", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"url":"enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770","failures":1,"reputation":5,"lastSeen":1476000000,"lastSession":1475999000,"sessionTime":1000,"clientVersion":"Parity/v1.4.0","recentFailures":[1475998000]}],"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_sync_status() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_syncStatus", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"state":"Idle","startingBlock":"0x0","currentBlock":"0x0","highestBlock":null,"peers":[],"blocks":{"downloadingHeaders":0,"downloadingBodies":0,"queuedBlocks":0,"completeBlocks":0,"gaps":0},"restoration":{"status":"inactive","stateChunksDone":0,"blockChunksDone":0,"totalChunks":0,"downloadedChunks":0},"history":[]},"id":1}"#;
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
@@ -319,3 +368,34 @@ fn rpc_ethcore_pending_transactions() {
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_ethcore_encrypt_and_decrypt_document() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+	let first = Random.generate().unwrap();
+	let second = Random.generate().unwrap();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_encryptDocument", "params":[[""#.to_owned()
+		+ &format!("0x{:?}", first.public()) + r#"",""# + &format!("0x{:?}", second.public()) + r#""], "0x0102030405"], "id": 1}"#;
+	let response: Value = serde_json::from_str(&io.handle_request_sync(&request).unwrap()).unwrap();
+	let result = response.find("result").unwrap();
+	let document = result.find("document").unwrap().as_str().unwrap().to_owned();
+	let keys = result.find("keys").unwrap().as_array().unwrap();
+	assert_eq!(keys.len(), 2);
+	let sealed = keys[1].as_str().unwrap()[2..].from_hex().unwrap();
+	let key = ecies::decrypt(second.secret(), &[0; 0], &sealed).unwrap();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_decryptDocument", "params":["0x"#.to_owned()
+		+ &key.to_hex() + r#"", ""# + &document + r#""], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x0102030405","id":1}"#;
+	assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_decryptDocument", "params":["0x"#.to_owned()
+		+ &[0u8; 32].to_hex() + r#"", ""# + &document + r#""], "id": 1}"#;
+	assert!(io.handle_request_sync(&request).unwrap().contains("error"));
+}
@@ -20,6 +20,10 @@ use jsonrpc_core::IoHandler;
 use v1::{EthcoreSet, EthcoreSetClient};
 use ethcore::miner::MinerService;
 use ethcore::client::TestBlockChainClient;
+use ethcore::account_provider::AccountProvider;
+use ethstore::EthStore;
+use ethstore::dir::DiskDirectory;
+use devtools::RandomTempPath;
 use v1::tests::helpers::TestMinerService;
 use util::{U256, Address};
 use rustc_serialize::hex::FromHex;
@@ -38,8 +42,12 @@ fn network_service() -> Arc<TestManageNetwork> {
 	Arc::new(TestManageNetwork)
 }
 
-fn ethcore_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, net: &Arc<TestManageNetwork>) -> EthcoreSetClient<TestBlockChainClient, TestMinerService> {
-	EthcoreSetClient::new(client, miner, &(net.clone() as Arc<ManageNetwork>))
+fn accounts_provider() -> Arc<AccountProvider> {
+	Arc::new(AccountProvider::transient_provider())
+}
+
+fn ethcore_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, net: &Arc<TestManageNetwork>, accounts: &Arc<AccountProvider>) -> EthcoreSetClient<TestBlockChainClient, TestMinerService> {
+	EthcoreSetClient::new(client, miner, &(net.clone() as Arc<ManageNetwork>), accounts)
 }
 
 #[test]
@@ -47,8 +55,9 @@ fn rpc_ethcore_set_min_gas_price() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setMinGasPrice", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -62,8 +71,9 @@ fn rpc_ethcore_set_gas_floor_target() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setGasFloorTarget", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -77,8 +87,9 @@ fn rpc_ethcore_set_extra_data() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setExtraData", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -92,8 +103,9 @@ fn rpc_ethcore_set_author() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setAuthor", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -107,8 +119,9 @@ fn rpc_ethcore_set_transactions_limit() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setTransactionsLimit", "params":[10240240], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -116,3 +129,55 @@ fn rpc_ethcore_set_transactions_limit() {
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 	assert_eq!(miner.transactions_limit(), 10_240_240);
 }
+
+#[test]
+fn rpc_ethcore_set_denied_peers() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setDeniedPeers", "params":[["10.0.0.0/8", "client:^Geth/v1\\.4\\.1-"]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_manage_vaults() {
+	let temp = RandomTempPath::create_dir();
+	let dir = DiskDirectory::create(temp.as_str()).unwrap();
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = Arc::new(AccountProvider::new(Box::new(EthStore::open_with_iterations(Box::new(dir), 1024).unwrap())));
+	let address = accounts.new_account("").unwrap();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_newVault", "params": ["customer", "vault password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_changeVault", "params": ["0x{}", "customer"], "id": 1}}"#, address.hex());
+	assert_eq!(io.handle_request_sync(&request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_closeVault", "params": ["customer"], "id": 1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert!(accounts.accounts().unwrap().is_empty());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_listVaults", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":["customer"],"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_listOpenedVaults", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_openVault", "params": ["customer", "vault password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(accounts.accounts().unwrap(), vec![address]);
+}
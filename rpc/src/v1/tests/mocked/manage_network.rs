@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethsync::{ManageNetwork, NetworkConfiguration};
+use ethsync::{ManageNetwork, NetworkConfiguration, PeerTraffic, NetworkTraffic, NodeTableEntry};
 
 pub struct TestManageNetwork;
 
@@ -25,6 +25,24 @@ impl ManageNetwork for TestManageNetwork {
 	fn remove_reserved_peer(&self, _peer: String) -> Result<(), String> { Ok(()) }
 	fn add_reserved_peer(&self, _peer: String) -> Result<(), String> { Ok(()) }
 	fn start_network(&self) {}
-	fn stop_network(&self) {}
+	fn stop_network(&self) -> Result<(), String> { Ok(()) }
 	fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::new_local() }
+	fn peers_traffic(&self) -> Vec<PeerTraffic> { Vec::new() }
+	fn network_traffic(&self) -> NetworkTraffic {
+		NetworkTraffic { bytes_sent: 1024, bytes_received: 2048, sessions: 3, packets: Vec::new() }
+	}
+	fn set_allowed_peers(&self, _rules: Vec<String>) -> Result<(), String> { Ok(()) }
+	fn set_denied_peers(&self, _rules: Vec<String>) -> Result<(), String> { Ok(()) }
+	fn node_table(&self) -> Vec<NodeTableEntry> {
+		vec![NodeTableEntry {
+			url: "enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770".into(),
+			failures: 1,
+			reputation: 5,
+			last_seen: Some(1476000000),
+			last_session: Some(1475999000),
+			session_time: 1000,
+			client_version: Some("Parity/v1.4.0".into()),
+			recent_failures: vec![1475998000],
+		}]
+	}
 }
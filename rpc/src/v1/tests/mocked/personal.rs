@@ -19,6 +19,9 @@ use std::str::FromStr;
 use jsonrpc_core::IoHandler;
 use util::{U256, Uint, Address};
 use ethcore::account_provider::AccountProvider;
+use ethstore::EthStore;
+use ethstore::dir::DiskDirectory;
+use devtools::RandomTempPath;
 use v1::{PersonalClient, Personal};
 use v1::tests::helpers::TestMinerService;
 use ethcore::client::TestBlockChainClient;
@@ -47,7 +50,10 @@ fn miner_service() -> Arc<TestMinerService> {
 }
 
 fn setup(signer: Option<u16>) -> PersonalTester {
-	let accounts = accounts_provider();
+	setup_with_accounts(signer, accounts_provider())
+}
+
+fn setup_with_accounts(signer: Option<u16>, accounts: Arc<AccountProvider>) -> PersonalTester {
 	let client = blockchain_client();
 	let miner = miner_service();
 	let personal = PersonalClient::new(&accounts, &client, &miner, signer, false);
@@ -252,3 +258,36 @@ fn sign_and_send_transaction() {
 
 	assert_eq!(tester.io.handle_request_sync(request.as_ref()), Some(response));
 }
+
+#[test]
+fn should_manage_vaults() {
+	let temp = RandomTempPath::create_dir();
+	let dir = DiskDirectory::create(temp.as_str()).unwrap();
+	let accounts = Arc::new(AccountProvider::new(Box::new(EthStore::open_with_iterations(Box::new(dir), 1024).unwrap())));
+	let tester = setup_with_accounts(None, accounts);
+	let address = tester.accounts.new_account("").unwrap();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_newVault", "params": ["customer", "vault password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.into()));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "personal_changeVault", "params": ["0x{}", "customer"], "id": 1}}"#, address.hex());
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.into()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_closeVault", "params": ["customer"], "id": 1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.into()));
+	assert!(tester.accounts.accounts().unwrap().is_empty());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_listVaults", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":["customer"],"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.into()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_listOpenedVaults", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.into()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_openVault", "params": ["customer", "vault password"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.io.handle_request_sync(request), Some(response.into()));
+	assert_eq!(tester.accounts.accounts().unwrap(), vec![address]);
+}
@@ -16,11 +16,16 @@
 
 use std::sync::Arc;
 use std::str::FromStr;
+use rustc_serialize::hex::ToHex;
 use jsonrpc_core::IoHandler;
 use util::{U256, Uint, Address};
-use ethcore::account_provider::AccountProvider;
-use ethcore::client::TestBlockChainClient;
+use ethcore::account_provider::{AccountProvider, SignerBackend, DeviceState, Signature};
+use ethcore::client::{TestBlockChainClient, Executed};
+use ethcore::executed::CallType;
+use ethcore::miner::TransactionCondition;
+use ethcore::trace::{trace, FlatTrace, TraceError};
 use ethcore::transaction::{Transaction, Action};
+use crypto::ecies;
 use v1::{SignerClient, PersonalSigner};
 use v1::tests::helpers::TestMinerService;
 use v1::helpers::{SigningQueue, SignerService, FilledTransactionRequest, ConfirmationPayload};
@@ -30,16 +35,30 @@ struct PersonalSignerTester {
 	accounts: Arc<AccountProvider>,
 	io: IoHandler,
 	miner: Arc<TestMinerService>,
-	// these unused fields are necessary to keep the data alive
-	// as the handler has only weak pointers.
-	_client: Arc<TestBlockChainClient>,
+	client: Arc<TestBlockChainClient>,
 }
 
 fn blockchain_client() -> Arc<TestBlockChainClient> {
 	let client = TestBlockChainClient::new();
+	client.set_execution_result(Ok(execution(vec![])));
 	Arc::new(client)
 }
 
+fn execution(trace: Vec<FlatTrace>) -> Executed {
+	Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(21_000),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![],
+		trace: trace,
+		vm_trace: None,
+		state_diff: None,
+	}
+}
+
 fn accounts_provider() -> Arc<AccountProvider> {
 	Arc::new(AccountProvider::transient_provider())
 }
@@ -62,7 +81,7 @@ fn signer_tester() -> PersonalSignerTester {
 		accounts: accounts,
 		io: io,
 		miner: miner,
-		_client: client,
+		client: client,
 	}
 }
 
@@ -95,6 +114,60 @@ fn should_return_list_of_items_to_confirm() {
 	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
 }
 
+struct AwaitingDevice {
+	account: Address,
+}
+
+impl SignerBackend for AwaitingDevice {
+	fn accounts(&self) -> Vec<Address> {
+		vec![self.account]
+	}
+
+	fn sign_transaction(&self, _account: &Address, _transaction: &[u8]) -> Result<Signature, String> {
+		Err("Device disconnected".into())
+	}
+
+	fn state(&self, _account: &Address) -> DeviceState {
+		DeviceState::AwaitingConfirmation
+	}
+}
+
+#[test]
+fn should_return_device_state_of_hardware_wallet_requests() {
+	// given
+	let tester = signer_tester();
+	tester.accounts.add_signer_backend(Arc::new(AwaitingDevice { account: Address::from(1) }));
+	tester.signer.add_request(ConfirmationPayload::Sign(1.into(), 5.into())).unwrap();
+	tester.signer.add_request(ConfirmationPayload::Sign(2.into(), 5.into())).unwrap();
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_requestsToConfirm","params":[],"id":1}"#;
+	let response = concat!(
+		r#"{"jsonrpc":"2.0","result":["#,
+		r#"{"device":"awaitingConfirmation","id":"0x1","payload":{"sign":{"address":"0x0000000000000000000000000000000000000001","hash":"0x0000000000000000000000000000000000000000000000000000000000000005"}}},"#,
+		r#"{"id":"0x2","payload":{"sign":{"address":"0x0000000000000000000000000000000000000002","hash":"0x0000000000000000000000000000000000000000000000000000000000000005"}}}"#,
+		r#"],"id":1}"#
+	);
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+}
+
+#[test]
+fn should_not_sign_data_with_hardware_wallet_account() {
+	// given
+	let tester = signer_tester();
+	tester.accounts.add_signer_backend(Arc::new(AwaitingDevice { account: Address::from(1) }));
+	tester.signer.add_request(ConfirmationPayload::Sign(1.into(), 5.into())).unwrap();
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{},""],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32024,"message":"The account is held by a hardware wallet, which can only sign transactions.","data":null},"id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 1);
+}
 
 #[test]
 fn should_reject_transaction_from_queue_without_dispatching() {
@@ -134,6 +207,7 @@ fn should_not_remove_transaction_if_password_is_invalid() {
 		data: vec![],
 		nonce: None,
 	})).unwrap();
+	tester.client.set_balance(Address::from(1), U256::from(1_000_000_000_000_000u64));
 	assert_eq!(tester.signer.requests().len(), 1);
 
 	// when
@@ -161,6 +235,26 @@ fn should_not_remove_sign_if_password_is_invalid() {
 	assert_eq!(tester.signer.requests().len(), 1);
 }
 
+#[test]
+fn should_confirm_decrypt_request() {
+	// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	tester.accounts.unlock_account_temporarily(address, "test".into()).unwrap();
+	let public = tester.accounts.account_public(address).unwrap();
+	let encrypted = ecies::encrypt(&public, &[0; 0], b"Hello World!").unwrap();
+	tester.signer.add_request(ConfirmationPayload::Decrypt(address, encrypted)).unwrap();
+	assert_eq!(tester.signer.requests().len(), 1);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{},"test"],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x"#.to_owned() + &b"Hello World!".to_hex() + r#"","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+	assert_eq!(tester.signer.requests().len(), 0);
+}
+
 #[test]
 fn should_confirm_transaction_and_dispatch() {
 	//// given
@@ -188,6 +282,7 @@ fn should_confirm_transaction_and_dispatch() {
 	tester.accounts.unlock_account_temporarily(address, "test".into()).unwrap();
 	let signature = tester.accounts.sign(address, t.hash()).unwrap();
 	let t = t.with_signature(signature);
+	tester.client.set_balance(address, U256::from(1_000_000_000_000_000u64));
 
 	assert_eq!(tester.signer.requests().len(), 1);
 
@@ -206,6 +301,151 @@ fn should_confirm_transaction_and_dispatch() {
 	assert_eq!(tester.miner.imported_transactions.lock().len(), 1);
 }
 
+#[test]
+fn should_confirm_transaction_with_modified_gas_and_nonce() {
+	// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	let recipient = Address::from_str("d46e8dd67c5d32be8058bb8eb970870f07244567").unwrap();
+	tester.signer.add_request(ConfirmationPayload::Transaction(FilledTransactionRequest {
+		from: address,
+		to: Some(recipient),
+		gas_price: U256::from(10_000),
+		gas: U256::from(10_000_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+	})).unwrap();
+	tester.client.set_balance(address, U256::from(1_000_000_000_000_000u64));
+
+	let t = Transaction {
+		nonce: U256::from(5),
+		gas_price: U256::from(10_000),
+		gas: U256::from(30_000),
+		action: Action::Call(recipient),
+		value: U256::from(0x1),
+		data: vec![]
+	};
+	tester.accounts.unlock_account_temporarily(address, "test".into()).unwrap();
+	let signature = tester.accounts.sign(address, t.hash()).unwrap();
+	let t = t.with_signature(signature);
+
+	// when
+	let request = r#"{
+		"jsonrpc":"2.0",
+		"method":"personal_confirmRequest",
+		"params":["0x1", {"gas":"0x7530","nonce":"0x5"}, "test"],
+		"id":1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{:?}", t.hash()).as_ref() + r#"","id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 0);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 1);
+}
+
+#[test]
+fn should_simulate_modified_transaction() {
+	// given
+	let tester = signer_tester();
+	tester.signer.add_request(ConfirmationPayload::Transaction(FilledTransactionRequest {
+		from: Address::from(1),
+		to: Some(Address::from(2)),
+		gas_price: U256::from(10_000),
+		gas: U256::from(10_000_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+	})).unwrap();
+	tester.client.set_balance(Address::from(1), U256::from(1_000_000_000_000_000u64));
+
+	// when
+	let request1 = r#"{"jsonrpc":"2.0","method":"personal_simulateRequest","params":["0x1",{"gas":"0x5208"}],"id":1}"#;
+	let request2 = r#"{"jsonrpc":"2.0","method":"personal_simulateRequest","params":["0x1",{"gas":"0x5207"}],"id":1}"#;
+	let response1 = r#"{"jsonrpc":"2.0","result":{"error":null,"gasEstimate":"0x5208","gasUsed":"0x5208","logs":[],"output":"0x","stateDiff":null,"success":true},"id":1}"#;
+	let response2 = r#"{"jsonrpc":"2.0","result":{"error":"Gas limit is too low: the transaction requires 21000 gas.","gasEstimate":"0x5208","gasUsed":"0x5208","logs":[],"output":"0x","stateDiff":null,"success":false},"id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request1), Some(response1.to_owned()));
+	assert_eq!(tester.io.handle_request_sync(&request2), Some(response2.to_owned()));
+}
+
+#[test]
+fn should_not_sign_transaction_which_would_fail() {
+	// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	tester.signer.add_request(ConfirmationPayload::Transaction(FilledTransactionRequest {
+		from: address,
+		to: Some(Address::from(2)),
+		gas_price: U256::from(10_000),
+		gas: U256::from(100_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+	})).unwrap();
+	tester.client.set_balance(address, U256::from(1_000_000_000_000_000u64));
+	tester.client.set_execution_result(Ok(execution(vec![FlatTrace {
+		action: trace::Action::Call(trace::Call {
+			from: address,
+			to: Address::from(2),
+			value: U256::from(1),
+			gas: U256::from(100_000),
+			input: vec![],
+			call_type: CallType::Call,
+		}),
+		result: trace::Res::FailedCall(TraceError::OutOfGas),
+		subtraces: 0,
+		trace_address: Default::default(),
+	}])));
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{},"test"],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32011,"message":"Transaction was not signed. Transaction would fail: Out of gas Set `ignoreSimulation` to sign it anyway.","data":{"error":"Transaction would fail: Out of gas","gasEstimate":"0x5208","gasUsed":"0x5208","logs":[],"output":"0x","stateDiff":null,"success":false}},"id":1}"#;
+
+	// then
+	assert_eq!(tester.io.handle_request_sync(&request), Some(response.to_owned()));
+	assert_eq!(tester.signer.requests().len(), 1);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{"ignoreSimulation":true},"test"],"id":1}"#;
+
+	// then
+	assert!(tester.io.handle_request_sync(&request).unwrap().contains("result"));
+	assert_eq!(tester.signer.requests().len(), 0);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 1);
+}
+
+#[test]
+fn should_hold_transaction_with_condition() {
+	// given
+	let tester = signer_tester();
+	let address = tester.accounts.new_account("test").unwrap();
+	tester.signer.add_request(ConfirmationPayload::Transaction(FilledTransactionRequest {
+		from: address,
+		to: Some(Address::from(2)),
+		gas_price: U256::from(10_000),
+		gas: U256::from(100_000),
+		value: U256::from(1),
+		data: vec![],
+		nonce: None,
+	})).unwrap();
+	tester.client.set_balance(address, U256::from(1_000_000_000_000_000u64));
+
+	// when
+	let request = r#"{"jsonrpc":"2.0","method":"personal_confirmRequest","params":["0x1",{"condition":{"block":100}},"test"],"id":1}"#;
+
+	// then
+	assert!(tester.io.handle_request_sync(&request).unwrap().contains("result"));
+	assert_eq!(tester.signer.requests().len(), 0);
+	assert_eq!(tester.miner.imported_transactions.lock().len(), 0);
+	let held = tester.miner.held_transactions.lock();
+	assert_eq!(held.len(), 1);
+	assert_eq!(held[0].1, TransactionCondition::Number(100));
+}
+
 #[test]
 fn should_generate_new_token() {
 	// given
@@ -228,7 +228,11 @@ pub trait EthSigning: Sized + Send + Sync + 'static {
 
 	/// Decrypt some ECIES-encrypted message.
 	/// First parameter is the address with which it is encrypted, second is the ciphertext.
-	fn decrypt_message(&self, _: Params) -> Result<Value, Error>;
+	/// Requires confirmation in Trusted Signer unless the account is unlocked.
+	fn decrypt_message(&self, _: Params, _: Ready);
+
+	/// Returns the 64-byte public key of an unlocked account, which others may use to encrypt messages to it.
+	fn account_public_key(&self, _: Params) -> Result<Value, Error>;
 
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
@@ -238,7 +242,8 @@ pub trait EthSigning: Sized + Send + Sync + 'static {
 		delegate.add_method("eth_postSign", EthSigning::post_sign);
 		delegate.add_method("eth_postTransaction", EthSigning::post_transaction);
 		delegate.add_method("eth_checkRequest", EthSigning::check_request);
-		delegate.add_method("ethcore_decryptMessage", EthSigning::decrypt_message);
+		delegate.add_async_method("ethcore_decryptMessage", EthSigning::decrypt_message);
+		delegate.add_method("ethcore_accountPublicKey", EthSigning::account_public_key);
 		delegate
 	}
 }
@@ -48,6 +48,15 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 	/// Returns peers details
 	fn net_peers(&self, _: Params) -> Result<Value, Error>;
 
+	/// Returns total network traffic by packet type
+	fn net_traffic(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns node table entries with connection history
+	fn net_node_table(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns detailed sync status with per-peer state and recent state changes
+	fn sync_status(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns network port
 	fn net_port(&self, _: Params) -> Result<Value, Error>;
 
@@ -80,6 +89,15 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 	/// First parameter is the 512-byte destination public key, second is the message.
 	fn encrypt_message(&self, _: Params) -> Result<Value, Error>;
 
+	/// Encrypt a document with a random symmetric key sealed to each of the recipients.
+	/// First parameter is the list of 64-byte recipient public keys, second is the document.
+	/// Recipients decrypt their key with `ethcore_decryptMessage` and then the document with `ethcore_decryptDocument`.
+	fn encrypt_document(&self, _: Params) -> Result<Value, Error>;
+
+	/// Decrypt a document encrypted with `ethcore_encryptDocument`.
+	/// First parameter is the decrypted 32-byte document key, second is the encrypted document.
+	fn decrypt_document(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns all pending (current) transactions from transaction queue.
 	fn pending_transactions(&self, _: Params) -> Result<Value, Error>;
 
@@ -99,6 +117,9 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_devLogsLevels", Ethcore::dev_logs_levels);
 		delegate.add_method("ethcore_netChain", Ethcore::net_chain);
 		delegate.add_method("ethcore_netPeers", Ethcore::net_peers);
+		delegate.add_method("ethcore_netTraffic", Ethcore::net_traffic);
+		delegate.add_method("ethcore_netNodeTable", Ethcore::net_node_table);
+		delegate.add_method("ethcore_syncStatus", Ethcore::sync_status);
 		delegate.add_method("ethcore_netPort", Ethcore::net_port);
 		delegate.add_method("ethcore_rpcSettings", Ethcore::rpc_settings);
 		delegate.add_method("ethcore_nodeName", Ethcore::node_name);
@@ -109,6 +130,8 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_phraseToAddress", Ethcore::phrase_to_address);
 		delegate.add_method("ethcore_registryAddress", Ethcore::registry_address);
 		delegate.add_method("ethcore_encryptMessage", Ethcore::encrypt_message);
+		delegate.add_method("ethcore_encryptDocument", Ethcore::encrypt_document);
+		delegate.add_method("ethcore_decryptDocument", Ethcore::decrypt_document);
 		delegate.add_method("ethcore_pendingTransactions", Ethcore::pending_transactions);
 		delegate.add_async_method("ethcore_hashContent", Ethcore::hash_content);
 
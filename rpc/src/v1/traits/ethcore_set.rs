@@ -55,12 +55,36 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 	/// Accept non-reserved peers (default behavior)
 	fn accept_non_reserved_peers(&self, _: Params) -> Result<Value, Error>;
 
+	/// Replace the peer allow list.
+	fn set_allowed_peers(&self, _: Params) -> Result<Value, Error>;
+
+	/// Replace the peer deny list.
+	fn set_denied_peers(&self, _: Params) -> Result<Value, Error>;
+
 	/// Start the network.
 	fn start_network(&self, _: Params) -> Result<Value, Error>;
 
-	/// Stop the network.
+	/// Stop the network. Fails if the network is shared with other chains.
 	fn stop_network(&self, _: Params) -> Result<Value, Error>;
 
+	/// Creates new vault with given name and password and opens it.
+	fn new_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Opens the vault with given name and password, making its accounts available.
+	fn open_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Closes the vault with given name. Its accounts are locked and hidden.
+	fn close_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns names of all vaults.
+	fn list_vaults(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns names of opened vaults.
+	fn list_opened_vaults(&self, _: Params) -> Result<Value, Error>;
+
+	/// Moves the account to an opened vault. Empty vault name moves it out of vaults.
+	fn change_vault(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -75,6 +99,14 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_removeReservedPeer", EthcoreSet::remove_reserved_peer);
 		delegate.add_method("ethcore_dropNonReservedPeers", EthcoreSet::drop_non_reserved_peers);
 		delegate.add_method("ethcore_acceptNonReservedPeers", EthcoreSet::accept_non_reserved_peers);
+		delegate.add_method("ethcore_setAllowedPeers", EthcoreSet::set_allowed_peers);
+		delegate.add_method("ethcore_setDeniedPeers", EthcoreSet::set_denied_peers);
+		delegate.add_method("ethcore_newVault", EthcoreSet::new_vault);
+		delegate.add_method("ethcore_openVault", EthcoreSet::open_vault);
+		delegate.add_method("ethcore_closeVault", EthcoreSet::close_vault);
+		delegate.add_method("ethcore_listVaults", EthcoreSet::list_vaults);
+		delegate.add_method("ethcore_listOpenedVaults", EthcoreSet::list_opened_vaults);
+		delegate.add_method("ethcore_changeVault", EthcoreSet::change_vault);
 
 		delegate
 	}
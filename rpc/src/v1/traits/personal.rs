@@ -60,6 +60,24 @@ pub trait Personal: Sized + Send + Sync + 'static {
 	/// Imports a number of Geth accounts, with the list provided as the argument.
 	fn import_geth_accounts(&self, _: Params) -> Result<Value, Error>;
 
+	/// Creates new vault with given name and password and opens it.
+	fn new_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Opens the vault with given name and password, making its accounts available.
+	fn open_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Closes the vault with given name. Its accounts are locked and hidden.
+	fn close_vault(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns names of all vaults.
+	fn list_vaults(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns names of opened vaults.
+	fn list_opened_vaults(&self, _: Params) -> Result<Value, Error>;
+
+	/// Moves the account to an opened vault. Empty vault name moves it out of vaults.
+	fn change_vault(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -75,6 +93,12 @@ pub trait Personal: Sized + Send + Sync + 'static {
 		delegate.add_method("personal_accountsInfo", Personal::accounts_info);
 		delegate.add_method("personal_listGethAccounts", Personal::geth_accounts);
 		delegate.add_method("personal_importGethAccounts", Personal::import_geth_accounts);
+		delegate.add_method("personal_newVault", Personal::new_vault);
+		delegate.add_method("personal_openVault", Personal::open_vault);
+		delegate.add_method("personal_closeVault", Personal::close_vault);
+		delegate.add_method("personal_listVaults", Personal::list_vaults);
+		delegate.add_method("personal_listOpenedVaults", Personal::list_opened_vaults);
+		delegate.add_method("personal_changeVault", Personal::change_vault);
 
 		delegate
 	}
@@ -87,8 +111,12 @@ pub trait PersonalSigner: Sized + Send + Sync + 'static {
 	fn requests_to_confirm(&self, _: Params) -> Result<Value, Error>;
 
 	/// Confirm specific request.
+	/// Transactions failing simulation are only signed if `ignoreSimulation` is set in the modification.
 	fn confirm_request(&self, _: Params) -> Result<Value, Error>;
 
+	/// Simulates execution of a transaction request with given modifications applied, without signing it.
+	fn simulate_request(&self, _: Params) -> Result<Value, Error>;
+
 	/// Reject the confirmation request.
 	fn reject_request(&self, _: Params) -> Result<Value, Error>;
 
@@ -100,6 +128,7 @@ pub trait PersonalSigner: Sized + Send + Sync + 'static {
 		let mut delegate = IoDelegate::new(Arc::new(self));
 		delegate.add_method("personal_requestsToConfirm", PersonalSigner::requests_to_confirm);
 		delegate.add_method("personal_confirmRequest", PersonalSigner::confirm_request);
+		delegate.add_method("personal_simulateRequest", PersonalSigner::simulate_request);
 		delegate.add_method("personal_rejectRequest", PersonalSigner::reject_request);
 		delegate.add_method("personal_generateAuthorizationToken", PersonalSigner::generate_token);
 		delegate
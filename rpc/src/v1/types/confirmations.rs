@@ -16,17 +16,34 @@
 
 //! Types used in Confirmations queue (Trusted Signer)
 
-use v1::types::{U256, TransactionRequest, H160, H256};
+use ethcore::account_provider::DeviceState;
+use ethcore::miner;
+use v1::types::{U256, TransactionRequest, H160, H256, Bytes, Log, StateDiff};
 use v1::helpers;
 
 
 /// Confirmation waiting in a queue
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ConfirmationRequest {
 	/// Id of this confirmation
 	pub id: U256,
 	/// Payload
 	pub payload: ConfirmationPayload,
+	/// State of the hardware wallet holding the account (if any)
+	#[serde(skip_serializing_if="Option::is_none")]
+	pub device: Option<String>,
+}
+
+impl ConfirmationRequest {
+	/// Attaches state of the external device that has to confirm this request.
+	pub fn with_device(mut self, state: Option<DeviceState>) -> Self {
+		self.device = state.map(|state| match state {
+			DeviceState::Ready => "ready",
+			DeviceState::AwaitingConfirmation => "awaitingConfirmation",
+			DeviceState::Unavailable => "unavailable",
+		}.to_owned());
+		self
+	}
 }
 
 impl From<helpers::ConfirmationRequest> for ConfirmationRequest {
@@ -34,12 +51,22 @@ impl From<helpers::ConfirmationRequest> for ConfirmationRequest {
 		ConfirmationRequest {
 			id: c.id.into(),
 			payload: c.payload.into(),
+			device: None,
+		}
+	}
+}
+
+impl Into<helpers::ConfirmationRequest> for ConfirmationRequest {
+	fn into(self) -> helpers::ConfirmationRequest {
+		helpers::ConfirmationRequest {
+			id: self.id.into(),
+			payload: self.payload.into(),
 		}
 	}
 }
 
 /// Sign request
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct SignRequest {
 	/// Address
 	pub address: H160,
@@ -47,8 +74,17 @@ pub struct SignRequest {
 	pub hash: H256,
 }
 
+/// Decrypt request
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DecryptRequest {
+	/// Address
+	pub address: H160,
+	/// Message to decrypt
+	pub msg: Bytes,
+}
+
 /// Confirmation payload, i.e. the thing to be confirmed
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum ConfirmationPayload {
 	/// Transaction
 	#[serde(rename="transaction")]
@@ -56,6 +92,9 @@ pub enum ConfirmationPayload {
 	/// Signature
 	#[serde(rename="sign")]
 	Sign(SignRequest),
+	/// Decryption
+	#[serde(rename="decrypt")]
+	Decrypt(DecryptRequest),
 }
 
 impl From<helpers::ConfirmationPayload> for ConfirmationPayload {
@@ -66,6 +105,28 @@ impl From<helpers::ConfirmationPayload> for ConfirmationPayload {
 				address: address.into(),
 				hash: hash.into(),
 			}),
+			helpers::ConfirmationPayload::Decrypt(address, msg) => ConfirmationPayload::Decrypt(DecryptRequest {
+				address: address.into(),
+				msg: msg.into(),
+			}),
+		}
+	}
+}
+
+impl Into<helpers::ConfirmationPayload> for ConfirmationPayload {
+	fn into(self) -> helpers::ConfirmationPayload {
+		match self {
+			ConfirmationPayload::Transaction(t) => helpers::ConfirmationPayload::Transaction(helpers::FilledTransactionRequest {
+				from: t.from.into(),
+				to: t.to.map(Into::into),
+				gas_price: t.gas_price.map(Into::into).unwrap_or_default(),
+				gas: t.gas.map(Into::into).unwrap_or_default(),
+				value: t.value.map(Into::into).unwrap_or_default(),
+				data: t.data.map(Into::into).unwrap_or_default(),
+				nonce: t.nonce.map(Into::into),
+			}),
+			ConfirmationPayload::Sign(SignRequest { address, hash }) => helpers::ConfirmationPayload::Sign(address.into(), hash.into()),
+			ConfirmationPayload::Decrypt(DecryptRequest { address, msg }) => helpers::ConfirmationPayload::Decrypt(address.into(), msg.into()),
 		}
 	}
 }
@@ -76,6 +137,72 @@ pub struct TransactionModification {
 	/// Modified gas price
 	#[serde(rename="gasPrice")]
 	pub gas_price: Option<U256>,
+	/// Modified gas limit
+	pub gas: Option<U256>,
+	/// Modified nonce
+	pub nonce: Option<U256>,
+	/// Condition the signed transaction waits for before it's sent.
+	pub condition: Option<TransactionCondition>,
+	/// Sign the transaction even if its simulation fails.
+	#[serde(rename="ignoreSimulation")]
+	pub ignore_simulation: Option<bool>,
+}
+
+impl TransactionModification {
+	/// Applies the modification to given transaction request.
+	pub fn apply(&self, request: &mut helpers::FilledTransactionRequest) {
+		if let Some(gas_price) = self.gas_price.clone() {
+			request.gas_price = gas_price.into();
+		}
+		if let Some(gas) = self.gas.clone() {
+			request.gas = gas.into();
+		}
+		if let Some(nonce) = self.nonce.clone() {
+			request.nonce = Some(nonce.into());
+		}
+	}
+}
+
+/// Condition the signed transaction waits for before it's imported to the transaction queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TransactionCondition {
+	/// Best block has at least given number
+	#[serde(rename="block")]
+	Number(u64),
+	/// Best block has at least given UNIX timestamp
+	#[serde(rename="time")]
+	Timestamp(u64),
+}
+
+impl Into<miner::TransactionCondition> for TransactionCondition {
+	fn into(self) -> miner::TransactionCondition {
+		match self {
+			TransactionCondition::Number(n) => miner::TransactionCondition::Number(n),
+			TransactionCondition::Timestamp(t) => miner::TransactionCondition::Timestamp(t),
+		}
+	}
+}
+
+/// Simulated outcome of a transaction awaiting confirmation, executed against pending state.
+#[derive(Debug, Default, Serialize)]
+pub struct TransactionSimulation {
+	/// Whether the transaction is expected to succeed
+	pub success: bool,
+	/// Reason of the expected failure
+	pub error: Option<String>,
+	/// Gas required by the transaction
+	#[serde(rename="gasEstimate")]
+	pub gas_estimate: Option<U256>,
+	/// Gas used by the transaction with requested gas limit
+	#[serde(rename="gasUsed")]
+	pub gas_used: Option<U256>,
+	/// Output of the execution
+	pub output: Option<Bytes>,
+	/// Logs produced by the execution
+	pub logs: Vec<Log>,
+	/// Changes to the state made by the execution
+	#[serde(rename="stateDiff")]
+	pub state_diff: Option<StateDiff>,
 }
 
 #[cfg(test)]
@@ -84,6 +211,7 @@ mod tests {
 	use serde_json;
 	use v1::types::U256;
 	use v1::helpers;
+	use ethcore::account_provider::DeviceState;
 	use super::*;
 
 	#[test]
@@ -102,6 +230,38 @@ mod tests {
 		assert_eq!(res.unwrap(), expected.to_owned());
 	}
 
+	#[test]
+	fn should_serialize_decrypt_confirmation() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::Decrypt(1.into(), vec![1, 2, 3]),
+		};
+
+		// when
+		let res = serde_json::to_string(&ConfirmationRequest::from(request));
+		let expected = r#"{"id":"0xf","payload":{"decrypt":{"address":"0x0000000000000000000000000000000000000001","msg":"0x010203"}}}"#;
+
+		// then
+		assert_eq!(res.unwrap(), expected.to_owned());
+	}
+
+	#[test]
+	fn should_serialize_device_state() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::Sign(1.into(), 5.into()),
+		};
+
+		// when
+		let res = serde_json::to_string(&ConfirmationRequest::from(request).with_device(Some(DeviceState::AwaitingConfirmation)));
+		let expected = r#"{"id":"0xf","payload":{"sign":{"address":"0x0000000000000000000000000000000000000001","hash":"0x0000000000000000000000000000000000000000000000000000000000000005"}},"device":"awaitingConfirmation"}"#;
+
+		// then
+		assert_eq!(res.unwrap(), expected.to_owned());
+	}
+
 	#[test]
 	fn should_serialize_transaction_confirmation() {
 		// given
@@ -126,6 +286,31 @@ mod tests {
 		assert_eq!(res.unwrap(), expected.to_owned());
 	}
 
+	#[test]
+	fn should_deserialize_confirmation() {
+		// given
+		let request = helpers::ConfirmationRequest {
+			id: 15.into(),
+			payload: helpers::ConfirmationPayload::Transaction(helpers::FilledTransactionRequest {
+				from: 0.into(),
+				to: Some(1.into()),
+				gas: 15_000.into(),
+				gas_price: 10_000.into(),
+				value: 100_000.into(),
+				data: vec![1, 2, 3],
+				nonce: None,
+			}),
+		};
+		let serialized = serde_json::to_string(&ConfirmationRequest::from(request.clone())).unwrap();
+
+		// when
+		let res: ConfirmationRequest = serde_json::from_str(&serialized).unwrap();
+
+		// then
+		let res: helpers::ConfirmationRequest = res.into();
+		assert_eq!(res, request);
+	}
+
 	#[test]
 	fn should_deserialize_modification() {
 		// given
@@ -133,18 +318,32 @@ mod tests {
 			"gasPrice":"0xba43b7400"
 		}"#;
 		let s2 = r#"{}"#;
+		let s3 = r#"{"condition":{"block":100}}"#;
+		let s4 = r#"{"condition":{"time":1480000000}}"#;
 
 		// when
 		let res1: TransactionModification = serde_json::from_str(s1).unwrap();
 		let res2: TransactionModification = serde_json::from_str(s2).unwrap();
+		let res3: TransactionModification = serde_json::from_str(s3).unwrap();
+		let res4: TransactionModification = serde_json::from_str(s4).unwrap();
 
 		// then
 		assert_eq!(res1, TransactionModification {
 			gas_price: Some(U256::from_str("0ba43b7400").unwrap()),
+			gas: None,
+			nonce: None,
+			condition: None,
+			ignore_simulation: None,
 		});
 		assert_eq!(res2, TransactionModification {
 			gas_price: None,
+			gas: None,
+			nonce: None,
+			condition: None,
+			ignore_simulation: None,
 		});
+		assert_eq!(res3.condition, Some(TransactionCondition::Number(100)));
+		assert_eq!(res4.condition, Some(TransactionCondition::Timestamp(1_480_000_000)));
 	}
 }
 
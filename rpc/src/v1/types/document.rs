@@ -0,0 +1,38 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Document encrypted for multiple recipients.
+
+use crypto::document;
+use v1::types::Bytes;
+
+/// Document encrypted with a random document key, sealed to each of the recipients.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct EncryptedDocument {
+	/// Encrypted document
+	pub document: Bytes,
+	/// Document key encrypted to each of the recipients, in the order of recipients
+	pub keys: Vec<Bytes>,
+}
+
+impl From<document::EncryptedDocument> for EncryptedDocument {
+	fn from(d: document::EncryptedDocument) -> Self {
+		EncryptedDocument {
+			document: d.document.into(),
+			keys: d.keys.into_iter().map(Into::into).collect(),
+		}
+	}
+}
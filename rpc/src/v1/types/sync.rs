@@ -15,7 +15,11 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use serde::{Serialize, Serializer};
-use v1::types::U256;
+use ethsync::{PeerTraffic as EthPeerTraffic, PacketTraffic as EthPacketTraffic, NetworkTraffic as EthNetworkTraffic,
+	NodeTableEntry as EthNodeTableEntry, SyncDetails as EthSyncDetails, PeerSyncInfo as EthPeerSyncInfo,
+	BlockRange as EthBlockRange, BlockCollectionStats, SyncTransition as EthSyncTransition};
+use ethcore::snapshot::RestorationStatus;
+use v1::types::{U256, H256};
 
 /// Sync info
 #[derive(Default, Debug, Serialize, PartialEq)]
@@ -29,6 +33,9 @@ pub struct SyncInfo {
 	/// Highest block seen so far
 	#[serde(rename="highestBlock")]
 	pub highest_block: U256,
+	/// Oldest block in the chain while ancient blocks are being downloaded
+	#[serde(rename="ancientBlock", skip_serializing_if="Option::is_none")]
+	pub ancient_block: Option<U256>,
 }
 
 /// Peers info
@@ -40,6 +47,334 @@ pub struct Peers {
 	pub connected: usize,
 	/// Max number of peers
 	pub max: u32,
+	/// Traffic of connected peers
+	pub peers: Vec<PeerTraffic>,
+}
+
+/// Traffic counters for a single packet type
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PacketTraffic {
+	/// Subprotocol name
+	pub protocol: String,
+	/// Packet id within the subprotocol
+	#[serde(rename="packetId")]
+	pub packet_id: u8,
+	/// Number of packets sent
+	#[serde(rename="sentMessages")]
+	pub sent_messages: u64,
+	/// Payload bytes sent
+	#[serde(rename="sentBytes")]
+	pub sent_bytes: u64,
+	/// Number of packets received
+	#[serde(rename="receivedMessages")]
+	pub received_messages: u64,
+	/// Payload bytes received
+	#[serde(rename="receivedBytes")]
+	pub received_bytes: u64,
+}
+
+impl From<EthPacketTraffic> for PacketTraffic {
+	fn from(p: EthPacketTraffic) -> Self {
+		PacketTraffic {
+			protocol: p.protocol,
+			packet_id: p.packet_id,
+			sent_messages: p.sent_messages,
+			sent_bytes: p.sent_bytes,
+			received_messages: p.received_messages,
+			received_bytes: p.received_bytes,
+		}
+	}
+}
+
+/// Traffic of a connected peer
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerTraffic {
+	/// Peer public key
+	pub id: Option<String>,
+	/// Peer client ID
+	pub name: String,
+	/// Remote address
+	#[serde(rename="remoteAddress")]
+	pub remote_address: String,
+	/// Bytes sent to the peer
+	#[serde(rename="bytesSent")]
+	pub bytes_sent: u64,
+	/// Bytes received from the peer
+	#[serde(rename="bytesReceived")]
+	pub bytes_received: u64,
+	/// Per packet counters
+	pub packets: Vec<PacketTraffic>,
+}
+
+impl From<EthPeerTraffic> for PeerTraffic {
+	fn from(p: EthPeerTraffic) -> Self {
+		PeerTraffic {
+			id: p.id,
+			name: p.client_version,
+			remote_address: p.remote_address,
+			bytes_sent: p.bytes_sent,
+			bytes_received: p.bytes_received,
+			packets: p.packets.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Total network traffic
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct NetworkTraffic {
+	/// Total bytes sent
+	#[serde(rename="bytesSent")]
+	pub bytes_sent: u64,
+	/// Total bytes received
+	#[serde(rename="bytesReceived")]
+	pub bytes_received: u64,
+	/// Total number of sessions created
+	pub sessions: u64,
+	/// Per packet counters
+	pub packets: Vec<PacketTraffic>,
+}
+
+impl From<EthNetworkTraffic> for NetworkTraffic {
+	fn from(t: EthNetworkTraffic) -> Self {
+		NetworkTraffic {
+			bytes_sent: t.bytes_sent,
+			bytes_received: t.bytes_received,
+			sessions: t.sessions,
+			packets: t.packets.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Node table entry with connection history
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct NodeTableEntry {
+	/// Node URL
+	pub url: String,
+	/// Total number of failures
+	pub failures: u32,
+	/// Reputation score
+	pub reputation: i32,
+	/// Last time a session with the node was active, as unix time
+	#[serde(rename="lastSeen")]
+	pub last_seen: Option<u64>,
+	/// Last time a session with the node was established, as unix time
+	#[serde(rename="lastSession")]
+	pub last_session: Option<u64>,
+	/// Total time spent in sessions with the node, in seconds
+	#[serde(rename="sessionTime")]
+	pub session_time: u64,
+	/// Client version reported in the last session
+	#[serde(rename="clientVersion")]
+	pub client_version: Option<String>,
+	/// Times of recent failures
+	#[serde(rename="recentFailures")]
+	pub recent_failures: Vec<u64>,
+}
+
+impl From<EthNodeTableEntry> for NodeTableEntry {
+	fn from(n: EthNodeTableEntry) -> Self {
+		NodeTableEntry {
+			url: n.url,
+			failures: n.failures,
+			reputation: n.reputation,
+			last_seen: n.last_seen,
+			last_session: n.last_session,
+			session_time: n.session_time,
+			client_version: n.client_version,
+			recent_failures: n.recent_failures,
+		}
+	}
+}
+
+/// Inclusive range of block numbers
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct BlockRange {
+	/// First block number
+	pub first: U256,
+	/// Last block number
+	pub last: U256,
+}
+
+impl From<EthBlockRange> for BlockRange {
+	fn from(r: EthBlockRange) -> Self {
+		BlockRange {
+			first: r.first.into(),
+			last: r.last.into(),
+		}
+	}
+}
+
+/// Sync state of a connected peer
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerSyncInfo {
+	/// Peer id
+	pub id: usize,
+	/// eth protocol version
+	#[serde(rename="protocolVersion")]
+	pub protocol_version: u32,
+	/// Peer best block hash
+	#[serde(rename="latestHash")]
+	pub latest_hash: H256,
+	/// Peer best block number if known
+	#[serde(rename="latestNumber")]
+	pub latest_number: Option<U256>,
+	/// Peer total difficulty if known
+	pub difficulty: Option<U256>,
+	/// Type of data currently being requested from the peer
+	pub asking: String,
+	/// Milliseconds since the pending request was sent
+	#[serde(rename="askingTime")]
+	pub asking_time: Option<u64>,
+	/// Blocks of the pending headers request, if known
+	#[serde(rename="askedBlocks")]
+	pub asked_blocks: Option<BlockRange>,
+	/// Blocks of the last headers response
+	#[serde(rename="receivedBlocks")]
+	pub received_blocks: Option<BlockRange>,
+	/// Milliseconds it took to answer the last request
+	#[serde(rename="roundTrip")]
+	pub round_trip: Option<u64>,
+	/// Peer chain is confirmed to include the fork block and the checkpoint
+	pub confirmed: bool,
+	/// Pending request is expired and its result will be ignored
+	pub expired: bool,
+}
+
+impl From<EthPeerSyncInfo> for PeerSyncInfo {
+	fn from(p: EthPeerSyncInfo) -> Self {
+		PeerSyncInfo {
+			id: p.id,
+			protocol_version: p.protocol_version,
+			latest_hash: p.latest_hash.into(),
+			latest_number: p.latest_number.map(Into::into),
+			difficulty: p.difficulty.map(Into::into),
+			asking: p.asking,
+			asking_time: p.asking_time,
+			asked_blocks: p.asked_blocks.map(Into::into),
+			received_blocks: p.received_blocks.map(Into::into),
+			round_trip: p.round_trip,
+			confirmed: p.confirmed,
+			expired: p.expired,
+		}
+	}
+}
+
+/// Block download statistics
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct BlockDownloadStats {
+	/// Number of subchain headers being downloaded
+	#[serde(rename="downloadingHeaders")]
+	pub downloading_headers: usize,
+	/// Number of block bodies being downloaded
+	#[serde(rename="downloadingBodies")]
+	pub downloading_bodies: usize,
+	/// Number of downloaded blocks waiting to be imported
+	#[serde(rename="queuedBlocks")]
+	pub queued_blocks: usize,
+	/// Number of queued blocks that also have a body
+	#[serde(rename="completeBlocks")]
+	pub complete_blocks: usize,
+	/// Number of subchains with headers still missing
+	pub gaps: usize,
+}
+
+impl From<BlockCollectionStats> for BlockDownloadStats {
+	fn from(s: BlockCollectionStats) -> Self {
+		BlockDownloadStats {
+			downloading_headers: s.downloading_headers,
+			downloading_bodies: s.downloading_bodies,
+			queued_blocks: s.queued_blocks,
+			complete_blocks: s.complete_blocks,
+			gaps: s.gaps,
+		}
+	}
+}
+
+/// Snapshot restoration progress
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct RestorationInfo {
+	/// One of `inactive`, `ongoing` or `failed`
+	pub status: String,
+	/// Number of state chunks restored
+	#[serde(rename="stateChunksDone")]
+	pub state_chunks_done: u32,
+	/// Number of block chunks restored
+	#[serde(rename="blockChunksDone")]
+	pub block_chunks_done: u32,
+	/// Number of chunks in the snapshot being downloaded
+	#[serde(rename="totalChunks")]
+	pub total_chunks: usize,
+	/// Number of chunks downloaded
+	#[serde(rename="downloadedChunks")]
+	pub downloaded_chunks: usize,
+}
+
+/// Sync state change
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct SyncTransition {
+	/// State entered
+	pub state: String,
+	/// Time of the change, as unix time
+	pub timestamp: u64,
+}
+
+impl From<EthSyncTransition> for SyncTransition {
+	fn from(t: EthSyncTransition) -> Self {
+		SyncTransition {
+			state: format!("{:?}", t.state),
+			timestamp: t.timestamp,
+		}
+	}
+}
+
+/// Detailed sync status
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct SyncDetails {
+	/// Current sync state
+	pub state: String,
+	/// Starting block
+	#[serde(rename="startingBlock")]
+	pub starting_block: U256,
+	/// Current block
+	#[serde(rename="currentBlock")]
+	pub current_block: U256,
+	/// Highest block seen so far
+	#[serde(rename="highestBlock")]
+	pub highest_block: Option<U256>,
+	/// Connected peers
+	pub peers: Vec<PeerSyncInfo>,
+	/// Block download statistics
+	pub blocks: BlockDownloadStats,
+	/// Snapshot restoration progress
+	pub restoration: RestorationInfo,
+	/// Recent sync state changes, oldest first
+	pub history: Vec<SyncTransition>,
+}
+
+impl From<EthSyncDetails> for SyncDetails {
+	fn from(d: EthSyncDetails) -> Self {
+		let (status, state_chunks_done, block_chunks_done) = match d.restoration {
+			RestorationStatus::Inactive => ("inactive", 0, 0),
+			RestorationStatus::Ongoing { state_chunks_done, block_chunks_done } => ("ongoing", state_chunks_done, block_chunks_done),
+			RestorationStatus::Failed => ("failed", 0, 0),
+		};
+		SyncDetails {
+			state: format!("{:?}", d.status.state),
+			starting_block: d.status.start_block_number.into(),
+			current_block: d.status.last_imported_block_number.unwrap_or(d.status.start_block_number).into(),
+			highest_block: d.status.highest_block_number.map(Into::into),
+			peers: d.peers.into_iter().map(Into::into).collect(),
+			blocks: d.blocks.into(),
+			restoration: RestorationInfo {
+				status: status.to_owned(),
+				state_chunks_done: state_chunks_done,
+				block_chunks_done: block_chunks_done,
+				total_chunks: d.status.num_snapshot_chunks,
+				downloaded_chunks: d.status.snapshot_chunks_done,
+			},
+			history: d.history.into_iter().map(Into::into).collect(),
+		}
+	}
 }
 
 /// Sync status
@@ -64,7 +399,7 @@ impl Serialize for SyncStatus {
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use super::{SyncInfo, SyncStatus, Peers};
+	use super::{SyncInfo, SyncStatus, Peers, NetworkTraffic, NodeTableEntry, SyncDetails};
 
 	#[test]
 	fn test_serialize_sync_info() {
@@ -77,7 +412,28 @@ mod tests {
 	fn test_serialize_peers() {
 		let t = Peers::default();
 		let serialized = serde_json::to_string(&t).unwrap();
-		assert_eq!(serialized, r#"{"active":0,"connected":0,"max":0}"#);
+		assert_eq!(serialized, r#"{"active":0,"connected":0,"max":0,"peers":[]}"#);
+	}
+
+	#[test]
+	fn test_serialize_network_traffic() {
+		let t = NetworkTraffic::default();
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"bytesSent":0,"bytesReceived":0,"sessions":0,"packets":[]}"#);
+	}
+
+	#[test]
+	fn test_serialize_node_table_entry() {
+		let t = NodeTableEntry::default();
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"url":"","failures":0,"reputation":0,"lastSeen":null,"lastSession":null,"sessionTime":0,"clientVersion":null,"recentFailures":[]}"#);
+	}
+
+	#[test]
+	fn test_serialize_sync_details() {
+		let t = SyncDetails::default();
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"state":"","startingBlock":"0x0","currentBlock":"0x0","highestBlock":null,"peers":[],"blocks":{"downloadingHeaders":0,"downloadingBodies":0,"queuedBlocks":0,"completeBlocks":0,"gaps":0},"restoration":{"status":"","stateChunksDone":0,"blockChunksDone":0,"totalChunks":0,"downloadedChunks":0},"history":[]}"#);
 	}
 
 	#[test]
@@ -0,0 +1,109 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{Arc, Weak};
+use ethcore::client::{BlockChainClient, BlockID};
+use ethcore::transaction::{Transaction, Action};
+use util::{Address, U256, Bytes, Hashable};
+use types::{Error, DocumentId};
+
+/// Document access permissions storage.
+pub trait AclStorage: Send + Sync {
+	/// Check if requester is allowed to access the document key.
+	fn check(&self, requester: &Address, document: &DocumentId) -> Result<bool, Error>;
+}
+
+/// Permissions storage, backed by the `checkPermissions(address,bytes32)` method of the on-chain contract.
+pub struct OnChainAclStorage<C> where C: BlockChainClient {
+	client: Weak<C>,
+	contract: Address,
+}
+
+impl<C> OnChainAclStorage<C> where C: BlockChainClient {
+	/// Create new on-chain permissions storage.
+	pub fn new(client: &Arc<C>, contract: Address) -> Self {
+		OnChainAclStorage {
+			client: Arc::downgrade(client),
+			contract: contract,
+		}
+	}
+}
+
+impl<C> AclStorage for OnChainAclStorage<C> where C: BlockChainClient + 'static {
+	fn check(&self, requester: &Address, document: &DocumentId) -> Result<bool, Error> {
+		let client = try!(self.client.upgrade().ok_or(Error::Internal("Client is not available".into())));
+		let transaction = Transaction {
+			nonce: U256::zero(),
+			action: Action::Call(self.contract.clone()),
+			gas: U256::from(50_000_000),
+			gas_price: U256::zero(),
+			value: U256::zero(),
+			data: check_permissions_call_data(requester, document),
+		}.fake_sign(Address::default());
+
+		let executed = try!(client.call(&transaction, BlockID::Latest, Default::default())
+			.map_err(|e| Error::Internal(format!("Permissions contract call has failed: {:?}", e))));
+		Ok(executed.output.len() == 32 && executed.output.iter().any(|b| *b != 0))
+	}
+}
+
+/// ABI-encoded call of `checkPermissions(address,bytes32)`.
+fn check_permissions_call_data(requester: &Address, document: &DocumentId) -> Bytes {
+	let mut data = "checkPermissions(address,bytes32)".sha3()[0..4].to_vec();
+	data.extend_from_slice(&[0u8; 12]);
+	data.extend_from_slice(&**requester);
+	data.extend_from_slice(&**document);
+	data
+}
+
+#[cfg(test)]
+pub mod tests {
+	use std::collections::HashSet;
+	use util::{Address, Hashable, RwLock};
+	use types::{Error, DocumentId};
+	use super::{AclStorage, check_permissions_call_data};
+
+	/// Permissions storage with explicitly prohibited (requester, document) pairs.
+	#[derive(Default)]
+	pub struct DummyAclStorage {
+		prohibited: RwLock<HashSet<(Address, DocumentId)>>,
+	}
+
+	impl DummyAclStorage {
+		pub fn prohibit(&self, requester: Address, document: DocumentId) {
+			self.prohibited.write().insert((requester, document));
+		}
+	}
+
+	impl AclStorage for DummyAclStorage {
+		fn check(&self, requester: &Address, document: &DocumentId) -> Result<bool, Error> {
+			Ok(!self.prohibited.read().contains(&(requester.clone(), document.clone())))
+		}
+	}
+
+	#[test]
+	fn should_encode_check_permissions_call() {
+		let requester = Address::from(0x42);
+		let document = "document".sha3();
+		let data = check_permissions_call_data(&requester, &document);
+
+		assert_eq!(data.len(), 4 + 32 + 32);
+		assert_eq!(&data[0..4], &"checkPermissions(address,bytes32)".sha3()[0..4]);
+		assert_eq!(&data[4..16], &[0u8; 12]);
+		assert_eq!(&data[16..36], &*requester);
+		assert_eq!(&data[36..68], &*document);
+	}
+}
@@ -0,0 +1,640 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::net::{TcpListener, TcpStream, SocketAddr};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::thread;
+use ethkey::{KeyPair, Secret, Signature, recover, public_to_address};
+use ethcrypto::ecies;
+use util::{H256, H520, FixedHash, Hashable, Bytes, Mutex};
+use acl_storage::AclStorage;
+use key_storage::{KeyStorage, DocumentKeyShare};
+use net::{Connection, Message, NodeShare};
+use traits::KeyServer;
+use types::{Error, NodeId, NodeAddress, DocumentId, RequestNonce, RequestSignature, EncryptedDocumentKeyShare, ServiceConfiguration};
+use math;
+
+/// Number of milliseconds the request nonce is accepted for.
+/// Key servers' clocks are expected to differ by less than that.
+const REQUEST_LIFETIME_MS: u64 = 60_000;
+/// Maximal number of incoming connections served at the same time.
+/// Connections above the limit are dropped before the handshake.
+const MAX_INCOMING_CONNECTIONS: usize = 64;
+
+/// Key server, which holds shares of the document keys.
+pub struct KeyServerImpl {
+	core: Arc<KeyServerCore>,
+	listener_address: SocketAddr,
+	listener_thread: Option<thread::JoinHandle<()>>,
+}
+
+struct KeyServerCore {
+	self_key: KeyPair,
+	nodes: BTreeMap<NodeId, NodeAddress>,
+	acl_storage: Arc<AclStorage>,
+	key_storage: Arc<KeyStorage>,
+	/// Key generation sessions this key server has dealt shares for.
+	sessions: Mutex<BTreeMap<DocumentId, Session>>,
+	/// Signatures of the processed requests with their nonces.
+	used_signatures: Mutex<HashMap<H520, RequestNonce>>,
+	/// Number of incoming connections being served.
+	connections: AtomicUsize,
+	stopped: AtomicBool,
+}
+
+/// Document key generation session.
+struct Session {
+	/// Key server, which has started the session.
+	master: NodeId,
+	/// Requester of the document key.
+	requester: NodeId,
+	/// Decryption threshold.
+	threshold: usize,
+	/// Time the session was started at.
+	started: u64,
+	/// True if the key share has been stored.
+	completed: bool,
+}
+
+impl KeyServerImpl {
+	/// Create new key server and start listening for other key servers.
+	pub fn new(config: &ServiceConfiguration, acl_storage: Arc<AclStorage>, key_storage: Arc<KeyStorage>) -> Result<Self, Error> {
+		let self_key = try!(KeyPair::from_secret(config.self_secret.clone()));
+		if !config.nodes.contains_key(self_key.public()) {
+			return Err(Error::InvalidNode);
+		}
+
+		let listener = try!(TcpListener::bind((&*config.listener_address.address, config.listener_address.port)));
+		let listener_address = try!(listener.local_addr());
+		let core = Arc::new(KeyServerCore {
+			self_key: self_key,
+			nodes: config.nodes.clone(),
+			acl_storage: acl_storage,
+			key_storage: key_storage,
+			sessions: Mutex::new(BTreeMap::new()),
+			used_signatures: Mutex::new(HashMap::new()),
+			connections: AtomicUsize::new(0),
+			stopped: AtomicBool::new(false),
+		});
+
+		let listener_core = core.clone();
+		let listener_thread = try!(thread::Builder::new().name("secretstore".into()).spawn(move || {
+			for stream in listener.incoming() {
+				if listener_core.stopped.load(Ordering::Relaxed) {
+					break;
+				}
+
+				match stream {
+					Ok(stream) => {
+						if listener_core.connections.fetch_add(1, Ordering::SeqCst) >= MAX_INCOMING_CONNECTIONS {
+							listener_core.connections.fetch_sub(1, Ordering::SeqCst);
+							warn!(target: "secretstore", "Too many incoming connections, dropping connection from {:?}", stream.peer_addr());
+							continue;
+						}
+
+						let core = listener_core.clone();
+						thread::spawn(move || {
+							core.serve(stream);
+							core.connections.fetch_sub(1, Ordering::SeqCst);
+						});
+					},
+					Err(err) => warn!(target: "secretstore", "Failed to accept connection: {}", err),
+				}
+			}
+		}));
+
+		Ok(KeyServerImpl {
+			core: core,
+			listener_address: listener_address,
+			listener_thread: Some(listener_thread),
+		})
+	}
+}
+
+impl KeyServer for KeyServerImpl {
+	fn generate_document_key(&self, signature: &RequestSignature, document: &DocumentId, nonce: RequestNonce, threshold: usize) -> Result<(), Error> {
+		let signature = H520::from_slice(&signature[..]);
+		try!(self.core.check_access(&signature, document, nonce));
+		try!(self.core.check_threshold(threshold));
+		if self.core.key_storage.contains(document) {
+			return Err(Error::DocumentAlreadyExists);
+		}
+
+		// key servers forget the session once it's over, dropping stored key shares if it has failed
+		let result = self.core.run_session(document, nonce, threshold, signature);
+		let finish = match result {
+			Ok(_) => Message::CommitSession { document: document.clone() },
+			Err(_) => Message::CancelSession { document: document.clone() },
+		};
+		for node in self.core.nodes.keys() {
+			if let Err(err) = self.core.send_message(node, finish.clone()) {
+				warn!(target: "secretstore", "Key server {} has not finished session {}: {}", node, document, err);
+			}
+		}
+
+		result
+	}
+
+	fn document_key_shares(&self, signature: &RequestSignature, document: &DocumentId, nonce: RequestNonce) -> Result<Vec<EncryptedDocumentKeyShare>, Error> {
+		let signature = H520::from_slice(&signature[..]);
+		try!(self.core.check_access(&signature, document, nonce));
+		let threshold = try!(self.core.key_storage.get(document)).threshold;
+
+		let mut shares = Vec::with_capacity(threshold + 1);
+		for node in self.core.nodes.keys() {
+			let message = Message::RequestKeyShare {
+				document: document.clone(),
+				nonce: nonce,
+				signature: signature.clone(),
+			};
+			match self.core.send_message(node, message) {
+				Ok(Message::KeyShare(share)) => shares.push(share),
+				Ok(_) => warn!(target: "secretstore", "Unexpected response from key server {}", node),
+				Err(err) => warn!(target: "secretstore", "Key server {} has not released key share: {}", node, err),
+			}
+
+			if shares.len() == threshold + 1 {
+				return Ok(shares);
+			}
+		}
+
+		Err(Error::ConsensusUnreachable)
+	}
+}
+
+impl Drop for KeyServerImpl {
+	fn drop(&mut self) {
+		self.core.stopped.store(true, Ordering::Relaxed);
+		// wake up the listener thread
+		let _ = TcpStream::connect(self.listener_address);
+		if let Some(listener_thread) = self.listener_thread.take() {
+			let _ = listener_thread.join();
+		}
+	}
+}
+
+impl KeyServerCore {
+	/// Let every key server deal shares of its random polynomial and combine shares, dealt to it, into its key share.
+	fn run_session(&self, document: &DocumentId, nonce: RequestNonce, threshold: usize, signature: H520) -> Result<(), Error> {
+		let mut dealt_shares: BTreeMap<NodeId, Vec<Bytes>> = BTreeMap::new();
+		for node in self.nodes.keys() {
+			let message = Message::InitializeSession {
+				document: document.clone(),
+				threshold: threshold,
+				nonce: nonce,
+				signature: signature.clone(),
+			};
+			match try!(self.send_message(node, message)) {
+				Message::SessionDealing { shares } => for share in shares {
+					dealt_shares.entry(share.node).or_insert_with(Vec::new).push(share.share);
+				},
+				_ => return Err(Error::InvalidMessage),
+			}
+		}
+
+		for node in self.nodes.keys() {
+			let shares = try!(dealt_shares.remove(node).ok_or(Error::InvalidMessage));
+			if shares.len() != self.nodes.len() {
+				return Err(Error::InvalidMessage);
+			}
+
+			let message = Message::CompleteSession {
+				document: document.clone(),
+				threshold: threshold,
+				shares: shares,
+			};
+			match try!(self.send_message(node, message)) {
+				Message::Confirmation => (),
+				_ => return Err(Error::InvalidMessage),
+			}
+		}
+
+		Ok(())
+	}
+
+	fn serve(&self, stream: TcpStream) {
+		let mut connection = match Connection::accept(stream, &self.self_key, &self.nodes) {
+			Ok(connection) => connection,
+			Err(err) => {
+				warn!(target: "secretstore", "Failed to authenticate incoming connection: {}", err);
+				return;
+			},
+		};
+
+		let node = connection.node().clone();
+		let result = connection.receive()
+			.map(|message| self.process_message(&node, message))
+			.and_then(|response| connection.send(&response));
+		if let Err(err) = result {
+			warn!(target: "secretstore", "Failed to process request from key server {}: {}", node, err);
+		}
+	}
+
+	fn send_message(&self, node: &NodeId, message: Message) -> Result<Message, Error> {
+		let response = match node == self.self_key.public() {
+			true => self.process_message(node, message),
+			false => {
+				let address = try!(self.nodes.get(node).ok_or(Error::InvalidNode));
+				let mut connection = try!(Connection::connect(&self.self_key, node, address));
+				try!(connection.send(&message));
+				try!(connection.receive())
+			},
+		};
+
+		match response {
+			Message::Error(err) => Err(Error::Internal(err)),
+			response => Ok(response),
+		}
+	}
+
+	fn process_message(&self, sender: &NodeId, message: Message) -> Message {
+		trace!(target: "secretstore", "Processing {:?} from key server {}", message, sender);
+		let result = match message {
+			Message::InitializeSession { document, threshold, nonce, signature } => self.initialize_session(sender, document, threshold, nonce, signature),
+			Message::CompleteSession { document, threshold, shares } => self.complete_session(sender, document, threshold, shares),
+			Message::CommitSession { document } => self.commit_session(sender, document),
+			Message::CancelSession { document } => self.cancel_session(sender, document),
+			Message::RequestKeyShare { document, nonce, signature } => self.release_key_share(document, nonce, signature),
+			_ => Err(Error::InvalidMessage),
+		};
+
+		match result {
+			Ok(response) => response,
+			Err(err) => Message::Error(format!("{}", err)),
+		}
+	}
+
+	fn initialize_session(&self, sender: &NodeId, document: DocumentId, threshold: usize, nonce: RequestNonce, signature: H520) -> Result<Message, Error> {
+		let requester = try!(self.check_request(&signature, &document, nonce));
+		try!(self.check_threshold(threshold));
+		if self.key_storage.contains(&document) {
+			return Err(Error::DocumentAlreadyExists);
+		}
+
+		{
+			// session, abandoned before the key share was stored, may be started again
+			let now = now_ms();
+			let mut sessions = self.sessions.lock();
+			if let Some(session) = sessions.get(&document) {
+				if session.completed || session.started + REQUEST_LIFETIME_MS > now {
+					return Err(Error::DocumentAlreadyExists);
+				}
+			}
+			sessions.insert(document.clone(), Session {
+				master: sender.clone(),
+				requester: requester,
+				threshold: threshold,
+				started: now,
+				completed: false,
+			});
+		}
+
+		let polynomial = math::generate_random_polynomial(threshold);
+		let shares = try!(self.nodes.keys().map(|node| -> Result<NodeShare, Error> {
+			let share = math::compute_polynomial(&polynomial, &math::node_id_number(node));
+			Ok(NodeShare {
+				node: node.clone(),
+				share: try!(ecies::encrypt(node, &[], &*share)),
+			})
+		}).collect::<Result<Vec<_>, Error>>());
+
+		Ok(Message::SessionDealing {
+			shares: shares,
+		})
+	}
+
+	fn complete_session(&self, sender: &NodeId, document: DocumentId, threshold: usize, shares: Vec<Bytes>) -> Result<Message, Error> {
+		let mut sessions = self.sessions.lock();
+		{
+			let session = try!(sessions.get(&document).ok_or(Error::UnknownSession));
+			if session.master != *sender || session.completed {
+				return Err(Error::UnknownSession);
+			}
+			if session.threshold != threshold || shares.len() != self.nodes.len() {
+				return Err(Error::InvalidMessage);
+			}
+			if !try!(self.acl_storage.check(&public_to_address(&session.requester), &document)) {
+				return Err(Error::AccessDenied);
+			}
+		}
+
+		let shares = try!(shares.iter()
+			.map(|share| decrypt_secret(self.self_key.secret(), share))
+			.collect::<Result<Vec<_>, Error>>());
+		try!(self.key_storage.insert(document.clone(), DocumentKeyShare {
+			threshold: threshold,
+			secret_share: math::compute_secret_share(shares.iter()),
+		}));
+		sessions.get_mut(&document).expect("session has been checked above; qed").completed = true;
+
+		Ok(Message::Confirmation)
+	}
+
+	fn commit_session(&self, sender: &NodeId, document: DocumentId) -> Result<Message, Error> {
+		try!(self.take_session(sender, &document));
+		Ok(Message::Confirmation)
+	}
+
+	fn cancel_session(&self, sender: &NodeId, document: DocumentId) -> Result<Message, Error> {
+		if try!(self.take_session(sender, &document)).completed {
+			try!(self.key_storage.remove(&document));
+		}
+		Ok(Message::Confirmation)
+	}
+
+	/// Remove the session, started by given key server.
+	fn take_session(&self, sender: &NodeId, document: &DocumentId) -> Result<Session, Error> {
+		let mut sessions = self.sessions.lock();
+		match sessions.get(document).map(|session| session.master == *sender) {
+			Some(true) => Ok(sessions.remove(document).expect("session exists; qed")),
+			_ => Err(Error::UnknownSession),
+		}
+	}
+
+	fn release_key_share(&self, document: DocumentId, nonce: RequestNonce, signature: H520) -> Result<Message, Error> {
+		let requester = try!(self.check_request(&signature, &document, nonce));
+		let key = try!(self.key_storage.get(&document));
+
+		Ok(Message::KeyShare(EncryptedDocumentKeyShare {
+			id_number: math::node_id_number(self.self_key.public()),
+			encrypted_share: try!(ecies::encrypt(&requester, &[], &*key.secret_share)),
+		}))
+	}
+
+	/// Recover requester public key from the signature and check if it is allowed to access the document key.
+	fn check_access(&self, signature: &H520, document: &DocumentId, nonce: RequestNonce) -> Result<NodeId, Error> {
+		let now = now_ms();
+		if nonce.saturating_add(REQUEST_LIFETIME_MS) < now || nonce > now + REQUEST_LIFETIME_MS {
+			return Err(Error::InvalidNonce);
+		}
+
+		let requester = try!(recover(&Signature::from(signature.clone()), &request_hash(document, nonce)).map_err(|_| Error::BadSignature));
+		match try!(self.acl_storage.check(&public_to_address(&requester), document)) {
+			true => Ok(requester),
+			false => Err(Error::AccessDenied),
+		}
+	}
+
+	/// Check access to the document key and make sure the request is processed only once.
+	fn check_request(&self, signature: &H520, document: &DocumentId, nonce: RequestNonce) -> Result<NodeId, Error> {
+		let requester = try!(self.check_access(signature, document, nonce));
+
+		let now = now_ms();
+		let mut used_signatures = self.used_signatures.lock();
+		// expired requests are rejected anyway
+		let expired: Vec<_> = used_signatures.iter()
+			.filter(|&(_, used_nonce)| used_nonce.saturating_add(REQUEST_LIFETIME_MS) < now)
+			.map(|(signature, _)| signature.clone())
+			.collect();
+		for signature in expired {
+			used_signatures.remove(&signature);
+		}
+
+		if used_signatures.insert(signature.clone(), nonce).is_some() {
+			return Err(Error::InvalidNonce);
+		}
+		Ok(requester)
+	}
+
+	fn check_threshold(&self, threshold: usize) -> Result<(), Error> {
+		match threshold < self.nodes.len() {
+			true => Ok(()),
+			false => Err(Error::InvalidThreshold),
+		}
+	}
+}
+
+/// Number of milliseconds since epoch.
+fn now_ms() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000).unwrap_or(0)
+}
+
+/// Hash of the request, which has to be signed by the requester.
+pub fn request_hash(document: &DocumentId, nonce: RequestNonce) -> H256 {
+	let mut data = document.to_vec();
+	data.extend((0..8).map(|i| (nonce >> (56 - 8 * i)) as u8));
+	data.sha3()
+}
+
+fn decrypt_secret(secret: &Secret, encrypted: &[u8]) -> Result<Secret, Error> {
+	let decrypted = try!(ecies::decrypt(secret, &[], encrypted));
+	match decrypted.len() {
+		32 => Ok(Secret::from_slice(&decrypted)),
+		_ => Err(Error::InvalidMessage),
+	}
+}
+
+/// Recover the document key from the key shares, released to the requester.
+pub fn decrypt_document_key(requester: &Secret, shares: &[EncryptedDocumentKeyShare]) -> Result<Secret, Error> {
+	let mut id_numbers = HashSet::new();
+	let shares = try!(shares.iter().map(|share| -> Result<(Secret, Secret), Error> {
+		if !id_numbers.insert(share.id_number.clone()) {
+			return Err(Error::InvalidMessage);
+		}
+		Ok((share.id_number.clone(), try!(decrypt_secret(requester, &share.encrypted_share))))
+	}).collect::<Result<Vec<_>, Error>>());
+
+	Ok(math::compute_joint_secret(&shares))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+	use std::collections::BTreeMap;
+	use ethkey::{Random, Generator, KeyPair, Signature, sign};
+	use ethcrypto::document;
+	use util::Hashable;
+	use acl_storage::tests::DummyAclStorage;
+	use key_storage::KeyStorage;
+	use key_storage::tests::DummyKeyStorage;
+	use net::Message;
+	use traits::KeyServer;
+	use types::{Error, NodeAddress, DocumentId, RequestNonce, ServiceConfiguration};
+	use super::{KeyServerImpl, request_hash, decrypt_document_key, now_ms};
+
+	struct TestKeyServer {
+		key_server: KeyServerImpl,
+		acl_storage: Arc<DummyAclStorage>,
+		key_storage: Arc<DummyKeyStorage>,
+	}
+
+	/// Start key servers, ordered by their node ids.
+	fn start_key_servers(start_port: u16, num_nodes: usize) -> Vec<TestKeyServer> {
+		let mut keys: Vec<KeyPair> = (0..num_nodes).map(|_| Random.generate().unwrap()).collect();
+		keys.sort_by(|a, b| a.public().cmp(b.public()));
+		let nodes: BTreeMap<_, _> = keys.iter().enumerate().map(|(i, key)| (key.public().clone(), NodeAddress {
+			address: "127.0.0.1".into(),
+			port: start_port + i as u16,
+		})).collect();
+
+		keys.iter().map(|key| {
+			let config = ServiceConfiguration {
+				self_secret: key.secret().clone(),
+				listener_address: nodes[key.public()].clone(),
+				nodes: nodes.clone(),
+				acl_contract: Default::default(),
+				data_path: Default::default(),
+			};
+			let acl_storage = Arc::new(DummyAclStorage::default());
+			let key_storage = Arc::new(DummyKeyStorage::default());
+			TestKeyServer {
+				key_server: KeyServerImpl::new(&config, acl_storage.clone(), key_storage.clone()).unwrap(),
+				acl_storage: acl_storage,
+				key_storage: key_storage,
+			}
+		}).collect()
+	}
+
+	/// Sign the request with a fresh nonce.
+	fn sign_request(requester: &KeyPair, document: &DocumentId) -> (Signature, RequestNonce) {
+		static COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+		let nonce = now_ms() + COUNTER.fetch_add(1, Ordering::Relaxed) as u64;
+		(sign(requester.secret(), &request_hash(document, nonce)).unwrap(), nonce)
+	}
+
+	#[test]
+	fn document_key_is_generated_and_released_by_threshold_of_key_servers() {
+		let key_servers = start_key_servers(6160, 4);
+		let requester = Random.generate().unwrap();
+		let document = "document".sha3();
+
+		// threshold must be less than the number of key servers
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert_eq!(key_servers[0].key_server.generate_document_key(&signature, &document, nonce, 4), Err(Error::InvalidThreshold));
+		let (signature, nonce) = sign_request(&requester, &document);
+		key_servers[0].key_server.generate_document_key(&signature, &document, nonce, 1).unwrap();
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert_eq!(key_servers[2].key_server.generate_document_key(&signature, &document, nonce, 1), Err(Error::DocumentAlreadyExists));
+
+		// any key server collects enough shares to recover the same document key
+		let (signature, nonce) = sign_request(&requester, &document);
+		let shares = key_servers[1].key_server.document_key_shares(&signature, &document, nonce).unwrap();
+		assert_eq!(shares.len(), 2);
+		let document_key = decrypt_document_key(requester.secret(), &shares).unwrap();
+		let (signature, nonce) = sign_request(&requester, &document);
+		let shares = key_servers[3].key_server.document_key_shares(&signature, &document, nonce).unwrap();
+		assert_eq!(decrypt_document_key(requester.secret(), &shares), Ok(document_key.clone()));
+
+		// the document key can be used to encrypt the document
+		let encrypted = document::encrypt_with_key(&*document_key, b"secret document").unwrap();
+		assert_eq!(document::decrypt(&*document_key, &encrypted).unwrap(), b"secret document".to_vec());
+
+		// shares are encrypted for the requester only
+		let other = Random.generate().unwrap();
+		assert!(decrypt_document_key(other.secret(), &shares).is_err());
+		let other_document = "other document".sha3();
+		let (other_signature, other_nonce) = sign_request(&requester, &other_document);
+		assert_eq!(key_servers[0].key_server.document_key_shares(&other_signature, &other_document, other_nonce), Err(Error::DocumentNotFound));
+	}
+
+	#[test]
+	fn document_key_shares_are_released_to_permitted_requesters_only() {
+		let key_servers = start_key_servers(6170, 3);
+		let requester = Random.generate().unwrap();
+		let document = "document".sha3();
+		let (signature, nonce) = sign_request(&requester, &document);
+		key_servers[0].key_server.generate_document_key(&signature, &document, nonce, 1).unwrap();
+		let (signature, nonce) = sign_request(&requester, &document);
+		let document_key = decrypt_document_key(requester.secret(), &key_servers[0].key_server.document_key_shares(&signature, &document, nonce).unwrap()).unwrap();
+
+		// single key server has revoked access: the rest of key servers are still enough
+		key_servers[2].acl_storage.prohibit(requester.address(), document.clone());
+		let (signature, nonce) = sign_request(&requester, &document);
+		let shares = key_servers[0].key_server.document_key_shares(&signature, &document, nonce).unwrap();
+		assert_eq!(decrypt_document_key(requester.secret(), &shares), Ok(document_key));
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert_eq!(key_servers[2].key_server.document_key_shares(&signature, &document, nonce), Err(Error::AccessDenied));
+
+		// two key servers have revoked access: the document key can't be recovered
+		key_servers[1].acl_storage.prohibit(requester.address(), document.clone());
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert_eq!(key_servers[0].key_server.document_key_shares(&signature, &document, nonce), Err(Error::ConsensusUnreachable));
+
+		// requester is not permitted to generate document keys
+		let other_document = "other document".sha3();
+		key_servers[1].acl_storage.prohibit(requester.address(), other_document.clone());
+		let (other_signature, other_nonce) = sign_request(&requester, &other_document);
+		assert!(key_servers[0].key_server.generate_document_key(&other_signature, &other_document, other_nonce, 1).is_err());
+		let (other_signature, other_nonce) = sign_request(&requester, &other_document);
+		assert_eq!(key_servers[1].key_server.generate_document_key(&other_signature, &other_document, other_nonce, 1), Err(Error::AccessDenied));
+	}
+
+	#[test]
+	fn stored_key_shares_are_dropped_when_generation_fails() {
+		let key_servers = start_key_servers(6180, 3);
+		let requester = Random.generate().unwrap();
+		let document = "document".sha3();
+
+		// the last key server to complete the session fails to store its share
+		key_servers[2].key_storage.fail_inserts();
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert!(key_servers[0].key_server.generate_document_key(&signature, &document, nonce, 1).is_err());
+		assert!(key_servers.iter().all(|server| !server.key_storage.contains(&document)));
+
+		// and the document key can't be requested
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert_eq!(key_servers[1].key_server.document_key_shares(&signature, &document, nonce), Err(Error::DocumentNotFound));
+	}
+
+	#[test]
+	fn requests_are_processed_once() {
+		let key_servers = start_key_servers(6190, 3);
+		let requester = Random.generate().unwrap();
+		let document = "document".sha3();
+		let (signature, nonce) = sign_request(&requester, &document);
+		key_servers[0].key_server.generate_document_key(&signature, &document, nonce, 1).unwrap();
+
+		// replayed request is rejected by every key server
+		let (signature, nonce) = sign_request(&requester, &document);
+		assert!(key_servers[0].key_server.document_key_shares(&signature, &document, nonce).is_ok());
+		assert_eq!(key_servers[0].key_server.document_key_shares(&signature, &document, nonce), Err(Error::ConsensusUnreachable));
+
+		// request signed long ago is rejected
+		let nonce = now_ms() - 3_600_000;
+		let signature = sign(requester.secret(), &request_hash(&document, nonce)).unwrap();
+		assert_eq!(key_servers[0].key_server.document_key_shares(&signature, &document, nonce), Err(Error::InvalidNonce));
+	}
+
+	#[test]
+	fn sessions_are_completed_by_their_master_only() {
+		let key_servers = start_key_servers(6200, 2);
+		let requester = Random.generate().unwrap();
+		let document = "document".sha3();
+		let master = key_servers[0].key_server.core.self_key.public().clone();
+		let other = key_servers[1].key_server.core.self_key.public().clone();
+		let core = &key_servers[1].key_server.core;
+
+		// unknown session
+		let complete = Message::CompleteSession { document: document.clone(), threshold: 1, shares: vec![vec![], vec![]] };
+		assert_eq!(core.process_message(&master, complete.clone()), Message::Error(format!("{}", Error::UnknownSession)));
+
+		// session started by another key server
+		let (signature, nonce) = sign_request(&requester, &document);
+		let initialize = Message::InitializeSession { document: document.clone(), threshold: 1, nonce: nonce, signature: signature.into() };
+		match core.process_message(&master, initialize) {
+			Message::SessionDealing { .. } => (),
+			message => panic!("Unexpected response: {:?}", message),
+		}
+		assert_eq!(core.process_message(&other, complete.clone()), Message::Error(format!("{}", Error::UnknownSession)));
+		assert_eq!(core.process_message(&other, Message::CancelSession { document: document.clone() }), Message::Error(format!("{}", Error::UnknownSession)));
+		assert_eq!(core.process_message(&master, Message::CancelSession { document: document.clone() }), Message::Confirmation);
+		assert_eq!(core.process_message(&master, complete), Message::Error(format!("{}", Error::UnknownSession)));
+	}
+}
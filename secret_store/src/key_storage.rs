@@ -0,0 +1,208 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use util::{Mutex, Database, DatabaseConfig};
+use rlp::{self, RlpStream, Encodable, Decodable, Decoder, DecoderError, View, Stream, UntrustedRlp};
+use ethkey::{KeyPair, Secret};
+use ethcrypto::ecies;
+use types::{Error, DocumentId, ServiceConfiguration};
+
+/// Key server's share of the document key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentKeyShare {
+	/// Decryption threshold: at least `threshold + 1` shares are required to recover the key.
+	pub threshold: usize,
+	/// Share of the document key.
+	pub secret_share: Secret,
+}
+
+impl Encodable for DocumentKeyShare {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&(self.threshold as u64));
+		s.append(&self.secret_share);
+	}
+}
+
+impl Decodable for DocumentKeyShare {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let threshold: u64 = try!(d.val_at(0));
+		Ok(DocumentKeyShare {
+			threshold: threshold as usize,
+			secret_share: try!(d.val_at(1)),
+		})
+	}
+}
+
+/// Storage of the document key shares.
+pub trait KeyStorage: Send + Sync {
+	/// Insert document key share. Fails if the share of this document key is already stored.
+	fn insert(&self, document: DocumentId, key: DocumentKeyShare) -> Result<(), Error>;
+	/// Get document key share.
+	fn get(&self, document: &DocumentId) -> Result<DocumentKeyShare, Error>;
+	/// Check if the share of the document key is stored.
+	fn contains(&self, document: &DocumentId) -> bool;
+	/// Remove document key share.
+	fn remove(&self, document: &DocumentId) -> Result<(), Error>;
+}
+
+/// Key shares storage, persisted in the database.
+/// Shares are encrypted with the public key of the key server.
+pub struct PersistentKeyStorage {
+	db: Database,
+	self_key: KeyPair,
+	write_lock: Mutex<()>,
+}
+
+impl PersistentKeyStorage {
+	/// Open the key shares database at the configured path.
+	pub fn new(config: &ServiceConfiguration) -> Result<Self, Error> {
+		let self_key = try!(KeyPair::from_secret(config.self_secret.clone()));
+		let db = try!(Database::open(&DatabaseConfig::default(), &config.data_path).map_err(Error::Database));
+		Ok(PersistentKeyStorage {
+			db: db,
+			self_key: self_key,
+			write_lock: Mutex::new(()),
+		})
+	}
+}
+
+impl KeyStorage for PersistentKeyStorage {
+	fn insert(&self, document: DocumentId, key: DocumentKeyShare) -> Result<(), Error> {
+		let _guard = self.write_lock.lock();
+		if self.contains(&document) {
+			return Err(Error::DocumentAlreadyExists);
+		}
+
+		let encrypted = try!(ecies::encrypt(self.self_key.public(), &[], &rlp::encode(&key)));
+		let mut batch = self.db.transaction();
+		batch.put(None, &document, &encrypted);
+		self.db.write(batch).map_err(Error::Database)
+	}
+
+	fn get(&self, document: &DocumentId) -> Result<DocumentKeyShare, Error> {
+		let encrypted = try!(try!(self.db.get(None, document).map_err(Error::Database)).ok_or(Error::DocumentNotFound));
+		let key = try!(ecies::decrypt(self.self_key.secret(), &[], &encrypted)
+			.map_err(|e| Error::Database(format!("Unable to decrypt key share: {:?}", e))));
+		UntrustedRlp::new(&key).as_val().map_err(|e| Error::Database(format!("Corrupted key share: {:?}", e)))
+	}
+
+	fn contains(&self, document: &DocumentId) -> bool {
+		self.db.get(None, document).map(|key| key.is_some()).unwrap_or(false)
+	}
+
+	fn remove(&self, document: &DocumentId) -> Result<(), Error> {
+		let _guard = self.write_lock.lock();
+		let mut batch = self.db.transaction();
+		batch.delete(None, document);
+		self.db.write(batch).map_err(Error::Database)
+	}
+}
+
+#[cfg(test)]
+pub mod tests {
+	use std::collections::HashMap;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use devtools::RandomTempPath;
+	use util::{RwLock, Hashable};
+	use ethkey::{Random, Generator};
+	use types::{Error, DocumentId, ServiceConfiguration, NodeAddress};
+	use super::{KeyStorage, PersistentKeyStorage, DocumentKeyShare};
+
+	/// In-memory key shares storage.
+	#[derive(Default)]
+	pub struct DummyKeyStorage {
+		keys: RwLock<HashMap<DocumentId, DocumentKeyShare>>,
+		failing: AtomicBool,
+	}
+
+	impl DummyKeyStorage {
+		/// Make all subsequent inserts fail.
+		pub fn fail_inserts(&self) {
+			self.failing.store(true, Ordering::Relaxed);
+		}
+	}
+
+	impl KeyStorage for DummyKeyStorage {
+		fn insert(&self, document: DocumentId, key: DocumentKeyShare) -> Result<(), Error> {
+			if self.failing.load(Ordering::Relaxed) {
+				return Err(Error::Database("Insert has failed".into()));
+			}
+
+			let mut keys = self.keys.write();
+			if keys.contains_key(&document) {
+				return Err(Error::DocumentAlreadyExists);
+			}
+			keys.insert(document, key);
+			Ok(())
+		}
+
+		fn get(&self, document: &DocumentId) -> Result<DocumentKeyShare, Error> {
+			self.keys.read().get(document).cloned().ok_or(Error::DocumentNotFound)
+		}
+
+		fn contains(&self, document: &DocumentId) -> bool {
+			self.keys.read().contains_key(document)
+		}
+
+		fn remove(&self, document: &DocumentId) -> Result<(), Error> {
+			self.keys.write().remove(document);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn persistent_key_storage() {
+		let path = RandomTempPath::create_dir();
+		let config = ServiceConfiguration {
+			self_secret: Random.generate().unwrap().secret().clone(),
+			listener_address: NodeAddress {
+				address: "127.0.0.1".into(),
+				port: 8083,
+			},
+			nodes: Default::default(),
+			acl_contract: Default::default(),
+			data_path: path.as_str().into(),
+		};
+
+		let document1 = "document1".sha3();
+		let key1 = DocumentKeyShare {
+			threshold: 1,
+			secret_share: Random.generate().unwrap().secret().clone(),
+		};
+		let document2 = "document2".sha3();
+
+		{
+			let key_storage = PersistentKeyStorage::new(&config).unwrap();
+			key_storage.insert(document1.clone(), key1.clone()).unwrap();
+			assert_eq!(key_storage.insert(document1.clone(), key1.clone()), Err(Error::DocumentAlreadyExists));
+		}
+
+		let key_storage = PersistentKeyStorage::new(&config).unwrap();
+		assert!(key_storage.contains(&document1));
+		assert_eq!(key_storage.get(&document1), Ok(key1.clone()));
+		assert!(!key_storage.contains(&document2));
+		assert_eq!(key_storage.get(&document2), Err(Error::DocumentNotFound));
+
+		// shares are not stored in plain form
+		let stored = key_storage.db.get(None, &document1).unwrap().unwrap();
+		assert!(!stored.windows(32).any(|window| window == &*key1.secret_share));
+
+		key_storage.remove(&document1).unwrap();
+		assert!(!key_storage.contains(&document1));
+	}
+}
@@ -0,0 +1,54 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Secret store: key servers, which generate document keys by distributed key generation
+//! and release threshold shares of them to the requesters, permitted by the on-chain contract.
+
+#[macro_use]
+extern crate log;
+extern crate ethcore;
+extern crate ethcore_util as util;
+extern crate ethcrypto;
+extern crate ethkey;
+extern crate rlp;
+
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
+mod acl_storage;
+mod key_server;
+mod key_storage;
+mod math;
+mod net;
+mod traits;
+mod types;
+
+use std::sync::Arc;
+use ethcore::client::Client;
+
+pub use types::{Error, NodeId, NodeAddress, DocumentId, RequestNonce, RequestSignature, ServiceConfiguration, EncryptedDocumentKeyShare};
+pub use traits::KeyServer;
+pub use acl_storage::{AclStorage, OnChainAclStorage};
+pub use key_storage::{KeyStorage, PersistentKeyStorage, DocumentKeyShare};
+pub use key_server::{KeyServerImpl, request_hash, decrypt_document_key};
+
+/// Start new key server instance.
+pub fn start(client: Arc<Client>, config: ServiceConfiguration) -> Result<Box<KeyServer>, Error> {
+	let acl_storage = Arc::new(OnChainAclStorage::new(&client, config.acl_contract.clone()));
+	let key_storage = Arc::new(try!(PersistentKeyStorage::new(&config)));
+	let key_server = try!(KeyServerImpl::new(&config, acl_storage, key_storage));
+	Ok(Box::new(key_server))
+}
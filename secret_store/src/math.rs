@@ -0,0 +1,147 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scalar arithmetic over the secp256k1 curve order and Shamir secret sharing.
+
+use util::{U256, U512, Uint, Hashable};
+use ethkey::{Random, Generator, Secret};
+use types::NodeId;
+
+/// Order of the secp256k1 curve.
+const CURVE_ORDER: U256 = U256([0xBFD25E8CD0364141, 0xBAAEDCE6AF48A03B, 0xFFFFFFFFFFFFFFFE, 0xFFFFFFFFFFFFFFFF]);
+
+fn reduce(value: U512) -> U256 {
+	U256::from(value % U512::from(CURVE_ORDER))
+}
+
+fn mul_scalars(a: U256, b: U256) -> U256 {
+	reduce(a.full_mul(b))
+}
+
+/// Compute `(a + b) mod n`.
+pub fn add(a: &Secret, b: &Secret) -> Secret {
+	reduce(U512::from(U256::from(a)) + U512::from(U256::from(b))).into()
+}
+
+/// Compute `(a - b) mod n`.
+pub fn sub(a: &Secret, b: &Secret) -> Secret {
+	let b = reduce(U512::from(U256::from(b)));
+	reduce(U512::from(U256::from(a)) + U512::from(CURVE_ORDER) - U512::from(b)).into()
+}
+
+/// Compute `(a * b) mod n`.
+pub fn mul(a: &Secret, b: &Secret) -> Secret {
+	mul_scalars(U256::from(a), U256::from(b)).into()
+}
+
+/// Compute multiplicative inverse of `a` modulo `n`. `a` must not be zero.
+pub fn inv(a: &Secret) -> Secret {
+	// n is prime, so a^(n - 2) is the inverse of a
+	let a = reduce(U512::from(U256::from(a)));
+	assert!(!a.is_zero(), "zero has no multiplicative inverse; qed");
+	let exponent = CURVE_ORDER - U256::from(2);
+	let mut result = U256::one();
+	for i in (0..exponent.bits()).rev() {
+		result = mul_scalars(result, result);
+		if exponent.bit(i) {
+			result = mul_scalars(result, a);
+		}
+	}
+	result.into()
+}
+
+/// Generate random non-zero scalar.
+pub fn generate_random_scalar() -> Secret {
+	Random.generate().expect("random key pair generation never fails; qed").secret().clone()
+}
+
+/// Id number of the key server: the point at which key server shares are evaluated.
+pub fn node_id_number(node: &NodeId) -> Secret {
+	let id_number = reduce(U512::from(U256::from(node.sha3())));
+	match id_number.is_zero() {
+		true => U256::one().into(),
+		false => id_number.into(),
+	}
+}
+
+/// Generate random polynomial of given degree (threshold).
+pub fn generate_random_polynomial(threshold: usize) -> Vec<Secret> {
+	(0..threshold + 1).map(|_| generate_random_scalar()).collect()
+}
+
+/// Evaluate polynomial at point `x`.
+pub fn compute_polynomial(coefficients: &[Secret], x: &Secret) -> Secret {
+	coefficients.iter().rev().fold(Secret::default(), |result, coefficient| add(&mul(&result, x), coefficient))
+}
+
+/// Compute key server secret share from the shares dealt to it by every key server.
+pub fn compute_secret_share<'a, I>(dealt_shares: I) -> Secret where I: Iterator<Item=&'a Secret> {
+	dealt_shares.fold(Secret::default(), |result, share| add(&result, share))
+}
+
+/// Recover the joint secret from at least `threshold + 1` (id number, secret share) pairs.
+pub fn compute_joint_secret(shares: &[(Secret, Secret)]) -> Secret {
+	shares.iter().enumerate().fold(Secret::default(), |result, (i, &(ref id_number, ref share))| {
+		// Lagrange coefficient at zero: prod(x_j / (x_j - x_i)) for j != i
+		let coefficient = shares.iter().enumerate()
+			.filter(|&(j, _)| j != i)
+			.fold(U256::one().into(), |coefficient: Secret, (_, &(ref other_id_number, _))| {
+				mul(&coefficient, &mul(other_id_number, &inv(&sub(other_id_number, id_number))))
+			});
+		add(&result, &mul(&coefficient, share))
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{U256, Uint};
+	use ethkey::{Random, Generator, Secret};
+	use super::{add, sub, mul, inv, node_id_number, generate_random_polynomial, compute_polynomial,
+		compute_secret_share, compute_joint_secret};
+
+	#[test]
+	fn scalar_arithmetic() {
+		let a: Secret = U256::from(7).into();
+		let b: Secret = U256::from(5).into();
+		let one: Secret = U256::one().into();
+
+		assert_eq!(add(&a, &b), U256::from(12).into());
+		assert_eq!(sub(&a, &b), U256::from(2).into());
+		assert_eq!(add(&sub(&b, &a), &a), b);
+		assert_eq!(mul(&a, &b), U256::from(35).into());
+		assert_eq!(mul(&a, &inv(&a)), one);
+
+		let c = Random.generate().unwrap().secret().clone();
+		assert_eq!(mul(&c, &inv(&c)), one);
+	}
+
+	#[test]
+	fn joint_secret_is_recovered_from_threshold_shares() {
+		let threshold = 2;
+		let id_numbers: Vec<Secret> = (0..5).map(|_| node_id_number(Random.generate().unwrap().public())).collect();
+		let polynomials: Vec<Vec<Secret>> = (0..5).map(|_| generate_random_polynomial(threshold)).collect();
+		let joint_secret = compute_secret_share(polynomials.iter().map(|p| &p[0]));
+		let shares: Vec<(Secret, Secret)> = id_numbers.iter().map(|id_number| {
+			let dealt: Vec<Secret> = polynomials.iter().map(|p| compute_polynomial(p, id_number)).collect();
+			(id_number.clone(), compute_secret_share(dealt.iter()))
+		}).collect();
+
+		assert_eq!(compute_joint_secret(&shares[0..3]), joint_secret);
+		assert_eq!(compute_joint_secret(&shares[2..5]), joint_secret);
+		assert_eq!(compute_joint_secret(&shares), joint_secret);
+		assert!(compute_joint_secret(&shares[0..2]) != joint_secret);
+	}
+}
@@ -0,0 +1,407 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Authenticated protocol, used by key servers to talk to each other.
+//!
+//! Every connection starts with a handshake: both sides send their node id with a random nonce
+//! and prove possession of the node secret by signing both node ids and both nonces. Every frame,
+//! sent after the handshake, is authenticated with a MAC, keyed by the ECDH agreement of the node keys.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use std::collections::BTreeMap;
+use rlp::{RlpStream, UntrustedRlp, DecoderError, View, Stream};
+use ethkey::{KeyPair, Signature, sign, verify_public};
+use ethcrypto::ecdh;
+use util::{H256, H520, FixedHash, Hashable, Bytes};
+use types::{Error, NodeId, NodeAddress, DocumentId, RequestNonce, EncryptedDocumentKeyShare};
+
+/// Maximal size of the single frame.
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+/// Read/write timeout of the connection.
+const IO_TIMEOUT_SECS: u64 = 30;
+/// Prefix of the data signed during the handshake.
+const HANDSHAKE_PREFIX: &'static [u8] = b"secretstore-handshake";
+
+/// Share of the polynomial, dealt to the key server and encrypted with its public key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeShare {
+	/// Key server, the share is dealt to.
+	pub node: NodeId,
+	/// Encrypted share.
+	pub share: Bytes,
+}
+
+/// Message, exchanged between key servers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+	/// Start document key generation: the receiver deals shares of its random polynomial.
+	InitializeSession {
+		/// Document id.
+		document: DocumentId,
+		/// Decryption threshold.
+		threshold: usize,
+		/// Requester nonce.
+		nonce: RequestNonce,
+		/// Requester signature of the document id and the nonce.
+		signature: H520,
+	},
+	/// Polynomial shares, dealt to every key server.
+	SessionDealing {
+		/// Encrypted shares.
+		shares: Vec<NodeShare>,
+	},
+	/// Finish document key generation: the receiver combines shares, dealt to it, into its key share.
+	CompleteSession {
+		/// Document id.
+		document: DocumentId,
+		/// Decryption threshold.
+		threshold: usize,
+		/// Shares, dealt to the receiver by every key server.
+		shares: Vec<Bytes>,
+	},
+	/// Document key has been stored by every key server: the receiver forgets the session.
+	CommitSession {
+		/// Document id.
+		document: DocumentId,
+	},
+	/// Document key generation has failed: the receiver forgets the session and drops its key share.
+	CancelSession {
+		/// Document id.
+		document: DocumentId,
+	},
+	/// Request key share of the document key.
+	RequestKeyShare {
+		/// Document id.
+		document: DocumentId,
+		/// Requester nonce.
+		nonce: RequestNonce,
+		/// Requester signature of the document id and the nonce.
+		signature: H520,
+	},
+	/// Key share, encrypted with the requester public key.
+	KeyShare(EncryptedDocumentKeyShare),
+	/// Request has been processed.
+	Confirmation,
+	/// Request has failed.
+	Error(String),
+}
+
+impl Message {
+	/// RLP-encode the message.
+	pub fn encode(&self) -> Bytes {
+		let mut s = RlpStream::new();
+		match *self {
+			Message::InitializeSession { ref document, threshold, nonce, ref signature } => {
+				s.begin_list(5).append(&0u32).append(document).append(&(threshold as u64)).append(&nonce).append(signature);
+			},
+			Message::SessionDealing { ref shares } => {
+				s.begin_list(2).append(&1u32).begin_list(shares.len());
+				for share in shares {
+					s.begin_list(2).append(&share.node).append(&share.share);
+				}
+			},
+			Message::CompleteSession { ref document, threshold, ref shares } => {
+				s.begin_list(4).append(&2u32).append(document).append(&(threshold as u64)).begin_list(shares.len());
+				for share in shares {
+					s.append(share);
+				}
+			},
+			Message::CommitSession { ref document } => {
+				s.begin_list(2).append(&7u32).append(document);
+			},
+			Message::CancelSession { ref document } => {
+				s.begin_list(2).append(&8u32).append(document);
+			},
+			Message::RequestKeyShare { ref document, nonce, ref signature } => {
+				s.begin_list(4).append(&3u32).append(document).append(&nonce).append(signature);
+			},
+			Message::KeyShare(ref share) => {
+				s.begin_list(3).append(&4u32).append(&share.id_number).append(&share.encrypted_share);
+			},
+			Message::Confirmation => {
+				s.begin_list(1).append(&5u32);
+			},
+			Message::Error(ref error) => {
+				s.begin_list(2).append(&6u32).append(error);
+			},
+		}
+		s.out()
+	}
+
+	/// Decode RLP-encoded message.
+	pub fn decode(data: &[u8]) -> Result<Self, Error> {
+		let rlp = UntrustedRlp::new(data);
+		let message = match try!(rlp.val_at::<u32>(0)) {
+			0 => Message::InitializeSession {
+				document: try!(rlp.val_at(1)),
+				threshold: try!(rlp.val_at::<u64>(2)) as usize,
+				nonce: try!(rlp.val_at(3)),
+				signature: try!(rlp.val_at(4)),
+			},
+			1 => Message::SessionDealing {
+				shares: try!(try!(rlp.at(1)).iter().map(|share| -> Result<NodeShare, DecoderError> {
+					Ok(NodeShare {
+						node: try!(share.val_at(0)),
+						share: try!(share.val_at(1)),
+					})
+				}).collect::<Result<Vec<_>, DecoderError>>()),
+			},
+			2 => Message::CompleteSession {
+				document: try!(rlp.val_at(1)),
+				threshold: try!(rlp.val_at::<u64>(2)) as usize,
+				shares: try!(try!(rlp.at(3)).iter().map(|share| share.as_val()).collect::<Result<Vec<_>, DecoderError>>()),
+			},
+			3 => Message::RequestKeyShare {
+				document: try!(rlp.val_at(1)),
+				nonce: try!(rlp.val_at(2)),
+				signature: try!(rlp.val_at(3)),
+			},
+			4 => Message::KeyShare(EncryptedDocumentKeyShare {
+				id_number: try!(rlp.val_at(1)),
+				encrypted_share: try!(rlp.val_at(2)),
+			}),
+			5 => Message::Confirmation,
+			6 => Message::Error(try!(rlp.val_at(1))),
+			7 => Message::CommitSession {
+				document: try!(rlp.val_at(1)),
+			},
+			8 => Message::CancelSession {
+				document: try!(rlp.val_at(1)),
+			},
+			_ => return Err(Error::InvalidMessage),
+		};
+		Ok(message)
+	}
+}
+
+/// Authenticated connection to another key server.
+pub struct Connection {
+	stream: TcpStream,
+	node: NodeId,
+	key: H256,
+	self_nonce: H256,
+	peer_nonce: H256,
+	egress_counter: u64,
+	ingress_counter: u64,
+}
+
+impl Connection {
+	/// Connect to the key server and authenticate it.
+	pub fn connect(self_key: &KeyPair, node: &NodeId, address: &NodeAddress) -> Result<Self, Error> {
+		let stream = try!(TcpStream::connect((&*address.address, address.port)));
+		Connection::handshake(stream, self_key, |peer| peer == node)
+	}
+
+	/// Authenticate incoming connection from one of the key servers.
+	pub fn accept(stream: TcpStream, self_key: &KeyPair, nodes: &BTreeMap<NodeId, NodeAddress>) -> Result<Self, Error> {
+		Connection::handshake(stream, self_key, |peer| nodes.contains_key(peer))
+	}
+
+	fn handshake<F>(mut stream: TcpStream, self_key: &KeyPair, is_known_node: F) -> Result<Self, Error> where F: Fn(&NodeId) -> bool {
+		try!(stream.set_read_timeout(Some(Duration::from_secs(IO_TIMEOUT_SECS))));
+		try!(stream.set_write_timeout(Some(Duration::from_secs(IO_TIMEOUT_SECS))));
+
+		let self_nonce = H256::random();
+		let mut s = RlpStream::new_list(2);
+		s.append(self_key.public()).append(&self_nonce);
+		try!(write_frame(&mut stream, &s.out()));
+
+		let (node, peer_nonce): (NodeId, H256) = {
+			let frame = try!(read_frame(&mut stream));
+			let rlp = UntrustedRlp::new(&frame);
+			(try!(rlp.val_at(0)), try!(rlp.val_at(1)))
+		};
+		if !is_known_node(&node) || node == *self_key.public() {
+			return Err(Error::InvalidNode);
+		}
+
+		let proof = handshake_hash(self_key.public(), &node, &peer_nonce, &self_nonce);
+		let proof: H520 = try!(sign(self_key.secret(), &proof)).into();
+		try!(write_frame(&mut stream, &proof));
+
+		let peer_proof = try!(read_frame(&mut stream));
+		if peer_proof.len() != 65 {
+			return Err(Error::InvalidNode);
+		}
+		let peer_proof = Signature::from(H520::from_slice(&peer_proof));
+		let expected = handshake_hash(&node, self_key.public(), &self_nonce, &peer_nonce);
+		if !try!(verify_public(&node, &peer_proof, &expected).map_err(|_| Error::InvalidNode)) {
+			return Err(Error::InvalidNode);
+		}
+
+		let key = try!(ecdh::agree(self_key.secret(), &node));
+		Ok(Connection {
+			stream: stream,
+			node: node,
+			key: key,
+			self_nonce: self_nonce,
+			peer_nonce: peer_nonce,
+			egress_counter: 0,
+			ingress_counter: 0,
+		})
+	}
+
+	/// Id of the authenticated key server on the other side.
+	pub fn node(&self) -> &NodeId {
+		&self.node
+	}
+
+	/// Send message to the key server.
+	pub fn send(&mut self, message: &Message) -> Result<(), Error> {
+		let mut frame = message.encode();
+		let mac = frame_mac(&self.key, &self.self_nonce, self.egress_counter, &frame);
+		frame.extend_from_slice(&*mac);
+		self.egress_counter += 1;
+		write_frame(&mut self.stream, &frame)
+	}
+
+	/// Receive message from the key server.
+	pub fn receive(&mut self) -> Result<Message, Error> {
+		let frame = try!(read_frame(&mut self.stream));
+		if frame.len() < 32 {
+			return Err(Error::InvalidMessage);
+		}
+
+		let (payload, mac) = frame.split_at(frame.len() - 32);
+		if frame_mac(&self.key, &self.peer_nonce, self.ingress_counter, payload) != H256::from_slice(mac) {
+			return Err(Error::InvalidMessage);
+		}
+		self.ingress_counter += 1;
+		Message::decode(payload)
+	}
+}
+
+/// Data signed by the node `signer` to authenticate to the node `verifier`.
+/// Binding both ids and both nonces makes the proof useless in any other handshake.
+fn handshake_hash(signer: &NodeId, verifier: &NodeId, verifier_nonce: &H256, signer_nonce: &H256) -> H256 {
+	let mut data = Vec::with_capacity(HANDSHAKE_PREFIX.len() + 64 + 64 + 32 + 32);
+	data.extend_from_slice(HANDSHAKE_PREFIX);
+	data.extend_from_slice(&**signer);
+	data.extend_from_slice(&**verifier);
+	data.extend_from_slice(&**verifier_nonce);
+	data.extend_from_slice(&**signer_nonce);
+	data.sha3()
+}
+
+fn frame_mac(key: &H256, nonce: &H256, counter: u64, payload: &[u8]) -> H256 {
+	let mut data = Vec::with_capacity(32 + 32 + 8 + payload.len());
+	data.extend_from_slice(&**key);
+	data.extend_from_slice(&**nonce);
+	data.extend((0..8).map(|i| (counter >> (56 - 8 * i)) as u8));
+	data.extend_from_slice(payload);
+	data.sha3()
+}
+
+fn write_frame(stream: &mut TcpStream, frame: &[u8]) -> Result<(), Error> {
+	let len = frame.len();
+	let header = [(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+	try!(stream.write_all(&header));
+	try!(stream.write_all(frame));
+	Ok(())
+}
+
+fn read_frame(stream: &mut TcpStream) -> Result<Bytes, Error> {
+	let mut header = [0u8; 4];
+	try!(stream.read_exact(&mut header));
+	let len = header.iter().fold(0usize, |len, byte| (len << 8) | *byte as usize);
+	if len > MAX_FRAME_SIZE {
+		return Err(Error::InvalidMessage);
+	}
+
+	let mut frame = vec![0u8; len];
+	try!(stream.read_exact(&mut frame));
+	Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use std::net::TcpListener;
+	use std::thread;
+	use ethkey::{KeyPair, Random, Generator};
+	use util::{H256, H520, FixedHash, Hashable};
+	use types::{Error, NodeAddress, EncryptedDocumentKeyShare};
+	use super::{Connection, Message, NodeShare, handshake_hash};
+
+	#[test]
+	fn message_rlp_roundtrip() {
+		let messages = vec![
+			Message::InitializeSession { document: "document".sha3(), threshold: 1, nonce: 42, signature: H520::random() },
+			Message::SessionDealing { shares: vec![
+				NodeShare { node: Random.generate().unwrap().public().clone(), share: vec![1, 2, 3] },
+				NodeShare { node: Random.generate().unwrap().public().clone(), share: vec![4, 5] },
+			] },
+			Message::CompleteSession { document: "document".sha3(), threshold: 1, shares: vec![vec![1, 2, 3], vec![4, 5]] },
+			Message::CommitSession { document: "document".sha3() },
+			Message::CancelSession { document: "document".sha3() },
+			Message::RequestKeyShare { document: "document".sha3(), nonce: 43, signature: H520::random() },
+			Message::KeyShare(EncryptedDocumentKeyShare { id_number: "id".sha3(), encrypted_share: vec![6, 7] }),
+			Message::Confirmation,
+			Message::Error("error".into()),
+		];
+
+		for message in messages {
+			assert_eq!(Message::decode(&message.encode()), Ok(message));
+		}
+	}
+
+	#[test]
+	fn handshake_proof_is_bound_to_direction() {
+		let a = Random.generate().unwrap().public().clone();
+		let b = Random.generate().unwrap().public().clone();
+		let (a_nonce, b_nonce) = (H256::random(), H256::random());
+		assert!(handshake_hash(&a, &b, &b_nonce, &a_nonce) != handshake_hash(&b, &a, &a_nonce, &b_nonce));
+		assert!(handshake_hash(&a, &b, &b_nonce, &a_nonce) != b_nonce);
+	}
+
+	#[test]
+	fn should_authenticate_known_nodes_only() {
+		let server_key = Random.generate().unwrap();
+		let client_key = Random.generate().unwrap();
+		let unknown_key = Random.generate().unwrap();
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let address = NodeAddress {
+			address: "127.0.0.1".into(),
+			port: listener.local_addr().unwrap().port(),
+		};
+		let mut nodes = BTreeMap::new();
+		nodes.insert(server_key.public().clone(), address.clone());
+		nodes.insert(client_key.public().clone(), address.clone());
+
+		let server = {
+			let server_key = KeyPair::from_secret(server_key.secret().clone()).unwrap();
+			thread::spawn(move || {
+				let mut connection = Connection::accept(listener.accept().unwrap().0, &server_key, &nodes).unwrap();
+				let message = connection.receive().unwrap();
+				connection.send(&message).unwrap();
+				for _ in 0..2 {
+					let _ = Connection::accept(listener.accept().unwrap().0, &server_key, &nodes);
+				}
+			})
+		};
+
+		let mut connection = Connection::connect(&client_key, server_key.public(), &address).unwrap();
+		assert_eq!(connection.node(), server_key.public());
+		connection.send(&Message::Confirmation).unwrap();
+		assert_eq!(connection.receive(), Ok(Message::Confirmation));
+
+		assert!(Connection::connect(&unknown_key, server_key.public(), &address).is_err());
+		assert_eq!(Connection::connect(&client_key, unknown_key.public(), &address).err(), Some(Error::InvalidNode));
+		server.join().unwrap();
+	}
+}
@@ -0,0 +1,27 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use types::{Error, DocumentId, RequestNonce, RequestSignature, EncryptedDocumentKeyShare};
+
+/// Secret store key server.
+/// Requests are signed over `request_hash(document, nonce)` and every signature is accepted once.
+pub trait KeyServer: Send + Sync {
+	/// Generate new document key and share it among all key servers.
+	/// At least `threshold + 1` shares are required to recover the key.
+	fn generate_document_key(&self, signature: &RequestSignature, document: &DocumentId, nonce: RequestNonce, threshold: usize) -> Result<(), Error>;
+	/// Collect `threshold + 1` shares of the document key, each encrypted with the requester public key.
+	fn document_key_shares(&self, signature: &RequestSignature, document: &DocumentId, nonce: RequestNonce) -> Result<Vec<EncryptedDocumentKeyShare>, Error>;
+}
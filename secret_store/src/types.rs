@@ -0,0 +1,140 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::io;
+use std::collections::BTreeMap;
+use ethkey;
+use ethcrypto;
+use rlp::DecoderError;
+use util::{H256, Address};
+
+/// Node id: public key of the key server node.
+pub type NodeId = ethkey::Public;
+/// Document id: hash of the document.
+pub type DocumentId = H256;
+/// Requester signature of the document id.
+pub type RequestSignature = ethkey::Signature;
+/// Requester's current unix time in milliseconds, signed together with the document id.
+pub type RequestNonce = u64;
+
+/// Secret store error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+	/// Request signature is invalid.
+	BadSignature,
+	/// Request nonce is too old or the request has already been processed.
+	InvalidNonce,
+	/// Requester is not allowed to access the document key.
+	AccessDenied,
+	/// Document key with the given id already exists.
+	DocumentAlreadyExists,
+	/// Document key with the given id was not found.
+	DocumentNotFound,
+	/// Threshold is too large for the configured set of key servers.
+	InvalidThreshold,
+	/// Not enough key servers have responded.
+	ConsensusUnreachable,
+	/// Peer is not a known key server or has failed authentication.
+	InvalidNode,
+	/// Malformed message received from peer.
+	InvalidMessage,
+	/// Key generation session is unknown or was started by another key server.
+	UnknownSession,
+	/// Database error.
+	Database(String),
+	/// Network I/O error.
+	Io(String),
+	/// Internal error.
+	Internal(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::BadSignature => write!(f, "Bad request signature"),
+			Error::InvalidNonce => write!(f, "Request has expired or has already been processed"),
+			Error::AccessDenied => write!(f, "Access to the document key is denied"),
+			Error::DocumentAlreadyExists => write!(f, "Document key already exists"),
+			Error::DocumentNotFound => write!(f, "Document key not found"),
+			Error::InvalidThreshold => write!(f, "Threshold is too large for the set of key servers"),
+			Error::ConsensusUnreachable => write!(f, "Not enough key servers have responded"),
+			Error::InvalidNode => write!(f, "Unknown or unauthenticated key server"),
+			Error::InvalidMessage => write!(f, "Invalid message"),
+			Error::UnknownSession => write!(f, "Unknown key generation session"),
+			Error::Database(ref msg) => write!(f, "Database error: {}", msg),
+			Error::Io(ref msg) => write!(f, "I/O error: {}", msg),
+			Error::Internal(ref msg) => write!(f, "Internal error: {}", msg),
+		}
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(format!("{}", err))
+	}
+}
+
+impl From<ethkey::Error> for Error {
+	fn from(err: ethkey::Error) -> Self {
+		Error::Internal(format!("{}", err))
+	}
+}
+
+impl From<ethcrypto::Error> for Error {
+	fn from(err: ethcrypto::Error) -> Self {
+		Error::Internal(format!("{:?}", err))
+	}
+}
+
+impl From<DecoderError> for Error {
+	fn from(_err: DecoderError) -> Self {
+		Error::InvalidMessage
+	}
+}
+
+/// Key server node address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAddress {
+	/// IP address or host name.
+	pub address: String,
+	/// Port.
+	pub port: u16,
+}
+
+/// Secret store configuration.
+#[derive(Debug, Clone)]
+pub struct ServiceConfiguration {
+	/// Secret key of this key server node.
+	pub self_secret: ethkey::Secret,
+	/// Address to listen for other key servers on.
+	pub listener_address: NodeAddress,
+	/// All key server nodes, including this one.
+	pub nodes: BTreeMap<NodeId, NodeAddress>,
+	/// Address of the permissions contract.
+	pub acl_contract: Address,
+	/// Path to the key shares database.
+	pub data_path: String,
+}
+
+/// Share of the document key, encrypted with the requester public key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedDocumentKeyShare {
+	/// Id number of the key server which has released the share.
+	pub id_number: ethkey::Secret,
+	/// Share, encrypted with the requester public key.
+	pub encrypted_share: Vec<u8>,
+}
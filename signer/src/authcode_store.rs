@@ -50,6 +50,11 @@ const TIME_THRESHOLD: u64 = 7;
 const TOKEN_LENGTH: usize = 16;
 const INITIAL_TOKEN: &'static str = "initial";
 
+/// Returns public identifier of given authorization code.
+fn token_id(code: &str) -> String {
+	code.sha3().hex()[..16].to_owned()
+}
+
 /// Manages authorization codes for `SignerUIs`
 pub struct AuthCodes<T: TimeProvider = DefaultTimeProvider> {
 	codes: Vec<String>,
@@ -100,14 +105,20 @@ impl<T: TimeProvider> AuthCodes<T> {
 
 	/// Checks if given hash is correct identifier of `SignerUI`
 	pub fn is_valid(&mut self, hash: &H256, time: u64) -> bool {
+		self.authorize(hash, time).is_some()
+	}
+
+	/// Checks if given hash is correct identifier of `SignerUI` and returns
+	/// a public identifier of the matching code (safe to be logged).
+	pub fn authorize(&mut self, hash: &H256, time: u64) -> Option<String> {
 		let now = self.now.now();
 		// check time
 		if time >= now + TIME_THRESHOLD || time <= now - TIME_THRESHOLD {
 			warn!(target: "signer", "Received old authentication request. ({} vs {})", now, time);
-			return false;
+			return None;
 		}
 
-		let as_token = |code| format!("{}:{}", code, time).sha3();
+		let as_token = |code: &str| format!("{}:{}", code, time).sha3();
 
 		// Check if it's the initial token.
 		if self.is_empty() {
@@ -115,13 +126,15 @@ impl<T: TimeProvider> AuthCodes<T> {
 			// Initial token can be used only once.
 			if initial {
 				let _ = self.generate_new();
+				return Some(INITIAL_TOKEN.into());
 			}
-			return initial;
+			return None;
 		}
 
 		// look for code
 		self.codes.iter()
-			.any(|code| &as_token(code) == hash)
+			.find(|code| &as_token(code) == hash)
+			.map(|code| token_id(code))
 	}
 
 	/// Generates and returns a new code that can be used by `SignerUIs`
@@ -216,6 +229,20 @@ mod tests {
 		assert_eq!(res2, false);
 	}
 
+	#[test]
+	fn should_return_public_identifier_of_matching_code() {
+		// given
+		let code = "23521352asdfasdfadf";
+		let time = 99;
+		let mut codes = AuthCodes::new(vec!["1234567890123456".into(), code.into()], || 100);
+
+		// when
+		let res = codes.authorize(&generate_hash(code, time), time);
+
+		// then
+		assert_eq!(res, Some(code.sha3().hex()[..16].to_owned()));
+	}
+
 }
 
 
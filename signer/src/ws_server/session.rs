@@ -22,6 +22,7 @@ use std::path::{PathBuf, Path};
 use std::sync::Arc;
 use std::str::FromStr;
 use jsonrpc_core::IoHandler;
+use rpc::with_signer_token;
 use util::{H256, Mutex, version};
 
 #[cfg(feature = "ui")]
@@ -59,10 +60,10 @@ fn origin_is_allowed(self_origin: &str, header: Option<&[u8]>) -> bool {
 	}
 }
 
-fn auth_is_valid(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> bool {
+fn authorize(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> Option<String> {
 	match protocols {
 		Ok(ref protocols) if protocols.len() == 1 => {
-			protocols.iter().any(|protocol| {
+			protocols.iter().filter_map(|protocol| {
 				let mut split = protocol.split('_');
 				let auth = split.next().and_then(|v| H256::from_str(v).ok());
 				let time = split.next().and_then(|v| u64::from_str_radix(v, 10).ok());
@@ -70,21 +71,21 @@ fn auth_is_valid(codes_path: &Path, protocols: ws::Result<Vec<&str>>) -> bool {
 				if let (Some(auth), Some(time)) = (auth, time) {
 					// Check if the code is valid
 					AuthCodes::from_file(codes_path)
-						.map(|mut codes| {
-							let res = codes.is_valid(&auth, time);
+						.ok()
+						.and_then(|mut codes| {
+							let res = codes.authorize(&auth, time);
 							// make sure to save back authcodes - it might have been modified
 							if let Err(_) = codes.to_file(codes_path) {
 								warn!(target: "signer", "Couldn't save authorization codes to file.");
 							}
 							res
 						})
-						.unwrap_or(false)
 				} else {
-					false
+					None
 				}
-			})
+			}).next()
 		},
-		_ => false
+		_ => None
 	}
 }
 
@@ -107,6 +108,7 @@ pub struct Session {
 	self_origin: String,
 	authcodes_path: PathBuf,
 	handler: Arc<IoHandler>,
+	token: Option<String>,
 }
 
 impl ws::Handler for Session {
@@ -137,7 +139,8 @@ impl ws::Handler for Session {
 		// (styles file skips origin validation, so make sure to prevent WS connections on this resource)
 		if req.header("sec-websocket-key").is_some() && !is_styles_file {
 			// Check authorization
-			if !auth_is_valid(&self.authcodes_path, req.protocols()) {
+			self.token = authorize(&self.authcodes_path, req.protocols());
+			if self.token.is_none() {
 				info!(target: "signer", "Unauthorized connection to Signer API blocked.");
 				return Ok(error(ErrorType::Forbidden, "Not Authorized", "Request to this API was not authorized.", None));
 			}
@@ -163,7 +166,9 @@ impl ws::Handler for Session {
 
 	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
 		let req = try!(msg.as_text());
-		if let Some(async) = self.handler.handle_request(req) {
+		// Decisions made within this request are attributed to the token used to authorize the session.
+		let handler = self.handler.clone();
+		if let Some(async) = with_signer_token(self.token.clone(), || handler.handle_request(req)) {
 			let out = self.out.clone();
 			async.on_result(move |result| {
 				let res = out.lock().send(result);
@@ -204,6 +209,7 @@ impl ws::Factory for Factory {
 			skip_origin_validation: self.skip_origin_validation,
 			self_origin: self.self_origin.clone(),
 			authcodes_path: self.authcodes_path.clone(),
+			token: None,
 		}
 	}
 }
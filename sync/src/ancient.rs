@@ -0,0 +1,288 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ancient block download.
+//! After a snapshot restoration the chain starts at the first restored block.
+//! Blocks below it are downloaded backwards towards genesis, one batch at a time.
+//! Headers are checked to form a chain down from the first block, and
+//! bodies and receipts are matched against the downloaded headers.
+
+use util::*;
+use rlp::*;
+use ethcore::header::{BlockNumber, Header as BlockHeader};
+
+/// Data to request from a peer.
+#[derive(PartialEq, Eq, Debug)]
+pub enum AncientRequest {
+	/// Headers going back from the given block hash.
+	Headers(H256, usize),
+	/// Bodies of the given blocks.
+	Bodies(Vec<H256>),
+	/// Receipts of the given blocks.
+	Receipts(Vec<H256>),
+}
+
+/// Downloaded ancient block data.
+struct AncientBlock {
+	header: BlockHeader,
+	header_bytes: Bytes,
+	body: Option<Bytes>,
+	receipts: Option<Bytes>,
+}
+
+impl AncientBlock {
+	fn new(header: BlockHeader, header_bytes: Bytes) -> AncientBlock {
+		// empty bodies and receipts are known without asking.
+		let body = if header.transactions_root() == &SHA3_NULL_RLP && header.uncles_hash() == &SHA3_EMPTY_LIST_RLP {
+			let mut body = RlpStream::new_list(2);
+			body.begin_list(0);
+			body.begin_list(0);
+			Some(body.out())
+		} else {
+			None
+		};
+		let receipts = if header.receipts_root() == &SHA3_NULL_RLP { Some(RlpStream::new_list(0).out()) } else { None };
+		AncientBlock {
+			header: header,
+			header_bytes: header_bytes,
+			body: body,
+			receipts: receipts,
+		}
+	}
+
+	fn is_complete(&self) -> bool {
+		self.body.is_some() && self.receipts.is_some()
+	}
+}
+
+/// Ancient blocks downloader.
+pub struct AncientBlocks {
+	/// Hash and number of the next block to import.
+	next: Option<(H256, BlockNumber)>,
+	/// Downloaded blocks going backwards from `next`.
+	blocks: Vec<AncientBlock>,
+	/// Set if there is a request in flight.
+	downloading: bool,
+}
+
+impl AncientBlocks {
+	/// Create a new instance.
+	pub fn new() -> AncientBlocks {
+		AncientBlocks {
+			next: None,
+			blocks: Vec::new(),
+			downloading: false,
+		}
+	}
+
+	/// Clear everything.
+	pub fn clear(&mut self) {
+		self.next = None;
+		self.blocks.clear();
+		self.downloading = false;
+	}
+
+	/// Start downloading backwards from the given block, which is the parent of the first block in the chain.
+	pub fn reset_to(&mut self, hash: H256, number: BlockNumber) {
+		self.clear();
+		if number > 0 {
+			self.next = Some((hash, number));
+		}
+	}
+
+	/// Returns true if there are blocks left to download.
+	pub fn is_active(&self) -> bool {
+		self.next.is_some()
+	}
+
+	/// Number of the oldest block imported so far, if ancient blocks are being downloaded.
+	pub fn first_block_number(&self) -> Option<BlockNumber> {
+		self.next.map(|(_, n)| n + 1)
+	}
+
+	/// Find the next request to send. Only one request is in flight at a time.
+	pub fn needed(&mut self, count: usize) -> Option<AncientRequest> {
+		if self.downloading {
+			return None;
+		}
+		let request = match self.next {
+			None => None,
+			Some((hash, number)) if self.blocks.is_empty() => Some(AncientRequest::Headers(hash, min(count as BlockNumber, number) as usize)),
+			Some(_) => {
+				let bodies: Vec<_> = self.blocks.iter().filter(|b| b.body.is_none()).map(|b| b.header.hash()).take(count).collect();
+				if !bodies.is_empty() {
+					Some(AncientRequest::Bodies(bodies))
+				} else {
+					let receipts: Vec<_> = self.blocks.iter().filter(|b| b.receipts.is_none()).map(|b| b.header.hash()).take(count).collect();
+					if receipts.is_empty() { None } else { Some(AncientRequest::Receipts(receipts)) }
+				}
+			}
+		};
+		self.downloading = request.is_some();
+		request
+	}
+
+	/// Clear the in-flight request marker.
+	pub fn clear_download(&mut self) {
+		self.downloading = false;
+	}
+
+	/// Insert headers going backwards from the next block.
+	/// Fails if the headers don't form a chain down from it.
+	pub fn insert_headers(&mut self, headers: Vec<Bytes>) -> Result<(), DecoderError> {
+		let (mut expected, mut number) = match self.next {
+			Some(next) if self.blocks.is_empty() => next,
+			_ => return Ok(()),
+		};
+		let mut blocks = Vec::with_capacity(headers.len());
+		for bytes in headers {
+			let header: BlockHeader = try!(UntrustedRlp::new(&bytes).as_val());
+			if header.hash() != expected || header.number() != number {
+				trace!(target: "sync", "Unexpected ancient header {} ({}), expected {} ({})", header.number(), header.hash(), number, expected);
+				return Err(DecoderError::Custom("Unexpected ancient header"));
+			}
+			if number == 1 {
+				blocks.push(AncientBlock::new(header, bytes));
+				break;
+			}
+			expected = header.parent_hash().clone();
+			number -= 1;
+			blocks.push(AncientBlock::new(header, bytes));
+		}
+		self.blocks = blocks;
+		Ok(())
+	}
+
+	/// Insert block bodies matching downloaded headers. Returns the number of bodies inserted.
+	pub fn insert_bodies(&mut self, bodies: Vec<Bytes>) -> usize {
+		let mut inserted = 0;
+		for bytes in bodies {
+			let (tx_root, uncles) = {
+				let body = UntrustedRlp::new(&bytes);
+				match (body.at(0), body.at(1)) {
+					(Ok(tx), Ok(uncles)) => (ordered_trie_root(tx.iter().map(|r| r.as_raw().to_vec())), uncles.as_raw().sha3()),
+					_ => continue,
+				}
+			};
+			if let Some(block) = self.blocks.iter_mut().find(|b| b.body.is_none() && b.header.transactions_root() == &tx_root && b.header.uncles_hash() == &uncles) {
+				block.body = Some(bytes);
+				inserted += 1;
+			}
+		}
+		inserted
+	}
+
+	/// Insert block receipts matching downloaded headers. Returns the number of receipt lists inserted.
+	pub fn insert_receipts(&mut self, receipts: Vec<Bytes>) -> usize {
+		let mut inserted = 0;
+		for bytes in receipts {
+			let receipts_root = ordered_trie_root(UntrustedRlp::new(&bytes).iter().map(|r| r.as_raw().to_vec()));
+			if let Some(block) = self.blocks.iter_mut().find(|b| b.receipts.is_none() && b.header.receipts_root() == &receipts_root) {
+				block.receipts = Some(bytes);
+				inserted += 1;
+			}
+		}
+		inserted
+	}
+
+	/// Take out fully downloaded blocks in import order, along with their receipts.
+	pub fn drain(&mut self) -> Vec<(Bytes, Bytes)> {
+		let count = self.blocks.iter().take_while(|b| b.is_complete()).count();
+		let mut drained = Vec::with_capacity(count);
+		for block in self.blocks.drain(..count) {
+			let body = block.body.expect("only complete blocks are drained; qed");
+			let body = UntrustedRlp::new(&body);
+			let mut rlp = RlpStream::new_list(3);
+			rlp.append_raw(&block.header_bytes, 1);
+			rlp.append_raw(body.at(0).expect("body was validated on insertion; qed").as_raw(), 1);
+			rlp.append_raw(body.at(1).expect("body was validated on insertion; qed").as_raw(), 1);
+
+			self.next = match block.header.number() {
+				1 => None,
+				n => Some((block.header.parent_hash().clone(), n - 1)),
+			};
+			drained.push((rlp.out(), block.receipts.expect("only complete blocks are drained; qed")));
+		}
+		drained
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use util::*;
+	use rlp::*;
+	use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockID, BlockChainClient};
+	use ethcore::views::BlockView;
+
+	fn test_chain(count: usize) -> (TestBlockChainClient, Vec<Bytes>) {
+		let client = TestBlockChainClient::new();
+		client.add_blocks(count, EachBlockWith::Uncle);
+		let blocks = (0..count + 1).map(|i| client.block(BlockID::Number(i as u64)).unwrap()).collect();
+		(client, blocks)
+	}
+
+	#[test]
+	fn downloads_backwards() {
+		let (_, blocks) = test_chain(10);
+		let mut ancient = AncientBlocks::new();
+		let next = BlockView::new(&blocks[5]).header_view();
+		ancient.reset_to(next.sha3(), next.number());
+		assert_eq!(ancient.first_block_number(), Some(6));
+
+		assert_eq!(ancient.needed(128), Some(AncientRequest::Headers(next.sha3(), 5)));
+		assert_eq!(ancient.needed(128), None);
+		let headers = (1..6).rev().map(|i| BlockView::new(&blocks[i]).header_view().rlp().as_raw().to_vec()).collect();
+		ancient.insert_headers(headers).unwrap();
+		ancient.clear_download();
+
+		match ancient.needed(128) {
+			Some(AncientRequest::Bodies(hashes)) => assert_eq!(hashes.len(), 5),
+			other => panic!("Unexpected request {:?}", other),
+		}
+		ancient.clear_download();
+		let bodies = (1..6).map(|i| {
+			let view = BlockView::new(&blocks[i]);
+			let mut body = RlpStream::new_list(2);
+			body.append_raw(view.rlp().at(1).as_raw(), 1);
+			body.append_raw(view.rlp().at(2).as_raw(), 1);
+			body.out()
+		}).collect();
+		assert_eq!(ancient.insert_bodies(bodies), 5);
+
+		// test blocks have empty receipts.
+		assert_eq!(ancient.needed(128), None);
+		let drained = ancient.drain();
+		assert_eq!(drained.len(), 5);
+		assert_eq!(drained[0].0, blocks[5]);
+		assert_eq!(drained[4].0, blocks[1]);
+		assert!(!ancient.is_active());
+	}
+
+	#[test]
+	fn rejects_unlinked_headers() {
+		let (_, blocks) = test_chain(10);
+		let mut ancient = AncientBlocks::new();
+		let next = BlockView::new(&blocks[5]).header_view();
+		ancient.reset_to(next.sha3(), next.number());
+		ancient.needed(128);
+		let headers = vec![
+			BlockView::new(&blocks[5]).header_view().rlp().as_raw().to_vec(),
+			BlockView::new(&blocks[3]).header_view().rlp().as_raw().to_vec(),
+		];
+		assert!(ancient.insert_headers(headers).is_err());
+	}
+}
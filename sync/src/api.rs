@@ -17,14 +17,16 @@
 use std::sync::Arc;
 use std::str;
 use network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId,
-	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError};
-use util::{U256, H256};
+	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError, PacketStatsMap, NodeInfo};
+use util::{U256, H256, Hashable};
 use io::{TimerToken};
 use ethcore::client::{BlockChainClient, ChainNotify};
-use ethcore::snapshot::SnapshotService;
+use ethcore::snapshot::{SnapshotService, RestorationStatus};
 use ethcore::header::BlockNumber;
+use ethcore::spec::Checkpoint;
 use sync_io::NetSyncIo;
-use chain::{ChainSync, SyncStatus};
+use chain::{ChainSync, SyncStatus, SyncState, SyncTransition, PACKET_COUNT};
+use blocks::BlockCollectionStats;
 use std::net::{SocketAddr, AddrParseError};
 use ipc::{BinaryConvertable, BinaryConvertError, IpcConfig};
 use std::str::FromStr;
@@ -41,6 +43,10 @@ pub struct SyncConfig {
 	pub subprotocol_name: [u8; 3],
 	/// Fork block to check
 	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Trusted checkpoint. Peers whose chain does not include it are ignored.
+	pub checkpoint: Option<Checkpoint>,
+	/// Download the state of the trusted checkpoint with GetNodeData when starting from scratch and no snapshot is available.
+	pub state_sync: bool,
 }
 
 impl Default for SyncConfig {
@@ -50,44 +56,129 @@ impl Default for SyncConfig {
 			network_id: U256::from(1),
 			subprotocol_name: *b"eth",
 			fork_block: None,
+			checkpoint: None,
+			state_sync: false,
 		}
 	}
 }
 
 binary_fixed_size!(SyncConfig);
 binary_fixed_size!(SyncStatus);
+binary_fixed_size!(SyncState);
+binary_fixed_size!(SyncTransition);
+binary_fixed_size!(BlockCollectionStats);
 
 /// Current sync status
 pub trait SyncProvider: Send + Sync {
 	/// Get sync status
 	fn status(&self) -> SyncStatus;
+	/// Get detailed sync status including per-peer state
+	fn details(&self) -> SyncDetails;
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Inclusive range of block numbers
+pub struct BlockRange {
+	/// First block number
+	pub first: u64,
+	/// Last block number
+	pub last: u64,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Sync state of a connected peer
+pub struct PeerSyncInfo {
+	/// Peer id
+	pub id: usize,
+	/// eth protocol version
+	pub protocol_version: u32,
+	/// Peer best block hash
+	pub latest_hash: H256,
+	/// Peer best block number if known
+	pub latest_number: Option<u64>,
+	/// Peer total difficulty if known
+	pub difficulty: Option<U256>,
+	/// Type of data currently being requested from the peer
+	pub asking: String,
+	/// Milliseconds since the pending request was sent
+	pub asking_time: Option<u64>,
+	/// Blocks of the pending headers request, if known
+	pub asked_blocks: Option<BlockRange>,
+	/// Blocks of the last headers response
+	pub received_blocks: Option<BlockRange>,
+	/// Milliseconds it took to answer the last request
+	pub round_trip: Option<u64>,
+	/// Peer chain is confirmed to include the fork block and the checkpoint
+	pub confirmed: bool,
+	/// Pending request is expired and its result will be ignored
+	pub expired: bool,
+}
+
+#[derive(Binary, Debug, Clone)]
+/// Detailed sync status
+pub struct SyncDetails {
+	/// Overall sync status
+	pub status: SyncStatus,
+	/// Connected peers
+	pub peers: Vec<PeerSyncInfo>,
+	/// Block download statistics
+	pub blocks: BlockCollectionStats,
+	/// Snapshot restoration status
+	pub restoration: RestorationStatus,
+	/// Recent sync state changes, oldest first
+	pub history: Vec<SyncTransition>,
 }
 
 /// Ethereum network protocol handler
 pub struct EthSync {
-	/// Network service
-	network: NetworkService,
+	/// Network service, shared by all chains synced in the process
+	network: Arc<NetworkService>,
 	/// Protocol handler
 	handler: Arc<SyncProtocolHandler>,
 	/// The main subprotocol name
 	subprotocol_name: [u8; 3],
+	/// Set if other chains use the same network service
+	shared_network: bool,
 }
 
 impl EthSync {
 	/// Creates and register protocol with the network service
 	pub fn new(config: SyncConfig, chain: Arc<BlockChainClient>, snapshot_service: Arc<SnapshotService>, network_config: NetworkConfiguration) -> Result<Arc<EthSync>, NetworkError> {
-		let chain_sync = ChainSync::new(config, &*chain);
-		let service = try!(NetworkService::new(try!(network_config.into_basic())));
-		let sync = Arc::new(EthSync{
-			network: service,
-			handler: Arc::new(SyncProtocolHandler { sync: RwLock::new(chain_sync), chain: chain, snapshot_service: snapshot_service }),
-			subprotocol_name: config.subprotocol_name,
-		});
+		let mut syncs = try!(EthSync::new_multi(vec![(config, chain, snapshot_service)], network_config));
+		Ok(syncs.remove(0))
+	}
 
-		Ok(sync)
+	/// Creates sync for several chains sharing a single network service.
+	/// Each chain is served over its own subprotocol, so subprotocol names must be unique.
+	pub fn new_multi(chains: Vec<(SyncConfig, Arc<BlockChainClient>, Arc<SnapshotService>)>, network_config: NetworkConfiguration) -> Result<Vec<Arc<EthSync>>, NetworkError> {
+		let mut network_config = try!(network_config.into_basic());
+		for (i, &(ref config, ref chain, _)) in chains.iter().enumerate() {
+			if chains[..i].iter().any(|&(ref c, _, _)| c.subprotocol_name == config.subprotocol_name) {
+				return Err(NetworkError::BadProtocol);
+			}
+			network_config.discovery_topics.push(discovery_topic(&chain.chain_info().genesis_hash, &config.subprotocol_name));
+		}
+		let service = Arc::new(try!(NetworkService::new(network_config)));
+		let shared_network = chains.len() > 1;
+		Ok(chains.into_iter().map(|(config, chain, snapshot_service)| {
+			let chain_sync = ChainSync::new(config, &*chain);
+			Arc::new(EthSync {
+				network: service.clone(),
+				handler: Arc::new(SyncProtocolHandler { sync: RwLock::new(chain_sync), chain: chain, snapshot_service: snapshot_service }),
+				subprotocol_name: config.subprotocol_name,
+				shared_network: shared_network,
+			})
+		}).collect())
 	}
 }
 
+/// Discovery topic for peers serving the given chain over the given subprotocol.
+fn discovery_topic(genesis_hash: &H256, subprotocol_name: &[u8]) -> H256 {
+	let mut data = genesis_hash.to_vec();
+	data.extend_from_slice(subprotocol_name);
+	data.sha3()
+}
+
 #[derive(Ipc)]
 #[ipc(client_ident="SyncClient")]
 impl SyncProvider for EthSync {
@@ -95,6 +186,18 @@ impl SyncProvider for EthSync {
 	fn status(&self) -> SyncStatus {
 		self.handler.sync.write().status()
 	}
+
+	/// Get detailed sync status
+	fn details(&self) -> SyncDetails {
+		let sync = self.handler.sync.read();
+		SyncDetails {
+			status: sync.status(),
+			peers: sync.peer_details(),
+			blocks: sync.block_stats(),
+			restoration: self.handler.snapshot_service.status(),
+			history: sync.state_history(),
+		}
+	}
 }
 
 struct SyncProtocolHandler {
@@ -153,7 +256,7 @@ impl ChainNotify for EthSync {
 
 	fn start(&self) {
 		self.network.start().unwrap_or_else(|e| warn!("Error starting network: {:?}", e));
-		self.network.register_protocol(self.handler.clone(), self.subprotocol_name, &[62u8, 63u8, 64u8])
+		self.network.register_protocol(self.handler.clone(), self.subprotocol_name, PACKET_COUNT, &[62u8, 63u8, 64u8, 65u8, 66u8])
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
 	}
 
@@ -177,10 +280,20 @@ pub trait ManageNetwork : Send + Sync {
 	fn add_reserved_peer(&self, peer: String) -> Result<(), String>;
 	/// Start network
 	fn start_network(&self);
-	/// Stop network
-	fn stop_network(&self);
+	/// Stop network. Fails if the network is shared with other chains.
+	fn stop_network(&self) -> Result<(), String>;
 	/// Query the current configuration of the network
 	fn network_config(&self) -> NetworkConfiguration;
+	/// Get traffic statistics for connected peers
+	fn peers_traffic(&self) -> Vec<PeerTraffic>;
+	/// Get total network traffic statistics
+	fn network_traffic(&self) -> NetworkTraffic;
+	/// Get statistics of nodes in the node table
+	fn node_table(&self) -> Vec<NodeTableEntry>;
+	/// Replace the peer allow list
+	fn set_allowed_peers(&self, rules: Vec<String>) -> Result<(), String>;
+	/// Replace the peer deny list
+	fn set_denied_peers(&self, rules: Vec<String>) -> Result<(), String>;
 }
 
 
@@ -207,17 +320,148 @@ impl ManageNetwork for EthSync {
 		self.start();
 	}
 
-	fn stop_network(&self) {
+	fn stop_network(&self) -> Result<(), String> {
+		if self.shared_network {
+			return Err("The network is shared with other chains".into());
+		}
 		self.network.with_context(self.subprotocol_name, |context| {
 			let mut sync_io = NetSyncIo::new(context, &*self.handler.chain, &*self.handler.snapshot_service);
 			self.handler.sync.write().abort(&mut sync_io);
 		});
 		self.stop();
+		Ok(())
 	}
 
 	fn network_config(&self) -> NetworkConfiguration {
-		NetworkConfiguration::from(self.network.config().clone())
+		NetworkConfiguration::from(self.network.config())
 	}
+
+	fn peers_traffic(&self) -> Vec<PeerTraffic> {
+		self.network.peer_stats().into_iter().map(|p| PeerTraffic {
+			id: p.id,
+			client_version: p.client_version,
+			remote_address: p.remote_address,
+			bytes_sent: p.stats.bytes_sent,
+			bytes_received: p.stats.bytes_received,
+			packets: packet_traffic(&p.stats.packets),
+		}).collect()
+	}
+
+	fn network_traffic(&self) -> NetworkTraffic {
+		let stats = self.network.stats();
+		NetworkTraffic {
+			bytes_sent: stats.send() as u64,
+			bytes_received: stats.recv() as u64,
+			sessions: stats.sessions() as u64,
+			packets: packet_traffic(&stats.packets()),
+		}
+	}
+
+	fn node_table(&self) -> Vec<NodeTableEntry> {
+		self.network.node_table().into_iter().map(Into::into).collect()
+	}
+
+	fn set_allowed_peers(&self, rules: Vec<String>) -> Result<(), String> {
+		self.network.set_allowed_peers(rules).map_err(|e| format!("{}", e))
+	}
+
+	fn set_denied_peers(&self, rules: Vec<String>) -> Result<(), String> {
+		self.network.set_denied_peers(rules).map_err(|e| format!("{}", e))
+	}
+}
+
+fn packet_traffic(packets: &PacketStatsMap) -> Vec<PacketTraffic> {
+	packets.iter().map(|(&(protocol, packet_id), p)| PacketTraffic {
+		protocol: String::from_utf8_lossy(&protocol).into_owned(),
+		packet_id: packet_id,
+		sent_messages: p.sent_messages,
+		sent_bytes: p.sent_bytes,
+		received_messages: p.received_messages,
+		received_bytes: p.received_bytes,
+	}).collect()
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Traffic counters for a single packet type
+pub struct PacketTraffic {
+	/// Subprotocol name
+	pub protocol: String,
+	/// Packet id within the subprotocol
+	pub packet_id: u8,
+	/// Number of packets sent
+	pub sent_messages: u64,
+	/// Payload bytes sent
+	pub sent_bytes: u64,
+	/// Number of packets received
+	pub received_messages: u64,
+	/// Payload bytes received
+	pub received_bytes: u64,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Traffic statistics of a connected peer
+pub struct PeerTraffic {
+	/// Peer public key
+	pub id: Option<String>,
+	/// Peer client ID
+	pub client_version: String,
+	/// Remote address
+	pub remote_address: String,
+	/// Bytes sent to the peer
+	pub bytes_sent: u64,
+	/// Bytes received from the peer
+	pub bytes_received: u64,
+	/// Per packet counters
+	pub packets: Vec<PacketTraffic>,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Node table entry with connection history
+pub struct NodeTableEntry {
+	/// Node URL
+	pub url: String,
+	/// Total number of failures
+	pub failures: u32,
+	/// Reputation score
+	pub reputation: i32,
+	/// Last time a session with the node was active, as unix time
+	pub last_seen: Option<u64>,
+	/// Last time a session with the node was established, as unix time
+	pub last_session: Option<u64>,
+	/// Total time spent in sessions with the node, in seconds
+	pub session_time: u64,
+	/// Client version reported in the last session
+	pub client_version: Option<String>,
+	/// Times of recent failures
+	pub recent_failures: Vec<u64>,
+}
+
+impl From<NodeInfo> for NodeTableEntry {
+	fn from(n: NodeInfo) -> Self {
+		NodeTableEntry {
+			url: n.url,
+			failures: n.failures,
+			reputation: n.reputation,
+			last_seen: n.last_seen,
+			last_session: n.last_session,
+			session_time: n.session_time,
+			client_version: n.client_version,
+			recent_failures: n.recent_failures,
+		}
+	}
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Total network traffic statistics
+pub struct NetworkTraffic {
+	/// Total bytes sent
+	pub bytes_sent: u64,
+	/// Total bytes received
+	pub bytes_received: u64,
+	/// Total number of sessions created
+	pub sessions: u64,
+	/// Per packet counters
+	pub packets: Vec<PacketTraffic>,
 }
 
 #[derive(Binary, Debug, Clone, PartialEq, Eq)]
@@ -249,6 +493,18 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub allow_non_reserved: bool,
+	/// Total upload limit in bytes per second
+	pub upload_limit: Option<u64>,
+	/// Total download limit in bytes per second
+	pub download_limit: Option<u64>,
+	/// Per peer upload limit in bytes per second
+	pub peer_upload_limit: Option<u64>,
+	/// Per peer download limit in bytes per second
+	pub peer_download_limit: Option<u64>,
+	/// Peer rules. If not empty, only matching peers are accepted
+	pub allow_peers: Vec<String>,
+	/// Peer rules. Matching peers are rejected
+	pub deny_peers: Vec<String>,
 }
 
 impl NetworkConfiguration {
@@ -286,6 +542,13 @@ impl NetworkConfiguration {
 			min_peers: self.min_peers,
 			reserved_nodes: self.reserved_nodes,
 			non_reserved_mode: if self.allow_non_reserved { NonReservedPeerMode::Accept } else { NonReservedPeerMode::Deny },
+			upload_limit: self.upload_limit,
+			download_limit: self.download_limit,
+			peer_upload_limit: self.peer_upload_limit,
+			peer_download_limit: self.peer_download_limit,
+			discovery_topics: Vec::new(),
+			allow_peers: self.allow_peers,
+			deny_peers: self.deny_peers,
 		})
 	}
 }
@@ -306,6 +569,12 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			min_peers: other.min_peers,
 			reserved_nodes: other.reserved_nodes,
 			allow_non_reserved: match other.non_reserved_mode { NonReservedPeerMode::Accept => true, _ => false } ,
+			upload_limit: other.upload_limit,
+			download_limit: other.download_limit,
+			peer_upload_limit: other.peer_upload_limit,
+			peer_download_limit: other.peer_download_limit,
+			allow_peers: other.allow_peers,
+			deny_peers: other.deny_peers,
 		}
 	}
 }
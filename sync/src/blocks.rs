@@ -40,6 +40,21 @@ struct HeaderId {
 	uncles: H256
 }
 
+/// Download statistics of a `BlockCollection`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BlockCollectionStats {
+	/// Number of subchain headers being downloaded.
+	pub downloading_headers: usize,
+	/// Number of block bodies being downloaded.
+	pub downloading_bodies: usize,
+	/// Number of downloaded blocks waiting to be imported.
+	pub queued_blocks: usize,
+	/// Number of queued blocks that also have a body.
+	pub complete_blocks: usize,
+	/// Number of subchains with headers still missing.
+	pub gaps: usize,
+}
+
 /// A collection of blocks and subchain pointers being downloaded. This keeps track of
 /// which headers/bodies need to be downloaded, which are being downloaded and also holds
 /// the downloaded blocks.
@@ -235,6 +250,17 @@ impl BlockCollection {
 			+ self.downloading_bodies.heap_size_of_children()
 	}
 
+	/// Return download statistics.
+	pub fn stats(&self) -> BlockCollectionStats {
+		BlockCollectionStats {
+			downloading_headers: self.downloading_headers.len(),
+			downloading_bodies: self.downloading_bodies.len(),
+			queued_blocks: self.blocks.len(),
+			complete_blocks: self.blocks.values().filter(|b| b.body.is_some()).count(),
+			gaps: self.heads.iter().filter(|h| self.head.map_or(true, |head| head != **h)).count(),
+		}
+	}
+
 	/// Check if given block hash is marked as being downloaded.
 	pub fn is_downloading(&self, hash: &H256) -> bool {
 		self.downloading_headers.contains(hash) || self.downloading_bodies.contains(hash)
@@ -343,7 +369,7 @@ impl BlockCollection {
 
 #[cfg(test)]
 mod test {
-	use super::BlockCollection;
+	use super::{BlockCollection, BlockCollectionStats};
 	use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockID, BlockChainClient};
 	use ethcore::views::HeaderView;
 	use ethcore::header::BlockNumber;
@@ -426,6 +452,31 @@ mod test {
 		assert!(bc.is_empty());
 	}
 
+	#[test]
+	fn reports_stats() {
+		let mut bc = BlockCollection::new();
+		assert_eq!(bc.stats(), BlockCollectionStats::default());
+		let client = TestBlockChainClient::new();
+		let nblocks = 200;
+		client.add_blocks(nblocks, EachBlockWith::Nothing);
+		let blocks: Vec<_> = (0 .. nblocks).map(|i| (&client as &BlockChainClient).block(BlockID::Number(i as BlockNumber)).unwrap()).collect();
+		let headers: Vec<_> = blocks.iter().map(|b| Rlp::new(b).at(0).as_raw().to_vec()).collect();
+		let hashes: Vec<_> = headers.iter().map(|h| HeaderView::new(h).sha3()).collect();
+		let heads: Vec<_> = hashes.iter().enumerate().filter_map(|(i, h)| if i % 20 == 0 { Some(h.clone()) } else { None }).collect();
+		bc.reset_to(heads);
+		assert_eq!(bc.stats().gaps, 10);
+
+		bc.needed_headers(6, false).unwrap();
+		assert_eq!(bc.stats().downloading_headers, 1);
+
+		bc.insert_headers(headers[0..6].to_vec());
+		let stats = bc.stats();
+		assert_eq!(stats.queued_blocks, 6);
+		// empty blocks do not need a body download
+		assert_eq!(stats.complete_blocks, 6);
+		assert_eq!(stats.downloading_bodies, 0);
+	}
+
 	#[test]
 	fn insert_headers_with_gap() {
 		let mut bc = BlockCollection::new();
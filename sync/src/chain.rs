@@ -86,6 +86,19 @@
 ///
 /// All other messages are ignored.
 ///
+/// Transaction propagation.
+/// New pending transactions are pushed in full to a random subset of sqrt(P) peers. Other peers that negotiated
+/// protocol v65 receive only the transaction hashes with `NewTransactionHashes` and request the unknown ones with
+/// `GetTransactions`. For each peer we keep a set of transactions it is known to have, so that no transaction is sent
+/// or announced twice. Requests that are not answered in time are repeated to another peer that announced the hash.
+///
+/// State download.
+/// When enabled and starting from an empty chain without a snapshot to restore, we pick a pivot block behind the best
+/// block of a peer and download its state trie with `GetNodeData`. The pivot block is then imported without execution
+/// and the regular sync continues from it. Blocks below the pivot are downloaded later as ancient blocks.
+/// With a trusted checkpoint, an empty chain starts from the checkpoint block instead: its state is downloaded the
+/// same way unless a snapshot at or after the checkpoint is available, and older blocks are downloaded as ancient blocks.
+///
 
 use util::*;
 use rlp::*;
@@ -96,12 +109,16 @@ use ethcore::header::{BlockNumber, Header as BlockHeader};
 use ethcore::client::{BlockChainClient, BlockStatus, BlockID, BlockChainInfo, BlockImportError};
 use ethcore::error::*;
 use ethcore::block::Block;
+use ethcore::transaction::SignedTransaction;
 use ethcore::snapshot::{ManifestData, RestorationStatus};
+use ethcore::spec::Checkpoint;
 use sync_io::SyncIo;
 use time;
-use super::SyncConfig;
-use blocks::BlockCollection;
+use super::{SyncConfig, PeerSyncInfo, BlockRange};
+use blocks::{BlockCollection, BlockCollectionStats};
 use snapshot::{Snapshot, ChunkType};
+use ancient::{AncientBlocks, AncientRequest};
+use state::{StateDownload, StateRequest};
 use rand::{thread_rng, Rng};
 
 known_heap_size!(0, PeerInfo);
@@ -122,7 +139,12 @@ const SUBCHAIN_SIZE: usize = 256;
 const MAX_ROUND_PARENTS: usize = 32;
 const MAX_NEW_HASHES: usize = 64;
 const MAX_TX_TO_IMPORT: usize = 512;
+const MAX_TX_TO_REQUEST: usize = 256;
+const MAX_TX_TO_SEND: usize = 256;
+const MAX_KNOWN_TRANSACTIONS: usize = 16384;
 const MAX_NEW_BLOCK_AGE: BlockNumber = 20;
+const MAX_ANCIENT_BLOCKS_TO_REQUEST: usize = 128;
+const MAX_NODE_DATA_TO_REQUEST: usize = 384;
 
 const STATUS_PACKET: u8 = 0x00;
 const NEW_BLOCK_HASHES_PACKET: u8 = 0x01;
@@ -141,12 +163,28 @@ const GET_SNAPSHOT_MANIFEST_PACKET: u8 = 0x11;
 const SNAPSHOT_MANIFEST_PACKET: u8 = 0x12;
 const GET_SNAPSHOT_DATA_PACKET: u8 = 0x13;
 const SNAPSHOT_DATA_PACKET: u8 = 0x14;
+const NEW_TRANSACTION_HASHES_PACKET: u8 = 0x15;
+const GET_TRANSACTIONS_PACKET: u8 = 0x16;
+
+/// Number of packet IDs used by all supported protocol versions.
+pub const PACKET_COUNT: u8 = 0x17;
+
+/// First protocol version supporting transaction hash announcements.
+const TRANSACTION_HASHES_PROTOCOL_VERSION: u32 = 65;
+/// First protocol version supporting incremental snapshots.
+const INCREMENTAL_SNAPSHOT_PROTOCOL_VERSION: u32 = 66;
 
 const HEADERS_TIMEOUT_SEC: f64 = 15f64;
 const BODIES_TIMEOUT_SEC: f64 = 5f64;
 const FORK_HEADER_TIMEOUT_SEC: f64 = 3f64;
 const SNAPSHOT_MANIFEST_TIMEOUT_SEC: f64 = 3f64;
 const SNAPSHOT_DATA_TIMEOUT_SEC: f64 = 10f64;
+const RECEIPTS_TIMEOUT_SEC: f64 = 10f64;
+const NODE_DATA_TIMEOUT_SEC: f64 = 10f64;
+const TRANSACTIONS_TIMEOUT_SEC: f64 = 10f64;
+/// Responses taking longer than this fraction of the request timeout are considered slow.
+const SLOW_RESPONSE_RATIO: f64 = 0.5f64;
+const MAX_STATE_HISTORY: usize = 32;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 /// Sync state
@@ -167,6 +205,8 @@ pub enum SyncState {
 	Blocks,
 	/// Downloading blocks learned from `NewHashes` packet
 	NewBlocks,
+	/// Downloading the state of a recent block with `GetNodeData`
+	StateData,
 }
 
 /// Syncing status and statistics
@@ -198,6 +238,21 @@ pub struct SyncStatus {
 	pub num_snapshot_chunks: usize,
 	/// Snapshot chunks downloaded
 	pub snapshot_chunks_done: usize,
+	/// Oldest block in the chain while ancient blocks are being downloaded.
+	pub ancient_block_number: Option<BlockNumber>,
+	/// State trie nodes downloaded
+	pub state_nodes_done: usize,
+	/// State trie nodes known to be missing
+	pub state_nodes_remaining: usize,
+}
+
+/// Sync state change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyncTransition {
+	/// State entered
+	pub state: SyncState,
+	/// Unix time of the change
+	pub timestamp: u64,
 }
 
 impl SyncStatus {
@@ -226,11 +281,35 @@ enum PeerAsking {
 	Heads,
 	SnapshotManifest,
 	SnapshotData,
+	AncientHeaders,
+	AncientBodies,
+	AncientReceipts,
+	StateHeaders,
+	StateBody,
+	StateReceipts,
+	NodeData,
+}
+
+impl PeerAsking {
+	/// Time in seconds to wait for the response.
+	#[cfg_attr(feature="dev", allow(match_same_arms))]
+	fn timeout(&self) -> Option<f64> {
+		match *self {
+			PeerAsking::BlockHeaders | PeerAsking::Heads | PeerAsking::AncientHeaders | PeerAsking::StateHeaders => Some(HEADERS_TIMEOUT_SEC),
+			PeerAsking::BlockBodies | PeerAsking::AncientBodies | PeerAsking::StateBody => Some(BODIES_TIMEOUT_SEC),
+			PeerAsking::AncientReceipts | PeerAsking::StateReceipts => Some(RECEIPTS_TIMEOUT_SEC),
+			PeerAsking::NodeData => Some(NODE_DATA_TIMEOUT_SEC),
+			PeerAsking::Nothing => None,
+			PeerAsking::ForkHeader => Some(FORK_HEADER_TIMEOUT_SEC),
+			PeerAsking::SnapshotManifest => Some(SNAPSHOT_MANIFEST_TIMEOUT_SEC),
+			PeerAsking::SnapshotData => Some(SNAPSHOT_DATA_TIMEOUT_SEC),
+		}
+	}
 }
 
 #[derive(Clone, Eq, PartialEq)]
 enum ForkConfirmation {
-	/// Fork block confirmation pending.
+	/// Fork block or checkpoint confirmation pending.
 	Unconfirmed,
 	/// Peers chain is too short to confirm the fork.
 	TooShort,
@@ -263,12 +342,20 @@ struct PeerInfo {
 	asking_snapshot_data: Option<H256>,
 	/// Request timestamp
 	ask_time: f64,
-	/// Holds a set of transactions recently sent to this peer to avoid spamming.
-	last_sent_transactions: HashSet<H256>,
+	/// Lowest and highest block number of the pending header request, if known
+	asked_blocks: Option<(BlockNumber, BlockNumber)>,
+	/// Lowest and highest block number in the last block headers response
+	received_blocks: Option<(BlockNumber, BlockNumber)>,
+	/// Time in seconds it took to answer the last request
+	round_trip: Option<f64>,
+	/// Hashes of transactions this peer is known to have: sent, received or announced.
+	known_transactions: HashSet<H256>,
 	/// Pending request is expired and result should be ignored
 	expired: bool,
 	/// Peer fork confirmation status
 	confirmation: ForkConfirmation,
+	/// Number of required blocks confirmed so far
+	confirmed_blocks: usize,
 	/// Best snapshot hash
 	snapshot_hash: Option<H256>,
 	/// Best snapshot block number
@@ -283,6 +370,17 @@ impl PeerInfo {
 	fn is_allowed(&self) -> bool {
 		self.confirmation != ForkConfirmation::Unconfirmed && !self.expired
 	}
+
+	fn supports_transaction_hashes(&self) -> bool {
+		self.protocol_version >= TRANSACTION_HASHES_PROTOCOL_VERSION
+	}
+
+	fn note_known_transactions<I>(&mut self, hashes: I) where I: IntoIterator<Item=H256> {
+		if self.known_transactions.len() > MAX_KNOWN_TRANSACTIONS {
+			self.known_transactions.clear();
+		}
+		self.known_transactions.extend(hashes);
+	}
 }
 
 /// Blockchain sync handler.
@@ -316,10 +414,24 @@ pub struct ChainSync {
 	round_parents: VecDeque<(H256, H256)>,
 	/// Network ID
 	network_id: U256,
-	/// Optional fork block to check
-	fork_block: Option<(BlockNumber, H256)>,
+	/// Blocks each peer must have in its chain: the fork block and the checkpoint
+	required_blocks: Vec<(BlockNumber, H256)>,
+	/// Optional trusted checkpoint
+	checkpoint: Option<Checkpoint>,
 	/// Snapshot downloader.
 	snapshot: Snapshot,
+	/// Downloader for blocks below the first restored block.
+	ancient: AncientBlocks,
+	/// Announced transactions requested from peers since the last new block.
+	requested_transactions: HashMap<H256, (PeerId, f64)>,
+	/// Start an empty chain from the checkpoint block when fast sync is enabled and there is no snapshot to restore.
+	checkpoint_sync: bool,
+	/// State downloader.
+	state_download: StateDownload,
+	/// Peers which answered a state request with none of the requested nodes.
+	state_unavailable: HashSet<PeerId>,
+	/// Recent state changes, oldest first.
+	state_history: VecDeque<SyncTransition>,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -328,6 +440,9 @@ impl ChainSync {
 	/// Create a new instance of syncing strategy.
 	pub fn new(config: SyncConfig, chain: &BlockChainClient) -> ChainSync {
 		let chain = chain.chain_info();
+		let required_blocks = config.fork_block.into_iter()
+			.chain(config.checkpoint.map(|c| (c.number, c.hash)).into_iter())
+			.collect();
 		ChainSync {
 			state: SyncState::Idle,
 			starting_block: chain.best_block_number,
@@ -343,8 +458,15 @@ impl ChainSync {
 			round_parents: VecDeque::new(),
 			_max_download_ahead_blocks: max(MAX_HEADERS_TO_REQUEST, config.max_download_ahead_blocks),
 			network_id: config.network_id,
-			fork_block: config.fork_block,
+			required_blocks: required_blocks,
+			checkpoint: config.checkpoint,
 			snapshot: Snapshot::new(),
+			ancient: AncientBlocks::new(),
+			requested_transactions: HashMap::new(),
+			checkpoint_sync: config.state_sync && config.checkpoint.map_or(false, |c| c.number > 0),
+			state_download: StateDownload::new(),
+			state_unavailable: HashSet::new(),
+			state_history: VecDeque::new(),
 		}
 	}
 
@@ -363,6 +485,9 @@ impl ChainSync {
 			num_active_peers: self.peers.values().filter(|p| p.is_allowed() && p.asking != PeerAsking::Nothing).count(),
 			num_snapshot_chunks: self.snapshot.total_chunks(),
 			snapshot_chunks_done: self.snapshot.done_chunks(),
+			ancient_block_number: self.ancient.first_block_number(),
+			state_nodes_done: self.state_download.downloaded(),
+			state_nodes_remaining: self.state_download.remaining(),
 			mem_used:
 				self.blocks.heap_size()
 				+ self.peers.heap_size_of_children()
@@ -370,6 +495,53 @@ impl ChainSync {
 		}
 	}
 
+	/// Returns sync details of connected peers ordered by peer id.
+	pub fn peer_details(&self) -> Vec<PeerSyncInfo> {
+		let now = time::precise_time_s();
+		let mut peers: Vec<_> = self.peers.iter().map(|(id, p)| PeerSyncInfo {
+			id: *id,
+			protocol_version: p.protocol_version,
+			latest_hash: p.latest_hash.clone(),
+			latest_number: p.latest_number,
+			difficulty: p.difficulty,
+			asking: format!("{:?}", p.asking),
+			asking_time: if p.asking == PeerAsking::Nothing { None } else { Some(millis(now - p.ask_time)) },
+			asked_blocks: p.asked_blocks.map(|(first, last)| BlockRange { first: first, last: last }),
+			received_blocks: p.received_blocks.map(|(first, last)| BlockRange { first: first, last: last }),
+			round_trip: p.round_trip.map(millis),
+			confirmed: p.confirmation == ForkConfirmation::Confirmed,
+			expired: p.expired,
+		}).collect();
+		peers.sort_by_key(|p| p.id);
+		peers
+	}
+
+	/// Returns block download statistics.
+	pub fn block_stats(&self) -> BlockCollectionStats {
+		self.blocks.stats()
+	}
+
+	/// Returns recent sync state changes, oldest first.
+	pub fn state_history(&self) -> Vec<SyncTransition> {
+		self.state_history.iter().cloned().collect()
+	}
+
+	/// Change sync state and record the change.
+	fn set_state(&mut self, state: SyncState) {
+		if self.state == state {
+			return;
+		}
+		debug!(target: "sync", "State {:?} -> {:?}", self.state, state);
+		if self.state_history.len() == MAX_STATE_HISTORY {
+			self.state_history.pop_front();
+		}
+		self.state_history.push_back(SyncTransition {
+			state: state,
+			timestamp: time::get_time().sec as u64,
+		});
+		self.state = state;
+	}
+
 	/// Abort all sync activity
 	pub fn abort(&mut self, io: &mut SyncIo) {
 		self.restart(io);
@@ -381,6 +553,8 @@ impl ChainSync {
 	fn reset(&mut self, io: &mut SyncIo) {
 		self.blocks.clear();
 		self.snapshot.clear();
+		// downloaded state is kept, the download is resumed with the next peer
+		self.state_download.clear_downloads();
 		if self.state == SyncState::SnapshotData {
 			debug!(target:"sync", "Aborting snapshot restore");
 			io.snapshot_service().abort_restore();
@@ -394,7 +568,7 @@ impl ChainSync {
 			}
 		}
 		self.syncing_difficulty = From::from(0u64);
-		self.state = SyncState::Idle;
+		self.set_state(SyncState::Idle);
 		self.active_peers = self.peers.keys().cloned().collect();
 	}
 
@@ -423,7 +597,46 @@ impl ChainSync {
 	fn start_snapshot_sync(&mut self, io: &mut SyncIo, peer_id: PeerId) {
 		self.snapshot.clear();
 		self.request_snapshot_manifest(io, peer_id);
-		self.state = SyncState::SnapshotManifest;
+		self.set_state(SyncState::SnapshotManifest);
+	}
+
+	/// Start or resume downloading the state of the checkpoint block.
+	fn start_checkpoint_sync(&mut self, io: &mut SyncIo, peer_id: PeerId, checkpoint: Checkpoint) {
+		if !self.state_download.is_active() {
+			debug!(target: "sync", "Starting from checkpoint #{} ({})", checkpoint.number, checkpoint.hash);
+			self.state_download.start(checkpoint.hash, checkpoint.total_difficulty);
+		}
+		self.set_state(SyncState::StateData);
+		self.request_state_data(io, peer_id);
+	}
+
+	/// Import the pivot block once its state is downloaded and continue with the regular sync.
+	fn complete_state_sync(&mut self, io: &mut SyncIo) {
+		if let Some((block, receipts, total_difficulty)) = self.state_download.pivot_block() {
+			match io.chain().import_pivot_block(block, receipts, total_difficulty) {
+				Ok(hash) => {
+					info!(target: "sync", "State download complete at block #{} ({})", self.state_download.pivot_number().unwrap_or(0), hash);
+				},
+				Err(e) => {
+					warn!(target: "sync", "Error importing the state sync pivot block: {:?}", e);
+					self.checkpoint_sync = false;
+				},
+			}
+		}
+		self.state_download.clear();
+		let chain = io.chain().chain_info();
+		self.last_imported_block = chain.best_block_number;
+		self.last_imported_hash = chain.best_block_hash;
+		self.set_state(SyncState::Idle);
+	}
+
+	/// Give up downloading state which no peer serves and continue with a full sync.
+	fn abandon_state_sync(&mut self, io: &mut SyncIo) {
+		info!(target: "sync", "No peer serves the requested state, falling back to full sync");
+		self.checkpoint_sync = false;
+		self.state_download.clear();
+		self.state_unavailable.clear();
+		self.complete_sync(io);
 	}
 
 	/// Restart sync after bad block has been detected. May end up re-downloading up to QUEUE_SIZE blocks
@@ -438,8 +651,9 @@ impl ChainSync {
 	/// Called by peer to report status
 	fn on_peer_status(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
 		let protocol_version: u32 = try!(r.val_at(0));
-		let peer = PeerInfo {
-			protocol_version: protocol_version,
+		let mut peer = PeerInfo {
+			// the status may claim a higher version than the one negotiated for the session
+			protocol_version: min(protocol_version, io.eth_protocol_version(peer_id) as u32),
 			network_id: try!(r.val_at(1)),
 			difficulty: Some(try!(r.val_at(2))),
 			latest_hash: try!(r.val_at(3)),
@@ -449,12 +663,16 @@ impl ChainSync {
 			asking_blocks: Vec::new(),
 			asking_hash: None,
 			ask_time: 0f64,
-			last_sent_transactions: HashSet::new(),
+			asked_blocks: None,
+			received_blocks: None,
+			round_trip: None,
+			known_transactions: HashSet::new(),
 			expired: false,
-			confirmation: if self.fork_block.is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
+			confirmation: if self.required_blocks.is_empty() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
+			confirmed_blocks: 0,
 			asking_snapshot_data: None,
-			snapshot_hash: if protocol_version == 64 { Some(try!(r.val_at(5))) } else { None },
-			snapshot_number: if protocol_version == 64 { Some(try!(r.val_at(6))) } else { None },
+			snapshot_hash: if protocol_version >= 64 { Some(try!(r.val_at(5))) } else { None },
+			snapshot_number: if protocol_version >= 64 { Some(try!(r.val_at(6))) } else { None },
 		};
 
 		trace!(target: "sync", "New peer {} (protocol: {}, network: {:?}, difficulty: {:?}, latest:{}, genesis:{})", peer_id, peer.protocol_version, peer.network_id, peer.difficulty, peer.latest_hash, peer.genesis);
@@ -479,11 +697,19 @@ impl ChainSync {
 			return Ok(());
 		}
 
+		// a chain with lower total difficulty can't include the checkpoint, such peers stay unconfirmed.
+		let below_checkpoint = match (self.checkpoint, peer.difficulty) {
+			(Some(ref checkpoint), Some(ref difficulty)) => *difficulty < checkpoint.total_difficulty,
+			_ => false,
+		};
+
 		self.peers.insert(peer_id.clone(), peer);
 		self.active_peers.insert(peer_id.clone());
 		debug!(target: "sync", "Connected {}:{}", peer_id, io.peer_info(peer_id));
-		if let Some((fork_block, _)) = self.fork_block {
-			self.request_headers_by_number(io, peer_id, fork_block, 1, 0, false, PeerAsking::ForkHeader);
+		if below_checkpoint {
+			trace!(target: "sync", "{}: Total difficulty is below the checkpoint", peer_id);
+		} else if let Some(&(number, _)) = self.required_blocks.first() {
+			self.request_headers_by_number(io, peer_id, number, 1, 0, false, PeerAsking::ForkHeader);
 		} else {
 			self.sync_peer(io, peer_id, false);
 		}
@@ -493,36 +719,58 @@ impl ChainSync {
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
 	/// Called by peer once it has new block headers during sync
 	fn on_peer_block_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
-		let confirmed = match self.peers.get_mut(&peer_id) {
+		let (confirmed, next_required) = match self.peers.get_mut(&peer_id) {
 			Some(ref mut peer) if peer.asking == PeerAsking::ForkHeader => {
 				let item_count = r.item_count();
-				if item_count == 0 || (item_count == 1 && try!(r.at(0)).as_raw().sha3() == self.fork_block.unwrap().1) {
+				let required_hash = self.required_blocks.get(peer.confirmed_blocks).map(|&(_, ref hash)| hash.clone());
+				if item_count == 0 || (item_count == 1 && Some(try!(r.at(0)).as_raw().sha3()) == required_hash) {
 					peer.asking = PeerAsking::Nothing;
-					if item_count == 0 {
+					if item_count == 0 && self.checkpoint.is_some() {
+						// peers which can't confirm the checkpoint are neither synced from nor counted.
+						trace!(target: "sync", "{}: Chain is too short to confirm the checkpoint", peer_id);
+						return Ok(());
+					} else if item_count == 0 {
 						trace!(target: "sync", "{}: Chain is too short to confirm the block", peer_id);
 						peer.confirmation = ForkConfirmation::TooShort;
+						(true, None)
 					} else {
-						trace!(target: "sync", "{}: Confirmed peer", peer_id);
-						peer.confirmation = ForkConfirmation::Confirmed;
+						peer.confirmed_blocks += 1;
+						match self.required_blocks.get(peer.confirmed_blocks) {
+							Some(&(number, _)) => (false, Some(number)),
+							None => {
+								trace!(target: "sync", "{}: Confirmed peer", peer_id);
+								peer.confirmation = ForkConfirmation::Confirmed;
+								(true, None)
+							}
+						}
 					}
-					true
 				} else {
-					trace!(target: "sync", "{}: Fork mismatch", peer_id);
+					trace!(target: "sync", "{}: Fork or checkpoint mismatch", peer_id);
 					io.disconnect_peer(peer_id);
 					return Ok(());
 				}
 			},
-			_ => false,
+			_ => (false, None),
 		};
+		if let Some(number) = next_required {
+			self.request_headers_by_number(io, peer_id, number, 1, 0, false, PeerAsking::ForkHeader);
+			return Ok(());
+		}
 		if confirmed {
 			self.sync_peer(io, peer_id, false);
 			return Ok(());
 		}
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::AncientHeaders) {
+			return self.on_peer_ancient_headers(io, peer_id, r);
+		}
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::StateHeaders) {
+			return self.on_peer_state_headers(io, peer_id, r);
+		}
 
 		self.clear_peer_download(peer_id);
 		let expected_asking = if self.state == SyncState::ChainHead { PeerAsking::Heads } else { PeerAsking::BlockHeaders };
 		let expected_hash = self.peers.get(&peer_id).and_then(|p| p.asking_hash);
-		if !self.reset_peer_asking(peer_id, expected_asking) || expected_hash.is_none() {
+		if !self.reset_peer_asking(io, peer_id, expected_asking) || expected_hash.is_none() {
 			trace!(target: "sync", "{}: Ignored unexpected headers", peer_id);
 			self.continue_sync(io);
 			return Ok(());
@@ -547,10 +795,20 @@ impl ChainSync {
 
 		let mut headers = Vec::new();
 		let mut hashes = Vec::new();
+		let mut received: Option<(BlockNumber, BlockNumber)> = None;
 		let mut valid_response = item_count == 0; //empty response is valid
 		for i in 0..item_count {
 			let info: BlockHeader = try!(r.val_at(i));
 			let number = BlockNumber::from(info.number());
+			received = Some(received.map_or((number, number), |(low, high)| (min(low, number), max(high, number))));
+			if let Some(checkpoint) = self.checkpoint {
+				if number == checkpoint.number && info.hash() != checkpoint.hash {
+					warn!(target: "sync", "Header {} ({}) from {} does not match the checkpoint", number, info.hash(), peer_id);
+					io.note_peer_behaviour(peer_id, PeerBehaviour::BadBlock);
+					io.disable_peer(peer_id);
+					return Ok(());
+				}
+			}
 			// Check if any of the headers matches the hash we requested
 			if !valid_response {
 				if let Some(expected) = expected_hash {
@@ -577,6 +835,7 @@ impl ChainSync {
 				},
 				BlockStatus::Bad => {
 					warn!(target: "sync", "Bad header {} ({}) from {}: {}, state = {:?}", number, hash, peer_id, io.peer_info(peer_id), self.state);
+					io.note_peer_behaviour(peer_id, PeerBehaviour::BadBlock);
 					io.disable_peer(peer_id);
 					return Ok(());
 				},
@@ -587,6 +846,10 @@ impl ChainSync {
 			}
 		}
 
+		if let Some(peer) = self.peers.get_mut(&peer_id) {
+			peer.received_blocks = received;
+		}
+
 		// Disable the peer for this syncing round if it gives invalid chain
 		if !valid_response {
 			trace!(target: "sync", "{} Disabled for invalid headers response", peer_id);
@@ -609,7 +872,7 @@ impl ChainSync {
 					// TODO: validate heads better. E.g. check that there is enough distance between blocks.
 					trace!(target: "sync", "Received {} subchain heads, proceeding to download", headers.len());
 					self.blocks.reset_to(hashes);
-					self.state = SyncState::Blocks;
+					self.set_state(SyncState::Blocks);
 				}
 			},
 			SyncState::Blocks | SyncState::NewBlocks | SyncState::Waiting => {
@@ -629,8 +892,14 @@ impl ChainSync {
 
 	/// Called by peer once it has new block bodies
 	fn on_peer_block_bodies(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::AncientBodies) {
+			return self.on_peer_ancient_bodies(io, peer_id, r);
+		}
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::StateBody) {
+			return self.on_peer_state_body(io, peer_id, r);
+		}
 		self.clear_peer_download(peer_id);
-		self.reset_peer_asking(peer_id, PeerAsking::BlockBodies);
+		self.reset_peer_asking(io, peer_id, PeerAsking::BlockBodies);
 		let item_count = r.item_count();
 		trace!(target: "sync", "{} -> BlockBodies ({} entries)", peer_id, item_count);
 		if item_count == 0 {
@@ -651,6 +920,8 @@ impl ChainSync {
 			if self.blocks.insert_bodies(bodies) != item_count {
 				trace!(target: "sync", "Deactivating peer for giving invalid block bodies");
 				self.deactivate_peer(io, peer_id);
+			} else {
+				io.note_peer_behaviour(peer_id, PeerBehaviour::Useful);
 			}
 			self.collect_blocks(io);
 		}
@@ -658,6 +929,179 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called by peer once it has ancient block headers
+	fn on_peer_ancient_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(io, peer_id, PeerAsking::AncientHeaders);
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> AncientHeaders ({} entries)", peer_id, item_count);
+		if item_count == 0 {
+			// peer does not have the ancient blocks, e.g. it was warp synced itself.
+			self.active_peers.remove(&peer_id);
+		} else {
+			let mut headers = Vec::with_capacity(item_count);
+			for i in 0..item_count {
+				headers.push(try!(r.at(i)).as_raw().to_vec());
+			}
+			if let Err(e) = self.ancient.insert_headers(headers) {
+				trace!(target: "sync", "{}: Invalid ancient headers: {:?}", peer_id, e);
+				io.disable_peer(peer_id);
+			}
+			self.collect_ancient_blocks(io);
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has ancient block bodies
+	fn on_peer_ancient_bodies(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(io, peer_id, PeerAsking::AncientBodies);
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> AncientBodies ({} entries)", peer_id, item_count);
+		let mut bodies = Vec::with_capacity(item_count);
+		for i in 0..item_count {
+			bodies.push(try!(r.at(i)).as_raw().to_vec());
+		}
+		if item_count == 0 || self.ancient.insert_bodies(bodies) != item_count {
+			trace!(target: "sync", "Deactivating peer for giving invalid ancient block bodies");
+			self.active_peers.remove(&peer_id);
+		}
+		self.collect_ancient_blocks(io);
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has block receipts
+	fn on_peer_block_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::StateReceipts) {
+			return self.on_peer_state_receipts(io, peer_id, r);
+		}
+		self.clear_peer_download(peer_id);
+		if !self.reset_peer_asking(io, peer_id, PeerAsking::AncientReceipts) {
+			trace!(target: "sync", "{}: Ignored unexpected receipts", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> Receipts ({} entries)", peer_id, item_count);
+		let mut receipts = Vec::with_capacity(item_count);
+		for i in 0..item_count {
+			receipts.push(try!(r.at(i)).as_raw().to_vec());
+		}
+		if item_count == 0 || self.ancient.insert_receipts(receipts) != item_count {
+			trace!(target: "sync", "Deactivating peer for giving invalid receipts");
+			self.active_peers.remove(&peer_id);
+		}
+		self.collect_ancient_blocks(io);
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has the state pivot block header
+	fn on_peer_state_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(io, peer_id, PeerAsking::StateHeaders);
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> StateHeaders ({} entries)", peer_id, item_count);
+		if item_count == 0 {
+			// peer does not know the pivot block
+			self.active_peers.remove(&peer_id);
+		} else {
+			let header = try!(r.at(0)).as_raw().to_vec();
+			let result = {
+				let chain = io.chain();
+				self.state_download.insert_header(header, |h| chain.state_data(h).is_some())
+			};
+			if let Err(e) = result {
+				trace!(target: "sync", "{}: Invalid state pivot header: {:?}", peer_id, e);
+				io.disable_peer(peer_id);
+			}
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has the state pivot block body
+	fn on_peer_state_body(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(io, peer_id, PeerAsking::StateBody);
+		trace!(target: "sync", "{} -> StateBody ({} entries)", peer_id, r.item_count());
+		if r.item_count() != 1 || !self.state_download.insert_body(try!(r.at(0)).as_raw().to_vec()) {
+			trace!(target: "sync", "Deactivating peer for giving invalid pivot block body");
+			self.active_peers.remove(&peer_id);
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has the state pivot block receipts
+	fn on_peer_state_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(io, peer_id, PeerAsking::StateReceipts);
+		trace!(target: "sync", "{} -> StateReceipts ({} entries)", peer_id, r.item_count());
+		if r.item_count() != 1 || !self.state_download.insert_receipts(try!(r.at(0)).as_raw().to_vec()) {
+			trace!(target: "sync", "Deactivating peer for giving invalid pivot block receipts");
+			self.active_peers.remove(&peer_id);
+		}
+		if self.state_download.is_complete() {
+			self.complete_state_sync(io);
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has state trie nodes
+	fn on_peer_node_data(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.state != SyncState::StateData || self.peers.get(&peer_id).map_or(true, |p| p.asking != PeerAsking::NodeData) {
+			trace!(target: "sync", "{}: Ignored unexpected node data", peer_id);
+			self.clear_peer_download(peer_id);
+			self.reset_peer_asking(io, peer_id, PeerAsking::NodeData);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> NodeData ({} entries)", peer_id, item_count);
+		let mut nodes = Vec::with_capacity(item_count);
+		for i in 0..item_count {
+			let item = try!(r.at(i));
+			nodes.push(if item.is_list() { item.as_raw().to_vec() } else { try!(item.data()).to_vec() });
+		}
+		let downloaded = self.state_download.downloaded();
+		let nodes = {
+			let chain = io.chain();
+			self.state_download.insert_nodes(nodes, |h| chain.state_data(h).is_some())
+		};
+		// nodes that were not delivered are requested again
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(io, peer_id, PeerAsking::NodeData);
+		if self.state_download.downloaded() == downloaded {
+			trace!(target: "sync", "Deactivating peer without the requested state");
+			self.active_peers.remove(&peer_id);
+			self.state_unavailable.insert(peer_id);
+			// fall back to a full sync once none of the peers has the state.
+			let no_state = {
+				let unavailable = &self.state_unavailable;
+				self.peers.iter().filter(|&(_, p)| p.can_sync()).all(|(id, _)| unavailable.contains(id))
+			};
+			if no_state {
+				self.abandon_state_sync(io);
+				self.continue_sync(io);
+				return Ok(());
+			}
+		} else {
+			io.note_peer_behaviour(peer_id, PeerBehaviour::Useful);
+			if !nodes.is_empty() {
+				io.chain().import_state_nodes(nodes);
+			}
+		}
+		if self.state_download.is_complete() {
+			self.complete_state_sync(io);
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
 	/// Called by peer once it has new block bodies
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
 	fn on_peer_new_block(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
@@ -697,6 +1141,7 @@ impl ChainSync {
 					self.last_imported_hash = header.hash();
 				}
 				trace!(target: "sync", "New block queued {:?} ({})", h, header.number());
+				io.note_peer_behaviour(peer_id, PeerBehaviour::Useful);
 			},
 			Err(BlockImportError::Block(BlockError::UnknownParent(p))) => {
 				unknown = true;
@@ -704,6 +1149,7 @@ impl ChainSync {
 			},
 			Err(e) => {
 				debug!(target: "sync", "Bad new block {:?} : {:?}", h, e);
+				io.note_peer_behaviour(peer_id, PeerBehaviour::BadBlock);
 				io.disable_peer(peer_id);
 			}
 		};
@@ -786,7 +1232,7 @@ impl ChainSync {
 		if max_height != 0 {
 			trace!(target: "sync", "Downloading blocks for new hashes");
 			self.blocks.reset_to(new_hashes);
-			self.state = SyncState::NewBlocks;
+			self.set_state(SyncState::NewBlocks);
 			self.sync_peer(io, peer_id, true);
 		}
 		Ok(())
@@ -799,7 +1245,7 @@ impl ChainSync {
 			return Ok(());
 		}
 		self.clear_peer_download(peer_id);
-		if !self.reset_peer_asking(peer_id, PeerAsking::SnapshotManifest) || self.state != SyncState::SnapshotManifest {
+		if !self.reset_peer_asking(io, peer_id, PeerAsking::SnapshotManifest) || self.state != SyncState::SnapshotManifest {
 			trace!(target: "sync", "{}: Ignored unexpected manifest", peer_id);
 			self.continue_sync(io);
 			return Ok(());
@@ -815,9 +1261,26 @@ impl ChainSync {
 			}
 			Ok(manifest) => manifest,
 		};
+		if let Some(checkpoint) = self.checkpoint {
+			if manifest.block_number < checkpoint.number || (manifest.block_number == checkpoint.number && manifest.block_hash != checkpoint.hash) {
+				trace!(target: "sync", "{}: Ignored manifest for block {} before the checkpoint", peer_id, manifest.block_number);
+				io.disconnect_peer(peer_id);
+				self.continue_sync(io);
+				return Ok(());
+			}
+		}
 		self.snapshot.reset_to(&manifest, &manifest_rlp.as_raw().sha3());
+
+		// chunks restored before an interruption don't need to be downloaded again.
+		let completed = io.snapshot_service().completed_chunks(&manifest);
+		if !completed.is_empty() {
+			debug!(target: "sync", "Resuming snapshot restoration with {} chunks already restored", completed.len());
+			self.snapshot.mark_completed(completed);
+		}
+
 		io.snapshot_service().begin_restore(manifest);
-		self.state = SyncState::SnapshotData;
+		let state = if self.snapshot.is_complete() { SyncState::SnapshotWaiting } else { SyncState::SnapshotData };
+		self.set_state(state);
 
 		// give a task to the same peer first.
 		self.sync_peer(io, peer_id, false);
@@ -833,7 +1296,7 @@ impl ChainSync {
 			return Ok(());
 		}
 		self.clear_peer_download(peer_id);
-		if !self.reset_peer_asking(peer_id, PeerAsking::SnapshotData) || self.state != SyncState::SnapshotData {
+		if !self.reset_peer_asking(io, peer_id, PeerAsking::SnapshotData) || self.state != SyncState::SnapshotData {
 			trace!(target: "sync", "{}: Ignored unexpected snapshot data", peer_id);
 			self.continue_sync(io);
 			return Ok(());
@@ -843,7 +1306,7 @@ impl ChainSync {
 		match io.snapshot_service().status() {
 			RestorationStatus::Inactive | RestorationStatus::Failed => {
 				trace!(target: "sync", "{}: Snapshot restoration aborted", peer_id);
-				self.state = SyncState::Idle;
+				self.set_state(SyncState::Idle);
 				self.snapshot.clear();
 				self.continue_sync(io);
 				return Ok(());
@@ -873,7 +1336,7 @@ impl ChainSync {
 
 		if self.snapshot.is_complete() {
 			// wait for snapshot restoration process to complete
-			self.state = SyncState::SnapshotWaiting;
+			self.set_state(SyncState::SnapshotWaiting);
 		}
 		// give a task to the same peer first.
 		self.sync_peer(io, peer_id, false);
@@ -926,13 +1389,13 @@ impl ChainSync {
 	fn complete_sync(&mut self, io: &mut SyncIo) {
 		trace!(target: "sync", "Sync complete");
 		self.reset(io);
-		self.state = SyncState::Idle;
+		self.set_state(SyncState::Idle);
 	}
 
 	/// Enter waiting state
 	fn pause_sync(&mut self) {
 		trace!(target: "sync", "Block queue full, pausing sync");
-		self.state = SyncState::Waiting;
+		self.set_state(SyncState::Waiting);
 	}
 
 	/// Find something to do for a peer. Called for a new peer or when a peer is done with its task.
@@ -964,8 +1427,13 @@ impl ChainSync {
 			match self.state {
 				SyncState::Idle => {
 					// check if we can start snapshot sync with this peer
-					if peer_snapshot_number.unwrap_or(0) > 0 && chain_info.best_block_number == 0 {
+					// snapshots taken before the checkpoint are not trusted
+					let min_snapshot_number = self.checkpoint.map_or(1, |c| max(c.number, 1));
+					if peer_snapshot_number.unwrap_or(0) >= min_snapshot_number && chain_info.best_block_number == 0 {
 						self.start_snapshot_sync(io, peer_id);
+					} else if self.checkpoint_sync && chain_info.best_block_number == 0 {
+						let checkpoint = self.checkpoint.expect("checkpoint_sync is only set with a checkpoint; qed");
+						self.start_checkpoint_sync(io, peer_id, checkpoint);
 					} else {
 						if self.last_imported_block < chain_info.best_block_number {
 							self.last_imported_block = chain_info.best_block_number;
@@ -994,14 +1462,20 @@ impl ChainSync {
 						self.request_snapshot_data(io, peer_id);
 					}
 				},
+				SyncState::StateData => {
+					self.request_state_data(io, peer_id);
+				},
 				SyncState::SnapshotManifest | //already downloading from other peer
 					SyncState::Waiting | SyncState::SnapshotWaiting => ()
 			}
+		} else if self.state == SyncState::Idle {
+			// nothing better to do with this peer, fill in the blocks below the restored snapshot.
+			self.request_ancient_blocks(io, peer_id);
 		}
 	}
 
 	fn start_sync_round(&mut self, io: &mut SyncIo) {
-		self.state = SyncState::ChainHead;
+		self.set_state(SyncState::ChainHead);
 		trace!(target: "sync", "Starting round (last imported count = {:?}, block = {:?}", self.imported_this_round, self.last_imported_block);
 		// Check if need to retract to find the common block. The problem is that the peers still return headers by hash even
 		// from the non-canonical part of the tree. So we also retract if nothing has been imported last round.
@@ -1043,7 +1517,7 @@ impl ChainSync {
 		let needed_bodies = self.blocks.needed_bodies(MAX_BODIES_TO_REQUEST, ignore_others);
 		if !needed_bodies.is_empty() {
 			replace(&mut self.peers.get_mut(&peer_id).unwrap().asking_blocks, needed_bodies.clone());
-			self.request_bodies(io, peer_id, needed_bodies);
+			self.request_bodies(io, peer_id, needed_bodies, PeerAsking::BlockBodies);
 			return;
 		}
 
@@ -1064,6 +1538,58 @@ impl ChainSync {
 		}
 	}
 
+	/// Find some ancient blocks to download for a peer.
+	fn request_ancient_blocks(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		if !self.ancient.is_active() {
+			let chain_info = io.chain().chain_info();
+			let first_hash = match chain_info.first_block_hash {
+				Some(hash) => hash,
+				None => return,
+			};
+			match io.chain().block_header(BlockID::Hash(first_hash)) {
+				Some(header) => {
+					let header = HeaderView::new(&header);
+					debug!(target: "sync", "Downloading ancient blocks below #{}", header.number());
+					self.ancient.reset_to(header.parent_hash(), header.number() - 1);
+				},
+				None => return,
+			}
+		}
+		self.clear_peer_download(peer_id);
+		match self.ancient.needed(MAX_ANCIENT_BLOCKS_TO_REQUEST) {
+			Some(AncientRequest::Headers(hash, count)) => {
+				self.request_headers_by_hash(io, peer_id, &hash, count, 0, true, PeerAsking::AncientHeaders);
+			},
+			Some(AncientRequest::Bodies(hashes)) => {
+				self.request_bodies(io, peer_id, hashes, PeerAsking::AncientBodies);
+			},
+			Some(AncientRequest::Receipts(hashes)) => {
+				self.request_receipts(io, peer_id, hashes, PeerAsking::AncientReceipts);
+			},
+			None => (),
+		}
+	}
+
+	/// Find some state data or pivot block data to download for a peer.
+	fn request_state_data(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		self.clear_peer_download(peer_id);
+		match self.state_download.needed(MAX_NODE_DATA_TO_REQUEST) {
+			Some(StateRequest::Header(hash)) => {
+				self.request_headers_by_hash(io, peer_id, &hash, 1, 0, false, PeerAsking::StateHeaders);
+			},
+			Some(StateRequest::Body(hash)) => {
+				self.request_bodies(io, peer_id, vec![hash], PeerAsking::StateBody);
+			},
+			Some(StateRequest::Receipts(hash)) => {
+				self.request_receipts(io, peer_id, vec![hash], PeerAsking::StateReceipts);
+			},
+			Some(StateRequest::NodeData(hashes)) => {
+				self.request_node_data(io, peer_id, hashes);
+			},
+			None => (),
+		}
+	}
+
 	/// Clear all blocks/headers marked as being downloaded by a peer.
 	fn clear_peer_download(&mut self, peer_id: PeerId) {
 		let peer = self.peers.get_mut(&peer_id).unwrap();
@@ -1083,6 +1609,15 @@ impl ChainSync {
 					self.snapshot.clear_chunk_download(&hash);
 				}
 			},
+			PeerAsking::AncientHeaders | PeerAsking::AncientBodies | PeerAsking::AncientReceipts => {
+				self.ancient.clear_download();
+			},
+			PeerAsking::StateHeaders | PeerAsking::StateBody | PeerAsking::StateReceipts => {
+				self.state_download.clear_pivot_download();
+			},
+			PeerAsking::NodeData => {
+				self.state_download.clear_node_download(&peer.asking_blocks);
+			},
 			_ => (),
 		}
 		peer.asking_blocks.clear();
@@ -1157,6 +1692,30 @@ impl ChainSync {
 		}
 	}
 
+	/// Imports downloaded ancient blocks into the blockchain.
+	fn collect_ancient_blocks(&mut self, io: &mut SyncIo) {
+		for (block, receipts) in self.ancient.drain() {
+			let (hash, number) = {
+				let header = BlockView::new(&block).header_view();
+				(header.sha3(), header.number())
+			};
+			match io.chain().import_ancient_block(block, receipts) {
+				Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
+					trace!(target: "sync", "Ancient block already in chain {:?}", hash);
+				},
+				Ok(_) => {
+					trace!(target: "sync", "Imported ancient block #{} {:?}", number, hash);
+				},
+				Err(e) => {
+					debug!(target: "sync", "Bad ancient block {:?} : {:?}", hash, e);
+					// start over from the chain's first block.
+					self.ancient.clear();
+					break;
+				}
+			}
+		}
+	}
+
 	/// Request headers from a peer by block hash
 	#[cfg_attr(feature="dev", allow(too_many_arguments))]
 	fn request_headers_by_hash(&mut self, sync: &mut SyncIo, peer_id: PeerId, h: &H256, count: usize, skip: usize, reverse: bool, asking: PeerAsking) {
@@ -1166,10 +1725,12 @@ impl ChainSync {
 		rlp.append(&count);
 		rlp.append(&skip);
 		rlp.append(&if reverse {1u32} else {0u32});
+		let first = sync.chain().block_header(BlockID::Hash(h.clone())).map(|header| HeaderView::new(&header).number());
 		self.send_request(sync, peer_id, asking, GET_BLOCK_HEADERS_PACKET, rlp.out());
-		self.peers.get_mut(&peer_id)
-			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed")
-			.asking_hash = Some(h.clone());
+		let peer = self.peers.get_mut(&peer_id)
+			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed");
+		peer.asking_hash = Some(h.clone());
+		peer.asked_blocks = first.and_then(|n| header_range(n, count, skip, reverse));
 	}
 
 	/// Request headers from a peer by block number
@@ -1182,6 +1743,9 @@ impl ChainSync {
 		rlp.append(&skip);
 		rlp.append(&if reverse {1u32} else {0u32});
 		self.send_request(sync, peer_id, asking, GET_BLOCK_HEADERS_PACKET, rlp.out());
+		if let Some(peer) = self.peers.get_mut(&peer_id) {
+			peer.asked_blocks = header_range(n, count, skip, reverse);
+		}
 	}
 
 	/// Request snapshot manifest from a peer.
@@ -1200,19 +1764,48 @@ impl ChainSync {
 	}
 
 	/// Request block bodies from a peer
-	fn request_bodies(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>) {
+	fn request_bodies(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>, asking: PeerAsking) {
 		let mut rlp = RlpStream::new_list(hashes.len());
 		trace!(target: "sync", "{} <- GetBlockBodies: {} entries starting from {:?}", peer_id, hashes.len(), hashes.first());
 		for h in hashes {
 			rlp.append(&h);
 		}
-		self.send_request(sync, peer_id, PeerAsking::BlockBodies, GET_BLOCK_BODIES_PACKET, rlp.out());
+		self.send_request(sync, peer_id, asking, GET_BLOCK_BODIES_PACKET, rlp.out());
+	}
+
+	/// Request block receipts from a peer
+	fn request_receipts(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>, asking: PeerAsking) {
+		let mut rlp = RlpStream::new_list(hashes.len());
+		trace!(target: "sync", "{} <- GetReceipts: {} entries starting from {:?}", peer_id, hashes.len(), hashes.first());
+		for h in hashes {
+			rlp.append(&h);
+		}
+		self.send_request(sync, peer_id, asking, GET_RECEIPTS_PACKET, rlp.out());
+	}
+
+	/// Request state trie nodes and code from a peer
+	fn request_node_data(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>) {
+		let mut rlp = RlpStream::new_list(hashes.len());
+		trace!(target: "sync", "{} <- GetNodeData: {} entries starting from {:?}", peer_id, hashes.len(), hashes.first());
+		for h in &hashes {
+			rlp.append(h);
+		}
+		self.send_request(sync, peer_id, PeerAsking::NodeData, GET_NODE_DATA_PACKET, rlp.out());
+		self.peers.get_mut(&peer_id).unwrap().asking_blocks = hashes;
 	}
 
 	/// Reset peer status after request is complete.
-	fn reset_peer_asking(&mut self, peer_id: PeerId, asking: PeerAsking) -> bool {
+	fn reset_peer_asking(&mut self, io: &mut SyncIo, peer_id: PeerId, asking: PeerAsking) -> bool {
 		let peer = self.peers.get_mut(&peer_id).unwrap();
 		peer.expired = false;
+		if let Some(timeout) = peer.asking.timeout() {
+			let round_trip = time::precise_time_s() - peer.ask_time;
+			peer.round_trip = Some(round_trip);
+			if round_trip > timeout * SLOW_RESPONSE_RATIO {
+				trace!(target:"sync", "Slow {:?} response from {}", peer.asking, peer_id);
+				io.note_peer_behaviour(peer_id, PeerBehaviour::SlowResponse);
+			}
+		}
 		if peer.asking != asking {
 			trace!(target:"sync", "Asking {:?} while expected {:?}", peer.asking, asking);
 			peer.asking = PeerAsking::Nothing;
@@ -1232,6 +1825,7 @@ impl ChainSync {
 		}
 		peer.asking = asking;
 		peer.ask_time = time::precise_time_s();
+		peer.asked_blocks = None;
 		if let Err(e) = sync.send(peer_id, packet_id, packet) {
 			debug!(target:"sync", "Error sending request: {:?}", e);
 			sync.disable_peer(peer_id);
@@ -1260,14 +1854,99 @@ impl ChainSync {
 		trace!(target: "sync", "{} -> Transactions ({} entries)", peer_id, item_count);
 		item_count = min(item_count, MAX_TX_TO_IMPORT);
 		let mut transactions = Vec::with_capacity(item_count);
+		let mut hashes = Vec::with_capacity(item_count);
 		for i in 0 .. item_count {
-			let tx = try!(r.at(i)).as_raw().to_vec();
-			transactions.push(tx);
+			let tx = try!(r.at(i));
+			match tx.as_val::<SignedTransaction>() {
+				Ok(signed) => hashes.push(signed.hash()),
+				Err(_) => {
+					trace!(target: "sync", "{} Invalid transaction {}", peer_id, i);
+					io.note_peer_behaviour(peer_id, PeerBehaviour::InvalidTransaction);
+					continue;
+				}
+			}
+			transactions.push(tx.as_raw().to_vec());
+		}
+		for hash in &hashes {
+			self.requested_transactions.remove(hash);
+		}
+		if let Some(peer) = self.peers.get_mut(&peer_id) {
+			peer.note_known_transactions(hashes);
 		}
 		io.chain().queue_transactions(transactions);
 		Ok(())
 	}
 
+	/// Called when peer announces new transaction hashes
+	fn on_peer_transaction_hashes(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		// accepting transactions once only fully synced
+		if !io.is_chain_queue_empty() {
+			return Ok(());
+		}
+		if !self.peers.get(&peer_id).map_or(false, |p| p.can_sync()) {
+			trace!(target: "sync", "{} Ignoring transaction hashes from unconfirmed/unknown peer", peer_id);
+			return Ok(());
+		}
+
+		let item_count = min(r.item_count(), MAX_TX_TO_IMPORT);
+		trace!(target: "sync", "{} -> NewTransactionHashes ({} entries)", peer_id, item_count);
+		let mut hashes = Vec::with_capacity(item_count);
+		for i in 0 .. item_count {
+			hashes.push(try!(r.val_at::<H256>(i)));
+		}
+		if let Some(peer) = self.peers.get_mut(&peer_id) {
+			peer.note_known_transactions(hashes.iter().cloned());
+		}
+
+		let unknown = hashes.into_iter()
+			.filter(|hash| !self.requested_transactions.contains_key(hash) && io.chain().pending_transaction(hash).is_none())
+			.take(MAX_TX_TO_REQUEST)
+			.collect::<Vec<_>>();
+		self.request_transactions(io, peer_id, unknown);
+		Ok(())
+	}
+
+	/// Request transactions by hash from a peer.
+	fn request_transactions(&mut self, io: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>) {
+		if hashes.is_empty() {
+			return;
+		}
+		trace!(target: "sync", "{} <- GetTransactions ({} entries)", peer_id, hashes.len());
+		let now = time::precise_time_s();
+		let mut rlp = RlpStream::new_list(hashes.len());
+		for hash in hashes {
+			rlp.append(&hash);
+			self.requested_transactions.insert(hash, (peer_id, now));
+		}
+		self.send_packet(io, peer_id, GET_TRANSACTIONS_PACKET, rlp.out());
+	}
+
+	/// Request transactions that were not delivered in time from other peers that announced them.
+	fn maintain_transaction_requests(&mut self, io: &mut SyncIo) {
+		let tick = time::precise_time_s();
+		let expired = self.requested_transactions.iter()
+			.filter(|&(_, &(_, time))| tick - time > TRANSACTIONS_TIMEOUT_SEC)
+			.map(|(hash, &(peer_id, _))| (hash.clone(), peer_id))
+			.collect::<Vec<_>>();
+		let mut requests: HashMap<PeerId, Vec<H256>> = HashMap::new();
+		for (hash, asked) in expired {
+			self.requested_transactions.remove(&hash);
+			if io.chain().pending_transaction(&hash).is_some() {
+				continue;
+			}
+			let other = self.peers.iter()
+				.filter(|&(id, p)| *id != asked && p.can_sync() && p.supports_transaction_hashes() && p.known_transactions.contains(&hash))
+				.map(|(id, _)| *id)
+				.find(|id| requests.get(id).map_or(true, |r| r.len() < MAX_TX_TO_REQUEST));
+			if let Some(peer_id) = other {
+				requests.entry(peer_id).or_insert_with(Vec::new).push(hash);
+			}
+		}
+		for (peer_id, hashes) in requests {
+			self.request_transactions(io, peer_id, hashes);
+		}
+	}
+
 	/// Send Status message
 	fn send_status(&mut self, io: &mut SyncIo, peer: PeerId) -> Result<(), NetworkError> {
 		let pv64 = io.eth_protocol_version(peer) >= 64;
@@ -1279,7 +1958,7 @@ impl ChainSync {
 		packet.append(&chain.best_block_hash);
 		packet.append(&chain.genesis_hash);
 		if pv64 {
-			let manifest = io.snapshot_service().manifest();
+			let manifest = ChainSync::snapshot_manifest_for(io, peer);
 			let block_number = manifest.as_ref().map_or(0, |m| m.block_number);
 			let manifest_hash = manifest.map_or(H256::new(), |m| m.into_rlp().sha3());
 			packet.append(&manifest_hash);
@@ -1397,6 +2076,27 @@ impl ChainSync {
 		Ok(Some((NODE_DATA_PACKET, rlp)))
 	}
 
+	fn return_transactions(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
+		let count = min(r.item_count(), MAX_TX_TO_SEND);
+		trace!(target: "sync", "{} -> GetTransactions: {} entries", peer_id, count);
+		if count == 0 {
+			debug!(target: "sync", "Empty GetTransactions request, ignoring.");
+			return Ok(None);
+		}
+		let mut transactions = Vec::with_capacity(count);
+		for i in 0..count {
+			if let Some(tx) = io.chain().pending_transaction(&try!(r.val_at::<H256>(i))) {
+				transactions.push(tx);
+			}
+		}
+		trace!(target: "sync", "{} -> GetTransactions: return {} entries", peer_id, transactions.len());
+		let mut rlp = RlpStream::new_list(transactions.len());
+		for tx in &transactions {
+			rlp.append(tx);
+		}
+		Ok(Some((TRANSACTIONS_PACKET, rlp)))
+	}
+
 	fn return_receipts(io: &SyncIo, rlp: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
 		let mut count = rlp.item_count();
 		trace!(target: "sync", "{} -> GetReceipts: {} entries", peer_id, count);
@@ -1421,6 +2121,15 @@ impl ChainSync {
 		Ok(Some((RECEIPTS_PACKET, rlp_result)))
 	}
 
+	/// Latest snapshot manifest the given peer can restore from. Incremental snapshots
+	/// are only offered to peers which negotiated a protocol version supporting them.
+	fn snapshot_manifest_for(io: &SyncIo, peer_id: PeerId) -> Option<ManifestData> {
+		match io.eth_protocol_version(peer_id) as u32 >= INCREMENTAL_SNAPSHOT_PROTOCOL_VERSION {
+			true => io.snapshot_service().manifest(),
+			false => io.snapshot_service().full_manifest(),
+		}
+	}
+
 	/// Respond to GetSnapshotManifest request
 	fn return_snapshot_manifest(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
 		let count = r.item_count();
@@ -1429,7 +2138,7 @@ impl ChainSync {
 			debug!(target: "sync", "Invalid GetSnapshotManifest request, ignoring.");
 			return Ok(None);
 		}
-		let rlp = match io.snapshot_service().manifest() {
+		let rlp = match ChainSync::snapshot_manifest_for(io, peer_id) {
 			Some(manifest) => {
 				trace!(target: "sync", "{} <- SnapshotManifest", peer_id);
 				let mut rlp = RlpStream::new_list(1);
@@ -1505,6 +2214,10 @@ impl ChainSync {
 				ChainSync::return_snapshot_data,
 				|e| format!("Error sending snapshot data: {:?}", e)),
 
+			GET_TRANSACTIONS_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+				ChainSync::return_transactions,
+				|e| format!("Error sending transactions: {:?}", e)),
+
 			_ => {
 				sync.write().on_packet(io, peer, packet_id, data);
 				Ok(())
@@ -1524,12 +2237,15 @@ impl ChainSync {
 		let result = match packet_id {
 			STATUS_PACKET => self.on_peer_status(io, peer, &rlp),
 			TRANSACTIONS_PACKET => self.on_peer_transactions(io, peer, &rlp),
+			NEW_TRANSACTION_HASHES_PACKET => self.on_peer_transaction_hashes(io, peer, &rlp),
 			BLOCK_HEADERS_PACKET => self.on_peer_block_headers(io, peer, &rlp),
 			BLOCK_BODIES_PACKET => self.on_peer_block_bodies(io, peer, &rlp),
 			NEW_BLOCK_PACKET => self.on_peer_new_block(io, peer, &rlp),
 			NEW_BLOCK_HASHES_PACKET => self.on_peer_new_hashes(io, peer, &rlp),
 			SNAPSHOT_MANIFEST_PACKET => self.on_snapshot_manifest(io, peer, &rlp),
 			SNAPSHOT_DATA_PACKET => self.on_snapshot_data(io, peer, &rlp),
+			RECEIPTS_PACKET => self.on_peer_block_receipts(io, peer, &rlp),
+			NODE_DATA_PACKET => self.on_peer_node_data(io, peer, &rlp),
 			_ => {
 				debug!(target: "sync", "Unknown packet {}", packet_id);
 				Ok(())
@@ -1540,21 +2256,14 @@ impl ChainSync {
 		})
 	}
 
-	#[cfg_attr(feature="dev", allow(match_same_arms))]
 	pub fn maintain_peers(&mut self, io: &mut SyncIo) {
 		let tick = time::precise_time_s();
 		let mut aborting = Vec::new();
 		for (peer_id, peer) in &self.peers {
-			let timeout = match peer.asking {
-				PeerAsking::BlockHeaders | PeerAsking::Heads => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
-				PeerAsking::BlockBodies => (tick - peer.ask_time) > BODIES_TIMEOUT_SEC,
-				PeerAsking::Nothing => false,
-				PeerAsking::ForkHeader => (tick - peer.ask_time) > FORK_HEADER_TIMEOUT_SEC,
-				PeerAsking::SnapshotManifest => (tick - peer.ask_time) > SNAPSHOT_MANIFEST_TIMEOUT_SEC,
-				PeerAsking::SnapshotData => (tick - peer.ask_time) > SNAPSHOT_DATA_TIMEOUT_SEC,
-			};
+			let timeout = peer.asking.timeout().map_or(false, |t| (tick - peer.ask_time) > t);
 			if timeout {
 				trace!(target:"sync", "Timeout {}", peer_id);
+				io.note_peer_behaviour(*peer_id, PeerBehaviour::Timeout);
 				io.disconnect_peer(*peer_id);
 				aborting.push(*peer_id);
 			}
@@ -1562,14 +2271,15 @@ impl ChainSync {
 		for p in aborting {
 			self.on_peer_aborting(io, p);
 		}
+		self.maintain_transaction_requests(io);
 	}
 
 	fn check_resume(&mut self, io: &mut SyncIo) {
 		if self.state == SyncState::Waiting && !io.chain().queue_info().is_full() && self.state == SyncState::Waiting {
-			self.state = SyncState::Blocks;
+			self.set_state(SyncState::Blocks);
 			self.continue_sync(io);
 		} else if self.state == SyncState::SnapshotWaiting && io.snapshot_service().status() == RestorationStatus::Inactive {
-			self.state = SyncState::Idle;
+			self.set_state(SyncState::Idle);
 			self.continue_sync(io);
 		}
 	}
@@ -1710,54 +2420,52 @@ impl ChainSync {
 			return 0;
 		}
 
-		let all_transactions_hashes = transactions.iter().map(|tx| tx.hash()).collect::<HashSet<H256>>();
-		let all_transactions_rlp = {
-			let mut packet = RlpStream::new_list(transactions.len());
-			for tx in &transactions { packet.append(tx); }
-			packet.out()
-		};
+		let transactions = transactions.into_iter().map(|tx| (tx.hash(), tx)).collect::<Vec<_>>();
 
 		// sqrt(x)/x scaled to max u32
 		let fraction = (self.peers.len() as f64).powf(-0.5).mul(u32::max_value() as f64).round() as u32;
 		let small = self.peers.len() < MIN_PEERS_PROPAGATION;
 
-		let lucky_peers = self.peers.iter_mut()
-			.filter(|_| small || ::rand::random::<u32>() < fraction)
-			.take(MAX_PEERS_PROPAGATION)
-			.filter_map(|(peer_id, mut peer_info)| {
-				// Send all transactions
-				if peer_info.last_sent_transactions.is_empty() {
-					peer_info.last_sent_transactions = all_transactions_hashes.clone();
-					return Some((*peer_id, all_transactions_rlp.clone()));
-				}
+		let mut full_peers = 0;
+		let mut packets = Vec::new();
+		for (peer_id, peer_info) in self.peers.iter_mut() {
+			// Push full transactions to a random subset of peers, announce hashes to the rest
+			let lucky = full_peers < MAX_PEERS_PROPAGATION && (small || ::rand::random::<u32>() < fraction);
+			if !lucky && !peer_info.supports_transaction_hashes() {
+				continue;
+			}
 
-				// Get hashes of all transactions to send to this peer
-				let to_send = all_transactions_hashes.difference(&peer_info.last_sent_transactions).cloned().collect::<HashSet<_>>();
-				if to_send.is_empty() {
-					return None;
-				}
+			// Transactions this peer does not know about yet
+			let to_send = transactions.iter().filter(|&&(ref hash, _)| !peer_info.known_transactions.contains(hash)).collect::<Vec<_>>();
+			if to_send.is_empty() {
+				continue;
+			}
 
-				// Construct RLP
-				let mut packet = RlpStream::new_list(to_send.len());
-				for tx in &transactions {
-					if to_send.contains(&tx.hash()) {
-						packet.append(tx);
-					}
+			let mut packet = RlpStream::new_list(to_send.len());
+			let packet_id = if lucky {
+				for &&(_, ref tx) in &to_send {
+					packet.append(tx);
 				}
-
-				peer_info.last_sent_transactions = all_transactions_hashes.clone();
-				Some((*peer_id, packet.out()))
-			})
-			.collect::<Vec<_>>();
+				full_peers += 1;
+				TRANSACTIONS_PACKET
+			} else {
+				for &&(ref hash, _) in &to_send {
+					packet.append(hash);
+				}
+				NEW_TRANSACTION_HASHES_PACKET
+			};
+			peer_info.note_known_transactions(to_send.into_iter().map(|&(ref hash, _)| hash.clone()));
+			packets.push((*peer_id, packet_id, packet.out()));
+		}
 
 		// Send RLPs
-		let sent = lucky_peers.len();
+		let sent = packets.len();
 		if sent > 0 {
-			for (peer_id, rlp) in lucky_peers.into_iter() {
-				self.send_packet(io, peer_id, TRANSACTIONS_PACKET, rlp);
+			for (peer_id, packet_id, rlp) in packets.into_iter() {
+				self.send_packet(io, peer_id, packet_id, rlp);
 			}
 
-			trace!(target: "sync", "Sent up to {} transactions to {} peers.", transactions.len(), sent);
+			trace!(target: "sync", "Sent up to {} transactions to {} peers, {} of them as hashes.", transactions.len(), sent, sent - full_peers);
 		}
 		sent
 	}
@@ -1798,17 +2506,32 @@ impl ChainSync {
 			self.restart_on_bad_block(io);
 		}
 		for peer_info in self.peers.values_mut() {
-			peer_info.last_sent_transactions.clear();
+			peer_info.known_transactions.clear();
 		}
+		self.requested_transactions.clear();
 	}
 }
 
+/// Convert seconds to whole milliseconds.
+fn millis(seconds: f64) -> u64 {
+	(seconds * 1000f64) as u64
+}
+
+/// Lowest and highest block number covered by a headers request.
+fn header_range(first: BlockNumber, count: usize, skip: usize, reverse: bool) -> Option<(BlockNumber, BlockNumber)> {
+	if count == 0 {
+		return None;
+	}
+	let span = (count as BlockNumber - 1) * (skip as BlockNumber + 1);
+	Some(if reverse { (first.saturating_sub(span), first) } else { (first, first + span) })
+}
+
 #[cfg(test)]
 mod tests {
 	use tests::helpers::*;
 	use tests::snapshot::TestSnapshotService;
 	use super::*;
-	use ::SyncConfig;
+	use ::{SyncConfig, BlockRange};
 	use util::*;
 	use rlp::*;
 	use super::{PeerInfo, PeerAsking};
@@ -1816,6 +2539,7 @@ mod tests {
 	use ethcore::header::*;
 	use ethcore::client::*;
 	use ethcore::miner::MinerService;
+	use ethcore::transaction::SignedTransaction;
 
 	fn get_dummy_block(order: u32, parent_hash: H256) -> Bytes {
 		let mut header = Header::new();
@@ -1856,6 +2580,28 @@ mod tests {
 		rlp.out()
 	}
 
+	#[test]
+	fn returns_incremental_manifest_only_to_supporting_peers() {
+		let mut client = TestBlockChainClient::new();
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new_with_incremental_snapshot(4, H256::new(), 10);
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		let manifest_number = |io: &TestIo| {
+			let (packet_id, rlp) = ChainSync::return_snapshot_manifest(io, &UntrustedRlp::new(&[0xc0]), 0).unwrap().unwrap();
+			assert_eq!(packet_id, SNAPSHOT_MANIFEST_PACKET);
+			let out = rlp.out();
+			let manifest = ManifestData::from_rlp(UntrustedRlp::new(&out).at(0).unwrap().as_raw()).unwrap();
+			(manifest.block_number, manifest.base.is_some())
+		};
+
+		io.protocol_version = 65;
+		assert_eq!(manifest_number(&io), (10, false));
+
+		io.protocol_version = 66;
+		assert_eq!(manifest_number(&io), (11, true));
+	}
+
 	#[test]
 	fn return_receipts_empty() {
 		let mut client = TestBlockChainClient::new();
@@ -2007,9 +2753,13 @@ mod tests {
 				asking_blocks: Vec::new(),
 				asking_hash: None,
 				ask_time: 0f64,
-				last_sent_transactions: HashSet::new(),
+				asked_blocks: None,
+				received_blocks: None,
+				round_trip: None,
+				known_transactions: HashSet::new(),
 				expired: false,
 				confirmation: super::ForkConfirmation::Confirmed,
+				confirmed_blocks: 0,
 				snapshot_number: None,
 				snapshot_hash: None,
 				asking_snapshot_data: None,
@@ -2190,6 +2940,122 @@ mod tests {
 		assert_eq!(0x02, io.queue[1].packet_id);
 	}
 
+	#[test]
+	fn announces_transaction_hashes_to_most_peers() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		client.insert_transaction_to_queue();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let mut peer = sync.peers.get(&0).unwrap().clone();
+		peer.protocol_version = 65;
+		for id in 0..100 {
+			sync.peers.insert(id, peer.clone());
+		}
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+		let peer_count = sync.propagate_new_transactions(&mut io);
+		// Try to propagate same transactions for the second time
+		let peer_count2 = sync.propagate_new_transactions(&mut io);
+
+		// every peer gets either the transaction or its hash, but only once
+		assert_eq!(100, peer_count);
+		assert_eq!(0, peer_count2);
+		assert_eq!(100, io.queue.len());
+		let full = io.queue.iter().filter(|p| p.packet_id == 0x02).count();
+		let hashes = io.queue.iter().filter(|p| p.packet_id == 0x15).count();
+		assert_eq!(100, full + hashes);
+		assert!(full < hashes);
+	}
+
+	#[test]
+	fn requests_unknown_announced_transactions() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		client.insert_transaction_to_queue();
+		let pending = client.pending_transactions()[0].hash();
+		let unknown = H256::random();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		sync.peers.get_mut(&0).unwrap().protocol_version = 65;
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		let mut announcement = RlpStream::new_list(2);
+		announcement.append(&pending);
+		announcement.append(&unknown);
+		let announcement = announcement.out();
+		sync.on_packet(&mut io, 0, super::NEW_TRANSACTION_HASHES_PACKET, &announcement);
+		// same announcement should not be requested again
+		sync.on_packet(&mut io, 0, super::NEW_TRANSACTION_HASHES_PACKET, &announcement);
+
+		assert_eq!(1, io.queue.len());
+		assert_eq!(0x16, io.queue[0].packet_id);
+		let request = UntrustedRlp::new(&io.queue[0].data);
+		assert_eq!(1, request.item_count());
+		assert_eq!(unknown, request.val_at::<H256>(0).unwrap());
+		// peer knows both transactions now
+		let peer = sync.peers.get(&0).unwrap();
+		assert!(peer.known_transactions.contains(&pending));
+		assert!(peer.known_transactions.contains(&unknown));
+	}
+
+	#[test]
+	fn rerequests_undelivered_transactions_from_other_peers() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let unknown = H256::random();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let mut peer = sync.peers.get(&0).unwrap().clone();
+		peer.protocol_version = 65;
+		sync.peers.insert(0, peer.clone());
+		sync.peers.insert(1, peer);
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		let mut announcement = RlpStream::new_list(1);
+		announcement.append(&unknown);
+		let announcement = announcement.out();
+		sync.on_packet(&mut io, 0, super::NEW_TRANSACTION_HASHES_PACKET, &announcement);
+		sync.on_packet(&mut io, 1, super::NEW_TRANSACTION_HASHES_PACKET, &announcement);
+		assert_eq!(1, io.queue.len());
+		assert_eq!(0, io.queue[0].recipient);
+
+		// peer 0 does not answer in time
+		sync.requested_transactions.get_mut(&unknown).unwrap().1 -= super::TRANSACTIONS_TIMEOUT_SEC + 1f64;
+		sync.maintain_peers(&mut io);
+		assert_eq!(2, io.queue.len());
+		assert_eq!(0x16, io.queue[1].packet_id);
+		assert_eq!(1, io.queue[1].recipient);
+		assert_eq!(Some(&1), sync.requested_transactions.get(&unknown).map(|&(ref peer, _)| peer));
+	}
+
+	#[test]
+	fn returns_requested_transactions() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		client.insert_transaction_to_queue();
+		client.insert_transaction_to_queue();
+		let pending = client.pending_transactions()[0].hash();
+		let sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, Some(0));
+
+		let mut request = RlpStream::new_list(2);
+		request.append(&pending);
+		request.append(&H256::random());
+		ChainSync::dispatch_packet(&RwLock::new(sync), &mut io, 0, super::GET_TRANSACTIONS_PACKET, &request.out());
+
+		assert_eq!(1, io.queue.len());
+		// TRANSACTIONS_PACKET
+		assert_eq!(0x02, io.queue[0].packet_id);
+		let response = UntrustedRlp::new(&io.queue[0].data);
+		assert_eq!(1, response.item_count());
+		assert_eq!(pending, response.val_at::<SignedTransaction>(0).unwrap().hash());
+	}
+
 	#[test]
 	fn handles_peer_new_block_malformed() {
 		let mut client = TestBlockChainClient::new();
@@ -2398,4 +3264,89 @@ mod tests {
 		assert_eq!(status.transactions_in_pending_queue, 0);
 		assert_eq!(status.transactions_in_future_queue, 0);
 	}
+
+	#[test]
+	fn state_sync_falls_back_to_full_sync() {
+		let mut client = TestBlockChainClient::new();
+		let mut sync = dummy_sync_with_peer(H256::new(), &client);
+		sync.checkpoint_sync = true;
+		sync.active_peers.insert(0);
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		sync.set_state(SyncState::StateData);
+		sync.peers.get_mut(&0).unwrap().asking = PeerAsking::NodeData;
+		sync.on_packet(&mut io, 0, super::NODE_DATA_PACKET, &RlpStream::new_list(0).out());
+
+		// the only peer doesn't have the state
+		assert!(sync.status().state != SyncState::StateData);
+		assert!(!sync.checkpoint_sync);
+	}
+
+	#[test]
+	fn checkpoint_sync_starts_from_checkpoint() {
+		let mut remote = TestBlockChainClient::new();
+		remote.add_blocks(100, EachBlockWith::Uncle);
+		let head = remote.chain_info();
+		let checkpoint = remote.block(BlockID::Number(30)).unwrap();
+		let checkpoint_hash = BlockView::new(&checkpoint).header_view().sha3();
+		let mut client = TestBlockChainClient::new();
+		let mut sync = dummy_sync_with_peer(head.best_block_hash.clone(), &client);
+		sync.checkpoint = Some(::ethcore::spec::Checkpoint { number: 30, hash: checkpoint_hash.clone(), total_difficulty: 3000.into() });
+		sync.checkpoint_sync = true;
+		sync.active_peers.insert(0);
+		sync.peers.get_mut(&0).unwrap().difficulty = Some(head.total_difficulty);
+		let mut queue = VecDeque::new();
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		sync.sync_peer(&mut io, 0, false);
+		assert_eq!(sync.status().state, SyncState::StateData);
+		assert_eq!(io.queue[0].packet_id, super::GET_BLOCK_HEADERS_PACKET);
+		let request = UntrustedRlp::new(&io.queue[0].data);
+		assert_eq!(request.val_at::<H256>(0).unwrap(), checkpoint_hash);
+		assert_eq!(request.val_at::<usize>(1).unwrap(), 1);
+
+		let mut headers = RlpStream::new_list(1);
+		headers.append_raw(BlockView::new(&checkpoint).header_view().rlp().as_raw(), 1);
+		sync.on_packet(&mut io, 0, super::BLOCK_HEADERS_PACKET, &headers.out());
+		let mut bodies = RlpStream::new_list(1);
+		bodies.begin_list(2);
+		bodies.append_raw(Rlp::new(&checkpoint).at(1).as_raw(), 1);
+		bodies.append_raw(Rlp::new(&checkpoint).at(2).as_raw(), 1);
+		sync.on_packet(&mut io, 0, super::BLOCK_BODIES_PACKET, &bodies.out());
+		let mut receipts = RlpStream::new_list(1);
+		receipts.begin_list(0);
+		sync.on_packet(&mut io, 0, super::RECEIPTS_PACKET, &receipts.out());
+
+		// the checkpoint is the first block, older ones are downloaded as ancient blocks
+		let chain_info = io.chain.chain_info();
+		assert_eq!(chain_info.best_block_hash, checkpoint_hash);
+		assert_eq!(chain_info.first_block_number, Some(30));
+		assert_eq!(chain_info.total_difficulty, U256::from(3000));
+	}
+
+	#[test]
+	fn reports_sync_details() {
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(5), &client);
+		let ss = TestSnapshotService::new();
+		let mut io = TestIo::new(&mut client, &ss, &mut queue, None);
+
+		sync.request_headers_by_number(&mut io, 0, 50, 10, 1, true, PeerAsking::BlockHeaders);
+		let peers = sync.peer_details();
+		assert_eq!(peers.len(), 1);
+		assert_eq!(peers[0].asking, "BlockHeaders");
+		assert_eq!(peers[0].asked_blocks, Some(BlockRange { first: 32, last: 50 }));
+		assert!(peers[0].asking_time.is_some());
+
+		sync.set_state(SyncState::Blocks);
+		sync.set_state(SyncState::Blocks);
+		sync.set_state(SyncState::Idle);
+		let history: Vec<_> = sync.state_history().into_iter().map(|t| t.state).collect();
+		assert_eq!(history, vec![SyncState::Blocks, SyncState::Idle]);
+	}
 }
@@ -50,6 +50,8 @@ mod chain;
 mod blocks;
 mod sync_io;
 mod snapshot;
+mod ancient;
+mod state;
 
 #[cfg(test)]
 mod tests;
@@ -60,6 +62,8 @@ mod api {
 }
 
 pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig,
-	ServiceConfiguration, NetworkConfiguration};
-pub use chain::{SyncStatus, SyncState};
-pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError};
+	ServiceConfiguration, NetworkConfiguration, PeerTraffic, PacketTraffic, NetworkTraffic, NodeTableEntry,
+	SyncDetails, PeerSyncInfo, BlockRange};
+pub use chain::{SyncStatus, SyncState, SyncTransition};
+pub use blocks::BlockCollectionStats;
+pub use network::{is_valid_node_url, saved_nodes, NonReservedPeerMode, NetworkError};
@@ -57,11 +57,21 @@ impl Snapshot {
 	/// Reset collection for a manifest RLP
 	pub fn reset_to(&mut self, manifest: &ManifestData, hash: &H256) {
 		self.clear();
-		self.pending_state_chunks = manifest.state_hashes.clone();
+		// delta chunks of incremental snapshots are downloaded like any other state chunk.
+		self.pending_state_chunks = manifest.state_hashes.iter().chain(&manifest.delta_hashes).cloned().collect();
 		self.pending_block_chunks = manifest.block_hashes.clone();
 		self.snapshot_hash = Some(hash.clone());
 	}
 
+	/// Mark chunks restored before as completed, so they are not downloaded again.
+	pub fn mark_completed(&mut self, chunks: Vec<H256>) {
+		for hash in chunks {
+			if self.pending_state_chunks.contains(&hash) || self.pending_block_chunks.contains(&hash) {
+				self.completed_chunks.insert(hash);
+			}
+		}
+	}
+
 	/// Validate chunk and mark it as downloaded
 	pub fn validate_chunk(&mut self, chunk: &[u8]) -> Result<ChunkType, ()> {
 		let hash = chunk.sha3();
@@ -144,6 +154,8 @@ mod test {
 			state_root: H256::new(),
 			block_number: 42,
 			block_hash: H256::new(),
+			delta_hashes: Vec::new(),
+			base: None,
 		};
 		let mhash = manifest.clone().into_rlp().sha3();
 		(manifest, mhash, state_chunks, block_chunks)
@@ -196,5 +208,34 @@ mod test {
 		assert!(snapshot.is_complete());
 		assert_eq!(snapshot.snapshot_hash(), Some(manifest.into_rlp().sha3()));
 	}
-}
 
+	#[test]
+	fn skips_completed_chunks() {
+		let mut snapshot = Snapshot::new();
+		let (manifest, mhash, state_chunks, _) = test_manifest();
+		snapshot.reset_to(&manifest, &mhash);
+		snapshot.mark_completed(vec![manifest.state_hashes[0].clone(), H256::random()]);
+		assert_eq!(snapshot.completed_chunks.len(), 1);
+
+		assert_eq!(snapshot.needed_chunk(), Some(manifest.state_hashes[1].clone()));
+		assert!(snapshot.validate_chunk(&state_chunks[0]).is_err());
+
+		let requested: Vec<H256> = (0..38).map(|_| snapshot.needed_chunk().unwrap()).collect();
+		assert!(!requested.contains(&manifest.state_hashes[0]));
+		assert!(snapshot.needed_chunk().is_none());
+	}
+
+	#[test]
+	fn downloads_delta_chunks() {
+		let mut snapshot = Snapshot::new();
+		let (mut manifest, _, _, _) = test_manifest();
+		let delta_chunks: Vec<Bytes> = (0..5).map(|_| H256::random().to_vec()).collect();
+		manifest.delta_hashes = delta_chunks.iter().map(|data| data.sha3()).collect();
+		manifest.base = Some(H256::random());
+		let mhash = manifest.clone().into_rlp().sha3();
+		snapshot.reset_to(&manifest, &mhash);
+
+		assert_eq!(snapshot.total_chunks(), 45);
+		assert_eq!(snapshot.validate_chunk(&delta_chunks[2]), Ok(ChunkType::State(manifest.delta_hashes[2].clone())));
+	}
+}
@@ -0,0 +1,555 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State trie download.
+//! The pivot block is the trusted checkpoint block. Its state trie is downloaded breadth-first
+//! with `GetNodeData`, starting from the state root and following branch, extension and account
+//! storage and code references. Nodes already in the state database are not requested again.
+//! A downloaded node is only handed out for writing once all of its children are stored, so the
+//! database never contains an incomplete subtrie and an interrupted download can be resumed.
+//! The pivot block body and receipts are downloaded as well so that the block can be imported
+//! once the state is complete.
+
+use util::*;
+use rlp::*;
+use ethcore::header::{BlockNumber, Header as BlockHeader};
+
+/// Data to request from a peer.
+#[derive(PartialEq, Eq, Debug)]
+pub enum StateRequest {
+	/// Header of the pivot block.
+	Header(H256),
+	/// Body of the pivot block.
+	Body(H256),
+	/// Receipts of the pivot block.
+	Receipts(H256),
+	/// Trie nodes or code with the given hashes.
+	NodeData(Vec<H256>),
+}
+
+/// Kind of a requested node.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum NodeKind {
+	/// Account trie node.
+	State,
+	/// Account storage trie node.
+	Storage,
+	/// Contract code.
+	Code,
+}
+
+/// A node which is not stored yet.
+struct Node {
+	kind: NodeKind,
+	/// Node data, once downloaded.
+	data: Option<Bytes>,
+	/// Number of children which are not stored yet.
+	missing: usize,
+	/// Nodes referencing this one.
+	parents: Vec<H256>,
+}
+
+/// Pivot block data.
+struct Pivot {
+	header: BlockHeader,
+	header_bytes: Bytes,
+	total_difficulty: U256,
+	body: Option<Bytes>,
+	receipts: Option<Bytes>,
+}
+
+/// State downloader.
+pub struct StateDownload {
+	/// Trusted pivot block hash and total difficulty.
+	target: Option<(H256, U256)>,
+	/// Pivot block, once the header is downloaded.
+	pivot: Option<Pivot>,
+	/// Set if a pivot block request is in flight.
+	pivot_downloading: bool,
+	/// Nodes to download, in breadth-first order.
+	pending: VecDeque<H256>,
+	/// Nodes being downloaded.
+	downloading: HashSet<H256>,
+	/// Nodes which are queued, being downloaded or waiting for their children.
+	nodes: HashMap<H256, Node>,
+	/// Number of nodes downloaded so far.
+	downloaded: usize,
+}
+
+impl StateDownload {
+	/// Create a new instance.
+	pub fn new() -> StateDownload {
+		StateDownload {
+			target: None,
+			pivot: None,
+			pivot_downloading: false,
+			pending: VecDeque::new(),
+			downloading: HashSet::new(),
+			nodes: HashMap::new(),
+			downloaded: 0,
+		}
+	}
+
+	/// Clear everything.
+	pub fn clear(&mut self) {
+		self.target = None;
+		self.pivot = None;
+		self.pivot_downloading = false;
+		self.pending.clear();
+		self.downloading.clear();
+		self.nodes.clear();
+		self.downloaded = 0;
+	}
+
+	/// Start the download with the given trusted block as the pivot.
+	pub fn start(&mut self, pivot: H256, total_difficulty: U256) {
+		self.clear();
+		self.target = Some((pivot, total_difficulty));
+	}
+
+	/// Returns true if a download has been started.
+	pub fn is_active(&self) -> bool {
+		self.target.is_some()
+	}
+
+	/// Returns true if the state and the pivot block are fully downloaded.
+	pub fn is_complete(&self) -> bool {
+		self.pivot.as_ref().map_or(false, |p| p.body.is_some() && p.receipts.is_some()) && self.nodes.is_empty()
+	}
+
+	/// Pivot block number, once known.
+	pub fn pivot_number(&self) -> Option<BlockNumber> {
+		self.pivot.as_ref().map(|p| p.header.number())
+	}
+
+	/// Number of nodes downloaded so far.
+	pub fn downloaded(&self) -> usize {
+		self.downloaded
+	}
+
+	/// Number of nodes known to be missing.
+	pub fn remaining(&self) -> usize {
+		self.pending.len() + self.downloading.len()
+	}
+
+	/// Find the next request to send. Pivot block requests are sent one at a time,
+	/// while node data can be requested from several peers at once.
+	pub fn needed(&mut self, count: usize) -> Option<StateRequest> {
+		if self.pivot_downloading {
+			return None;
+		}
+		let request = match self.pivot {
+			None => self.target.map(|(hash, _)| StateRequest::Header(hash)),
+			Some(ref pivot) if pivot.body.is_none() => Some(StateRequest::Body(pivot.header.hash())),
+			Some(ref pivot) if pivot.receipts.is_none() => Some(StateRequest::Receipts(pivot.header.hash())),
+			Some(_) => {
+				let mut hashes = Vec::new();
+				while hashes.len() < count {
+					match self.pending.pop_front() {
+						Some(hash) => {
+							self.downloading.insert(hash.clone());
+							hashes.push(hash);
+						},
+						None => break,
+					}
+				}
+				return if hashes.is_empty() { None } else { Some(StateRequest::NodeData(hashes)) };
+			}
+		};
+		self.pivot_downloading = request.is_some();
+		request
+	}
+
+	/// Clear the in-flight pivot block request marker.
+	pub fn clear_pivot_download(&mut self) {
+		self.pivot_downloading = false;
+	}
+
+	/// Return nodes that were requested but not delivered to the front of the queue.
+	pub fn clear_node_download(&mut self, hashes: &[H256]) {
+		for hash in hashes.iter().rev() {
+			if self.downloading.remove(hash) {
+				self.pending.push_front(hash.clone());
+			}
+		}
+	}
+
+	/// Return all in-flight requests to the queue.
+	pub fn clear_downloads(&mut self) {
+		self.pivot_downloading = false;
+		let downloading = self.downloading.drain().collect::<Vec<_>>();
+		for hash in downloading {
+			self.pending.push_front(hash);
+		}
+	}
+
+	/// Insert the pivot block header and queue its state root unless it is already stored.
+	/// Fails if the header is not the trusted pivot block.
+	pub fn insert_header<F>(&mut self, bytes: Bytes, known: F) -> Result<(), DecoderError> where F: Fn(&H256) -> bool {
+		let (expected, total_difficulty) = match self.target {
+			Some(target) if self.pivot.is_none() => target,
+			_ => return Ok(()),
+		};
+		let header: BlockHeader = try!(UntrustedRlp::new(&bytes).as_val());
+		if header.hash() != expected {
+			trace!(target: "sync", "Unexpected state pivot header {} ({}), expected {}", header.number(), header.hash(), expected);
+			return Err(DecoderError::Custom("Unexpected pivot header"));
+		}
+		debug!(target: "sync", "Downloading state of block #{} ({})", header.number(), header.hash());
+		self.enqueue(None, header.state_root().clone(), NodeKind::State, &known);
+		self.pivot = Some(Pivot {
+			header: header,
+			header_bytes: bytes,
+			total_difficulty: total_difficulty,
+			body: None,
+			receipts: None,
+		});
+		Ok(())
+	}
+
+	/// Insert the pivot block body. Returns false if it does not match the pivot header.
+	pub fn insert_body(&mut self, bytes: Bytes) -> bool {
+		let pivot = match self.pivot {
+			Some(ref mut pivot) if pivot.body.is_none() => pivot,
+			_ => return false,
+		};
+		let (tx_root, uncles) = {
+			let body = UntrustedRlp::new(&bytes);
+			match (body.at(0), body.at(1)) {
+				(Ok(tx), Ok(uncles)) => (ordered_trie_root(tx.iter().map(|r| r.as_raw().to_vec())), uncles.as_raw().sha3()),
+				_ => return false,
+			}
+		};
+		if pivot.header.transactions_root() != &tx_root || pivot.header.uncles_hash() != &uncles {
+			return false;
+		}
+		pivot.body = Some(bytes);
+		true
+	}
+
+	/// Insert the pivot block receipts. Returns false if they do not match the pivot header.
+	pub fn insert_receipts(&mut self, bytes: Bytes) -> bool {
+		let pivot = match self.pivot {
+			Some(ref mut pivot) if pivot.receipts.is_none() => pivot,
+			_ => return false,
+		};
+		let receipts_root = ordered_trie_root(UntrustedRlp::new(&bytes).iter().map(|r| r.as_raw().to_vec()));
+		if pivot.header.receipts_root() != &receipts_root {
+			return false;
+		}
+		pivot.receipts = Some(bytes);
+		true
+	}
+
+	/// Insert downloaded nodes and queue their children unless they are already stored.
+	/// Returns the nodes with all of their children stored, to be written to the state database.
+	pub fn insert_nodes<F>(&mut self, nodes: Vec<Bytes>, known: F) -> Vec<Bytes> where F: Fn(&H256) -> bool {
+		let mut complete = Vec::new();
+		for node in nodes {
+			let hash = node.sha3();
+			if !self.downloading.remove(&hash) {
+				trace!(target: "sync", "Ignored unexpected node {}", hash);
+				continue;
+			}
+			let kind = self.nodes.get(&hash).expect("downloading nodes are tracked; qed").kind;
+			if kind != NodeKind::Code {
+				// the node matches a requested hash, so it is kept even if it can't be followed
+				if let Err(e) = self.enqueue_children(&hash, &UntrustedRlp::new(&node), kind, &known) {
+					warn!(target: "sync", "Error decoding state node {}: {:?}", hash, e);
+				}
+			}
+			self.downloaded += 1;
+			self.nodes.get_mut(&hash).expect("downloading nodes are tracked; qed").data = Some(node);
+			self.release(hash, &mut complete);
+		}
+		complete
+	}
+
+	/// Take out the pivot block along with its receipts and total difficulty, if everything is downloaded.
+	pub fn pivot_block(&self) -> Option<(Bytes, Bytes, U256)> {
+		if !self.is_complete() {
+			return None;
+		}
+		let pivot = self.pivot.as_ref().expect("pivot is set when the download is complete; qed");
+		let body = UntrustedRlp::new(pivot.body.as_ref().expect("body is set when the download is complete; qed"));
+		let mut rlp = RlpStream::new_list(3);
+		rlp.append_raw(&pivot.header_bytes, 1);
+		rlp.append_raw(body.at(0).expect("body was validated on insertion; qed").as_raw(), 1);
+		rlp.append_raw(body.at(1).expect("body was validated on insertion; qed").as_raw(), 1);
+		Some((rlp.out(), pivot.receipts.clone().expect("receipts are set when the download is complete; qed"), pivot.total_difficulty))
+	}
+
+	/// Hand out a downloaded node once all of its children are stored, along with the parents it completes.
+	fn release(&mut self, hash: H256, complete: &mut Vec<Bytes>) {
+		let mut ready = vec![hash];
+		while let Some(hash) = ready.pop() {
+			let done = self.nodes.get(&hash).map_or(false, |n| n.missing == 0 && n.data.is_some());
+			if !done {
+				continue;
+			}
+			let node = self.nodes.remove(&hash).expect("checked above; qed");
+			complete.push(node.data.expect("checked above; qed"));
+			for parent in node.parents {
+				if let Some(parent_node) = self.nodes.get_mut(&parent) {
+					parent_node.missing -= 1;
+				}
+				ready.push(parent);
+			}
+		}
+	}
+
+	/// Queue a node unless it is empty or already stored, and make the parent wait for it.
+	fn enqueue<F>(&mut self, parent: Option<&H256>, hash: H256, kind: NodeKind, known: &F) where F: Fn(&H256) -> bool {
+		let empty = match kind {
+			NodeKind::Code => hash == SHA3_EMPTY,
+			_ => hash == SHA3_NULL_RLP,
+		};
+		if empty || (!self.nodes.contains_key(&hash) && known(&hash)) {
+			return;
+		}
+		if !self.nodes.contains_key(&hash) {
+			self.nodes.insert(hash.clone(), Node { kind: kind, data: None, missing: 0, parents: Vec::new() });
+			self.pending.push_back(hash.clone());
+		}
+		if let Some(parent) = parent {
+			self.nodes.get_mut(&hash).expect("inserted above; qed").parents.push(parent.clone());
+			if let Some(parent_node) = self.nodes.get_mut(parent) {
+				parent_node.missing += 1;
+			}
+		}
+	}
+
+	/// Queue a child node reference. It is either a hash or a node embedded into its parent.
+	fn enqueue_reference<F>(&mut self, parent: &H256, reference: &UntrustedRlp, kind: NodeKind, known: &F) -> Result<(), DecoderError> where F: Fn(&H256) -> bool {
+		if reference.is_empty() {
+			Ok(())
+		} else if reference.is_list() {
+			self.enqueue_children(parent, reference, kind, known)
+		} else {
+			let hash: H256 = try!(reference.as_val());
+			self.enqueue(Some(parent), hash, kind, known);
+			Ok(())
+		}
+	}
+
+	fn enqueue_children<F>(&mut self, parent: &H256, node: &UntrustedRlp, kind: NodeKind, known: &F) -> Result<(), DecoderError> where F: Fn(&H256) -> bool {
+		if !node.is_list() {
+			return Err(DecoderError::RlpExpectedToBeList);
+		}
+		match node.item_count() {
+			// branch node
+			17 => {
+				for i in 0..16 {
+					try!(self.enqueue_reference(parent, &try!(node.at(i)), kind, known));
+				}
+				if kind == NodeKind::State {
+					let value = try!(node.at(16));
+					if !value.is_empty() {
+						let account = try!(value.data());
+						try!(self.enqueue_account(parent, account, known));
+					}
+				}
+				Ok(())
+			},
+			// leaf or extension node
+			2 => {
+				let path = try!(node.at(0));
+				let is_leaf = try!(path.data()).first().map_or(false, |p| p & 0x20 != 0);
+				if is_leaf {
+					if kind == NodeKind::State {
+						let value = try!(node.at(1));
+						let account = try!(value.data());
+						try!(self.enqueue_account(parent, account, known));
+					}
+					Ok(())
+				} else {
+					self.enqueue_reference(parent, &try!(node.at(1)), kind, known)
+				}
+			},
+			0 => Ok(()),
+			_ => Err(DecoderError::Custom("Invalid trie node")),
+		}
+	}
+
+	/// Queue storage and code of an account: rlp([nonce, balance, storage_root, code_hash]).
+	fn enqueue_account<F>(&mut self, parent: &H256, account: &[u8], known: &F) -> Result<(), DecoderError> where F: Fn(&H256) -> bool {
+		let account = UntrustedRlp::new(account);
+		self.enqueue(Some(parent), try!(account.val_at(2)), NodeKind::Storage, known);
+		self.enqueue(Some(parent), try!(account.val_at(3)), NodeKind::Code, known);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use util::*;
+	use rlp::*;
+	use util::trie::{TrieDBMut, TrieMut};
+	use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockID, BlockChainClient};
+	use ethcore::views::BlockView;
+	use ethcore::header::Header as BlockHeader;
+
+	fn account(storage_root: &H256, code_hash: &H256) -> Bytes {
+		let mut rlp = RlpStream::new_list(4);
+		rlp.append(&U256::zero());
+		rlp.append(&U256::from(100));
+		rlp.append(storage_root);
+		rlp.append(code_hash);
+		rlp.out()
+	}
+
+	fn state_trie() -> (MemoryDB, H256) {
+		let mut memdb = MemoryDB::new();
+		let mut storage_root = H256::new();
+		{
+			let mut storage = TrieDBMut::new(&mut memdb, &mut storage_root);
+			for i in 0..20u8 {
+				storage.insert(&[i; 32], &[i + 1]).unwrap();
+			}
+		}
+		let code = vec![0x60u8, 0x00, 0x56];
+		let code_hash = code.sha3();
+		memdb.insert(&code);
+		let mut root = H256::new();
+		{
+			let mut accounts = TrieDBMut::new(&mut memdb, &mut root);
+			for i in 0..50u8 {
+				let account = if i % 10 == 0 { account(&storage_root, &code_hash) } else { account(&SHA3_NULL_RLP, &SHA3_EMPTY) };
+				accounts.insert(&[i; 20].sha3(), &account).unwrap();
+			}
+		}
+		(memdb, root)
+	}
+
+	fn start_download(state: &mut StateDownload, root: &H256, known: &MemoryDB) {
+		let mut header = BlockHeader::new();
+		header.set_number(1);
+		header.set_state_root(root.clone());
+		state.start(header.hash(), U256::from(1));
+		state.insert_header(::rlp::encode(&header).to_vec(), |h| known.contains(h)).unwrap();
+		state.pivot.as_mut().unwrap().body = Some(Vec::new());
+		state.pivot.as_mut().unwrap().receipts = Some(Vec::new());
+	}
+
+	/// Download up to `rounds` requests, storing the returned nodes. Returns the hashes in the order they were stored.
+	fn download(state: &mut StateDownload, memdb: &MemoryDB, stored: &mut MemoryDB, rounds: usize) -> Vec<H256> {
+		let mut order = Vec::new();
+		for _ in 0..rounds {
+			let hashes = match state.needed(8) {
+				Some(StateRequest::NodeData(hashes)) => hashes,
+				_ => break,
+			};
+			let mut nodes = hashes.iter().map(|h| memdb.get(h).unwrap().to_vec()).collect::<Vec<_>>();
+			// unknown nodes are ignored
+			nodes.push(vec![0x80]);
+			let complete = {
+				let known = &*stored;
+				state.insert_nodes(nodes, |h| known.contains(h))
+			};
+			for node in complete {
+				order.push(node.sha3());
+				stored.insert(&node);
+			}
+		}
+		order
+	}
+
+	#[test]
+	fn downloads_pivot_block() {
+		let client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Uncle);
+		let pivot = BlockView::new(&client.block(BlockID::Number(36)).unwrap()).header_view().sha3();
+		let mut state = StateDownload::new();
+		state.start(pivot.clone(), U256::from(666));
+		assert!(state.is_active());
+
+		assert_eq!(state.needed(16), Some(StateRequest::Header(pivot.clone())));
+		assert_eq!(state.needed(16), None);
+		let header = |i| BlockView::new(&client.block(BlockID::Number(i)).unwrap()).header_view().rlp().as_raw().to_vec();
+		// only the trusted pivot header is accepted
+		assert!(state.insert_header(header(37), |_| false).is_err());
+		state.insert_header(header(36), |_| false).unwrap();
+		state.clear_pivot_download();
+		assert_eq!(state.pivot_number(), Some(36));
+
+		assert_eq!(state.needed(16), Some(StateRequest::Body(pivot.clone())));
+		state.clear_pivot_download();
+		let block = client.block(BlockID::Number(36)).unwrap();
+		let view = BlockView::new(&block);
+		let mut body = RlpStream::new_list(2);
+		body.append_raw(view.rlp().at(1).as_raw(), 1);
+		body.append_raw(view.rlp().at(2).as_raw(), 1);
+		assert!(!state.insert_body(RlpStream::new_list(0).out()));
+		assert!(state.insert_body(body.out()));
+		assert_eq!(state.needed(16), Some(StateRequest::Receipts(pivot)));
+		state.clear_pivot_download();
+		assert!(state.insert_receipts(RlpStream::new_list(0).out()));
+		assert!(!state.insert_receipts(RlpStream::new_list(0).out()));
+
+		// the state of test blocks is empty
+		assert_eq!(state.needed(16), None);
+		assert!(state.is_complete());
+		// total difficulty comes from the trusted pivot
+		assert_eq!(state.pivot_block(), Some((block.clone(), RlpStream::new_list(0).out(), U256::from(666))));
+	}
+
+	#[test]
+	fn downloads_state_trie() {
+		let (memdb, root) = state_trie();
+		let mut state = StateDownload::new();
+		let mut stored = MemoryDB::new();
+		start_download(&mut state, &root, &stored);
+		let order = download(&mut state, &memdb, &mut stored, usize::max_value());
+
+		assert!(state.is_complete());
+		assert_eq!(state.downloaded(), memdb.keys().len());
+		for key in memdb.keys().keys() {
+			assert!(stored.contains(key));
+		}
+		// nodes are stored after their children
+		assert_eq!(order.last(), Some(&root));
+	}
+
+	#[test]
+	fn resumes_from_stored_nodes() {
+		let (memdb, root) = state_trie();
+		let mut stored = MemoryDB::new();
+		{
+			let mut state = StateDownload::new();
+			start_download(&mut state, &root, &stored);
+			download(&mut state, &memdb, &mut stored, 5);
+			assert!(!state.is_complete());
+			assert!(!stored.contains(&root));
+		}
+
+		let mut state = StateDownload::new();
+		start_download(&mut state, &root, &stored);
+		download(&mut state, &memdb, &mut stored, usize::max_value());
+		assert!(state.is_complete());
+		// stored subtries are not downloaded again
+		assert!(state.downloaded() < memdb.keys().len());
+		for key in memdb.keys().keys() {
+			assert!(stored.contains(key));
+		}
+
+		// nothing is downloaded when the whole state is stored
+		let mut state = StateDownload::new();
+		start_download(&mut state, &root, &stored);
+		assert_eq!(state.needed(8), None);
+		assert!(state.is_complete());
+	}
+}
@@ -17,6 +17,8 @@
 use util::*;
 use ethcore::client::{TestBlockChainClient, BlockChainClient, BlockID, EachBlockWith};
 use chain::{SyncState};
+use ethcore::spec::Checkpoint;
+use ::SyncConfig;
 use super::helpers::*;
 
 #[test]
@@ -51,6 +53,19 @@ fn status_after_sync() {
 	assert_eq!(status.state, SyncState::Idle);
 }
 
+#[test]
+fn ancient_blocks() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(2);
+	net.peer_mut(0).chain.add_blocks(500, EachBlockWith::Uncle);
+	net.peer_mut(1).chain.add_blocks(500, EachBlockWith::Uncle);
+	net.peer_mut(0).chain.set_first_block(300);
+	net.sync();
+	assert!(net.peer(0).chain.first_block.read().is_none());
+	assert_eq!(net.peer(0).sync.read().status().ancient_block_number, None);
+	assert_eq!(*net.peer(0).chain.blocks.read(), *net.peer(1).chain.blocks.read());
+}
+
 #[test]
 fn takes_few_steps() {
 	let mut net = TestNet::new(3);
@@ -114,6 +129,43 @@ fn net_hard_fork() {
 	}
 }
 
+#[test]
+fn net_checkpoint() {
+	::env_logger::init().ok();
+	let ref_client = TestBlockChainClient::new();
+	ref_client.add_blocks(50, EachBlockWith::Uncle);
+	let checkpoint = |total_difficulty: u64| {
+		let mut config = SyncConfig::default();
+		config.checkpoint = Some(Checkpoint {
+			number: 30,
+			hash: ref_client.block_hash(BlockID::Number(30)).unwrap(),
+			total_difficulty: total_difficulty.into(),
+		});
+		config
+	};
+	{
+		let mut net = TestNet::new_with_config(2, checkpoint(0));
+		net.peer_mut(0).chain.add_blocks(100, EachBlockWith::Uncle);
+		net.sync();
+		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 100);
+	}
+	{
+		// peer chain does not include the checkpoint
+		let mut net = TestNet::new_with_config(2, checkpoint(0));
+		net.peer_mut(0).chain.add_blocks(100, EachBlockWith::Nothing);
+		net.sync();
+		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 0);
+	}
+	{
+		// peer chain is too light to include the checkpoint
+		let mut net = TestNet::new_with_config(2, checkpoint(u64::max_value()));
+		net.peer_mut(0).chain.add_blocks(100, EachBlockWith::Uncle);
+		net.sync();
+		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 0);
+		assert_eq!(net.peer(1).sync.read().status().num_peers, 0);
+	}
+}
+
 #[test]
 fn restart() {
 	let mut net = TestNet::new(3);
@@ -29,6 +29,7 @@ pub struct TestIo<'p> {
 	pub snapshot_service: &'p TestSnapshotService,
 	pub queue: &'p mut VecDeque<TestPacket>,
 	pub sender: Option<PeerId>,
+	pub protocol_version: u8,
 }
 
 impl<'p> TestIo<'p> {
@@ -37,7 +38,8 @@ impl<'p> TestIo<'p> {
 			chain: chain,
 			snapshot_service: ss,
 			queue: queue,
-			sender: sender
+			sender: sender,
+			protocol_version: 64,
 		}
 	}
 }
@@ -49,6 +51,9 @@ impl<'p> SyncIo for TestIo<'p> {
 	fn disconnect_peer(&mut self, _peer_id: PeerId) {
 	}
 
+	fn note_peer_behaviour(&mut self, _peer_id: PeerId, _behaviour: PeerBehaviour) {
+	}
+
 	fn is_expired(&self) -> bool {
 		false
 	}
@@ -80,7 +85,7 @@ impl<'p> SyncIo for TestIo<'p> {
 	}
 
 	fn eth_protocol_version(&self, _peer: PeerId) -> u8 {
-		64
+		self.protocol_version
 	}
 }
 
@@ -108,14 +113,18 @@ impl TestNet {
 	}
 
 	pub fn new_with_fork(n: usize, fork: Option<(BlockNumber, H256)>) -> TestNet {
+		let mut config = SyncConfig::default();
+		config.fork_block = fork;
+		Self::new_with_config(n, config)
+	}
+
+	pub fn new_with_config(n: usize, config: SyncConfig) -> TestNet {
 		let mut net = TestNet {
 			peers: Vec::new(),
 			started: false,
 		};
 		for _ in 0..n {
 			let chain = TestBlockChainClient::new();
-			let mut config = SyncConfig::default();
-			config.fork_block = fork;
 			let ss = Arc::new(TestSnapshotService::new());
 			let sync = ChainSync::new(config, &chain);
 			net.peers.push(TestPeer {
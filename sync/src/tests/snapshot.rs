@@ -22,6 +22,7 @@ use super::helpers::*;
 
 pub struct TestSnapshotService {
 	manifest: Option<ManifestData>,
+	full_manifest: Option<ManifestData>,
 	chunks: HashMap<H256, Bytes>,
 
 	restoration_manifest: Mutex<Option<ManifestData>>,
@@ -33,6 +34,7 @@ impl TestSnapshotService {
 	pub fn new() -> TestSnapshotService {
 		TestSnapshotService {
 			manifest: None,
+			full_manifest: None,
 			chunks: HashMap::new(),
 			restoration_manifest: Mutex::new(None),
 			state_restoration_chunks: Mutex::new(HashMap::new()),
@@ -51,17 +53,31 @@ impl TestSnapshotService {
 			state_root: H256::new(),
 			block_number: block_number,
 			block_hash: block_hash,
+			delta_hashes: Vec::new(),
+			base: None,
 		};
 		let mut chunks: HashMap<H256, Bytes> = state_chunks.into_iter().map(|data| (data.sha3(), data)).collect();
 		chunks.extend(block_chunks.into_iter().map(|data| (data.sha3(), data)));
 		TestSnapshotService {
-			manifest: Some(manifest),
+			manifest: Some(manifest.clone()),
+			full_manifest: Some(manifest),
 			chunks: chunks,
 			restoration_manifest: Mutex::new(None),
 			state_restoration_chunks: Mutex::new(HashMap::new()),
 			block_restoration_chunks: Mutex::new(HashMap::new()),
 		}
 	}
+
+	pub fn new_with_incremental_snapshot(num_chunks: usize, block_hash: H256, block_number: BlockNumber) -> TestSnapshotService {
+		let mut service = TestSnapshotService::new_with_snapshot(num_chunks, block_hash, block_number);
+		let full = service.full_manifest.clone().expect("full snapshot created above; qed");
+		service.manifest = Some(ManifestData {
+			block_number: block_number + 1,
+			base: Some(full.clone().into_rlp().sha3()),
+			..full
+		});
+		service
+	}
 }
 
 impl SnapshotService for TestSnapshotService {
@@ -69,6 +85,10 @@ impl SnapshotService for TestSnapshotService {
 		self.manifest.as_ref().cloned()
 	}
 
+	fn full_manifest(&self) -> Option<ManifestData> {
+		self.full_manifest.as_ref().cloned()
+	}
+
 	fn chunk(&self, hash: H256) -> Option<Bytes> {
 		self.chunks.get(&hash).cloned()
 	}
@@ -85,10 +105,25 @@ impl SnapshotService for TestSnapshotService {
 		}
 	}
 
+	fn completed_chunks(&self, manifest: &ManifestData) -> Vec<H256> {
+		match *self.restoration_manifest.lock() {
+			Some(ref m) if m == manifest => self.state_restoration_chunks.lock().keys()
+				.chain(self.block_restoration_chunks.lock().keys())
+				.cloned()
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+
 	fn begin_restore(&self, manifest: ManifestData) {
-		*self.restoration_manifest.lock() = Some(manifest);
-		self.state_restoration_chunks.lock().clear();
-		self.block_restoration_chunks.lock().clear();
+		let mut restoration_manifest = self.restoration_manifest.lock();
+
+		// resume restoration of the same snapshot.
+		if restoration_manifest.as_ref() != Some(&manifest) {
+			self.state_restoration_chunks.lock().clear();
+			self.block_restoration_chunks.lock().clear();
+		}
+		*restoration_manifest = Some(manifest);
 	}
 
 	fn abort_restore(&self) {
@@ -121,3 +156,25 @@ fn snapshot_sync() {
 	assert_eq!(net.peer(1).snapshot_service.block_restoration_chunks.lock().len(), net.peer(0).snapshot_service.manifest.as_ref().unwrap().block_hashes.len());
 }
 
+#[test]
+fn snapshot_sync_resumes_restoration() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(2);
+	net.peer_mut(0).snapshot_service = Arc::new(TestSnapshotService::new_with_snapshot(16, H256::new(), 1));
+	net.peer_mut(0).chain.add_blocks(1, EachBlockWith::Nothing);
+
+	// pretend the first state chunk was restored before an interruption.
+	let manifest = net.peer(0).snapshot_service.manifest.clone().unwrap();
+	let restored = manifest.state_hashes[0];
+	*net.peer(1).snapshot_service.restoration_manifest.lock() = Some(manifest.clone());
+	net.peer(1).snapshot_service.state_restoration_chunks.lock().insert(restored, Vec::new());
+
+	net.sync_steps(19);
+
+	let state_chunks = net.peer(1).snapshot_service.state_restoration_chunks.lock();
+	assert_eq!(state_chunks.len(), manifest.state_hashes.len());
+	assert_eq!(net.peer(1).snapshot_service.block_restoration_chunks.lock().len(), manifest.block_hashes.len());
+
+	// the chunk restored before wasn't downloaded again.
+	assert_eq!(state_chunks[&restored], Vec::<u8>::new());
+}
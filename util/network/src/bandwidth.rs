@@ -0,0 +1,216 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bandwidth limiting.
+//! Limits are enforced with token buckets holding up to one second worth of traffic.
+//! Each connection checks both its own buckets and the buckets shared by all connections.
+
+use std::sync::Arc;
+use std::cmp::min;
+use parking_lot::Mutex;
+use time::precise_time_s;
+
+/// Token bucket allowing `rate` bytes per second.
+#[derive(Debug)]
+pub struct TokenBucket {
+	rate: usize,
+	tokens: usize,
+	last: f64,
+}
+
+impl TokenBucket {
+	/// Create a new full bucket.
+	pub fn new(rate: usize) -> TokenBucket {
+		TokenBucket {
+			rate: rate,
+			tokens: rate,
+			last: precise_time_s(),
+		}
+	}
+
+	fn refill(&mut self, now: f64) {
+		if now > self.last {
+			let added = ((now - self.last) * self.rate as f64) as usize;
+			// keep the fraction for the next refill.
+			if added > 0 {
+				self.tokens = min(self.rate, self.tokens.saturating_add(added));
+				self.last = now;
+			}
+		}
+	}
+
+	fn available_at(&mut self, wanted: usize, now: f64) -> usize {
+		self.refill(now);
+		min(wanted, self.tokens)
+	}
+
+	/// Returns how many of the `wanted` bytes can be transferred now.
+	pub fn available(&mut self, wanted: usize) -> usize {
+		self.available_at(wanted, precise_time_s())
+	}
+
+	/// Note transferred bytes.
+	pub fn consume(&mut self, used: usize) {
+		self.tokens = self.tokens.saturating_sub(used);
+	}
+}
+
+/// Bandwidth limits shared by all connections.
+pub struct Throttle {
+	upload: Option<Mutex<TokenBucket>>,
+	download: Option<Mutex<TokenBucket>>,
+	peer_upload: Option<usize>,
+	peer_download: Option<usize>,
+}
+
+impl Throttle {
+	/// Create a new instance. Limits are in bytes per second, `None` means unlimited.
+	pub fn new(upload: Option<u64>, download: Option<u64>, peer_upload: Option<u64>, peer_download: Option<u64>) -> Throttle {
+		Throttle {
+			upload: upload.map(|r| Mutex::new(TokenBucket::new(r as usize))),
+			download: download.map(|r| Mutex::new(TokenBucket::new(r as usize))),
+			peer_upload: peer_upload.map(|r| r as usize),
+			peer_download: peer_download.map(|r| r as usize),
+		}
+	}
+
+	/// Create a new instance without any limits.
+	pub fn unlimited() -> Throttle {
+		Throttle::new(None, None, None, None)
+	}
+
+	/// Check if any limit is set.
+	pub fn is_limited(&self) -> bool {
+		self.upload.is_some() || self.download.is_some() || self.peer_upload.is_some() || self.peer_download.is_some()
+	}
+}
+
+/// Bandwidth limiter for a single connection.
+pub struct PeerThrottle {
+	shared: Arc<Throttle>,
+	upload: Option<TokenBucket>,
+	download: Option<TokenBucket>,
+	upload_throttled: bool,
+	download_throttled: bool,
+}
+
+fn available(local: &mut Option<TokenBucket>, shared: &Option<Mutex<TokenBucket>>, wanted: usize) -> usize {
+	let mut allowed = wanted;
+	if let Some(ref mut bucket) = *local {
+		allowed = bucket.available(allowed);
+	}
+	if let Some(ref bucket) = *shared {
+		allowed = bucket.lock().available(allowed);
+	}
+	allowed
+}
+
+fn consume(local: &mut Option<TokenBucket>, shared: &Option<Mutex<TokenBucket>>, used: usize) {
+	if let Some(ref mut bucket) = *local {
+		bucket.consume(used);
+	}
+	if let Some(ref bucket) = *shared {
+		bucket.lock().consume(used);
+	}
+}
+
+impl PeerThrottle {
+	/// Create a new connection limiter using the given shared limits.
+	pub fn new(shared: Arc<Throttle>) -> PeerThrottle {
+		PeerThrottle {
+			upload: shared.peer_upload.map(TokenBucket::new),
+			download: shared.peer_download.map(TokenBucket::new),
+			shared: shared,
+			upload_throttled: false,
+			download_throttled: false,
+		}
+	}
+
+	/// Get the shared limits.
+	pub fn shared(&self) -> Arc<Throttle> {
+		self.shared.clone()
+	}
+
+	/// Returns how many of the `wanted` bytes can be sent now.
+	pub fn upload_available(&mut self, wanted: usize) -> usize {
+		let allowed = available(&mut self.upload, &self.shared.upload, wanted);
+		self.upload_throttled = allowed < wanted;
+		allowed
+	}
+
+	/// Returns how many of the `wanted` bytes can be received now.
+	pub fn download_available(&mut self, wanted: usize) -> usize {
+		let allowed = available(&mut self.download, &self.shared.download, wanted);
+		self.download_throttled = allowed < wanted;
+		allowed
+	}
+
+	/// Note sent bytes.
+	pub fn uploaded(&mut self, used: usize) {
+		consume(&mut self.upload, &self.shared.upload, used);
+	}
+
+	/// Note received bytes.
+	pub fn downloaded(&mut self, used: usize) {
+		consume(&mut self.download, &self.shared.download, used);
+	}
+
+	/// Check if the last transfer was cut short by a limit.
+	/// Throttled connections have to be polled again, since the socket won't signal readiness.
+	pub fn is_throttled(&self) -> bool {
+		self.upload_throttled || self.download_throttled
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+
+	#[test]
+	fn bucket_refills() {
+		let mut bucket = TokenBucket::new(1000);
+		let start = bucket.last;
+		assert_eq!(bucket.available_at(2000, start), 1000);
+		bucket.consume(1000);
+		assert_eq!(bucket.available_at(2000, start), 0);
+		assert_eq!(bucket.available_at(2000, start + 0.5), 500);
+		bucket.consume(200);
+		assert_eq!(bucket.available_at(2000, start + 10.0), 1000);
+	}
+
+	#[test]
+	fn unlimited_peer() {
+		let mut throttle = PeerThrottle::new(Arc::new(Throttle::unlimited()));
+		assert_eq!(throttle.upload_available(100000), 100000);
+		throttle.uploaded(100000);
+		assert_eq!(throttle.download_available(100000), 100000);
+		assert!(!throttle.is_throttled());
+	}
+
+	#[test]
+	fn shared_limit() {
+		let shared = Arc::new(Throttle::new(Some(100), None, Some(1000), None));
+		let mut throttle1 = PeerThrottle::new(shared.clone());
+		let mut throttle2 = PeerThrottle::new(shared.clone());
+		assert_eq!(throttle1.upload_available(80), 80);
+		throttle1.uploaded(80);
+		assert!(!throttle1.is_throttled());
+		assert_eq!(throttle2.upload_available(80), 20);
+		assert!(throttle2.is_throttled());
+		assert_eq!(throttle2.download_available(5000), 5000);
+	}
+}
@@ -29,6 +29,7 @@ use error::*;
 use io::{IoContext, StreamToken};
 use handshake::Handshake;
 use stats::NetworkStats;
+use bandwidth::{Throttle, PeerThrottle};
 use rcrypto::blockmodes::*;
 use rcrypto::aessafe::*;
 use rcrypto::symmetriccipher::*;
@@ -60,6 +61,12 @@ pub struct GenericConnection<Socket: GenericSocket> {
 	interest: EventSet,
 	/// Shared network statistics
 	stats: Arc<NetworkStats>,
+	/// Bandwidth limiter
+	throttle: PeerThrottle,
+	/// Bytes sent over this connection
+	bytes_sent: u64,
+	/// Bytes received over this connection
+	bytes_received: u64,
 	/// Registered flag
 	registered: AtomicBool,
 }
@@ -80,10 +87,16 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 		}
 		let sock_ref = <Socket as Read>::by_ref(&mut self.socket);
 		loop {
-			let max = self.rec_size - self.rec_buf.len();
+			let max = self.throttle.download_available(self.rec_size - self.rec_buf.len());
+			if max == 0 {
+				trace!(target:"network", "{}: Download throttled", self.token);
+				return Ok(None);
+			}
 			match sock_ref.take(max as u64).try_read_buf(&mut self.rec_buf) {
 				Ok(Some(size)) if size != 0  => {
 					self.stats.inc_recv(size);
+					self.throttle.downloaded(size);
+					self.bytes_received += size as u64;
 					trace!(target:"network", "{}: Read {} of {} bytes", self.token, self.rec_buf.len(), self.rec_size);
 					if self.rec_size != 0 && self.rec_buf.len() == self.rec_size {
 						self.rec_size = 0;
@@ -119,6 +132,21 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 		self.interest.is_writable()
 	}
 
+	/// Check if the last read or write was limited by bandwidth throttling.
+	pub fn is_throttled(&self) -> bool {
+		self.throttle.is_throttled()
+	}
+
+	/// Get number of bytes sent over this connection.
+	pub fn bytes_sent(&self) -> u64 {
+		self.bytes_sent
+	}
+
+	/// Get number of bytes received over this connection.
+	pub fn bytes_received(&self) -> u64 {
+		self.bytes_received
+	}
+
 	/// Writable IO handler. Called when the socket is ready to send.
 	pub fn writable<Message>(&mut self, io: &IoContext<Message>) -> Result<WriteStatus, NetworkError> where Message: Send + Clone {
 		if self.send_queue.is_empty() {
@@ -127,17 +155,29 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 		{
 			let buf = self.send_queue.front_mut().unwrap();
 			let send_size = buf.get_ref().len();
-			if (buf.position() as usize) >= send_size {
+			let position = buf.position() as usize;
+			if position >= send_size {
 				warn!(target:"net", "Unexpected connection data");
 				return Ok(WriteStatus::Complete)
 			}
-			match self.socket.try_write_buf(buf) {
-				Ok(Some(size)) if (buf.position() as usize) < send_size => {
+			let allowed = self.throttle.upload_available(send_size - position);
+			if allowed == 0 {
+				trace!(target:"network", "{}: Upload throttled", self.token);
+				return Ok(WriteStatus::Ongoing)
+			}
+			match self.socket.try_write(&buf.get_ref()[position..position + allowed]) {
+				Ok(Some(size)) if position + size < send_size => {
+					buf.set_position((position + size) as u64);
 					self.stats.inc_send(size);
+					self.throttle.uploaded(size);
+					self.bytes_sent += size as u64;
 					Ok(WriteStatus::Ongoing)
 				},
-				Ok(Some(size)) if (buf.position() as usize) == send_size => {
+				Ok(Some(size)) if position + size == send_size => {
+					buf.set_position(send_size as u64);
 					self.stats.inc_send(size);
+					self.throttle.uploaded(size);
+					self.bytes_sent += size as u64;
 					trace!(target:"network", "{}: Wrote {} bytes", self.token, send_size);
 					Ok(WriteStatus::Complete)
 				},
@@ -163,7 +203,7 @@ pub type Connection = GenericConnection<TcpStream>;
 
 impl Connection {
 	/// Create a new connection with given id and socket.
-	pub fn new(token: StreamToken, socket: TcpStream, stats: Arc<NetworkStats>) -> Connection {
+	pub fn new(token: StreamToken, socket: TcpStream, stats: Arc<NetworkStats>, throttle: Arc<Throttle>) -> Connection {
 		Connection {
 			token: token,
 			socket: socket,
@@ -172,6 +212,9 @@ impl Connection {
 			rec_size: 0,
 			interest: EventSet::hup() | EventSet::readable(),
 			stats: stats,
+			throttle: PeerThrottle::new(throttle),
+			bytes_sent: 0,
+			bytes_received: 0,
 			registered: AtomicBool::new(false),
 		}
 	}
@@ -201,6 +244,9 @@ impl Connection {
 			send_queue: self.send_queue.clone(),
 			interest: EventSet::hup(),
 			stats: self.stats.clone(),
+			throttle: PeerThrottle::new(self.throttle.shared()),
+			bytes_sent: self.bytes_sent,
+			bytes_received: self.bytes_received,
 			registered: AtomicBool::new(false),
 		})
 	}
@@ -492,6 +538,7 @@ mod tests {
 	use std::sync::Arc;
 	use std::sync::atomic::AtomicBool;
 	use super::super::stats::*;
+	use bandwidth::{Throttle, PeerThrottle};
 	use std::io::{Read, Write, Error, Cursor, ErrorKind};
 	use mio::{EventSet};
 	use std::collections::VecDeque;
@@ -541,6 +588,9 @@ mod tests {
 				rec_size: 0,
 				interest: EventSet::hup() | EventSet::readable(),
 				stats: Arc::<NetworkStats>::new(NetworkStats::new()),
+				throttle: PeerThrottle::new(Arc::new(Throttle::unlimited())),
+				bytes_sent: 0,
+				bytes_received: 0,
 				registered: AtomicBool::new(false),
 			}
 		}
@@ -564,6 +614,9 @@ mod tests {
 				rec_size: 0,
 				interest: EventSet::hup() | EventSet::readable(),
 				stats: Arc::<NetworkStats>::new(NetworkStats::new()),
+				throttle: PeerThrottle::new(Arc::new(Throttle::unlimited())),
+				bytes_sent: 0,
+				bytes_received: 0,
 				registered: AtomicBool::new(false),
 			}
 		}
@@ -614,6 +667,22 @@ mod tests {
 		assert_eq!(1024, connection.socket.write_buffer.len());
 	}
 
+	#[test]
+	fn connection_write_is_throttled() {
+		let mut connection = TestConnection::new();
+		connection.throttle = PeerThrottle::new(Arc::new(Throttle::new(None, None, Some(1000), None)));
+		let data = Cursor::new(vec![0; 10240]);
+		connection.send_queue.push_back(data);
+
+		let status = connection.writable(&test_io());
+
+		assert!(status.is_ok());
+		assert!(WriteStatus::Ongoing == status.unwrap());
+		assert_eq!(1000, connection.socket.write_buffer.len());
+		assert_eq!(1000, connection.bytes_sent());
+		assert!(connection.is_throttled());
+	}
+
 	#[test]
 	fn connection_write_to_broken() {
 		let mut connection = TestBrokenConnection::new();
@@ -639,6 +708,22 @@ mod tests {
 		assert_eq!(1024, connection.socket.cursor);
 	}
 
+	#[test]
+	fn connection_read_is_throttled() {
+		let mut connection = TestConnection::new();
+		connection.throttle = PeerThrottle::new(Arc::new(Throttle::new(None, Some(512), None, None)));
+		connection.rec_size = 2048;
+		connection.socket.read_buffer = vec![99; 2048];
+
+		let status = connection.readable();
+
+		assert!(status.is_ok());
+		assert!(status.unwrap().is_none());
+		assert_eq!(512, connection.socket.cursor);
+		assert_eq!(512, connection.bytes_received());
+		assert!(connection.is_throttled());
+	}
+
 	#[test]
 	fn connection_read_from_broken() {
 		let mut connection = TestBrokenConnection::new();
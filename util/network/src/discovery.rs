@@ -44,9 +44,18 @@ const PACKET_PING: u8 = 1;
 const PACKET_PONG: u8 = 2;
 const PACKET_FIND_NODE: u8 = 3;
 const PACKET_NEIGHBOURS: u8 = 4;
+// Topic packets. Nodes that don't know them simply drop them.
+const PACKET_TOPIC_REGISTER: u8 = 5;
+const PACKET_TOPIC_QUERY: u8 = 6;
+const PACKET_TOPIC_NODES: u8 = 7;
 
 const PING_TIMEOUT_MS: u64 = 300;
 const MAX_NODES_PING: usize = 32; // Max nodes to add/ping at once
+const MAX_TOPICS: usize = 16; // Max topics accepted in a single registration
+const TOPIC_QUEUE_SIZE: usize = 64; // Max registrations kept for a single topic
+const MAX_REGISTERED_TOPICS: usize = 1024; // Max topics registrations are kept for
+const TOPIC_REGISTRATION_TIMEOUT_MS: u64 = 600_000; // Registrations not renewed by the node are dropped after this long
+const TOPIC_QUERY_TIMEOUT_MS: u64 = 60_000; // TopicNodes replies and the matches they carry are kept this long
 
 #[derive(Clone, Debug)]
 pub struct NodeEntry {
@@ -77,6 +86,14 @@ impl NodeBucket {
 	}
 }
 
+/// Topic query sent to a node.
+struct TopicQuery {
+	/// Time the query was last sent, in nanoseconds.
+	sent: u64,
+	/// Nodes reported by the replies as advertising one of our topics.
+	matching: HashSet<NodeId>,
+}
+
 struct Datagramm {
 	payload: Bytes,
 	address: SocketAddr,
@@ -95,11 +112,24 @@ pub struct Discovery {
 	send_queue: VecDeque<Datagramm>,
 	check_timestamps: bool,
 	adding_nodes: Vec<NodeEntry>,
+	topics: Vec<Topic>,
+	topic_registrations: HashMap<Topic, VecDeque<(NodeEntry, u64)>>, // registered nodes with registration time
+	topic_queries: HashMap<NodeId, TopicQuery>,
 }
 
 pub struct TableUpdates {
 	pub added: HashMap<NodeId, NodeEntry>,
 	pub removed: HashSet<NodeId>,
+	/// Nodes known to advertise one of our topics.
+	pub matching: HashSet<NodeId>,
+	/// Nodes no longer known to advertise one of our topics.
+	pub unmatched: HashSet<NodeId>,
+}
+
+impl TableUpdates {
+	fn with_added(added: HashMap<NodeId, NodeEntry>) -> TableUpdates {
+		TableUpdates { added: added, removed: HashSet::new(), matching: HashSet::new(), unmatched: HashSet::new() }
+	}
 }
 
 impl Discovery {
@@ -118,9 +148,17 @@ impl Discovery {
 			send_queue: VecDeque::new(),
 			check_timestamps: true,
 			adding_nodes: Vec::new(),
+			topics: Vec::new(),
+			topic_registrations: HashMap::new(),
+			topic_queries: HashMap::new(),
 		}
 	}
 
+	/// Set topics to advertise and look for during discovery.
+	pub fn set_topics(&mut self, topics: Vec<Topic>) {
+		self.topics = topics;
+	}
+
 	/// Add a new node to discovery table. Pings the node.
 	pub fn add_node(&mut self, e: NodeEntry) {
 		let endpoint = e.endpoint.clone();
@@ -205,6 +243,7 @@ impl Discovery {
 				self.discovery_nodes.insert(r.id.clone());
 				tried_count += 1;
 				trace!(target: "discovery", "Sent FindNode to {:?}", &r.endpoint);
+				self.advertise_topics(&r);
 			}
 		}
 
@@ -239,6 +278,23 @@ impl Discovery {
 		self.send_packet(PACKET_PING, &node.udp_address(), &rlp.drain());
 	}
 
+	fn advertise_topics(&mut self, node: &NodeEntry) {
+		if self.topics.is_empty() {
+			return;
+		}
+		let address = node.endpoint.udp_address();
+		let mut rlp = RlpStream::new_list(1);
+		rlp.append(&self.topics);
+		self.send_packet(PACKET_TOPIC_REGISTER, &address, &rlp.drain());
+		for topic in self.topics.clone() {
+			let rlp = encode(&(&[topic][..]));
+			self.send_packet(PACKET_TOPIC_QUERY, &address, &rlp);
+		}
+		let now = time::precise_time_ns();
+		self.topic_queries.entry(node.id.clone()).or_insert_with(|| TopicQuery { sent: now, matching: HashSet::new() }).sent = now;
+		trace!(target: "discovery", "Sent {} topics to {:?}", self.topics.len(), &node.endpoint);
+	}
+
 	fn send_packet(&mut self, packet_id: u8, address: &SocketAddr, payload: &[u8]) {
 		let mut rlp = RlpStream::new();
 		rlp.append_raw(&[packet_id], 1);
@@ -371,6 +427,9 @@ impl Discovery {
 			PACKET_PONG => self.on_pong(&rlp, &node_id, &from),
 			PACKET_FIND_NODE => self.on_find_node(&rlp, &node_id, &from),
 			PACKET_NEIGHBOURS => self.on_neighbours(&rlp, &node_id, &from),
+			PACKET_TOPIC_REGISTER => self.on_topic_register(&rlp, &node_id, &from),
+			PACKET_TOPIC_QUERY => self.on_topic_query(&rlp, &node_id, &from),
+			PACKET_TOPIC_NODES => self.on_topic_nodes(&rlp, &node_id, &from),
 			_ => {
 				debug!("Unknown UDP packet: {}", packet_id);
 				Ok(None)
@@ -407,7 +466,7 @@ impl Discovery {
 		response.append(&hash);
 		self.send_packet(PACKET_PONG, from, &response.drain());
 
-		Ok(Some(TableUpdates { added: added_map, removed: HashSet::new() }))
+		Ok(Some(TableUpdates::with_added(added_map)))
 	}
 
 	fn on_pong(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
@@ -479,7 +538,154 @@ impl Discovery {
 			self.ping(&entry.endpoint);
 			self.update_node(entry);
 		}
-		Ok(Some(TableUpdates { added: added, removed: HashSet::new() }))
+		Ok(Some(TableUpdates::with_added(added)))
+	}
+
+	fn find_entry(&self, id: &NodeId) -> Option<NodeEntry> {
+		let bucket = &self.node_buckets[Discovery::distance(&self.id, id) as usize];
+		bucket.nodes.iter().find(|n| &n.address.id == id).map(|n| n.address.clone())
+	}
+
+	fn on_topic_register(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		let topics: Vec<Topic> = try!(rlp.val_at(0));
+		let timestamp: u64 = try!(rlp.val_at(1));
+		try!(self.check_timestamp(timestamp));
+		trace!(target: "discovery", "Got {} topics from {:?}", topics.len(), &from);
+		// Only accept registrations from nodes that are already in the table, so that the endpoint is known.
+		let entry = match self.find_entry(node) {
+			Some(entry) => entry,
+			None => return Ok(None),
+		};
+		let mut matching = false;
+		for topic in topics.into_iter().take(MAX_TOPICS) {
+			matching = matching || self.topics.contains(&topic);
+			self.register_topic(topic, &entry);
+		}
+		if matching {
+			let mut update = TableUpdates::with_added(HashMap::new());
+			update.matching.insert(node.clone());
+			Ok(Some(update))
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Register the node for the topic. New topics are not accepted once `MAX_REGISTERED_TOPICS` are kept.
+	fn register_topic(&mut self, topic: Topic, entry: &NodeEntry) -> bool {
+		if !self.topic_registrations.contains_key(&topic) && self.topic_registrations.len() >= MAX_REGISTERED_TOPICS {
+			trace!(target: "discovery", "Too many registered topics, ignoring {:?}", &topic);
+			return false;
+		}
+		let queue = self.topic_registrations.entry(topic).or_insert_with(VecDeque::new);
+		queue.retain(|&(ref e, _)| e.id != entry.id);
+		queue.push_front((entry.clone(), time::precise_time_ns()));
+		queue.truncate(TOPIC_QUEUE_SIZE);
+		true
+	}
+
+	/// Drop registrations which were not renewed in time or whose node was removed, and topics left without any.
+	fn expire_topic_registrations(&mut self, removed: &HashSet<NodeId>, force: bool) {
+		let now = time::precise_time_ns();
+		let mut empty = Vec::new();
+		for (topic, queue) in &mut self.topic_registrations {
+			// registrations are kept newest first.
+			while queue.back().map_or(false, |&(_, registered)| force || now - registered >= TOPIC_REGISTRATION_TIMEOUT_MS * 1_000_000) {
+				queue.pop_back();
+			}
+			if !removed.is_empty() {
+				queue.retain(|&(ref e, _)| !removed.contains(&e.id));
+			}
+			if queue.is_empty() {
+				empty.push(topic.clone());
+			}
+		}
+		for topic in empty {
+			self.topic_registrations.remove(&topic);
+		}
+	}
+
+	fn on_topic_query(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		let topic: Topic = try!(rlp.val_at(0));
+		let timestamp: u64 = try!(rlp.val_at(1));
+		try!(self.check_timestamp(timestamp));
+		trace!(target: "discovery", "Got TopicQuery for {:?} from {:?}", &topic, &from);
+		let registered: Vec<NodeEntry> = match self.topic_registrations.get(&topic) {
+			Some(queue) => queue.iter().filter(|&&(ref e, _)| &e.id != node).map(|&(ref e, _)| e.clone()).collect(),
+			None => return Ok(None),
+		};
+		if registered.is_empty() {
+			return Ok(None);
+		}
+		// Leave room for the topic hash.
+		let limit = (MAX_DATAGRAM_SIZE - 109 - 33) / 90;
+		for c in registered.chunks(limit) {
+			let mut rlp = RlpStream::new_list(2);
+			rlp.append(&topic);
+			rlp.begin_list(c.len());
+			for n in c {
+				rlp.begin_list(4);
+				n.endpoint.to_rlp(&mut rlp);
+				rlp.append(&n.id);
+			}
+			self.send_packet(PACKET_TOPIC_NODES, from, &rlp.drain());
+		}
+		trace!(target: "discovery", "Sent {} TopicNodes to {:?}", registered.len(), &from);
+		Ok(None)
+	}
+
+	fn on_topic_nodes(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
+		let topic: Topic = try!(rlp.val_at(0));
+		let timestamp: u64 = try!(rlp.val_at(2));
+		try!(self.check_timestamp(timestamp));
+		// Only replies to our own queries are accepted, otherwise any node could mark others as preferred.
+		if !self.topics.contains(&topic) || !self.topic_queries.contains_key(node) {
+			debug!(target: "discovery", "Unexpected TopicNodes for {:?} from {:?}", &topic, &from);
+			return Ok(None);
+		}
+		let mut update = TableUpdates::with_added(HashMap::new());
+		trace!(target: "discovery", "Got {} TopicNodes from {:?}", try!(rlp.at(1)).item_count(), &from);
+		for r in try!(rlp.at(1)).iter() {
+			let endpoint = try!(NodeEndpoint::from_rlp(&r));
+			if !endpoint.is_valid() {
+				debug!(target: "discovery", "Bad address: {:?}", endpoint);
+				continue;
+			}
+			let node_id: NodeId = try!(r.val_at(3));
+			if node_id == self.id {
+				continue;
+			}
+			let entry = NodeEntry { id: node_id.clone(), endpoint: endpoint };
+			update.matching.insert(node_id.clone());
+			update.added.insert(node_id, entry.clone());
+			self.ping(&entry.endpoint);
+			self.update_node(entry);
+		}
+		if let Some(query) = self.topic_queries.get_mut(node) {
+			query.matching.extend(update.matching.iter().cloned());
+		}
+		Ok(Some(update))
+	}
+
+	/// Drop timed out topic queries. Returns nodes that are no longer reported as matching by any query.
+	fn expire_topic_queries(&mut self, force: bool) -> HashSet<NodeId> {
+		let now = time::precise_time_ns();
+		let expired: Vec<NodeId> = self.topic_queries.iter()
+			.filter(|&(_, q)| force || now - q.sent >= TOPIC_QUERY_TIMEOUT_MS * 1_000_000)
+			.map(|(id, _)| id.clone())
+			.collect();
+		let mut unmatched = HashSet::new();
+		for id in expired {
+			if let Some(query) = self.topic_queries.remove(&id) {
+				trace!(target: "discovery", "Topic query to {:?} expired", &id);
+				unmatched.extend(query.matching.into_iter());
+			}
+		}
+		for query in self.topic_queries.values() {
+			for id in &query.matching {
+				unmatched.remove(id);
+			}
+		}
+		unmatched
 	}
 
 	fn check_expired(&mut self, force: bool) -> HashSet<NodeId> {
@@ -504,9 +710,11 @@ impl Discovery {
 
 	pub fn round(&mut self) -> Option<TableUpdates> {
 		let removed = self.check_expired(false);
+		let unmatched = self.expire_topic_queries(false);
+		self.expire_topic_registrations(&removed, false);
 		self.discover();
-		if !removed.is_empty() {
-			Some(TableUpdates { added: HashMap::new(), removed: removed })
+		if !removed.is_empty() || !unmatched.is_empty() {
+			Some(TableUpdates { added: HashMap::new(), removed: removed, matching: HashSet::new(), unmatched: unmatched })
 		} else { None }
 	}
 
@@ -533,10 +741,13 @@ impl Discovery {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use super::{PACKET_TOPIC_NODES, MAX_REGISTERED_TOPICS};
 	use util::hash::*;
 	use std::net::*;
+	use rlp::RlpStream;
 	use node_table::*;
 	use std::str::FromStr;
+	use std::collections::{HashMap, HashSet};
 	use rustc_serialize::hex::FromHex;
 	use ethkey::{Random, Generator};
 
@@ -592,6 +803,109 @@ mod tests {
 		assert_eq!(Discovery::nearest_node_entries(&NodeId::new(), &discovery2.node_buckets).len(), 3)
 	}
 
+	#[test]
+	fn topics() {
+		let key1 = Random.generate().unwrap();
+		let key2 = Random.generate().unwrap();
+		let key3 = Random.generate().unwrap();
+		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40448").unwrap(), udp_port: 40448 };
+		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40449").unwrap(), udp_port: 40449 };
+		let ep3 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40450").unwrap(), udp_port: 40450 };
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0);
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0);
+		let mut discovery3 = Discovery::new(&key3, ep3.address.clone(), ep3.clone(), 0);
+		let topic = Topic::from(1);
+		discovery1.set_topics(vec![topic.clone()]);
+		discovery3.set_topics(vec![topic.clone()]);
+		discovery2.init_node_list(vec![
+			NodeEntry { id: key1.public().clone(), endpoint: ep1.clone() },
+			NodeEntry { id: key3.public().clone(), endpoint: ep3.clone() },
+		]);
+
+		let entry2 = NodeEntry { id: key2.public().clone(), endpoint: ep2.clone() };
+		// node 1 registers the topic with node 2
+		discovery1.advertise_topics(&entry2);
+		while let Some(datagramm) = discovery1.send_queue.pop_front() {
+			if datagramm.address == ep2.address {
+				discovery2.on_packet(&datagramm.payload, ep1.address.clone()).unwrap();
+			}
+		}
+		assert_eq!(discovery2.topic_registrations[&topic].len(), 1);
+		assert!(discovery2.send_queue.is_empty());
+
+		// node 3 registers the topic and learns about node 1
+		discovery3.advertise_topics(&entry2);
+		while let Some(datagramm) = discovery3.send_queue.pop_front() {
+			if datagramm.address == ep2.address {
+				discovery2.on_packet(&datagramm.payload, ep3.address.clone()).unwrap();
+			}
+		}
+		assert_eq!(discovery2.topic_registrations[&topic].len(), 2);
+		let mut added = HashMap::new();
+		while let Some(datagramm) = discovery2.send_queue.pop_front() {
+			if datagramm.address == ep3.address {
+				if let Some(update) = discovery3.on_packet(&datagramm.payload, ep2.address.clone()).unwrap() {
+					assert!(update.matching.contains(key1.public()));
+					added.extend(update.added.into_iter());
+				}
+			}
+		}
+		assert!(added.contains_key(key1.public()));
+
+		// the match is dropped once the query expires
+		assert!(discovery3.expire_topic_queries(true).contains(key1.public()));
+	}
+
+	#[test]
+	fn topic_registrations_are_bounded() {
+		let key = Random.generate().unwrap();
+		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40453").unwrap(), udp_port: 40453 };
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0);
+		let entry = NodeEntry { id: NodeId::random(), endpoint: ep.clone() };
+
+		for i in 0..MAX_REGISTERED_TOPICS {
+			assert!(discovery.register_topic(Topic::from(i as u64), &entry));
+		}
+		assert!(!discovery.register_topic(Topic::from(MAX_REGISTERED_TOPICS as u64), &entry));
+		// known topics are still renewed.
+		assert!(discovery.register_topic(Topic::from(0), &entry));
+
+		// registrations of removed nodes are dropped, along with topics left empty.
+		let mut removed = HashSet::new();
+		removed.insert(entry.id.clone());
+		discovery.expire_topic_registrations(&removed, false);
+		assert!(discovery.topic_registrations.is_empty());
+
+		discovery.register_topic(Topic::from(0), &entry);
+		discovery.expire_topic_registrations(&HashSet::new(), false);
+		assert_eq!(discovery.topic_registrations.len(), 1);
+		discovery.expire_topic_registrations(&HashSet::new(), true);
+		assert!(discovery.topic_registrations.is_empty());
+	}
+
+	#[test]
+	fn ignores_unsolicited_topic_nodes() {
+		let key1 = Random.generate().unwrap();
+		let key2 = Random.generate().unwrap();
+		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40451").unwrap(), udp_port: 40451 };
+		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40452").unwrap(), udp_port: 40452 };
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0);
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0);
+		let topic = Topic::from(1);
+		discovery1.set_topics(vec![topic.clone()]);
+
+		// node 2 announces a node for our topic without being asked
+		let mut rlp = RlpStream::new_list(2);
+		rlp.append(&topic);
+		rlp.begin_list(1);
+		rlp.begin_list(4);
+		ep2.to_rlp(&mut rlp);
+		rlp.append(key2.public());
+		discovery2.send_packet(PACKET_TOPIC_NODES, &ep1.address, &rlp.drain());
+		let datagramm = discovery2.send_queue.pop_front().unwrap();
+		assert!(discovery1.on_packet(&datagramm.payload, ep2.address.clone()).unwrap().is_none());
+	}
+
 	#[test]
 	fn removes_expired() {
 		let key = Random.generate().unwrap();
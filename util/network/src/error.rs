@@ -106,6 +106,10 @@ pub enum NetworkError {
 	AddressResolve(Option<::std::io::Error>),
 	/// Error concerning the Rust standard library's IO subsystem.
 	StdIo(::std::io::Error),
+	/// Invalid peer allow or deny rule.
+	InvalidPeerRule(String),
+	/// Network service is not running.
+	NotRunning,
 }
 
 impl fmt::Display for NetworkError {
@@ -124,6 +128,8 @@ impl fmt::Display for NetworkError {
 			AddressResolve(_) => "Failed to resolve network address.".into(),
 			StdIo(ref err) => format!("{}", err),
 			Util(ref err) => format!("{}", err),
+			InvalidPeerRule(ref rule) => format!("Invalid peer rule: {}", rule),
+			NotRunning => "Network is not running".into(),
 		};
 
 		f.write_fmt(format_args!("Network error ({})", msg))
@@ -0,0 +1,233 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Peer admission lists.
+//! A rule is either a node ID (optionally as an enode URL), an IP address or CIDR range,
+//! or a client version regex prefixed with `client:`.
+
+use std::str::FromStr;
+use std::net::IpAddr;
+use regex::Regex;
+use node_table::{NodeId, Node};
+use error::NetworkError;
+
+const CLIENT_PREFIX: &'static str = "client:";
+
+fn ip_bytes(ip: &IpAddr) -> Vec<u8> {
+	match *ip {
+		IpAddr::V4(ref ip) => ip.octets().to_vec(),
+		IpAddr::V6(ref ip) => {
+			let s = ip.segments();
+			if s[0..5] == [0, 0, 0, 0, 0] && s[5] == 0xffff {
+				// IPv4-mapped address
+				vec![(s[6] >> 8) as u8, s[6] as u8, (s[7] >> 8) as u8, s[7] as u8]
+			} else {
+				s.iter().flat_map(|s| vec![(s >> 8) as u8, *s as u8].into_iter()).collect()
+			}
+		}
+	}
+}
+
+/// IP address range in CIDR notation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpRange {
+	address: Vec<u8>,
+	prefix: usize,
+}
+
+impl IpRange {
+	/// Check if the address is in this range.
+	pub fn contains(&self, ip: &IpAddr) -> bool {
+		let ip = ip_bytes(ip);
+		if ip.len() != self.address.len() {
+			return false;
+		}
+		let full = self.prefix / 8;
+		if ip[..full] != self.address[..full] {
+			return false;
+		}
+		let bits = self.prefix % 8;
+		bits == 0 || (ip[full] ^ self.address[full]) >> (8 - bits) == 0
+	}
+}
+
+impl FromStr for IpRange {
+	type Err = NetworkError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (address, prefix) = match s.find('/') {
+			Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+			None => (s, None),
+		};
+		let address = ip_bytes(&try!(IpAddr::from_str(address).map_err(|_| NetworkError::InvalidPeerRule(s.into()))));
+		let max_prefix = address.len() * 8;
+		let prefix = match prefix {
+			Some(p) => try!(p.parse::<usize>().ok().and_then(|p| if p <= max_prefix { Some(p) } else { None })
+				.ok_or_else(|| NetworkError::InvalidPeerRule(s.into()))),
+			None => max_prefix,
+		};
+		Ok(IpRange {
+			address: address,
+			prefix: prefix,
+		})
+	}
+}
+
+/// Single peer admission rule.
+#[derive(Clone)]
+pub enum PeerRule {
+	/// Matches a node ID.
+	Node(NodeId),
+	/// Matches peers connecting from an IP range.
+	Ip(IpRange),
+	/// Matches peers with a client version.
+	ClientVersion(Regex),
+}
+
+impl PeerRule {
+	/// Check if the rule matches a peer. `client_version` is not known before the handshake.
+	pub fn matches(&self, id: &NodeId, ip: &IpAddr, client_version: Option<&str>) -> bool {
+		match *self {
+			PeerRule::Node(ref node) => node == id,
+			PeerRule::Ip(ref range) => range.contains(ip),
+			PeerRule::ClientVersion(ref regex) => client_version.map_or(false, |v| regex.is_match(v)),
+		}
+	}
+}
+
+impl FromStr for PeerRule {
+	type Err = NetworkError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		if s.starts_with(CLIENT_PREFIX) {
+			let regex = try!(Regex::new(&s[CLIENT_PREFIX.len()..]).map_err(|_| NetworkError::InvalidPeerRule(s.into())));
+			return Ok(PeerRule::ClientVersion(regex));
+		}
+		if s.starts_with("enode://") {
+			return Node::from_str(s).map(|n| PeerRule::Node(n.id)).map_err(|_| NetworkError::InvalidPeerRule(s.into()));
+		}
+		if let Ok(id) = NodeId::from_str(s) {
+			return Ok(PeerRule::Node(id));
+		}
+		IpRange::from_str(s).map(PeerRule::Ip)
+	}
+}
+
+/// Allow and deny lists for peer admission.
+/// A peer is admitted if it matches no deny rule and, when the allow list is not empty, at least one allow rule.
+#[derive(Default, Clone)]
+pub struct PeerFilter {
+	allow: Vec<PeerRule>,
+	deny: Vec<PeerRule>,
+}
+
+impl PeerFilter {
+	/// Create a new filter from lists of rules.
+	pub fn new(allow: &[String], deny: &[String]) -> Result<PeerFilter, NetworkError> {
+		let parse = |rules: &[String]| rules.iter()
+			.filter(|r| !r.trim().is_empty())
+			.map(|r| PeerRule::from_str(r))
+			.collect::<Result<Vec<_>, _>>();
+		Ok(PeerFilter {
+			allow: try!(parse(allow)),
+			deny: try!(parse(deny)),
+		})
+	}
+
+	/// Check if a peer should be admitted. `client_version` is not known before the handshake,
+	/// in which case client version rules are ignored.
+	pub fn is_allowed(&self, id: &NodeId, ip: &IpAddr, client_version: Option<&str>) -> bool {
+		if self.deny.iter().any(|r| r.matches(id, ip, client_version)) {
+			return false;
+		}
+		if self.allow.is_empty() {
+			return true;
+		}
+		self.allow.iter().any(|r| match *r {
+			PeerRule::ClientVersion(_) if client_version.is_none() => true,
+			_ => r.matches(id, ip, client_version),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+	use std::net::IpAddr;
+	use node_table::NodeId;
+
+	const NODE: &'static str = "a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c";
+
+	#[test]
+	fn ip_ranges() {
+		let range = IpRange::from_str("10.1.0.0/16").unwrap();
+		assert!(range.contains(&IpAddr::from_str("10.1.2.3").unwrap()));
+		assert!(range.contains(&IpAddr::from_str("::ffff:10.1.2.3").unwrap()));
+		assert!(!range.contains(&IpAddr::from_str("10.2.2.3").unwrap()));
+		assert!(!range.contains(&IpAddr::from_str("fe80::1").unwrap()));
+
+		let range = IpRange::from_str("192.168.1.128/25").unwrap();
+		assert!(range.contains(&IpAddr::from_str("192.168.1.200").unwrap()));
+		assert!(!range.contains(&IpAddr::from_str("192.168.1.100").unwrap()));
+
+		let range = IpRange::from_str("2001:db8::/32").unwrap();
+		assert!(range.contains(&IpAddr::from_str("2001:db8:1::1").unwrap()));
+		assert!(!range.contains(&IpAddr::from_str("2001:db9::1").unwrap()));
+
+		assert!(IpRange::from_str("1.2.3.4").unwrap().contains(&IpAddr::from_str("1.2.3.4").unwrap()));
+		assert!(IpRange::from_str("1.2.3.4/33").is_err());
+		assert!(IpRange::from_str("1.2.3/8").is_err());
+	}
+
+	#[test]
+	fn deny_list() {
+		let deny = vec![NODE.to_owned(), "10.0.0.0/8".to_owned(), "client:^Geth/v1\\.4\\.1-".to_owned()];
+		let filter = PeerFilter::new(&[], &deny).unwrap();
+		let id = NodeId::from_str(NODE).unwrap();
+		let other = NodeId::new();
+		let ip = IpAddr::from_str("22.99.55.44").unwrap();
+
+		assert!(!filter.is_allowed(&id, &ip, None));
+		assert!(!filter.is_allowed(&other, &IpAddr::from_str("10.1.1.1").unwrap(), None));
+		assert!(!filter.is_allowed(&other, &ip, Some("Geth/v1.4.1-stable/linux")));
+		assert!(filter.is_allowed(&other, &ip, Some("Geth/v1.4.10-stable/linux")));
+		assert!(filter.is_allowed(&other, &ip, None));
+	}
+
+	#[test]
+	fn allow_list() {
+		let allow = vec![format!("enode://{}@22.99.55.44:7770", NODE), "client:^Parity/".to_owned()];
+		let filter = PeerFilter::new(&allow, &[]).unwrap();
+		let id = NodeId::from_str(NODE).unwrap();
+		let other = NodeId::new();
+		let ip = IpAddr::from_str("22.99.55.44").unwrap();
+
+		assert!(filter.is_allowed(&id, &ip, Some("Geth/v1.4.10-stable/linux")));
+		assert!(filter.is_allowed(&other, &ip, Some("Parity/v1.4.0")));
+		assert!(!filter.is_allowed(&other, &ip, Some("Geth/v1.4.10-stable/linux")));
+		// client version is unknown before the handshake
+		assert!(filter.is_allowed(&other, &ip, None));
+	}
+
+	#[test]
+	fn invalid_rules() {
+		assert!(PeerFilter::new(&["client:(".to_owned()], &[]).is_err());
+		assert!(PeerFilter::new(&[], &["not a rule".to_owned()]).is_err());
+		assert!(PeerFilter::new(&["".to_owned()], &[]).is_ok());
+	}
+}
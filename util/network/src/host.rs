@@ -36,7 +36,9 @@ use error::*;
 use io::*;
 use {NetworkProtocolHandler, NonReservedPeerMode, PROTOCOL_VERSION};
 use node_table::*;
-use stats::NetworkStats;
+use stats::{NetworkStats, PeerStats};
+use bandwidth::Throttle;
+use filter::PeerFilter;
 use discovery::{Discovery, TableUpdates, NodeEntry};
 use ip_utils::{map_external_address, select_public_address};
 use util::path::restrict_permissions_owner;
@@ -48,6 +50,9 @@ const MAX_SESSIONS: usize = 1024 + MAX_HANDSHAKES;
 const MAX_HANDSHAKES: usize = 80;
 const MAX_HANDSHAKES_PER_ROUND: usize = 32;
 const MAINTENANCE_TIMEOUT: u64 = 1000;
+const THROTTLE_TIMEOUT: u64 = 100;
+/// Reputation an incoming peer must exceed a connected peer's by to evict it.
+const EVICTION_MARGIN: i32 = 10;
 
 #[derive(Debug, PartialEq, Clone)]
 /// Network service configuration
@@ -78,6 +83,20 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub non_reserved_mode: NonReservedPeerMode,
+	/// Total upload limit in bytes per second. Unlimited if none.
+	pub upload_limit: Option<u64>,
+	/// Total download limit in bytes per second. Unlimited if none.
+	pub download_limit: Option<u64>,
+	/// Per peer upload limit in bytes per second. Unlimited if none.
+	pub peer_upload_limit: Option<u64>,
+	/// Per peer download limit in bytes per second. Unlimited if none.
+	pub peer_download_limit: Option<u64>,
+	/// Discovery topics to advertise. Nodes advertising the same topics are dialed first.
+	pub discovery_topics: Vec<H256>,
+	/// Peer rules. If not empty, only matching peers are accepted.
+	pub allow_peers: Vec<String>,
+	/// Peer rules. Matching peers are rejected.
+	pub deny_peers: Vec<String>,
 }
 
 impl Default for NetworkConfiguration {
@@ -103,6 +122,13 @@ impl NetworkConfiguration {
 			max_peers: 50,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
+			upload_limit: None,
+			download_limit: None,
+			peer_upload_limit: None,
+			peer_download_limit: None,
+			discovery_topics: Vec::new(),
+			allow_peers: Vec::new(),
+			deny_peers: Vec::new(),
 		}
 	}
 
@@ -129,6 +155,7 @@ const DISCOVERY: usize = SYS_TIMER + 3;
 const DISCOVERY_REFRESH: usize = SYS_TIMER + 4;
 const DISCOVERY_ROUND: usize = SYS_TIMER + 5;
 const NODE_TABLE: usize = SYS_TIMER + 6;
+const THROTTLE: usize = SYS_TIMER + 7;
 const FIRST_SESSION: usize = 0;
 const LAST_SESSION: usize = FIRST_SESSION + MAX_SESSIONS - 1;
 const USER_TIMER: usize = LAST_SESSION + 256;
@@ -148,6 +175,8 @@ pub enum NetworkIoMessage {
 		handler: Arc<NetworkProtocolHandler + Sync>,
 		/// Protocol Id.
 		protocol: ProtocolId,
+		/// Number of packet IDs reserved by the protocol.
+		packet_count: u8,
 		/// Supported protocol versions.
 		versions: Vec<u8>,
 	},
@@ -198,6 +227,7 @@ pub struct NetworkContext<'s> {
 	session: Option<SharedSession>,
 	session_id: Option<StreamToken>,
 	_reserved_peers: &'s HashSet<NodeId>,
+	nodes: &'s RwLock<NodeTable>,
 }
 
 impl<'s> NetworkContext<'s> {
@@ -205,7 +235,8 @@ impl<'s> NetworkContext<'s> {
 	fn new(io: &'s IoContext<NetworkIoMessage>,
 		protocol: ProtocolId,
 		session: Option<SharedSession>, sessions: Arc<RwLock<Slab<SharedSession>>>,
-		reserved_peers: &'s HashSet<NodeId>) -> NetworkContext<'s> {
+		reserved_peers: &'s HashSet<NodeId>,
+		nodes: &'s RwLock<NodeTable>) -> NetworkContext<'s> {
 		let id = session.as_ref().map(|s| s.lock().token());
 		NetworkContext {
 			io: io,
@@ -214,6 +245,7 @@ impl<'s> NetworkContext<'s> {
 			session: session,
 			sessions: sessions,
 			_reserved_peers: reserved_peers,
+			nodes: nodes,
 		}
 	}
 
@@ -259,6 +291,21 @@ impl<'s> NetworkContext<'s> {
 			.unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
 	}
 
+	/// Report peer behaviour. Affects node reputation immediately, so the penalty
+	/// is kept even if the peer is disabled or disconnected right after.
+	pub fn note_behaviour(&self, peer: PeerId, behaviour: PeerBehaviour) {
+		trace!(target: "network", "Peer {} behaviour: {:?}", peer, behaviour);
+		// the session keeps its own score, so peers missing from the node table are tracked as well.
+		let id = self.resolve_session(peer).and_then(|s| {
+			let mut s = s.lock();
+			s.note_behaviour(behaviour);
+			s.id().cloned()
+		});
+		if let Some(id) = id {
+			self.nodes.write().note_behaviour(&id, behaviour);
+		}
+	}
+
 	/// Check if the session is still active.
 	pub fn is_expired(&self) -> bool {
 		self.session.as_ref().map_or(false, |s| s.lock().expired())
@@ -350,6 +397,8 @@ pub struct Host {
 	timers: RwLock<HashMap<TimerToken, ProtocolTimer>>,
 	timer_counter: RwLock<usize>,
 	stats: Arc<NetworkStats>,
+	throttle: Arc<Throttle>,
+	peer_filter: RwLock<PeerFilter>,
 	reserved_nodes: RwLock<HashSet<NodeId>>,
 	num_sessions: AtomicUsize,
 	stopping: AtomicBool,
@@ -387,6 +436,8 @@ impl Host {
 
 		let boot_nodes = config.boot_nodes.clone();
 		let reserved_nodes = config.reserved_nodes.clone();
+		let throttle = Throttle::new(config.upload_limit, config.download_limit, config.peer_upload_limit, config.peer_download_limit);
+		let peer_filter = try!(PeerFilter::new(&config.allow_peers, &config.deny_peers));
 
 		let mut host = Host {
 			info: RwLock::new(HostInfo {
@@ -407,6 +458,8 @@ impl Host {
 			timers: RwLock::new(HashMap::new()),
 			timer_counter: RwLock::new(USER_TIMER),
 			stats: stats,
+			throttle: Arc::new(throttle),
+			peer_filter: RwLock::new(peer_filter),
 			reserved_nodes: RwLock::new(HashSet::new()),
 			num_sessions: AtomicUsize::new(0),
 			stopping: AtomicBool::new(false),
@@ -421,6 +474,8 @@ impl Host {
 				debug!(target: "network", "Error parsing node id: {}: {:?}", n, e);
 			}
 		}
+		// Expire stale nodes only once the reserved set is known, so reserved nodes are kept.
+		host.nodes.write().expire(&*host.reserved_nodes.read());
 		Ok(host)
 	}
 
@@ -489,6 +544,57 @@ impl Host {
 		Ok(())
 	}
 
+	/// Replace the peer allow list and disconnect peers that are no longer allowed.
+	pub fn set_allowed_peers(&self, rules: Vec<String>, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
+		let deny = self.info.read().config.deny_peers.clone();
+		self.set_peer_filter(rules, deny, io)
+	}
+
+	/// Replace the peer deny list and disconnect peers that are no longer allowed.
+	pub fn set_denied_peers(&self, rules: Vec<String>, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
+		let allow = self.info.read().config.allow_peers.clone();
+		self.set_peer_filter(allow, rules, io)
+	}
+
+	fn set_peer_filter(&self, allow: Vec<String>, deny: Vec<String>, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
+		let filter = try!(PeerFilter::new(&allow, &deny));
+		{
+			let mut info = self.info.write();
+			info.config.allow_peers = allow;
+			info.config.deny_peers = deny;
+		}
+		*self.peer_filter.write() = filter;
+
+		let mut to_kill = Vec::new();
+		for e in self.sessions.write().iter_mut() {
+			let mut s = e.lock();
+			if s.is_ready() && !self.is_peer_allowed(&s) {
+				s.disconnect(io, DisconnectReason::UselessPeer);
+				to_kill.push(s.token());
+			}
+		}
+		for p in to_kill {
+			trace!(target: "network", "Disconnecting peer rejected by peer rules: {}", p);
+			self.kill_connection(p, io, false);
+		}
+		Ok(())
+	}
+
+	/// Check session against the peer allow and deny lists. Reserved peers are always allowed.
+	fn is_peer_allowed(&self, s: &Session) -> bool {
+		let id = match s.id() {
+			Some(id) => id,
+			None => return true,
+		};
+		if self.reserved_nodes.read().contains(id) {
+			return true;
+		}
+		match s.remote_addr() {
+			Ok(address) => self.peer_filter.read().is_allowed(id, &address.ip(), Some(&s.info.client_version)),
+			Err(_) => true,
+		}
+	}
+
 	pub fn client_version() -> String {
 		version()
 	}
@@ -556,7 +662,9 @@ impl Host {
 			if info.config.discovery_enabled && info.config.non_reserved_mode == NonReservedPeerMode::Accept {
 				let mut udp_addr = local_endpoint.address.clone();
 				udp_addr.set_port(local_endpoint.udp_port);
-				Some(Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY))
+				let mut discovery = Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY);
+				discovery.set_topics(info.config.discovery_topics.clone());
+				Some(discovery)
 			} else { None }
 		};
 
@@ -594,6 +702,57 @@ impl Host {
 		self.sessions.read().count() - self.session_count()
 	}
 
+	/// Collect ready non-reserved sessions that may be evicted in favour of `exclude`.
+	/// Must not be called while holding a session lock.
+	fn eviction_candidates(&self, exclude: StreamToken) -> Vec<(StreamToken, i32)> {
+		let reserved = self.reserved_nodes.read();
+		self.sessions.read().iter()
+			// sessions locked elsewhere are busy and are not considered.
+			.filter_map(|e| e.try_lock().and_then(|s| {
+				if s.token() == exclude || !s.is_ready() || s.expired() {
+					return None;
+				}
+				s.id().and_then(|id| if reserved.contains(id) { None } else { Some((s.token(), s.reputation())) })
+			}))
+			.collect()
+	}
+
+	/// Pick the worst of the candidates whose session reputation is lower than given by at least `EVICTION_MARGIN`.
+	fn eviction_candidate(&self, candidates: &[(StreamToken, i32)], reputation: i32) -> Option<StreamToken> {
+		let mut candidate: Option<(i32, StreamToken)> = None;
+		for &(token, r) in candidates {
+			if r + EVICTION_MARGIN < reputation && candidate.map_or(true, |(c, _)| r < c) {
+				candidate = Some((r, token));
+			}
+		}
+		candidate.map(|(_, token)| token)
+	}
+
+	/// Resume reading and writing for sessions stopped by bandwidth limits.
+	fn resume_throttled(&self, io: &IoContext<NetworkIoMessage>) {
+		let throttled: Vec<_> = self.sessions.read().iter()
+			.filter_map(|e| e.try_lock().and_then(|s| if s.is_throttled() { Some(s.token()) } else { None }))
+			.collect();
+		for token in throttled {
+			self.session_readable(token, io);
+			self.session_writable(token, io);
+		}
+	}
+
+	/// Get traffic statistics for all connected peers.
+	pub fn peer_stats(&self) -> Vec<PeerStats> {
+		self.sessions.read().iter()
+			.map(|e| e.lock())
+			.filter(|s| s.is_ready() && !s.expired())
+			.map(|s| s.peer_stats())
+			.collect()
+	}
+
+	/// Statistics of nodes in the node table, best first.
+	pub fn node_table(&self) -> Vec<NodeInfo> {
+		self.nodes.read().entries()
+	}
+
 	fn keep_alive(&self, io: &IoContext<NetworkIoMessage>) {
 		let mut to_kill = Vec::new();
 		for e in self.sessions.write().iter_mut() {
@@ -680,6 +839,10 @@ impl Host {
 					return;
 				}
 			};
+			if !self.reserved_nodes.read().contains(id) && !self.peer_filter.read().is_allowed(id, &address.ip(), None) {
+				trace!(target: "network", "Aborted connect. Node is rejected by peer rules.");
+				return;
+			}
 			match TcpStream::connect(&address) {
 				Ok(socket) => socket,
 				Err(e) => {
@@ -699,7 +862,7 @@ impl Host {
 		let mut sessions = self.sessions.write();
 
 		let token = sessions.insert_with_opt(|token| {
-			match Session::new(io, socket, token, id, &nonce, self.stats.clone(), &self.info.read()) {
+			match Session::new(io, socket, token, id, &nonce, self.stats.clone(), self.throttle.clone(), &self.info.read()) {
 				Ok(s) => Some(Arc::new(Mutex::new(s))),
 				Err(e) => {
 					debug!(target: "network", "Session create error: {:?}", e);
@@ -758,7 +921,15 @@ impl Host {
 		let mut ready_data: Vec<ProtocolId> = Vec::new();
 		let mut packet_data: Vec<(ProtocolId, PacketId, Vec<u8>)> = Vec::new();
 		let mut kill = false;
+		let mut evict = None;
 		let session = { self.sessions.read().get(token).cloned() };
+		// Eviction candidates are gathered before the session is locked: taking `sessions`
+		// while holding a session lock would invert the lock order used elsewhere.
+		let candidates = match session {
+			Some(ref session) if !session.lock().is_ready() && self.session_count() >= self.info.read().config.max_peers as usize =>
+				self.eviction_candidates(token),
+			_ => Vec::new(),
+		};
 		if let Some(session) = session.clone() {
 			let mut s = session.lock();
 			loop {
@@ -778,6 +949,11 @@ impl Host {
 					},
 					Ok(SessionData::Ready) => {
 						self.num_sessions.fetch_add(1, AtomicOrdering::SeqCst);
+						if !self.is_peer_allowed(&s) {
+							trace!(target: "network", "Rejecting peer {} by peer rules: {:?}", token, s.id());
+							s.disconnect(io, DisconnectReason::UselessPeer);
+							return;
+						}
 						if !s.info.originated {
 							let session_count = self.session_count();
 							let (max_peers, reserved_only) = {
@@ -785,11 +961,23 @@ impl Host {
 								(info.config.max_peers, info.config.non_reserved_mode == NonReservedPeerMode::Deny)
 							};
 
+							let id = s.id().unwrap().clone();
+							let reserved = self.reserved_nodes.read().contains(&id);
+							let reputation = self.nodes.read().reputation(&id);
+							if !reserved && reputation < BAN_REPUTATION {
+								trace!(target: "network", "Rejecting banned peer {}: {:?}", token, id);
+								s.disconnect(io, DisconnectReason::UselessPeer);
+								return;
+							}
 							if session_count >= max_peers as usize || reserved_only {
-								// only proceed if the connecting peer is reserved.
-								if !self.reserved_nodes.read().contains(s.id().unwrap()) {
-									s.disconnect(io, DisconnectReason::TooManyPeers);
-									return;
+								// only proceed if the connecting peer is reserved
+								// or has better reputation than one of the connected peers.
+								if !reserved {
+									evict = if reserved_only { None } else { self.eviction_candidate(&candidates, reputation) };
+									if evict.is_none() {
+										s.disconnect(io, DisconnectReason::TooManyPeers);
+										return;
+									}
 								}
 							}
 
@@ -803,6 +991,12 @@ impl Host {
 								}
 							}
 						}
+						if let Some(id) = s.id().cloned() {
+							// carry over the reputation from previous sessions.
+							let mut nodes = self.nodes.write();
+							nodes.note_session_start(&id, &s.info.client_version);
+							s.set_reputation(nodes.reputation(&id));
+						}
 						for (p, _) in self.handlers.read().iter() {
 							if s.have_capability(*p) {
 								ready_data.push(*p);
@@ -827,17 +1021,25 @@ impl Host {
 		if kill {
 			self.kill_connection(token, io, true);
 		}
+		if let Some(evicted) = evict {
+			let session = { self.sessions.read().get(evicted).cloned() };
+			if let Some(session) = session {
+				session.lock().disconnect(io, DisconnectReason::TooManyPeers);
+			}
+			trace!(target: "network", "Evicting peer {} for {}", evicted, token);
+			self.kill_connection(evicted, io, false);
+		}
 		let handlers = self.handlers.read();
 		for p in ready_data {
 			let h = handlers.get(&p).unwrap().clone();
 			self.stats.inc_sessions();
 			let reserved = self.reserved_nodes.read();
-			h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token);
+			h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved, &self.nodes), &token);
 		}
 		for (p, packet_id, data) in packet_data {
 			let h = handlers.get(&p).unwrap().clone();
 			let reserved = self.reserved_nodes.read();
-			h.read(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token, packet_id, &data[1..]);
+			h.read(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved, &self.nodes), &token, packet_id, &data[1..]);
 		}
 	}
 
@@ -849,6 +1051,7 @@ impl Host {
 	fn kill_connection(&self, token: StreamToken, io: &IoContext<NetworkIoMessage>, remote: bool) {
 		let mut to_disconnect: Vec<ProtocolId> = Vec::new();
 		let mut failure_id = None;
+		let mut ended_id = None;
 		let mut deregister = false;
 		let mut expired_session = None;
 		if let FIRST_SESSION ... LAST_SESSION = token {
@@ -858,6 +1061,7 @@ impl Host {
 				let mut s = session.lock();
 				if !s.expired() {
 					if s.is_ready() {
+						ended_id = s.id().cloned();
 						self.num_sessions.fetch_sub(1, AtomicOrdering::SeqCst);
 						for (p, _) in self.handlers.read().iter() {
 							if s.have_capability(*p)  {
@@ -871,6 +1075,9 @@ impl Host {
 				deregister = remote || s.done();
 			}
 		}
+		if let Some(id) = ended_id {
+			self.nodes.write().note_session_end(&id);
+		}
 		if let Some(id) = failure_id {
 			if remote {
 				self.nodes.write().note_failure(&id);
@@ -879,7 +1086,7 @@ impl Host {
 		for p in to_disconnect {
 			let h = self.handlers.read().get(&p).unwrap().clone();
 			let reserved = self.reserved_nodes.read();
-			h.disconnected(&NetworkContext::new(io, p, expired_session.clone(), self.sessions.clone(), &reserved), &token);
+			h.disconnected(&NetworkContext::new(io, p, expired_session.clone(), self.sessions.clone(), &reserved, &self.nodes), &token);
 		}
 		if deregister {
 			io.deregister_stream(token).unwrap_or_else(|e| debug!("Error deregistering stream: {:?}", e));
@@ -909,7 +1116,7 @@ impl Host {
 	pub fn with_context<F>(&self, protocol: ProtocolId, io: &IoContext<NetworkIoMessage>, action: F) where F: Fn(&NetworkContext) {
 		let reserved = { self.reserved_nodes.read() };
 
-		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved);
+		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved, &self.nodes);
 		action(&context);
 	}
 }
@@ -918,6 +1125,9 @@ impl IoHandler<NetworkIoMessage> for Host {
 	/// Initialize networking
 	fn initialize(&self, io: &IoContext<NetworkIoMessage>) {
 		io.register_timer(IDLE, MAINTENANCE_TIMEOUT).expect("Error registering Network idle timer");
+		if self.throttle.is_limited() {
+			io.register_timer(THROTTLE, THROTTLE_TIMEOUT).expect("Error registering network throttle timer");
+		}
 		io.message(NetworkIoMessage::InitPublicInterface).unwrap_or_else(|e| warn!("Error sending IO notification: {:?}", e));
 		self.maintain_network(io)
 	}
@@ -966,6 +1176,7 @@ impl IoHandler<NetworkIoMessage> for Host {
 		}
 		match token {
 			IDLE => self.maintain_network(io),
+			THROTTLE => self.resume_throttled(io),
 			FIRST_SESSION ... LAST_SESSION => self.connection_timeout(token, io),
 			DISCOVERY_REFRESH => {
 				self.discovery.lock().as_mut().unwrap().refresh();
@@ -980,14 +1191,25 @@ impl IoHandler<NetworkIoMessage> for Host {
 			},
 			NODE_TABLE => {
 				trace!(target: "network", "Refreshing node table");
-				self.nodes.write().clear_useless();
+				{
+					let mut nodes = self.nodes.write();
+					nodes.clear_useless();
+					nodes.decay_reputation();
+					nodes.expire(&*self.reserved_nodes.read());
+					nodes.save();
+				}
+				for session in self.sessions.read().iter() {
+					if let Some(mut s) = session.try_lock() {
+						s.decay_reputation();
+					}
+				}
 			},
 			_ => match self.timers.read().get(&token).cloned() {
 				Some(timer) => match self.handlers.read().get(&timer.protocol).cloned() {
 					None => { warn!(target: "network", "No handler found for protocol: {:?}", timer.protocol) },
 					Some(h) => {
 						let reserved = self.reserved_nodes.read();
-						h.timeout(&NetworkContext::new(io, timer.protocol, None, self.sessions.clone(), &reserved), timer.token);
+						h.timeout(&NetworkContext::new(io, timer.protocol, None, self.sessions.clone(), &reserved, &self.nodes), timer.token);
 					}
 				},
 				None => { warn!("Unknown timer token: {}", token); } // timer is not registerd through us
@@ -1003,15 +1225,16 @@ impl IoHandler<NetworkIoMessage> for Host {
 			NetworkIoMessage::AddHandler {
 				ref handler,
 				ref protocol,
+				ref packet_count,
 				ref versions
 			} => {
 				let h = handler.clone();
 				let reserved = self.reserved_nodes.read();
-				h.initialize(&NetworkContext::new(io, *protocol, None, self.sessions.clone(), &reserved));
+				h.initialize(&NetworkContext::new(io, *protocol, None, self.sessions.clone(), &reserved, &self.nodes));
 				self.handlers.write().insert(*protocol, h);
 				let mut info = self.info.write();
 				for v in versions {
-					info.capabilities.push(CapabilityInfo { protocol: *protocol, version: *v, packet_count:0 });
+					info.capabilities.push(CapabilityInfo { protocol: *protocol, version: *v, packet_count: *packet_count });
 				}
 			},
 			NetworkIoMessage::AddTimer {
@@ -1166,3 +1389,15 @@ fn host_client_url() {
 	let host: Host = Host::new(config, Arc::new(NetworkStats::new())).unwrap();
 	assert!(host.local_url().starts_with("enode://101b3ef5a4ea7a1c7928e24c4c75fd053c235d7b80c22ae5c03d145d0ac7396e2a4ffff9adee3133a7b05044a5cee08115fd65145e5165d646bde371010d803c@"));
 }
+
+#[test]
+fn eviction_requires_reputation_margin() {
+	let host: Host = Host::new(NetworkConfiguration::new(), Arc::new(NetworkStats::new())).unwrap();
+	let bad = PeerBehaviour::InvalidTransaction.apply(0);
+	let worse = PeerBehaviour::Timeout.apply(PeerBehaviour::Timeout.apply(0));
+	let candidates = vec![(1, bad), (2, worse)];
+	// an unknown peer is not better than a slightly penalized one by enough.
+	assert_eq!(host.eviction_candidate(&candidates, 0), Some(2));
+	assert_eq!(host.eviction_candidate(&candidates[..1], 0), None);
+	assert_eq!(host.eviction_candidate(&candidates, -15), None);
+}
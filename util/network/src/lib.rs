@@ -45,7 +45,7 @@
 //!
 //! fn main () {
 //! 	let mut service = NetworkService::new(NetworkConfiguration::new_local()).expect("Error creating network service");
-//! 	service.register_protocol(Arc::new(MyHandler), *b"myp", &[1u8]);
+//! 	service.register_protocol(Arc::new(MyHandler), *b"myp", 1, &[1u8]);
 //! 	service.start().expect("Error starting service");
 //!
 //! 	// Wait for quit condition
@@ -70,6 +70,7 @@ extern crate slab;
 extern crate ethkey;
 extern crate ethcrypto as crypto;
 extern crate rlp;
+extern crate regex;
 
 #[macro_use]
 extern crate log;
@@ -86,6 +87,8 @@ mod service;
 mod error;
 mod node_table;
 mod stats;
+mod bandwidth;
+mod filter;
 mod ip_utils;
 
 #[cfg(test)]
@@ -98,10 +101,12 @@ pub use service::NetworkService;
 pub use host::NetworkIoMessage;
 pub use error::NetworkError;
 pub use host::NetworkConfiguration;
-pub use stats::NetworkStats;
+pub use stats::{NetworkStats, PeerStats, SessionStats, PacketStats, PacketStatsMap};
 
 use io::TimerToken;
 pub use node_table::is_valid_node_url;
+pub use node_table::PeerBehaviour;
+pub use node_table::{NodeInfo, saved_nodes};
 
 const PROTOCOL_VERSION: u32 = 4;
 
@@ -15,11 +15,12 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::mem;
+use std::cmp::{min, max};
 use std::slice::from_raw_parts;
 use std::net::{SocketAddr, ToSocketAddrs, SocketAddrV4, SocketAddrV6, Ipv4Addr, Ipv6Addr};
 use std::hash::{Hash, Hasher};
 use std::str::{FromStr};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::path::{PathBuf};
 use std::fmt;
@@ -37,6 +38,9 @@ pub use rustc_serialize::json::Json;
 /// Node public key
 pub type NodeId = H512;
 
+/// Discovery topic. Identifies a chain and subprotocol served by a node.
+pub type Topic = H256;
+
 #[derive(Debug, Clone)]
 /// Node address info
 pub struct NodeEndpoint {
@@ -130,12 +134,110 @@ pub enum PeerType {
 	Optional
 }
 
+/// Lowest possible reputation score.
+const MIN_REPUTATION: i32 = -1000;
+/// Highest possible reputation score.
+const MAX_REPUTATION: i32 = 1000;
+/// Nodes with reputation below this are not dialed and are not accepted as peers.
+pub const BAN_REPUTATION: i32 = -100;
+/// Reputation points restored towards zero on each decay round.
+const REPUTATION_DECAY: i32 = 1;
+/// Number of recent failure times kept for each node.
+const MAX_FAILURE_HISTORY: usize = 16;
+/// Failures older than this many seconds are forgotten.
+const FAILURE_HISTORY_AGE: u64 = 24 * 60 * 60;
+/// Nodes not seen for this many seconds are removed.
+const NODE_EXPIRY_AGE: u64 = 14 * 24 * 60 * 60;
+/// Nodes that were never seen are removed after this many recent failures.
+const NODE_EXPIRY_FAILURES: usize = 8;
+
+fn unix_now() -> u64 {
+	::time::get_time().sec as u64
+}
+
+/// Peer behaviour reported by the protocol handlers.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum PeerBehaviour {
+	/// Peer delivered useful data, such as a new block.
+	Useful,
+	/// Peer sent an invalid block.
+	BadBlock,
+	/// Peer did not answer a request in time.
+	Timeout,
+	/// Peer sent an invalid transaction.
+	InvalidTransaction,
+	/// Peer answered a request, but slowly.
+	SlowResponse,
+}
+
+impl PeerBehaviour {
+	/// Reputation change for this behaviour.
+	pub fn score(&self) -> i32 {
+		match *self {
+			PeerBehaviour::Useful => 1,
+			PeerBehaviour::BadBlock => -50,
+			PeerBehaviour::Timeout => -10,
+			PeerBehaviour::InvalidTransaction => -5,
+			PeerBehaviour::SlowResponse => -2,
+		}
+	}
+
+	/// Apply this behaviour to the given reputation, keeping it within bounds.
+	pub fn apply(&self, reputation: i32) -> i32 {
+		max(MIN_REPUTATION, min(MAX_REPUTATION, reputation + self.score()))
+	}
+}
+
+/// Move the given reputation one step towards zero.
+pub fn decay_reputation(reputation: i32) -> i32 {
+	if reputation > 0 {
+		max(0, reputation - REPUTATION_DECAY)
+	} else {
+		min(0, reputation + REPUTATION_DECAY)
+	}
+}
+
 pub struct Node {
 	pub id: NodeId,
 	pub endpoint: NodeEndpoint,
 	pub peer_type: PeerType,
 	pub failures: u32,
+	pub reputation: i32,
+	pub topic_match: bool,
 	pub last_attempted: Option<Tm>,
+	/// Last time a session with the node was active, as unix time.
+	pub last_seen: Option<u64>,
+	/// Last time a session with the node was established, as unix time.
+	pub last_session: Option<u64>,
+	/// Total time spent in sessions with the node, in seconds.
+	pub session_time: u64,
+	/// Client version reported in the last session.
+	pub client_version: Option<String>,
+	/// Times of recent failures, most recent last.
+	pub failure_history: VecDeque<u64>,
+	/// True while a session with the node is active. Not persisted.
+	pub connected: bool,
+}
+
+/// Node statistics as kept in the node table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeInfo {
+	/// Node URL.
+	pub url: String,
+	/// Total number of failures.
+	pub failures: u32,
+	/// Reputation score.
+	pub reputation: i32,
+	/// Last time a session with the node was active, as unix time.
+	pub last_seen: Option<u64>,
+	/// Last time a session with the node was established, as unix time.
+	pub last_session: Option<u64>,
+	/// Total time spent in sessions with the node, in seconds.
+	pub session_time: u64,
+	/// Client version reported in the last session.
+	pub client_version: Option<String>,
+	/// Times of recent failures.
+	pub recent_failures: Vec<u64>,
 }
 
 impl Node {
@@ -145,7 +247,52 @@ impl Node {
 			endpoint: endpoint,
 			peer_type: PeerType::Optional,
 			failures: 0,
+			reputation: 0,
+			topic_match: false,
 			last_attempted: None,
+			last_seen: None,
+			last_session: None,
+			session_time: 0,
+			client_version: None,
+			failure_history: VecDeque::new(),
+			connected: false,
+		}
+	}
+
+	/// Get node statistics.
+	pub fn info(&self) -> NodeInfo {
+		NodeInfo {
+			url: format!("{}", self),
+			failures: self.failures,
+			reputation: self.reputation,
+			last_seen: self.last_seen,
+			last_session: self.last_session,
+			session_time: self.session_time,
+			client_version: self.client_version.clone(),
+			recent_failures: self.failure_history.iter().cloned().collect(),
+		}
+	}
+
+	fn note_failure(&mut self, now: u64) {
+		self.failures += 1;
+		self.failure_history.push_back(now);
+		while self.failure_history.len() > MAX_FAILURE_HISTORY {
+			self.failure_history.pop_front();
+		}
+	}
+
+	/// Forget old failures and check if the node should be removed from the table.
+	/// Connected nodes are never removed.
+	fn expire(&mut self, now: u64) -> bool {
+		while self.failure_history.front().map_or(false, |t| *t + FAILURE_HISTORY_AGE < now) {
+			self.failure_history.pop_front();
+		}
+		if self.connected {
+			return false;
+		}
+		match self.last_seen {
+			Some(seen) => seen + NODE_EXPIRY_AGE < now,
+			None => self.failure_history.len() >= NODE_EXPIRY_FAILURES,
 		}
 	}
 }
@@ -177,6 +324,14 @@ impl FromStr for Node {
 			peer_type: PeerType::Optional,
 			last_attempted: None,
 			failures: 0,
+			reputation: 0,
+			topic_match: false,
+			last_seen: None,
+			last_session: None,
+			session_time: 0,
+			client_version: None,
+			failure_history: VecDeque::new(),
+			connected: false,
 		})
 	}
 }
@@ -212,17 +367,41 @@ impl NodeTable {
 
 	/// Add a node to table
 	pub fn add_node(&mut self, mut node: Node) {
-		// preserve failure counter
-		let failures = self.nodes.get(&node.id).map_or(0, |n| n.failures);
-		node.failures = failures;
+		// preserve failure counter, reputation, topic match and history
+		if let Some(n) = self.nodes.remove(&node.id) {
+			node.failures = n.failures;
+			node.reputation = n.reputation;
+			node.topic_match = node.topic_match || n.topic_match;
+			node.last_seen = n.last_seen;
+			node.last_session = n.last_session;
+			node.session_time = n.session_time;
+			node.client_version = n.client_version;
+			node.failure_history = n.failure_history;
+			node.connected = n.connected;
+		}
 		self.nodes.insert(node.id.clone(), node);
 	}
 
-	/// Returns node ids sorted by number of failures
+	/// All nodes sorted by topic match, reputation, previous sessions, then by number of failures
+	fn ordered_nodes(&self) -> Vec<&Node> {
+		let mut refs: Vec<&Node> = self.nodes.values().collect();
+		refs.sort_by(|a, b| (!a.topic_match, -a.reputation, a.last_session.is_none(), a.failures)
+			.cmp(&(!b.topic_match, -b.reputation, b.last_session.is_none(), b.failures)));
+		refs
+	}
+
+	/// Statistics of all nodes, best first.
+	pub fn entries(&self) -> Vec<NodeInfo> {
+		self.ordered_nodes().into_iter().map(Node::info).collect()
+	}
+
+	/// Returns ids of nodes worth connecting to, best first.
+	/// Useless and banned nodes are excluded.
 	pub fn nodes(&self) -> Vec<NodeId> {
-		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains(&n.id)).collect();
-		refs.sort_by(|a, b| a.failures.cmp(&b.failures));
-		refs.iter().map(|n| n.id.clone()).collect()
+		self.ordered_nodes().into_iter()
+			.filter(|n| !self.useless_nodes.contains(&n.id) && n.reputation >= BAN_REPUTATION)
+			.map(|n| n.id.clone())
+			.collect()
 	}
 
 	/// Unordered list of all entries
@@ -242,6 +421,16 @@ impl NodeTable {
 			let mut entry = self.nodes.entry(node.id.clone()).or_insert_with(|| Node::new(node.id.clone(), node.endpoint.clone()));
 			entry.endpoint = node.endpoint;
 		}
+		for id in &update.matching {
+			if let Some(node) = self.nodes.get_mut(id) {
+				node.topic_match = true;
+			}
+		}
+		for id in &update.unmatched {
+			if let Some(node) = self.nodes.get_mut(id) {
+				node.topic_match = false;
+			}
+		}
 		for r in update.removed {
 			if !reserved.contains(&r) {
 				self.nodes.remove(&r);
@@ -252,7 +441,67 @@ impl NodeTable {
 	/// Increase failure counte for a node
 	pub fn note_failure(&mut self, id: &NodeId) {
 		if let Some(node) = self.nodes.get_mut(id) {
-			node.failures += 1;
+			node.note_failure(unix_now());
+		}
+	}
+
+	/// Note an established session with a node.
+	pub fn note_session_start(&mut self, id: &NodeId, client_version: &str) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			let now = unix_now();
+			node.last_seen = Some(now);
+			node.last_session = Some(now);
+			node.client_version = Some(client_version.to_owned());
+			node.connected = true;
+		}
+	}
+
+	/// Note the end of a session started with `note_session_start`.
+	pub fn note_session_end(&mut self, id: &NodeId) {
+		self.note_session_end_at(id, unix_now());
+	}
+
+	fn note_session_end_at(&mut self, id: &NodeId, now: u64) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			if let Some(start) = node.last_session {
+				node.session_time += now.saturating_sub(start);
+			}
+			node.last_seen = Some(now);
+			node.connected = false;
+		}
+	}
+
+	/// Remove nodes that were not seen for a long time or keep failing. Reserved nodes are kept.
+	pub fn expire(&mut self, reserved: &HashSet<NodeId>) {
+		self.expire_at(unix_now(), reserved);
+	}
+
+	fn expire_at(&mut self, now: u64, reserved: &HashSet<NodeId>) {
+		let expired: Vec<NodeId> = self.nodes.values_mut()
+			.filter_map(|n| if n.expire(now) && !reserved.contains(&n.id) { Some(n.id.clone()) } else { None })
+			.collect();
+		for id in expired {
+			trace!(target: "network", "Expired node {:?}", id);
+			self.nodes.remove(&id);
+		}
+	}
+
+	/// Adjust node reputation according to reported behaviour.
+	pub fn note_behaviour(&mut self, id: &NodeId, behaviour: PeerBehaviour) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			node.reputation = behaviour.apply(node.reputation);
+		}
+	}
+
+	/// Get node reputation. Unknown nodes have neutral reputation.
+	pub fn reputation(&self, id: &NodeId) -> i32 {
+		self.nodes.get(id).map_or(0, |n| n.reputation)
+	}
+
+	/// Move all reputation scores one step towards zero.
+	pub fn decay_reputation(&mut self) {
+		for node in self.nodes.values_mut() {
+			node.reputation = decay_reputation(node.reputation);
 		}
 	}
 
@@ -266,7 +515,8 @@ impl NodeTable {
 		self.useless_nodes.clear();
 	}
 
-	fn save(&self) {
+	/// Write the table to disk.
+	pub fn save(&self) {
 		if let Some(ref path) = self.path {
 			let mut path_buf = PathBuf::from(path);
 			if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
@@ -277,10 +527,20 @@ impl NodeTable {
 			let mut json = String::new();
 			json.push_str("{\n");
 			json.push_str("\"nodes\": [\n");
-			let node_ids = self.nodes();
-			for i in 0 .. node_ids.len() {
-				let node = self.nodes.get(&node_ids[i]).unwrap();
-				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {} }}{}\n", node, node.failures, if i == node_ids.len() - 1 {""} else {","}))
+			let nodes = self.ordered_nodes();
+			for i in 0 .. nodes.len() {
+				let node = nodes[i];
+				let optional = |v: Option<u64>| v.map_or("null".to_owned(), |v| format!("{}", v));
+				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {}, \"reputation\": {}, \"last_seen\": {}, \"last_session\": {}, \"session_time\": {}, \"client_version\": {}, \"failure_history\": {:?} }}{}\n",
+					node,
+					node.failures,
+					node.reputation,
+					optional(node.last_seen),
+					optional(node.last_session),
+					node.session_time,
+					node.client_version.as_ref().map_or("null".to_owned(), |v| Json::String(v.clone()).to_string()),
+					node.failure_history.iter().collect::<Vec<_>>(),
+					if i == nodes.len() - 1 {""} else {","}))
 			}
 			json.push_str("]\n");
 			json.push_str("}");
@@ -331,6 +591,16 @@ impl NodeTable {
 							if let Some(failures) = n.get("failures").and_then(|f| f.as_u64()) {
 								node.failures = failures as u32;
 							}
+							if let Some(reputation) = n.get("reputation").and_then(|r| r.as_i64()) {
+								node.reputation = max(MIN_REPUTATION as i64, min(MAX_REPUTATION as i64, reputation)) as i32;
+							}
+							node.last_seen = n.get("last_seen").and_then(|t| t.as_u64());
+							node.last_session = n.get("last_session").and_then(|t| t.as_u64());
+							node.session_time = n.get("session_time").and_then(|t| t.as_u64()).unwrap_or(0);
+							node.client_version = n.get("client_version").and_then(|v| v.as_string()).map(str::to_owned);
+							if let Some(history) = n.get("failure_history").and_then(|h| h.as_array()) {
+								node.failure_history = history.iter().filter_map(|t| t.as_u64()).take(MAX_FAILURE_HISTORY).collect();
+							}
 							nodes.insert(node.id.clone(), node);
 						}
 					}
@@ -347,6 +617,16 @@ impl Drop for NodeTable {
 	}
 }
 
+/// Read statistics of nodes saved in the node table at `path`, best first.
+pub fn saved_nodes(path: &str) -> Vec<NodeInfo> {
+	let table = NodeTable {
+		path: None,
+		nodes: NodeTable::load(Some(path.to_owned())),
+		useless_nodes: HashSet::new(),
+	};
+	table.entries()
+}
+
 /// Check if node url is valid
 pub fn is_valid_node_url(url: &str) -> bool {
 	use std::str::FromStr;
@@ -358,8 +638,10 @@ mod tests {
 	use super::*;
 	use std::str::FromStr;
 	use std::net::*;
+	use std::collections::{HashMap, HashSet};
 	use util::hash::*;
 	use devtools::*;
+	use discovery::TableUpdates;
 
 	#[test]
 	fn endpoint_parse() {
@@ -411,6 +693,84 @@ mod tests {
 		assert_eq!(r[2][..], id1[..]);
 	}
 
+	#[test]
+	fn table_reputation_order() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id3 = H512::from_str("c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.add_node(node3);
+
+		table.note_failure(&id2);
+		table.note_behaviour(&id2, PeerBehaviour::Useful);
+		table.note_behaviour(&id3, PeerBehaviour::SlowResponse);
+		assert_eq!(table.reputation(&id2), 1);
+		assert_eq!(table.reputation(&id3), -2);
+
+		let r = table.nodes();
+		assert_eq!(r[0][..], id2[..]);
+		assert_eq!(r[1][..], id1[..]);
+		assert_eq!(r[2][..], id3[..]);
+
+		// banned nodes are not dialed
+		table.note_behaviour(&id1, PeerBehaviour::BadBlock);
+		table.note_behaviour(&id1, PeerBehaviour::BadBlock);
+		table.note_behaviour(&id1, PeerBehaviour::BadBlock);
+		let r = table.nodes();
+		assert_eq!(r.len(), 2);
+		assert!(!r.contains(&id1));
+	}
+
+	#[test]
+	fn table_topic_match_order() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.note_behaviour(&id1, PeerBehaviour::Useful);
+		assert_eq!(table.nodes()[0][..], id1[..]);
+
+		let mut matching = HashSet::new();
+		matching.insert(id2.clone());
+		table.update(TableUpdates { added: HashMap::new(), removed: HashSet::new(), matching: matching.clone(), unmatched: HashSet::new() }, &HashSet::new());
+		let r = table.nodes();
+		assert_eq!(r[0][..], id2[..]);
+		assert_eq!(r[1][..], id1[..]);
+
+		table.update(TableUpdates { added: HashMap::new(), removed: HashSet::new(), matching: HashSet::new(), unmatched: matching }, &HashSet::new());
+		assert_eq!(table.nodes()[0][..], id1[..]);
+	}
+
+	#[test]
+	fn table_reputation_decay() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.note_behaviour(&id1, PeerBehaviour::Useful);
+		table.note_behaviour(&id1, PeerBehaviour::Useful);
+		table.note_behaviour(&id2, PeerBehaviour::SlowResponse);
+
+		table.decay_reputation();
+		assert_eq!(table.reputation(&id1), 1);
+		assert_eq!(table.reputation(&id2), -1);
+		table.decay_reputation();
+		table.decay_reputation();
+		assert_eq!(table.reputation(&id1), 0);
+		assert_eq!(table.reputation(&id2), 0);
+	}
+
 	#[test]
 	fn table_save_load() {
 		let temp_path = RandomTempPath::create_dir();
@@ -432,4 +792,115 @@ mod tests {
 			assert_eq!(r[1][..], id2[..]);
 		}
 	}
+
+	#[test]
+	fn table_save_load_history() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.add_node(node2);
+			table.note_failure(&id2);
+			table.note_behaviour(&id1, PeerBehaviour::Useful);
+			table.note_session_start(&id2, "Parity/\"test\"");
+			table.note_session_end(&id2);
+		}
+
+		{
+			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			let r = table.nodes();
+			assert_eq!(r[0][..], id1[..]);
+			assert_eq!(r[1][..], id2[..]);
+			assert_eq!(table.reputation(&id1), 1);
+		}
+
+		let saved = saved_nodes(temp_path.as_path().to_str().unwrap());
+		assert_eq!(saved.len(), 2);
+		assert_eq!(saved[1].client_version, Some("Parity/\"test\"".to_owned()));
+		assert!(saved[1].last_seen.is_some());
+		assert_eq!(saved[1].recent_failures.len(), 1);
+		assert_eq!(saved[0].last_seen, None);
+	}
+
+	#[test]
+	fn table_loads_stale_nodes_until_expired() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = node1.id.clone();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.note_session_end_at(&id1, 1);
+		}
+
+		// a stale node may be reserved, so loading the table must not drop it.
+		let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+		assert_eq!(table.nodes(), vec![id1.clone()]);
+		let mut reserved = HashSet::new();
+		reserved.insert(id1.clone());
+		table.expire(&reserved);
+		assert_eq!(table.nodes(), vec![id1.clone()]);
+		table.expire(&HashSet::new());
+		assert!(table.nodes().is_empty());
+	}
+
+	#[test]
+	fn table_keeps_connected_nodes() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = node1.id.clone();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+
+		// a node connected for longer than the expiry age is kept.
+		table.note_session_start(&id1, "Parity");
+		for _ in 0..NODE_EXPIRY_FAILURES {
+			table.get_mut(&id1).unwrap().note_failure(0);
+		}
+		let now = unix_now() + NODE_EXPIRY_AGE + 1;
+		table.expire_at(now, &HashSet::new());
+		assert_eq!(table.nodes(), vec![id1.clone()]);
+
+		table.note_session_end_at(&id1, 0);
+		table.expire_at(now, &HashSet::new());
+		assert!(table.nodes().is_empty());
+	}
+
+	#[test]
+	fn table_expires_nodes() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = node1.id.clone();
+		let id2 = node2.id.clone();
+		let id3 = node3.id.clone();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.add_node(node3);
+
+		// node 1 was seen long ago, node 2 keeps failing, node 3 is reserved
+		let now = 100 * NODE_EXPIRY_AGE;
+		table.note_session_end_at(&id1, now - NODE_EXPIRY_AGE - 1);
+		table.note_session_end_at(&id3, now - NODE_EXPIRY_AGE - 1);
+		for _ in 0..NODE_EXPIRY_FAILURES {
+			table.get_mut(&id2).unwrap().note_failure(now);
+		}
+		let mut reserved = HashSet::new();
+		reserved.insert(id3.clone());
+
+		// failures are forgotten after a day
+		table.expire_at(now + FAILURE_HISTORY_AGE + 1, &reserved);
+		assert!(!table.nodes().contains(&id1));
+		assert!(table.nodes().contains(&id2));
+
+		for _ in 0..NODE_EXPIRY_FAILURES {
+			table.get_mut(&id2).unwrap().note_failure(now);
+		}
+		table.expire_at(now, &reserved);
+		assert_eq!(table.nodes(), vec![id3.clone()]);
+	}
 }
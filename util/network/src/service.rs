@@ -17,7 +17,8 @@
 use {NetworkProtocolHandler, NetworkConfiguration, NonReservedPeerMode};
 use error::NetworkError;
 use host::{Host, NetworkContext, NetworkIoMessage, ProtocolId};
-use stats::NetworkStats;
+use stats::{NetworkStats, PeerStats};
+use node_table::NodeInfo;
 use io::*;
 use parking_lot::RwLock;
 use std::sync::Arc;
@@ -48,7 +49,7 @@ pub struct NetworkService {
 	stats: Arc<NetworkStats>,
 	panic_handler: Arc<PanicHandler>,
 	host_handler: Arc<HostHandler>,
-	config: NetworkConfiguration,
+	config: RwLock<NetworkConfiguration>,
 }
 
 impl NetworkService {
@@ -67,16 +68,18 @@ impl NetworkService {
 			stats: stats,
 			panic_handler: panic_handler,
 			host: RwLock::new(None),
-			config: config,
+			config: RwLock::new(config),
 			host_handler: host_handler,
 		})
 	}
 
 	/// Regiter a new protocol handler with the event loop.
-	pub fn register_protocol(&self, handler: Arc<NetworkProtocolHandler + Send + Sync>, protocol: ProtocolId, versions: &[u8]) -> Result<(), NetworkError> {
+	/// `packet_count` is the number of packet IDs the protocol uses in all of its versions.
+	pub fn register_protocol(&self, handler: Arc<NetworkProtocolHandler + Send + Sync>, protocol: ProtocolId, packet_count: u8, versions: &[u8]) -> Result<(), NetworkError> {
 		try!(self.io_service.send_message(NetworkIoMessage::AddHandler {
 			handler: handler,
 			protocol: protocol,
+			packet_count: packet_count,
 			versions: versions.to_vec(),
 		}));
 		Ok(())
@@ -97,9 +100,21 @@ impl NetworkService {
 		&self.stats
 	}
 
+	/// Returns traffic statistics for connected peers.
+	pub fn peer_stats(&self) -> Vec<PeerStats> {
+		let host = self.host.read();
+		host.as_ref().map_or_else(Vec::new, |h| h.peer_stats())
+	}
+
+	/// Returns statistics of nodes in the node table.
+	pub fn node_table(&self) -> Vec<NodeInfo> {
+		let host = self.host.read();
+		host.as_ref().map_or_else(Vec::new, |h| h.node_table())
+	}
+
 	/// Returns network configuration.
-	pub fn config(&self) -> &NetworkConfiguration {
-		&self.config
+	pub fn config(&self) -> NetworkConfiguration {
+		self.config.read().clone()
 	}
 
 	/// Returns external url if available.
@@ -118,7 +133,7 @@ impl NetworkService {
 	pub fn start(&self) -> Result<(), NetworkError> {
 		let mut host = self.host.write();
 		if host.is_none() {
-			let h = Arc::new(try!(Host::new(self.config.clone(), self.stats.clone())));
+			let h = Arc::new(try!(Host::new(self.config.read().clone(), self.stats.clone())));
 			try!(self.io_service.register_handler(h.clone()));
 			*host = Some(h);
 		}
@@ -161,6 +176,36 @@ impl NetworkService {
 		}
 	}
 
+	/// Replace the peer allow list. Peers that are no longer allowed are disconnected.
+	/// The rules are kept across network restarts.
+	pub fn set_allowed_peers(&self, rules: Vec<String>) -> Result<(), NetworkError> {
+		let host = self.host.read();
+		match *host {
+			Some(ref host) => {
+				let io_ctxt = IoContext::new(self.io_service.channel(), 0);
+				try!(host.set_allowed_peers(rules.clone(), &io_ctxt));
+				self.config.write().allow_peers = rules;
+				Ok(())
+			},
+			None => Err(NetworkError::NotRunning),
+		}
+	}
+
+	/// Replace the peer deny list. Peers that are no longer allowed are disconnected.
+	/// The rules are kept across network restarts.
+	pub fn set_denied_peers(&self, rules: Vec<String>) -> Result<(), NetworkError> {
+		let host = self.host.read();
+		match *host {
+			Some(ref host) => {
+				let io_ctxt = IoContext::new(self.io_service.channel(), 0);
+				try!(host.set_denied_peers(rules.clone(), &io_ctxt));
+				self.config.write().deny_peers = rules;
+				Ok(())
+			},
+			None => Err(NetworkError::NotRunning),
+		}
+	}
+
 	/// Set the non-reserved peer mode.
 	pub fn set_non_reserved_mode(&self, mode: NonReservedPeerMode) {
 		let host = self.host.read();
@@ -26,8 +26,9 @@ use handshake::Handshake;
 use io::{IoContext, StreamToken};
 use error::{NetworkError, DisconnectReason};
 use host::*;
-use node_table::NodeId;
-use stats::NetworkStats;
+use node_table::{NodeId, PeerBehaviour, decay_reputation};
+use stats::{NetworkStats, SessionStats, PeerStats};
+use bandwidth::Throttle;
 use time;
 
 const PING_TIMEOUT_SEC: u64 = 30;
@@ -46,6 +47,12 @@ pub struct Session {
 	ping_time_ns: u64,
 	pong_time_ns: Option<u64>,
 	state: State,
+	/// Per packet traffic counters
+	traffic: SessionStats,
+	/// Shared network statistics
+	stats: Arc<NetworkStats>,
+	/// Peer reputation, kept for the session even if the peer is not in the node table.
+	reputation: i32,
 }
 
 enum State {
@@ -130,10 +137,10 @@ impl Session {
 	/// Create a new session out of comepleted handshake. This clones the handshake connection object
 	/// and leaves the handhsake in limbo to be deregistered from the event loop.
 	pub fn new<Message>(io: &IoContext<Message>, socket: TcpStream, token: StreamToken, id: Option<&NodeId>,
-		nonce: &H256, stats: Arc<NetworkStats>, host: &HostInfo) -> Result<Session, NetworkError>
+		nonce: &H256, stats: Arc<NetworkStats>, throttle: Arc<Throttle>, host: &HostInfo) -> Result<Session, NetworkError>
 		where Message: Send + Clone {
 		let originated = id.is_some();
-		let mut handshake = Handshake::new(token, id, socket, nonce, stats).expect("Can't create handshake");
+		let mut handshake = Handshake::new(token, id, socket, nonce, stats.clone(), throttle).expect("Can't create handshake");
 		try!(handshake.start(io, host, originated));
 		Ok(Session {
 			state: State::Handshake(handshake),
@@ -149,6 +156,9 @@ impl Session {
 			ping_time_ns: 0,
 			pong_time_ns: None,
 			expired: false,
+			traffic: SessionStats::default(),
+			stats: stats,
+			reputation: 0,
 		})
 	}
 
@@ -182,6 +192,26 @@ impl Session {
 		self.had_hello
 	}
 
+	/// Get the peer reputation.
+	pub fn reputation(&self) -> i32 {
+		self.reputation
+	}
+
+	/// Set the peer reputation, e.g. to the one known from previous sessions.
+	pub fn set_reputation(&mut self, reputation: i32) {
+		self.reputation = reputation;
+	}
+
+	/// Adjust the peer reputation according to reported behaviour.
+	pub fn note_behaviour(&mut self, behaviour: PeerBehaviour) {
+		self.reputation = behaviour.apply(self.reputation);
+	}
+
+	/// Move the peer reputation one step towards zero.
+	pub fn decay_reputation(&mut self) {
+		self.reputation = decay_reputation(self.reputation);
+	}
+
 	/// Mark this session as inactive to be deleted lated.
 	pub fn set_expired(&mut self) {
 		self.expired = true;
@@ -205,6 +235,25 @@ impl Session {
 		self.connection().remote_addr()
 	}
 
+	/// Check if reading or writing was stopped by bandwidth throttling.
+	pub fn is_throttled(&self) -> bool {
+		self.connection().is_throttled()
+	}
+
+	/// Get traffic statistics for this session.
+	pub fn peer_stats(&self) -> PeerStats {
+		let connection = self.connection();
+		let mut stats = self.traffic.clone();
+		stats.bytes_sent = connection.bytes_sent();
+		stats.bytes_received = connection.bytes_received();
+		PeerStats {
+			id: self.info.id.as_ref().map(|id| id.hex()),
+			client_version: self.info.client_version.clone(),
+			remote_address: connection.remote_addr_str(),
+			stats: stats,
+		}
+	}
+
 	/// Readable IO handler. Returns packet data if available.
 	pub fn readable<Message>(&mut self, io: &IoContext<Message>, host: &HostInfo) -> Result<SessionData, NetworkError>  where Message: Send + Sync + Clone {
 		if self.expired() {
@@ -294,6 +343,8 @@ impl Session {
 			}
 		}
 		let pid = self.info.capabilities[i].id_offset + packet_id;
+		self.traffic.note_sent(protocol, packet_id, data.len());
+		self.stats.note_packet_sent(protocol, packet_id, data.len());
 		let mut rlp = RlpStream::new();
 		rlp.append(&(pid as u32));
 		rlp.append_raw(data, 1);
@@ -358,18 +409,19 @@ impl Session {
 			PACKET_GET_PEERS => Ok(SessionData::None), //TODO;
 			PACKET_PEERS => Ok(SessionData::None),
 			PACKET_USER ... PACKET_LAST => {
-				let mut i = 0usize;
-				while packet_id < self.info.capabilities[i].id_offset {
-					i += 1;
-					if i == self.info.capabilities.len() {
+				let i = match self.info.capabilities.iter().position(|c| packet_id >= c.id_offset && packet_id - c.id_offset < c.packet_count) {
+					Some(i) => i,
+					None => {
 						debug!(target: "network", "Unknown packet: {:?}", packet_id);
 						return Ok(SessionData::Continue)
 					}
-				}
+				};
 
 				// map to protocol
 				let protocol = self.info.capabilities[i].protocol;
 				let pid = packet_id - self.info.capabilities[i].id_offset;
+				self.traffic.note_received(protocol, pid, packet.data.len());
+				self.stats.note_packet_received(protocol, pid, packet.data.len());
 				Ok(SessionData::Packet { data: packet.data, protocol: protocol, packet_id: pid } )
 			},
 			_ => {
@@ -423,6 +475,8 @@ impl Session {
 			}
 		}
 
+		// packet IDs are assigned to the protocols in alphabetical order
+		caps.sort_by(|a, b| a.protocol.cmp(&b.protocol));
 		i = 0;
 		let mut offset: u8 = PACKET_USER;
 		while i < caps.len() {
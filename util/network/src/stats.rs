@@ -16,6 +16,72 @@
 
 //! Network Statistics
 use std::sync::atomic::*;
+use std::collections::BTreeMap;
+use parking_lot::Mutex;
+use host::{ProtocolId, PacketId};
+
+/// Traffic counters for a single packet type.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PacketStats {
+	/// Number of packets sent
+	pub sent_messages: u64,
+	/// Payload bytes sent
+	pub sent_bytes: u64,
+	/// Number of packets received
+	pub received_messages: u64,
+	/// Payload bytes received
+	pub received_bytes: u64,
+}
+
+/// Packet counters by protocol and packet id.
+pub type PacketStatsMap = BTreeMap<(ProtocolId, PacketId), PacketStats>;
+
+fn note_packet(packets: &mut PacketStatsMap, protocol: ProtocolId, packet_id: PacketId, size: usize, sent: bool) {
+	let entry = packets.entry((protocol, packet_id)).or_insert_with(PacketStats::default);
+	if sent {
+		entry.sent_messages += 1;
+		entry.sent_bytes += size as u64;
+	} else {
+		entry.received_messages += 1;
+		entry.received_bytes += size as u64;
+	}
+}
+
+/// Traffic statistics for a single session.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct SessionStats {
+	/// Bytes sent over the connection, including protocol overhead
+	pub bytes_sent: u64,
+	/// Bytes received over the connection, including protocol overhead
+	pub bytes_received: u64,
+	/// Per packet counters
+	pub packets: PacketStatsMap,
+}
+
+impl SessionStats {
+	/// Note a sent packet.
+	pub fn note_sent(&mut self, protocol: ProtocolId, packet_id: PacketId, size: usize) {
+		note_packet(&mut self.packets, protocol, packet_id, size, true);
+	}
+
+	/// Note a received packet.
+	pub fn note_received(&mut self, protocol: ProtocolId, packet_id: PacketId, size: usize) {
+		note_packet(&mut self.packets, protocol, packet_id, size, false);
+	}
+}
+
+/// Traffic statistics of a connected peer.
+#[derive(Debug, Clone)]
+pub struct PeerStats {
+	/// Peer public key, if known
+	pub id: Option<String>,
+	/// Peer client ID
+	pub client_version: String,
+	/// Remote address
+	pub remote_address: String,
+	/// Traffic counters
+	pub stats: SessionStats,
+}
 
 /// Network statistics structure
 #[derive(Default, Debug)]
@@ -26,6 +92,8 @@ pub struct NetworkStats {
 	send: AtomicUsize,
 	/// Total number of sessions created
 	sessions: AtomicUsize,
+	/// Per packet counters
+	packets: Mutex<PacketStatsMap>,
 }
 
 impl NetworkStats {
@@ -47,6 +115,21 @@ impl NetworkStats {
 		self.sessions.fetch_add(1, Ordering::Relaxed);
 	}
 
+	/// Note a sent packet.
+	pub fn note_packet_sent(&self, protocol: ProtocolId, packet_id: PacketId, size: usize) {
+		note_packet(&mut self.packets.lock(), protocol, packet_id, size, true);
+	}
+
+	/// Note a received packet.
+	pub fn note_packet_received(&self, protocol: ProtocolId, packet_id: PacketId, size: usize) {
+		note_packet(&mut self.packets.lock(), protocol, packet_id, size, false);
+	}
+
+	/// Get per packet counters.
+	pub fn packets(&self) -> PacketStatsMap {
+		self.packets.lock().clone()
+	}
+
 	/// Get bytes sent.
 	#[inline]
 	pub fn send(&self) -> usize {
@@ -71,6 +154,7 @@ impl NetworkStats {
 			recv: AtomicUsize::new(0),
 			send: AtomicUsize::new(0),
 			sessions: AtomicUsize::new(0),
+			packets: Mutex::new(BTreeMap::new()),
 		}
 	}
 }
@@ -40,8 +40,13 @@ impl TestProtocol {
 	}
 	/// Creates and register protocol with the network service
 	pub fn register(service: &mut NetworkService, drop_session: bool) -> Arc<TestProtocol> {
+		TestProtocol::register_as(service, *b"tst", drop_session)
+	}
+
+	/// Creates and register protocol with the network service under the given protocol id
+	pub fn register_as(service: &mut NetworkService, protocol: [u8; 3], drop_session: bool) -> Arc<TestProtocol> {
 		let handler = Arc::new(TestProtocol::new(drop_session));
-		service.register_protocol(handler.clone(), *b"tst", &[42u8, 43u8]).expect("Error registering test protocol handler");
+		service.register_protocol(handler.clone(), protocol, 34, &[42u8, 43u8]).expect("Error registering test protocol handler");
 		handler
 	}
 
@@ -93,7 +98,7 @@ impl NetworkProtocolHandler for TestProtocol {
 fn net_service() {
 	let service = NetworkService::new(NetworkConfiguration::new_local()).expect("Error creating network service");
 	service.start().unwrap();
-	service.register_protocol(Arc::new(TestProtocol::new(false)), *b"myp", &[1u8]).unwrap();
+	service.register_protocol(Arc::new(TestProtocol::new(false)), *b"myp", 34, &[1u8]).unwrap();
 }
 
 #[test]
@@ -119,6 +124,29 @@ fn net_connect() {
 	assert!(service2.stats().sessions() >= 1);
 }
 
+#[test]
+fn net_connect_two_protocols() {
+	let key1 = Random.generate().unwrap();
+	let mut config1 = NetworkConfiguration::new_local();
+	config1.use_secret = Some(key1.secret().clone());
+	config1.boot_nodes = vec![ ];
+	let mut service1 = NetworkService::new(config1).unwrap();
+	service1.start().unwrap();
+	// registered out of alphabetical order
+	let handler1b = TestProtocol::register_as(&mut service1, *b"two", false);
+	let handler1a = TestProtocol::register_as(&mut service1, *b"one", false);
+	let mut config2 = NetworkConfiguration::new_local();
+	config2.boot_nodes = vec![ service1.local_url().unwrap() ];
+	let mut service2 = NetworkService::new(config2).unwrap();
+	service2.start().unwrap();
+	let handler2a = TestProtocol::register_as(&mut service2, *b"one", false);
+	let handler2b = TestProtocol::register_as(&mut service2, *b"two", false);
+	// each handler gets exactly one packet from its own protocol
+	while !(handler1a.got_packet() && handler1b.got_packet() && handler2a.got_packet() && handler2b.got_packet()) {
+		thread::sleep(Duration::from_millis(50));
+	}
+}
+
 #[test]
 fn net_start_stop() {
 	let config = NetworkConfiguration::new_local();
@@ -159,3 +187,15 @@ fn net_timeout() {
 		thread::sleep(Duration::from_millis(50));
 	}
 }
+
+#[test]
+fn net_peer_rules_survive_restart() {
+	let service = NetworkService::new(NetworkConfiguration::new_local()).unwrap();
+	assert!(service.set_denied_peers(vec!["10.0.0.0/8".into()]).is_err());
+	service.start().unwrap();
+	assert!(service.set_denied_peers(vec!["not a rule".into()]).is_err());
+	service.set_denied_peers(vec!["10.0.0.0/8".into()]).unwrap();
+	service.stop().unwrap();
+	service.start().unwrap();
+	assert_eq!(service.config().deny_peers, vec!["10.0.0.0/8".to_owned()]);
+}
@@ -151,7 +151,7 @@ pub use memorydb::*;
 pub use overlaydb::*;
 pub use journaldb::JournalDB;
 pub use triehash::*;
-pub use trie::{Trie, TrieMut, TrieDB, TrieDBMut, TrieFactory, TrieError, SecTrieDB, SecTrieDBMut};
+pub use trie::{Trie, TrieMut, TrieDB, TrieDBMut, TrieFactory, TrieError, SecTrieDB, SecTrieDBMut, FatDB};
 pub use nibbleslice::*;
 pub use semantic_version::*;
 pub use log::*;
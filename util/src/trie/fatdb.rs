@@ -43,6 +43,14 @@ impl<'db> FatDB<'db> {
 	pub fn db(&self) -> &HashDB {
 		self.raw.db()
 	}
+
+	/// Returns an iterator over inserted pairs, starting at the first key whose hash
+	/// is not less than the hash of `key`.
+	pub fn iter_from<'a>(&'a self, key: &[u8]) -> super::Result<FatDBIterator<'a>> {
+		let mut iter = try!(FatDBIterator::new(&self.raw));
+		try!(iter.seek(key));
+		Ok(iter)
+	}
 }
 
 impl<'db> Trie for FatDB<'db> {
@@ -79,6 +87,11 @@ impl<'db> FatDBIterator<'db> {
 			trie: trie,
 		})
 	}
+
+	/// Position the iterator on the first key whose hash is not less than the hash of `key`.
+	pub fn seek(&mut self, key: &[u8]) -> super::Result<()> {
+		self.trie_iterator.seek(&key.sha3())
+	}
 }
 
 impl<'db> Iterator for FatDBIterator<'db> {
@@ -225,6 +225,11 @@ impl TrieFactory {
 		}
 	}
 
+	/// Returns true iff the trie DB is a fat DB (allows enumeration of keys).
+	pub fn is_fat(&self) -> bool {
+		self.spec == TrieSpec::Fat
+	}
+
 	/// Create new mutable instance of trie and check for errors.
 	pub fn from_existing<'db>(&self, db: &'db mut HashDB, root: &'db mut H256) -> Result<Box<TrieMut + 'db>> {
 		match self.spec {
@@ -312,6 +312,56 @@ impl<'a> TrieDBIterator<'a> {
 		}
 	}
 
+	/// Position the iterator on the first element with key >= `key`.
+	pub fn seek(&mut self, key: &[u8]) -> super::Result<()> {
+		self.trail.clear();
+		self.key_nibbles.clear();
+
+		let mut partial = NibbleSlice::new(key);
+		let mut data = try!(self.db.root_data(&mut NoOp));
+		loop {
+			let node = try!(self.db.get_node(data, &mut NoOp, 0));
+			let (status, next) = match node {
+				Node::Empty => return Ok(()),
+				// a leaf is either entirely before or entirely after the sought key.
+				Node::Leaf(slice, _) => {
+					self.key_nibbles.extend(slice.iter());
+					(if slice < partial { Status::At } else { Status::Entering }, None)
+				},
+				Node::Extension(slice, item) => {
+					self.key_nibbles.extend(slice.iter());
+					if partial.starts_with(&slice) {
+						partial = partial.mid(slice.len());
+						(Status::At, Some(item))
+					} else if slice < partial {
+						(Status::At, None)
+					} else {
+						(Status::Entering, None)
+					}
+				},
+				Node::Branch(ref nodes, _) => match partial.is_empty() {
+					true => (Status::Entering, None),
+					false => {
+						let i = partial.at(0);
+						self.key_nibbles.push(i);
+						partial = partial.mid(1);
+						(Status::AtChild(i as usize), Some(nodes[i as usize]))
+					}
+				},
+			};
+
+			self.trail.push(Crumb {
+				status: status,
+				node: node,
+			});
+
+			match next {
+				Some(d) => data = d,
+				None => return Ok(()),
+			}
+		}
+	}
+
 	/// The present key.
 	fn key(&self) -> Bytes {
 		// collapse the key_nibbles down to bytes.
@@ -403,3 +453,36 @@ fn iterator() {
 	assert_eq!(d.iter().map(|i|i.to_vec()).collect::<Vec<_>>(), t.iter().unwrap().map(|x| x.unwrap().0).collect::<Vec<_>>());
 	assert_eq!(d, t.iter().unwrap().map(|x| x.unwrap().1).collect::<Vec<_>>());
 }
+
+#[test]
+fn iterator_seek() {
+	use memorydb::*;
+	use super::TrieMut;
+	use super::triedbmut::*;
+
+	let d = vec![ &b"A"[..], &b"AA"[..], &b"AB"[..], &b"B"[..] ];
+
+	let mut memdb = MemoryDB::new();
+	let mut root = H256::new();
+	{
+		let mut t = TrieDBMut::new(&mut memdb, &mut root);
+		for x in &d {
+			t.insert(x, x).unwrap();
+		}
+	}
+
+	let t = TrieDB::new(&memdb, &root).unwrap();
+	let seek = |key: &[u8]| {
+		let mut iter = TrieDBIterator::new(&t).unwrap();
+		iter.seek(key).unwrap();
+		iter.map(|x| x.unwrap().1.to_vec()).collect::<Vec<_>>()
+	};
+
+	assert_eq!(seek(b""), d.iter().map(|i| i.to_vec()).collect::<Vec<_>>());
+	assert_eq!(seek(b"A"), d.iter().map(|i| i.to_vec()).collect::<Vec<_>>());
+	assert_eq!(seek(b"AA"), vec![b"AA".to_vec(), b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(seek(b"AAA"), vec![b"AB".to_vec(), b"B".to_vec()]);
+	assert_eq!(seek(b"AC"), vec![b"B".to_vec()]);
+	assert_eq!(seek(b"B"), vec![b"B".to_vec()]);
+	assert!(seek(b"C").is_empty());
+}